@@ -8,13 +8,68 @@
 //! - **Event-loop-based** ([`EventLoopDriver`]): the driver owns the main
 //!   thread event loop and pushes events into an [`AppRunner`] that the
 //!   driver calls into (winit, SDL2, browser).
+//!
+//! Custom messages can be intercepted on their way to the model by
+//! [`EffectHandler`]s, so models ask the platform for services (a window
+//! title, a sound) by emitting a message, without each driver growing a
+//! dedicated effect.
+//!
+//! Several models can share the screen through a
+//! [`SplitModel`](split::SplitModel), which hosts them in panes.
+//!
+//! Work too long for one update, such as resting for a hundred turns, can
+//! be resolved in slices with [`Effect::Continue`] and a [`TurnBudget`], so
+//! intermediate states get drawn and input can interrupt it.
+//!
+//! Recordings can carry events next to the frames, such as the sounds a
+//! game plays, so that replays can play them too: models emit a
+//! [`RecordEvent`] with [`record_event`], which the runner writes to the
+//! recording in progress.
+//!
+//! Recurring messages, such as animation ticks, come from
+//! [`Subscription`]s that the runner polls, and delayed ones from
+//! [`Effect::Tick`] timers that it keeps, neither of which needs a thread.
+//!
+//! Input latency is measured on the way: how long key and mouse messages
+//! take to reach the model, a frame and the screen, as
+//! [`AppRunner::stats`] and [`App::stats`] report. See [`latency`].
+//!
+//! Double clicks are synthesized here rather than by each driver: with
+//! [`AppConfig::double_click`] or [`AppRunner::set_double_click`] set, a
+//! second quick `Main` press on a cell is followed by a
+//! [`MouseAction::DoubleMain`].
+//!
+//! The model learns its size the same way on every driver: a
+//! [`Msg::Screen`] follows [`Msg::Init`] before any other message, so
+//! before the messages of effects returned for `Init`. Later resizes are
+//! coalesced, so a window being dragged larger costs one relayout per
+//! frame rather than one per resize event; the final size always gets
+//! through.
 
+use std::any::{Any, TypeId};
+use std::io::{self, Write};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
 
-use crate::grid::{Frame, Grid, compute_frame};
-use crate::messages::Msg;
+use crate::caps::DriverCaps;
+use crate::clipboard::ClipboardProvider;
+use crate::geom::Point;
+use crate::grid::{Frame, Grid, OverlayGrid, compose_overlay, compute_frame};
+use crate::messages::{MouseAction, Msg, Seed, SystemTheme};
+use crate::recording::{FrameEncoder, RecordingMeta};
+use crate::seed;
+
+pub mod latency;
+pub mod split;
+#[cfg(feature = "test-utils")]
+mod test_runner;
+
+use latency::Latency;
+pub use latency::{LATENCY_SAMPLES, Percentiles, RunnerStats};
+#[cfg(feature = "test-utils")]
+pub use test_runner::TestRunner;
 
 // ---------------------------------------------------------------------------
 // Context (cancellation token)
@@ -53,6 +108,204 @@ impl Default for Context {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Progress
+// ---------------------------------------------------------------------------
+
+/// Progress reporter handed to [`Effect::CmdProgress`] closures.
+///
+/// Each call to [`report`](Self::report) delivers a [`Msg::Progress`] to
+/// the model, in order, before the closure's final message.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    ctx: Context,
+    tx: Sender<Msg>,
+    /// Panes of nested [`split::SplitModel`]s the reports go back to,
+    /// outermost first.
+    route: Vec<usize>,
+}
+
+impl Progress {
+    /// Report that `fraction` (0.0–1.0) of the work is done, with a short
+    /// status `label`. Reports are dropped once the app is quitting.
+    pub fn report(&self, fraction: f32, label: impl Into<String>) {
+        if self.ctx.is_done() {
+            return;
+        }
+        let msg = Msg::Progress {
+            fraction: fraction.clamp(0.0, 1.0),
+            label: label.into(),
+        };
+        let msg = self
+            .route
+            .iter()
+            .rev()
+            .fold(msg, |msg, &pane| split::to_pane(pane, msg));
+        let _ = self.tx.send(msg);
+    }
+
+    /// Whether the app is quitting. Long-running work should check this
+    /// between steps and return early.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.ctx.is_done()
+    }
+}
+
+/// Boxed closure run by [`Effect::CmdProgress`].
+pub type ProgressFn = Box<dyn FnOnce(&Progress) -> Option<Msg> + Send>;
+
+// ---------------------------------------------------------------------------
+// Continuations
+// ---------------------------------------------------------------------------
+
+/// Time allowance for one slice of work resumed by [`Msg::Continue`].
+///
+/// Start one when the slice begins and do units of work (turns, explore
+/// steps) until it is [`exhausted`](Self::exhausted), then return
+/// [`Effect::Continue`] if some work is left:
+///
+/// ```
+/// use gruid_core::app::{Effect, TurnBudget};
+///
+/// fn rest(turns_left: &mut u32) -> Option<Effect> {
+///     let budget = TurnBudget::default();
+///     while *turns_left > 0 && !budget.exhausted() {
+///         *turns_left -= 1; // Resolve one turn.
+///     }
+///     (*turns_left > 0).then_some(Effect::Continue)
+/// }
+/// # let mut left = 100;
+/// # while rest(&mut left).is_some() {}
+/// # assert_eq!(left, 0);
+/// ```
+///
+/// At least one unit should be done per slice, so that work progresses
+/// even when a unit costs more than the whole budget.
+#[derive(Clone, Copy, Debug)]
+pub struct TurnBudget {
+    start: Instant,
+    budget: Duration,
+}
+
+impl TurnBudget {
+    /// Default allowance: a third of a 60 Hz frame, leaving time to draw.
+    pub const DEFAULT: Duration = Duration::from_millis(5);
+
+    /// A budget of `budget` starting now.
+    pub fn new(budget: Duration) -> Self {
+        Self::starting_at(Instant::now(), budget)
+    }
+
+    /// A budget of `budget` starting at `start`.
+    pub fn starting_at(start: Instant, budget: Duration) -> Self {
+        Self { start, budget }
+    }
+
+    /// Time spent since the start.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Whether the allowance is spent.
+    pub fn exhausted(&self) -> bool {
+        self.exhausted_at(Instant::now())
+    }
+
+    /// Whether the allowance is spent at time `now`.
+    pub fn exhausted_at(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.budget
+    }
+}
+
+impl Default for TurnBudget {
+    /// A [`DEFAULT`](Self::DEFAULT) budget starting now.
+    fn default() -> Self {
+        Self::new(Self::DEFAULT)
+    }
+}
+
+/// Where a request for [`Msg::Continue`] stands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Continuation {
+    #[default]
+    Idle,
+    /// Requested, waiting for the current state to be drawn.
+    Requested,
+    /// Drawn: deliver once pending messages are processed.
+    Due,
+}
+
+impl Continuation {
+    /// Note that a frame was drawn.
+    fn drawn(&mut self) {
+        if *self == Self::Requested {
+            *self = Self::Due;
+        }
+    }
+
+    /// Whether a continuation is due, clearing it.
+    fn take_due(&mut self) -> bool {
+        let due = *self == Self::Due;
+        if due {
+            *self = Self::Idle;
+        }
+        due
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Double clicks
+// ---------------------------------------------------------------------------
+
+/// The usual double-click interval, for [`AppConfig::double_click`] and
+/// [`AppRunner::set_double_click`].
+pub const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Pairs `Main` presses into double clicks, by their message times.
+struct DoubleClicks {
+    interval: Duration,
+    /// Cell and time of the last press not yet paired.
+    last: Option<(Point, Instant)>,
+}
+
+impl DoubleClicks {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+        }
+    }
+
+    /// The `DoubleMain` that `msg` completes, if any. A press pairs with
+    /// the one before it when on the same cell and within the interval; a
+    /// paired press does not pair again, so a third one starts over.
+    fn click(&mut self, msg: &Msg) -> Option<Msg> {
+        let Msg::Mouse {
+            action: MouseAction::Main,
+            pos,
+            modifiers,
+            time,
+        } = *msg
+        else {
+            return None;
+        };
+        if let Some((p, t)) = self.last.take()
+            && p == pos
+            && time.saturating_duration_since(t) <= self.interval
+        {
+            return Some(Msg::Mouse {
+                action: MouseAction::DoubleMain,
+                pos,
+                modifiers,
+                time,
+            });
+        }
+        self.last = Some((pos, time));
+        None
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Effect / Cmd
 // ---------------------------------------------------------------------------
@@ -60,11 +313,62 @@ impl Default for Context {
 /// A side-effect returned by [`Model::update`].
 pub enum Effect {
     /// A one-shot command that produces an optional follow-up message.
+    ///
+    /// It runs on a new thread, unless the driver installed a [`Spawner`]
+    /// with [`AppRunner::set_spawner`].
     Cmd(Box<dyn FnOnce() -> Option<Msg> + Send>),
-    /// A long-running subscription that may send many messages.
+    /// A one-shot command that reports progress while it runs, then
+    /// produces an optional final message.
+    ///
+    /// The closure runs on a worker thread, or with the [`Spawner`] if one
+    /// is installed, and receives a [`Progress`] reporter; use it for slow
+    /// startup work such as map generation so the model can draw a loading
+    /// screen meanwhile. On `wasm32` there are no threads: the web driver's
+    /// spawner runs the closure from a later task, after the current
+    /// message, but to completion on the main thread, its reports being
+    /// delivered afterwards. Split long work into several chunked commands
+    /// to keep the page responsive.
+    CmdProgress(ProgressFn),
+    /// A long-running subscription that may send many messages, from a
+    /// thread of its own. Without threads, as in the browser, use
+    /// [`Subscribe`](Self::Subscribe).
     Sub(Box<dyn FnOnce(Context, Sender<Msg>) + Send>),
+    /// A recurring source of messages polled by the runner, which works
+    /// without threads; see [`Subscription`].
+    Subscribe(Box<dyn Subscription>),
+    /// Deliver `msg` once `after` has elapsed, from a timer the runner
+    /// keeps, which works without threads. Timers are checked as often as
+    /// [`Subscription`]s; event loop drivers also wake up for the next one
+    /// with [`AppRunner::next_tick`]. Ticks due at the same time arrive in
+    /// the order they were asked for, and [`End`](Self::End) cancels
+    /// those still waiting. A tick too far off for an [`Instant`] never
+    /// fires.
+    Tick { after: Duration, msg: Msg },
+    /// Put text on the system clipboard, through the driver's
+    /// [`ClipboardProvider`]. Ignored when the driver has none, and
+    /// failures are ignored too: check
+    /// [`DriverCaps::clipboard`] to tell the user whether copying works.
+    SetClipboard(String),
+    /// Deliver a message as if a driver had sent it: [`EffectHandler`]s
+    /// get a chance to consume it, then the model receives it. Delivery
+    /// happens after the current message is processed.
+    Emit(Msg),
+    /// Run a program that takes over the terminal, such as `$EDITOR` on a
+    /// character dump, or stop the process on Ctrl-Z: [`App`] calls
+    /// [`Driver::suspend`], runs the closure, then
+    /// [`Driver::resume`], and sends a [`Msg::Screen`] so the model redraws
+    /// the whole screen. Messages wait meanwhile. [`AppRunner`] has no
+    /// terminal to give away and just runs the closure.
+    Suspend(Box<dyn FnOnce() + Send>),
     /// Multiple effects batched together.
     Batch(Vec<Effect>),
+    /// Deliver [`Msg::Continue`] once the current state has been drawn, to
+    /// resume long work in slices: do a bounded amount per message, for
+    /// instance with a [`TurnBudget`], and return `Continue` again while
+    /// some is left. Input received meanwhile is delivered first, so the
+    /// model can abort by not returning `Continue` any more. Requests made
+    /// before the same frame give a single message.
+    Continue,
     /// Signal the application loop to stop.
     End,
 }
@@ -73,13 +377,32 @@ impl std::fmt::Debug for Effect {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Cmd(_) => f.write_str("Effect::Cmd(..)"),
+            Self::CmdProgress(_) => f.write_str("Effect::CmdProgress(..)"),
             Self::Sub(_) => f.write_str("Effect::Sub(..)"),
+            Self::Subscribe(_) => f.write_str("Effect::Subscribe(..)"),
+            Self::Tick { after, msg } => f
+                .debug_struct("Effect::Tick")
+                .field("after", after)
+                .field("msg", msg)
+                .finish(),
+            Self::SetClipboard(text) => f.debug_tuple("Effect::SetClipboard").field(text).finish(),
+            Self::Emit(msg) => f.debug_tuple("Effect::Emit").field(msg).finish(),
+            Self::Suspend(_) => f.write_str("Effect::Suspend(..)"),
             Self::Batch(v) => f.debug_tuple("Effect::Batch").field(&v.len()).finish(),
+            Self::Continue => f.write_str("Effect::Continue"),
             Self::End => f.write_str("Effect::End"),
         }
     }
 }
 
+/// Work of an [`Effect::Cmd`] or [`Effect::CmdProgress`] handed to a
+/// [`Spawner`]: it runs the command and queues its follow-up message.
+pub type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs [`Job`]s for drivers where [`Effect::Cmd`] cannot spawn a thread,
+/// such as the browser, where it defers them to a later task.
+pub type Spawner = Box<dyn FnMut(Job)>;
+
 /// Convenience constructor for a [`Effect::Cmd`].
 pub fn cmd<F>(f: F) -> Effect
 where
@@ -88,9 +411,264 @@ where
     Effect::Cmd(Box::new(f))
 }
 
+/// Convenience constructor for a [`Effect::CmdProgress`].
+pub fn cmd_progress<F>(f: F) -> Effect
+where
+    F: FnOnce(&Progress) -> Option<Msg> + Send + 'static,
+{
+    Effect::CmdProgress(Box::new(f))
+}
+
+/// Convenience constructor for a [`Effect::SetClipboard`].
+pub fn set_clipboard(text: impl Into<String>) -> Effect {
+    Effect::SetClipboard(text.into())
+}
+
+/// Convenience constructor for an [`Effect::Suspend`].
+pub fn suspend<F>(f: F) -> Effect
+where
+    F: FnOnce() + Send + 'static,
+{
+    Effect::Suspend(Box::new(f))
+}
+
+/// Convenience constructor for an [`Effect::Emit`] of a custom message.
+pub fn emit<T: Any + Send + Sync>(value: T) -> Effect {
+    Effect::Emit(Msg::custom(value))
+}
+
+/// Convenience constructor emitting a [`SetWindowTitle`] request.
+pub fn set_window_title(title: impl Into<String>) -> Effect {
+    emit(SetWindowTitle(title.into()))
+}
+
+/// Convenience constructor emitting a [`SetFullscreen`] request.
+pub fn set_fullscreen(request: SetFullscreen) -> Effect {
+    emit(request)
+}
+
+/// Convenience constructor emitting a [`RecordEvent`].
+pub fn record_event(tag: u16, payload: impl Into<Vec<u8>>) -> Effect {
+    emit(RecordEvent {
+        tag,
+        payload: payload.into(),
+    })
+}
+
+/// Convenience constructor for an [`Effect::Tick`] delivering `msg` once
+/// `after` has elapsed.
+pub fn tick(after: Duration, msg: Msg) -> Effect {
+    Effect::Tick { after, msg }
+}
+
 /// Convenience type alias.
 pub type Cmd = Effect;
 
+// ---------------------------------------------------------------------------
+// Subscriptions
+// ---------------------------------------------------------------------------
+
+/// Most messages taken from one subscription per poll, so that one always
+/// ready cannot stall the runner.
+const MAX_SUB_MSGS: usize = 64;
+
+/// Answer of a [`Subscription`] when polled.
+#[derive(Debug)]
+pub enum SubPoll {
+    /// Nothing to deliver for now.
+    Pending,
+    /// A message to deliver. The subscription is polled again right away.
+    Msg(Msg),
+    /// The subscription is over, and is dropped.
+    Done,
+}
+
+/// A recurring source of messages, driven by polling instead of a thread,
+/// started with [`Effect::Subscribe`].
+///
+/// Runners poll their subscriptions whenever they process pending
+/// messages: about every 16ms for [`App`], and once per animation frame
+/// for event loop drivers, so timings are no finer than that. All
+/// subscriptions are dropped when the model returns [`Effect::End`].
+pub trait Subscription {
+    /// Report what happened since the last poll, `now` being the current
+    /// time.
+    fn poll(&mut self, now: Instant) -> SubPoll;
+}
+
+/// Subscription calling a function at regular intervals.
+struct Every<F> {
+    interval: Duration,
+    next: Option<Instant>,
+    f: F,
+}
+
+impl<F: FnMut() -> Option<Msg>> Subscription for Every<F> {
+    fn poll(&mut self, now: Instant) -> SubPoll {
+        let next = *self.next.get_or_insert(now + self.interval);
+        if now < next {
+            return SubPoll::Pending;
+        }
+        // Ticks missed while the runner was away are dropped.
+        let after = next + self.interval;
+        self.next = Some(if after > now {
+            after
+        } else {
+            now + self.interval
+        });
+        match (self.f)() {
+            Some(msg) => SubPoll::Msg(msg),
+            None => SubPoll::Done,
+        }
+    }
+}
+
+/// Convenience constructor for an [`Effect::Subscribe`] delivering the
+/// message returned by `f` every `interval`, starting one interval from
+/// now, until it returns `None`.
+pub fn every<F>(interval: Duration, f: F) -> Effect
+where
+    F: FnMut() -> Option<Msg> + 'static,
+{
+    Effect::Subscribe(Box::new(Every {
+        interval,
+        next: None,
+        f,
+    }))
+}
+
+/// Messages of [`Effect::Tick`]s waiting for their time, soonest first.
+#[derive(Default)]
+struct Timers(Vec<(Instant, Msg)>);
+
+impl Timers {
+    fn schedule(&mut self, at: Instant, msg: Msg) {
+        // After those due at the same time, so ticks keep their order.
+        let i = self.0.partition_point(|&(t, _)| t <= at);
+        self.0.insert(i, (at, msg));
+    }
+
+    /// Remove and return the messages due at `now`.
+    fn take_due(&mut self, now: Instant) -> Vec<Msg> {
+        let n = self.0.partition_point(|&(t, _)| t <= now);
+        self.0.drain(..n).map(|(_, msg)| msg).collect()
+    }
+
+    fn next(&self) -> Option<Instant> {
+        self.0.first().map(|&(t, _)| t)
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Poll `subs`, dropping finished ones, and return the messages they gave.
+fn poll_subscriptions(subs: &mut Vec<Box<dyn Subscription>>, now: Instant) -> Vec<Msg> {
+    let mut msgs = Vec::new();
+    subs.retain_mut(|sub| {
+        for _ in 0..MAX_SUB_MSGS {
+            match sub.poll(now) {
+                SubPoll::Pending => break,
+                SubPoll::Msg(msg) => msgs.push(msg),
+                SubPoll::Done => return false,
+            }
+        }
+        true
+    });
+    msgs
+}
+
+// ---------------------------------------------------------------------------
+// Effect handlers
+// ---------------------------------------------------------------------------
+
+/// Intercepts custom messages of one type before they reach the model.
+///
+/// Handlers run on the thread driving the model, in order of registration:
+/// first the application's, from [`AppConfig::effect_handlers`] or
+/// [`AppRunner::add_effect_handler`], then the driver's. The first handler
+/// that consumes a message stops it; a message no handler consumes is
+/// delivered to the model as usual. Use [`effect_handler`] to make one
+/// from a closure.
+pub trait EffectHandler {
+    /// Type of the [`Msg::Custom`] payloads this handler is given.
+    fn message_type(&self) -> TypeId;
+
+    /// Handle a payload of type [`message_type`](Self::message_type).
+    /// Returns whether the message is consumed.
+    fn handle(&mut self, msg: &dyn Any) -> bool;
+}
+
+struct FnHandler<T, F> {
+    f: F,
+    _msg: std::marker::PhantomData<fn(&T)>,
+}
+
+impl<T: Any, F: FnMut(&T) -> bool> EffectHandler for FnHandler<T, F> {
+    fn message_type(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn handle(&mut self, msg: &dyn Any) -> bool {
+        msg.downcast_ref::<T>().is_some_and(|msg| (self.f)(msg))
+    }
+}
+
+/// An [`EffectHandler`] for messages of type `T`, consuming those for
+/// which `f` returns `true`.
+pub fn effect_handler<T: Any, F>(f: F) -> Box<dyn EffectHandler>
+where
+    F: FnMut(&T) -> bool + 'static,
+{
+    Box::new(FnHandler {
+        f,
+        _msg: std::marker::PhantomData,
+    })
+}
+
+/// Whether one of `handlers` consumed `msg`.
+fn intercept(handlers: &mut [Box<dyn EffectHandler>], msg: &Msg) -> bool {
+    let Msg::Custom(payload) = msg else {
+        return false;
+    };
+    let ty = payload.type_id();
+    handlers
+        .iter_mut()
+        .filter(|h| h.message_type() == ty)
+        .any(|h| h.handle(payload.as_any()))
+}
+
+/// Request to set the window title, or the terminal title. Emit it with
+/// [`set_window_title`]; drivers that can show a title handle it, and the
+/// model receives it otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetWindowTitle(pub String);
+
+/// Request to switch the window in or out of borderless fullscreen, as an
+/// F11 key would. Emit it with [`set_fullscreen`]; windowed drivers handle
+/// it, and the model receives it otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SetFullscreen {
+    On,
+    Off,
+    Toggle,
+}
+
+/// Request to write an event to the recording in progress, for effects the
+/// frames do not show, such as a sound: a replay can then play it too. See
+/// [`FrameEncoder::event`]. Emit it with [`record_event`], next to the
+/// message asking for the sound itself. The runner takes it, when the
+/// effect is handled, and drops it when not recording: models and
+/// [`EffectHandler`]s never receive it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordEvent {
+    /// What the event is, as numbered by the application.
+    pub tag: u16,
+    /// Data of the event.
+    pub payload: Vec<u8>,
+}
+
 // ---------------------------------------------------------------------------
 // Model trait
 // ---------------------------------------------------------------------------
@@ -102,6 +680,23 @@ pub trait Model {
 
     /// Render the current state into `grid`.
     fn draw(&self, grid: &mut Grid);
+
+    /// Draw decorations shown for this frame only, such as a hover
+    /// highlight, over the grid drawn by [`draw`](Self::draw). Overlay cells
+    /// are left out of recordings, and those not drawn again are restored
+    /// on the next frame. Does nothing by default.
+    fn draw_transient(&self, _overlay: &mut OverlayGrid) {}
+
+    /// Called exactly once when the application shuts down, whatever the
+    /// reason: the model returned [`Effect::End`], the window was closed,
+    /// the driver failed, or the page is being hidden. Save state here.
+    ///
+    /// Delivery is guaranteed for every exit path that leaves the process
+    /// running: [`App::run`] returning (including with an error) and
+    /// [`AppRunner::shutdown`] or dropping the runner. It is best-effort
+    /// when the platform kills the program outright (`SIGKILL`, a browser
+    /// tab killed without `pagehide`, a crash).
+    fn on_exit(&mut self) {}
 }
 
 // ---------------------------------------------------------------------------
@@ -132,6 +727,57 @@ pub trait Driver {
 
     /// Clean up / restore the terminal.
     fn close(&mut self);
+
+    /// Give the terminal back for an [`Effect::Suspend`], as
+    /// [`close`](Driver::close) does, but to take it again in
+    /// [`resume`](Driver::resume). Does nothing by default.
+    fn suspend(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    /// Take the terminal again after [`suspend`](Driver::suspend). The next
+    /// frame is diffed against a blank screen, so the driver should clear
+    /// it and forget what it showed. Does nothing by default.
+    fn resume(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    /// The size of the screen in cells, if the back-end can tell, queried
+    /// after [`resume`](Driver::resume) for the [`Msg::Screen`] that
+    /// follows, as the terminal may have been resized meanwhile. The size
+    /// of the grid is sent otherwise.
+    fn size(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    /// What the back-end supports, queried after [`init`](Driver::init) and
+    /// sent to the model as [`Msg::Capabilities`] right after
+    /// [`Msg::Init`].  A driver whose capabilities change later sends a new
+    /// [`Msg::Capabilities`] from [`poll_msgs`](Driver::poll_msgs).
+    fn capabilities(&self) -> DriverCaps {
+        DriverCaps::default()
+    }
+
+    /// The colour scheme in use, if the back-end can tell, queried after
+    /// [`init`](Driver::init) and sent to the model as a [`SystemTheme`]
+    /// message right after the capabilities. A driver noticing a change
+    /// later sends the new one from [`poll_msgs`](Driver::poll_msgs).
+    fn system_theme(&self) -> Option<SystemTheme> {
+        None
+    }
+
+    /// The clipboard [`Effect::SetClipboard`] writes to, if the back-end
+    /// has one.
+    fn clipboard(&mut self) -> Option<&mut dyn ClipboardProvider> {
+        None
+    }
+
+    /// Handlers for the platform services the back-end provides, called
+    /// once before [`init`](Driver::init). They run after the
+    /// application's own.
+    fn effect_handlers(&mut self) -> Vec<Box<dyn EffectHandler>> {
+        Vec::new()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -150,10 +796,18 @@ pub trait EventLoopDriver {
     /// 1. Create its window / surface.
     /// 2. Call `runner.init()` once.
     /// 3. For each input event, call `runner.handle_msg(msg)`.
-    /// 4. When `runner.should_quit()` is true, exit.
+    /// 4. When `runner.should_quit()` is true, or the platform closes the
+    ///    window, call `runner.shutdown()` and exit.
     /// 5. After processing events, call `runner.draw_frame()` to get the
     ///    frame diff and render it.
     fn run(self, runner: AppRunner) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// What the back-end supports, as far as the configuration tells.  The
+    /// driver hands it to [`AppRunner::set_capabilities`], refined once its
+    /// window or canvas exists if needed.
+    fn capabilities(&self) -> DriverCaps {
+        DriverCaps::default()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -168,10 +822,39 @@ pub struct AppRunner {
     model: Box<dyn Model>,
     prev_grid: Grid,
     curr_grid: Grid,
+    /// Overlay shown by the last frame.
+    overlay: OverlayGrid,
     ctx: Context,
     needs_draw: bool,
     tx: Sender<Msg>,
     rx: Receiver<Msg>,
+    exited: bool,
+    initialized: bool,
+    /// Last capabilities set by the driver.
+    caps: Option<DriverCaps>,
+    /// Last colour scheme set by the driver.
+    theme: Option<SystemTheme>,
+    clipboard: Option<Box<dyn ClipboardProvider>>,
+    handlers: Vec<Box<dyn EffectHandler>>,
+    /// Runs commands instead of a new thread each, if set.
+    spawner: Option<Spawner>,
+    subs: Vec<Box<dyn Subscription>>,
+    timers: Timers,
+    continuation: Continuation,
+    /// Encoder of the frames drawn, and when recording started.
+    recording: Option<(FrameEncoder<Box<dyn Write>>, Instant)>,
+    /// First error met while recording, which stopped it.
+    recording_error: Option<io::Error>,
+    double_clicks: Option<DoubleClicks>,
+    /// Resize reported by the driver and not yet delivered.
+    pending_screen: Option<Msg>,
+    /// Master seed, once set or resolved by [`init`](Self::init).
+    seed: Option<u64>,
+    /// Cursor of the last frame drawn.
+    cursor: Option<Point>,
+    latency: Latency,
+    /// What latencies are measured against.
+    clock: Box<dyn Fn() -> Instant>,
 }
 
 impl AppRunner {
@@ -179,24 +862,221 @@ impl AppRunner {
     /// before processing events.
     pub fn new(model: Box<dyn Model>, width: i32, height: i32) -> Self {
         let (tx, rx) = mpsc::channel();
+        let curr_grid = Grid::new(width, height);
         Self {
             model,
             prev_grid: Grid::new(width, height),
-            curr_grid: Grid::new(width, height),
+            overlay: OverlayGrid::new(curr_grid.clone()),
+            curr_grid,
             ctx: Context::new(),
             needs_draw: false,
             tx,
             rx,
+            exited: false,
+            initialized: false,
+            caps: None,
+            theme: None,
+            clipboard: None,
+            handlers: Vec::new(),
+            spawner: None,
+            subs: Vec::new(),
+            timers: Timers::default(),
+            continuation: Continuation::Idle,
+            recording: None,
+            recording_error: None,
+            double_clicks: None,
+            pending_screen: None,
+            seed: None,
+            cursor: None,
+            latency: Latency::default(),
+            clock: Box::new(Instant::now),
+        }
+    }
+
+    /// Set the master seed of the run, sent to the model as a [`Seed`]
+    /// message by [`init`](Self::init). Without it, `init` takes the
+    /// `GRUID_SEED` environment variable, or draws one from entropy. See
+    /// [`seed`].
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// The master seed of the run: `None` until set with
+    /// [`set_seed`](Self::set_seed) or resolved by [`init`](Self::init) or
+    /// [`start_recording`](Self::start_recording).
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    fn resolve_seed(&mut self) -> u64 {
+        *self.seed.get_or_insert_with(|| seed::resolve(None))
+    }
+
+    /// Record the frames drawn from now on to `writer`, with `meta` at the
+    /// start of the stream. The first frame recorded holds the whole
+    /// screen, so the recording replays on its own; frame times count from
+    /// this call. A recording in progress is stopped first.
+    ///
+    /// The metadata records the master [`seed`](Self::seed) unless `meta`
+    /// has one already.
+    pub fn start_recording(
+        &mut self,
+        writer: impl Write + 'static,
+        meta: Option<RecordingMeta>,
+    ) -> io::Result<()> {
+        self.stop_recording()?;
+        let mut meta = meta.unwrap_or_default();
+        meta.seed = meta.seed.or(Some(self.resolve_seed()));
+        let encoder = FrameEncoder::new(Box::new(writer) as Box<dyn Write>).with_metadata(meta);
+        self.recording = Some((encoder, Instant::now()));
+        // Diff the next frame against a blank screen.
+        self.prev_grid = Grid::new(self.curr_grid.width(), self.curr_grid.height());
+        self.needs_draw = true;
+        Ok(())
+    }
+
+    /// Stop recording and flush the writer. Returns the error that stopped
+    /// the recording early, if any.
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        if let Some(e) = self.recording_error.take() {
+            self.recording = None;
+            return Err(e);
+        }
+        match self.recording.take() {
+            Some((mut encoder, _)) => encoder.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether frames are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Write an event to the recording in progress, timed like the
+    /// frames; does nothing when not recording. Models can do the same by
+    /// emitting a [`RecordEvent`].
+    pub fn record_event(&mut self, tag: u16, payload: &[u8]) {
+        if let Some((encoder, start)) = &mut self.recording
+            && let Err(e) = encoder.event(start.elapsed().as_millis() as u64, tag, payload)
+        {
+            self.recording = None;
+            self.recording_error = Some(e);
         }
     }
 
     /// Send the `Msg::Init` message to the model.  Call once at startup.
+    ///
+    /// It is preceded by the master seed as a [`Seed`] message, and
+    /// followed by a [`Msg::Screen`]: the size last passed to
+    /// [`screen`](Self::screen), or the current size if there was none.
+    /// Capabilities and system theme set before are delivered next, in
+    /// that order.
     pub fn init(&mut self) {
-        self.handle_msg(Msg::Init);
+        let seed = self.resolve_seed();
+        self.dispatch(Msg::custom(Seed(seed)));
+        self.dispatch(Msg::Init);
+        self.initialized = true;
+        let screen = self.pending_screen.take().unwrap_or_else(|| Msg::Screen {
+            width: self.width(),
+            height: self.height(),
+            time: Instant::now(),
+        });
+        self.dispatch(screen);
+        if let Some(caps) = self.caps {
+            self.handle_msg(Msg::Capabilities(caps));
+        }
+        if let Some(theme) = self.theme {
+            self.handle_msg(Msg::custom(theme));
+        }
+    }
+
+    /// Report the driver capabilities to the model as
+    /// [`Msg::Capabilities`], unless they are the ones already reported.
+    /// The model never sees them before [`Msg::Init`]: if
+    /// [`init`](Self::init) has not been called yet, they are delivered
+    /// right after it.
+    pub fn set_capabilities(&mut self, caps: DriverCaps) {
+        if self.caps == Some(caps) {
+            return;
+        }
+        self.caps = Some(caps);
+        if self.initialized {
+            self.handle_msg(Msg::Capabilities(caps));
+        }
+    }
+
+    /// The capabilities last set by the driver.
+    pub fn capabilities(&self) -> Option<DriverCaps> {
+        self.caps
+    }
+
+    /// Report the system colour scheme to the model as a [`SystemTheme`]
+    /// message, unless it is the one already reported. Like
+    /// [`set_capabilities`](Self::set_capabilities), it is held back until
+    /// [`init`](Self::init).
+    pub fn set_system_theme(&mut self, theme: SystemTheme) {
+        if self.theme == Some(theme) {
+            return;
+        }
+        self.theme = Some(theme);
+        if self.initialized {
+            self.handle_msg(Msg::custom(theme));
+        }
+    }
+
+    /// The system colour scheme last set by the driver.
+    pub fn system_theme(&self) -> Option<SystemTheme> {
+        self.theme
+    }
+
+    /// Install the clipboard [`Effect::SetClipboard`] writes to.
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn ClipboardProvider>) {
+        self.clipboard = Some(clipboard);
+    }
+
+    /// Add an [`EffectHandler`], tried after those added before. The
+    /// application adds its handlers before handing the runner to the
+    /// driver, which adds its own.
+    pub fn add_effect_handler(&mut self, handler: Box<dyn EffectHandler>) {
+        self.handlers.push(handler);
+    }
+
+    /// Run [`Effect::Cmd`] and [`Effect::CmdProgress`] jobs with `spawner`
+    /// rather than on a new thread each. Their messages are delivered by
+    /// [`process_pending_msgs`](Self::process_pending_msgs), like those of
+    /// threads. Drivers without threads install one.
+    pub fn set_spawner(&mut self, spawner: impl FnMut(Job) + 'static) {
+        self.spawner = Some(Box::new(spawner));
     }
 
-    /// Push a message into the model.
+    /// Follow a second `Main` press on a cell within `interval` of the
+    /// first with a [`MouseAction::DoubleMain`] there, or stop with `None`
+    /// (the default). [`DOUBLE_CLICK_INTERVAL`] is the usual interval.
+    pub fn set_double_click(&mut self, interval: Option<Duration>) {
+        self.double_clicks = interval.map(DoubleClicks::new);
+    }
+
+    /// Push a message into the model, unless an [`EffectHandler`]
+    /// consumes it. A press completing a double click is followed by its
+    /// `DoubleMain`. A resize reported with [`screen`](Self::screen) and
+    /// not delivered yet goes first.
     pub fn handle_msg(&mut self, msg: Msg) {
+        self.flush_screen();
+        self.latency.update(&msg, (self.clock)());
+        let double = self.double_clicks.as_mut().and_then(|d| d.click(&msg));
+        self.dispatch(msg);
+        if let Some(double) = double
+            && !self.should_quit()
+        {
+            self.dispatch(double);
+        }
+    }
+
+    fn dispatch(&mut self, msg: Msg) {
+        if intercept(&mut self.handlers, &msg) {
+            return;
+        }
         if let Some(effect) = self.model.update(msg) {
             self.handle_effect(effect);
         }
@@ -208,22 +1088,82 @@ impl AppRunner {
         self.ctx.is_done()
     }
 
-    /// Compute a diff frame if anything changed since the last call.
+    /// Whether the model changed since the last [`draw_frame`](Self::draw_frame).
+    pub fn needs_draw(&self) -> bool {
+        self.needs_draw
+    }
+
+    /// Shut the application down: cancel background effects, call
+    /// [`Model::on_exit`] and flush a recording in progress. Drivers must
+    /// call this on every exit path; calls after the first are no-ops.
+    /// Dropping the runner also shuts it down.
+    pub fn shutdown(&mut self) {
+        if self.exited {
+            return;
+        }
+        self.exited = true;
+        self.ctx.cancel();
+        self.subs.clear();
+        self.timers.clear();
+        self.model.on_exit();
+        let _ = self.stop_recording();
+    }
+
+    /// Compute a diff frame if anything changed since the last call, first
+    /// delivering the last resize reported with [`screen`](Self::screen).
     ///
     /// Returns `Some(frame)` if the model was updated, `None` otherwise.
+    /// The frame shows the [transient overlay](Model::draw_transient); the
+    /// recording gets the grid's diff without it.
     pub fn draw_frame(&mut self) -> Option<Frame> {
+        self.flush_screen();
         if !self.needs_draw {
             return None;
         }
         self.needs_draw = false;
+        self.continuation.drawn();
+        self.curr_grid.set_cursor(None);
         self.model.draw(&mut self.curr_grid);
-        let frame = compute_frame(&self.prev_grid, &self.curr_grid);
+        let mut frame = compute_frame(&self.prev_grid, &self.curr_grid);
         self.prev_grid.copy_from(&self.curr_grid);
-        if frame.cells.is_empty() {
-            None
-        } else {
-            Some(frame)
+        let changed = !frame.cells.is_empty() || frame.cursor != self.cursor;
+        self.cursor = frame.cursor;
+        if changed && let Some((encoder, start)) = &mut self.recording {
+            frame.time_ms = start.elapsed().as_millis() as u64;
+            if let Err(e) = encoder.encode(&frame) {
+                self.recording = None;
+                self.recording_error = Some(e);
+            }
         }
+        let mut overlay = OverlayGrid::new(self.curr_grid.clone());
+        self.model.draw_transient(&mut overlay);
+        let frame = compose_overlay(&frame, &self.curr_grid, &self.overlay, &overlay);
+        self.overlay = overlay;
+        let shown = changed || !frame.cells.is_empty();
+        self.latency.drawn(shown, (self.clock)());
+        shown.then_some(frame)
+    }
+
+    /// Tell the runner that the frames drawn so far are on screen, to
+    /// measure the [`present`](RunnerStats::present) latency. Graphical
+    /// drivers call it once they presented, rather than when they got the
+    /// frame from [`draw_frame`](Self::draw_frame); calling it when there
+    /// was nothing new to present is harmless.
+    pub fn frame_presented(&mut self) {
+        self.latency.presented((self.clock)());
+    }
+
+    /// Input latencies measured so far. See [`latency`].
+    pub fn stats(&self) -> RunnerStats {
+        self.latency.stats()
+    }
+
+    /// Read the time from `clock` rather than [`Instant::now`] to measure
+    /// latencies and time [`Effect::Tick`]s, as tests do with a clock of
+    /// their own. Message timestamps are not affected: tests set them with
+    /// [`Msg::with_time`].
+    pub fn set_clock(&mut self, clock: impl Fn() -> Instant + 'static) {
+        self.clock = Box::new(clock);
     }
 
     /// The current grid width.
@@ -241,38 +1181,146 @@ impl AppRunner {
     pub fn resize(&mut self, width: i32, height: i32) {
         self.prev_grid = Grid::new(width, height);
         self.curr_grid = Grid::new(width, height);
+        self.overlay = OverlayGrid::new(self.curr_grid.clone());
         self.needs_draw = true;
     }
 
-    /// Drain any messages from background effects (Cmd/Sub).
-    /// The driver should call this periodically (e.g. each frame).
+    /// Resize the grids and tell the model with a [`Msg::Screen`], which
+    /// drivers should use rather than sending one themselves.
+    ///
+    /// The message waits for the next [`draw_frame`](Self::draw_frame) or
+    /// [`handle_msg`](Self::handle_msg), and replaces one still waiting:
+    /// a burst of resizes reaches the model as its final size only.
+    /// Before [`init`](Self::init), it is the size sent after `Msg::Init`.
+    pub fn screen(&mut self, width: i32, height: i32) {
+        self.resize(width, height);
+        self.pending_screen = Some(Msg::Screen {
+            width,
+            height,
+            time: Instant::now(),
+        });
+    }
+
+    fn flush_screen(&mut self) {
+        if self.initialized
+            && let Some(msg) = self.pending_screen.take()
+        {
+            self.dispatch(msg);
+        }
+    }
+
+    /// Drain any messages from background effects (Cmd/Sub), poll the
+    /// [`Subscription`]s, deliver the [`Effect::Tick`]s due, then the
+    /// [`Msg::Continue`] requested before the last
+    /// [`draw_frame`](Self::draw_frame), if any. The driver should call
+    /// this periodically (e.g. each frame).
     pub fn process_pending_msgs(&mut self) {
         while let Ok(msg) = self.rx.try_recv() {
-            if let Some(effect) = self.model.update(msg) {
-                self.handle_effect(effect);
+            self.handle_msg(msg);
+        }
+        for msg in poll_subscriptions(&mut self.subs, Instant::now()) {
+            if self.ctx.is_done() {
+                break;
             }
-            self.needs_draw = true;
+            self.handle_msg(msg);
+        }
+        for msg in self.timers.take_due((self.clock)()) {
+            if self.ctx.is_done() {
+                break;
+            }
+            self.handle_msg(msg);
+        }
+        if self.continuation.take_due() && !self.ctx.is_done() {
+            self.handle_msg(Msg::Continue);
         }
     }
 
+    /// Whether the model waits for a [`Msg::Continue`]. Drivers that only
+    /// wake up on input should keep calling
+    /// [`process_pending_msgs`](Self::process_pending_msgs) and
+    /// [`draw_frame`](Self::draw_frame) meanwhile.
+    pub fn is_continuing(&self) -> bool {
+        self.continuation != Continuation::Idle
+    }
+
+    /// Number of active [`Subscription`]s.
+    pub fn subscriptions(&self) -> usize {
+        self.subs.len()
+    }
+
+    /// When the next [`Effect::Tick`] is due, if one is waiting. Drivers
+    /// that sleep between events wake up by then, and deliver it with
+    /// [`process_pending_msgs`](Self::process_pending_msgs).
+    pub fn next_tick(&self) -> Option<Instant> {
+        self.timers.next()
+    }
+
     fn handle_effect(&mut self, effect: Effect) {
         match effect {
             Effect::End => {
                 self.ctx.cancel();
+                self.subs.clear();
+                self.timers.clear();
             }
             Effect::Cmd(f) => {
                 let tx = self.tx.clone();
-                std::thread::spawn(move || {
+                let job = move || {
                     if let Some(msg) = f() {
                         let _ = tx.send(msg);
                     }
-                });
+                };
+                match &mut self.spawner {
+                    Some(spawner) => spawner(Box::new(job)),
+                    None => {
+                        std::thread::spawn(job);
+                    }
+                }
+            }
+            Effect::CmdProgress(f) => {
+                let progress = Progress {
+                    ctx: self.ctx.clone(),
+                    tx: self.tx.clone(),
+                    route: Vec::new(),
+                };
+                match &mut self.spawner {
+                    Some(spawner) => spawner(Box::new(move || run_progress(f, progress))),
+                    None => spawn_progress(f, progress),
+                }
             }
             Effect::Sub(f) => {
                 let ctx = self.ctx.clone();
                 let tx = self.tx.clone();
                 std::thread::spawn(move || f(ctx, tx));
             }
+            Effect::Subscribe(sub) => {
+                if !self.ctx.is_done() {
+                    self.subs.push(sub);
+                }
+            }
+            Effect::Tick { after, msg } => {
+                if !self.ctx.is_done()
+                    && let Some(at) = (self.clock)().checked_add(after)
+                {
+                    self.timers.schedule(at, msg);
+                }
+            }
+            Effect::SetClipboard(text) => {
+                if let Some(clipboard) = &mut self.clipboard {
+                    let _ = clipboard.set_text(&text);
+                }
+            }
+            Effect::Emit(msg) => match msg.downcast_ref::<RecordEvent>() {
+                Some(ev) => self.record_event(ev.tag, &ev.payload),
+                None => {
+                    let _ = self.tx.send(msg);
+                }
+            },
+            Effect::Suspend(f) => f(),
+            Effect::Continue => {
+                if self.continuation == Continuation::Idle {
+                    self.continuation = Continuation::Requested;
+                }
+            }
             Effect::Batch(effects) => {
                 for e in effects {
                     self.handle_effect(e);
@@ -285,6 +1333,12 @@ impl AppRunner {
     }
 }
 
+impl Drop for AppRunner {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // AppConfig / App  (poll-based driver)
 // ---------------------------------------------------------------------------
@@ -295,7 +1349,19 @@ pub struct AppConfig<M: Model, D: Driver> {
     pub driver: D,
     pub width: i32,
     pub height: i32,
+    /// Receives a recording of the frames drawn, without their transient
+    /// overlays, if set.
     pub frame_writer: Option<Box<dyn std::io::Write>>,
+    /// Handlers for custom messages, tried in order before the driver's.
+    pub effect_handlers: Vec<Box<dyn EffectHandler>>,
+    /// Interval within which a second `Main` press on a cell also sends a
+    /// [`MouseAction::DoubleMain`], if set; see [`DOUBLE_CLICK_INTERVAL`].
+    pub double_click: Option<Duration>,
+    /// Master seed of the run, sent to the model as a [`Seed`] message and
+    /// recorded with the frames. If `None`, the `GRUID_SEED` environment
+    /// variable gives it, else one is drawn from entropy and logged. See
+    /// [`seed`].
+    pub seed: Option<u64>,
 }
 
 /// The main application runner for poll-based [`Driver`]s.
@@ -304,62 +1370,128 @@ pub struct App<M: Model, D: Driver> {
     driver: D,
     width: i32,
     height: i32,
-    _frame_writer: Option<Box<dyn std::io::Write>>,
+    /// Encoder of the frames drawn, and when the app started.
+    recording: Option<(FrameEncoder<Box<dyn Write>>, Instant)>,
+    handlers: Vec<Box<dyn EffectHandler>>,
+    subs: Vec<Box<dyn Subscription>>,
+    timers: Timers,
+    continuation: Continuation,
+    double_clicks: Option<DoubleClicks>,
+    seed: u64,
+    /// Cursor of the last frame drawn.
+    cursor: Option<Point>,
+    /// Whether the next frame redraws the whole screen, after a suspend.
+    redraw: bool,
+    latency: Latency,
 }
 
 impl<M: Model, D: Driver> App<M, D> {
-    /// Create a new application from a configuration.
+    /// Create a new application from a configuration, resolving its
+    /// master seed.
     pub fn new(config: AppConfig<M, D>) -> Self {
+        let seed = seed::resolve(config.seed);
         Self {
             model: config.model,
             driver: config.driver,
             width: config.width,
             height: config.height,
-            _frame_writer: config.frame_writer,
+            recording: config.frame_writer.map(|w| {
+                let meta = RecordingMeta {
+                    seed: Some(seed),
+                    ..RecordingMeta::default()
+                };
+                (FrameEncoder::new(w).with_metadata(meta), Instant::now())
+            }),
+            handlers: config.effect_handlers,
+            subs: Vec::new(),
+            timers: Timers::default(),
+            continuation: Continuation::Idle,
+            double_clicks: config.double_click.map(DoubleClicks::new),
+            seed,
+            cursor: None,
+            redraw: false,
+            latency: Latency::default(),
         }
     }
 
+    /// The master seed of the run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Input latencies measured so far, frames being presented once the
+    /// driver's [`flush`](Driver::flush) returns. See [`latency`].
+    pub fn stats(&self) -> RunnerStats {
+        self.latency.stats()
+    }
+
     /// Run the main Model-View-Update loop.
     ///
     /// 1. Initialises the driver.
-    /// 2. Sends `Msg::Init` through the model.
-    /// 3. Enters the event loop: poll → update → draw → diff → flush.
+    /// 2. Sends the master seed as a [`Seed`] message through the model,
+    ///    then `Msg::Init`, then a [`Msg::Screen`] with the configured size.
+    /// 3. Enters the event loop: poll → update → continue → draw → diff →
+    ///    flush, where a [`Msg::Continue`] requested before the previous
+    ///    frame is delivered after the polled messages. Of several
+    ///    `Msg::Screen` polled in a row, only the last is delivered.
     /// 4. Stops when the model returns `Effect::End` or the driver signals
     ///    quit.
+    /// 5. Calls [`Model::on_exit`] and closes the driver, whether the loop
+    ///    ended normally or with an error.
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let driver_handlers = self.driver.effect_handlers();
+        self.handlers.extend(driver_handlers);
         self.driver.init()?;
-
         let ctx = Context::new();
+        let mut res = self.run_loop(&ctx);
+        ctx.cancel();
+        self.model.on_exit();
+        if let Some((mut encoder, _)) = self.recording.take()
+            && let Err(e) = encoder.flush()
+            && res.is_ok()
+        {
+            res = Err(e.into());
+        }
+        self.driver.close();
+        res
+    }
+
+    fn run_loop(&mut self, ctx: &Context) -> Result<(), Box<dyn std::error::Error>> {
         let (tx, rx): (Sender<Msg>, Receiver<Msg>) = mpsc::channel();
 
-        // Seed with Init.
+        // Start with the seed, Init and the size, then what the driver
+        // supports and knows.
+        tx.send(Msg::custom(Seed(self.seed))).ok();
         tx.send(Msg::Init).ok();
+        tx.send(Msg::Screen {
+            width: self.width,
+            height: self.height,
+            time: Instant::now(),
+        })
+        .ok();
+        tx.send(Msg::Capabilities(self.driver.capabilities())).ok();
+        if let Some(theme) = self.driver.system_theme() {
+            tx.send(Msg::custom(theme)).ok();
+        }
 
         let mut prev_grid = Grid::new(self.width, self.height);
         let mut curr_grid = Grid::new(self.width, self.height);
+        let mut overlay = OverlayGrid::new(curr_grid.clone());
 
         // Process the Init message first.
-        self.process_pending(&rx, &ctx, &tx, &mut prev_grid, &mut curr_grid)?;
+        self.process_pending(&rx, ctx, &tx, &mut prev_grid, &mut curr_grid, &mut overlay)?;
 
         // Main loop: poll then process.
         while !ctx.is_done() {
-            match self.driver.poll_msgs(&ctx, tx.clone()) {
-                Ok(()) => {}
-                Err(e) => {
-                    ctx.cancel();
-                    self.driver.close();
-                    return Err(e);
-                }
-            }
+            self.driver.poll_msgs(ctx, tx.clone())?;
 
             if ctx.is_done() {
                 break;
             }
 
-            self.process_pending(&rx, &ctx, &tx, &mut prev_grid, &mut curr_grid)?;
+            self.process_pending(&rx, ctx, &tx, &mut prev_grid, &mut curr_grid, &mut overlay)?;
         }
 
-        self.driver.close();
         Ok(())
     }
 
@@ -370,35 +1502,101 @@ impl<M: Model, D: Driver> App<M, D> {
         tx: &Sender<Msg>,
         prev_grid: &mut Grid,
         curr_grid: &mut Grid,
+        overlay: &mut OverlayGrid,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let mut needs_draw = false;
 
-        while let Ok(msg) = rx.try_recv() {
-            if let Some(effect) = self.model.update(msg) {
-                if self.handle_effect(effect, ctx, tx) {
-                    return Ok(());
+        let now = Instant::now();
+        for msg in poll_subscriptions(&mut self.subs, now) {
+            let _ = tx.send(msg);
+        }
+        for msg in self.timers.take_due(now) {
+            let _ = tx.send(msg);
+        }
+        // A resize waits for the next message, or the end of the batch, in
+        // case another one replaces it.
+        let mut screen = None;
+        loop {
+            let (first, msg) = match rx.try_recv() {
+                Ok(msg @ Msg::Screen { .. }) => {
+                    screen = Some(msg);
+                    continue;
+                }
+                Ok(msg) => (screen.take(), msg),
+                Err(_) => match screen.take() {
+                    Some(msg) => (None, msg),
+                    None => break,
+                },
+            };
+            let double = self.double_clicks.as_mut().and_then(|d| d.click(&msg));
+            self.latency.update(&msg, Instant::now());
+            for msg in first.into_iter().chain(std::iter::once(msg)).chain(double) {
+                if intercept(&mut self.handlers, &msg) {
+                    continue;
+                }
+                if let Some(effect) = self.model.update(msg) {
+                    if self.handle_effect(effect, ctx, tx)? {
+                        return Ok(());
+                    }
                 }
+                needs_draw = true;
+            }
+        }
+
+        if self.continuation.take_due() {
+            if let Some(effect) = self.model.update(Msg::Continue)
+                && self.handle_effect(effect, ctx, tx)?
+            {
+                return Ok(());
             }
             needs_draw = true;
         }
 
         if needs_draw {
+            if std::mem::take(&mut self.redraw) {
+                // The driver cleared the screen on resume.
+                *prev_grid = Grid::new(self.width, self.height);
+                *overlay = OverlayGrid::new(curr_grid.clone());
+                self.cursor = None;
+            }
+            curr_grid.set_cursor(None);
             self.model.draw(curr_grid);
-            let frame = compute_frame(prev_grid, curr_grid);
-            if !frame.cells.is_empty() {
+            let mut frame = compute_frame(prev_grid, curr_grid);
+            prev_grid.copy_from(curr_grid);
+            let changed = !frame.cells.is_empty() || frame.cursor != self.cursor;
+            self.cursor = frame.cursor;
+            if changed && let Some((encoder, start)) = &mut self.recording {
+                frame.time_ms = start.elapsed().as_millis() as u64;
+                encoder.encode(&frame)?;
+            }
+            let mut next = OverlayGrid::new(curr_grid.clone());
+            self.model.draw_transient(&mut next);
+            let frame = compose_overlay(&frame, curr_grid, overlay, &next);
+            *overlay = next;
+            let shown = changed || !frame.cells.is_empty();
+            self.latency.drawn(shown, Instant::now());
+            if shown {
                 self.driver.flush(frame)?;
+                self.latency.presented(Instant::now());
             }
-            prev_grid.copy_from(curr_grid);
+            self.continuation.drawn();
         }
 
         Ok(())
     }
 
-    fn handle_effect(&self, effect: Effect, ctx: &Context, tx: &Sender<Msg>) -> bool {
+    fn handle_effect(
+        &mut self,
+        effect: Effect,
+        ctx: &Context,
+        tx: &Sender<Msg>,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
         match effect {
             Effect::End => {
                 ctx.cancel();
-                true
+                self.subs.clear();
+                self.timers.clear();
+                return Ok(true);
             }
             Effect::Cmd(f) => {
                 let tx = tx.clone();
@@ -407,22 +1605,1573 @@ impl<M: Model, D: Driver> App<M, D> {
                         let _ = tx.send(msg);
                     }
                 });
-                false
+            }
+            Effect::CmdProgress(f) => {
+                let progress = Progress {
+                    ctx: ctx.clone(),
+                    tx: tx.clone(),
+                    route: Vec::new(),
+                };
+                spawn_progress(f, progress);
             }
             Effect::Sub(f) => {
                 let ctx = ctx.clone();
                 let tx = tx.clone();
                 std::thread::spawn(move || f(ctx, tx));
-                false
+            }
+            Effect::Subscribe(sub) => {
+                self.subs.push(sub);
+            }
+            Effect::Tick { after, msg } => {
+                if let Some(at) = Instant::now().checked_add(after) {
+                    self.timers.schedule(at, msg);
+                }
+            }
+            Effect::SetClipboard(text) => {
+                if let Some(clipboard) = self.driver.clipboard() {
+                    let _ = clipboard.set_text(&text);
+                }
+            }
+            Effect::Emit(msg) => {
+                match (msg.downcast_ref::<RecordEvent>(), &mut self.recording) {
+                    (Some(ev), Some((encoder, start))) => {
+                        let time_ms = start.elapsed().as_millis() as u64;
+                        // Frames stop the app on a write error; an event
+                        // is not worth it.
+                        if let Err(e) = encoder.event(time_ms, ev.tag, &ev.payload) {
+                            log::warn!("could not record event: {e}");
+                        }
+                    }
+                    (Some(_), None) => {}
+                    (None, _) => {
+                        let _ = tx.send(msg);
+                    }
+                }
+            }
+            Effect::Suspend(f) => {
+                self.driver.suspend()?;
+                f();
+                self.driver.resume()?;
+                self.redraw = true;
+                let (width, height) = self.driver.size().unwrap_or((self.width, self.height));
+                let _ = tx.send(Msg::Screen {
+                    width,
+                    height,
+                    time: Instant::now(),
+                });
+            }
+            Effect::Continue => {
+                if self.continuation == Continuation::Idle {
+                    self.continuation = Continuation::Requested;
+                }
             }
             Effect::Batch(effects) => {
                 for e in effects {
-                    if self.handle_effect(e, ctx, tx) {
-                        return true;
+                    if self.handle_effect(e, ctx, tx)? {
+                        return Ok(true);
                     }
                 }
-                false
             }
         }
+        Ok(false)
+    }
+}
+
+/// Run an [`Effect::CmdProgress`] closure, sending its final message after
+/// all progress reports.
+fn run_progress(f: ProgressFn, progress: Progress) {
+    if let Some(msg) = f(&progress) {
+        if !progress.is_cancelled() {
+            let _ = progress.tx.send(msg);
+        }
+    }
+}
+
+fn spawn_progress(f: ProgressFn, progress: Progress) {
+    #[cfg(target_arch = "wasm32")]
+    run_progress(f, progress);
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || run_progress(f, progress));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cell, Point};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Done;
+
+    struct Recorder {
+        log: Rc<RefCell<Vec<Msg>>>,
+        on_init: Option<Effect>,
+    }
+
+    impl Model for Recorder {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            let effect = match msg {
+                Msg::Init => self.on_init.take(),
+                Msg::Quit => Some(Effect::End),
+                _ => None,
+            };
+            // Left out so the logs start at Init; see `seed_precedes_init`.
+            if msg.seed().is_none() {
+                self.log.borrow_mut().push(msg);
+            }
+            effect
+        }
+
+        fn draw(&self, _grid: &mut Grid) {}
+    }
+
+    fn runner(on_init: Effect) -> (AppRunner, Rc<RefCell<Vec<Msg>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let model = Recorder {
+            log: Rc::clone(&log),
+            on_init: Some(on_init),
+        };
+        (AppRunner::new(Box::new(model), 10, 4), log)
+    }
+
+    #[test]
+    fn progress_in_order_before_final_msg() {
+        let (mut r, log) = runner(cmd_progress(|p| {
+            for i in 1..=4 {
+                p.report(i as f32 / 4.0, format!("step {i}"));
+            }
+            Some(Msg::custom(Done))
+        }));
+        r.init();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !log
+            .borrow()
+            .iter()
+            .any(|m| m.downcast_ref::<Done>().is_some())
+        {
+            assert!(Instant::now() < deadline, "final message never arrived");
+            r.process_pending_msgs();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let log = log.borrow();
+        let labels: Vec<&str> = log
+            .iter()
+            .filter_map(|m| match m {
+                Msg::Progress { label, .. } => Some(label.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels, ["step 1", "step 2", "step 3", "step 4"]);
+        assert!(log.last().unwrap().downcast_ref::<Done>().is_some());
+    }
+
+    #[test]
+    fn double_clicks_pair_quick_presses_on_one_cell() {
+        let (mut r, log) = runner(Effect::Batch(Vec::new()));
+        r.set_double_click(Some(DOUBLE_CLICK_INTERVAL));
+        let start = Instant::now();
+        let click = |action, x, ms| Msg::Mouse {
+            action,
+            pos: Point::new(x, 1),
+            modifiers: Default::default(),
+            time: start + Duration::from_millis(ms),
+        };
+        let script = [
+            (MouseAction::Main, 0, 0),
+            (MouseAction::Release, 0, 50),
+            (MouseAction::Main, 0, 300),
+            // A third press starts over.
+            (MouseAction::Main, 0, 350),
+            // Too slow.
+            (MouseAction::Main, 0, 800),
+            // Another cell.
+            (MouseAction::Main, 1, 900),
+            (MouseAction::Main, 1, 1300),
+        ];
+        for (action, x, ms) in script {
+            r.handle_msg(click(action, x, ms));
+        }
+        let actions: Vec<_> = log
+            .borrow()
+            .iter()
+            .filter_map(|m| match m {
+                Msg::Mouse { action, pos, .. } => Some((*action, pos.x)),
+                _ => None,
+            })
+            .collect();
+        use MouseAction::{DoubleMain, Main, Release};
+        assert_eq!(
+            actions,
+            [
+                (Main, 0),
+                (Release, 0),
+                (Main, 0),
+                (DoubleMain, 0),
+                (Main, 0),
+                (Main, 0),
+                (Main, 1),
+                (Main, 1),
+                (DoubleMain, 1),
+            ]
+        );
+
+        // Switched off again.
+        r.set_double_click(None);
+        r.handle_msg(click(Main, 2, 2000));
+        r.handle_msg(click(Main, 2, 2010));
+        assert!(matches!(
+            log.borrow().last(),
+            Some(Msg::Mouse { action: Main, .. })
+        ));
+    }
+
+    #[test]
+    fn spawner_runs_commands() {
+        let (mut r, log) = runner(cmd(|| Some(Msg::custom(Done))));
+        let jobs: Rc<RefCell<Vec<Job>>> = Rc::default();
+        let queue = Rc::clone(&jobs);
+        r.set_spawner(move |job| queue.borrow_mut().push(job));
+        r.init();
+        // Nothing runs until the spawner does.
+        assert_eq!(jobs.borrow().len(), 1);
+        r.process_pending_msgs();
+        assert_eq!(log.borrow().len(), 2, "Init and Screen");
+        for job in jobs.borrow_mut().drain(..) {
+            job();
+        }
+        r.process_pending_msgs();
+        assert!(log.borrow()[2].downcast_ref::<Done>().is_some());
+    }
+
+    #[test]
+    fn spawner_defers_progress_commands() {
+        let (mut r, log) = runner(cmd_progress(|p| {
+            p.report(1.0, "done");
+            Some(Msg::custom(Done))
+        }));
+        let jobs: Rc<RefCell<Vec<Job>>> = Rc::default();
+        let queue = Rc::clone(&jobs);
+        r.set_spawner(move |job| queue.borrow_mut().push(job));
+        r.init();
+        // The model can draw its loading screen before the work starts.
+        r.process_pending_msgs();
+        assert_eq!(jobs.borrow().len(), 1);
+        assert_eq!(log.borrow().len(), 2, "Init and Screen");
+        for job in jobs.borrow_mut().drain(..) {
+            job();
+        }
+        r.process_pending_msgs();
+        let log = log.borrow();
+        assert!(matches!(log[2], Msg::Progress { .. }));
+        assert!(log[3].downcast_ref::<Done>().is_some());
+    }
+
+    /// Gives its messages one poll at a time, then ends.
+    struct Script(Vec<Option<Msg>>);
+
+    impl Subscription for Script {
+        fn poll(&mut self, _now: Instant) -> SubPoll {
+            match self.0.pop() {
+                Some(Some(msg)) => SubPoll::Msg(msg),
+                Some(None) => SubPoll::Pending,
+                None => SubPoll::Done,
+            }
+        }
+    }
+
+    #[test]
+    fn subscriptions_polled_until_done() {
+        let script = Script(vec![None, Some(Msg::Continue), None, Some(Msg::Quit)]);
+        let (mut r, log) = runner(Effect::Subscribe(Box::new(script)));
+        r.init();
+        assert_eq!(r.subscriptions(), 1);
+        // Each poll takes messages until the subscription has none.
+        r.process_pending_msgs();
+        assert!(matches!(log.borrow()[2], Msg::Quit));
+        // The model ended on Quit: the subscription is gone.
+        assert_eq!(r.subscriptions(), 0);
+        r.process_pending_msgs();
+        assert_eq!(log.borrow().len(), 3);
+
+        let script = Script(vec![None, Some(Msg::Continue)]);
+        let (mut r, log) = runner(Effect::Subscribe(Box::new(script)));
+        r.init();
+        r.process_pending_msgs();
+        r.process_pending_msgs();
+        assert_eq!(log.borrow().len(), 3);
+        assert_eq!(r.subscriptions(), 0);
+    }
+
+    #[test]
+    fn every_skips_missed_ticks() {
+        let n = Rc::new(std::cell::Cell::new(0));
+        let count = Rc::clone(&n);
+        let Effect::Subscribe(mut sub) = every(Duration::from_secs(1), move || {
+            count.set(count.get() + 1);
+            (count.get() < 3).then_some(Msg::Continue)
+        }) else {
+            unreachable!()
+        };
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        assert!(matches!(sub.poll(at(0)), SubPoll::Pending));
+        assert!(matches!(sub.poll(at(999)), SubPoll::Pending));
+        assert!(matches!(sub.poll(at(1000)), SubPoll::Msg(_)));
+        assert!(matches!(sub.poll(at(1000)), SubPoll::Pending));
+        // Five seconds late: one tick, then a full interval.
+        assert!(matches!(sub.poll(at(7000)), SubPoll::Msg(_)));
+        assert!(matches!(sub.poll(at(7999)), SubPoll::Pending));
+        assert!(matches!(sub.poll(at(8000)), SubPoll::Done));
+        assert_eq!(n.get(), 3);
+    }
+
+    #[test]
+    fn ticks_delivered_when_due() {
+        let ms = Duration::from_millis;
+        let (mut r, log) = runner(Effect::Batch(vec![
+            tick(ms(20), Msg::custom(Done)),
+            tick(ms(10), Msg::Paste("soon".into())),
+            tick(ms(20), Msg::Paste("last".into())),
+        ]));
+        let t0 = Instant::now();
+        let clock = Rc::new(std::cell::Cell::new(t0));
+        let now = Rc::clone(&clock);
+        r.set_clock(move || now.get());
+        r.init();
+        assert_eq!(r.next_tick(), Some(t0 + ms(10)));
+
+        clock.set(t0 + ms(9));
+        r.process_pending_msgs();
+        assert_eq!(log.borrow().len(), 2, "Init and Screen");
+        clock.set(t0 + ms(10));
+        r.process_pending_msgs();
+        assert!(matches!(&log.borrow()[2], Msg::Paste(s) if s == "soon"));
+        assert_eq!(r.next_tick(), Some(t0 + ms(20)));
+        // Late: both ticks due, in the order they were asked for.
+        clock.set(t0 + ms(500));
+        r.process_pending_msgs();
+        assert!(log.borrow()[3].is_custom::<Done>());
+        assert!(matches!(&log.borrow()[4], Msg::Paste(s) if s == "last"));
+        assert_eq!(r.next_tick(), None);
+    }
+
+    #[test]
+    fn distant_ticks_never_fire() {
+        let (mut r, log) = runner(tick(Duration::MAX, Msg::custom(Done)));
+        r.init();
+        assert_eq!(r.next_tick(), None);
+        r.process_pending_msgs();
+        assert!(!log.borrow().iter().any(|m| m.is_custom::<Done>()));
+    }
+
+    #[test]
+    fn end_cancels_ticks() {
+        let (mut r, log) = runner(tick(Duration::ZERO, Msg::custom(Done)));
+        r.init();
+        assert!(r.next_tick().is_some());
+        r.handle_msg(Msg::Quit);
+        assert_eq!(r.next_tick(), None);
+        r.process_pending_msgs();
+        assert!(!log.borrow().iter().any(|m| m.is_custom::<Done>()));
+    }
+
+    /// Asks for a tick on Init, and ends when it arrives, noting how long
+    /// it took.
+    struct Timed {
+        asked: Option<Instant>,
+        took: Rc<std::cell::Cell<Option<Duration>>>,
+    }
+
+    impl Model for Timed {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            if let Msg::Init = msg {
+                self.asked = Some(Instant::now());
+                return Some(tick(Duration::from_millis(30), Msg::custom(Done)));
+            }
+            if msg.is_custom::<Done>() {
+                self.took.set(self.asked.map(|t| t.elapsed()));
+                return Some(Effect::End);
+            }
+            None
+        }
+
+        fn draw(&self, _grid: &mut Grid) {}
+    }
+
+    /// Poll driver with no input, giving up after a while.
+    struct Idle(Instant);
+
+    impl Driver for Idle {
+        fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn poll_msgs(
+            &mut self,
+            ctx: &Context,
+            _tx: Sender<Msg>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            std::thread::sleep(Duration::from_millis(2));
+            if self.0.elapsed() > Duration::from_secs(5) {
+                ctx.cancel();
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self, _frame: Frame) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn app_delivers_ticks_after_their_delay() {
+        let took = Rc::new(std::cell::Cell::new(None));
+        let mut app = App::new(AppConfig {
+            model: Timed {
+                asked: None,
+                took: Rc::clone(&took),
+            },
+            driver: Idle(Instant::now()),
+            width: 1,
+            height: 1,
+            frame_writer: None,
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: None,
+        });
+        app.run().unwrap();
+        let took = took.get().expect("tick delivered");
+        assert!(took >= Duration::from_millis(30), "{took:?}");
+        assert!(took < Duration::from_secs(2), "{took:?}");
+    }
+
+    /// Counts `on_exit` calls; ends on Escape.
+    struct Exits(Rc<std::cell::Cell<u32>>);
+
+    impl Model for Exits {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            match msg {
+                Msg::KeyDown {
+                    key: crate::Key::Escape,
+                    ..
+                } => Some(Effect::End),
+                _ => None,
+            }
+        }
+
+        fn draw(&self, _grid: &mut Grid) {}
+
+        fn on_exit(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn runner_on_exit_once() {
+        // Model-initiated quit.
+        let exits = Rc::new(std::cell::Cell::new(0));
+        let mut r = AppRunner::new(Box::new(Exits(Rc::clone(&exits))), 4, 4);
+        r.init();
+        r.handle_msg(Msg::key(crate::Key::Escape));
+        assert!(r.should_quit());
+        assert_eq!(exits.get(), 0);
+        r.shutdown();
+        r.shutdown();
+        drop(r);
+        assert_eq!(exits.get(), 1);
+
+        // Driver-initiated quit: the model ignores Msg::Quit.
+        let exits = Rc::new(std::cell::Cell::new(0));
+        let mut r = AppRunner::new(Box::new(Exits(Rc::clone(&exits))), 4, 4);
+        r.init();
+        r.handle_msg(Msg::Quit);
+        r.shutdown();
+        assert!(r.should_quit());
+        drop(r);
+        assert_eq!(exits.get(), 1);
+
+        // A driver that forgets to call shutdown still delivers on drop.
+        let exits = Rc::new(std::cell::Cell::new(0));
+        drop(AppRunner::new(Box::new(Exits(Rc::clone(&exits))), 4, 4));
+        assert_eq!(exits.get(), 1);
+    }
+
+    /// Poll driver that sends scripted messages, then quits or fails.
+    struct MockDriver {
+        script: Vec<Msg>,
+        fail: bool,
+        closed: Rc<std::cell::Cell<bool>>,
+    }
+
+    impl Driver for MockDriver {
+        fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn poll_msgs(
+            &mut self,
+            ctx: &Context,
+            tx: Sender<Msg>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            if let Some(msg) = self.script.pop() {
+                tx.send(msg).ok();
+            } else if self.fail {
+                return Err("driver failed".into());
+            } else {
+                ctx.cancel();
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self, _frame: Frame) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn close(&mut self) {
+            self.closed.set(true);
+        }
+    }
+
+    fn run_app(script: Vec<Msg>, fail: bool) -> (bool, u32, bool) {
+        let exits = Rc::new(std::cell::Cell::new(0));
+        let closed = Rc::new(std::cell::Cell::new(false));
+        let mut app = App::new(AppConfig {
+            model: Exits(Rc::clone(&exits)),
+            driver: MockDriver {
+                script,
+                fail,
+                closed: Rc::clone(&closed),
+            },
+            width: 4,
+            height: 4,
+            frame_writer: None,
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: None,
+        });
+        let ok = app.run().is_ok();
+        (ok, exits.get(), closed.get())
+    }
+
+    #[test]
+    fn app_on_exit_once() {
+        assert_eq!(
+            run_app(vec![Msg::key(crate::Key::Escape)], false),
+            (true, 1, true)
+        );
+        assert_eq!(run_app(Vec::new(), false), (true, 1, true));
+        assert_eq!(run_app(Vec::new(), true), (false, 1, true));
+    }
+
+    /// Shows how many keys were pressed.
+    struct KeyCount(u32);
+
+    impl Model for KeyCount {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            self.0 += u32::from(matches!(msg, Msg::KeyDown { .. }));
+            None
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            let digit = char::from_digit(self.0 % 10, 10).unwrap();
+            grid.set(Point::new(0, 0), crate::Cell::default().with_char(digit));
+        }
+    }
+
+    #[test]
+    fn app_measures_input_latency() {
+        // Keys that happened a while ago, each polled on its own.
+        let ago = Instant::now() - Duration::from_millis(50);
+        let key = || Msg::key(crate::Key::Enter).with_time(ago);
+        let mut app = App::new(AppConfig {
+            model: KeyCount(0),
+            driver: MockDriver {
+                script: vec![key(), key()],
+                fail: false,
+                closed: Rc::default(),
+            },
+            width: 1,
+            height: 1,
+            frame_writer: None,
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: None,
+        });
+        app.run().unwrap();
+        let s = app.stats();
+        assert_eq!(
+            (s.update.samples, s.frame.samples, s.present.samples),
+            (2, 2, 2)
+        );
+        assert!(s.update.p50 >= Duration::from_millis(50));
+        assert!(s.frame.p50 >= s.update.p50 && s.present.max >= s.frame.max);
+    }
+
+    struct SharedClipboard(Rc<RefCell<Option<String>>>);
+
+    impl ClipboardProvider for SharedClipboard {
+        fn set_text(&mut self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+            *self.0.borrow_mut() = Some(text.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn set_clipboard_reaches_provider() {
+        // Without a provider the effect is dropped.
+        let (mut r, _) = runner(set_clipboard("lost"));
+        r.init();
+
+        let text = Rc::new(RefCell::new(None));
+        let (mut r, _) = runner(Effect::Batch(vec![
+            set_clipboard("seed 42"),
+            set_clipboard("seed 43"),
+        ]));
+        r.set_clipboard(Box::new(SharedClipboard(Rc::clone(&text))));
+        r.init();
+        assert_eq!(text.borrow().as_deref(), Some("seed 43"));
+    }
+
+    #[test]
+    fn capabilities_follow_init() {
+        let caps = DriverCaps {
+            mouse: true,
+            ..DriverCaps::default()
+        };
+        let (mut r, log) = runner(Effect::Batch(Vec::new()));
+        r.set_capabilities(caps);
+        assert!(log.borrow().is_empty());
+        r.init();
+        // Unchanged capabilities are not sent again.
+        r.set_capabilities(caps);
+        let richer = DriverCaps {
+            unicode: true,
+            ..caps
+        };
+        r.set_capabilities(richer);
+        let log = log.borrow();
+        assert_eq!(log.len(), 4);
+        assert!(matches!(log[0], Msg::Init));
+        assert!(matches!(log[1], Msg::Screen { .. }));
+        assert!(matches!(log[2], Msg::Capabilities(c) if c == caps));
+        assert!(matches!(log[3], Msg::Capabilities(c) if c == richer));
+        assert_eq!(r.capabilities(), Some(richer));
+    }
+
+    #[test]
+    fn system_theme_follows_capabilities() {
+        let (mut r, log) = runner(Effect::Batch(Vec::new()));
+        r.set_system_theme(SystemTheme::Dark);
+        r.set_capabilities(DriverCaps::default());
+        r.init();
+        r.set_system_theme(SystemTheme::Dark);
+        r.set_system_theme(SystemTheme::Light);
+        let log = log.borrow();
+        assert_eq!(log.len(), 5);
+        assert!(matches!(log[2], Msg::Capabilities(_)));
+        assert_eq!(log[3].system_theme(), Some(SystemTheme::Dark));
+        assert_eq!(log[4].system_theme(), Some(SystemTheme::Light));
+        assert_eq!(log[0].system_theme(), None);
+        assert_eq!(r.system_theme(), Some(SystemTheme::Light));
+    }
+
+    fn screens(log: &[Msg]) -> Vec<(i32, i32)> {
+        log.iter()
+            .filter_map(|m| match m {
+                Msg::Screen { width, height, .. } => Some((*width, *height)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn screen_follows_init() {
+        // Synthesized from the runner size.
+        let (mut r, log) = runner(Effect::Batch(Vec::new()));
+        r.init();
+        assert_eq!(screens(&log.borrow()), [(10, 4)]);
+
+        // The driver's size, once, if it gave one before.
+        let (mut r, log) = runner(emit(Done));
+        r.screen(30, 8);
+        r.screen(40, 12);
+        assert!(log.borrow().is_empty());
+        r.init();
+        r.process_pending_msgs();
+        assert!(r.draw_frame().is_none());
+        let log = log.borrow();
+        assert_eq!(screens(&log), [(40, 12)]);
+        assert!(matches!(log[1], Msg::Screen { .. }));
+        // Messages of Init's effects come after.
+        assert!(log[2].downcast_ref::<Done>().is_some());
+        assert_eq!((r.width(), r.height()), (40, 12));
+    }
+
+    #[test]
+    fn resize_bursts_coalesce() {
+        let (mut r, log) = runner(Effect::Batch(Vec::new()));
+        r.init();
+        r.draw_frame();
+        for w in 11..=30 {
+            r.screen(w, 5);
+        }
+        assert_eq!(screens(&log.borrow()), [(10, 4)]);
+        // One per frame, the latest.
+        r.draw_frame();
+        assert_eq!(screens(&log.borrow()), [(10, 4), (30, 5)]);
+        assert!(r.draw_frame().is_none());
+        assert_eq!(screens(&log.borrow()).len(), 2);
+
+        // Other messages never see a stale size: the final one goes first.
+        r.screen(31, 5);
+        r.screen(32, 6);
+        r.handle_msg(Msg::key(crate::Key::Enter));
+        let log = log.borrow();
+        assert_eq!(screens(&log), [(10, 4), (30, 5), (32, 6)]);
+        assert!(matches!(log[log.len() - 2], Msg::Screen { .. }));
+        assert!(matches!(log[log.len() - 1], Msg::KeyDown { .. }));
+    }
+
+    /// Sends a batch of messages per poll.
+    struct Bursts(Vec<Vec<Msg>>);
+
+    impl Driver for Bursts {
+        fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn poll_msgs(
+            &mut self,
+            ctx: &Context,
+            tx: Sender<Msg>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            let Some(batch) = self.0.pop() else {
+                ctx.cancel();
+                return Ok(());
+            };
+            for msg in batch {
+                tx.send(msg).ok();
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self, _frame: Frame) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn app_coalesces_resizes_per_frame() {
+        let screen = |width| Msg::Screen {
+            width,
+            height: 3,
+            time: Instant::now(),
+        };
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(AppConfig {
+            model: Recorder {
+                log: Rc::clone(&log),
+                on_init: None,
+            },
+            driver: Bursts(vec![
+                vec![screen(9), screen(10)],
+                vec![
+                    screen(5),
+                    screen(6),
+                    Msg::key(crate::Key::Enter),
+                    screen(7),
+                    screen(8),
+                ],
+            ]),
+            width: 4,
+            height: 4,
+            frame_writer: None,
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: None,
+        });
+        app.run().unwrap();
+        let log = log.borrow();
+        assert_eq!(screens(&log), [(4, 4), (6, 3), (8, 3), (10, 3)]);
+        assert!(matches!(log[1], Msg::Screen { .. }));
+        assert!(matches!(log[4], Msg::KeyDown { .. }));
+    }
+
+    #[test]
+    fn app_sends_capabilities_after_init() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(AppConfig {
+            model: Recorder {
+                log: Rc::clone(&log),
+                on_init: None,
+            },
+            driver: MockDriver {
+                script: vec![Msg::Quit],
+                fail: false,
+                closed: Rc::new(std::cell::Cell::new(false)),
+            },
+            width: 4,
+            height: 4,
+            frame_writer: None,
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: None,
+        });
+        app.run().unwrap();
+        let log = log.borrow();
+        assert!(matches!(log[0], Msg::Init));
+        assert!(matches!(
+            log[1],
+            Msg::Screen {
+                width: 4,
+                height: 4,
+                ..
+            }
+        ));
+        assert!(matches!(log[2], Msg::Capabilities(c) if c == DriverCaps::default()));
+    }
+
+    struct Sound(&'static str);
+
+    /// A handler logging `Sound`s as `"{name}:{sound}"`, consuming them
+    /// when `consume` is set.
+    fn sound_logger(
+        name: &'static str,
+        consume: bool,
+        log: &Rc<RefCell<Vec<String>>>,
+    ) -> Box<dyn EffectHandler> {
+        let log = Rc::clone(log);
+        effect_handler(move |s: &Sound| {
+            log.borrow_mut().push(format!("{name}:{}", s.0));
+            consume
+        })
+    }
+
+    #[test]
+    fn effect_handlers_in_order_first_consumer_wins() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let (mut r, log) = runner(Effect::Batch(vec![
+            emit(Sound("step")),
+            emit(Done),
+            set_window_title("Cave 1"),
+        ]));
+        r.add_effect_handler(sound_logger("peek", false, &calls));
+        r.add_effect_handler(sound_logger("audio", true, &calls));
+        r.add_effect_handler(sound_logger("never", true, &calls));
+        r.init();
+        // Emitted messages are delivered after the current one.
+        assert!(calls.borrow().is_empty());
+        r.process_pending_msgs();
+        assert_eq!(*calls.borrow(), ["peek:step", "audio:step"]);
+
+        // Messages nobody claims reach the model.
+        let log = log.borrow();
+        assert_eq!(log.len(), 4);
+        assert!(matches!(log[0], Msg::Init));
+        assert!(log[2].downcast_ref::<Done>().is_some());
+        assert_eq!(
+            log[3].downcast_ref::<SetWindowTitle>(),
+            Some(&SetWindowTitle("Cave 1".into()))
+        );
+    }
+
+    #[test]
+    fn effect_handlers_see_driver_messages() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let (mut r, log) = runner(Effect::Batch(Vec::new()));
+        r.add_effect_handler(sound_logger("audio", true, &calls));
+        r.init();
+        r.handle_msg(Msg::custom(Sound("click")));
+        r.handle_msg(Msg::key(crate::Key::Enter));
+        assert_eq!(*calls.borrow(), ["audio:click"]);
+        assert_eq!(log.borrow().len(), 3, "Init, Screen and the key");
+    }
+
+    /// Model filling its grid, that suspends the driver to run an
+    /// "editor" on `'e'`, and quits on Escape.
+    struct Shell(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl Model for Shell {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            match msg {
+                Msg::Screen { width, height, .. } => self
+                    .0
+                    .lock()
+                    .unwrap()
+                    .push(format!("screen {width}x{height}")),
+                Msg::KeyDown {
+                    key: crate::Key::Escape,
+                    ..
+                } => return Some(Effect::End),
+                Msg::KeyDown {
+                    key: crate::Key::Char('e'),
+                    ..
+                } => {
+                    let log = std::sync::Arc::clone(&self.0);
+                    return Some(suspend(move || log.lock().unwrap().push("editor".into())));
+                }
+                _ => {}
+            }
+            None
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            grid.fill(Cell::default().with_char('#'));
+        }
+    }
+
+    /// Poll driver sending scripted messages, logging the other calls.
+    struct Suspending {
+        script: Vec<Msg>,
+        log: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl Driver for Suspending {
+        fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn poll_msgs(
+            &mut self,
+            _ctx: &Context,
+            tx: Sender<Msg>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            if let Some(msg) = self.script.pop() {
+                tx.send(msg).ok();
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self, frame: Frame) -> Result<(), Box<dyn std::error::Error>> {
+            let cells = frame.cells.len();
+            self.log.lock().unwrap().push(format!("flush {cells}"));
+            Ok(())
+        }
+
+        fn close(&mut self) {}
+
+        fn suspend(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            self.log.lock().unwrap().push("suspend".into());
+            Ok(())
+        }
+
+        fn resume(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            self.log.lock().unwrap().push("resume".into());
+            Ok(())
+        }
+
+        /// The terminal was resized while suspended.
+        fn size(&self) -> Option<(i32, i32)> {
+            Some((6, 3))
+        }
+    }
+
+    #[test]
+    fn suspend_wraps_closure_and_redraws() {
+        let log = std::sync::Arc::default();
+        let mut app = App::new(AppConfig {
+            model: Shell(std::sync::Arc::clone(&log)),
+            driver: Suspending {
+                script: vec![
+                    Msg::key(crate::Key::Escape),
+                    Msg::key(crate::Key::Char('x')),
+                    Msg::key(crate::Key::Char('e')),
+                ],
+                log: std::sync::Arc::clone(&log),
+            },
+            width: 4,
+            height: 2,
+            frame_writer: None,
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: None,
+        });
+        app.run().unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "screen 4x2",
+                "flush 8",
+                "suspend",
+                "editor",
+                "resume",
+                // The size on resume, and the whole screen again, though
+                // the grid did not change, then only changes: none for the
+                // last key.
+                "screen 6x3",
+                "flush 8",
+            ]
+        );
+    }
+
+    /// Poll driver with its own handler, counting titles it is asked for.
+    struct TitledDriver {
+        script: Vec<Msg>,
+        titles: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Driver for TitledDriver {
+        fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn poll_msgs(
+            &mut self,
+            ctx: &Context,
+            tx: Sender<Msg>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            match self.script.pop() {
+                Some(msg) => tx.send(msg).map_err(|e| e.to_string())?,
+                None => ctx.cancel(),
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self, _frame: Frame) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn close(&mut self) {}
+
+        fn effect_handlers(&mut self) -> Vec<Box<dyn EffectHandler>> {
+            let titles = Rc::clone(&self.titles);
+            vec![effect_handler(move |t: &SetWindowTitle| {
+                titles.borrow_mut().push(t.0.clone());
+                true
+            })]
+        }
+    }
+
+    #[test]
+    fn app_handlers_before_driver_handlers() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let titles = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::clone(&calls);
+        let mut app = App::new(AppConfig {
+            model: Recorder {
+                log: Rc::clone(&log),
+                on_init: Some(Effect::Batch(vec![
+                    set_window_title("gruid"),
+                    emit(Sound("init")),
+                ])),
+            },
+            driver: TitledDriver {
+                script: vec![Msg::custom(Sound("late")), set_title_msg("Cave 2")],
+                titles: Rc::clone(&titles),
+            },
+            width: 4,
+            height: 4,
+            frame_writer: None,
+            effect_handlers: vec![
+                // Sees titles first, without consuming them.
+                effect_handler(move |t: &SetWindowTitle| {
+                    seen.borrow_mut().push(format!("app:{}", t.0));
+                    false
+                }),
+                sound_logger("audio", true, &calls),
+            ],
+            double_click: None,
+            seed: None,
+        });
+        app.run().unwrap();
+        assert_eq!(
+            *calls.borrow(),
+            ["app:gruid", "audio:init", "app:Cave 2", "audio:late"]
+        );
+        assert_eq!(*titles.borrow(), ["gruid", "Cave 2"]);
+        // Init, Screen and Capabilities only.
+        assert_eq!(log.borrow().len(), 3);
+    }
+
+    fn set_title_msg(title: &str) -> Msg {
+        Msg::custom(SetWindowTitle(title.into()))
+    }
+
+    #[test]
+    fn progress_worker_stops_on_quit() {
+        let exited = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&exited);
+        let (mut r, _log) = runner(cmd_progress(move |p| {
+            while !p.is_cancelled() {
+                p.report(0.5, "working");
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            flag.store(true, Ordering::SeqCst);
+            Some(Msg::custom(Done))
+        }));
+        r.init();
+        r.handle_msg(Msg::Quit);
+        assert!(r.should_quit());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !exited.load(Ordering::SeqCst) {
+            assert!(Instant::now() < deadline, "worker thread leaked");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Works through 50 steps, one per [`Msg::Continue`], until a key
+    /// cancels it. Draws the step count and records what it drew.
+    struct Stepper {
+        steps: u32,
+        cancelled: bool,
+        drawn: Rc<RefCell<Vec<u32>>>,
+    }
+
+    impl Stepper {
+        fn new() -> (Self, Rc<RefCell<Vec<u32>>>) {
+            let drawn = Rc::new(RefCell::new(Vec::new()));
+            let model = Self {
+                steps: 0,
+                cancelled: false,
+                drawn: Rc::clone(&drawn),
+            };
+            (model, drawn)
+        }
+    }
+
+    impl Model for Stepper {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            match msg {
+                Msg::Init => Some(Effect::Continue),
+                Msg::KeyDown { .. } => {
+                    self.cancelled = true;
+                    None
+                }
+                Msg::Continue if !self.cancelled => {
+                    self.steps += 1;
+                    (self.steps < 50).then_some(Effect::Continue)
+                }
+                _ => None,
+            }
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            let ch = char::from_digit(self.steps % 10, 10).unwrap();
+            grid.set(crate::Point::ZERO, crate::Cell::default().with_char(ch));
+            self.drawn.borrow_mut().push(self.steps);
+        }
+    }
+
+    /// Runs frames of `r`, pressing a key before frame `key_at`.
+    fn run_frames(r: &mut AppRunner, frames: usize, key_at: Option<usize>) -> usize {
+        let mut drawn = 0;
+        for i in 0..frames {
+            if key_at == Some(i) {
+                r.handle_msg(Msg::key(crate::Key::Char('x')));
+            }
+            r.process_pending_msgs();
+            drawn += r.draw_frame().is_some() as usize;
+        }
+        drawn
+    }
+
+    #[test]
+    fn continuation_draws_between_steps() {
+        let (model, drawn) = Stepper::new();
+        let mut r = AppRunner::new(Box::new(model), 4, 1);
+        r.init();
+        assert!(r.is_continuing());
+        assert_eq!(run_frames(&mut r, 60, None), 51);
+        assert!(!r.is_continuing());
+        // One step per frame, each drawn.
+        assert_eq!(*drawn.borrow(), (0..=50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn continuation_cancelled_by_input() {
+        let (model, drawn) = Stepper::new();
+        let mut r = AppRunner::new(Box::new(model), 4, 1);
+        r.init();
+        // Steps 1..=19 come in frames 1..=19; the key arrives before the
+        // continuation due in frame 20.
+        run_frames(&mut r, 60, Some(20));
+        assert!(!r.is_continuing());
+        let drawn = drawn.borrow();
+        assert_eq!(drawn.last(), Some(&19));
+        assert_eq!(drawn[..20], (0..20).collect::<Vec<_>>());
+    }
+
+    /// A writer whose bytes stay readable after it was given away.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn recording_starts_with_whole_screen() {
+        let (model, _) = Stepper::new();
+        let mut r = AppRunner::new(Box::new(model), 4, 1);
+        r.init();
+        // Cancelled at step 4: the screen no longer changes.
+        run_frames(&mut r, 10, Some(5));
+        assert!(r.draw_frame().is_none());
+
+        let buf = SharedBuf::default();
+        let meta = RecordingMeta::new("stepper", "1.0").with_seed(3);
+        r.start_recording(buf.clone(), Some(meta.clone())).unwrap();
+        assert!(r.is_recording());
+        let frame = r.draw_frame().unwrap();
+        assert_eq!(frame.cells.len(), 1);
+        assert!(r.draw_frame().is_none());
+        r.stop_recording().unwrap();
+        assert!(!r.is_recording());
+
+        let data = buf.0.borrow();
+        let mut dec = crate::recording::FrameDecoder::new(data.as_slice());
+        assert_eq!(dec.metadata(), Some(&meta));
+        let recorded = dec.decode().unwrap().unwrap();
+        assert_eq!(recorded.cells[0].cell.ch, '4');
+        assert!(dec.decode().unwrap().is_none());
+    }
+
+    /// Draws the last key typed, recording an event for each.
+    struct Beeper(char);
+
+    impl Model for Beeper {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            match msg {
+                Msg::KeyDown {
+                    key: crate::Key::Char(c),
+                    ..
+                } => {
+                    self.0 = c;
+                    Some(record_event(1, vec![c as u8]))
+                }
+                _ => None,
+            }
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            grid.set(Point::new(0, 0), Cell::default().with_char(self.0));
+        }
+    }
+
+    #[test]
+    fn recorded_events_precede_their_frame() {
+        use crate::recording::Record;
+        let mut r = AppRunner::new(Box::new(Beeper('.')), 1, 1);
+        r.init();
+        // Not recording: dropped.
+        r.handle_msg(Msg::key(crate::Key::Char('x')));
+        let buf = SharedBuf::default();
+        r.start_recording(buf.clone(), None).unwrap();
+        r.draw_frame().unwrap();
+        r.handle_msg(Msg::key(crate::Key::Char('a')));
+        r.record_event(2, b"direct");
+        r.process_pending_msgs();
+        r.draw_frame().unwrap();
+        r.stop_recording().unwrap();
+
+        let data = buf.0.borrow();
+        let mut dec = crate::recording::FrameDecoder::new(data.as_slice());
+        let mut records = Vec::new();
+        while let Some(record) = dec.decode_record().unwrap() {
+            records.push(match record {
+                Record::Frame(f) => format!("frame {}", f.cells[0].cell.ch),
+                Record::Event(e) => format!("event {} {:?}", e.tag, e.payload),
+            });
+        }
+        assert_eq!(
+            records,
+            [
+                "frame x",
+                "event 1 [97]",
+                "event 2 [100, 105, 114, 101, 99, 116]",
+                "frame a"
+            ]
+        );
+    }
+
+    /// Logs the seeds received, and `None` for Init.
+    struct SeedLog(Rc<RefCell<Vec<Option<u64>>>>);
+
+    impl Model for SeedLog {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            if msg.seed().is_some() || matches!(msg, Msg::Init) {
+                self.0.borrow_mut().push(msg.seed());
+            }
+            matches!(msg, Msg::Quit).then_some(Effect::End)
+        }
+
+        fn draw(&self, _grid: &mut Grid) {}
+    }
+
+    #[test]
+    fn seed_precedes_init() {
+        // The configured seed, whatever the environment says.
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut r = AppRunner::new(Box::new(SeedLog(Rc::clone(&log))), 4, 1);
+        assert_eq!(r.seed(), None);
+        r.set_seed(7);
+        r.init();
+        assert_eq!(r.seed(), Some(7));
+        assert_eq!(*log.borrow(), [Some(7), None]);
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let buf = SharedBuf::default();
+        let mut app = App::new(AppConfig {
+            model: SeedLog(Rc::clone(&log)),
+            driver: MockDriver {
+                script: vec![Msg::Quit],
+                fail: false,
+                closed: Rc::new(std::cell::Cell::new(false)),
+            },
+            width: 4,
+            height: 4,
+            frame_writer: Some(Box::new(buf.clone())),
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: Some(9),
+        });
+        app.run().unwrap();
+        assert_eq!(app.seed(), 9);
+        assert_eq!(*log.borrow(), [Some(9), None]);
+        let data = buf.0.borrow();
+        let dec = crate::recording::FrameDecoder::new(data.as_slice());
+        assert_eq!(dec.metadata().unwrap().seed, Some(9));
+    }
+
+    #[test]
+    fn recording_carries_seed() {
+        let mut r = AppRunner::new(Box::new(Hover(Rc::default())), 4, 1);
+        let buf = SharedBuf::default();
+        // Resolved for the recording, then kept for init.
+        r.start_recording(buf.clone(), None).unwrap();
+        let seed = r.seed().unwrap();
+        r.init();
+        assert_eq!(r.seed(), Some(seed));
+        r.draw_frame();
+        r.stop_recording().unwrap();
+        let data = buf.0.borrow();
+        let dec = crate::recording::FrameDecoder::new(data.as_slice());
+        assert_eq!(dec.metadata().unwrap().seed, Some(seed));
+    }
+
+    /// What [`Hover`] draws.
+    #[derive(Default)]
+    struct HoverState {
+        wall: std::cell::Cell<bool>,
+        cursor: std::cell::Cell<Option<Point>>,
+    }
+
+    /// Model drawing a row of `'.'`, with an `'@'` on it when `wall` is
+    /// set, and a transient `'*'` at `cursor`.
+    struct Hover(Rc<HoverState>);
+
+    impl Model for Hover {
+        fn update(&mut self, _msg: Msg) -> Option<Effect> {
+            None
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            grid.fill(Cell::default().with_char('.'));
+            if self.0.wall.get() {
+                grid.set(Point::new(1, 0), Cell::default().with_char('@'));
+            }
+        }
+
+        fn draw_transient(&self, overlay: &mut OverlayGrid) {
+            if let Some(p) = self.0.cursor.get() {
+                overlay.set(p, overlay.base(p).with_char('*'));
+            }
+        }
+    }
+
+    fn hover_runner() -> (AppRunner, Rc<HoverState>) {
+        let state = Rc::new(HoverState::default());
+        let mut r = AppRunner::new(Box::new(Hover(Rc::clone(&state))), 4, 1);
+        r.init();
+        (r, state)
+    }
+
+    fn chars(frame: &Frame) -> Vec<(i32, char)> {
+        frame
+            .cells
+            .iter()
+            .map(|fc| (fc.pos.x, fc.cell.ch))
+            .collect()
+    }
+
+    #[test]
+    fn overlay_cells_restored_when_gone() {
+        let (mut r, hover) = hover_runner();
+        hover.cursor.set(Some(Point::new(2, 0)));
+        let frame = r.draw_frame().unwrap();
+        assert_eq!(chars(&frame), [(0, '.'), (1, '.'), (2, '*'), (3, '.')]);
+
+        // The cursor moves: its old cell shows the grid again.
+        hover.cursor.set(Some(Point::new(3, 0)));
+        r.handle_msg(Msg::Init);
+        assert_eq!(chars(&r.draw_frame().unwrap()), [(3, '*'), (2, '.')]);
+        // Nothing drawn over: only the restore.
+        hover.cursor.set(None);
+        r.handle_msg(Msg::Init);
+        assert_eq!(chars(&r.draw_frame().unwrap()), [(3, '.')]);
+        r.handle_msg(Msg::Init);
+        assert!(r.draw_frame().is_none());
+    }
+
+    #[test]
+    fn overlay_wins_over_grid_changes() {
+        let (mut r, hover) = hover_runner();
+        hover.cursor.set(Some(Point::new(1, 0)));
+        r.draw_frame().unwrap();
+        // The grid changes under the overlay: the overlay is still shown.
+        hover.wall.set(true);
+        r.handle_msg(Msg::Init);
+        assert_eq!(chars(&r.draw_frame().unwrap()), [(1, '*')]);
+        // Once gone, the changed grid cell shows.
+        hover.cursor.set(None);
+        r.handle_msg(Msg::Init);
+        assert_eq!(chars(&r.draw_frame().unwrap()), [(1, '@')]);
+    }
+
+    #[test]
+    fn overlay_not_recorded() {
+        let (mut r, hover) = hover_runner();
+        let buf = SharedBuf::default();
+        r.start_recording(buf.clone(), None).unwrap();
+        hover.cursor.set(Some(Point::new(0, 0)));
+        r.draw_frame().unwrap();
+        hover.cursor.set(Some(Point::new(3, 0)));
+        r.handle_msg(Msg::Init);
+        r.draw_frame().unwrap();
+        hover.wall.set(true);
+        r.handle_msg(Msg::Init);
+        r.draw_frame().unwrap();
+        r.stop_recording().unwrap();
+
+        let data = buf.0.borrow();
+        let mut dec = crate::recording::FrameDecoder::new(data.as_slice());
+        let first = dec.decode().unwrap().unwrap();
+        assert_eq!(chars(&first), [(0, '.'), (1, '.'), (2, '.'), (3, '.')]);
+        // Cursor moves alone change nothing in the grid.
+        let second = dec.decode().unwrap().unwrap();
+        assert_eq!(chars(&second), [(1, '@')]);
+        assert!(dec.decode().unwrap().is_none());
+    }
+
+    /// Model drawing a row of `'.'` and showing the terminal cursor at
+    /// its position, if any.
+    struct Caret(Rc<std::cell::Cell<Option<Point>>>);
+
+    impl Model for Caret {
+        fn update(&mut self, _msg: Msg) -> Option<Effect> {
+            None
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            grid.fill(Cell::default().with_char('.'));
+            grid.set_cursor(self.0.get());
+        }
+    }
+
+    #[test]
+    fn cursor_moves_alone_draw_frames() {
+        let caret = Rc::new(std::cell::Cell::new(Some(Point::new(1, 0))));
+        let mut r = AppRunner::new(Box::new(Caret(Rc::clone(&caret))), 4, 1);
+        r.init();
+        let frame = r.draw_frame().unwrap();
+        assert_eq!(frame.cursor, Some(Point::new(1, 0)));
+        r.handle_msg(Msg::Init);
+        assert!(r.draw_frame().is_none(), "unchanged");
+
+        caret.set(Some(Point::new(2, 0)));
+        r.handle_msg(Msg::Init);
+        let frame = r.draw_frame().unwrap();
+        assert!(frame.cells.is_empty());
+        assert_eq!(frame.cursor, Some(Point::new(2, 0)));
+        caret.set(None);
+        r.handle_msg(Msg::Init);
+        assert_eq!(r.draw_frame().unwrap().cursor, None);
+        // Outside the grid, the cursor is hidden.
+        caret.set(Some(Point::new(4, 0)));
+        r.handle_msg(Msg::Init);
+        assert!(r.draw_frame().is_none());
+    }
+
+    #[test]
+    fn turn_budget_expires() {
+        let t0 = Instant::now();
+        let budget = TurnBudget::starting_at(t0, Duration::from_millis(5));
+        assert!(!budget.exhausted_at(t0));
+        assert!(!budget.exhausted_at(t0 + Duration::from_millis(4)));
+        assert!(budget.exhausted_at(t0 + Duration::from_millis(5)));
+        assert!(TurnBudget::new(Duration::ZERO).exhausted());
+    }
+
+    /// Poll driver pressing a key at one poll and quitting at another,
+    /// counting flushed frames.
+    struct KeyAtDriver {
+        polls: usize,
+        key_at: usize,
+        quit_at: usize,
+        flushes: Rc<std::cell::Cell<usize>>,
+    }
+
+    impl Driver for KeyAtDriver {
+        fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn poll_msgs(
+            &mut self,
+            ctx: &Context,
+            tx: Sender<Msg>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            self.polls += 1;
+            if self.polls == self.key_at {
+                tx.send(Msg::key(crate::Key::Char('x'))).ok();
+            } else if self.polls == self.quit_at {
+                ctx.cancel();
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self, _frame: Frame) -> Result<(), Box<dyn std::error::Error>> {
+            self.flushes.set(self.flushes.get() + 1);
+            Ok(())
+        }
+
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn app_continuation_yields_to_input() {
+        let (model, drawn) = Stepper::new();
+        let flushes = Rc::new(std::cell::Cell::new(0));
+        let mut app = App::new(AppConfig {
+            model,
+            driver: KeyAtDriver {
+                polls: 0,
+                key_at: 10,
+                quit_at: 100,
+                flushes: Rc::clone(&flushes),
+            },
+            width: 4,
+            height: 1,
+            frame_writer: None,
+            effect_handlers: Vec::new(),
+            double_click: None,
+            seed: None,
+        });
+        app.run().unwrap();
+        // One step after each poll until the key, each flushed.
+        assert_eq!(drawn.borrow().last(), Some(&9));
+        assert_eq!(flushes.get(), 10);
     }
 }
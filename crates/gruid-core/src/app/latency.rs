@@ -0,0 +1,194 @@
+//! Input latency: how long key and mouse messages take to reach the model,
+//! a frame and the screen.
+//!
+//! Each input is measured from its timestamp ([`Msg::time`]), set by the
+//! driver when it got the event, to:
+//!
+//! - the start of the model's update for it;
+//! - the end of the next frame drawn, when drawing it showed a change;
+//! - that frame being presented, on drivers that report it with
+//!   [`AppRunner::frame_presented`](super::AppRunner::frame_presented).
+//!
+//! Inputs handled before the same frame wait for it together, so a frame
+//! counts once, for its oldest input: the one a player waited on longest.
+//! An input that changed nothing on screen has no frame latency.
+//!
+//! The [`RunnerStats`] keep percentiles of the last [`LATENCY_SAMPLES`]
+//! measurements of each kind.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::messages::Msg;
+
+/// Measurements the percentiles of [`RunnerStats`] are computed over, for
+/// each kind of latency.
+pub const LATENCY_SAMPLES: usize = 256;
+
+/// Percentiles of recent latency measurements, all zero before the first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+    /// Measurements the percentiles are computed from.
+    pub samples: usize,
+}
+
+/// Input latencies measured by the application loop, from the last
+/// [`LATENCY_SAMPLES`] inputs or frames. See the [module
+/// documentation](self).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunnerStats {
+    /// From an input's timestamp to the model's update for it.
+    pub update: Percentiles,
+    /// From the oldest input shown by a frame to the frame being drawn.
+    pub frame: Percentiles,
+    /// From the oldest input shown by a frame to it being presented.
+    /// Empty on drivers that do not report presenting.
+    pub present: Percentiles,
+}
+
+/// The last [`LATENCY_SAMPLES`] measurements of one kind.
+#[derive(Debug, Default)]
+struct Window(VecDeque<Duration>);
+
+impl Window {
+    fn push(&mut self, d: Duration) {
+        if self.0.len() == LATENCY_SAMPLES {
+            self.0.pop_front();
+        }
+        self.0.push_back(d);
+    }
+
+    /// Nearest-rank percentiles.
+    fn percentiles(&self) -> Percentiles {
+        let mut sorted: Vec<Duration> = self.0.iter().copied().collect();
+        sorted.sort_unstable();
+        let Some(&max) = sorted.last() else {
+            return Percentiles::default();
+        };
+        let rank = |p: usize| sorted[(p * sorted.len()).div_ceil(100) - 1];
+        Percentiles {
+            p50: rank(50),
+            p95: rank(95),
+            max,
+            samples: sorted.len(),
+        }
+    }
+}
+
+/// Latency bookkeeping shared by [`App`](super::App) and
+/// [`AppRunner`](super::AppRunner).
+#[derive(Debug, Default)]
+pub(crate) struct Latency {
+    update: Window,
+    frame: Window,
+    present: Window,
+    /// Oldest input handled since the last frame.
+    unframed: Option<Instant>,
+    /// Oldest input shown by frames not presented yet.
+    unpresented: Option<Instant>,
+}
+
+/// The timestamp of `msg` if it is an input.
+fn input_time(msg: &Msg) -> Option<Instant> {
+    match msg {
+        Msg::KeyDown { .. } | Msg::KeyUp { .. } | Msg::Mouse { .. } => msg.time(),
+        _ => None,
+    }
+}
+
+fn oldest(a: Option<Instant>, b: Instant) -> Option<Instant> {
+    Some(a.map_or(b, |a| a.min(b)))
+}
+
+impl Latency {
+    /// `msg` is about to be handled, at `now`.
+    pub fn update(&mut self, msg: &Msg, now: Instant) {
+        if let Some(t) = input_time(msg) {
+            self.update.push(now.saturating_duration_since(t));
+            self.unframed = oldest(self.unframed, t);
+        }
+    }
+
+    /// A frame was drawn at `now`: `shown` if it changed the screen.
+    pub fn drawn(&mut self, shown: bool, now: Instant) {
+        let Some(t) = self.unframed.take() else {
+            return;
+        };
+        if shown {
+            self.frame.push(now.saturating_duration_since(t));
+            self.unpresented = oldest(self.unpresented, t);
+        }
+    }
+
+    /// The frames drawn so far were presented at `now`.
+    pub fn presented(&mut self, now: Instant) {
+        if let Some(t) = self.unpresented.take() {
+            self.present.push(now.saturating_duration_since(t));
+        }
+    }
+
+    pub fn stats(&self) -> RunnerStats {
+        RunnerStats {
+            update: self.update.percentiles(),
+            frame: self.frame.percentiles(),
+            present: self.present.percentiles(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::Key;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let mut w = Window::default();
+        assert_eq!(w.percentiles(), Percentiles::default());
+        for n in (1..=20).rev() {
+            w.push(ms(n));
+        }
+        let p = w.percentiles();
+        assert_eq!(
+            (p.p50, p.p95, p.max, p.samples),
+            (ms(10), ms(19), ms(20), 20)
+        );
+
+        // The window keeps the latest measurements only.
+        for _ in 0..LATENCY_SAMPLES {
+            w.push(ms(1));
+        }
+        assert_eq!(w.percentiles().max, ms(1));
+    }
+
+    #[test]
+    fn frames_count_their_oldest_input() {
+        let t0 = Instant::now();
+        let key = |at| Msg::key(Key::Enter).with_time(t0 + ms(at));
+        let mut l = Latency::default();
+        l.update(&key(0), t0 + ms(2));
+        l.update(&key(5), t0 + ms(6));
+        l.update(&Msg::Init, t0 + ms(7));
+        l.drawn(true, t0 + ms(10));
+        l.presented(t0 + ms(12));
+        // Presenting again shows no new input.
+        l.presented(t0 + ms(30));
+        let s = l.stats();
+        assert_eq!((s.update.samples, s.update.max), (2, ms(2)));
+        assert_eq!((s.frame.samples, s.frame.max), (1, ms(10)));
+        assert_eq!((s.present.samples, s.present.max), (1, ms(12)));
+
+        // An input that changed nothing is not charged to a later frame.
+        l.update(&key(40), t0 + ms(40));
+        l.drawn(false, t0 + ms(41));
+        l.drawn(true, t0 + ms(90));
+        assert_eq!(l.stats().frame.samples, 1);
+    }
+}
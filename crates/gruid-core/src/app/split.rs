@@ -0,0 +1,950 @@
+//! Several models sharing the screen: [`SplitModel`].
+//!
+//! A [`SplitModel`] hosts child [`Model`]s, each drawing into its own pane
+//! of the grid, for local multiplayer or editor layouts where each part is
+//! a separate model. It is a [`Model`] itself, so [`App`](super::App) and
+//! [`AppRunner`](super::AppRunner) run it like any other, diffing the
+//! composed grid.
+//!
+//! Panes are [`Range`]s, typically cut with the range helpers:
+//!
+//! ```
+//! use gruid_core::app::split::{SplitConfig, SplitModel};
+//! use gruid_core::{Effect, Grid, Model, Msg, Range};
+//!
+//! struct Player(char);
+//!
+//! impl Model for Player {
+//!     fn update(&mut self, _msg: Msg) -> Option<Effect> {
+//!         None
+//!     }
+//!     fn draw(&self, grid: &mut Grid) {
+//!         grid.fill(gruid_core::Cell::default().with_char(self.0));
+//!     }
+//! }
+//!
+//! let screen = Range::new(0, 0, 80, 24);
+//! let split = SplitModel::new(SplitConfig::default())
+//!     .with_pane(screen.columns(0, 40), Box::new(Player('1')))
+//!     .with_pane(screen.columns(40, 80), Box::new(Player('2')));
+//! assert_eq!(split.focus(), Some(0));
+//! ```
+//!
+//! Messages are routed as follows:
+//!
+//! - Keys go to the focused pane. The [`SplitConfig::focus_keys`] move the
+//!   focus to the next pane and are not delivered.
+//! - Mouse messages go to the pane under the pointer, relative to it (see
+//!   [`Range::rel_msg`]). A main-button click also focuses that pane.
+//!   Panes get [`MouseAction::Enter`] and [`MouseAction::Leave`] as the
+//!   pointer crosses from one to another. A pane pressed keeps the pointer
+//!   until the release, the position clamped into it, so that it sees
+//!   the whole drag; a press outside every pane sends nothing until then.
+//! - [`Msg::Screen`] gives each pane its own size.
+//! - Messages from a pane's commands, ticks, subscriptions and progress
+//!   reports go back to that pane only.
+//! - Other messages, custom ones included, go to every pane. This covers
+//!   [`Effect::Emit`], delivered as if a driver sent it, and
+//!   [`Msg::Continue`].
+
+use std::sync::mpsc;
+use std::time::Instant;
+
+use crate::geom::{Point, Range};
+use crate::messages::{Key, ModMask, MouseAction, Msg};
+use crate::style::Style;
+use crate::{Cell, Grid};
+
+use super::{Effect, Model, SubPoll, Subscription};
+
+/// What happens to a pane whose model returns [`Effect::End`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChildEnd {
+    /// Keep drawing its last state, but send it no more messages.
+    #[default]
+    Deactivate,
+    /// Remove the pane: its area is left blank.
+    Remove,
+    /// End the application.
+    EndApp,
+}
+
+/// Configuration of a [`SplitModel`].
+#[derive(Debug, Clone)]
+pub struct SplitConfig {
+    /// Keys moving the focus to the next active pane.
+    pub focus_keys: Vec<Key>,
+    /// What happens to a pane whose model ends.
+    pub on_end: ChildEnd,
+    /// If set, each pane gets a one-cell border, drawn in this style for
+    /// the focused pane and the default style for the others. The models
+    /// draw inside it.
+    pub focus_style: Option<Style>,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        Self {
+            focus_keys: vec![Key::Tab],
+            on_end: ChildEnd::Deactivate,
+            focus_style: None,
+        }
+    }
+}
+
+struct Pane {
+    model: Box<dyn Model>,
+    range: Range,
+    active: bool,
+    /// Identifies the pane in the messages of its effects, unlike its
+    /// index, which changes when an earlier pane is removed.
+    id: usize,
+}
+
+/// A message from the effects of the pane with id `pane`, going back to it.
+#[derive(Debug)]
+struct ToPane {
+    pane: usize,
+    msg: Msg,
+}
+
+/// `msg` wrapped to go back to the pane with id `pane`.
+pub(super) fn to_pane(pane: usize, msg: Msg) -> Msg {
+    Msg::custom(ToPane { pane, msg })
+}
+
+/// A subscription of a pane, its messages going back to it.
+struct PaneSub {
+    pane: usize,
+    sub: Box<dyn Subscription>,
+}
+
+impl Subscription for PaneSub {
+    fn poll(&mut self, now: Instant) -> SubPoll {
+        match self.sub.poll(now) {
+            SubPoll::Msg(msg) => SubPoll::Msg(to_pane(self.pane, msg)),
+            poll => poll,
+        }
+    }
+}
+
+/// `effect` of the pane with id `pane`, with the messages it produces
+/// going back to that pane.
+fn route_effect(pane: usize, effect: Effect) -> Effect {
+    match effect {
+        Effect::Cmd(f) => Effect::Cmd(Box::new(move || f().map(|msg| to_pane(pane, msg)))),
+        Effect::CmdProgress(f) => Effect::CmdProgress(Box::new(move |progress| {
+            let mut progress = progress.clone();
+            progress.route.push(pane);
+            f(&progress).map(|msg| to_pane(pane, msg))
+        })),
+        Effect::Sub(f) => Effect::Sub(Box::new(move |ctx, tx| {
+            // The subscription already has a thread: give it one more,
+            // and forward from this one.
+            let (sub_tx, sub_rx) = mpsc::channel();
+            std::thread::spawn(move || f(ctx, sub_tx));
+            for msg in sub_rx {
+                if tx.send(to_pane(pane, msg)).is_err() {
+                    break;
+                }
+            }
+        })),
+        Effect::Subscribe(sub) => Effect::Subscribe(Box::new(PaneSub { pane, sub })),
+        Effect::Tick { after, msg } => Effect::Tick {
+            after,
+            msg: to_pane(pane, msg),
+        },
+        Effect::Batch(effects) => {
+            Effect::Batch(effects.into_iter().map(|e| route_effect(pane, e)).collect())
+        }
+        e => e,
+    }
+}
+
+/// A model hosting child models, each in its own pane. See the
+/// [module documentation](self).
+///
+/// The application ends once no pane is active.
+pub struct SplitModel {
+    panes: Vec<Pane>,
+    focus: usize,
+    cfg: SplitConfig,
+    /// The pane under the pointer, as its model last heard.
+    hover: Option<usize>,
+    /// While a button is held, the pane that got the press, if any.
+    grab: Option<Option<usize>>,
+    /// Id of the next pane added.
+    next_id: usize,
+}
+
+impl SplitModel {
+    /// Create a split model with no panes.
+    pub fn new(cfg: SplitConfig) -> Self {
+        Self {
+            panes: Vec::new(),
+            focus: 0,
+            cfg,
+            hover: None,
+            grab: None,
+            next_id: 0,
+        }
+    }
+
+    /// Add a pane showing `model` in `range`. The first pane added has the
+    /// focus. Panes should not overlap.
+    pub fn with_pane(mut self, range: Range, model: Box<dyn Model>) -> Self {
+        self.panes.push(Pane {
+            model,
+            range,
+            active: true,
+            id: self.next_id,
+        });
+        self.next_id += 1;
+        self
+    }
+
+    /// Number of panes.
+    pub fn len(&self) -> usize {
+        self.panes.len()
+    }
+
+    /// Whether there are no panes.
+    pub fn is_empty(&self) -> bool {
+        self.panes.is_empty()
+    }
+
+    /// Whether pane `i` still receives messages.
+    pub fn is_active(&self, i: usize) -> bool {
+        self.panes.get(i).is_some_and(|p| p.active)
+    }
+
+    /// The focused pane, if any is active.
+    pub fn focus(&self) -> Option<usize> {
+        self.is_active(self.focus).then_some(self.focus)
+    }
+
+    /// Focus pane `i`, if it is active.
+    pub fn set_focus(&mut self, i: usize) {
+        if self.is_active(i) {
+            self.focus = i;
+        }
+    }
+
+    /// Where pane `i`'s model draws: its range, inside the border if any.
+    pub fn content_range(&self, i: usize) -> Range {
+        let rg = self.panes[i].range;
+        if self.cfg.focus_style.is_some() {
+            rg.shift(1, 1, -1, -1)
+        } else {
+            rg
+        }
+    }
+
+    /// Move the focus to the next active pane after the current one.
+    fn focus_next(&mut self) {
+        let n = self.panes.len();
+        if let Some(i) = (1..=n)
+            .map(|d| (self.focus + d) % n)
+            .find(|&i| self.panes[i].active)
+        {
+            self.focus = i;
+        }
+    }
+
+    /// Deliver `msg` to pane `i`, handling the pane ending.
+    fn send(&mut self, i: usize, msg: Msg) -> Option<Effect> {
+        if !self.panes[i].active {
+            return None;
+        }
+        let effect = self.panes[i].model.update(msg)?;
+        let (effect, ended) = self.filter_end(effect);
+        let id = self.panes[i].id;
+        if ended {
+            self.end_pane(i);
+        }
+        effect.map(|e| route_effect(id, e))
+    }
+
+    /// Deliver a message from a pane's effects back to it, if it is still
+    /// there.
+    fn send_back(&mut self, to: &ToPane) -> Option<Effect> {
+        let i = self.panes.iter().position(|p| p.id == to.pane)?;
+        self.send(i, to.msg.clone())
+    }
+
+    /// Take [`Effect::End`] out of a pane's effect, unless it ends the
+    /// application, and tell whether it was there.
+    fn filter_end(&self, effect: Effect) -> (Option<Effect>, bool) {
+        match effect {
+            Effect::End if self.cfg.on_end != ChildEnd::EndApp => (None, true),
+            Effect::Batch(effects) => {
+                let mut ended = false;
+                let mut kept = Vec::new();
+                for e in effects {
+                    let (e, end) = self.filter_end(e);
+                    ended |= end;
+                    kept.extend(e);
+                }
+                (batch(kept), ended)
+            }
+            e => (Some(e), false),
+        }
+    }
+
+    fn end_pane(&mut self, i: usize) {
+        let pane = &mut self.panes[i];
+        pane.active = false;
+        pane.model.on_exit();
+        if self.cfg.on_end == ChildEnd::Remove {
+            self.panes.remove(i);
+            // Later panes shift back.
+            let shift = |pane: &mut Option<usize>| {
+                *pane = match *pane {
+                    Some(j) if j == i => None,
+                    Some(j) if j > i => Some(j - 1),
+                    p => p,
+                }
+            };
+            shift(&mut self.hover);
+            if let Some(grab) = &mut self.grab {
+                shift(grab);
+            }
+            if self.focus > i {
+                self.focus -= 1;
+            }
+            if self.panes.is_empty() {
+                return;
+            }
+            self.focus %= self.panes.len();
+        }
+        if !self.is_active(self.focus) {
+            self.focus_next();
+        }
+    }
+
+    /// The pane whose content is under `pos`.
+    fn pane_at(&self, pos: Point) -> Option<usize> {
+        (0..self.panes.len()).find(|&i| self.content_range(i).contains(pos))
+    }
+
+    /// Deliver a mouse message to pane `i`, at `pos` clamped into the pane
+    /// and relative to it.
+    fn send_mouse(&mut self, i: usize, msg: Msg, effects: &mut Vec<Effect>) {
+        let rg = self.content_range(i);
+        let msg = match msg {
+            Msg::Mouse {
+                action,
+                pos,
+                modifiers,
+                time,
+            } => Msg::Mouse {
+                action,
+                pos: Point::new(
+                    pos.x.min(rg.max.x - 1).max(rg.min.x),
+                    pos.y.min(rg.max.y - 1).max(rg.min.y),
+                ),
+                modifiers,
+                time,
+            },
+            msg => msg,
+        };
+        effects.extend(self.send(i, rg.rel_msg(msg)));
+    }
+
+    /// Tell the pane the pointer left and the one it entered, at `pos`, if
+    /// the pointer moved from one to the other. `then_move` follows the
+    /// entry with a move, for messages that are not one.
+    fn hover_to(
+        &mut self,
+        under: Option<usize>,
+        mouse: impl Fn(MouseAction) -> Msg,
+        then_move: bool,
+        effects: &mut Vec<Effect>,
+    ) {
+        if under == self.hover {
+            return;
+        }
+        if let Some(h) = self.hover.take() {
+            self.send_mouse(h, mouse(MouseAction::Leave), effects);
+        }
+        if let Some(u) = under.filter(|&u| u < self.panes.len()) {
+            self.hover = Some(u);
+            self.send_mouse(u, mouse(MouseAction::Enter), effects);
+            if then_move {
+                self.send_mouse(u, mouse(MouseAction::Move), effects);
+            }
+        }
+    }
+
+    /// Route a mouse message at `pos`, as the module documentation says.
+    fn route_mouse(
+        &mut self,
+        action: MouseAction,
+        pos: Point,
+        modifiers: ModMask,
+        time: Instant,
+    ) -> Option<Effect> {
+        let mouse = |action| Msg::Mouse {
+            action,
+            pos,
+            modifiers,
+            time,
+        };
+        let mut effects = Vec::new();
+        if let Some(grab) = self.grab {
+            match action {
+                MouseAction::Leave => self.hover = None,
+                MouseAction::Enter => self.hover = grab,
+                _ => {}
+            }
+            if let Some(i) = grab {
+                self.send_mouse(i, mouse(action), &mut effects);
+            }
+            if action == MouseAction::Release {
+                self.grab = None;
+                self.hover_to(self.pane_at(pos), mouse, true, &mut effects);
+            }
+            return batch(effects);
+        }
+        match action {
+            MouseAction::Leave => {
+                if let Some(h) = self.hover.take() {
+                    self.send_mouse(h, mouse(action), &mut effects);
+                }
+            }
+            // The driver's move into the cell follows.
+            MouseAction::Enter => self.hover_to(self.pane_at(pos), mouse, false, &mut effects),
+            _ => {
+                self.hover_to(self.pane_at(pos), mouse, !action.is_move(), &mut effects);
+                let under = self.pane_at(pos);
+                if action.drag().is_some() {
+                    self.grab = Some(under);
+                }
+                if let Some(i) = under {
+                    if action == MouseAction::Main {
+                        self.set_focus(i);
+                    }
+                    self.send_mouse(i, mouse(action), &mut effects);
+                }
+            }
+        }
+        batch(effects)
+    }
+
+    /// Deliver `msg` to every pane, `f` making it for each.
+    fn broadcast(&mut self, f: impl Fn(&Self, usize) -> Msg) -> Option<Effect> {
+        let mut effects = Vec::new();
+        let mut i = 0;
+        while i < self.panes.len() {
+            let len = self.panes.len();
+            let msg = f(self, i);
+            effects.extend(self.send(i, msg));
+            // A removed pane shifts the next ones back.
+            if self.panes.len() == len {
+                i += 1;
+            }
+        }
+        batch(effects)
+    }
+
+    /// Draw a one-cell border along `rg`. This is what gruid-ui's
+    /// `BoxDecor` does, but gruid-ui depends on this crate.
+    fn draw_border(&self, grid: &Grid, rg: Range, style: Style) {
+        if rg.width() < 2 || rg.height() < 2 {
+            return;
+        }
+        let (x0, y0, x1, y1) = (rg.min.x, rg.min.y, rg.max.x - 1, rg.max.y - 1);
+        let cell = |ch| Cell::default().with_char(ch).with_style(style);
+        for x in x0 + 1..x1 {
+            grid.set(Point::new(x, y0), cell('─'));
+            grid.set(Point::new(x, y1), cell('─'));
+        }
+        for y in y0 + 1..y1 {
+            grid.set(Point::new(x0, y), cell('│'));
+            grid.set(Point::new(x1, y), cell('│'));
+        }
+        grid.set(Point::new(x0, y0), cell('┌'));
+        grid.set(Point::new(x1, y0), cell('┐'));
+        grid.set(Point::new(x0, y1), cell('└'));
+        grid.set(Point::new(x1, y1), cell('┘'));
+    }
+}
+
+/// `effects` as one effect, if there are any.
+fn batch(mut effects: Vec<Effect>) -> Option<Effect> {
+    match effects.len() {
+        0 => None,
+        1 => effects.pop(),
+        _ => Some(Effect::Batch(effects)),
+    }
+}
+
+impl Model for SplitModel {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        let effect = match msg {
+            Msg::KeyDown { ref key, .. } if self.cfg.focus_keys.contains(key) => {
+                self.focus_next();
+                None
+            }
+            Msg::KeyUp { ref key, .. } if self.cfg.focus_keys.contains(key) => None,
+            Msg::KeyDown { .. } | Msg::KeyUp { .. } => match self.focus() {
+                Some(i) => self.send(i, msg),
+                None => None,
+            },
+            Msg::Mouse {
+                action,
+                pos,
+                modifiers,
+                time,
+            } => self.route_mouse(action, pos, modifiers, time),
+            Msg::Custom(ref c) if c.is::<ToPane>() => {
+                self.send_back(c.downcast_ref().expect("checked type"))
+            }
+            Msg::Screen { time, .. } => self.broadcast(|s, i| {
+                let size = s.content_range(i).size();
+                Msg::Screen {
+                    width: size.x,
+                    height: size.y,
+                    time,
+                }
+            }),
+            msg => self.broadcast(|_, _| msg.clone()),
+        };
+        if self.panes.iter().all(|p| !p.active) {
+            return Some(match effect {
+                Some(e) => Effect::Batch(vec![e, Effect::End]),
+                None => Effect::End,
+            });
+        }
+        effect
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        grid.fill(Cell::default());
+        for (i, pane) in self.panes.iter().enumerate() {
+            let mut content = grid.slice(self.content_range(i));
+            pane.model.draw(&mut content);
+            if let Some(focus_style) = self.cfg.focus_style {
+                let style = if self.focus() == Some(i) {
+                    focus_style
+                } else {
+                    Style::default()
+                };
+                self.draw_border(grid, pane.range, style);
+            }
+        }
+    }
+
+    fn on_exit(&mut self) {
+        for pane in self.panes.iter_mut().filter(|p| p.active) {
+            pane.active = false;
+            pane.model.on_exit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppRunner;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Instant;
+
+    /// Logs the messages it gets, shows the last key typed, and ends on
+    /// 'q'.
+    struct Child {
+        log: Rc<RefCell<Vec<Msg>>>,
+        ch: char,
+        exits: Rc<RefCell<u32>>,
+    }
+
+    impl Model for Child {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            self.log.borrow_mut().push(msg.clone());
+            match msg {
+                Msg::KeyDown {
+                    key: Key::Char('q'),
+                    ..
+                } => Some(Effect::End),
+                Msg::KeyDown {
+                    key: Key::Char('r'),
+                    ..
+                } => Some(Effect::Batch(vec![
+                    crate::app::cmd(|| Some(Msg::custom("cmd"))),
+                    Effect::Tick {
+                        after: std::time::Duration::ZERO,
+                        msg: Msg::custom("tick"),
+                    },
+                ])),
+                Msg::KeyDown {
+                    key: Key::Char('p'),
+                    ..
+                } => Some(crate::app::cmd_progress(|progress| {
+                    progress.report(0.5, "half");
+                    Some(Msg::custom("done"))
+                })),
+                Msg::KeyDown {
+                    key: Key::Char('x'),
+                    ..
+                } => Some(Effect::Batch(vec![Effect::End])),
+                Msg::KeyDown {
+                    key: Key::Char(c), ..
+                } => {
+                    self.ch = c;
+                    None
+                }
+                _ => None,
+            }
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            grid.set(Point::ZERO, Cell::default().with_char(self.ch));
+        }
+
+        fn on_exit(&mut self) {
+            *self.exits.borrow_mut() += 1;
+        }
+    }
+
+    type Log = Rc<RefCell<Vec<Msg>>>;
+
+    /// Two 5x3 panes side by side.
+    fn split(cfg: SplitConfig) -> (SplitModel, [Log; 2], Rc<RefCell<u32>>) {
+        let exits = Rc::new(RefCell::new(0));
+        let logs: [Log; 2] = Default::default();
+        let screen = Range::new(0, 0, 10, 3);
+        let child = |i: usize| {
+            Box::new(Child {
+                log: Rc::clone(&logs[i]),
+                ch: ' ',
+                exits: Rc::clone(&exits),
+            })
+        };
+        let model = SplitModel::new(cfg)
+            .with_pane(screen.columns(0, 5), child(0))
+            .with_pane(screen.columns(5, 10), child(1));
+        (model, logs, exits)
+    }
+
+    fn mouse(action: MouseAction, x: i32, y: i32) -> Msg {
+        Msg::Mouse {
+            action,
+            pos: Point::new(x, y),
+            modifiers: Default::default(),
+            time: Instant::now(),
+        }
+    }
+
+    fn click(x: i32, y: i32) -> Msg {
+        mouse(MouseAction::Main, x, y)
+    }
+
+    /// The mouse messages of `log`, as actions and positions.
+    fn mice(log: &Log) -> Vec<(MouseAction, Point)> {
+        log.borrow()
+            .iter()
+            .filter_map(|m| match m {
+                Msg::Mouse { action, pos, .. } => Some((*action, *pos)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn keys(log: &Log) -> String {
+        log.borrow()
+            .iter()
+            .filter_map(|m| match m {
+                Msg::KeyDown {
+                    key: Key::Char(c), ..
+                } => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keys_go_to_focused_pane() {
+        let (mut model, logs, _) = split(SplitConfig::default());
+        model.update(Msg::key(Key::Char('a')));
+        model.update(Msg::key(Key::Tab));
+        model.update(Msg::key(Key::Char('b')));
+        model.update(Msg::key(Key::Tab));
+        model.update(Msg::key(Key::Char('c')));
+        assert_eq!(keys(&logs[0]), "ac");
+        assert_eq!(keys(&logs[1]), "b");
+        // The focus key is the runner's.
+        assert!(
+            logs.iter()
+                .flat_map(|l| l.borrow().clone())
+                .all(|m| !matches!(m, Msg::KeyDown { key: Key::Tab, .. }))
+        );
+        // Other messages go to both.
+        model.update(Msg::Init);
+        assert!(
+            logs.iter()
+                .all(|l| matches!(l.borrow().last(), Some(Msg::Init)))
+        );
+    }
+
+    #[test]
+    fn key_releases_follow_focus() {
+        let (mut model, logs, _) = split(SplitConfig::default());
+        model.update(Msg::key_up(Key::Char('a')));
+        model.update(Msg::key(Key::Tab));
+        model.update(Msg::key_up(Key::Tab));
+        model.update(Msg::key_up(Key::Char('b')));
+        let ups = |log: &Log| {
+            log.borrow()
+                .iter()
+                .filter_map(|m| match m {
+                    Msg::KeyUp { key, .. } => Some(key.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ups(&logs[0]), [Key::Char('a')]);
+        // The focus key's release is the runner's too.
+        assert_eq!(ups(&logs[1]), [Key::Char('b')]);
+    }
+
+    #[test]
+    fn mouse_goes_to_pane_under_pointer() {
+        let cfg = SplitConfig {
+            focus_style: Some(Style::default()),
+            ..SplitConfig::default()
+        };
+        let (mut model, logs, _) = split(cfg);
+        // (7, 1) is in the second pane, at (1, 0) inside its border.
+        model.update(click(7, 1));
+        assert_eq!(model.focus(), Some(1));
+        match logs[1].borrow().last() {
+            Some(Msg::Mouse { pos, .. }) => assert_eq!(*pos, Point::new(1, 0)),
+            m => panic!("{m:?}"),
+        }
+        model.update(mouse(MouseAction::Release, 7, 1));
+        // Borders belong to no pane.
+        model.update(click(5, 1));
+        model.update(mouse(MouseAction::Release, 5, 1));
+        assert!(logs[0].borrow().is_empty());
+        // Keys follow the click.
+        model.update(Msg::key(Key::Char('x')));
+        assert_eq!(keys(&logs[1]), "x");
+        // Each pane gets its own size.
+        model.update(Msg::Screen {
+            width: 10,
+            height: 3,
+            time: Instant::now(),
+        });
+        match logs[0].borrow().last() {
+            Some(Msg::Screen { width, height, .. }) => assert_eq!((*width, *height), (3, 1)),
+            m => panic!("{m:?}"),
+        }
+    }
+
+    #[test]
+    fn drag_stays_with_pressed_pane() {
+        use MouseAction::*;
+        let (mut model, logs, _) = split(SplitConfig::default());
+        model.update(mouse(Move, 1, 1));
+        model.update(mouse(Main, 1, 1));
+        model.update(mouse(MainDrag, 7, 1));
+        model.update(mouse(MainDrag, 8, 2));
+        model.update(mouse(Release, 8, 2));
+        let p = Point::new;
+        // Clamped to the pane's last column until the release, then out.
+        assert_eq!(
+            mice(&logs[0]),
+            [
+                (Enter, p(1, 1)),
+                (Move, p(1, 1)),
+                (Main, p(1, 1)),
+                (MainDrag, p(4, 1)),
+                (MainDrag, p(4, 2)),
+                (Release, p(4, 2)),
+                (Leave, p(4, 2)),
+            ]
+        );
+        assert_eq!(mice(&logs[1]), [(Enter, p(3, 2)), (Move, p(3, 2))]);
+        assert_eq!(model.focus(), Some(0));
+
+        // A press outside every pane is nobody's drag.
+        let (mut model, logs, _) = split(SplitConfig {
+            focus_style: Some(Style::default()),
+            ..SplitConfig::default()
+        });
+        model.update(click(5, 1));
+        model.update(mouse(MainDrag, 7, 1));
+        model.update(mouse(Release, 7, 1));
+        assert!(mice(&logs[0]).is_empty());
+        assert_eq!(mice(&logs[1]), [(Enter, p(1, 0)), (Move, p(1, 0))]);
+    }
+
+    #[test]
+    fn crossing_panes_leaves_and_enters() {
+        use MouseAction::*;
+        let (mut model, logs, _) = split(SplitConfig::default());
+        model.update(mouse(Enter, 3, 0));
+        model.update(mouse(Move, 3, 0));
+        model.update(mouse(Move, 4, 0));
+        model.update(mouse(Move, 5, 0));
+        model.update(mouse(Leave, 5, 0));
+        let p = Point::new;
+        assert_eq!(
+            mice(&logs[0]),
+            [
+                (Enter, p(3, 0)),
+                (Move, p(3, 0)),
+                (Move, p(4, 0)),
+                (Leave, p(4, 0))
+            ]
+        );
+        assert_eq!(
+            mice(&logs[1]),
+            [(Enter, p(0, 0)), (Move, p(0, 0)), (Leave, p(0, 0))]
+        );
+        // Wheel events go where the pointer is, entering the pane first.
+        model.update(mouse(WheelDown, 1, 2));
+        assert_eq!(
+            mice(&logs[0])[4..],
+            [(Enter, p(1, 2)), (Move, p(1, 2)), (WheelDown, p(1, 2))]
+        );
+    }
+
+    #[test]
+    fn pane_ending_keeps_app() {
+        let (mut model, logs, exits) = split(SplitConfig::default());
+        assert!(model.update(Msg::key(Key::Char('q'))).is_none());
+        assert!(!model.is_active(0));
+        assert_eq!(*exits.borrow(), 1);
+        // The focus moved on, and the ended pane gets nothing more.
+        assert_eq!(model.focus(), Some(1));
+        model.update(Msg::Init);
+        assert!(matches!(logs[0].borrow().last(), Some(Msg::KeyDown { .. })));
+        // The last pane ending ends the app.
+        assert!(matches!(
+            model.update(Msg::key(Key::Char('q'))),
+            Some(Effect::End)
+        ));
+        model.on_exit();
+        assert_eq!(*exits.borrow(), 2);
+
+        let cfg = SplitConfig {
+            on_end: ChildEnd::Remove,
+            ..SplitConfig::default()
+        };
+        let (mut model, logs, _) = split(cfg);
+        model.update(Msg::key(Key::Tab));
+        model.update(Msg::key(Key::Char('q')));
+        assert_eq!(model.len(), 1);
+        model.update(Msg::key(Key::Char('k')));
+        assert_eq!(keys(&logs[0]), "k");
+
+        let cfg = SplitConfig {
+            on_end: ChildEnd::EndApp,
+            ..SplitConfig::default()
+        };
+        let (mut model, _, _) = split(cfg);
+        assert!(matches!(
+            model.update(Msg::key(Key::Char('q'))),
+            Some(Effect::End)
+        ));
+    }
+
+    /// The custom string messages of `log`.
+    fn customs(log: &Log) -> Vec<&'static str> {
+        log.borrow()
+            .iter()
+            .filter_map(|m| match m {
+                Msg::Custom(c) => c.downcast_ref::<&str>().copied(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replies_go_back_to_their_pane() {
+        let cfg = SplitConfig {
+            on_end: ChildEnd::Remove,
+            ..SplitConfig::default()
+        };
+        let (mut model, logs, _) = split(cfg);
+        model.update(Msg::key(Key::Tab));
+        let Some(Effect::Batch(effects)) = model.update(Msg::key(Key::Char('r'))) else {
+            panic!("expected a batch");
+        };
+        // Pane 0 going away must not send the replies to the pane taking
+        // its index.
+        model.update(Msg::key(Key::Tab));
+        model.update(Msg::key(Key::Char('q')));
+        assert_eq!(model.len(), 1);
+        for effect in effects {
+            let msg = match effect {
+                Effect::Cmd(f) => f().unwrap(),
+                Effect::Tick { msg, .. } => msg,
+                e => panic!("unexpected {e:?}"),
+            };
+            model.update(msg);
+        }
+        assert_eq!(customs(&logs[1]), ["cmd", "tick"]);
+        assert!(customs(&logs[0]).is_empty());
+        // Other custom messages go to every pane.
+        model.update(Msg::custom("all"));
+        assert_eq!(customs(&logs[1]), ["cmd", "tick", "all"]);
+    }
+
+    #[test]
+    fn progress_goes_back_to_its_pane() {
+        let (mut model, logs, _) = split(SplitConfig::default());
+        model.update(Msg::key(Key::Tab));
+        let Some(Effect::CmdProgress(f)) = model.update(Msg::key(Key::Char('p'))) else {
+            panic!("expected a progress command");
+        };
+        let (tx, rx) = mpsc::channel();
+        let progress = crate::app::Progress {
+            ctx: crate::app::Context::new(),
+            tx,
+            route: Vec::new(),
+        };
+        let done = f(&progress).unwrap();
+        for msg in rx.try_iter().chain([done]) {
+            model.update(msg);
+        }
+        assert!(matches!(
+            logs[1].borrow()[..],
+            [.., Msg::Progress { .. }, Msg::Custom(_)]
+        ));
+        assert!(
+            !logs[0]
+                .borrow()
+                .iter()
+                .any(|m| matches!(m, Msg::Progress { .. }))
+        );
+        assert_eq!(customs(&logs[1]), ["done"]);
+    }
+
+    #[test]
+    fn ended_pane_returns_no_effect() {
+        let (mut model, _, _) = split(SplitConfig::default());
+        assert!(model.update(Msg::key(Key::Char('x'))).is_none());
+        assert!(!model.is_active(0));
+    }
+
+    #[test]
+    fn frame_spans_panes() {
+        let (model, _, _) = split(SplitConfig::default());
+        let mut runner = AppRunner::new(Box::new(model), 10, 3);
+        runner.init();
+        runner.handle_msg(Msg::key(Key::Char('a')));
+        runner.handle_msg(Msg::key(Key::Tab));
+        runner.handle_msg(Msg::key(Key::Char('b')));
+        let frame = runner.draw_frame().unwrap();
+        let cells: Vec<(Point, char)> = frame.cells.iter().map(|c| (c.pos, c.cell.ch)).collect();
+        assert_eq!(cells, [(Point::new(0, 0), 'a'), (Point::new(5, 0), 'b')]);
+        // Only the changed pane is in the next diff.
+        runner.handle_msg(Msg::key(Key::Char('c')));
+        let frame = runner.draw_frame().unwrap();
+        assert_eq!(frame.cells.len(), 1);
+        assert_eq!(frame.cells[0].pos, Point::new(5, 0));
+    }
+}
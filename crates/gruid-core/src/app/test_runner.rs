@@ -0,0 +1,439 @@
+//! Running a [`Model`] headless in tests: [`TestRunner`].
+//!
+//! A [`TestRunner`] wraps an [`AppRunner`] the way a driver would, without
+//! a screen: it delivers messages, draws the frames and applies them to a
+//! grid of its own, so that tests assert on what a player would see and
+//! on the [`Frame`] diffs drivers get.
+//!
+//! Commands run synchronously: [`Effect::Cmd`](super::Effect::Cmd) and
+//! [`Effect::CmdProgress`](super::Effect::CmdProgress) go through a
+//! [`Spawner`](super::Spawner) that runs them at once, and each message is
+//! followed by the messages they queue, frame by frame, until the model
+//! settles. Chains of commands or [`Effect::Continue`](super::Effect::Continue)
+//! slices, such as a character walking along a path, thus play out fully
+//! and deterministically within one [`send`](TestRunner::send).
+//!
+//! ```
+//! use gruid_core::app::TestRunner;
+//! use gruid_core::{Cell, Effect, Grid, Key, Model, Msg, Point};
+//!
+//! /// An `@` walking right.
+//! struct Walker(i32);
+//!
+//! impl Model for Walker {
+//!     fn update(&mut self, msg: Msg) -> Option<Effect> {
+//!         if let Msg::KeyDown { key: Key::ArrowRight, .. } = msg {
+//!             self.0 += 1;
+//!         }
+//!         None
+//!     }
+//!
+//!     fn draw(&self, grid: &mut Grid) {
+//!         grid.fill(Cell::default().with_char('.'));
+//!         grid.set(Point::new(self.0, 0), Cell::default().with_char('@'));
+//!     }
+//! }
+//!
+//! let mut t = TestRunner::new(Box::new(Walker(0)), 4, 2);
+//! t.script([(Msg::key(Key::ArrowRight), vec![(Point::new(1, 0), '@')])]);
+//! assert_eq!(t.grid_snapshot(), ".@..\n....");
+//! // The last frame only holds the cells that changed.
+//! assert_eq!(t.frames().last().unwrap().cells.len(), 2);
+//! ```
+//!
+//! Frames count as presented once applied, for the
+//! [latency statistics](AppRunner::stats); tests measure them with a clock
+//! of their own, set with [`AppRunner::set_clock`] through
+//! [`runner`](TestRunner::runner).
+//!
+//! [`Effect::Sub`](super::Effect::Sub) threads still run concurrently, and
+//! [`Subscription`](super::Subscription)s are polled with the wall clock,
+//! so tests relying on them are not deterministic.
+//! [`Effect::Tick`](super::Effect::Tick) timers go by the runner's clock:
+//! set a manual one to fire them at will.
+
+use crate::geom::Point;
+use crate::grid::{Frame, Grid};
+use crate::messages::{Key, Msg};
+
+use super::{AppRunner, Model};
+
+/// Frames drawn for one message before giving up on the model settling.
+const MAX_ROUNDS: usize = 10_000;
+
+/// Runs a [`Model`] without a driver, for tests. See the
+/// [module documentation](self).
+///
+/// The model gets [`Msg::Init`] and its size with the first message
+/// sent, or on [`start`](Self::start). The master seed is 0 unless set
+/// with [`with_seed`](Self::with_seed), whatever `GRUID_SEED` says.
+pub struct TestRunner {
+    runner: AppRunner,
+    /// The screen as a driver would show it, built from the frames.
+    screen: Grid,
+    frames: Vec<Frame>,
+    started: bool,
+}
+
+impl TestRunner {
+    /// A runner for `model`, on a `width` × `height` screen.
+    pub fn new(model: Box<dyn Model>, width: i32, height: i32) -> Self {
+        let mut runner = AppRunner::new(model, width, height);
+        runner.set_seed(0);
+        runner.set_spawner(|job| job());
+        Self {
+            runner,
+            screen: Grid::new(width, height),
+            frames: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Use `seed` as master seed (builder).
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.runner.set_seed(seed);
+        self
+    }
+
+    /// Initialize the model and draw its first frame, if not done yet.
+    pub fn start(&mut self) {
+        if !self.started {
+            self.started = true;
+            self.runner.init();
+            self.settle();
+        }
+    }
+
+    /// Deliver `msg`, then the messages it leads to, drawing a frame after
+    /// each, until the model settles or quits. Returns the frames drawn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the model keeps going after 10 000 frames, as with a
+    /// command that always schedules another one.
+    pub fn send(&mut self, msg: Msg) -> &[Frame] {
+        self.start();
+        let first = self.frames.len();
+        if !self.runner.should_quit() {
+            self.runner.handle_msg(msg);
+            self.settle();
+        }
+        &self.frames[first..]
+    }
+
+    /// [`send`](Self::send) a press of `key`.
+    pub fn key(&mut self, key: Key) -> &[Frame] {
+        self.send(Msg::key(key))
+    }
+
+    /// Resize the screen, as a driver would on a window resize.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.start();
+        self.runner.screen(width, height);
+        self.screen = Grid::new(width, height);
+        self.settle();
+    }
+
+    /// Send each message of `steps` in turn, and check after each that the
+    /// screen shows the expected characters.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the first cell that differs, naming the step, along with
+    /// the [snapshot](Self::grid_snapshot) of the screen.
+    pub fn script<E: AsRef<[(Point, char)]>>(&mut self, steps: impl IntoIterator<Item = (Msg, E)>) {
+        for (i, (msg, expected)) in steps.into_iter().enumerate() {
+            self.send(msg);
+            if let Err(e) = self.check_cells(expected.as_ref()) {
+                panic!("step {i}: {e}");
+            }
+        }
+    }
+
+    /// Check that the screen shows the expected characters.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the first cell that differs, with the
+    /// [snapshot](Self::grid_snapshot) of the screen.
+    pub fn assert_cells(&self, expected: &[(Point, char)]) {
+        if let Err(e) = self.check_cells(expected) {
+            panic!("{e}");
+        }
+    }
+
+    fn check_cells(&self, expected: &[(Point, char)]) -> Result<(), String> {
+        for &(p, ch) in expected {
+            let got = self.screen.at(p).ch;
+            if got != ch {
+                return Err(format!(
+                    "expected {ch:?} at {p}, got {got:?}\n{}",
+                    self.grid_snapshot()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The screen as text: one line per row, without trailing spaces.
+    pub fn grid_snapshot(&self) -> String {
+        let mut s = String::new();
+        for y in 0..self.screen.height() {
+            if y > 0 {
+                s.push('\n');
+            }
+            let row: String = (0..self.screen.width())
+                .map(|x| self.screen.at(Point::new(x, y)).ch)
+                .collect();
+            s.push_str(row.trim_end_matches(' '));
+        }
+        s
+    }
+
+    /// The screen as a driver would show it, transient overlay included.
+    pub fn grid(&self) -> &Grid {
+        &self.screen
+    }
+
+    /// Every frame drawn so far, in order.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Take the frames drawn so far, leaving none.
+    pub fn take_frames(&mut self) -> Vec<Frame> {
+        std::mem::take(&mut self.frames)
+    }
+
+    /// Whether the model has ended the application.
+    pub fn should_quit(&self) -> bool {
+        self.runner.should_quit()
+    }
+
+    /// The wrapped runner, to set capabilities, a clipboard or effect
+    /// handlers. Messages sent through it directly are only drawn with
+    /// the next [`send`](Self::send).
+    pub fn runner(&mut self) -> &mut AppRunner {
+        &mut self.runner
+    }
+
+    /// Draw frames and deliver the messages queued meanwhile until there
+    /// are none left.
+    fn settle(&mut self) {
+        for _ in 0..MAX_ROUNDS {
+            if let Some(frame) = self.runner.draw_frame() {
+                self.apply(frame);
+            }
+            if self.runner.should_quit() {
+                return;
+            }
+            self.runner.process_pending_msgs();
+            if !self.runner.needs_draw() {
+                return;
+            }
+        }
+        panic!("the model did not settle after {MAX_ROUNDS} frames");
+    }
+
+    fn apply(&mut self, frame: Frame) {
+        if (frame.width, frame.height) != (self.screen.width(), self.screen.height()) {
+            self.screen = Grid::new(frame.width, frame.height);
+        }
+        for c in &frame.cells {
+            self.screen.set(c.pos, c.cell);
+        }
+        self.screen.set_cursor(frame.cursor);
+        self.frames.push(frame);
+        self.runner.frame_presented();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::Cell;
+    use crate::app::{Effect, cmd, cmd_progress};
+
+    struct Step;
+    struct Loaded(u64);
+
+    /// Walks along a row a cell per frame once loaded, and draws its
+    /// position and the seed it loaded with.
+    struct Walk {
+        seed: Option<u64>,
+        pos: i32,
+        target: i32,
+    }
+
+    impl Model for Walk {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            if let Some(seed) = msg.seed() {
+                return Some(cmd_progress(move |_| Some(Msg::custom(Loaded(seed)))));
+            }
+            if let Some(Loaded(seed)) = msg.downcast_ref() {
+                self.seed = Some(*seed);
+                return None;
+            }
+            match msg {
+                Msg::KeyDown {
+                    key: Key::Char('g'),
+                    ..
+                } => {
+                    self.target = 3;
+                    Some(Effect::Continue)
+                }
+                Msg::KeyDown {
+                    key: Key::Char('q'),
+                    ..
+                } => Some(Effect::End),
+                Msg::Continue if self.pos < self.target => {
+                    self.pos += 1;
+                    Some(cmd(|| Some(Msg::custom(Step))))
+                }
+                _ if msg.downcast_ref::<Step>().is_some() => Some(Effect::Continue),
+                _ => None,
+            }
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            grid.fill(Cell::default());
+            grid.set(Point::new(self.pos, 0), Cell::default().with_char('@'));
+            if let Some(seed) = self.seed {
+                let digit = char::from_digit(seed as u32 % 10, 10).unwrap();
+                grid.set(Point::new(0, 1), Cell::default().with_char(digit));
+            }
+        }
+    }
+
+    fn walk() -> Box<Walk> {
+        Box::new(Walk {
+            seed: None,
+            pos: 0,
+            target: 0,
+        })
+    }
+
+    #[test]
+    fn commands_and_continuations_play_out() {
+        if crate::seed::from_env().is_some() {
+            return;
+        }
+        let mut t = TestRunner::new(walk(), 5, 2).with_seed(7);
+        t.start();
+        // The progress command loaded with the seed before the first send.
+        assert_eq!(t.grid_snapshot(), "@\n7");
+
+        let changed: Vec<usize> = t
+            .key(Key::Char('g'))
+            .iter()
+            .map(|f| f.cells.len())
+            .collect();
+        assert_eq!(t.grid_snapshot(), "   @\n7");
+        // A frame per step, each moving the `@`.
+        assert_eq!(changed, [2, 2, 2]);
+
+        t.script([
+            (Msg::key(Key::Char('x')), vec![(Point::new(3, 0), '@')]),
+            (Msg::key(Key::Char('q')), vec![]),
+        ]);
+        assert!(t.should_quit());
+        let before = t.frames().len();
+        assert!(t.key(Key::Char('g')).is_empty());
+        assert_eq!(t.take_frames().len(), before);
+    }
+
+    #[test]
+    fn resize_redraws() {
+        let mut t = TestRunner::new(walk(), 3, 1);
+        t.resize(2, 2);
+        t.assert_cells(&[(Point::new(0, 0), '@'), (Point::new(1, 1), ' ')]);
+        assert_eq!(t.grid().size(), Point::new(2, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "step 1: expected '@' at (1, 0)")]
+    fn script_names_the_failing_step() {
+        let mut t = TestRunner::new(walk(), 3, 1);
+        t.script([
+            (Msg::key(Key::Char('x')), [(Point::new(0, 0), '@')]),
+            (Msg::key(Key::Char('x')), [(Point::new(1, 0), '@')]),
+        ]);
+    }
+
+    /// Counts presses of `a`, pretending updates take 5 ms and drawing 2 ms
+    /// on `clock`.
+    struct Slow {
+        clock: Clock,
+        count: u32,
+    }
+
+    impl Model for Slow {
+        fn update(&mut self, msg: Msg) -> Option<Effect> {
+            if let Msg::KeyDown { key, .. } = msg {
+                self.clock.set(self.clock.get() + ms(5));
+                self.count += u32::from(key == Key::Char('a'));
+            }
+            None
+        }
+
+        fn draw(&self, grid: &mut Grid) {
+            self.clock.set(self.clock.get() + ms(2));
+            let digit = char::from_digit(self.count % 10, 10).unwrap();
+            grid.set(Point::new(0, 0), Cell::default().with_char(digit));
+        }
+    }
+
+    type Clock = Rc<std::cell::Cell<Instant>>;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn latencies_follow_a_manual_clock() {
+        let t0 = Instant::now();
+        let clock = Clock::new(t0.into());
+        let model = Slow {
+            clock: Rc::clone(&clock),
+            count: 0,
+        };
+        let mut t = TestRunner::new(Box::new(model), 1, 1);
+        let now = Rc::clone(&clock);
+        t.runner().set_clock(move || now.get());
+        t.start();
+        let key = |ch, at| Msg::key(Key::Char(ch)).with_time(t0 + ms(at));
+
+        // Got at 4 ms, handled at 10: updated at 10, drawn and presented
+        // at 17.
+        clock.set(t0 + ms(10));
+        t.send(key('a', 4));
+        let s = t.runner().stats();
+        assert_eq!(
+            (s.update.max, s.frame.max, s.present.max),
+            (ms(6), ms(13), ms(13))
+        );
+
+        // Two presses wait while the model is busy with the first: the
+        // frame showing all three counts from the oldest.
+        clock.set(t0 + ms(105));
+        t.runner().handle_msg(key('a', 100));
+        t.runner().handle_msg(key('a', 108));
+        t.send(key('a', 112));
+        let s = t.runner().stats();
+        // Updates waited 5, 2 and 3 ms; the frame was done at 122.
+        assert_eq!(
+            (s.update.samples, s.update.p50, s.update.max),
+            (4, ms(3), ms(6))
+        );
+        assert_eq!((s.frame.samples, s.frame.max), (2, ms(22)));
+        assert_eq!(s.present, s.frame);
+
+        // A key that changes nothing has no frame latency.
+        t.send(key('x', 200));
+        assert_eq!(t.runner().stats().frame.samples, 2);
+        assert_eq!(t.grid_snapshot(), "4");
+    }
+}
@@ -0,0 +1,103 @@
+//! Driver capabilities.
+//!
+//! Drivers describe what their back-end can do with a [`DriverCaps`], which
+//! the model receives as [`Msg::Capabilities`](crate::Msg::Capabilities)
+//! right after [`Msg::Init`](crate::Msg::Init), and again whenever it
+//! changes. Models use it to adapt their UI: hide mouse hints on a
+//! mouse-less terminal, pick ASCII fallbacks for symbols, or stretch bars
+//! drawn with tall terminal cells.
+
+/// How many colours a back-end can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorDepth {
+    /// The 16 ANSI colours (or fewer).
+    #[default]
+    Ansi16,
+    /// The xterm 256-colour palette.
+    Ansi256,
+    /// 24-bit colour.
+    TrueColor,
+}
+
+/// The kind of platform a driver runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Platform {
+    /// Not reported by the driver.
+    #[default]
+    Unknown,
+    /// A terminal emulator.
+    Terminal,
+    /// A native window.
+    Desktop,
+    /// A browser page.
+    Web,
+}
+
+/// What a driver's back-end supports.
+///
+/// The default is deliberately pessimistic, and is what drivers that do
+/// not override [`Driver::capabilities`](crate::Driver::capabilities)
+/// report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DriverCaps {
+    /// Whether mouse events are delivered.
+    pub mouse: bool,
+    /// How many colours can be shown.
+    pub color_depth: ColorDepth,
+    /// Cell width divided by cell height: about 0.5 for terminal and font
+    /// cells, 1.0 for square tiles.
+    pub cell_aspect: f32,
+    /// Whether cells are drawn with a [`TileManager`](crate::TileManager).
+    pub tiles: bool,
+    /// Whether timer commands are serviced while no input arrives, so
+    /// animations run on their own.
+    pub animation_ticks: bool,
+    /// Whether key releases arrive as [`Msg::KeyUp`](crate::Msg::KeyUp).
+    pub key_release: bool,
+    /// Whether the driver has a [`ClipboardProvider`](crate::ClipboardProvider)
+    /// for [`Effect::SetClipboard`](crate::Effect::SetClipboard). Terminals
+    /// may still ignore the OSC 52 sequence a terminal driver writes.
+    pub clipboard: bool,
+    /// Whether symbols beyond ASCII, such as `○` or `·`, are likely to
+    /// render. This is a hint: a driver can rarely know the font for sure.
+    pub unicode: bool,
+    /// The kind of platform.
+    pub platform: Platform,
+}
+
+impl Default for DriverCaps {
+    fn default() -> Self {
+        Self {
+            mouse: false,
+            color_depth: ColorDepth::Ansi16,
+            cell_aspect: 0.5,
+            tiles: false,
+            animation_ticks: false,
+            key_release: false,
+            clipboard: false,
+            unicode: false,
+            platform: Platform::Unknown,
+        }
+    }
+}
+
+impl DriverCaps {
+    /// `fancy` if the back-end likely renders it, `plain` otherwise.
+    ///
+    /// ```
+    /// use gruid_core::DriverCaps;
+    ///
+    /// let caps = DriverCaps::default();
+    /// assert_eq!(caps.symbol('○', 'o'), 'o');
+    /// ```
+    pub fn symbol(&self, fancy: char, plain: char) -> char {
+        if self.unicode || fancy.is_ascii() {
+            fancy
+        } else {
+            plain
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! System clipboard access.
+//!
+//! Models put text on the clipboard by returning
+//! [`Effect::SetClipboard`](crate::Effect::SetClipboard), which the
+//! application loop hands to the driver's [`ClipboardProvider`]: through
+//! [`Driver::clipboard`](crate::Driver::clipboard) for poll-based drivers,
+//! or the provider an event-loop driver installs with
+//! [`AppRunner::set_clipboard`](crate::AppRunner::set_clipboard). Whether
+//! a driver has one is reported in
+//! [`DriverCaps::clipboard`](crate::DriverCaps::clipboard).
+
+use std::error::Error;
+
+/// Writes text to a system clipboard.
+pub trait ClipboardProvider {
+    /// Replace the clipboard contents with `text`.
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// A provider that keeps the text in memory, for tests and headless runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryClipboard {
+    /// The text last set, if any.
+    pub text: Option<String>,
+}
+
+impl ClipboardProvider for MemoryClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+}
+
+/// Standard base64 encoding of `data`, with padding.
+pub fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, want) in cases {
+            assert_eq!(base64(input.as_bytes()), want, "{input:?}");
+        }
+        assert_eq!(base64("é\n".as_bytes()), "w6kK");
+    }
+}
@@ -0,0 +1,332 @@
+//! Canonical frames every driver should draw alike, and the ways drivers
+//! differ on purpose: the material of the driver conformance tests.
+//!
+//! Each driver checks that it draws the [`cases`]: drivers drawing pixels
+//! with the colours [`Style::rgb`] gives, terminals by sending each cell's
+//! character and style. What a driver leaves out on purpose is in its
+//! [`Profile`], which its tests check as well, so that differences are
+//! documented rather than accidental. [`differences_table`] renders the
+//! profiles as the table of `docs/driver-differences.md`.
+//!
+//! Double-width characters are not covered: no driver supports them yet.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::cell::Cell;
+use crate::geom::Point;
+use crate::grid::{Frame, FrameCell};
+use crate::style::{AttrMask, Color, DEFAULT_BG_RGB, DEFAULT_FG_RGB, Style};
+
+/// A frame to draw, with the name tests report it under.
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub name: &'static str,
+    pub frame: Frame,
+}
+
+/// Every case: [`gradient`], [`attributes`], [`box_drawing`] and
+/// [`defaults`].
+pub fn cases() -> Vec<Case> {
+    Vec::from([gradient(), attributes(), box_drawing(), defaults()])
+}
+
+fn case(name: &'static str, width: i32, cells: Vec<(Point, Cell)>) -> Case {
+    let height = cells.iter().map(|(p, _)| p.y + 1).max().unwrap_or(0);
+    let cells = cells
+        .into_iter()
+        .map(|(pos, cell)| FrameCell { cell, pos })
+        .collect();
+    Case {
+        name,
+        frame: Frame {
+            cells,
+            width,
+            height,
+            time_ms: 0,
+            cursor: None,
+        },
+    }
+}
+
+/// Ramps of 16 steps from dark to bright: red, green and blue backgrounds
+/// under the opposite foreground ramp, then grays.
+pub fn gradient() -> Case {
+    let mut cells = Vec::new();
+    for x in 0..16 {
+        let v = 15 + 16 * x as u8;
+        let ramps = [
+            Color::from_rgb(v, 0, 0),
+            Color::from_rgb(0, v, 0),
+            Color::from_rgb(0, 0, v),
+            Color::from_rgb(v, v, v),
+        ];
+        for (y, &bg) in ramps.iter().enumerate() {
+            let w = 255 - v;
+            let style = Style::default()
+                .with_fg(Color::from_rgb(w, w, w))
+                .with_bg(bg);
+            let cell = Cell::default().with_char('#').with_style(style);
+            cells.push((Point::new(x, y as i32), cell));
+        }
+    }
+    case("gradient", 16, cells)
+}
+
+/// Attribute combinations: `AttrMask(n)` for each `n` below 64, so every
+/// combination of the six attributes, at `(n % 16, n / 16)`.
+pub fn attributes() -> Case {
+    let style = Style::default()
+        .with_fg(Color::from_rgb(230, 180, 40))
+        .with_bg(Color::from_rgb(20, 40, 90));
+    let cells = (0..64)
+        .map(|n| {
+            let cell = Cell::default()
+                .with_char('A')
+                .with_style(style.with_attrs(AttrMask(n)));
+            (Point::new(n as i32 % 16, n as i32 / 16), cell)
+        })
+        .collect();
+    case("attributes", 16, cells)
+}
+
+/// Rows of [`box_drawing`].
+pub const BOX_DRAWING: [&str; 7] = [
+    "┌─┬─┐╔═╦═╗",
+    "│ │ │║ ║ ║",
+    "├─┼─┤╠═╬═╣",
+    "└─┴─┘╚═╩═╝",
+    "▀▄▌▐░▒▓·•…",
+    "←↑→↓↖↗↘↙♥♠",
+    "αβγδλπΣΩ°±",
+];
+
+/// Box-drawing and other symbols of [`BOX_DRAWING`], in the default
+/// colours.
+pub fn box_drawing() -> Case {
+    let mut cells = Vec::new();
+    for (y, row) in BOX_DRAWING.iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            cells.push((
+                Point::new(x as i32, y as i32),
+                Cell::default().with_char(ch),
+            ));
+        }
+    }
+    case("box_drawing", 10, cells)
+}
+
+/// [`Color::DEFAULT`] against explicit colours, one cell per column:
+/// default colours, a default foreground on blue, red on a default
+/// background, black as `from_rgb(0, 0, 0)`, and defaults reversed then
+/// dimmed.
+pub fn defaults() -> Case {
+    let blue = Color::from_rgb(30, 60, 200);
+    let red = Color::from_rgb(200, 30, 30);
+    let styles = [
+        Style::default(),
+        Style::default().with_bg(blue),
+        Style::default().with_fg(red),
+        Style::default()
+            .with_fg(Color::from_rgb(0, 0, 0))
+            .with_bg(Color::from_rgb(0, 0, 0)),
+        Style::default().with_attrs(AttrMask::REVERSE),
+        Style::default().with_attrs(AttrMask::DIM),
+    ];
+    let cells = styles
+        .iter()
+        .enumerate()
+        .map(|(x, &style)| {
+            let cell = Cell::default().with_char('d').with_style(style);
+            (Point::new(x as i32, 0), cell)
+        })
+        .collect();
+    case("defaults", styles.len() as i32, cells)
+}
+
+/// What [`Color::DEFAULT`] stands for on a driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Defaults {
+    /// The terminal's own colours, whatever its theme.
+    Terminal,
+    /// [`DEFAULT_FG_RGB`] and [`DEFAULT_BG_RGB`].
+    Rgb,
+}
+
+/// What a driver draws of the cases, where drivers differ on purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    pub driver: &'static str,
+    /// Attributes the driver shows; it ignores the others.
+    pub attrs: AttrMask,
+    pub defaults: Defaults,
+    /// How characters outside ASCII are drawn.
+    pub glyphs: &'static str,
+}
+
+/// Every attribute, with its name.
+pub const ATTRIBUTES: [(AttrMask, &str); 6] = [
+    (AttrMask::BOLD, "BOLD"),
+    (AttrMask::ITALIC, "ITALIC"),
+    (AttrMask::UNDERLINE, "UNDERLINE"),
+    (AttrMask::BLINK, "BLINK"),
+    (AttrMask::REVERSE, "REVERSE"),
+    (AttrMask::DIM, "DIM"),
+];
+
+/// Profiles of the drivers of the workspace.
+pub const PROFILES: [Profile; 4] = [
+    Profile {
+        driver: "crossterm",
+        attrs: AttrMask(
+            AttrMask::BOLD.0
+                | AttrMask::ITALIC.0
+                | AttrMask::UNDERLINE.0
+                | AttrMask::REVERSE.0
+                | AttrMask::DIM.0,
+        ),
+        defaults: Defaults::Terminal,
+        glyphs: "terminal font",
+    },
+    Profile {
+        driver: "winit",
+        attrs: AttrMask(AttrMask::REVERSE.0 | AttrMask::DIM.0),
+        defaults: Defaults::Rgb,
+        glyphs: "configured font, blank if missing",
+    },
+    Profile {
+        driver: "wgpu",
+        attrs: AttrMask(AttrMask::REVERSE.0 | AttrMask::DIM.0),
+        defaults: Defaults::Rgb,
+        glyphs: "configured font, blank if missing",
+    },
+    Profile {
+        driver: "web",
+        attrs: AttrMask(
+            AttrMask::BOLD.0
+                | AttrMask::ITALIC.0
+                | AttrMask::UNDERLINE.0
+                | AttrMask::REVERSE.0
+                | AttrMask::DIM.0,
+        ),
+        defaults: Defaults::Rgb,
+        glyphs: "browser fonts, with fallback",
+    },
+];
+
+/// The profile of `driver`.
+///
+/// # Panics
+///
+/// Panics if there is none.
+pub fn profile(driver: &str) -> &'static Profile {
+    PROFILES
+        .iter()
+        .find(|p| p.driver == driver)
+        .unwrap_or_else(|| panic!("no profile for driver {driver:?}"))
+}
+
+/// The [`PROFILES`] as a Markdown table, with a row per attribute, for
+/// [`Color::DEFAULT`], black and glyphs.
+pub fn differences_table() -> String {
+    let mut rows: Vec<(String, Vec<String>)> = Vec::new();
+    let column = |f: &dyn Fn(&Profile) -> String| PROFILES.iter().map(f).collect();
+    for (mask, name) in ATTRIBUTES {
+        let shown = |p: &Profile| {
+            let s = if p.attrs.contains(mask) {
+                "yes"
+            } else {
+                "ignored"
+            };
+            String::from(s)
+        };
+        rows.push((format!("`{name}`"), column(&shown)));
+    }
+    let rgb = |(r, g, b): (u8, u8, u8)| format!("rgb({r}, {g}, {b})");
+    let default = |c: (u8, u8, u8)| {
+        move |p: &Profile| match p.defaults {
+            Defaults::Terminal => String::from("terminal default"),
+            Defaults::Rgb => rgb(c),
+        }
+    };
+    rows.push((
+        String::from("`DEFAULT` foreground"),
+        column(&default(DEFAULT_FG_RGB)),
+    ));
+    rows.push((
+        String::from("`DEFAULT` background"),
+        column(&default(DEFAULT_BG_RGB)),
+    ));
+    let black = |p: &Profile| {
+        String::from(match p.defaults {
+            Defaults::Terminal => "terminal default",
+            Defaults::Rgb => "default colour",
+        })
+    };
+    rows.push((String::from("`from_rgb(0, 0, 0)`"), column(&black)));
+    rows.push((
+        String::from("Non-ASCII glyphs"),
+        column(&|p: &Profile| String::from(p.glyphs)),
+    ));
+
+    let mut table = String::from("| |");
+    for p in &PROFILES {
+        table.push_str(&format!(" {} |", p.driver));
+    }
+    table.push_str("\n|---|");
+    table.push_str(&"---|".repeat(PROFILES.len()));
+    for (name, cells) in rows {
+        table.push_str(&format!("\n| {name} |"));
+        for c in cells {
+            table.push_str(&format!(" {c} |"));
+        }
+    }
+    table.push('\n');
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cases_fit_their_frames() {
+        for case in cases() {
+            let f = &case.frame;
+            assert!(!f.cells.is_empty(), "{}", case.name);
+            for c in &f.cells {
+                let p = c.pos;
+                assert!(
+                    p.x >= 0 && p.y >= 0 && p.x < f.width && p.y < f.height,
+                    "{}: {p}",
+                    case.name
+                );
+            }
+        }
+        let mut masks: Vec<u32> = attributes()
+            .frame
+            .cells
+            .iter()
+            .map(|c| c.cell.style.attrs.0)
+            .collect();
+        masks.sort_unstable();
+        masks.dedup();
+        assert_eq!(masks.len(), 1 << ATTRIBUTES.len());
+        assert!(BOX_DRAWING.iter().all(|row| row.chars().count() == 10));
+    }
+
+    #[test]
+    fn differences_table_is_documented() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../docs/driver-differences.md"
+        );
+        let doc = std::fs::read_to_string(path).unwrap();
+        let table = differences_table();
+        assert!(
+            doc.contains(&table),
+            "docs/driver-differences.md is out of date; its table should be:\n\n{table}"
+        );
+    }
+}
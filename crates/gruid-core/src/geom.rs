@@ -1,12 +1,23 @@
-//! Geometry primitives: [`Point`] and [`Range`].
+//! Geometry primitives: [`Point`], [`Direction`] and [`Range`].
 //!
 //! These mirror Go gruid's `gruid.Point` and `gruid.Range` but are idiomatic Rust.
-
-use std::fmt;
-use std::hash::{Hash, Hasher};
-use std::ops::{Add, Div, Mul, Sub};
-
-use crate::messages::Msg;
+//!
+//! Coordinates are plain `i32`s, and so is their arithmetic: adding points,
+//! or translating a range with [`Range::add`], overflows like the integer
+//! operators, panicking in debug builds. Screens and maps are far smaller
+//! than the `i32` range, so this only matters for sentinel values. The
+//! methods taking **relative** rows or columns, such as
+//! [`Range::lines`], saturate instead, so that `rg.lines(1, i32::MAX)`
+//! means "all rows but the first". Ranges whose `min` is not below their
+//! `max`, including inverted ones built from the fields, are empty: they
+//! contain no point, iterate none, and have a [`len`](Range::len) of 0,
+//! although their [`size`](Range::size) may be negative.
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Add, Div, Mul, Sub};
+
+use crate::messages::{Key, Msg};
 
 // ---------------------------------------------------------------------------
 // Point
@@ -70,6 +81,13 @@ impl Point {
             Self::new(self.x - 1, self.y - 1),
         ]
     }
+
+    /// The neighbouring point in direction `dir`.
+    #[inline]
+    pub const fn step(self, dir: Direction) -> Self {
+        let d = dir.delta();
+        self.shift(d.x, d.y)
+    }
 }
 
 // --- trait impls for Point ---
@@ -82,13 +100,13 @@ impl Hash for Point {
 }
 
 impl PartialOrd for Point {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for Point {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.y.cmp(&other.y).then(self.x.cmp(&other.x))
     }
 }
@@ -131,6 +149,196 @@ impl Div<i32> for Point {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Direction
+// ---------------------------------------------------------------------------
+
+/// One of the eight grid directions. North is up (negative Y).
+///
+/// Variants are listed clockwise from north, the order of
+/// [`Point::neighbors_8`] and [`Direction::ALL`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Direction {
+    /// All directions, clockwise from north.
+    pub const ALL: [Self; 8] = [
+        Self::N,
+        Self::NE,
+        Self::E,
+        Self::SE,
+        Self::S,
+        Self::SW,
+        Self::W,
+        Self::NW,
+    ];
+
+    /// The four cardinal directions, clockwise from north.
+    pub const CARDINAL: [Self; 4] = [Self::N, Self::E, Self::S, Self::W];
+
+    /// All directions, clockwise from north.
+    pub fn iter_all() -> impl Iterator<Item = Self> {
+        Self::ALL.into_iter()
+    }
+
+    /// The cardinal directions, clockwise from north.
+    pub fn iter_cardinal() -> impl Iterator<Item = Self> {
+        Self::CARDINAL.into_iter()
+    }
+
+    /// The unit step in this direction, such as `(1, -1)` for north-east.
+    pub const fn delta(self) -> Point {
+        match self {
+            Self::N => Point::new(0, -1),
+            Self::NE => Point::new(1, -1),
+            Self::E => Point::new(1, 0),
+            Self::SE => Point::new(1, 1),
+            Self::S => Point::new(0, 1),
+            Self::SW => Point::new(-1, 1),
+            Self::W => Point::new(-1, 0),
+            Self::NW => Point::new(-1, -1),
+        }
+    }
+
+    /// The direction whose [`delta`](Self::delta) is exactly `d`, if any.
+    pub fn from_delta_exact(d: Point) -> Option<Self> {
+        Self::ALL.into_iter().find(|dir| dir.delta() == d)
+    }
+
+    /// The direction closest to the vector `d`, or `None` for `(0, 0)`.
+    ///
+    /// `d` points diagonally when its smaller component is at least half
+    /// its larger one, in absolute value, and along the axis of the larger
+    /// one otherwise: `(2, 1)` is [`SE`](Self::SE) and `(3, 1)` is
+    /// [`E`](Self::E). Each octant thus spans about 53° around diagonals
+    /// and 37° around axes.
+    pub fn from_delta(d: Point) -> Option<Self> {
+        let (ax, ay) = (d.x.unsigned_abs(), d.y.unsigned_abs());
+        let (major, minor) = (ax.max(ay), ax.min(ay));
+        if major == 0 {
+            return None;
+        }
+        let unit = if 2 * minor as u64 >= major as u64 {
+            Point::new(d.x.signum(), d.y.signum())
+        } else if ax > ay {
+            Point::new(d.x.signum(), 0)
+        } else {
+            Point::new(0, d.y.signum())
+        };
+        Self::from_delta_exact(unit)
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The direction `steps` eighths of a turn clockwise.
+    fn turn(self, steps: usize) -> Self {
+        Self::ALL[(self.index() + steps) % 8]
+    }
+
+    /// The opposite direction.
+    pub fn opposite(self) -> Self {
+        self.turn(4)
+    }
+
+    /// The next direction clockwise, 45° away.
+    pub fn rotate_cw(self) -> Self {
+        self.turn(1)
+    }
+
+    /// The next direction counter-clockwise, 45° away.
+    pub fn rotate_ccw(self) -> Self {
+        self.turn(7)
+    }
+
+    /// The direction 90° clockwise.
+    pub fn rotate_cw_90(self) -> Self {
+        self.turn(2)
+    }
+
+    /// The direction 90° counter-clockwise.
+    pub fn rotate_ccw_90(self) -> Self {
+        self.turn(6)
+    }
+
+    /// Whether this is N, E, S or W.
+    pub fn is_cardinal(self) -> bool {
+        self.index() % 2 == 0
+    }
+
+    /// Whether this is NE, SE, SW or NW.
+    pub fn is_diagonal(self) -> bool {
+        !self.is_cardinal()
+    }
+
+    /// The direction of an arrow key.
+    pub fn from_arrow_key(key: &Key) -> Option<Self> {
+        match key {
+            Key::ArrowUp => Some(Self::N),
+            Key::ArrowRight => Some(Self::E),
+            Key::ArrowDown => Some(Self::S),
+            Key::ArrowLeft => Some(Self::W),
+            _ => None,
+        }
+    }
+
+    /// The direction of a vi key: `hjkl` for the cardinal directions and
+    /// `yubn` for the diagonals.
+    pub fn from_vi_key(key: &Key) -> Option<Self> {
+        match key {
+            Key::Char('k') => Some(Self::N),
+            Key::Char('u') => Some(Self::NE),
+            Key::Char('l') => Some(Self::E),
+            Key::Char('n') => Some(Self::SE),
+            Key::Char('j') => Some(Self::S),
+            Key::Char('b') => Some(Self::SW),
+            Key::Char('h') => Some(Self::W),
+            Key::Char('y') => Some(Self::NW),
+            _ => None,
+        }
+    }
+
+    /// The direction of a numeric keypad key: the digits around `5`, or
+    /// for the diagonals the navigation keys the keypad sends without
+    /// NumLock (Home, PageUp, End, PageDown). Without NumLock the cardinal
+    /// digits arrive as arrow keys.
+    pub fn from_numpad_key(key: &Key) -> Option<Self> {
+        match key {
+            Key::Char('8') => Some(Self::N),
+            Key::Char('9') | Key::PageUp => Some(Self::NE),
+            Key::Char('6') => Some(Self::E),
+            Key::Char('3') | Key::PageDown => Some(Self::SE),
+            Key::Char('2') => Some(Self::S),
+            Key::Char('1') | Key::End => Some(Self::SW),
+            Key::Char('4') => Some(Self::W),
+            Key::Char('7') | Key::Home => Some(Self::NW),
+            _ => None,
+        }
+    }
+
+    /// The direction of an arrow, vi or numeric keypad key. Games that use
+    /// some of these keys for other commands should combine
+    /// [`from_arrow_key`](Self::from_arrow_key),
+    /// [`from_vi_key`](Self::from_vi_key) and
+    /// [`from_numpad_key`](Self::from_numpad_key) instead.
+    pub fn from_key(key: &Key) -> Option<Self> {
+        Self::from_arrow_key(key)
+            .or_else(|| Self::from_vi_key(key))
+            .or_else(|| Self::from_numpad_key(key))
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Range
 // ---------------------------------------------------------------------------
@@ -242,14 +450,7 @@ impl Range {
     /// out of bounds. Matches Go gruid's `Range.Line`.
     #[inline]
     pub fn line(self, y: i32) -> Self {
-        if self.min.shift(0, y).in_range(&self) {
-            Self {
-                min: Point::new(self.min.x, self.min.y + y),
-                max: Point::new(self.max.x, self.min.y + y + 1),
-            }
-        } else {
-            Self::default()
-        }
+        self.lines(y, y.saturating_add(1))
     }
 
     /// Reduces the range to **relative** rows `[y0, y1)` (0 = first line).
@@ -259,8 +460,8 @@ impl Range {
     #[inline]
     pub fn lines(self, y0: i32, y1: i32) -> Self {
         let nrg = Self {
-            min: Point::new(self.min.x, self.min.y + y0),
-            max: Point::new(self.max.x, self.min.y + y1),
+            min: Point::new(self.min.x, self.min.y.saturating_add(y0)),
+            max: Point::new(self.max.x, self.min.y.saturating_add(y1)),
         };
         self.intersect(nrg)
     }
@@ -271,14 +472,7 @@ impl Range {
     /// out of bounds. Matches Go gruid's `Range.Column`.
     #[inline]
     pub fn column(self, x: i32) -> Self {
-        if self.min.shift(x, 0).in_range(&self) {
-            Self {
-                min: Point::new(self.min.x + x, self.min.y),
-                max: Point::new(self.min.x + x + 1, self.max.y),
-            }
-        } else {
-            Self::default()
-        }
+        self.columns(x, x.saturating_add(1))
     }
 
     /// Reduces the range to **relative** columns `[x0, x1)` (0 = first column).
@@ -288,8 +482,8 @@ impl Range {
     #[inline]
     pub fn columns(self, x0: i32, x1: i32) -> Self {
         let nrg = Self {
-            min: Point::new(self.min.x + x0, self.min.y),
-            max: Point::new(self.min.x + x1, self.max.y),
+            min: Point::new(self.min.x.saturating_add(x0), self.min.y),
+            max: Point::new(self.min.x.saturating_add(x1), self.max.y),
         };
         self.intersect(nrg)
     }
@@ -300,7 +494,10 @@ impl Range {
         if self.is_empty() {
             return 0;
         }
-        (self.width() as usize) * (self.height() as usize)
+        // Differences of far apart coordinates do not fit an `i32`.
+        let w = self.max.x.abs_diff(self.min.x) as usize;
+        let h = self.max.y.abs_diff(self.min.y) as usize;
+        w.saturating_mul(h)
     }
 
     /// Whether the range has zero or negative area.
@@ -442,10 +639,12 @@ impl Iterator for RangeIter {
         if self.range.is_empty() || self.cur.y >= self.range.max.y {
             return (0, Some(0));
         }
-        let w = self.range.width() as usize;
-        let remaining_in_row = (self.range.max.x - self.cur.x) as usize;
-        let remaining_rows = (self.range.max.y - self.cur.y - 1) as usize;
-        let total = remaining_in_row + remaining_rows * w;
+        let w = self.range.max.x.abs_diff(self.range.min.x) as usize;
+        let remaining_in_row = self.range.max.x.abs_diff(self.cur.x) as usize;
+        let remaining_rows = self.range.max.y.abs_diff(self.cur.y) as usize - 1;
+        let total = remaining_rows
+            .saturating_mul(w)
+            .saturating_add(remaining_in_row);
         (total, Some(total))
     }
 }
@@ -481,7 +680,80 @@ mod tests {
     use super::*;
     use crate::messages::{ModMask, MouseAction};
     use std::collections::HashSet;
-    use std::time::Instant;
+
+    // -----------------------------------------------------------------------
+    // Direction tests
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn direction_rotations() {
+        for d in Direction::iter_all() {
+            let mut r = d;
+            for _ in 0..8 {
+                r = r.rotate_cw();
+            }
+            assert_eq!(r, d);
+            assert_eq!(d.rotate_cw().rotate_ccw(), d);
+            assert_eq!(d.rotate_cw_90(), d.rotate_cw().rotate_cw());
+            assert_eq!(d.rotate_cw_90().rotate_ccw_90(), d);
+            assert_eq!(d.opposite().opposite(), d);
+            assert_eq!(d.opposite().delta(), d.delta() * -1);
+            assert_eq!(d.is_cardinal(), d.delta().x * d.delta().y == 0);
+            assert_ne!(d.is_cardinal(), d.is_diagonal());
+            assert_eq!(Direction::from_delta_exact(d.delta()), Some(d));
+        }
+        assert_eq!(Direction::N.rotate_cw(), Direction::NE);
+        assert_eq!(Direction::W.rotate_cw_90(), Direction::N);
+        let p = Point::new(3, 3);
+        let steps: Vec<Point> = Direction::iter_all().map(|d| p.step(d)).collect();
+        assert_eq!(steps, p.neighbors_8());
+        let cardinal: Vec<Point> = Direction::iter_cardinal().map(|d| p.step(d)).collect();
+        assert_eq!(cardinal, p.neighbors_4());
+    }
+
+    #[test]
+    fn direction_from_delta_octants() {
+        use Direction::*;
+        let from = |x, y| Direction::from_delta(Point::new(x, y));
+        assert_eq!(from(0, 0), None);
+        assert_eq!(from(5, 0), Some(E));
+        assert_eq!(from(0, -7), Some(N));
+        // The boundary: a minor component of half the major is diagonal.
+        assert_eq!(from(2, 1), Some(SE));
+        assert_eq!(from(3, 1), Some(E));
+        assert_eq!(from(-4, 2), Some(SW));
+        assert_eq!(from(-5, 2), Some(W));
+        assert_eq!(from(1, -2), Some(NE));
+        assert_eq!(from(1, -3), Some(N));
+        assert_eq!(from(-9, -9), Some(NW));
+        assert_eq!(from(i32::MIN, i32::MIN / 2), Some(NW));
+        assert_eq!(Direction::from_delta_exact(Point::new(2, 0)), None);
+    }
+
+    #[test]
+    fn direction_from_keys() {
+        use Direction::*;
+        let keys = |chars: &str| -> Vec<Option<Direction>> {
+            chars
+                .chars()
+                .map(|c| Direction::from_key(&Key::Char(c)))
+                .collect()
+        };
+        assert_eq!(
+            keys("kulnjbhy"),
+            [N, NE, E, SE, S, SW, W, NW].map(Some).to_vec()
+        );
+        assert_eq!(
+            keys("89632147"),
+            [N, NE, E, SE, S, SW, W, NW].map(Some).to_vec()
+        );
+        assert_eq!(keys("5x0"), vec![None; 3]);
+        assert_eq!(Direction::from_key(&Key::ArrowLeft), Some(W));
+        assert_eq!(Direction::from_key(&Key::PageDown), Some(SE));
+        assert_eq!(Direction::from_vi_key(&Key::Char('8')), None);
+        assert_eq!(Direction::from_numpad_key(&Key::ArrowUp), None);
+        assert_eq!(Direction::from_arrow_key(&Key::Char('k')), None);
+    }
 
     // -----------------------------------------------------------------------
     // Point tests (unchanged)
@@ -804,7 +1076,7 @@ mod tests {
             action: MouseAction::Main,
             pos: Point::new(7, 12),
             modifiers: ModMask::NONE,
-            time: Instant::now(),
+            time: crate::messages::now(),
         };
         let rel = r.rel_msg(msg);
         match rel {
@@ -7,13 +7,166 @@
 //! All public methods use **relative** coordinates (0-based within the grid
 //! view), matching Go gruid's semantics. After `grid.slice(Range::new(5,5,10,10))`,
 //! `grid.set(Point::new(0,0), c)` writes to position (5,5) in the underlying buffer.
+//!
+//! ## Out-of-bounds positions
+//!
+//! Grid methods never panic on positions or ranges, however far outside
+//! the grid they are: everything is clipped. Reads outside the grid return
+//! [`Cell::default()`], writes there are ignored, and ranges, whether for
+//! [`slice`](Grid::slice), [`clear_range`](Grid::clear_range) or
+//! [`scroll_up`](Grid::scroll_up), are first intersected with the grid. A
+//! slice beyond the grid is empty rather than inverted, so sizes are never
+//! negative, and [`iter`](Grid::iter) visits exactly the
+//! [`size`](Grid::size) of a grid. Negative sizes given to [`Grid::new`] or
+//! [`Grid::resize`] count as zero. Only allocation can fail: like a `Vec`,
+//! a grid too large for memory aborts.
+//!
+//! To save memory on large grids, the buffer stores each distinct [`Style`]
+//! once in a table and cells refer to it by a 16-bit id, halving the size
+//! of a cell. This is invisible through the API: [`Grid::set`] interns the
+//! style and [`Grid::at`] looks it up. A buffer that would need more than
+//! 65536 styles first drops the unused ones from its table, and if that is
+//! not enough falls back to storing full styles.
 
-use std::cell::RefCell;
-use std::fmt;
-use std::rc::Rc;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::hash::{BuildHasherDefault, Hasher};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 use crate::cell::Cell;
 use crate::geom::{Point, Range};
+use crate::style::Style;
+
+// ---------------------------------------------------------------------------
+// Style interning
+// ---------------------------------------------------------------------------
+
+/// Index of a style in a [`StyleTable`].
+type StyleId = u16;
+
+/// Number of distinct styles a buffer can intern.
+const MAX_STYLES: usize = u16::MAX as usize;
+
+/// Id of a cell whose style did not fit in the table.
+const OVERFLOW: StyleId = u16::MAX;
+
+/// A cell as stored in an interned buffer: 8 bytes instead of 16.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Packed {
+    ch: char,
+    style: StyleId,
+}
+
+impl Packed {
+    /// The default cell; the default style always has id 0.
+    const DEFAULT: Self = Self { ch: ' ', style: 0 };
+}
+
+/// A fast hasher for the few words of a [`Style`], in the manner of FxHash.
+/// Flooding a grid's style table is no concern.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct StyleHasher(u64);
+
+#[cfg(feature = "std")]
+impl Hasher for StyleHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u64(b as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.write_u64(n as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Ids of interned styles: hashed with `std`, ordered without.
+#[cfg(feature = "std")]
+type StyleIds = HashMap<Style, StyleId, BuildHasherDefault<StyleHasher>>;
+#[cfg(not(feature = "std"))]
+type StyleIds = BTreeMap<Style, StyleId>;
+
+/// Styles that did not fit in the table, by cell index.
+#[cfg(feature = "std")]
+type Overflow = HashMap<usize, Style>;
+#[cfg(not(feature = "std"))]
+type Overflow = BTreeMap<usize, Style>;
+
+/// The distinct styles of one buffer.
+///
+/// Tables belong to a buffer rather than being global: they need no
+/// locking, go away with their grid, and a big world map cannot use up the
+/// ids of the screen grid. Ids never leave the grid, since
+/// [`Grid::at`] resolves them, so grids compare cells by value.
+#[derive(Debug, Clone)]
+struct StyleTable {
+    styles: Vec<Style>,
+    ids: StyleIds,
+    /// The last style interned, which fills and text runs repeat.
+    last: (Style, StyleId),
+    cap: usize,
+}
+
+impl StyleTable {
+    fn new(cap: usize) -> Self {
+        let default = Style::default();
+        Self {
+            styles: vec![default],
+            ids: StyleIds::from_iter([(default, 0)]),
+            last: (default, 0),
+            cap,
+        }
+    }
+
+    #[inline]
+    fn get(&self, id: StyleId) -> Style {
+        self.styles[id as usize]
+    }
+
+    /// The id of `style`, adding it to the table if needed. `None` if the
+    /// table is full.
+    #[inline]
+    fn intern(&mut self, style: Style) -> Option<StyleId> {
+        if self.last.0 == style {
+            return Some(self.last.1);
+        }
+        self.lookup(style)
+    }
+
+    fn lookup(&mut self, style: Style) -> Option<StyleId> {
+        let id = match self.ids.get(&style) {
+            Some(&id) => id,
+            None if self.styles.len() < self.cap => {
+                let id = self.styles.len() as StyleId;
+                self.styles.push(style);
+                self.ids.insert(style, id);
+                id
+            }
+            None => return None,
+        };
+        self.last = (style, id);
+        Some(id)
+    }
+}
 
 // ---------------------------------------------------------------------------
 // Internal shared buffer
@@ -21,15 +174,35 @@ use crate::geom::{Point, Range};
 
 #[derive(Debug, Clone)]
 struct GridBuffer {
-    cells: Vec<Cell>,
+    cells: Vec<Packed>,
+    styles: StyleTable,
+    /// Styles of the cells set while the table was full of live styles,
+    /// which have the [`OVERFLOW`] id.
+    overflow: Overflow,
+    /// Styles that did not fit in the table since it was last compacted.
+    misses: usize,
+    /// Misses before the table is compacted again, so that a table full of
+    /// live styles is not rescanned on every set.
+    compact_at: usize,
+    /// Where the terminal cursor is shown, in absolute coordinates.
+    cursor: Option<Point>,
     width: usize,
     height: usize,
 }
 
 impl GridBuffer {
     fn new(width: usize, height: usize) -> Self {
+        Self::with_style_cap(width, height, MAX_STYLES)
+    }
+
+    fn with_style_cap(width: usize, height: usize, cap: usize) -> Self {
         Self {
-            cells: vec![Cell::default(); width * height],
+            cells: vec![Packed::DEFAULT; width * height],
+            styles: StyleTable::new(cap.min(MAX_STYLES)),
+            overflow: Overflow::new(),
+            misses: 0,
+            compact_at: 0,
+            cursor: None,
             width,
             height,
         }
@@ -43,6 +216,133 @@ impl GridBuffer {
             None
         }
     }
+
+    #[inline]
+    fn get(&self, i: usize) -> Cell {
+        let c = self.cells[i];
+        let style = match self.styles.styles.get(c.style as usize) {
+            Some(&style) => style,
+            None => self.overflow[&i],
+        };
+        Cell { ch: c.ch, style }
+    }
+
+    #[inline]
+    fn ch(&self, i: usize) -> char {
+        self.cells[i].ch
+    }
+
+    #[inline]
+    fn set(&mut self, i: usize, cell: Cell) {
+        if self.styles.last.0 == cell.style && self.overflow.is_empty() {
+            self.cells[i] = Packed {
+                ch: cell.ch,
+                style: self.styles.last.1,
+            };
+            return;
+        }
+        let p = self.pack(cell);
+        self.put(i, p, cell.style);
+    }
+
+    /// Store `p` at `i`, recording `style` if `p` is an overflow cell.
+    fn put(&mut self, i: usize, p: Packed, style: Style) {
+        self.cells[i] = p;
+        if p.style == OVERFLOW {
+            self.overflow.insert(i, style);
+        } else if !self.overflow.is_empty() {
+            self.overflow.remove(&i);
+        }
+    }
+
+    /// The packed form of `cell`, making room in the style table if needed.
+    fn pack(&mut self, cell: Cell) -> Packed {
+        let mut id = self.styles.intern(cell.style);
+        if id.is_none() {
+            self.misses += 1;
+            if self.misses > self.compact_at {
+                self.compact_styles();
+                id = self.styles.intern(cell.style);
+            }
+        }
+        Packed {
+            ch: cell.ch,
+            style: id.unwrap_or(OVERFLOW),
+        }
+    }
+
+    /// Set the cells of the absolute range `rg` to `cell`.
+    fn fill(&mut self, rg: Range, cell: Cell) {
+        let rg = rg.intersect(Range::new(0, 0, self.width as i32, self.height as i32));
+        if rg.is_empty() {
+            return;
+        }
+        let p = self.pack(cell);
+        let w = rg.width() as usize;
+        for y in rg.min.y..rg.max.y {
+            let start = y as usize * self.width + rg.min.x as usize;
+            if p.style == OVERFLOW || !self.overflow.is_empty() {
+                (start..start + w).for_each(|i| self.put(i, p, cell.style));
+            } else {
+                self.cells[start..start + w].fill(p);
+            }
+        }
+    }
+
+    /// Copy the cells of `src` to start at `dst`, like [`slice::copy_within`].
+    fn copy_within(&mut self, src: core::ops::Range<usize>, dst: usize) {
+        if self.overflow.is_empty() {
+            self.cells.copy_within(src, dst);
+            return;
+        }
+        let moved: Vec<Cell> = src.map(|i| self.get(i)).collect();
+        for (k, cell) in moved.into_iter().enumerate() {
+            self.set(dst + k, cell);
+        }
+    }
+
+    /// Rebuild the style table with only the styles still in use, then move
+    /// as many overflow cells as fit back into it.
+    fn compact_styles(&mut self) {
+        let mut fresh = StyleTable::new(self.styles.cap);
+        let mut remap: Vec<Option<StyleId>> = vec![None; self.styles.styles.len()];
+        for c in self.cells.iter_mut().filter(|c| c.style != OVERFLOW) {
+            let old = &self.styles;
+            c.style = *remap[c.style as usize].get_or_insert_with(|| {
+                fresh
+                    .intern(old.get(c.style))
+                    .expect("a fresh table holds as many styles as the old one")
+            });
+        }
+        self.styles = fresh;
+        let cells = &mut self.cells;
+        let styles = &mut self.styles;
+        self.overflow
+            .retain(|&i, style| match styles.intern(*style) {
+                Some(id) => {
+                    cells[i].style = id;
+                    false
+                }
+                None => true,
+            });
+        self.misses = 0;
+        self.compact_at = self.cells.len() / 8;
+    }
+
+    /// Approximate heap size in bytes.
+    fn memory_size(&self) -> usize {
+        let entry = core::mem::size_of::<(Style, StyleId)>() + core::mem::size_of::<u64>();
+        let overflow_entry = core::mem::size_of::<(usize, Style)>() + core::mem::size_of::<u64>();
+        // B-trees have no capacity; their nodes are about full.
+        #[cfg(feature = "std")]
+        let (ids, overflow) = (self.styles.ids.capacity(), self.overflow.capacity());
+        #[cfg(not(feature = "std"))]
+        let (ids, overflow) = (self.styles.ids.len(), self.overflow.len());
+        self.cells.capacity() * core::mem::size_of::<Packed>()
+            + self.styles.styles.capacity() * core::mem::size_of::<Style>()
+            + ids * entry
+            + overflow * overflow_entry
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -104,20 +404,29 @@ impl Grid {
     /// Whether relative point `p` is inside this grid.
     #[inline]
     pub fn contains(&self, p: Point) -> bool {
-        let q = Point::new(p.x + self.bounds.min.x, p.y + self.bounds.min.y);
-        self.bounds.contains(q)
+        self.abs(p).is_some()
+    }
+
+    /// The absolute position in the buffer of relative point `p`, if it is
+    /// inside this grid. Checked before adding the offset, which cannot
+    /// overflow then.
+    #[inline]
+    fn abs(&self, p: Point) -> Option<Point> {
+        let size = self.size();
+        (p.x >= 0 && p.y >= 0 && p.x < size.x && p.y < size.y).then(|| p + self.bounds.min)
     }
 
     /// Get a sub-grid view. `rg` is a **relative** range within this grid.
     /// The returned `Grid` shares the same backing buffer.
     ///
-    /// Like Go's `Grid.Slice`, the range is clamped to this grid's size.
+    /// Like Go's `Grid.Slice`, the range is clamped to this grid's size. A
+    /// range outside the grid gives an empty slice.
     pub fn slice(&self, rg: Range) -> Grid {
         let max = self.size();
-        let min_x = rg.min.x.max(0);
-        let min_y = rg.min.y.max(0);
-        let max_x = rg.max.x.min(max.x);
-        let max_y = rg.max.y.min(max.y);
+        let min_x = rg.min.x.max(0).min(max.x);
+        let min_y = rg.min.y.max(0).min(max.y);
+        let max_x = rg.max.x.min(max.x).max(min_x);
+        let max_y = rg.max.y.min(max.y).max(min_y);
         // Offset to absolute coords in the underlying buffer.
         let abs_min = Point::new(min_x + self.bounds.min.x, min_y + self.bounds.min.y);
         let abs_max = Point::new(max_x + self.bounds.min.x, max_y + self.bounds.min.y);
@@ -132,58 +441,76 @@ impl Grid {
 
     /// Read the cell at relative position `p`. Returns `Cell::default()` if
     /// `p` is outside bounds.
+    #[inline]
     pub fn at(&self, p: Point) -> Cell {
-        let q = Point::new(p.x + self.bounds.min.x, p.y + self.bounds.min.y);
-        if !self.bounds.contains(q) {
+        let Some(q) = self.abs(p) else {
             return Cell::default();
-        }
+        };
         let buf = self.buffer.borrow();
-        buf.index(q.x, q.y)
-            .map(|i| buf.cells[i])
-            .unwrap_or_default()
+        buf.index(q.x, q.y).map(|i| buf.get(i)).unwrap_or_default()
     }
 
     /// Set the cell at relative position `p`. No-op if `p` is outside bounds.
+    #[inline]
     pub fn set(&self, p: Point, cell: Cell) {
-        let q = Point::new(p.x + self.bounds.min.x, p.y + self.bounds.min.y);
-        if !self.bounds.contains(q) {
+        let Some(q) = self.abs(p) else {
             return;
-        }
+        };
         let mut buf = self.buffer.borrow_mut();
         if let Some(i) = buf.index(q.x, q.y) {
-            buf.cells[i] = cell;
+            buf.set(i, cell);
         }
     }
 
+    /// Show the terminal cursor at relative position `p`, or hide it with
+    /// `None`. A position outside this grid hides it too. Slices share the
+    /// cursor of their buffer, so a widget drawing in one can place it.
+    ///
+    /// [`AppRunner`](crate::AppRunner) hides the cursor before each
+    /// [`Model::draw`](crate::Model::draw): a model shows it by setting it
+    /// on every draw.
+    pub fn set_cursor(&self, p: Option<Point>) {
+        self.buffer.borrow_mut().cursor = p.and_then(|p| self.abs(p));
+    }
+
+    /// Where the terminal cursor is shown, relative to this grid, if it is
+    /// shown inside it.
+    pub fn cursor(&self) -> Option<Point> {
+        let c = self.buffer.borrow().cursor?;
+        self.bounds.contains(c).then(|| c - self.bounds.min)
+    }
+
     /// Fill every cell in the grid with `cell`.
     pub fn fill(&self, cell: Cell) {
-        let mut buf = self.buffer.borrow_mut();
-        for abs_p in self.bounds.iter() {
-            if let Some(i) = buf.index(abs_p.x, abs_p.y) {
-                buf.cells[i] = cell;
-            }
-        }
+        self.buffer.borrow_mut().fill(self.bounds, cell);
     }
 
-    /// Apply `f` to every cell in the grid, replacing each with the return
-    /// value. The callback receives **relative** coordinates.
+    /// Apply `f` to every cell in the grid, in row-major order, replacing
+    /// each with the return value. The callback receives **relative**
+    /// coordinates, and may read the grid: cells before the current one
+    /// are already replaced.
     pub fn map_cells<F: Fn(Point, Cell) -> Cell>(&self, f: F) {
-        let mut buf = self.buffer.borrow_mut();
-        let min = self.bounds.min;
-        for abs_p in self.bounds.iter() {
-            if let Some(i) = buf.index(abs_p.x, abs_p.y) {
-                let rel = Point::new(abs_p.x - min.x, abs_p.y - min.y);
-                buf.cells[i] = f(rel, buf.cells[i]);
-            }
+        for p in self.points() {
+            let cell = f(p, self.at(p));
+            self.set(p, cell);
         }
     }
 
     /// Copy cells from `src` into `self`, aligning origins. Returns the
-    /// size actually copied (min of both grids on each axis).
+    /// size actually copied (min of both grids on each axis). The grids
+    /// may be overlapping views of the same buffer.
     pub fn copy_from(&self, src: &Grid) -> Point {
         let sw = src.bounds.width().min(self.bounds.width());
         let sh = src.bounds.height().min(self.bounds.height());
-        if Rc::ptr_eq(&self.buffer, &src.buffer) && self.bounds == src.bounds {
+        if Rc::ptr_eq(&self.buffer, &src.buffer) {
+            if self.bounds.min != src.bounds.min {
+                // Both views borrow the one buffer: read everything first.
+                let copied = Range::new(0, 0, sw, sh);
+                let cells: Vec<Cell> = copied.iter().map(|p| src.at(p)).collect();
+                for (p, cell) in copied.iter().zip(cells) {
+                    self.set(p, cell);
+                }
+            }
             return Point::new(sw, sh);
         }
         let src_buf = src.buffer.borrow();
@@ -194,14 +521,15 @@ impl Grid {
                 let dp = Point::new(self.bounds.min.x + dx, self.bounds.min.y + dy);
                 if let (Some(si), Some(di)) = (src_buf.index(sp.x, sp.y), dst_buf.index(dp.x, dp.y))
                 {
-                    dst_buf.cells[di] = src_buf.cells[si];
+                    dst_buf.set(di, src_buf.get(si));
                 }
             }
         }
         Point::new(sw, sh)
     }
 
-    /// Resize the grid to the given dimensions.
+    /// Resize the grid to the given dimensions. A width or height of zero
+    /// or less makes the grid empty.
     ///
     /// Creates a new backing buffer of the requested size, copies cells from
     /// the old grid that fall within the overlapping region, and fills any
@@ -249,8 +577,9 @@ impl Grid {
             for dy in 0..copy_h {
                 let src_start = (old_min_y + dy) * old_width + old_min_x;
                 let dst_start = dy * new_w;
-                new_buf.cells[dst_start..dst_start + copy_w]
-                    .copy_from_slice(&old_buf.cells[src_start..src_start + copy_w]);
+                for dx in 0..copy_w {
+                    new_buf.set(dst_start + dx, old_buf.get(src_start + dx));
+                }
             }
         }
 
@@ -258,6 +587,81 @@ impl Grid {
         self.bounds = Range::new(0, 0, width, height);
     }
 
+    /// Fill the relative range `rg` (clipped to the grid) with `fill`.
+    pub fn clear_range(&self, rg: Range, fill: Cell) {
+        self.slice(rg).fill(fill);
+    }
+
+    /// Fill relative row `y` with `fill`.
+    pub fn clear_line(&self, y: i32, fill: Cell) {
+        self.clear_range(Range::new(0, y, self.width(), y.saturating_add(1)), fill);
+    }
+
+    /// Move the rows of the relative range `rg` up by `n`, discarding the
+    /// top `n` rows and filling the `n` exposed bottom rows with `fill`.
+    /// Cells outside `rg` are untouched. Does nothing if `n <= 0`.
+    ///
+    /// The result is an ordinary cell change as far as
+    /// [`compute_frame`] is concerned; drivers may recognize it as a
+    /// scroll on their own.
+    pub fn scroll_up(&self, rg: Range, n: i32, fill: Cell) {
+        if n > 0 {
+            self.scroll_rows(rg, n, fill);
+        }
+    }
+
+    /// Like [`scroll_up`](Self::scroll_up), moving rows down and filling
+    /// the exposed top rows.
+    pub fn scroll_down(&self, rg: Range, n: i32, fill: Cell) {
+        if n > 0 {
+            self.scroll_rows(rg, -n, fill);
+        }
+    }
+
+    /// Shift the rows of `rg` by `dy` within the range: up for positive
+    /// `dy`, down for negative.
+    fn scroll_rows(&self, rg: Range, dy: i32, fill: Cell) {
+        let rg = rg.intersect(self.range_());
+        let n = dy.abs();
+        if n == 0 || rg.is_empty() {
+            return;
+        }
+        if n >= rg.height() {
+            self.clear_range(rg, fill);
+            return;
+        }
+        let abs = rg.add(self.bounds.min);
+        let w = abs.width() as usize;
+        {
+            let mut buf = self.buffer.borrow_mut();
+            let mut copy_row = |y: i32| {
+                if let (Some(si), Some(di)) =
+                    (buf.index(abs.min.x, y + dy), buf.index(abs.min.x, y))
+                {
+                    buf.copy_within(si..si + w, di);
+                }
+            };
+            // Copy in the direction that never reads an overwritten row.
+            if dy > 0 {
+                (abs.min.y..abs.max.y - n).for_each(&mut copy_row);
+            } else {
+                (abs.min.y + n..abs.max.y).rev().for_each(&mut copy_row);
+            }
+        }
+        let exposed = if dy > 0 {
+            Range::new(rg.min.x, rg.max.y - n, rg.max.x, rg.max.y)
+        } else {
+            Range::new(rg.min.x, rg.min.y, rg.max.x, rg.min.y + n)
+        };
+        self.clear_range(exposed, fill);
+    }
+
+    /// Approximate memory used by the backing buffer, in bytes, shared by
+    /// all views of it.
+    pub fn buffer_memory(&self) -> usize {
+        self.buffer.borrow().memory_size()
+    }
+
     /// Row-major iterator over `(Point, Cell)` pairs with **relative**
     /// coordinates.
     pub fn iter(&self) -> GridIter<'_> {
@@ -293,7 +697,7 @@ impl fmt::Display for Grid {
                 let abs_x = self.bounds.min.x + x;
                 let abs_y = self.bounds.min.y + y;
                 if let Some(i) = buf.index(abs_x, abs_y) {
-                    write!(f, "{}", buf.cells[i].ch)?;
+                    write!(f, "{}", buf.ch(i))?;
                 }
             }
             writeln!(f)?;
@@ -340,14 +744,92 @@ pub struct FrameCell {
 }
 
 /// A set of cell changes (a diff frame).
+///
+/// With the `serde` feature, frames serialize each distinct style once,
+/// with cells referring to them by index.
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "FrameRepr", try_from = "FrameRepr"))]
 pub struct Frame {
     pub cells: Vec<FrameCell>,
     pub width: i32,
     pub height: i32,
     /// Milliseconds since recording start (used for replay timing).
     pub time_ms: u64,
+    /// Where the terminal cursor is shown once the frame is drawn, or
+    /// `None` to hide it. Unlike the cells, this is not a change: every
+    /// frame has it. Drivers without a text cursor ignore it.
+    pub cursor: Option<Point>,
+}
+
+/// Serialized form of a [`Frame`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FrameRepr {
+    width: i32,
+    height: i32,
+    time_ms: u64,
+    styles: Vec<Style>,
+    /// Position, character and style index of each cell.
+    cells: Vec<(Point, char, u32)>,
+    #[serde(default)]
+    cursor: Option<Point>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Frame> for FrameRepr {
+    fn from(frame: Frame) -> Self {
+        let mut styles = Vec::new();
+        let mut ids = std::collections::HashMap::new();
+        let cells = frame
+            .cells
+            .iter()
+            .map(|fc| {
+                let id = *ids.entry(fc.cell.style).or_insert_with(|| {
+                    styles.push(fc.cell.style);
+                    styles.len() as u32 - 1
+                });
+                (fc.pos, fc.cell.ch, id)
+            })
+            .collect();
+        Self {
+            width: frame.width,
+            height: frame.height,
+            time_ms: frame.time_ms,
+            styles,
+            cells,
+            cursor: frame.cursor,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<FrameRepr> for Frame {
+    type Error = String;
+
+    fn try_from(repr: FrameRepr) -> Result<Self, Self::Error> {
+        let cells = repr
+            .cells
+            .into_iter()
+            .map(|(pos, ch, id)| {
+                let style = *repr
+                    .styles
+                    .get(id as usize)
+                    .ok_or_else(|| format!("unknown style index {id}"))?;
+                Ok(FrameCell {
+                    cell: Cell { ch, style },
+                    pos,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            cells,
+            width: repr.width,
+            height: repr.height,
+            time_ms: repr.time_ms,
+            cursor: repr.cursor,
+        })
+    }
 }
 
 /// Compute the difference between two same-sized grids.
@@ -358,16 +840,28 @@ pub fn compute_frame(prev: &Grid, curr: &Grid) -> Frame {
     let bounds = curr.bounds();
     let min = bounds.min;
     let mut cells = Vec::new();
-    for abs_p in bounds.iter() {
-        // Read using absolute coords from buffer directly.
-        let pc = prev.at(Point::new(abs_p.x - min.x, abs_p.y - min.y));
-        let cc = curr.at(Point::new(abs_p.x - min.x, abs_p.y - min.y));
-        if pc != cc {
-            let rel_p = Point::new(abs_p.x - min.x, abs_p.y - min.y);
-            cells.push(FrameCell {
-                cell: cc,
-                pos: rel_p,
-            });
+    // Styles are compared by value: ids are only meaningful within one
+    // buffer's table.
+    let prev_buf = prev.buffer.borrow();
+    let curr_buf = curr.buffer.borrow();
+    let (pw, ph) = (prev.width(), prev.height());
+    for y in 0..bounds.height() {
+        let Some(row) = curr_buf.index(min.x, min.y + y) else {
+            continue;
+        };
+        let prev_row = prev_buf.index(prev.bounds.min.x, prev.bounds.min.y + y);
+        for x in 0..bounds.width() {
+            let cc = curr_buf.get(row + x as usize);
+            let pc = match prev_row {
+                Some(pr) if x < pw && y < ph => prev_buf.get(pr + x as usize),
+                _ => Cell::default(),
+            };
+            if pc != cc {
+                cells.push(FrameCell {
+                    cell: cc,
+                    pos: Point::new(x, y),
+                });
+            }
         }
     }
     Frame {
@@ -375,6 +869,179 @@ pub fn compute_frame(prev: &Grid, curr: &Grid) -> Frame {
         width: bounds.width(),
         height: bounds.height(),
         time_ms: 0,
+        cursor: curr.cursor(),
+    }
+}
+
+/// Show `frame` on `out`, a row-major buffer of `frame.width *
+/// frame.height` cells, as a driver for a bare framebuffer would. This is
+/// all the driver `no_std` code needs: draw into a [`Grid`], diff it with
+/// [`compute_frame`], and send the returned range to the display. Cells
+/// outside the buffer are skipped.
+///
+/// Returns the smallest range holding every changed cell, or `None` if
+/// there was none.
+///
+/// ```
+/// use gruid_core::grid::{Grid, compute_frame, render_to_slice};
+/// use gruid_core::{Cell, Point, Range};
+///
+/// let (prev, curr) = (Grid::new(16, 4), Grid::new(16, 4));
+/// let mut lcd = vec![Cell::default(); 16 * 4];
+/// curr.set(Point::new(3, 1), Cell::default().with_char('@'));
+/// let dirty = render_to_slice(&compute_frame(&prev, &curr), &mut lcd);
+/// assert_eq!(dirty, Some(Range::new(3, 1, 4, 2)));
+/// assert_eq!(lcd[16 + 3].ch, '@');
+/// prev.copy_from(&curr);
+/// ```
+pub fn render_to_slice(frame: &Frame, out: &mut [Cell]) -> Option<Range> {
+    let mut dirty = Range::default();
+    for fc in &frame.cells {
+        let p = fc.pos;
+        if p.x < 0 || p.y < 0 || p.x >= frame.width {
+            continue;
+        }
+        // Frames may come from a recording: do not trust the positions.
+        let i = (p.y as usize)
+            .checked_mul(frame.width as usize)
+            .and_then(|i| i.checked_add(p.x as usize));
+        let Some(slot) = i.and_then(|i| out.get_mut(i)) else {
+            continue;
+        };
+        *slot = fc.cell;
+        dirty = dirty.union(Range::new(p.x, p.y, p.x + 1, p.y + 1));
+    }
+    (!dirty.is_empty()).then_some(dirty)
+}
+
+// ---------------------------------------------------------------------------
+// OverlayGrid
+// ---------------------------------------------------------------------------
+
+/// Cells drawn over a [`Grid`] for a single frame, such as a hover
+/// highlight or a targeting cursor.
+///
+/// Overlay cells are shown on screen but never enter the grid, so they
+/// leave its diffs and recordings alone, and need not be undone: a cell
+/// the next overlay does not write shows the grid again. See
+/// [`Model::draw_transient`](crate::app::Model::draw_transient).
+///
+/// Positions are relative to the grid; writes outside it are ignored.
+#[derive(Debug, Clone)]
+pub struct OverlayGrid {
+    base: Grid,
+    cells: Vec<FrameCell>,
+    index: BTreeMap<Point, usize>,
+}
+
+impl OverlayGrid {
+    /// Create an empty overlay over `base`.
+    pub fn new(base: Grid) -> Self {
+        Self {
+            base,
+            cells: Vec::new(),
+            index: BTreeMap::new(),
+        }
+    }
+
+    /// Size of the underlying grid.
+    pub fn size(&self) -> Point {
+        self.base.size()
+    }
+
+    /// The grid cell under `p`, as drawn by [`Model::draw`](crate::app::Model::draw).
+    pub fn base(&self, p: Point) -> Cell {
+        self.base.at(p)
+    }
+
+    /// Draw `cell` at `p`, replacing any overlay cell there.
+    pub fn set(&mut self, p: Point, cell: Cell) {
+        if !self.base.contains(p) {
+            return;
+        }
+        match self.index.get(&p) {
+            Some(&i) => self.cells[i].cell = cell,
+            None => {
+                self.index.insert(p, self.cells.len());
+                self.cells.push(FrameCell { cell, pos: p });
+            }
+        }
+    }
+
+    /// The overlay cell at `p`, if any.
+    pub fn at(&self, p: Point) -> Option<Cell> {
+        self.index.get(&p).map(|&i| self.cells[i].cell)
+    }
+
+    /// Number of cells written.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Whether no cell was written.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Remove every overlay cell.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.index.clear();
+    }
+
+    /// Iterate the overlay cells, in the order they were first written.
+    pub fn iter(&self) -> impl Iterator<Item = &FrameCell> {
+        self.cells.iter()
+    }
+}
+
+/// Compose an overlay over `frame`, a diff of `grid`, for display.
+///
+/// Overlay cells replace the frame's; those outside it are added when they
+/// differ from what is on screen. Cells of `prev`, the overlay shown last,
+/// that `next` no longer covers are restored from `grid`.
+pub fn compose_overlay(
+    frame: &Frame,
+    grid: &Grid,
+    prev: &OverlayGrid,
+    next: &OverlayGrid,
+) -> Frame {
+    if prev.is_empty() && next.is_empty() {
+        return frame.clone();
+    }
+    let mut in_frame = BTreeSet::new();
+    let mut cells = Vec::with_capacity(frame.cells.len() + next.len());
+    for fc in &frame.cells {
+        in_frame.insert(fc.pos);
+        cells.push(FrameCell {
+            cell: next.at(fc.pos).unwrap_or(fc.cell),
+            pos: fc.pos,
+        });
+    }
+    for fc in next.iter() {
+        if in_frame.contains(&fc.pos) {
+            continue;
+        }
+        let shown = prev.at(fc.pos).unwrap_or_else(|| grid.at(fc.pos));
+        if shown != fc.cell {
+            cells.push(fc.clone());
+        }
+    }
+    for fc in prev.iter() {
+        if in_frame.contains(&fc.pos) || next.at(fc.pos).is_some() {
+            continue;
+        }
+        let cell = grid.at(fc.pos);
+        if cell != fc.cell {
+            cells.push(FrameCell { cell, pos: fc.pos });
+        }
+    }
+    Frame {
+        cells,
+        width: frame.width,
+        height: frame.height,
+        time_ms: frame.time_ms,
+        cursor: frame.cursor,
     }
 }
 
@@ -382,6 +1049,26 @@ pub fn compute_frame(prev: &Grid, curr: &Grid) -> Frame {
 mod tests {
     use super::*;
 
+    #[test]
+    fn render_to_slice_applies_diff() {
+        let prev = Grid::new(4, 3);
+        let curr = Grid::new(4, 3);
+        let mut out = vec![Cell::default(); 12];
+        assert_eq!(
+            render_to_slice(&compute_frame(&prev, &curr), &mut out),
+            None
+        );
+        curr.set(Point::new(1, 0), Cell::default().with_char('a'));
+        curr.set(Point::new(3, 2), Cell::default().with_char('b'));
+        let dirty = render_to_slice(&compute_frame(&prev, &curr), &mut out);
+        assert_eq!(dirty, Some(Range::new(1, 0, 4, 3)));
+        assert_eq!((out[1].ch, out[11].ch, out[5].ch), ('a', 'b', ' '));
+        // A short buffer keeps what fits.
+        let mut short = vec![Cell::default(); 4];
+        let dirty = render_to_slice(&compute_frame(&prev, &curr), &mut short);
+        assert_eq!(dirty, Some(Range::new(1, 0, 2, 1)));
+    }
+
     #[test]
     fn grid_new_and_at() {
         let g = Grid::new(4, 3);
@@ -437,6 +1124,21 @@ mod tests {
         assert_eq!(s2.at(Point::new(0, 0)).ch, 'Z');
     }
 
+    #[test]
+    fn cursor_relative_to_slices() {
+        let g = Grid::new(10, 10);
+        let s = g.slice(Range::new(2, 3, 7, 8));
+        s.set_cursor(Some(Point::new(1, 1)));
+        assert_eq!(g.cursor(), Some(Point::new(3, 4)));
+        assert_eq!(s.cursor(), Some(Point::new(1, 1)));
+        // Shown outside a slice, the cursor is not in it.
+        g.set_cursor(Some(Point::new(0, 0)));
+        assert_eq!(s.cursor(), None);
+        // Outside the slice it is set on, the cursor is hidden.
+        s.set_cursor(Some(Point::new(5, 0)));
+        assert_eq!(g.cursor(), None);
+    }
+
     #[test]
     fn grid_contains_relative() {
         let g = Grid::new(10, 10);
@@ -621,4 +1323,226 @@ mod tests {
         assert_eq!(pts[0], Point::new(0, 0));
         assert_eq!(pts[5], Point::new(2, 1));
     }
+
+    // -----------------------------------------------------------------------
+    // Scrolling
+    // -----------------------------------------------------------------------
+
+    fn lettered(w: i32, h: i32) -> Grid {
+        let g = Grid::new(w, h);
+        for p in g.points() {
+            let ch = (b'a' + (p.y * w + p.x) as u8) as char;
+            g.set(p, Cell::default().with_char(ch));
+        }
+        g
+    }
+
+    #[test]
+    fn scroll_up_and_down_in_slice() {
+        let g = lettered(4, 5);
+        let dot = Cell::default().with_char('.');
+        // Rows 1..4, columns 1..3 of the full grid.
+        let s = g.slice(Range::new(1, 1, 3, 4));
+        s.scroll_up(s.range_(), 1, dot);
+        assert_eq!(g.to_string(), "abcd\nejkh\ninol\nm..p\nqrst\n");
+        s.scroll_down(s.range_(), 2, dot);
+        assert_eq!(g.to_string(), "abcd\ne..h\ni..l\nmjkp\nqrst\n");
+        // Scrolling by the full height just clears.
+        s.scroll_up(Range::new(0, 0, 2, 3), 5, dot);
+        assert_eq!(g.to_string(), "abcd\ne..h\ni..l\nm..p\nqrst\n");
+        // Ranges are clipped to the slice; n <= 0 is a no-op.
+        let g = lettered(3, 3);
+        g.scroll_up(Range::new(-5, 1, 10, 10), 1, dot);
+        assert_eq!(g.to_string(), "abc\nghi\n...\n");
+        g.scroll_down(g.range_(), 0, dot);
+        assert_eq!(g.to_string(), "abc\nghi\n...\n");
+    }
+
+    #[test]
+    fn clear_line_and_range() {
+        let g = lettered(3, 3);
+        let dot = Cell::default().with_char('.');
+        let s = g.slice(Range::new(1, 0, 3, 3));
+        s.clear_line(1, dot);
+        s.clear_range(Range::new(1, 2, 5, 5), dot);
+        assert_eq!(g.to_string(), "abc\nd..\ngh.\n");
+        // Scrolls show up as ordinary cell changes.
+        let prev = lettered(3, 3);
+        let frame = compute_frame(&prev, &g);
+        assert_eq!(frame.cells.len(), 3);
+    }
+
+    // -----------------------------------------------------------------------
+    // Style interning
+    // -----------------------------------------------------------------------
+
+    fn styled(ch: char, n: u32) -> Cell {
+        Cell::default()
+            .with_char(ch)
+            .with_style(Style::default().with_fg(crate::Color(n)))
+    }
+
+    fn with_style_cap(w: i32, h: i32, cap: usize) -> Grid {
+        Grid {
+            buffer: Rc::new(RefCell::new(GridBuffer::with_style_cap(
+                w as usize, h as usize, cap,
+            ))),
+            bounds: Range::new(0, 0, w, h),
+        }
+    }
+
+    #[test]
+    fn interned_cells_halve_memory() {
+        assert_eq!(std::mem::size_of::<Packed>(), 8);
+        let g = Grid::new(512, 512);
+        for (i, p) in g.points().enumerate() {
+            g.set(p, styled('#', (i % 200) as u32));
+        }
+        // 4 MiB with full styles in every cell.
+        let direct = 512 * 512 * std::mem::size_of::<Cell>();
+        assert_eq!(direct, 4 << 20);
+        assert!(
+            g.buffer_memory() < direct / 2 + 16 * 1024,
+            "{}",
+            g.buffer_memory()
+        );
+    }
+
+    #[test]
+    fn full_table_compacts_then_overflows() {
+        let g = with_style_cap(4, 2, 3);
+        // Default style plus two more fill the table.
+        for x in 0..4 {
+            g.set(Point::new(x, 0), styled('a', x as u32 + 1));
+        }
+        for x in 0..4 {
+            assert_eq!(g.at(Point::new(x, 0)), styled('a', x as u32 + 1));
+        }
+        assert!(!g.buffer.borrow().overflow.is_empty());
+
+        // Overwriting frees styles, which compaction reclaims.
+        g.fill(Cell::default());
+        g.set(Point::new(0, 1), styled('b', 9));
+        g.set(Point::new(1, 1), styled('c', 10));
+        let buf = g.buffer.borrow();
+        assert!(buf.overflow.is_empty());
+        assert_eq!(buf.styles.styles.len(), 3);
+        drop(buf);
+        assert_eq!(g.at(Point::new(1, 1)), styled('c', 10));
+    }
+
+    #[test]
+    fn overflow_cells_survive_grid_operations() {
+        let g = with_style_cap(5, 4, 2);
+        for (i, p) in g.points().enumerate() {
+            g.set(p, styled('x', i as u32));
+        }
+        let expect = |p: Point| styled('x', (p.y * 5 + p.x) as u32);
+        for p in g.points() {
+            assert_eq!(g.at(p), expect(p));
+        }
+
+        let copy = Grid::new(5, 4);
+        copy.copy_from(&g);
+        assert!(compute_frame(&g, &copy).cells.is_empty());
+
+        g.scroll_up(g.range_(), 1, Cell::default());
+        for p in g.points() {
+            let want = if p.y < 3 {
+                expect(p.shift(0, 1))
+            } else {
+                Cell::default()
+            };
+            assert_eq!(g.at(p), want, "{p:?}");
+        }
+        let frame = compute_frame(&copy, &g);
+        assert_eq!(frame.cells.len(), 20);
+
+        let mut g = g;
+        g.resize(6, 6);
+        assert_eq!(g.at(Point::new(4, 2)), expect(Point::new(4, 3)));
+    }
+
+    /// Apply random operations to a grid and to a plain vector of cells,
+    /// checking they agree.
+    #[test]
+    fn random_edits_match_reference() {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % n) as i32
+        };
+        for cap in [3, 40, MAX_STYLES] {
+            let (w, h) = (9, 7);
+            let g = with_style_cap(w, h, cap);
+            let prev = Grid::new(w, h);
+            let mut reference = vec![Cell::default(); (w * h) as usize];
+            for round in 0..300 {
+                let cell = styled((b'a' + next(26) as u8) as char, next(60) as u32);
+                match next(4) {
+                    0 | 1 => {
+                        let p = Point::new(next(w as u64), next(h as u64));
+                        g.set(p, cell);
+                        reference[(p.y * w + p.x) as usize] = cell;
+                    }
+                    2 => {
+                        let rg = Range::new(next(9), next(7), next(10), next(8));
+                        g.clear_range(rg, cell);
+                        for p in rg.intersect(g.range_()).iter() {
+                            reference[(p.y * w + p.x) as usize] = cell;
+                        }
+                    }
+                    _ => {
+                        g.map_cells(|p, c| if (p.x + p.y) % 3 == 0 { cell } else { c });
+                        for p in g.points().filter(|p| (p.x + p.y) % 3 == 0) {
+                            reference[(p.y * w + p.x) as usize] = cell;
+                        }
+                    }
+                }
+                for p in g.points() {
+                    assert_eq!(
+                        g.at(p),
+                        reference[(p.y * w + p.x) as usize],
+                        "{cap} {round}"
+                    );
+                }
+                let frame = compute_frame(&prev, &g);
+                let changed: Vec<Point> =
+                    prev.points().filter(|&p| prev.at(p) != g.at(p)).collect();
+                assert_eq!(
+                    frame.cells.iter().map(|fc| fc.pos).collect::<Vec<_>>(),
+                    changed
+                );
+                prev.copy_from(&g);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_frame_stores_styles_once() {
+        let red = Style::default().with_fg(crate::Color::from_rgb(255, 0, 0));
+        let frame = Frame {
+            cells: (0..10)
+                .map(|x| FrameCell {
+                    cell: Cell::default().with_char('#').with_style(red),
+                    pos: Point::new(x, 0),
+                })
+                .collect(),
+            width: 10,
+            height: 1,
+            time_ms: 7,
+            cursor: None,
+        };
+        let json = serde_json::to_string(&frame).unwrap();
+        assert_eq!(json.matches("16711680").count(), 1, "{json}");
+        let back: Frame = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.cells, frame.cells);
+        assert_eq!((back.width, back.height, back.time_ms), (10, 1, 7));
+
+        let bad = json.replace(",0]", ",3]");
+        assert!(serde_json::from_str::<Frame>(&bad).is_err());
+    }
 }
@@ -3,20 +3,48 @@
 //! This crate provides the foundational types used across the *gruid*
 //! ecosystem: geometry primitives, styled cells, a shared-buffer grid, input
 //! events, and the Elm-architecture application loop.
+//!
+//! Without the default `std` feature, the crate is `no_std` and needs only
+//! an allocator: the geometry, cell, style, grid and message types remain,
+//! while the application loop, recordings and clipboard go. Embedded code
+//! draws into a [`Grid`] itself and shows the [`Frame`](grid::Frame) diffs
+//! with [`render_to_slice`](grid::render_to_slice).
+
+// Tests use std either way; `cargo build --no-default-features` checks
+// the library alone.
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
 pub mod app;
+pub mod caps;
 pub mod cell;
+#[cfg(feature = "std")]
+pub mod clipboard;
+#[cfg(feature = "test-utils")]
+pub mod conformance;
 pub mod geom;
 pub mod grid;
 pub mod messages;
+#[cfg(feature = "std")]
 pub mod recording;
+pub mod seed;
 pub mod style;
 pub mod tiles;
 
-pub use app::{App, AppConfig, AppRunner, Cmd, Driver, Effect, EventLoopDriver, Model};
+#[cfg(feature = "std")]
+pub use app::{
+    App, AppConfig, AppRunner, Cmd, Driver, Effect, EffectHandler, EventLoopDriver, Job, Model,
+    Progress, RecordEvent, RunnerStats, SetFullscreen, SetWindowTitle, Spawner, SubPoll,
+    Subscription, TurnBudget,
+};
+pub use caps::{ColorDepth, DriverCaps, Platform};
 pub use cell::Cell;
-pub use geom::{Point, Range};
-pub use grid::Grid;
+#[cfg(feature = "std")]
+pub use clipboard::ClipboardProvider;
+pub use geom::{Direction, Point, Range};
+pub use grid::{Grid, OverlayGrid};
 pub use messages::*;
 pub use style::{AttrMask, Color, Style};
 pub use tiles::TileManager;
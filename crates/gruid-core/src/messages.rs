@@ -1,11 +1,51 @@
-//! Input events: [`Msg`], [`Key`], [`MouseAction`], [`ModMask`].
+//! Input events: [`Msg`], [`Key`], [`MouseAction`], [`ModMask`],
+//! [`SystemTheme`], [`Visibility`], [`Seed`], [`CustomMsg`].
 
-use std::any::Any;
-use std::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc as Shared;
+use alloc::string::String;
+use core::any::{Any, TypeId};
+#[cfg(feature = "std")]
+use std::sync::Arc as Shared;
+#[cfg(feature = "std")]
 use std::time::Instant;
 
+use crate::caps::DriverCaps;
 use crate::geom::Point;
 
+// ---------------------------------------------------------------------------
+// Time
+// ---------------------------------------------------------------------------
+
+/// When an event happened, in milliseconds since a start the embedder
+/// picks. Without `std` there is no clock to read, so the constructors of
+/// [`Msg`] leave it at zero; embedded code sets it when it knows better.
+#[cfg(not(feature = "std"))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(pub u64);
+
+/// The time to stamp new messages with.
+#[cfg(feature = "std")]
+pub(crate) fn now() -> Instant {
+    Instant::now()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn now() -> Instant {
+    Instant::default()
+}
+
+/// Seconds shown for `time` by [`Msg`]'s `Display`: its age with `std`.
+#[cfg(feature = "std")]
+fn secs(time: &Instant) -> u64 {
+    time.elapsed().as_secs()
+}
+
+#[cfg(not(feature = "std"))]
+fn secs(time: &Instant) -> u64 {
+    time.0 / 1000
+}
+
 // ---------------------------------------------------------------------------
 // Key
 // ---------------------------------------------------------------------------
@@ -29,12 +69,14 @@ pub enum Key {
     PageUp,
     PageDown,
     Insert,
+    /// A function key: `F(1)` is F1.
+    F(u8),
     /// A printable character.
     Char(char),
 }
 
-impl std::fmt::Display for Key {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Key {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::ArrowUp => write!(f, "ArrowUp"),
             Self::ArrowDown => write!(f, "ArrowDown"),
@@ -51,6 +93,7 @@ impl std::fmt::Display for Key {
             Self::PageUp => write!(f, "PageUp"),
             Self::PageDown => write!(f, "PageDown"),
             Self::Insert => write!(f, "Insert"),
+            Self::F(n) => write!(f, "F{}", n),
             Self::Char(c) => write!(f, "Char({})", c),
         }
     }
@@ -65,13 +108,13 @@ impl std::fmt::Display for Key {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModMask(pub u8);
 
-impl std::fmt::Display for ModMask {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ModMask {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if self.is_empty() {
             return write!(f, "None");
         }
         let mut first = true;
-        let mut addmod = |name: &str| -> std::fmt::Result {
+        let mut addmod = |name: &str| -> core::fmt::Result {
             if !first {
                 write!(f, "+")?;
             }
@@ -113,7 +156,7 @@ impl ModMask {
     }
 }
 
-impl std::ops::BitOr for ModMask {
+impl core::ops::BitOr for ModMask {
     type Output = Self;
     #[inline]
     fn bitor(self, rhs: Self) -> Self {
@@ -121,7 +164,7 @@ impl std::ops::BitOr for ModMask {
     }
 }
 
-impl std::ops::BitAnd for ModMask {
+impl core::ops::BitAnd for ModMask {
     type Output = Self;
     #[inline]
     fn bitand(self, rhs: Self) -> Self {
@@ -134,6 +177,16 @@ impl std::ops::BitAnd for ModMask {
 // ---------------------------------------------------------------------------
 
 /// A mouse action.
+///
+/// Drivers only report positions inside the grid: events over window
+/// padding, letterboxing or terminal cells beyond the grid are dropped
+/// (see [`MouseTracker`]). When the pointer leaves the grid a single
+/// [`Leave`](Self::Leave) is sent, and no [`Move`](Self::Move) follows until
+/// the pointer comes back with an [`Enter`](Self::Enter).
+///
+/// Motion with a button held is reported as a drag
+/// ([`MainDrag`](Self::MainDrag) and so on) instead of `Move`; code that
+/// only follows the pointer can test [`is_move`](Self::is_move).
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseAction {
@@ -147,12 +200,50 @@ pub enum MouseAction {
     WheelDown,
     /// Button released.
     Release,
-    /// Mouse moved (no button state change).
+    /// Mouse moved to another cell (no button state change).
     Move,
+    /// The pointer entered the grid at `pos`. Always followed by a `Move`
+    /// (or a drag, with a button held) to the same cell.
+    Enter,
+    /// The pointer left the grid; `pos` is the last cell it was over.
+    Leave,
+    /// Mouse moved to another cell with the primary button held.
+    MainDrag,
+    /// Mouse moved to another cell with the middle button held.
+    AuxiliaryDrag,
+    /// Mouse moved to another cell with the secondary button held.
+    SecondaryDrag,
+    /// The second of two quick `Main` presses on the same cell. It follows
+    /// that second `Main`, and is only sent when the app asks for double
+    /// clicks (see [`AppRunner::set_double_click`]).
+    ///
+    /// [`AppRunner::set_double_click`]: crate::app::AppRunner::set_double_click
+    DoubleMain,
 }
 
-impl std::fmt::Display for MouseAction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl MouseAction {
+    /// The drag made with a button: `MainDrag` for `Main`, and so on.
+    /// `None` for actions that are not button presses.
+    pub fn drag(self) -> Option<MouseAction> {
+        match self {
+            Self::Main => Some(Self::MainDrag),
+            Self::Auxiliary => Some(Self::AuxiliaryDrag),
+            Self::Secondary => Some(Self::SecondaryDrag),
+            _ => None,
+        }
+    }
+
+    /// Whether the pointer moved to another cell: `Move` or a drag.
+    pub fn is_move(self) -> bool {
+        matches!(
+            self,
+            Self::Move | Self::MainDrag | Self::AuxiliaryDrag | Self::SecondaryDrag
+        )
+    }
+}
+
+impl core::fmt::Display for MouseAction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Main => write!(f, "Main"),
             Self::Auxiliary => write!(f, "Auxiliary"),
@@ -161,10 +252,294 @@ impl std::fmt::Display for MouseAction {
             Self::WheelDown => write!(f, "WheelDown"),
             Self::Release => write!(f, "Release"),
             Self::Move => write!(f, "Move"),
+            Self::Enter => write!(f, "Enter"),
+            Self::Leave => write!(f, "Leave"),
+            Self::MainDrag => write!(f, "MainDrag"),
+            Self::AuxiliaryDrag => write!(f, "AuxiliaryDrag"),
+            Self::SecondaryDrag => write!(f, "SecondaryDrag"),
+            Self::DoubleMain => write!(f, "DoubleMain"),
         }
     }
 }
 
+// ---------------------------------------------------------------------------
+// MouseTracker
+// ---------------------------------------------------------------------------
+
+/// Pointer state shared by drivers, turning raw cell positions into mouse
+/// messages that follow the [`MouseAction`] invariants.
+///
+/// Drivers convert pixels (or terminal cells) to cell coordinates without
+/// clamping and feed them to [`moved`](Self::moved); window-level leave or
+/// focus-loss events go to [`left`](Self::left).
+#[derive(Clone, Debug, Default)]
+pub struct MouseTracker {
+    width: i32,
+    height: i32,
+    inside: bool,
+    last: Point,
+}
+
+impl MouseTracker {
+    /// A tracker for a `width` × `height` grid, with the pointer outside.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            ..Self::default()
+        }
+    }
+
+    /// Update the grid size after a resize.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Whether the pointer is currently over the grid.
+    pub fn is_inside(&self) -> bool {
+        self.inside
+    }
+
+    /// The last cell the pointer was over.
+    pub fn last_pos(&self) -> Point {
+        self.last
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && p.x < self.width && p.y < self.height
+    }
+
+    /// The pointer is now over cell `pos`, which may lie outside the grid.
+    ///
+    /// Yields `Enter` then `Move` when the pointer comes onto the grid,
+    /// `Move` when it changes cell, `Leave` when it moves off the grid, and
+    /// nothing otherwise.
+    pub fn moved(&mut self, pos: Point, modifiers: ModMask) -> impl Iterator<Item = Msg> + use<> {
+        self.dragged(pos, None, modifiers)
+    }
+
+    /// Like [`moved`](Self::moved), with the button the driver knows to be
+    /// `held`: motion is then reported as that button's drag instead of
+    /// `Move`.
+    pub fn dragged(
+        &mut self,
+        pos: Point,
+        held: Option<MouseAction>,
+        modifiers: ModMask,
+    ) -> impl Iterator<Item = Msg> + use<> {
+        let motion = held
+            .and_then(MouseAction::drag)
+            .unwrap_or(MouseAction::Move);
+        let mut out = [None, None];
+        if !self.contains(pos) {
+            out[0] = self.left(modifiers);
+        } else if !self.inside {
+            self.inside = true;
+            self.last = pos;
+            out[0] = Some(mouse(MouseAction::Enter, pos, modifiers));
+            out[1] = Some(mouse(motion, pos, modifiers));
+        } else if pos != self.last {
+            self.last = pos;
+            out[0] = Some(mouse(motion, pos, modifiers));
+        }
+        out.into_iter().flatten()
+    }
+
+    /// The pointer left the window, canvas or terminal. Yields `Leave` at
+    /// the last cell if the pointer was over the grid.
+    pub fn left(&mut self, modifiers: ModMask) -> Option<Msg> {
+        if !self.inside {
+            return None;
+        }
+        self.inside = false;
+        Some(mouse(MouseAction::Leave, self.last, modifiers))
+    }
+
+    /// A button or wheel `action` at `pos`, or at the pointer's last cell
+    /// when the platform does not report one.
+    ///
+    /// Events outside the grid are dropped, except `Release`, which is
+    /// delivered at the last cell so that drags always end.
+    pub fn button(
+        &self,
+        action: MouseAction,
+        pos: Option<Point>,
+        modifiers: ModMask,
+    ) -> Option<Msg> {
+        let inside = match pos {
+            Some(p) => self.contains(p),
+            None => self.inside,
+        };
+        match pos {
+            Some(p) if inside => Some(mouse(action, p, modifiers)),
+            None if inside => Some(mouse(action, self.last, modifiers)),
+            _ if action == MouseAction::Release => Some(mouse(action, self.last, modifiers)),
+            _ => None,
+        }
+    }
+}
+
+fn mouse(action: MouseAction, pos: Point, modifiers: ModMask) -> Msg {
+    Msg::Mouse {
+        action,
+        pos,
+        modifiers,
+        time: now(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SystemTheme
+// ---------------------------------------------------------------------------
+
+/// Whether the system, browser or terminal uses a dark or light colour
+/// scheme.
+///
+/// Drivers that can tell send it as a [`Msg::Custom`] right after
+/// [`Msg::Init`] and the capabilities, and again whenever it changes.
+/// Models that do not care can ignore it; others read it with
+/// [`Msg::system_theme`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SystemTheme {
+    Dark,
+    Light,
+}
+
+// ---------------------------------------------------------------------------
+// Visibility
+// ---------------------------------------------------------------------------
+
+/// Whether the window can be seen: it is hidden while minimized or fully
+/// covered by other windows.
+///
+/// Graphical drivers stop rendering while their window is hidden. Those
+/// configured to tell the model send this as a [`Msg::Custom`] when it
+/// changes, so a game can pause; read it with [`Msg::visibility`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Visibility {
+    Visible,
+    Hidden,
+}
+
+// ---------------------------------------------------------------------------
+// Seed
+// ---------------------------------------------------------------------------
+
+/// The master seed of the run, sent as a [`Msg::Custom`] right before
+/// [`Msg::Init`] so the model can build its random generators from it.
+///
+/// Read it with [`Msg::seed`]; derive one seed per purpose from it with
+/// [`sub_seed`](crate::seed::sub_seed).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Seed(pub u64);
+
+// ---------------------------------------------------------------------------
+// CustomMsg
+// ---------------------------------------------------------------------------
+
+/// The payload of a [`Msg::Custom`]: a value of any `Send + Sync` type,
+/// with the name of its type for debugging.
+///
+/// Match it in place of downcasting in a catch-all arm:
+///
+/// ```
+/// use gruid_core::Msg;
+///
+/// struct Tick(u32);
+///
+/// let msg = Msg::custom(Tick(3));
+/// match msg {
+///     Msg::Custom(c) if c.is::<Tick>() => assert_eq!(c.take::<Tick>().unwrap().0, 3),
+///     _ => unreachable!(),
+/// }
+/// ```
+///
+/// The value is shared through an `Arc`, or an `Rc` without `std` since
+/// not every embedded target has atomics, so cloning a message is cheap.
+#[derive(Clone)]
+pub struct CustomMsg {
+    value: Shared<dyn Any + Send + Sync>,
+    type_name: &'static str,
+}
+
+impl CustomMsg {
+    /// Wrap `value`.
+    pub fn new<T: Any + Send + Sync>(value: T) -> Self {
+        Self {
+            value: Shared::new(value),
+            type_name: core::any::type_name::<T>(),
+        }
+    }
+
+    /// Whether the value is a `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.value.is::<T>()
+    }
+
+    /// The value, if it is a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref::<T>()
+    }
+
+    /// The value, if it is a `T`, by value. Gives the payload back if the
+    /// value is of another type, or still shared with a clone of the
+    /// message.
+    pub fn take<T: Any + Send + Sync>(self) -> Result<T, Self> {
+        let type_name = self.type_name;
+        match downcast::<T>(self.value) {
+            Ok(value) => Shared::try_unwrap(value).map_err(|value| Self { value, type_name }),
+            Err(value) => Err(Self { value, type_name }),
+        }
+    }
+
+    /// [`TypeId`] of the value.
+    pub fn type_id(&self) -> TypeId {
+        (*self.value).type_id()
+    }
+
+    /// Name of the type of the value, as given by
+    /// [`core::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The value, for code that dispatches on [`type_id`](Self::type_id).
+    pub fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        &*self.value
+    }
+}
+
+/// `value` as a `T`, if it is one.
+#[cfg(feature = "std")]
+fn downcast<T: Any + Send + Sync>(
+    value: Shared<dyn Any + Send + Sync>,
+) -> Result<Shared<T>, Shared<dyn Any + Send + Sync>> {
+    value.downcast()
+}
+
+#[cfg(not(feature = "std"))]
+fn downcast<T: Any + Send + Sync>(
+    value: Shared<dyn Any + Send + Sync>,
+) -> Result<Shared<T>, Shared<dyn Any + Send + Sync>> {
+    if !value.is::<T>() {
+        return Err(value);
+    }
+    // `Rc` only has `downcast` for `dyn Any`: drop the auto traits, which
+    // `T` has anyway.
+    let value: Shared<dyn Any> = value;
+    Ok(value.downcast().unwrap_or_else(|_| unreachable!()))
+}
+
+impl core::fmt::Debug for CustomMsg {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.type_name)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Msg
 // ---------------------------------------------------------------------------
@@ -172,11 +547,20 @@ impl std::fmt::Display for MouseAction {
 /// An input message delivered to the application.
 #[derive(Clone, Debug)]
 pub enum Msg {
-    /// A key was pressed.
+    /// A key was pressed. Held keys repeat it, with `repeat` set when the
+    /// driver can tell (most terminals cannot).
     KeyDown {
         key: Key,
         modifiers: ModMask,
         time: Instant,
+        repeat: bool,
+    },
+    /// A key was released, on drivers whose
+    /// [`DriverCaps::key_release`](crate::DriverCaps::key_release) is set.
+    KeyUp {
+        key: Key,
+        modifiers: ModMask,
+        time: Instant,
     },
     /// A mouse event.
     Mouse {
@@ -185,16 +569,43 @@ pub enum Msg {
         modifiers: ModMask,
         time: Instant,
     },
-    /// The screen / terminal was resized.
+    /// The size of the grid. Sent right after [`Init`](Self::Init), then
+    /// when the screen or terminal is resized. The app loop coalesces a
+    /// burst of resizes: the model sees at most one per frame, always the
+    /// latest size.
     Screen {
         width: i32,
         height: i32,
         time: Instant,
     },
+    /// Text pasted from the clipboard, whole, on drivers that can tell a
+    /// paste from typing. It may hold several lines.
+    Paste(String),
+    /// Time for an animation frame, sent at a steady rate by drivers
+    /// configured with one (`frame_rate` of the winit and wgpu drivers)
+    /// while the window can be seen. Draw the animation as of `time`.
+    Frame { time: Instant },
     /// Sent once when the application starts.
     Init,
+    /// What the driver supports. Sent right after the first
+    /// [`Screen`](Self::Screen), and again if the capabilities change (for
+    /// instance once a terminal's colour depth has been detected).
+    Capabilities(DriverCaps),
+    /// Progress report from an [`Effect::CmdProgress`](crate::app::Effect::CmdProgress)
+    /// command.
+    Progress {
+        /// Completed fraction of the work, in `0.0..=1.0`.
+        fraction: f32,
+        /// Short description of the current step.
+        label: String,
+    },
     /// Request to quit.
     Quit,
+    /// Resume long work, in answer to
+    /// [`Effect::Continue`](crate::app::Effect::Continue). Sent once the
+    /// state left by the previous slice has been drawn, after any input
+    /// received meanwhile.
+    Continue,
     /// User-defined message.
     ///
     /// Go gruid uses `interface{}` for `Msg`, so any type can be a message.
@@ -202,25 +613,41 @@ pub enum Msg {
     /// application-specific messages (timers, animation ticks, async
     /// results, etc.).
     ///
-    /// Downcast with `msg.downcast_ref::<YourType>()`.
-    Custom(Arc<dyn Any + Send + Sync>),
+    /// Create one with [`Msg::custom`]. Read it with
+    /// [`Msg::downcast_ref`], [`Msg::take_custom`] or [`Msg::is_custom`],
+    /// or match on the [`CustomMsg`] payload.
+    Custom(CustomMsg),
 }
 
-impl std::fmt::Display for Msg {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Msg {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Init => write!(f, "Init"),
             Self::Quit => write!(f, "Quit"),
+            Self::Continue => write!(f, "Continue"),
             Self::KeyDown {
                 key,
                 modifiers,
                 time,
+                repeat,
             } => write!(
                 f,
-                "KeyDown {{ key: {}, modifiers: {}, time: {} }}",
+                "KeyDown {{ key: {}, modifiers: {}, time: {}, repeat: {} }}",
                 key,
                 modifiers,
-                time.elapsed().as_secs()
+                secs(time),
+                repeat
+            ),
+            Self::KeyUp {
+                key,
+                modifiers,
+                time,
+            } => write!(
+                f,
+                "KeyUp {{ key: {}, modifiers: {}, time: {} }}",
+                key,
+                modifiers,
+                secs(time)
             ),
             Self::Mouse {
                 action,
@@ -233,7 +660,7 @@ impl std::fmt::Display for Msg {
                 action,
                 pos,
                 modifiers,
-                time.elapsed().as_secs()
+                secs(time)
             ),
             Self::Screen {
                 width,
@@ -244,9 +671,15 @@ impl std::fmt::Display for Msg {
                 "Screen {{ width: {}, height: {}, time: {} }}",
                 width,
                 height,
-                time.elapsed().as_secs()
+                secs(time)
             ),
-            Self::Custom(_) => write!(f, "Custom(..)"),
+            Self::Paste(text) => write!(f, "Paste({:?})", text),
+            Self::Frame { time } => write!(f, "Frame {{ time: {} }}", secs(time)),
+            Self::Capabilities(caps) => write!(f, "Capabilities({:?})", caps),
+            Self::Progress { fraction, label } => {
+                write!(f, "Progress {{ fraction: {}, label: {} }}", fraction, label)
+            }
+            Self::Custom(c) => write!(f, "Custom({})", c.type_name()),
         }
     }
 }
@@ -257,7 +690,8 @@ impl Msg {
         Self::KeyDown {
             key,
             modifiers: ModMask::NONE,
-            time: Instant::now(),
+            time: now(),
+            repeat: false,
         }
     }
 
@@ -266,10 +700,53 @@ impl Msg {
         Self::KeyDown {
             key,
             modifiers,
-            time: Instant::now(),
+            time: now(),
+            repeat: false,
+        }
+    }
+
+    /// Whether this is a `KeyDown` sent again for a held key.
+    pub fn is_repeat(&self) -> bool {
+        matches!(self, Self::KeyDown { repeat: true, .. })
+    }
+
+    /// Convenience: create a `KeyUp` with no modifiers.
+    pub fn key_up(key: Key) -> Self {
+        Self::KeyUp {
+            key,
+            modifiers: ModMask::NONE,
+            time: now(),
         }
     }
 
+    /// When the event happened, for the messages stamped with a time:
+    /// keys, mouse events, screen sizes and animation frames.
+    pub fn time(&self) -> Option<Instant> {
+        match self {
+            Self::KeyDown { time, .. }
+            | Self::KeyUp { time, .. }
+            | Self::Mouse { time, .. }
+            | Self::Screen { time, .. }
+            | Self::Frame { time } => Some(*time),
+            _ => None,
+        }
+    }
+
+    /// The message stamped with `time` instead, if it carries one (see
+    /// [`time`](Self::time)). Drivers that know when an event happened
+    /// better than when they got it use this, as do latency tests.
+    pub fn with_time(mut self, at: Instant) -> Self {
+        match &mut self {
+            Self::KeyDown { time, .. }
+            | Self::KeyUp { time, .. }
+            | Self::Mouse { time, .. }
+            | Self::Screen { time, .. }
+            | Self::Frame { time } => *time = at,
+            _ => {}
+        }
+        self
+    }
+
     /// Create a custom message wrapping any `Send + Sync + 'static` value.
     ///
     /// This is the Rust equivalent of Go gruid's `Msg = interface{}`.
@@ -281,7 +758,7 @@ impl Msg {
     /// let msg = Msg::custom(MyTick(42));
     /// ```
     pub fn custom<T: Any + Send + Sync>(value: T) -> Self {
-        Self::Custom(Arc::new(value))
+        Self::Custom(CustomMsg::new(value))
     }
 
     /// Try to downcast a `Custom` payload to a concrete type.
@@ -296,10 +773,47 @@ impl Msg {
     /// ```
     pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
         match self {
-            Self::Custom(arc) => arc.downcast_ref::<T>(),
+            Self::Custom(c) => c.downcast_ref::<T>(),
             _ => None,
         }
     }
+
+    /// Whether this is a `Custom` message holding a `T`.
+    pub fn is_custom<T: Any>(&self) -> bool {
+        self.downcast_ref::<T>().is_some()
+    }
+
+    /// The `T` of a `Custom` message, by value, or the message back if it
+    /// holds something else or its payload is still shared with a clone.
+    ///
+    /// ```ignore
+    /// let msg = match msg.take_custom::<Loaded>() {
+    ///     Ok(Loaded(level)) => return self.enter(level),
+    ///     Err(msg) => msg,
+    /// };
+    /// ```
+    pub fn take_custom<T: Any + Send + Sync>(self) -> Result<T, Self> {
+        match self {
+            Self::Custom(c) => c.take::<T>().map_err(Self::Custom),
+            msg => Err(msg),
+        }
+    }
+
+    /// The theme reported by a [`SystemTheme`] message, if this is one.
+    pub fn system_theme(&self) -> Option<SystemTheme> {
+        self.downcast_ref::<SystemTheme>().copied()
+    }
+
+    /// The window visibility reported by a [`Visibility`] message, if this
+    /// is one.
+    pub fn visibility(&self) -> Option<Visibility> {
+        self.downcast_ref::<Visibility>().copied()
+    }
+
+    /// The master seed carried by a [`Seed`] message, if this is one.
+    pub fn seed(&self) -> Option<u64> {
+        self.downcast_ref::<Seed>().map(|s| s.0)
+    }
 }
 
 #[cfg(test)]
@@ -331,4 +845,167 @@ mod tests {
             "Ctrl+Alt+Meta+Shift"
         );
     }
+
+    fn actions(msgs: impl Iterator<Item = Msg>) -> Vec<(MouseAction, Point)> {
+        msgs.map(|m| match m {
+            Msg::Mouse { action, pos, .. } => (action, pos),
+            other => panic!("unexpected {other}"),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn tracker_enter_move_leave() {
+        let mut t = MouseTracker::new(10, 5);
+        let m = ModMask::NONE;
+        let p = Point::new;
+        assert_eq!(
+            actions(t.moved(p(2, 2), m)),
+            [(MouseAction::Enter, p(2, 2)), (MouseAction::Move, p(2, 2))]
+        );
+        // Same cell: nothing new.
+        assert!(actions(t.moved(p(2, 2), m)).is_empty());
+        assert_eq!(actions(t.moved(p(3, 2), m)), [(MouseAction::Move, p(3, 2))]);
+        // Off the grid (padding): one Leave at the last valid cell.
+        assert_eq!(
+            actions(t.moved(p(10, 2), m)),
+            [(MouseAction::Leave, p(3, 2))]
+        );
+        assert!(actions(t.moved(p(11, 2), m)).is_empty());
+        assert!(actions(t.moved(p(-1, 0), m)).is_empty());
+        assert!(t.left(m).is_none());
+        assert_eq!(
+            actions(t.moved(p(9, 4), m)),
+            [(MouseAction::Enter, p(9, 4)), (MouseAction::Move, p(9, 4))]
+        );
+        assert_eq!(
+            actions(t.left(m).into_iter()),
+            [(MouseAction::Leave, p(9, 4))]
+        );
+    }
+
+    #[test]
+    fn tracker_never_moves_between_leave_and_enter() {
+        let mut t = MouseTracker::new(4, 4);
+        let path = [(0, 0), (5, 0), (3, 3), (3, -1), (2, 9), (1, 1), (1, 2)];
+        let mut inside = false;
+        for (x, y) in path {
+            for (action, pos) in actions(t.moved(Point::new(x, y), ModMask::NONE)) {
+                match action {
+                    MouseAction::Enter => inside = true,
+                    MouseAction::Leave => inside = false,
+                    MouseAction::Move => assert!(inside, "Move at {pos} after Leave"),
+                    _ => unreachable!(),
+                }
+                assert!(pos.x < 4 && pos.y < 4 && pos.x >= 0 && pos.y >= 0);
+            }
+        }
+    }
+
+    #[test]
+    fn tracker_drags_with_held_button() {
+        let mut t = MouseTracker::new(10, 5);
+        let m = ModMask::NONE;
+        let p = Point::new;
+        let held = Some(MouseAction::Secondary);
+        assert_eq!(
+            actions(t.dragged(p(2, 2), held, m)),
+            [
+                (MouseAction::Enter, p(2, 2)),
+                (MouseAction::SecondaryDrag, p(2, 2))
+            ]
+        );
+        assert_eq!(
+            actions(t.dragged(p(3, 2), Some(MouseAction::Main), m)),
+            [(MouseAction::MainDrag, p(3, 2))]
+        );
+        assert_eq!(
+            actions(t.dragged(p(4, 2), None, m)),
+            [(MouseAction::Move, p(4, 2))]
+        );
+        assert_eq!(
+            actions(t.dragged(p(10, 2), held, m)),
+            [(MouseAction::Leave, p(4, 2))]
+        );
+        assert!(MouseAction::AuxiliaryDrag.is_move() && MouseAction::Move.is_move());
+        assert!(!MouseAction::Main.is_move());
+        assert_eq!(MouseAction::Release.drag(), None);
+    }
+
+    #[test]
+    fn tracker_buttons_outside_are_dropped() {
+        let mut t = MouseTracker::new(4, 4);
+        let m = ModMask::NONE;
+        assert!(t.button(MouseAction::Main, None, m).is_none());
+        assert!(
+            t.button(MouseAction::Main, Some(Point::new(4, 0)), m)
+                .is_none()
+        );
+        t.moved(Point::new(1, 2), m).for_each(drop);
+        assert_eq!(
+            actions(t.button(MouseAction::WheelUp, None, m).into_iter()),
+            [(MouseAction::WheelUp, Point::new(1, 2))]
+        );
+        // Releases outside still end the drag, at the last valid cell.
+        assert_eq!(
+            actions(
+                t.button(MouseAction::Release, Some(Point::new(7, 7)), m)
+                    .into_iter()
+            ),
+            [(MouseAction::Release, Point::new(1, 2))]
+        );
+        t.resize(1, 1);
+        assert!(
+            t.button(MouseAction::Main, Some(Point::new(1, 2)), m)
+                .is_none()
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Loaded(String);
+
+    #[derive(Debug)]
+    struct Tick;
+
+    #[test]
+    fn custom_downcasts() {
+        let msg = Msg::custom(Loaded("cave".into()));
+        assert!(msg.is_custom::<Loaded>());
+        assert!(!msg.is_custom::<Tick>());
+        assert!(!Msg::Init.is_custom::<Loaded>());
+        assert_eq!(msg.downcast_ref(), Some(&Loaded("cave".into())));
+        assert!(msg.downcast_ref::<Tick>().is_none());
+        let Msg::Custom(c) = &msg else { unreachable!() };
+        assert_eq!(c.type_id(), TypeId::of::<Loaded>());
+        assert!(c.type_name().ends_with("::Loaded"));
+    }
+
+    #[test]
+    fn take_custom_moves_the_value_out() {
+        // A miss gives the message back, still holding the value.
+        let msg = Msg::custom(Loaded("cave".into()));
+        let msg = msg.take_custom::<Tick>().unwrap_err();
+        assert_eq!(msg.take_custom::<Loaded>().unwrap(), Loaded("cave".into()));
+        assert!(matches!(Msg::Init.take_custom::<Tick>(), Err(Msg::Init)));
+
+        // A payload shared with a clone cannot be moved out until the
+        // clone is gone.
+        let msg = Msg::custom(Loaded("keep".into()));
+        let clone = msg.clone();
+        let msg = msg.take_custom::<Loaded>().unwrap_err();
+        assert!(msg.is_custom::<Loaded>());
+        drop(clone);
+        assert_eq!(msg.take_custom::<Loaded>().unwrap().0, "keep");
+    }
+
+    #[test]
+    fn custom_messages_print_their_type() {
+        let msg = Msg::custom(Tick);
+        let debug = alloc::format!("{msg:?}");
+        assert!(
+            debug.starts_with("Custom(") && debug.ends_with("::Tick)"),
+            "{debug}"
+        );
+        assert!(alloc::format!("{msg}").ends_with("::Tick)"));
+    }
 }
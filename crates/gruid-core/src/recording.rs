@@ -7,19 +7,83 @@
 //!
 //! ## Wire format
 //!
-//! Each frame is written as:
+//! A stream starts with the magic bytes `GRF2`. Styles are numbered in the
+//! order they first appear and written only once per stream; cells refer
+//! to them by id. Each frame is written as:
 //! ```text
 //! [total_byte_len: u32 LE]
 //! [time_ms: u64 LE]
 //! [width: i32 LE]
 //! [height: i32 LE]
+//! [num_styles: u32 LE]  (bit 31 set: forget all previous styles first;
+//!                        bit 30 set: the frame shows the cursor)
+//! for each new style:
+//!   [fg: u32 LE] [bg: u32 LE] [attrs: u32 LE]
 //! [num_cells: u32 LE]
 //! for each cell:
 //!   [pos.x: i32 LE] [pos.y: i32 LE]
 //!   [ch: u32 LE]  (Unicode scalar value)
+//!   [style: u16 LE]
+//! if the frame shows the cursor:
+//!   [cursor.x: i32 LE] [cursor.y: i32 LE]
+//! ```
+//!
+//! Frames hiding the [cursor](Frame::cursor) are written as before it
+//! existed; decoders predating it fail on frames showing it.
+//!
+//! A stream with [metadata](RecordingMeta) starts with `GRF3` instead,
+//! followed by a length-prefixed metadata record, then frames as above.
+//! Streams without metadata keep the `GRF2` magic, so that older decoders
+//! still read them.
+//! ```text
+//! [meta_byte_len: u32 LE]
+//! [meta_version: u8]  (1)
+//! [app_name: str] [app_version: str]
+//! [has_seed: u8] [seed: u64 LE]
+//! [started_at_unix: u64 LE]
+//! [num_custom: u32 LE]
+//! for each custom tag, sorted by key:
+//!   [key: str] [value: str]
+//! ```
+//! where each `str` is a `u32 LE` byte length followed by UTF-8. A decoder
+//! that cannot parse the record skips it and still decodes the frames.
+//!
+//! Streams without the magic bytes are read in the original format, in
+//! which each cell carries its full style:
+//! ```text
+//! [total_byte_len: u32 LE]
+//! [time_ms: u64 LE] [width: i32 LE] [height: i32 LE] [num_cells: u32 LE]
+//! for each cell:
+//!   [pos.x: i32 LE] [pos.y: i32 LE] [ch: u32 LE]
 //!   [fg: u32 LE] [bg: u32 LE] [attrs: u32 LE]
 //! ```
+//!
+//! ## Events
+//!
+//! [`FrameEncoder::event`] interleaves events with the frames: sounds,
+//! toasts and other effects the frames do not show, each with a tag and
+//! an opaque payload. An event record starts with a zero length, which no
+//! frame has:
+//! ```text
+//! [0: u32 LE]
+//! [event_byte_len: u32 LE]
+//! [time_ms: u64 LE] [tag: u16 LE] [payload: event_byte_len - 10 bytes]
+//! ```
+//! [`FrameDecoder::decode_record`] returns frames and events in stream
+//! order; [`FrameDecoder::decode`] skips events. A stream without events
+//! is unchanged, while decoders predating events stop at the first one
+//! with a "frame too small" error.
+//!
+//! ## Filters
+//!
+//! [`FrameEncoder::with_filter`] transforms or drops frames before they are
+//! written, to hide parts of the screen or make recordings smaller. The
+//! [`filters`] module has ready-made ones. Filtered recordings use the
+//! same format and decode as any other.
+
+pub mod filters;
 
+use std::collections::HashMap;
 use std::io::{self, Read, Write};
 
 use crate::cell::Cell;
@@ -27,30 +91,365 @@ use crate::geom::Point;
 use crate::grid::{Frame, FrameCell};
 use crate::style::{AttrMask, Color, Style};
 
-/// Bytes per serialized cell: pos(8) + ch(4) + fg(4) + bg(4) + attrs(4) = 24
-const CELL_SIZE: usize = 24;
-/// Header size: time_ms(8) + width(4) + height(4) + num_cells(4) = 20
-const HEADER_SIZE: usize = 20;
+/// Magic bytes at the start of a stream with a style table.
+const MAGIC: [u8; 4] = *b"GRF2";
+/// Magic bytes at the start of a stream with a style table and metadata.
+const MAGIC_META: [u8; 4] = *b"GRF3";
+/// Version of the metadata record.
+const META_VERSION: u8 = 1;
+/// Largest metadata record accepted, in bytes.
+const MAX_META_SIZE: usize = 1 << 20;
+/// Bytes per serialized cell: pos(8) + ch(4) + style id(2) = 14
+const CELL_SIZE: usize = 14;
+/// Bytes per serialized style: fg(4) + bg(4) + attrs(4) = 12
+const STYLE_SIZE: usize = 12;
+/// Header size: time_ms(8) + width(4) + height(4) + num_styles(4) +
+/// num_cells(4) = 24
+const HEADER_SIZE: usize = 24;
+/// Number of styles a stream can refer to before its table is reset.
+const MAX_STYLES: usize = 1 << 16;
+/// Flag in `num_styles` clearing the style table.
+const RESET_STYLES: u32 = 1 << 31;
+/// Flag in `num_styles` for a frame showing the cursor.
+const SHOW_CURSOR: u32 = 1 << 30;
+/// Bytes for the cursor position: x(4) + y(4) = 8
+const CURSOR_SIZE: usize = 8;
+/// Event header size: time_ms(8) + tag(2) = 10
+const EVENT_HEADER_SIZE: usize = 10;
+/// Largest event payload accepted, in bytes.
+const MAX_EVENT_PAYLOAD: usize = 1 << 20;
+
+/// Bytes per cell in the original format: pos(8) + ch(4) + fg(4) + bg(4) +
+/// attrs(4) = 24
+const V1_CELL_SIZE: usize = 24;
+/// Header size in the original format: time_ms(8) + width(4) + height(4) +
+/// num_cells(4) = 20
+const V1_HEADER_SIZE: usize = 20;
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn i32_at(data: &[u8], at: usize) -> i32 {
+    i32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn style_at(data: &[u8], at: usize) -> Style {
+    Style {
+        fg: Color(u32_at(data, at)),
+        bg: Color(u32_at(data, at + 4)),
+        attrs: AttrMask(u32_at(data, at + 8)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// RecordingMeta
+// ---------------------------------------------------------------------------
+
+/// Information about the session a recording comes from, written once at
+/// the start of the stream with [`FrameEncoder::with_metadata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordingMeta {
+    /// Name of the application that was recorded.
+    pub app_name: String,
+    /// Version of the application.
+    pub app_version: String,
+    /// Seed of the random number generator, if the game has one.
+    pub seed: Option<u64>,
+    /// When the recording started, in seconds since the Unix epoch.
+    pub started_at_unix: u64,
+    /// Free-form tags: player name, game mode, outcome...
+    pub custom: HashMap<String, String>,
+}
+
+impl RecordingMeta {
+    /// Metadata for the given application, started now.
+    pub fn new(app_name: &str, app_version: &str) -> Self {
+        let started_at_unix = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            app_name: app_name.to_string(),
+            app_version: app_version.to_string(),
+            started_at_unix,
+            ..Self::default()
+        }
+    }
+
+    /// Set the random seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Add a custom tag.
+    pub fn with_tag(mut self, key: &str, value: &str) -> Self {
+        self.custom.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        fn put_str(buf: &mut Vec<u8>, s: &str) {
+            buf.extend((s.len() as u32).to_le_bytes());
+            buf.extend(s.as_bytes());
+        }
+        let mut buf = vec![META_VERSION];
+        put_str(&mut buf, &self.app_name);
+        put_str(&mut buf, &self.app_version);
+        buf.push(self.seed.is_some() as u8);
+        buf.extend(self.seed.unwrap_or(0).to_le_bytes());
+        buf.extend(self.started_at_unix.to_le_bytes());
+        let mut custom: Vec<_> = self.custom.iter().collect();
+        custom.sort();
+        buf.extend((custom.len() as u32).to_le_bytes());
+        for (k, v) in custom {
+            put_str(&mut buf, k);
+            put_str(&mut buf, v);
+        }
+        buf
+    }
+
+    fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let mut r = MetaReader { data, at: 0 };
+        let version = r.take(1)?[0];
+        if version != META_VERSION {
+            return Err(invalid(format!("unknown metadata version {version}")));
+        }
+        let app_name = r.string()?;
+        let app_version = r.string()?;
+        let has_seed = r.take(1)?[0] != 0;
+        let seed = r.u64()?;
+        let started_at_unix = r.u64()?;
+        let n = r.u32()?;
+        let mut custom = HashMap::new();
+        for _ in 0..n {
+            let k = r.string()?;
+            custom.insert(k, r.string()?);
+        }
+        if r.at != data.len() {
+            return Err(invalid("trailing bytes in metadata"));
+        }
+        Ok(Self {
+            app_name,
+            app_version,
+            seed: has_seed.then_some(seed),
+            started_at_unix,
+            custom,
+        })
+    }
+}
+
+/// Bounds-checked reading of a metadata record.
+struct MetaReader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> MetaReader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .at
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("metadata truncated"))?;
+        let bytes = &self.data[self.at..end];
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| invalid("metadata string is not UTF-8"))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Events
+// ---------------------------------------------------------------------------
+
+/// An event recorded with [`FrameEncoder::event`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordedEvent {
+    /// Time of the event, on the same clock as the frames'.
+    pub time_ms: u64,
+    /// What the event is, as numbered by the application.
+    pub tag: u16,
+    /// Data of the event, in a format of the application's choosing.
+    pub payload: Vec<u8>,
+}
+
+/// A record of a stream, as returned by [`FrameDecoder::decode_record`].
+#[derive(Debug, Clone)]
+pub enum Record {
+    Frame(Frame),
+    Event(RecordedEvent),
+}
 
 // ---------------------------------------------------------------------------
 // FrameEncoder
 // ---------------------------------------------------------------------------
 
+/// A transformation applied to frames before encoding, returning `None`
+/// to drop the frame.
+pub type FrameFilter = Box<dyn FnMut(Frame) -> Option<Frame> + Send>;
+
 /// Encodes [`Frame`]s to a byte-oriented writer.
 pub struct FrameEncoder<W: Write> {
     writer: W,
+    started: bool,
+    meta: Option<RecordingMeta>,
+    /// Ids of the styles already written.
+    styles: HashMap<Style, u16>,
+    filters: Vec<FrameFilter>,
 }
 
 impl<W: Write> FrameEncoder<W> {
     /// Wrap a writer.
     pub fn new(writer: W) -> Self {
-        Self { writer }
+        Self {
+            writer,
+            started: false,
+            meta: None,
+            styles: HashMap::new(),
+            filters: Vec::new(),
+        }
+    }
+
+    /// Write `meta` at the start of the stream. Has no effect once a frame
+    /// has been written.
+    pub fn with_metadata(mut self, meta: RecordingMeta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    /// Write the magic bytes and metadata, if not done yet.
+    fn start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        match &self.meta {
+            Some(meta) => {
+                let data = meta.to_bytes();
+                if data.len() > MAX_META_SIZE {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "recording metadata is larger than 1 MiB",
+                    ));
+                }
+                self.writer.write_all(&MAGIC_META)?;
+                self.writer.write_all(&(data.len() as u32).to_le_bytes())?;
+                self.writer.write_all(&data)
+            }
+            None => self.writer.write_all(&MAGIC),
+        }
     }
 
-    /// Write a single frame.
+    /// Add a filter applied to each frame before encoding. Filters run in
+    /// the order they were added, each on the previous one's output; a
+    /// frame any of them drops is not written.
+    pub fn with_filter(
+        mut self,
+        filter: impl FnMut(Frame) -> Option<Frame> + Send + 'static,
+    ) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Write a single frame, after passing it through the filters.
     pub fn encode(&mut self, frame: &Frame) -> io::Result<()> {
+        if self.filters.is_empty() {
+            return self.encode_frame(frame);
+        }
+        let mut frame = frame.clone();
+        for filter in &mut self.filters {
+            match filter(frame) {
+                Some(f) => frame = f,
+                None => return Ok(()),
+            }
+        }
+        self.encode_frame(&frame)
+    }
+
+    fn encode_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        self.start()?;
+
+        match self.number_styles(frame) {
+            Some((new_styles, ids)) => self.write_frame(frame, false, &new_styles, &ids),
+            None => {
+                // Out of ids: start the table over with this frame.
+                self.styles.clear();
+                let (new_styles, ids) = self.number_styles(frame).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "frame has more than 65536 distinct styles",
+                    )
+                })?;
+                self.write_frame(frame, true, &new_styles, &ids)
+            }
+        }
+    }
+
+    /// The style ids of the cells of `frame`, and the styles it introduces,
+    /// or `None` if the ids run out.
+    fn number_styles(&mut self, frame: &Frame) -> Option<(Vec<Style>, Vec<u16>)> {
+        let mut new_styles = Vec::new();
+        let mut ids = Vec::with_capacity(frame.cells.len());
+        for fc in &frame.cells {
+            let style = fc.cell.style;
+            let id = match self.styles.get(&style) {
+                Some(&id) => id,
+                None if self.styles.len() < MAX_STYLES => {
+                    let id = self.styles.len() as u16;
+                    self.styles.insert(style, id);
+                    new_styles.push(style);
+                    id
+                }
+                None => return None,
+            };
+            ids.push(id);
+        }
+        Some((new_styles, ids))
+    }
+
+    fn write_frame(
+        &mut self,
+        frame: &Frame,
+        reset: bool,
+        new_styles: &[Style],
+        ids: &[u16],
+    ) -> io::Result<()> {
         let num_cells = frame.cells.len() as u32;
-        let total_len = (HEADER_SIZE + frame.cells.len() * CELL_SIZE) as u32;
+        let cursor_len = if frame.cursor.is_some() {
+            CURSOR_SIZE
+        } else {
+            0
+        };
+        let total_len = (HEADER_SIZE
+            + new_styles.len() * STYLE_SIZE
+            + frame.cells.len() * CELL_SIZE
+            + cursor_len) as u32;
+        let mut num_styles = new_styles.len() as u32;
+        if reset {
+            num_styles |= RESET_STYLES;
+        }
+        if frame.cursor.is_some() {
+            num_styles |= SHOW_CURSOR;
+        }
 
         // Length prefix
         self.writer.write_all(&total_len.to_le_bytes())?;
@@ -59,24 +458,60 @@ impl<W: Write> FrameEncoder<W> {
         self.writer.write_all(&frame.time_ms.to_le_bytes())?;
         self.writer.write_all(&frame.width.to_le_bytes())?;
         self.writer.write_all(&frame.height.to_le_bytes())?;
-        self.writer.write_all(&num_cells.to_le_bytes())?;
+
+        // New styles
+        self.writer.write_all(&num_styles.to_le_bytes())?;
+        for st in new_styles {
+            self.writer.write_all(&st.fg.0.to_le_bytes())?;
+            self.writer.write_all(&st.bg.0.to_le_bytes())?;
+            self.writer.write_all(&st.attrs.0.to_le_bytes())?;
+        }
 
         // Cells
-        for fc in &frame.cells {
+        self.writer.write_all(&num_cells.to_le_bytes())?;
+        for (fc, id) in frame.cells.iter().zip(ids) {
             self.writer.write_all(&fc.pos.x.to_le_bytes())?;
             self.writer.write_all(&fc.pos.y.to_le_bytes())?;
             self.writer.write_all(&(fc.cell.ch as u32).to_le_bytes())?;
-            self.writer.write_all(&fc.cell.style.fg.0.to_le_bytes())?;
-            self.writer.write_all(&fc.cell.style.bg.0.to_le_bytes())?;
-            self.writer
-                .write_all(&fc.cell.style.attrs.0.to_le_bytes())?;
+            self.writer.write_all(&id.to_le_bytes())?;
+        }
+
+        if let Some(p) = frame.cursor {
+            self.writer.write_all(&p.x.to_le_bytes())?;
+            self.writer.write_all(&p.y.to_le_bytes())?;
         }
 
         Ok(())
     }
 
-    /// Flush the underlying writer.
+    /// Write an event that happened at `time_ms`, with the application's
+    /// `tag` and `payload`. Filters do not see events. Events are decoded in
+    /// the order written, so write them as they happen: a player places
+    /// each before the first frame written after it.
+    ///
+    /// Fails if `payload` is larger than 1 MiB.
+    pub fn event(&mut self, time_ms: u64, tag: u16, payload: &[u8]) -> io::Result<()> {
+        if payload.len() > MAX_EVENT_PAYLOAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "event payload is larger than 1 MiB",
+            ));
+        }
+        self.start()?;
+        let len = (EVENT_HEADER_SIZE + payload.len()) as u32;
+        self.writer.write_all(&0u32.to_le_bytes())?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&time_ms.to_le_bytes())?;
+        self.writer.write_all(&tag.to_le_bytes())?;
+        self.writer.write_all(payload)
+    }
+
+    /// Flush the underlying writer. The metadata, if any, is written
+    /// first, so that a recording without frames still carries it.
     pub fn flush(&mut self) -> io::Result<()> {
+        if self.meta.is_some() {
+            self.start()?;
+        }
         self.writer.flush()
     }
 
@@ -90,90 +525,220 @@ impl<W: Write> FrameEncoder<W> {
 // FrameDecoder
 // ---------------------------------------------------------------------------
 
+/// The format of the stream being decoded.
+enum Format {
+    /// The original format; holds the first length prefix, read while
+    /// looking for the magic bytes.
+    V1(Option<[u8; 4]>),
+    /// With a style table.
+    V2,
+}
+
 /// Decodes [`Frame`]s from a byte-oriented reader.
 pub struct FrameDecoder<R: Read> {
     reader: R,
+    format: Format,
+    /// Styles seen so far in the stream, by id.
+    styles: Vec<Style>,
+    meta: Option<RecordingMeta>,
+    meta_error: Option<io::Error>,
+    /// Error met while reading the start of the stream, returned by the
+    /// first [`decode`](Self::decode).
+    error: Option<io::Error>,
 }
 
 impl<R: Read> FrameDecoder<R> {
-    /// Wrap a reader.
+    /// Wrap a reader, reading the start of the stream and its metadata.
     pub fn new(reader: R) -> Self {
-        Self { reader }
+        let mut dec = Self {
+            reader,
+            format: Format::V2,
+            styles: Vec::new(),
+            meta: None,
+            meta_error: None,
+            error: None,
+        };
+        if let Err(e) = dec.read_start() {
+            dec.error = Some(e);
+        }
+        dec
+    }
+
+    /// The metadata at the start of the stream, if it has any that could
+    /// be read.
+    pub fn metadata(&self) -> Option<&RecordingMeta> {
+        self.meta.as_ref()
     }
 
-    /// Read the next frame, or `None` at EOF.
+    /// Why the metadata at the start of the stream could not be read, if
+    /// so. The frames still decode.
+    pub fn metadata_error(&self) -> Option<&io::Error> {
+        self.meta_error.as_ref()
+    }
+
+    fn read_start(&mut self) -> io::Result<()> {
+        let Some(word) = self.read_word()? else {
+            return Ok(());
+        };
+        if word == MAGIC {
+            return Ok(());
+        }
+        if word != MAGIC_META {
+            self.format = Format::V1(Some(word));
+            return Ok(());
+        }
+        let len_buf = self
+            .read_word()?
+            .ok_or_else(|| invalid("stream ends in its metadata"))?;
+        let len = u32::from_le_bytes(len_buf) as usize;
+        if len > MAX_META_SIZE {
+            // Frames cannot be found past a length this wrong either.
+            return Err(invalid(format!("metadata of {len} bytes is too large")));
+        }
+        let mut data = vec![0u8; len];
+        self.reader.read_exact(&mut data)?;
+        match RecordingMeta::from_bytes(&data) {
+            Ok(meta) => self.meta = Some(meta),
+            Err(e) => self.meta_error = Some(e),
+        }
+        Ok(())
+    }
+
+    /// Read 4 bytes, or `None` at EOF.
+    fn read_word(&mut self) -> io::Result<Option<[u8; 4]>> {
+        let mut buf = [0u8; 4];
+        match self.reader.read_exact(&mut buf) {
+            Ok(()) => Ok(Some(buf)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read the next frame, or `None` at EOF. Events are skipped; use
+    /// [`decode_record`](Self::decode_record) to get them too.
     pub fn decode(&mut self) -> io::Result<Option<Frame>> {
-        // Read length prefix
-        let mut len_buf = [0u8; 4];
-        match self.reader.read_exact(&mut len_buf) {
-            Ok(()) => {}
-            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
-            Err(e) => return Err(e),
+        loop {
+            match self.decode_record()? {
+                Some(Record::Frame(frame)) => return Ok(Some(frame)),
+                Some(Record::Event(_)) => {}
+                None => return Ok(None),
+            }
         }
-        let total_len = u32::from_le_bytes(len_buf) as usize;
+    }
 
-        if total_len < HEADER_SIZE {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "frame too small",
-            ));
+    /// Read the next frame or event, or `None` at EOF.
+    pub fn decode_record(&mut self) -> io::Result<Option<Record>> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        // Read length prefix
+        let len_buf = match &mut self.format {
+            Format::V1(first) if first.is_some() => first.take(),
+            _ => self.read_word()?,
+        };
+        let Some(len_buf) = len_buf else {
+            return Ok(None);
+        };
+        let total_len = u32::from_le_bytes(len_buf) as usize;
+        let v2 = matches!(self.format, Format::V2);
+        if v2 && total_len == 0 {
+            return self.read_event().map(|e| Some(Record::Event(e)));
+        }
+        let header = if v2 { HEADER_SIZE } else { V1_HEADER_SIZE };
+        if total_len < header {
+            return Err(invalid("frame too small"));
         }
 
         // Read the entire frame payload
         let mut data = vec![0u8; total_len];
         self.reader.read_exact(&mut data)?;
 
-        // Parse header
+        if v2 {
+            self.parse(&data).map(|f| Some(Record::Frame(f)))
+        } else {
+            parse_v1(&data).map(|f| Some(Record::Frame(f)))
+        }
+    }
+
+    /// Read an event record, past its zero length.
+    fn read_event(&mut self) -> io::Result<RecordedEvent> {
+        let len_buf = self
+            .read_word()?
+            .ok_or_else(|| invalid("stream ends in an event"))?;
+        let len = u32::from_le_bytes(len_buf) as usize;
+        if !(EVENT_HEADER_SIZE..=EVENT_HEADER_SIZE + MAX_EVENT_PAYLOAD).contains(&len) {
+            return Err(invalid(format!("event of {len} bytes")));
+        }
+        let mut data = vec![0u8; len];
+        self.reader.read_exact(&mut data)?;
+        Ok(RecordedEvent {
+            time_ms: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+            tag: u16::from_le_bytes(data[8..10].try_into().unwrap()),
+            payload: data.split_off(EVENT_HEADER_SIZE),
+        })
+    }
+
+    fn parse(&mut self, data: &[u8]) -> io::Result<Frame> {
         let time_ms = u64::from_le_bytes(data[0..8].try_into().unwrap());
-        let width = i32::from_le_bytes(data[8..12].try_into().unwrap());
-        let height = i32::from_le_bytes(data[12..16].try_into().unwrap());
-        let num_cells = u32::from_le_bytes(data[16..20].try_into().unwrap()) as usize;
+        let width = i32_at(data, 8);
+        let height = i32_at(data, 12);
+        let num_styles = u32_at(data, 16);
+        let reset = num_styles & RESET_STYLES != 0;
+        let shows_cursor = num_styles & SHOW_CURSOR != 0;
+        let num_styles = (num_styles & !(RESET_STYLES | SHOW_CURSOR)) as usize;
 
-        let expected = HEADER_SIZE + num_cells * CELL_SIZE;
-        if total_len != expected {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!(
-                    "frame size mismatch: expected {} bytes, got {}",
-                    expected, total_len
-                ),
-            ));
+        let styles_end = 20 + num_styles * STYLE_SIZE;
+        if data.len() < styles_end + 4 {
+            return Err(invalid("frame too small for its styles"));
+        }
+        let num_cells = u32_at(data, styles_end) as usize;
+        let cursor_len = if shows_cursor { CURSOR_SIZE } else { 0 };
+        let expected = HEADER_SIZE + num_styles * STYLE_SIZE + num_cells * CELL_SIZE + cursor_len;
+        if data.len() != expected {
+            return Err(invalid(format!(
+                "frame size mismatch: expected {} bytes, got {}",
+                expected,
+                data.len()
+            )));
         }
 
-        // Parse cells
+        if reset {
+            self.styles.clear();
+        }
+        if self.styles.len() + num_styles > MAX_STYLES {
+            return Err(invalid("too many styles"));
+        }
+        self.styles
+            .extend((0..num_styles).map(|k| style_at(data, 20 + k * STYLE_SIZE)));
+
         let mut cells = Vec::with_capacity(num_cells);
-        let mut offset = HEADER_SIZE;
+        let mut offset = styles_end + 4;
         for _ in 0..num_cells {
-            let x = i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
-            let y = i32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap());
-            let ch_u32 = u32::from_le_bytes(data[offset + 8..offset + 12].try_into().unwrap());
-            let fg = u32::from_le_bytes(data[offset + 12..offset + 16].try_into().unwrap());
-            let bg = u32::from_le_bytes(data[offset + 16..offset + 20].try_into().unwrap());
-            let attrs = u32::from_le_bytes(data[offset + 20..offset + 24].try_into().unwrap());
-
-            let ch = char::from_u32(ch_u32).unwrap_or('\u{FFFD}');
-
+            let x = i32_at(data, offset);
+            let y = i32_at(data, offset + 4);
+            let ch = char::from_u32(u32_at(data, offset + 8)).unwrap_or('\u{FFFD}');
+            let id = u16::from_le_bytes(data[offset + 12..offset + 14].try_into().unwrap());
+            let style = *self
+                .styles
+                .get(id as usize)
+                .ok_or_else(|| invalid(format!("unknown style id {id}")))?;
             cells.push(FrameCell {
                 pos: Point::new(x, y),
-                cell: Cell {
-                    ch,
-                    style: Style {
-                        fg: Color(fg),
-                        bg: Color(bg),
-                        attrs: AttrMask(attrs),
-                    },
-                },
+                cell: Cell { ch, style },
             });
-
             offset += CELL_SIZE;
         }
+        let cursor =
+            shows_cursor.then(|| Point::new(i32_at(data, offset), i32_at(data, offset + 4)));
 
-        Ok(Some(Frame {
+        Ok(Frame {
             cells,
             width,
             height,
             time_ms,
-        }))
+            cursor,
+        })
     }
 
     /// Consume the decoder, returning the inner reader.
@@ -182,6 +747,47 @@ impl<R: Read> FrameDecoder<R> {
     }
 }
 
+/// Parse a frame payload in the original format.
+fn parse_v1(data: &[u8]) -> io::Result<Frame> {
+    let time_ms = u64::from_le_bytes(data[0..8].try_into().unwrap());
+    let width = i32_at(data, 8);
+    let height = i32_at(data, 12);
+    let num_cells = u32_at(data, 16) as usize;
+
+    let expected = V1_HEADER_SIZE + num_cells * V1_CELL_SIZE;
+    if data.len() != expected {
+        return Err(invalid(format!(
+            "frame size mismatch: expected {} bytes, got {}",
+            expected,
+            data.len()
+        )));
+    }
+
+    let mut cells = Vec::with_capacity(num_cells);
+    let mut offset = V1_HEADER_SIZE;
+    for _ in 0..num_cells {
+        let x = i32_at(data, offset);
+        let y = i32_at(data, offset + 4);
+        let ch = char::from_u32(u32_at(data, offset + 8)).unwrap_or('\u{FFFD}');
+        cells.push(FrameCell {
+            pos: Point::new(x, y),
+            cell: Cell {
+                ch,
+                style: style_at(data, offset + 12),
+            },
+        });
+        offset += V1_CELL_SIZE;
+    }
+
+    Ok(Frame {
+        cells,
+        width,
+        height,
+        time_ms,
+        cursor: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,6 +799,7 @@ mod tests {
             width: 80,
             height: 24,
             time_ms: 1234,
+            cursor: None,
         };
 
         let mut buf = Vec::new();
@@ -235,6 +842,7 @@ mod tests {
             width: 40,
             height: 20,
             time_ms: 5000,
+            cursor: None,
         };
 
         let mut buf = Vec::new();
@@ -277,6 +885,7 @@ mod tests {
                 width: 80,
                 height: 24,
                 time_ms: i as u64 * 100,
+                cursor: None,
             })
             .collect();
 
@@ -301,6 +910,43 @@ mod tests {
         assert!(dec.decode().unwrap().is_none());
     }
 
+    #[test]
+    fn round_trip_cursor() {
+        let frame = |cursor| Frame {
+            cells: vec![FrameCell {
+                pos: Point::new(1, 0),
+                cell: Cell::default().with_char('>'),
+            }],
+            width: 10,
+            height: 4,
+            time_ms: 0,
+            cursor,
+        };
+        let cursors = [Some(Point::new(3, 2)), None, Some(Point::new(0, 0))];
+
+        let mut buf = Vec::new();
+        let mut sizes = Vec::new();
+        {
+            let mut enc = FrameEncoder::new(&mut buf);
+            for cursor in cursors {
+                let before = enc.writer.len();
+                enc.encode(&frame(cursor)).unwrap();
+                sizes.push(enc.writer.len() - before);
+            }
+        }
+        // A hidden cursor takes no space.
+        assert_eq!(sizes[1] + CURSOR_SIZE, sizes[2]);
+
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        for cursor in cursors {
+            let decoded = dec.decode().unwrap().unwrap();
+            assert_eq!(decoded.cursor, cursor);
+            assert_eq!(decoded.cells.len(), 1);
+            assert_eq!(decoded.cells[0].cell.ch, '>');
+        }
+        assert!(dec.decode().unwrap().is_none());
+    }
+
     #[test]
     fn unicode_round_trip() {
         let frame = Frame {
@@ -311,6 +957,7 @@ mod tests {
             width: 1,
             height: 1,
             time_ms: 0,
+            cursor: None,
         };
 
         let mut buf = Vec::new();
@@ -318,4 +965,248 @@ mod tests {
         let decoded = FrameDecoder::new(buf.as_slice()).decode().unwrap().unwrap();
         assert_eq!(decoded.cells[0].cell.ch, '\u{1F600}');
     }
+
+    fn red_frame(n: i32, time_ms: u64) -> Frame {
+        let red = Style::default().with_fg(Color::from_rgb(255, 0, 0));
+        Frame {
+            cells: (0..n)
+                .map(|x| FrameCell {
+                    pos: Point::new(x, 0),
+                    cell: Cell::default().with_char('#').with_style(red),
+                })
+                .collect(),
+            width: 80,
+            height: 24,
+            time_ms,
+            cursor: None,
+        }
+    }
+
+    #[test]
+    fn styles_are_written_once_per_stream() {
+        let mut enc = FrameEncoder::new(Vec::new());
+        enc.encode(&red_frame(10, 0)).unwrap();
+        let first = enc.writer.len();
+        enc.encode(&red_frame(10, 1)).unwrap();
+        let buf = enc.into_inner();
+        assert_eq!(
+            first,
+            MAGIC.len() + 4 + HEADER_SIZE + STYLE_SIZE + 10 * CELL_SIZE
+        );
+        assert_eq!(buf.len() - first, 4 + HEADER_SIZE + 10 * CELL_SIZE);
+
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        for t in 0..2 {
+            let f = dec.decode().unwrap().unwrap();
+            assert_eq!(f.cells, red_frame(10, t).cells);
+        }
+        assert!(dec.decode().unwrap().is_none());
+    }
+
+    #[test]
+    fn original_format_still_decodes() {
+        let frame = red_frame(2, 42);
+        let mut buf = Vec::new();
+        buf.extend(((V1_HEADER_SIZE + 2 * V1_CELL_SIZE) as u32).to_le_bytes());
+        buf.extend(42u64.to_le_bytes());
+        buf.extend(80i32.to_le_bytes());
+        buf.extend(24i32.to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        for fc in &frame.cells {
+            buf.extend(fc.pos.x.to_le_bytes());
+            buf.extend(fc.pos.y.to_le_bytes());
+            buf.extend((fc.cell.ch as u32).to_le_bytes());
+            buf.extend(fc.cell.style.fg.0.to_le_bytes());
+            buf.extend(fc.cell.style.bg.0.to_le_bytes());
+            buf.extend(fc.cell.style.attrs.0.to_le_bytes());
+        }
+        let copy = buf.clone();
+        buf.extend(copy);
+
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        assert!(dec.metadata().is_none());
+        for _ in 0..2 {
+            let f = dec.decode().unwrap().unwrap();
+            assert_eq!((f.time_ms, f.width, f.height), (42, 80, 24));
+            assert_eq!(f.cells, frame.cells);
+        }
+        assert!(dec.decode().unwrap().is_none());
+    }
+
+    #[test]
+    fn style_table_resets_when_ids_run_out() {
+        let frame = |first: u32, n: u32| Frame {
+            cells: (0..n)
+                .map(|i| FrameCell {
+                    pos: Point::new(i as i32, 0),
+                    cell: Cell::default().with_style(Style::default().with_bg(Color(first + i))),
+                })
+                .collect(),
+            width: 1,
+            height: 1,
+            time_ms: 0,
+            cursor: None,
+        };
+        let frames = [frame(0, 60_000), frame(60_000, 10_000), frame(5, 3)];
+        let mut buf = Vec::new();
+        let mut enc = FrameEncoder::new(&mut buf);
+        for f in &frames {
+            enc.encode(f).unwrap();
+        }
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        for f in &frames {
+            assert_eq!(dec.decode().unwrap().unwrap().cells, f.cells);
+        }
+
+        let err = FrameEncoder::new(Vec::new())
+            .encode(&frame(0, MAX_STYLES as u32 + 1))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn unknown_style_id_is_an_error() {
+        let mut buf = Vec::new();
+        FrameEncoder::new(&mut buf)
+            .encode(&red_frame(1, 0))
+            .unwrap();
+        // Point the cell at style 1, which was never defined.
+        let last = buf.len() - 2;
+        buf[last] = 1;
+        let err = FrameDecoder::new(buf.as_slice()).decode().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    fn meta() -> RecordingMeta {
+        RecordingMeta {
+            seed: Some(7),
+            started_at_unix: 1_700_000_000,
+            ..RecordingMeta::new("Shamogu", "0.3.1")
+        }
+        .with_tag("player", "Zoé 🐉")
+        .with_tag("outcome", "残念")
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        let mut buf = Vec::new();
+        let mut enc = FrameEncoder::new(&mut buf).with_metadata(meta());
+        enc.encode(&red_frame(2, 0)).unwrap();
+        enc.encode(&red_frame(3, 10)).unwrap();
+        assert_eq!(&buf[..4], b"GRF3");
+
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        assert_eq!(dec.metadata(), Some(&meta()));
+        assert!(dec.metadata_error().is_none());
+        assert_eq!(dec.decode().unwrap().unwrap().cells, red_frame(2, 0).cells);
+        assert_eq!(dec.decode().unwrap().unwrap().cells, red_frame(3, 10).cells);
+        assert!(dec.decode().unwrap().is_none());
+
+        // Without frames, flushing still writes the metadata.
+        let mut enc = FrameEncoder::new(Vec::new()).with_metadata(meta());
+        enc.flush().unwrap();
+        let buf = enc.into_inner();
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        assert_eq!(dec.metadata(), Some(&meta()));
+        assert!(dec.decode().unwrap().is_none());
+    }
+
+    #[test]
+    fn no_metadata_keeps_old_format() {
+        let mut buf = Vec::new();
+        FrameEncoder::new(&mut buf)
+            .encode(&red_frame(1, 0))
+            .unwrap();
+        assert_eq!(&buf[..4], b"GRF2");
+        let dec = FrameDecoder::new(buf.as_slice());
+        assert!(dec.metadata().is_none());
+        assert!(dec.metadata_error().is_none());
+        assert!(FrameDecoder::new(&[][..]).metadata().is_none());
+    }
+
+    #[test]
+    fn corrupted_metadata_still_decodes_frames() {
+        let mut buf = Vec::new();
+        let mut enc = FrameEncoder::new(&mut buf).with_metadata(meta());
+        enc.encode(&red_frame(2, 5)).unwrap();
+        // Claim an app name longer than the record.
+        buf[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        assert!(dec.metadata().is_none());
+        let err = dec.metadata_error().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(dec.decode().unwrap().unwrap().cells, red_frame(2, 5).cells);
+        assert!(dec.decode().unwrap().is_none());
+
+        // A wrong record length cannot be skipped.
+        buf[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = FrameDecoder::new(buf.as_slice()).decode().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn events_interleave_with_frames() {
+        let mut plain = Vec::new();
+        let mut enc = FrameEncoder::new(&mut plain);
+        enc.encode(&red_frame(1, 0)).unwrap();
+        enc.encode(&red_frame(2, 50)).unwrap();
+
+        let mut buf = Vec::new();
+        let mut enc = FrameEncoder::new(&mut buf);
+        enc.encode(&red_frame(1, 0)).unwrap();
+        enc.event(20, 7, b"boom").unwrap();
+        enc.event(20, 8, &[]).unwrap();
+        enc.encode(&red_frame(2, 50)).unwrap();
+        enc.event(60, 7, b"bang").unwrap();
+
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        let mut records = Vec::new();
+        while let Some(r) = dec.decode_record().unwrap() {
+            records.push(match r {
+                Record::Frame(f) => format!("frame {}", f.time_ms),
+                Record::Event(e) => format!(
+                    "event {} {} {}",
+                    e.time_ms,
+                    e.tag,
+                    String::from_utf8(e.payload).unwrap()
+                ),
+            });
+        }
+        assert_eq!(
+            records,
+            [
+                "frame 0",
+                "event 20 7 boom",
+                "event 20 8 ",
+                "frame 50",
+                "event 60 7 bang"
+            ]
+        );
+
+        // decode skips the events, giving what the same frames alone give.
+        let frames = |data: &[u8]| {
+            let mut dec = FrameDecoder::new(data);
+            std::iter::from_fn(|| dec.decode().unwrap())
+                .map(|f| (f.time_ms, f.cells))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(frames(&buf), frames(&plain));
+        assert_eq!(&buf[..4], b"GRF2");
+    }
+
+    #[test]
+    fn bad_events_are_errors() {
+        let mut buf = Vec::new();
+        let mut enc = FrameEncoder::new(&mut buf);
+        let err = enc
+            .event(0, 1, &vec![0; MAX_EVENT_PAYLOAD + 1])
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        enc.event(0, 1, b"ok").unwrap();
+        // Shorter than its header.
+        buf[8..12].copy_from_slice(&4u32.to_le_bytes());
+        let err = FrameDecoder::new(buf.as_slice()).decode().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
 }
@@ -0,0 +1,283 @@
+//! Ready-made filters for [`FrameEncoder::with_filter`](super::FrameEncoder::with_filter).
+//!
+//! Filters compose by adding several of them to an encoder:
+//!
+//! ```
+//! use gruid_core::recording::{FrameEncoder, filters};
+//! use gruid_core::{Cell, Range};
+//!
+//! // Hide the status bar's name field, then keep only the map, at most
+//! // 10 frames per second in 8 levels per colour channel.
+//! let enc = FrameEncoder::new(Vec::new())
+//!     .with_filter(filters::mask_region(Range::new(0, 23, 20, 24), Cell::default()))
+//!     .with_filter(filters::crop(Range::new(0, 1, 80, 22)))
+//!     .with_filter(filters::throttle(100))
+//!     .with_filter(filters::quantize_colors(8));
+//! # drop(enc);
+//! ```
+
+use std::collections::HashSet;
+
+use crate::cell::Cell;
+use crate::geom::{Point, Range};
+use crate::grid::{Frame, FrameCell};
+use crate::style::Color;
+
+/// Keep only the cells inside `rng`, moved so that `rng.min` becomes the
+/// origin. The frame size becomes that of `rng`, clamped to the frame. A
+/// cursor outside `rng` is hidden.
+pub fn crop(rng: Range) -> impl FnMut(Frame) -> Option<Frame> + Send + 'static {
+    move |mut frame| {
+        let rng = rng.intersect(Range::new(0, 0, frame.width, frame.height));
+        frame.cells.retain(|fc| rng.contains(fc.pos));
+        for fc in &mut frame.cells {
+            fc.pos = Point::new(fc.pos.x - rng.min.x, fc.pos.y - rng.min.y);
+        }
+        frame.cursor = frame
+            .cursor
+            .filter(|&p| rng.contains(p))
+            .map(|p| p - rng.min);
+        frame.width = rng.width();
+        frame.height = rng.height();
+        Some(frame)
+    }
+}
+
+/// Replace the cells inside `rng` with `cell`, to hide what they show.
+/// The cursor is hidden too while inside, as it could tell where typing
+/// is.
+pub fn mask_region(rng: Range, cell: Cell) -> impl FnMut(Frame) -> Option<Frame> + Send + 'static {
+    move |mut frame| {
+        for fc in frame.cells.iter_mut().filter(|fc| rng.contains(fc.pos)) {
+            fc.cell = cell;
+        }
+        frame.cursor = frame.cursor.filter(|&p| !rng.contains(p));
+        Some(frame)
+    }
+}
+
+/// Drop frames coming less than `min_interval_ms` after the last frame
+/// kept, going by [`Frame::time_ms`]: the first frame of each window is
+/// kept, and the ones after it in the window are dropped.
+///
+/// Frames only hold the cells that changed, so the cells of dropped frames
+/// are not lost: the next frame kept carries them, with the newest value of
+/// each cell. The state a burst of frames ends in thus shows only with the
+/// first frame after the window, however late it comes, and changes in the
+/// frames dropped at the very end of a recording are lost.
+pub fn throttle(min_interval_ms: u64) -> impl FnMut(Frame) -> Option<Frame> + Send + 'static {
+    let mut last: Option<u64> = None;
+    let mut pending: Vec<FrameCell> = Vec::new();
+    move |mut frame| {
+        if last.is_some_and(|t| frame.time_ms < t.saturating_add(min_interval_ms)) {
+            pending.append(&mut frame.cells);
+            return None;
+        }
+        last = Some(frame.time_ms);
+        if !pending.is_empty() {
+            pending.append(&mut frame.cells);
+            // Keep the last change of each cell, in order.
+            let mut seen = HashSet::new();
+            let mut cells: Vec<FrameCell> = pending
+                .drain(..)
+                .rev()
+                .filter(|fc| seen.insert(fc.pos))
+                .collect();
+            cells.reverse();
+            frame.cells = cells;
+        }
+        Some(frame)
+    }
+}
+
+/// Round each colour channel to one of `levels` evenly spaced values,
+/// so that recordings use fewer distinct styles. `levels` below 2 count as
+/// 2. [`Color::DEFAULT`] is kept as is, and colours rounded to black
+/// become `from_rgb(0, 0, 1)`, so as not to turn into the default.
+pub fn quantize_colors(levels: u8) -> impl FnMut(Frame) -> Option<Frame> + Send + 'static {
+    let steps = levels.max(2) as u32 - 1;
+    let channel = move |v: u8| {
+        let level = (v as u32 * steps + 127) / 255;
+        (level * 255 / steps) as u8
+    };
+    let color = move |c: Color| {
+        if c == Color::DEFAULT {
+            c
+        } else {
+            match Color::from_rgb(channel(c.r()), channel(c.g()), channel(c.b())) {
+                Color::DEFAULT => Color::from_rgb(0, 0, 1),
+                c => c,
+            }
+        }
+    };
+    move |mut frame| {
+        for fc in &mut frame.cells {
+            fc.cell.style.fg = color(fc.cell.style.fg);
+            fc.cell.style.bg = color(fc.cell.style.bg);
+        }
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::{FrameDecoder, FrameEncoder};
+    use crate::style::Style;
+
+    fn frame(time_ms: u64, cells: &[(i32, i32, char)]) -> Frame {
+        Frame {
+            cells: cells
+                .iter()
+                .map(|&(x, y, ch)| FrameCell {
+                    pos: Point::new(x, y),
+                    cell: Cell::default().with_char(ch),
+                })
+                .collect(),
+            width: 10,
+            height: 5,
+            time_ms,
+            cursor: None,
+        }
+    }
+
+    fn chars(frame: &Frame) -> Vec<(i32, i32, char)> {
+        frame
+            .cells
+            .iter()
+            .map(|fc| (fc.pos.x, fc.pos.y, fc.cell.ch))
+            .collect()
+    }
+
+    fn cells_at(cells: &[(i32, i32, char)], p: Point) -> char {
+        cells
+            .iter()
+            .find(|&&(x, y, _)| Point::new(x, y) == p)
+            .unwrap()
+            .2
+    }
+
+    #[test]
+    fn crop_moves_and_drops_cells() {
+        let f = frame(
+            0,
+            &[
+                (0, 0, 'a'),
+                (2, 1, 'b'),
+                (4, 3, 'c'),
+                (5, 3, 'd'),
+                (3, 4, 'e'),
+            ],
+        );
+        let cropped = crop(Range::new(2, 1, 5, 4))(f).unwrap();
+        assert_eq!(chars(&cropped), [(0, 0, 'b'), (2, 2, 'c')]);
+        assert_eq!((cropped.width, cropped.height), (3, 3));
+        // Clamped to the frame.
+        let cropped = crop(Range::new(8, 3, 20, 20))(frame(0, &[(9, 4, 'z')])).unwrap();
+        assert_eq!(chars(&cropped), [(1, 1, 'z')]);
+        assert_eq!((cropped.width, cropped.height), (2, 2));
+
+        // The cursor moves with the cells, or is hidden outside.
+        let mut f = frame(0, &[]);
+        f.cursor = Some(Point::new(3, 2));
+        assert_eq!(
+            crop(Range::new(2, 1, 5, 4))(f.clone()).unwrap().cursor,
+            Some(Point::new(1, 1))
+        );
+        assert_eq!(crop(Range::new(4, 1, 5, 4))(f).unwrap().cursor, None);
+    }
+
+    #[test]
+    fn mask_hides_region() {
+        let region = Range::new(0, 4, 6, 5);
+        let cells: Vec<_> = (0..10).map(|x| (x, 4, 'N')).chain([(1, 1, '@')]).collect();
+        let masked = mask_region(region, Cell::default().with_char('#'))(frame(0, &cells)).unwrap();
+        assert_eq!(masked.cells.len(), cells.len());
+        for fc in &masked.cells {
+            let want = if region.contains(fc.pos) {
+                '#'
+            } else {
+                cells_at(&cells, fc.pos)
+            };
+            assert_eq!(fc.cell.ch, want, "{}", fc.pos);
+        }
+
+        let mut f = frame(0, &[]);
+        f.cursor = Some(Point::new(2, 4));
+        assert_eq!(
+            mask_region(region, Cell::default())(f).unwrap().cursor,
+            None
+        );
+        let mut f = frame(0, &[]);
+        f.cursor = Some(Point::new(2, 3));
+        assert_eq!(
+            mask_region(region, Cell::default())(f).unwrap().cursor,
+            Some(Point::new(2, 3))
+        );
+    }
+
+    #[test]
+    fn throttle_keeps_first_per_window_with_newest_cells() {
+        let mut t = throttle(100);
+        assert!(t(frame(0, &[(0, 0, 'a')])).is_some());
+        assert!(t(frame(40, &[(1, 0, 'b'), (2, 0, 'c')])).is_none());
+        assert!(t(frame(99, &[(1, 0, 'B')])).is_none());
+        // The kept frame has the newest value of each cell changed since.
+        let kept = t(frame(120, &[(3, 0, 'd')])).unwrap();
+        assert_eq!(kept.time_ms, 120);
+        assert_eq!(chars(&kept), [(2, 0, 'c'), (1, 0, 'B'), (3, 0, 'd')]);
+        // Windows start at the last frame kept.
+        assert!(t(frame(219, &[])).is_none());
+        assert_eq!(chars(&t(frame(220, &[])).unwrap()), []);
+    }
+
+    #[test]
+    fn quantize_rounds_channels() {
+        let style = Style::default()
+            .with_fg(Color::from_rgb(10, 130, 250))
+            .with_bg(Color::DEFAULT);
+        let mut f = frame(0, &[(0, 0, 'x')]);
+        f.cells[0].cell.style = style;
+        let q = quantize_colors(3)(f).unwrap();
+        assert_eq!(q.cells[0].cell.style.fg, Color::from_rgb(0, 127, 255));
+        assert_eq!(q.cells[0].cell.style.bg, Color::DEFAULT);
+        let mut f = frame(0, &[(0, 0, 'x')]);
+        f.cells[0].cell.style = style;
+        let q = quantize_colors(0)(f).unwrap();
+        assert_eq!(q.cells[0].cell.style.fg, Color::from_rgb(0, 255, 255));
+        // Dark colours round to black, which is not the default colour.
+        let mut f = frame(0, &[(0, 0, 'x')]);
+        f.cells[0].cell.style = Style::default()
+            .with_fg(Color::from_rgb(20, 10, 30))
+            .with_bg(Color::from_rgb(0, 0, 1));
+        let q = quantize_colors(4)(f).unwrap();
+        assert_eq!(q.cells[0].cell.style.fg, Color::from_rgb(0, 0, 1));
+        assert_eq!(q.cells[0].cell.style.bg, Color::from_rgb(0, 0, 1));
+    }
+
+    #[test]
+    fn filters_apply_in_order() {
+        let mut buf = Vec::new();
+        let mut enc = FrameEncoder::new(&mut buf)
+            .with_filter(mask_region(
+                Range::new(0, 0, 2, 1),
+                Cell::default().with_char('#'),
+            ))
+            .with_filter(crop(Range::new(1, 0, 4, 1)))
+            .with_filter(|f: Frame| (f.time_ms != 5).then_some(f));
+        enc.encode(&frame(0, &[(0, 0, 'a'), (1, 0, 'b'), (2, 0, 'c')]))
+            .unwrap();
+        enc.encode(&frame(5, &[(2, 0, 'x')])).unwrap();
+        enc.encode(&frame(9, &[(0, 0, 'y')])).unwrap();
+
+        let mut dec = FrameDecoder::new(buf.as_slice());
+        // Masking came first: the cropped frame shows the mask, moved.
+        let first = dec.decode().unwrap().unwrap();
+        assert_eq!(chars(&first), [(0, 0, '#'), (1, 0, 'c')]);
+        assert_eq!((first.width, first.height), (3, 1));
+        // The second frame was dropped, the third cropped to nothing.
+        let third = dec.decode().unwrap().unwrap();
+        assert_eq!((third.time_ms, third.cells.len()), (9, 0));
+        assert!(dec.decode().unwrap().is_none());
+    }
+}
@@ -0,0 +1,127 @@
+//! The master seed of a run, and the seeds derived from it.
+//!
+//! [`App`](crate::App) and [`AppRunner`](crate::AppRunner) pick one seed
+//! per run: the one configured, else the `GRUID_SEED` environment
+//! variable if set, else one drawn from entropy and logged. The model receives
+//! it as a [`Seed`](crate::Seed) message right before
+//! [`Msg::Init`](crate::Msg::Init), and recordings carry it in their
+//! metadata, so a run can be replayed from a bug report.
+//!
+//! Models should not seed several generators with the master seed itself:
+//! map generation would then draw the same numbers as combat. They derive
+//! one seed per purpose with [`sub_seed`] instead, so that adding a roll
+//! to one system leaves the others unchanged.
+
+/// The environment variable giving the seed of runs that configure none,
+/// in decimal or as `0x`-prefixed hexadecimal.
+pub const SEED_ENV: &str = "GRUID_SEED";
+
+/// The seed for `purpose` derived from `master`: `splitmix64(master ^
+/// fnv1a64(purpose))`, where `fnv1a64` is the 64-bit FNV-1a hash of the
+/// purpose's bytes and `splitmix64` the finalizer of the SplitMix64
+/// generator.
+///
+/// The derivation is part of the API and will not change, so that seeds
+/// in old bug reports stay valid.
+///
+/// ```
+/// use gruid_core::seed::sub_seed;
+///
+/// let mapgen = sub_seed(42, "mapgen");
+/// assert_eq!(mapgen, sub_seed(42, "mapgen"));
+/// assert_ne!(mapgen, sub_seed(42, "combat"));
+/// assert_ne!(mapgen, sub_seed(43, "mapgen"));
+/// ```
+pub fn sub_seed(master: u64, purpose: &str) -> u64 {
+    splitmix64(master ^ fnv1a64(purpose.as_bytes()))
+}
+
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Parse a seed in decimal or as `0x`-prefixed hexadecimal.
+pub fn parse(s: &str) -> Option<u64> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// The seed in [`SEED_ENV`], if set. An unparsable value is ignored with
+/// a warning.
+#[cfg(feature = "std")]
+pub fn from_env() -> Option<u64> {
+    let value = std::env::var(SEED_ENV).ok()?;
+    let seed = parse(&value);
+    if seed.is_none() {
+        log::warn!("ignoring {SEED_ENV}={value:?}: not a decimal or 0x-hexadecimal u64");
+    }
+    seed
+}
+
+/// A fresh seed from the process's hash keys and the clock.
+#[cfg(feature = "std")]
+pub fn entropy() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut h = std::collections::hash_map::RandomState::new().build_hasher();
+    // There is no clock in std on the web; the browser driver sets the
+    // seed itself.
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(t) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        h.write_u128(t.as_nanos());
+    }
+    splitmix64(h.finish())
+}
+
+/// The master seed of a run: `configured`, else [`from_env`], else
+/// [`entropy`]. An explicit seed wins so that tests and replays asking for
+/// one get it whatever the environment. A seed from entropy is logged, so
+/// the run can be reproduced.
+#[cfg(feature = "std")]
+pub fn resolve(configured: Option<u64>) -> u64 {
+    if let Some(seed) = configured {
+        return seed;
+    }
+    if let Some(seed) = from_env() {
+        log::info!("seed {seed} from {SEED_ENV}");
+        return seed;
+    }
+    let seed = entropy();
+    log::info!("seed {seed} (set {SEED_ENV}={seed} to reproduce this run)");
+    seed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sub_seeds_are_stable() {
+        // Pinned: a change here breaks every recorded seed.
+        assert_eq!(fnv1a64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(splitmix64(0), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(sub_seed(0, ""), splitmix64(0xcbf2_9ce4_8422_2325));
+    }
+
+    #[test]
+    fn parses_decimal_and_hex() {
+        assert_eq!(parse("42"), Some(42));
+        assert_eq!(parse(" 0x2a\n"), Some(42));
+        assert_eq!(parse("0XFF"), Some(255));
+        assert_eq!(parse("-1"), None);
+        assert_eq!(parse("seed"), None);
+    }
+}
@@ -1,13 +1,17 @@
 //! Visual styling: [`Color`], [`AttrMask`], and [`Style`].
 
-use std::ops::{BitAnd, BitOr};
+use core::ops::{BitAnd, BitOr};
 
 // ---------------------------------------------------------------------------
 // Color
 // ---------------------------------------------------------------------------
 
 /// An RGB colour packed into a `u32` (0x00RRGGBB).
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+///
+/// Black is [`Color::DEFAULT`]: drivers draw it with their default colour
+/// for the layer, which is black only for pixel backgrounds. See
+/// [`Style::rgb`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color(pub u32);
 
@@ -40,12 +44,18 @@ impl Color {
     }
 }
 
+/// RGB of a [`Color::DEFAULT`] foreground, on drivers drawing pixels.
+pub const DEFAULT_FG_RGB: (u8, u8, u8) = (200, 200, 200);
+
+/// RGB of a [`Color::DEFAULT`] background, on drivers drawing pixels.
+pub const DEFAULT_BG_RGB: (u8, u8, u8) = (0, 0, 0);
+
 // ---------------------------------------------------------------------------
 // AttrMask
 // ---------------------------------------------------------------------------
 
 /// Bitmask of text attributes.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttrMask(pub u32);
 
@@ -92,7 +102,7 @@ impl BitAnd for AttrMask {
 // ---------------------------------------------------------------------------
 
 /// Complete visual style for a single cell.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Style {
     pub fg: Color,
@@ -121,6 +131,34 @@ impl Style {
         self.attrs = attrs;
         self
     }
+
+    /// Foreground and background RGB colours drivers drawing pixels use
+    /// for this style.
+    ///
+    /// [`Color::DEFAULT`] is [`DEFAULT_FG_RGB`] or [`DEFAULT_BG_RGB`].
+    /// [`AttrMask::REVERSE`] swaps the colours, and [`AttrMask::DIM`] takes
+    /// the foreground halfway to the background. Terminals leave defaults
+    /// and attributes to the terminal instead.
+    pub const fn rgb(self) -> ((u8, u8, u8), (u8, u8, u8)) {
+        const fn or(c: Color, default: (u8, u8, u8)) -> (u8, u8, u8) {
+            if c.0 == Color::DEFAULT.0 {
+                default
+            } else {
+                (c.r(), c.g(), c.b())
+            }
+        }
+        let (mut fg, mut bg) = (or(self.fg, DEFAULT_FG_RGB), or(self.bg, DEFAULT_BG_RGB));
+        if self.attrs.contains(AttrMask::REVERSE) {
+            (fg, bg) = (bg, fg);
+        }
+        if self.attrs.contains(AttrMask::DIM) {
+            const fn mid(a: u8, b: u8) -> u8 {
+                ((a as u16 + b as u16) / 2) as u8
+            }
+            fg = (mid(fg.0, bg.0), mid(fg.1, bg.1), mid(fg.2, bg.2));
+        }
+        (fg, bg)
+    }
 }
 
 #[cfg(test)]
@@ -153,4 +191,16 @@ mod tests {
         assert_eq!(s.fg.r(), 255);
         assert!(s.attrs.contains(AttrMask::BOLD));
     }
+
+    #[test]
+    fn rgb_resolves_defaults_and_attributes() {
+        let red = Color::from_rgb(200, 0, 0);
+        let s = Style::default().with_bg(red);
+        assert_eq!(s.rgb(), (DEFAULT_FG_RGB, (200, 0, 0)));
+        let s = s.with_attrs(AttrMask::REVERSE | AttrMask::BOLD);
+        assert_eq!(s.rgb(), ((200, 0, 0), DEFAULT_FG_RGB));
+        let s = s.with_attrs(AttrMask::REVERSE | AttrMask::DIM);
+        assert_eq!(s.rgb(), ((200, 100, 100), DEFAULT_FG_RGB));
+        assert_eq!(Style::default().rgb(), (DEFAULT_FG_RGB, DEFAULT_BG_RGB));
+    }
 }
@@ -0,0 +1,322 @@
+//! Property tests: random operations on grids and ranges, with arbitrary
+//! coordinates, never panic and keep the documented invariants.
+
+use gruid_core::grid::{Frame, FrameCell, compute_frame, render_to_slice};
+use gruid_core::{Cell, Color, Grid, Point, Range, Style};
+use proptest::prelude::*;
+
+/// Mostly small coordinates, around a grid of up to 12×12, and now and
+/// then any `i32`.
+fn coord() -> impl Strategy<Value = i32> {
+    prop_oneof![10 => -3..15i32, 1 => any::<i32>()]
+}
+
+fn point() -> impl Strategy<Value = Point> {
+    (coord(), coord()).prop_map(|(x, y)| Point::new(x, y))
+}
+
+/// A range built from its fields, so possibly inverted.
+fn range() -> impl Strategy<Value = Range> {
+    (point(), point()).prop_map(|(min, max)| Range { min, max })
+}
+
+fn cell() -> impl Strategy<Value = Cell> {
+    (prop::sample::select(vec!['a', 'b', '#', ' ', '@']), 0..3u8).prop_map(|(ch, r)| {
+        Cell::default()
+            .with_char(ch)
+            .with_style(Style::default().with_fg(Color::from_rgb(r, 0, 0)))
+    })
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Set(usize, Point, Cell),
+    Slice(usize, Range),
+    Fill(usize, Cell),
+    ClearRange(usize, Range, Cell),
+    ClearLine(usize, i32, Cell),
+    CopyFrom(usize, usize),
+    MapCells(usize, Cell),
+    Scroll(usize, Range, i32, bool, Cell),
+    Resize(i32, i32),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let view = 0..8usize;
+    prop_oneof![
+        4 => (view.clone(), point(), cell()).prop_map(|(v, p, c)| Op::Set(v, p, c)),
+        2 => (view.clone(), range()).prop_map(|(v, r)| Op::Slice(v, r)),
+        1 => (view.clone(), cell()).prop_map(|(v, c)| Op::Fill(v, c)),
+        1 => (view.clone(), range(), cell()).prop_map(|(v, r, c)| Op::ClearRange(v, r, c)),
+        1 => (view.clone(), coord(), cell()).prop_map(|(v, y, c)| Op::ClearLine(v, y, c)),
+        2 => (view.clone(), view.clone()).prop_map(|(d, s)| Op::CopyFrom(d, s)),
+        1 => (view.clone(), cell()).prop_map(|(v, c)| Op::MapCells(v, c)),
+        1 => (view.clone(), range(), coord(), any::<bool>(), cell())
+            .prop_map(|(v, r, n, up, c)| Op::Scroll(v, r, n, up, c)),
+        1 => (-2..16i32, -2..16i32).prop_map(|(w, h)| Op::Resize(w, h)),
+    ]
+}
+
+/// What the grids should hold: the cells of the buffer, and the absolute
+/// bounds of each view, the first being the resizable root grid.
+struct Model {
+    width: i32,
+    height: i32,
+    cells: Vec<Cell>,
+    views: Vec<(Grid, Range)>,
+}
+
+impl Model {
+    fn new(w: i32, h: i32) -> Self {
+        Self {
+            width: w,
+            height: h,
+            cells: vec![Cell::default(); (w * h) as usize],
+            views: vec![(Grid::new(w, h), Range::new(0, 0, w, h))],
+        }
+    }
+
+    fn view(&self, i: usize) -> usize {
+        i % self.views.len()
+    }
+
+    fn cell(&mut self, abs: Point) -> &mut Cell {
+        &mut self.cells[(abs.y * self.width + abs.x) as usize]
+    }
+
+    /// Apply `f` to the cells of the relative range `rg` of view `v`,
+    /// clipped to it.
+    fn each(&mut self, v: usize, rg: Range, mut f: impl FnMut(&mut Self, Point)) {
+        let b = self.views[v].1;
+        let rg = rg.intersect(Range::new(0, 0, b.width(), b.height()));
+        for p in rg.iter() {
+            f(self, p + b.min);
+        }
+    }
+
+    fn apply(&mut self, op: Op) {
+        match op {
+            Op::Set(v, p, c) => {
+                let v = self.view(v);
+                self.views[v].0.set(p, c);
+                let b = self.views[v].1;
+                if (0..b.width()).contains(&p.x) && (0..b.height()).contains(&p.y) {
+                    *self.cell(p + b.min) = c;
+                }
+            }
+            Op::Slice(v, rg) => {
+                let v = self.view(v);
+                let (g, b) = self.views[v].clone();
+                let (w, h) = (b.width(), b.height());
+                let min = Point::new(rg.min.x.clamp(0, w), rg.min.y.clamp(0, h));
+                let max = Point::new(rg.max.x.clamp(min.x, w), rg.max.y.clamp(min.y, h));
+                let slice = g.slice(rg);
+                if self.views.len() < 8 {
+                    self.views.push((slice, Range { min, max } + b.min));
+                }
+            }
+            Op::Fill(v, c) => {
+                let v = self.view(v);
+                self.views[v].0.fill(c);
+                self.each(v, Range::new(0, 0, i32::MAX, i32::MAX), |m, q| {
+                    *m.cell(q) = c
+                });
+            }
+            Op::ClearRange(v, rg, c) => {
+                let v = self.view(v);
+                self.views[v].0.clear_range(rg, c);
+                self.each(v, rg, |m, q| *m.cell(q) = c);
+            }
+            Op::ClearLine(v, y, c) => {
+                let v = self.view(v);
+                self.views[v].0.clear_line(y, c);
+                self.each(v, Range::new(0, 0, i32::MAX, i32::MAX).line(y), |m, q| {
+                    *m.cell(q) = c
+                });
+            }
+            Op::CopyFrom(d, s) => {
+                let (d, s) = (self.view(d), self.view(s));
+                let copied = self.views[d].0.copy_from(&self.views[s].0);
+                let (db, sb) = (self.views[d].1, self.views[s].1);
+                assert_eq!(
+                    copied,
+                    Point::new(db.width().min(sb.width()), db.height().min(sb.height()))
+                );
+                let src: Vec<Cell> = Range::new(0, 0, copied.x, copied.y)
+                    .iter()
+                    .map(|p| *self.cell(p + sb.min))
+                    .collect();
+                for (p, c) in Range::new(0, 0, copied.x, copied.y).iter().zip(src) {
+                    *self.cell(p + db.min) = c;
+                }
+            }
+            Op::MapCells(v, c) => {
+                let v = self.view(v);
+                let g = self.views[v].0.clone();
+                // Reading the grid from the callback is allowed.
+                self.views[v]
+                    .0
+                    .map_cells(|p, _| if (p.x + p.y) % 2 == 0 { c } else { g.at(p) });
+                self.each(v, Range::new(0, 0, i32::MAX, i32::MAX), |m, q| {
+                    let p = q - m.views[v].1.min;
+                    if (p.x + p.y) % 2 == 0 {
+                        *m.cell(q) = c;
+                    }
+                });
+            }
+            Op::Scroll(v, rg, n, up, c) => {
+                let v = self.view(v);
+                if up {
+                    self.views[v].0.scroll_up(rg, n, c);
+                } else {
+                    self.views[v].0.scroll_down(rg, n, c);
+                }
+                let b = self.views[v].1;
+                let r = rg.intersect(Range::new(0, 0, b.width(), b.height())) + b.min;
+                if n <= 0 || r.is_empty() {
+                    return;
+                }
+                let rows: Vec<Vec<Cell>> = (r.min.y..r.max.y)
+                    .map(|y| {
+                        (r.min.x..r.max.x)
+                            .map(|x| *self.cell(Point::new(x, y)))
+                            .collect()
+                    })
+                    .collect();
+                let height = r.height() as usize;
+                for (k, y) in (r.min.y..r.max.y).enumerate() {
+                    let from = if up {
+                        k.checked_add(n as usize).filter(|&i| i < height)
+                    } else {
+                        k.checked_sub(n as usize)
+                    };
+                    for (j, x) in (r.min.x..r.max.x).enumerate() {
+                        *self.cell(Point::new(x, y)) = from.map_or(c, |i| rows[i][j]);
+                    }
+                }
+            }
+            Op::Resize(w, h) => {
+                let root = self.views[0].1;
+                let (ow, oh) = (root.width(), root.height());
+                self.views[0].0.resize(w, h);
+                if (ow, oh) == (w, h) {
+                } else if w <= 0 || h <= 0 {
+                    self.views[0].1 = Range::default();
+                } else if w <= self.width && h <= self.height {
+                    self.views[0].1 = Range::new(0, 0, w, h);
+                } else {
+                    let mut cells = vec![Cell::default(); (w * h) as usize];
+                    for p in Range::new(0, 0, ow.min(w), oh.min(h)).iter() {
+                        cells[(p.y * w + p.x) as usize] = *self.cell(p);
+                    }
+                    // The other views keep the old buffer.
+                    let root = self.views.swap_remove(0).0;
+                    *self = Self {
+                        width: w,
+                        height: h,
+                        cells,
+                        views: vec![(root, Range::new(0, 0, w, h))],
+                    };
+                }
+            }
+        }
+    }
+
+    fn check(&mut self) {
+        for i in 0..self.views.len() {
+            let (g, b) = self.views[i].clone();
+            let size = g.size();
+            assert!(size.x >= 0 && size.y >= 0, "negative size {size}");
+            assert_eq!(size, b.size());
+            let points: Vec<Point> = g.iter().map(|(p, _)| p).collect();
+            assert_eq!(
+                points,
+                Range::new(0, 0, size.x, size.y).iter().collect::<Vec<_>>()
+            );
+            assert_eq!(g.iter().size_hint().0, points.len());
+            for p in points {
+                assert!(g.contains(p));
+                assert_eq!(g.at(p), *self.cell(p + b.min), "view {i} at {p}");
+            }
+            for p in [
+                Point::new(-1, 0),
+                Point::new(0, size.y),
+                size,
+                Point::new(i32::MAX, 0),
+            ] {
+                assert!(!g.contains(p));
+                assert_eq!(g.at(p), Cell::default());
+            }
+            assert_eq!(g.to_string().lines().count(), size.y as usize);
+            let frame = compute_frame(&Grid::new(size.x, size.y), &g);
+            let changed = g.iter().filter(|(_, c)| *c != Cell::default()).count();
+            assert_eq!(frame.cells.len(), changed);
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn grid_ops_match_model(
+        (w, h) in (0..12i32, 0..12i32),
+        ops in prop::collection::vec(op(), 1..40),
+    ) {
+        let mut model = Model::new(w, h);
+        for op in ops {
+            model.apply(op);
+            model.check();
+        }
+    }
+
+    #[test]
+    fn range_algebra(a in range(), b in range(), p in point(), y0 in coord(), y1 in coord()) {
+        let i = a.intersect(b);
+        prop_assert_eq!(i, b.intersect(a));
+        prop_assert!(i.in_range(a) && i.in_range(b));
+        prop_assert_eq!(a.overlaps(b), !i.is_empty());
+        prop_assert!(i.len() <= a.len().min(b.len()));
+        let u = a.union(b);
+        prop_assert!(a.in_range(u) && b.in_range(u));
+        prop_assert_eq!(a.contains(p) && b.contains(p), i.contains(p));
+        prop_assert!(a.line(y0).in_range(a));
+        prop_assert!(a.lines(y0, y1).in_range(a));
+        prop_assert!(a.columns(y0, y1).in_range(a));
+        prop_assert!(a.column(y1).in_range(a));
+        let n = Range::new(a.min.x, a.min.y, a.max.x, a.max.y);
+        prop_assert!(n.min.x <= n.max.x && n.min.y <= n.max.y);
+        // Translating is plain arithmetic, which overflows near the limits.
+        if [a.min.x, a.min.y, a.max.x, a.max.y].iter().all(|c| c.unsigned_abs() < 1 << 30) {
+            let s = a.shift(1, 1, -1, -1);
+            prop_assert!(s.is_empty() || (s.min.x < s.max.x && s.min.y < s.max.y));
+        }
+        prop_assert_eq!(a.is_empty(), a.iter().next().is_none());
+        prop_assert_eq!(a.iter().size_hint(), (a.len(), Some(a.len())));
+        if a.len() <= 400 {
+            let points: Vec<Point> = a.iter().collect();
+            prop_assert_eq!(points.len(), a.len());
+            prop_assert!(points.iter().all(|&q| a.contains(q)));
+            prop_assert!(points.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn render_to_slice_takes_any_frame(
+        (width, height) in (coord(), coord()),
+        cells in prop::collection::vec((point(), cell()), 0..20),
+        len in 0..200usize,
+    ) {
+        let frame = Frame {
+            cells: cells.into_iter().map(|(pos, cell)| FrameCell { pos, cell }).collect(),
+            width,
+            height,
+            time_ms: 0,
+            cursor: None,
+        };
+        let mut out = vec![Cell::default(); len];
+        if let Some(dirty) = render_to_slice(&frame, &mut out) {
+            prop_assert!(dirty.min.x >= 0 && dirty.min.y >= 0 && dirty.max.x <= width);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! `GRUID_SEED` gives the seed of runs that configure none. A test binary
+//! of its own, since setting the variable would race with the other tests.
+
+#![cfg(feature = "std")]
+
+use gruid_core::{AppRunner, Effect, Grid, Model, Msg, seed};
+
+struct Idle;
+
+impl Model for Idle {
+    fn update(&mut self, _msg: Msg) -> Option<Effect> {
+        None
+    }
+
+    fn draw(&self, _grid: &mut Grid) {}
+}
+
+#[test]
+fn env_seeds_unconfigured_runs() {
+    // SAFETY: the only test in this binary, and no other thread reads the
+    // environment.
+    unsafe { std::env::set_var(seed::SEED_ENV, "0x2a") };
+    let mut r = AppRunner::new(Box::new(Idle), 4, 1);
+    r.init();
+    assert_eq!(r.seed(), Some(42));
+    assert_eq!(seed::resolve(None), 42);
+    // An explicit seed wins.
+    let mut r = AppRunner::new(Box::new(Idle), 4, 1);
+    r.set_seed(7);
+    r.init();
+    assert_eq!(r.seed(), Some(7));
+    assert_eq!(seed::resolve(Some(7)), 7);
+
+    // Garbage is ignored. SAFETY: as above.
+    unsafe { std::env::set_var(seed::SEED_ENV, "forty-two") };
+    assert_ne!(seed::resolve(None), 42);
+}
@@ -0,0 +1,255 @@
+//! Colour conversion for terminals without 24-bit colour.
+//!
+//! Colours are quantized to the nearest one of the palette, by squared
+//! distance in RGB space: for 256 colours, the nearest of the xterm
+//! colour cube and grayscale ramp (palette entries 16 to 255, leaving out
+//! the 16 basic colours whose values terminal themes change); for 16
+//! colours, the nearest basic colour with its xterm default value, set
+//! with the original SGR codes that all terminals understand.
+
+use std::io::{self, Write};
+
+use crossterm::queue;
+use crossterm::style::{Color as CtColor, SetBackgroundColor, SetForegroundColor};
+use gruid_core::{ColorDepth, style::Color};
+
+/// How the driver writes colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorMode {
+    /// Guess what the terminal supports from the `COLORTERM` and `TERM`
+    /// environment variables.
+    #[default]
+    Auto,
+    /// 24-bit colour.
+    TrueColor,
+    /// The xterm 256-colour palette.
+    Ansi256,
+    /// The 16 basic colours.
+    Ansi16,
+}
+
+impl ColorMode {
+    /// The colour depth used, with environment variables looked up with
+    /// `env`.
+    pub(crate) fn depth(self, env: impl Fn(&str) -> Option<String>) -> ColorDepth {
+        match self {
+            Self::Auto => detect_depth(env),
+            Self::TrueColor => ColorDepth::TrueColor,
+            Self::Ansi256 => ColorDepth::Ansi256,
+            Self::Ansi16 => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// Guesses the colour depth from the `COLORTERM` and `TERM` environment
+/// variables, looked up with `env`.
+pub(crate) fn detect_depth(env: impl Fn(&str) -> Option<String>) -> ColorDepth {
+    let has = |var: &str, pats: &[&str]| {
+        env(var).is_some_and(|v| {
+            let v = v.to_ascii_lowercase();
+            pats.iter().any(|p| v.contains(p))
+        })
+    };
+    // Windows Terminal supports truecolor, but does not say so.
+    if env("WT_SESSION").is_some()
+        || has("COLORTERM", &["truecolor", "24bit"])
+        || has("TERM", &["direct"])
+    {
+        ColorDepth::TrueColor
+    } else if has("TERM", &["256color"]) {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    }
+}
+
+/// Writes the escape sequence setting the foreground colour, or the
+/// background colour if `bg`, to `c` as a terminal with `depth` colours
+/// shows it. [`Color::DEFAULT`] is the terminal default colour at any
+/// depth.
+pub(crate) fn write_color(
+    out: &mut impl Write,
+    c: Color,
+    depth: ColorDepth,
+    bg: bool,
+) -> io::Result<()> {
+    let ct = if c == Color::DEFAULT {
+        CtColor::Reset
+    } else {
+        match depth {
+            ColorDepth::TrueColor => CtColor::Rgb {
+                r: c.r(),
+                g: c.g(),
+                b: c.b(),
+            },
+            ColorDepth::Ansi256 => CtColor::AnsiValue(to_ansi256(c)),
+            ColorDepth::Ansi16 => {
+                // Crossterm writes even basic colours as 256-colour ones.
+                let n = to_ansi16(c);
+                let code = match (bg, n < 8) {
+                    (false, true) => 30 + n,
+                    (false, false) => 90 + n - 8,
+                    (true, true) => 40 + n,
+                    (true, false) => 100 + n - 8,
+                };
+                return write!(out, "\x1b[{code}m");
+            }
+        }
+    };
+    if bg {
+        queue!(out, SetBackgroundColor(ct))
+    } else {
+        queue!(out, SetForegroundColor(ct))
+    }
+}
+
+type Rgb = (u8, u8, u8);
+
+fn rgb(c: Color) -> Rgb {
+    (c.r(), c.g(), c.b())
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (x.abs_diff(y) as u32).pow(2);
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Levels of each component in the xterm colour cube.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Index in [`CUBE`] of the level nearest `v`.
+fn cube_level(v: u8) -> usize {
+    (0..CUBE.len())
+        .min_by_key(|&i| CUBE[i].abs_diff(v))
+        .unwrap_or(0)
+}
+
+/// The xterm 256-colour palette entry nearest `c`.
+pub(crate) fn to_ansi256(c: Color) -> u8 {
+    let (r, g, b) = rgb(c);
+    let (ri, gi, bi) = (cube_level(r), cube_level(g), cube_level(b));
+    let cube = (CUBE[ri], CUBE[gi], CUBE[bi]);
+    // The 24 grays from 232 go from 8 to 238 by steps of 10.
+    let avg = (r as i32 + g as i32 + b as i32) / 3;
+    let step = ((avg - 3) / 10).clamp(0, 23);
+    let level = (8 + 10 * step) as u8;
+    if distance((r, g, b), (level, level, level)) < distance((r, g, b), cube) {
+        232 + step as u8
+    } else {
+        (16 + 36 * ri + 6 * gi + bi) as u8
+    }
+}
+
+/// The xterm default values of the basic colours: black, red, green,
+/// yellow, blue, magenta, cyan and white, then their bright variants.
+const ANSI16: [Rgb; 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Number of the basic colour nearest `c`.
+pub(crate) fn to_ansi16(c: Color) -> u8 {
+    (0..16)
+        .min_by_key(|&i| distance(rgb(c), ANSI16[i as usize]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ansi256(r: u8, g: u8, b: u8) -> u8 {
+        to_ansi256(Color::from_rgb(r, g, b))
+    }
+
+    #[test]
+    fn cube_corners() {
+        assert_eq!(ansi256(1, 1, 1), 16);
+        assert_eq!(ansi256(255, 0, 0), 196);
+        assert_eq!(ansi256(0, 255, 0), 46);
+        assert_eq!(ansi256(0, 0, 255), 21);
+        assert_eq!(ansi256(255, 255, 0), 226);
+        assert_eq!(ansi256(255, 0, 255), 201);
+        assert_eq!(ansi256(0, 255, 255), 51);
+        assert_eq!(ansi256(255, 255, 255), 231);
+        // Cube levels map to themselves, values between to the nearest.
+        assert_eq!(ansi256(95, 135, 175), 16 + 36 + 2 * 6 + 3);
+        assert_eq!(ansi256(100, 130, 180), 16 + 36 + 2 * 6 + 3);
+    }
+
+    #[test]
+    fn grayscale_ramp() {
+        for step in 0..24u8 {
+            let v = 8 + 10 * step;
+            assert_eq!(ansi256(v, v, v), 232 + step, "gray {v}");
+        }
+        // Near the ends, the cube's black and white are closer.
+        assert_eq!(ansi256(2, 2, 2), 16);
+        assert_eq!(ansi256(250, 250, 250), 231);
+        // Slightly tinted grays still use the ramp.
+        assert_eq!(ansi256(60, 55, 50), 232 + 5);
+    }
+
+    #[test]
+    fn basic_colors() {
+        let ansi16 = |r, g, b| to_ansi16(Color::from_rgb(r, g, b));
+        assert_eq!(ansi16(250, 10, 10), 9);
+        assert_eq!(ansi16(180, 20, 10), 1);
+        assert_eq!(ansi16(30, 28, 25), 0);
+        assert_eq!(ansi16(120, 120, 130), 8);
+        assert_eq!(ansi16(220, 220, 220), 7);
+        assert_eq!(ansi16(100, 100, 250), 12);
+    }
+
+    fn sequence(c: Color, depth: ColorDepth, bg: bool) -> String {
+        let mut out = Vec::new();
+        write_color(&mut out, c, depth, bg).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn sequences() {
+        for depth in [
+            ColorDepth::TrueColor,
+            ColorDepth::Ansi256,
+            ColorDepth::Ansi16,
+        ] {
+            assert_eq!(sequence(Color::DEFAULT, depth, false), "\x1b[39m");
+            assert_eq!(sequence(Color::DEFAULT, depth, true), "\x1b[49m");
+        }
+        let c = Color::from_rgb(1, 2, 3);
+        assert_eq!(
+            sequence(c, ColorDepth::TrueColor, false),
+            "\x1b[38;2;1;2;3m"
+        );
+        assert_eq!(sequence(c, ColorDepth::Ansi256, true), "\x1b[48;5;16m");
+        assert_eq!(sequence(c, ColorDepth::Ansi16, false), "\x1b[30m");
+        assert_eq!(sequence(c, ColorDepth::Ansi16, true), "\x1b[40m");
+        let red = Color::from_rgb(250, 10, 10);
+        assert_eq!(sequence(red, ColorDepth::Ansi16, false), "\x1b[91m");
+        assert_eq!(sequence(red, ColorDepth::Ansi16, true), "\x1b[101m");
+    }
+
+    #[test]
+    fn forced_modes_ignore_environment() {
+        let env = |var: &str| (var == "COLORTERM").then(|| "truecolor".to_string());
+        assert_eq!(ColorMode::Auto.depth(env), ColorDepth::TrueColor);
+        assert_eq!(ColorMode::Ansi256.depth(env), ColorDepth::Ansi256);
+        assert_eq!(ColorMode::Ansi16.depth(env), ColorDepth::Ansi16);
+        assert_eq!(ColorMode::TrueColor.depth(|_| None), ColorDepth::TrueColor);
+    }
+}
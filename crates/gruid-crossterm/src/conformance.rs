@@ -0,0 +1,266 @@
+//! Driver conformance: the escape sequences written for the cases of
+//! [`gruid_core::conformance`] are played on a virtual terminal, whose
+//! screen should then show each cell's character, colours and the
+//! attributes of the driver's profile.
+
+use gruid_core::conformance::{self, Case};
+use gruid_core::grid::{Frame, FrameCell};
+use gruid_core::style::{AttrMask, Color};
+use gruid_core::{ColorDepth, Point};
+
+use super::CrosstermDriver;
+use crate::ColorMode;
+use crate::color::{to_ansi16, to_ansi256};
+
+/// A colour as the terminal was told it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ink {
+    Default,
+    Rgb(u8, u8, u8),
+    /// A palette entry.
+    Indexed(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VCell {
+    ch: char,
+    fg: Ink,
+    bg: Ink,
+    attrs: AttrMask,
+}
+
+impl Default for VCell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            fg: Ink::Default,
+            bg: Ink::Default,
+            attrs: AttrMask::NONE,
+        }
+    }
+}
+
+/// The part of a terminal that the driver's frames use: cursor moves,
+/// SGR colours and attributes, and scrolling regions.
+struct Terminal {
+    width: i32,
+    height: i32,
+    cells: Vec<VCell>,
+    cursor: Point,
+    pen: VCell,
+    /// Scrolling region, rows `top..bottom`.
+    region: (i32, i32),
+}
+
+impl Terminal {
+    fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![VCell::default(); (width * height) as usize],
+            cursor: Point::new(0, 0),
+            pen: VCell::default(),
+            region: (0, height),
+        }
+    }
+
+    fn at(&self, p: Point) -> VCell {
+        self.cells[(p.y * self.width + p.x) as usize]
+    }
+
+    /// Play `out`.
+    ///
+    /// # Panics
+    ///
+    /// Panics on sequences the interpreter does not know.
+    fn play(&mut self, out: &str) {
+        let mut chars = out.chars();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                self.print(c);
+                continue;
+            }
+            assert_eq!(chars.next(), Some('['), "only CSI sequences: {out:?}");
+            let mut params = String::new();
+            let fin = loop {
+                match chars.next() {
+                    Some(c @ ('0'..='9' | ';' | '?')) => params.push(c),
+                    Some(c) => break c,
+                    None => panic!("unterminated sequence in {out:?}"),
+                }
+            };
+            self.csi(&params, fin);
+        }
+    }
+
+    fn print(&mut self, ch: char) {
+        let p = self.cursor;
+        assert!(
+            p.x < self.width && p.y < self.height,
+            "{ch:?} printed at {p}"
+        );
+        self.cells[(p.y * self.width + p.x) as usize] = VCell { ch, ..self.pen };
+        self.cursor = p + Point::new(1, 0);
+    }
+
+    fn csi(&mut self, params: &str, fin: char) {
+        if params.starts_with('?') {
+            // Private modes: synchronized output and cursor visibility.
+            assert!(matches!(fin, 'h' | 'l'), "CSI ?{params}{fin}");
+            return;
+        }
+        let nums: Vec<u32> = params
+            .split(';')
+            .map(|n| if n.is_empty() { 0 } else { n.parse().unwrap() })
+            .collect();
+        let n = |i: usize, default: u32| match nums.get(i) {
+            Some(&0) | None => default,
+            Some(&v) => v,
+        };
+        match fin {
+            'H' => self.cursor = Point::new(n(1, 1) as i32 - 1, n(0, 1) as i32 - 1),
+            'm' => self.sgr(&nums),
+            'r' => self.region = (n(0, 1) as i32 - 1, n(1, self.height as u32) as i32),
+            'S' => self.scroll(n(0, 1) as i32),
+            'T' => self.scroll(-(n(0, 1) as i32)),
+            _ => panic!("unexpected CSI {params}{fin}"),
+        }
+    }
+
+    /// Scroll the region up by `n` rows, or down if negative.
+    fn scroll(&mut self, n: i32) {
+        let (top, bottom) = self.region;
+        let rows: Vec<Vec<VCell>> = (top..bottom)
+            .map(|y| (0..self.width).map(|x| self.at(Point::new(x, y))).collect())
+            .collect();
+        for y in top..bottom {
+            let src = y - top + n;
+            for x in 0..self.width {
+                let cell = usize::try_from(src)
+                    .ok()
+                    .and_then(|s| rows.get(s))
+                    .map_or(VCell::default(), |row| row[x as usize]);
+                self.cells[(y * self.width + x) as usize] = cell;
+            }
+        }
+    }
+
+    fn sgr(&mut self, nums: &[u32]) {
+        let mut nums = nums.iter().copied();
+        while let Some(code) = nums.next() {
+            let color = |nums: &mut dyn Iterator<Item = u32>| match nums.next() {
+                Some(2) => {
+                    let mut c = || nums.next().unwrap() as u8;
+                    Ink::Rgb(c(), c(), c())
+                }
+                Some(5) => Ink::Indexed(nums.next().unwrap() as u8),
+                other => panic!("colour kind {other:?}"),
+            };
+            let pen = &mut self.pen;
+            let attr = |a: AttrMask, on: bool, attrs: &mut AttrMask| {
+                *attrs = if on {
+                    *attrs | a
+                } else {
+                    AttrMask(attrs.0 & !a.0)
+                };
+            };
+            match code {
+                0 => *pen = VCell::default(),
+                1 => attr(AttrMask::BOLD, true, &mut pen.attrs),
+                2 => attr(AttrMask::DIM, true, &mut pen.attrs),
+                3 => attr(AttrMask::ITALIC, true, &mut pen.attrs),
+                4 => attr(AttrMask::UNDERLINE, true, &mut pen.attrs),
+                5 => attr(AttrMask::BLINK, true, &mut pen.attrs),
+                7 => attr(AttrMask::REVERSE, true, &mut pen.attrs),
+                22 => attr(AttrMask::BOLD | AttrMask::DIM, false, &mut pen.attrs),
+                23 => attr(AttrMask::ITALIC, false, &mut pen.attrs),
+                24 => attr(AttrMask::UNDERLINE, false, &mut pen.attrs),
+                25 => attr(AttrMask::BLINK, false, &mut pen.attrs),
+                27 => attr(AttrMask::REVERSE, false, &mut pen.attrs),
+                30..=37 => pen.fg = Ink::Indexed((code - 30) as u8),
+                90..=97 => pen.fg = Ink::Indexed((code - 90 + 8) as u8),
+                40..=47 => pen.bg = Ink::Indexed((code - 40) as u8),
+                100..=107 => pen.bg = Ink::Indexed((code - 100 + 8) as u8),
+                38 => pen.fg = color(&mut nums),
+                48 => pen.bg = color(&mut nums),
+                39 => pen.fg = Ink::Default,
+                49 => pen.bg = Ink::Default,
+                _ => panic!("unexpected SGR {code}"),
+            }
+        }
+    }
+}
+
+/// How a terminal with `depth` colours is told `c`.
+fn ink(c: Color, depth: ColorDepth) -> Ink {
+    match depth {
+        _ if c == Color::DEFAULT => Ink::Default,
+        ColorDepth::TrueColor => Ink::Rgb(c.r(), c.g(), c.b()),
+        ColorDepth::Ansi256 => Ink::Indexed(to_ansi256(c)),
+        ColorDepth::Ansi16 => Ink::Indexed(to_ansi16(c)),
+    }
+}
+
+/// Draw `frames` in turn with a driver in `mode`, and play the output on
+/// a virtual terminal.
+fn play(mode: ColorMode, frames: &[&Frame]) -> Terminal {
+    let mut driver = CrosstermDriver::new().with_color_mode(mode);
+    let f = frames[0];
+    let mut term = Terminal::new(f.width, f.height);
+    for frame in frames {
+        let mut out = Vec::new();
+        driver.write_frame(&mut out, frame).unwrap();
+        term.play(&String::from_utf8(out).unwrap());
+    }
+    term
+}
+
+/// Check that `term` shows the cells of `case` as drawn by a terminal
+/// with `depth` colours.
+fn check(term: &Terminal, case: &Case, depth: ColorDepth) {
+    let shown = conformance::profile("crossterm").attrs;
+    for FrameCell { cell, pos } in &case.frame.cells {
+        let want = VCell {
+            ch: cell.ch,
+            fg: ink(cell.style.fg, depth),
+            bg: ink(cell.style.bg, depth),
+            attrs: cell.style.attrs & shown,
+        };
+        assert_eq!(term.at(*pos), want, "{} at {pos}, {depth:?}", case.name);
+    }
+}
+
+#[test]
+fn terminals_show_the_conformance_cases() {
+    let modes = [
+        (ColorMode::TrueColor, ColorDepth::TrueColor),
+        (ColorMode::Ansi256, ColorDepth::Ansi256),
+        (ColorMode::Ansi16, ColorDepth::Ansi16),
+    ];
+    for (mode, depth) in modes {
+        for case in conformance::cases() {
+            check(&play(mode, &[&case.frame]), &case, depth);
+
+            // Drawn over itself with the styles shifted by a cell, so
+            // that each cell follows other attributes and colours.
+            let mut shifted = case.clone();
+            let cells = &mut shifted.frame.cells;
+            let styles: Vec<_> = cells.iter().map(|fc| fc.cell.style).collect();
+            for (i, fc) in cells.iter_mut().enumerate() {
+                fc.cell.style = styles[(i + 1) % styles.len()];
+            }
+            check(&play(mode, &[&case.frame, &shifted.frame]), &shifted, depth);
+        }
+    }
+}
+
+#[test]
+fn scrolled_rows_keep_their_cells() {
+    let case = conformance::attributes();
+    let mut scrolled = case.clone();
+    for fc in &mut scrolled.frame.cells {
+        fc.pos.y = (fc.pos.y + 3) % 4;
+    }
+    let term = play(ColorMode::TrueColor, &[&case.frame, &scrolled.frame]);
+    check(&term, &scrolled, ColorDepth::TrueColor);
+}
@@ -2,6 +2,71 @@
 //!
 //! Provides a [`CrosstermDriver`] that implements [`gruid_core::Driver`],
 //! mapping gruid's grid-based rendering model to a terminal via crossterm.
+//!
+//! The terminal runs in raw mode, so Ctrl+C does not interrupt the process:
+//! it arrives as a `KeyDown` for `'c'` with [`ModMask::CTRL`], and the model
+//! decides whether to quit. Either way [`Model::on_exit`] runs before the
+//! terminal is restored.
+//!
+//! Mouse positions beyond the grid (the terminal may be larger than it)
+//! are dropped and reported as a `Leave`, as are focus losses when the
+//! terminal supports focus reporting; see [`MouseAction`].
+//!
+//! Modified special keys such as Ctrl+Arrow are reported with their
+//! modifiers; terminals supporting the kitty keyboard protocol get it
+//! enabled so that more combinations can be told apart.
+//!
+//! Frames that shift whole rows, such as a scrolling message log, are drawn
+//! by scrolling a terminal region and redrawing only the exposed rows; see
+//! [`CrosstermDriver::with_scroll_regions`].
+//!
+//! Each frame is built in memory and written in one go, with only the
+//! cursor moves, colours and attributes that change from one cell to the
+//! next. It is wrapped in a synchronized update (mode 2026), so that
+//! terminals supporting it show the whole frame at once instead of tearing
+//! half-way through; see [`CrosstermDriver::with_synchronized_output`].
+//! The terminal cursor is hidden, unless the frame shows it at
+//! [`Frame::cursor`](gruid_core::Frame::cursor), for text input.
+//!
+//! [`Effect::SetClipboard`](gruid_core::Effect::SetClipboard) is carried
+//! out with an OSC 52 escape sequence, which the terminal turns into a
+//! write to the system clipboard, even over SSH; models without effects at
+//! hand can also call [`CrosstermDriver::copy_to_clipboard`]. Most modern
+//! terminals honour it, some only after opting in, and others silently
+//! ignore it, so there is no telling whether a copy worked. Inside tmux
+//! the sequence only reaches the terminal with `set-clipboard on` or with
+//! [`CrosstermDriver::with_tmux_passthrough`].
+//!
+//! [`Effect::Suspend`](gruid_core::Effect::Suspend) gives the terminal
+//! back while its closure runs, in its normal mode and main screen, for an
+//! external editor or a Ctrl-Z stop, then takes it again and redraws.
+//!
+//! Bracketed paste is enabled, so that text pasted into the terminal
+//! arrives whole as a [`Msg::Paste`] instead of as a burst of key presses;
+//! see [`CrosstermDriver::with_bracketed_paste`].
+//!
+//! Colours are written in 24 bits on terminals that say they support it
+//! through `COLORTERM` or `TERM`, and otherwise replaced by the nearest
+//! colour of the xterm 256-colour palette or of the 16 basic colours; see
+//! [`CrosstermDriver::with_color_mode`].
+//!
+//! [`SetWindowTitle`] requests set the terminal title.
+//!
+//! At startup the driver asks the terminal for its background colour with
+//! an OSC 11 query and reports it to the model as a dark or light
+//! [`SystemTheme`]. This is best effort: many terminals and multiplexers do
+//! not answer, in which case no theme is reported; see
+//! [`CrosstermDriver::with_theme_query`].
+//!
+//! [`Model::on_exit`]: gruid_core::app::Model::on_exit
+
+mod color;
+#[cfg(test)]
+mod conformance;
+mod scroll;
+mod theme;
+
+pub use color::ColorMode;
 
 use std::io::{self, Write};
 use std::sync::mpsc::Sender;
@@ -9,31 +74,31 @@ use std::time::Duration;
 
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind},
-    execute,
-    style::{self, Attribute, Color as CtColor, SetBackgroundColor, SetForegroundColor},
+    event::{
+        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        MouseButton, MouseEvent, MouseEventKind, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
+    execute, queue,
+    style::{self, Attribute},
     terminal::{self, ClearType},
 };
 
 use gruid_core::{
-    Point,
-    app::{Context, Driver},
+    Cell, ClipboardProvider, ColorDepth, DriverCaps, Platform, Point, SetWindowTitle,
+    app::{Context, Driver, EffectHandler, effect_handler},
+    clipboard::base64,
     grid::Frame,
-    messages::{Key, ModMask, MouseAction, Msg},
+    messages::{Key, ModMask, MouseAction, MouseTracker, Msg, SystemTheme},
     style::{AttrMask, Color},
 };
 
 use std::time::Instant;
 
-/// Maps a [`gruid_core::Color`] to a [`crossterm::style::Color`].
-fn to_ct_color(c: Color) -> CtColor {
-    if c == Color::DEFAULT {
-        CtColor::Reset
-    } else {
-        let (r, g, b) = (c.r(), c.g(), c.b());
-        CtColor::Rgb { r, g, b }
-    }
-}
+use scroll::Screen;
+
+/// How long to wait for the terminal to report its background colour.
+const THEME_QUERY_TIMEOUT: Duration = Duration::from_millis(100);
 
 /// Maps crossterm key modifiers to gruid's [`ModMask`].
 fn to_mod_mask(mods: KeyModifiers) -> ModMask {
@@ -54,13 +119,18 @@ fn to_mod_mask(mods: KeyModifiers) -> ModMask {
 }
 
 /// Maps a crossterm [`KeyCode`] to a gruid [`Key`].
+///
+/// The match is deliberately exhaustive so that new crossterm variants are
+/// a compile error here rather than silently dropped keys. `None` marks
+/// keys gruid has no [`Key`] for.
 fn to_key(code: KeyCode) -> Option<Key> {
     match code {
         KeyCode::Char(c) => Some(Key::Char(c)),
         KeyCode::Enter => Some(Key::Enter),
         KeyCode::Esc => Some(Key::Escape),
         KeyCode::Backspace => Some(Key::Backspace),
-        KeyCode::Tab => Some(Key::Tab),
+        // Shift+Tab; the modifier is added by `translate_key`.
+        KeyCode::Tab | KeyCode::BackTab => Some(Key::Tab),
         KeyCode::Delete => Some(Key::Delete),
         KeyCode::Insert => Some(Key::Insert),
         KeyCode::Home => Some(Key::Home),
@@ -71,13 +141,247 @@ fn to_key(code: KeyCode) -> Option<Key> {
         KeyCode::Down => Some(Key::ArrowDown),
         KeyCode::Left => Some(Key::ArrowLeft),
         KeyCode::Right => Some(Key::ArrowRight),
-        _ => None,
+        KeyCode::F(n) => Some(Key::F(n)),
+        // Keypad 5 without NumLock, treated as Enter like the other drivers.
+        KeyCode::KeypadBegin => Some(Key::Enter),
+        // Lock and system keys, media keys and lone modifier presses (the
+        // last two only with the kitty protocol) carry no gruid key.
+        KeyCode::Null
+        | KeyCode::CapsLock
+        | KeyCode::ScrollLock
+        | KeyCode::NumLock
+        | KeyCode::PrintScreen
+        | KeyCode::Pause
+        | KeyCode::Menu
+        | KeyCode::Media(_)
+        | KeyCode::Modifier(_) => None,
+    }
+}
+
+/// Translates a crossterm key event into a `KeyDown`, keeping modifiers
+/// attached to special keys. Key releases (reported on Windows and with
+/// the kitty protocol) are dropped; repeats are only flagged there too.
+fn translate_key(ke: KeyEvent) -> Option<Msg> {
+    if ke.kind == KeyEventKind::Release {
+        return None;
+    }
+    let key = to_key(ke.code)?;
+    let mut modifiers = to_mod_mask(ke.modifiers);
+    if ke.code == KeyCode::BackTab {
+        modifiers = modifiers | ModMask::SHIFT;
+    }
+    Some(Msg::KeyDown {
+        key,
+        modifiers,
+        time: Instant::now(),
+        repeat: ke.kind == KeyEventKind::Repeat,
+    })
+}
+
+/// The press action of a crossterm mouse button.
+fn to_button(button: MouseButton) -> MouseAction {
+    match button {
+        MouseButton::Left => MouseAction::Main,
+        MouseButton::Right => MouseAction::Secondary,
+        MouseButton::Middle => MouseAction::Auxiliary,
+    }
+}
+
+/// Translates a crossterm mouse event, dropping positions outside the
+/// grid known to `tracker`.
+fn translate_mouse(me: MouseEvent, tracker: &mut MouseTracker) -> Vec<Msg> {
+    let pos = Point::new(me.column as i32, me.row as i32);
+    let modifiers = to_mod_mask(me.modifiers);
+    let action = match me.kind {
+        MouseEventKind::Down(button) => to_button(button),
+        MouseEventKind::Up(_) => MouseAction::Release,
+        MouseEventKind::ScrollUp => MouseAction::WheelUp,
+        MouseEventKind::ScrollDown => MouseAction::WheelDown,
+        MouseEventKind::Moved => return tracker.moved(pos, modifiers).collect(),
+        MouseEventKind::Drag(button) => {
+            return tracker
+                .dragged(pos, Some(to_button(button)), modifiers)
+                .collect();
+        }
+        _ => return Vec::new(),
+    };
+    tracker
+        .button(action, Some(pos), modifiers)
+        .into_iter()
+        .collect()
+}
+
+/// Terminal attributes for each [`AttrMask`] flag.
+const ATTRIBUTES: [(AttrMask, Attribute); 5] = [
+    (AttrMask::BOLD, Attribute::Bold),
+    (AttrMask::ITALIC, Attribute::Italic),
+    (AttrMask::UNDERLINE, Attribute::Underlined),
+    (AttrMask::REVERSE, Attribute::Reverse),
+    (AttrMask::DIM, Attribute::Dim),
+];
+
+/// Writes the cells of a frame, emitting only the cursor moves, colours
+/// and attributes that change from one cell to the next.
+#[derive(Debug)]
+struct CellWriter {
+    depth: ColorDepth,
+    /// Where the cursor is, if known.
+    cursor: Option<Point>,
+    fg: Option<Color>,
+    bg: Option<Color>,
+    /// Attributes set on the terminal; none between frames.
+    attrs: AttrMask,
+}
+
+impl CellWriter {
+    /// A writer for a terminal showing `depth` colours, in an unknown
+    /// state.
+    fn new(depth: ColorDepth) -> Self {
+        Self {
+            depth,
+            cursor: None,
+            fg: None,
+            bg: None,
+            attrs: AttrMask::NONE,
+        }
+    }
+
+    /// Writes `cell` at `p`.
+    fn draw(&mut self, out: &mut impl Write, p: Point, cell: &Cell) -> io::Result<()> {
+        if self.cursor != Some(p) {
+            queue!(out, cursor::MoveTo(p.x as u16, p.y as u16))?;
+        }
+        let attrs = cell.style.attrs;
+        if attrs != self.attrs {
+            // Attributes can only be unset all at once, colours included.
+            if self.attrs != AttrMask::NONE {
+                queue!(out, style::SetAttribute(Attribute::Reset))?;
+                self.fg = None;
+                self.bg = None;
+            }
+            for (mask, attr) in ATTRIBUTES {
+                if attrs.contains(mask) {
+                    queue!(out, style::SetAttribute(attr))?;
+                }
+            }
+            self.attrs = attrs;
+        }
+        if self.fg != Some(cell.style.fg) {
+            color::write_color(out, cell.style.fg, self.depth, false)?;
+            self.fg = Some(cell.style.fg);
+        }
+        if self.bg != Some(cell.style.bg) {
+            color::write_color(out, cell.style.bg, self.depth, true)?;
+            self.bg = Some(cell.style.bg);
+        }
+        write!(out, "{}", cell.ch)?;
+        // Other characters may be wide, or not printable at all.
+        self.cursor = (cell.ch.is_ascii_graphic() || cell.ch == ' ').then(|| p + Point::new(1, 0));
+        Ok(())
+    }
+
+    /// Leaves the terminal without attributes.
+    fn finish(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.attrs != AttrMask::NONE {
+            queue!(out, style::SetAttribute(Attribute::Reset))?;
+            self.attrs = AttrMask::NONE;
+        }
+        Ok(())
+    }
+}
+
+/// Largest base64 payload of an OSC 52 sequence. Terminals cap it, most
+/// at 100KB or more, and drop longer sequences; there is no way to split
+/// one clipboard write across several.
+const OSC52_MAX_PAYLOAD: usize = 100_000;
+
+/// The OSC 52 sequence setting the clipboard to `text`, wrapped in a tmux
+/// passthrough sequence if `tmux` is set, or an error if `text` is too
+/// long for terminals to accept.
+fn osc52(text: &str, tmux: bool) -> io::Result<String> {
+    let payload = base64(text.as_bytes());
+    if payload.len() > OSC52_MAX_PAYLOAD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} bytes of text are too many for OSC 52 (base64 payload over {OSC52_MAX_PAYLOAD} bytes)",
+                text.len()
+            ),
+        ));
+    }
+    let seq = format!("\x1b]52;c;{payload}\x07");
+    Ok(if tmux {
+        // DCS passthrough: escapes inside are doubled.
+        format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))
+    } else {
+        seq
+    })
+}
+
+/// Clipboard written to through OSC 52 on standard output.
+struct Osc52 {
+    tmux: bool,
+}
+
+impl Osc52 {
+    fn write(&self, out: &mut impl Write, text: &str) -> io::Result<()> {
+        out.write_all(osc52(text, self.tmux)?.as_bytes())?;
+        out.flush()
+    }
+}
+
+impl ClipboardProvider for Osc52 {
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(self.write(&mut io::stdout(), text)?)
+    }
+}
+
+/// Guesses the terminal capabilities from environment variables, looked up
+/// with `env`: `COLORTERM` and `TERM` for the colour depth, the locale for
+/// Unicode support.
+fn detect_caps(mouse: bool, env: impl Fn(&str) -> Option<String>) -> DriverCaps {
+    let color_depth = color::detect_depth(&env);
+    // The first locale variable set wins, as in setlocale(3).
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|&var| env(var).filter(|v| !v.is_empty()))
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    // Windows Terminal supports Unicode, but sets no locale variable.
+    let unicode =
+        env("WT_SESSION").is_some() || locale.contains("utf-8") || locale.contains("utf8");
+    DriverCaps {
+        mouse,
+        color_depth,
+        cell_aspect: 0.5,
+        tiles: false,
+        animation_ticks: true,
+        key_release: false,
+        clipboard: true,
+        unicode,
+        platform: Platform::Terminal,
     }
 }
 
 /// A terminal back-end for gruid using crossterm.
 pub struct CrosstermDriver {
     mouse_enabled: bool,
+    mouse: MouseTracker,
+    /// Whether the kitty keyboard protocol was enabled in `init`.
+    key_enhanced: bool,
+    scroll_regions: bool,
+    /// What the terminal shows, for scroll detection.
+    screen: Screen,
+    clipboard: Osc52,
+    theme_query: bool,
+    /// Theme of the terminal background, if it told.
+    theme: Option<SystemTheme>,
+    synchronized_output: bool,
+    bracketed_paste: bool,
+    /// Colours the terminal shows, from the [`ColorMode`].
+    color_depth: ColorDepth,
+    /// Whether the terminal cursor is shown, at [`Frame::cursor`].
+    cursor_shown: bool,
 }
 
 impl CrosstermDriver {
@@ -85,6 +389,17 @@ impl CrosstermDriver {
     pub fn new() -> Self {
         Self {
             mouse_enabled: true,
+            mouse: MouseTracker::default(),
+            key_enhanced: false,
+            scroll_regions: true,
+            screen: Screen::new(0, 0),
+            clipboard: Osc52 { tmux: false },
+            theme_query: true,
+            theme: None,
+            synchronized_output: true,
+            bracketed_paste: true,
+            color_depth: ColorMode::Auto.depth(|var| std::env::var(var).ok()),
+            cursor_shown: false,
         }
     }
 
@@ -93,6 +408,174 @@ impl CrosstermDriver {
         self.mouse_enabled = enabled;
         self
     }
+
+    /// Configure whether frames that shift whole rows, like a scrolling
+    /// log, are drawn by scrolling a terminal region instead of rewriting
+    /// the rows. Enabled by default.
+    pub fn with_scroll_regions(mut self, enabled: bool) -> Self {
+        self.scroll_regions = enabled;
+        self
+    }
+
+    /// Configure whether to ask the terminal for its background colour at
+    /// startup, to report a [`SystemTheme`]. Enabled by default; the query
+    /// delays startup by up to 100ms on terminals that answer neither it
+    /// nor the device attributes query sent after it.
+    pub fn with_theme_query(mut self, enabled: bool) -> Self {
+        self.theme_query = enabled;
+        self
+    }
+
+    /// Configure whether frames are wrapped in synchronized update
+    /// sequences (`CSI ? 2026 h` and `CSI ? 2026 l`), which tell the
+    /// terminal to show the frame only once it is complete. Enabled by
+    /// default: terminals without support ignore the unknown mode, so
+    /// this only needs disabling to work around a misbehaving one.
+    pub fn with_synchronized_output(mut self, enabled: bool) -> Self {
+        self.synchronized_output = enabled;
+        self
+    }
+
+    /// Configure whether bracketed paste mode is enabled, so that pasted
+    /// text is delivered as one [`Msg::Paste`]. Enabled by default. Without
+    /// it, terminals send pasted text as if it were typed, and the model
+    /// sees a key press per character.
+    pub fn with_bracketed_paste(mut self, enabled: bool) -> Self {
+        self.bracketed_paste = enabled;
+        self
+    }
+
+    /// Configure how colours are written. By default, with
+    /// [`ColorMode::Auto`], the `COLORTERM` and `TERM` environment
+    /// variables tell whether the terminal shows 24-bit colour, the xterm
+    /// 256-colour palette, or only the 16 basic colours; with less than 24
+    /// bits, colours are replaced by the nearest one shown. The depth used
+    /// is the one reported in [`DriverCaps::color_depth`].
+    pub fn with_color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_depth = mode.depth(|var| std::env::var(var).ok());
+        self
+    }
+
+    /// Configure whether OSC 52 clipboard writes are wrapped in a tmux
+    /// passthrough sequence. Disabled by default. tmux drops OSC 52 from
+    /// applications unless its `set-clipboard` option is `on`; passthrough
+    /// gets the sequence to the outer terminal anyway, but needs tmux 3.3
+    /// or later with `allow-passthrough on`. Outside tmux, wrapped
+    /// sequences are ignored.
+    pub fn with_tmux_passthrough(mut self, enabled: bool) -> Self {
+        self.clipboard.tmux = enabled;
+        self
+    }
+
+    /// Put `text` on the system clipboard through the terminal, with an
+    /// OSC 52 sequence. This is what
+    /// [`Effect::SetClipboard`](gruid_core::Effect::SetClipboard) does.
+    ///
+    /// Fails if `text` is longer than terminals accept (about 75KB) or
+    /// cannot be written. Success does not mean the terminal honoured the
+    /// sequence: some disable clipboard writes, or ask the user first.
+    pub fn copy_to_clipboard(&mut self, text: &str) -> io::Result<()> {
+        self.clipboard.write(&mut io::stdout(), text)
+    }
+
+    /// Write the escape sequences setting up the terminal in `init`.
+    fn write_setup(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(
+            out,
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            terminal::Clear(ClearType::All)
+        )?;
+        if self.mouse_enabled {
+            queue!(out, event::EnableMouseCapture, event::EnableFocusChange)?;
+        }
+        if self.bracketed_paste {
+            queue!(out, event::EnableBracketedPaste)?;
+        }
+        out.flush()
+    }
+
+    /// Write the escape sequences undoing [`write_setup`](Self::write_setup)
+    /// in `close`.
+    fn write_teardown(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.bracketed_paste {
+            queue!(out, event::DisableBracketedPaste)?;
+        }
+        if self.mouse_enabled {
+            queue!(out, event::DisableFocusChange, event::DisableMouseCapture)?;
+        }
+        queue!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+        out.flush()
+    }
+
+    /// Write the escape sequences drawing `frame` to `out`.
+    fn write_frame(&mut self, out: &mut impl Write, frame: &Frame) -> io::Result<()> {
+        if self.synchronized_output {
+            queue!(out, terminal::BeginSynchronizedUpdate)?;
+        }
+        if self.screen.size() != (frame.width, frame.height) {
+            self.screen = Screen::new(frame.width, frame.height);
+        }
+        let next = self.screen.next(frame);
+        let mut writer = CellWriter::new(self.color_depth);
+
+        match self
+            .scroll_regions
+            .then(|| self.screen.detect_scroll(&next))
+            .flatten()
+        {
+            Some(sc) => {
+                // Set the scroll region (1-based, inclusive), scroll it and
+                // reset it; only cells still differing are then drawn.
+                queue!(out, style::ResetColor)?;
+                write!(out, "\x1b[{};{}r", sc.top + 1, sc.bottom)?;
+                if sc.n > 0 {
+                    queue!(out, terminal::ScrollUp(sc.n as u16))?;
+                } else {
+                    queue!(out, terminal::ScrollDown((-sc.n) as u16))?;
+                }
+                write!(out, "\x1b[r")?;
+                self.screen.scroll(sc);
+                for (x, y, cell) in self.screen.diff(&next) {
+                    writer.draw(out, Point::new(x, y), &cell)?;
+                }
+            }
+            None => {
+                // In reading order, so that cells next to each other on a
+                // row need no cursor move.
+                let mut cells: Vec<_> = frame.cells.iter().collect();
+                cells.sort_by_key(|fc| (fc.pos.y, fc.pos.x));
+                for fc in cells {
+                    writer.draw(out, fc.pos, &fc.cell)?;
+                }
+            }
+        }
+        writer.finish(out)?;
+        match frame
+            .cursor
+            .filter(|&p| p.x < frame.width && p.y < frame.height)
+        {
+            Some(p) => {
+                if writer.cursor != Some(p) {
+                    queue!(out, cursor::MoveTo(p.x as u16, p.y as u16))?;
+                }
+                if !self.cursor_shown {
+                    queue!(out, cursor::Show)?;
+                    self.cursor_shown = true;
+                }
+            }
+            None if self.cursor_shown => {
+                queue!(out, cursor::Hide)?;
+                self.cursor_shown = false;
+            }
+            None => {}
+        }
+        self.screen.set(next);
+        if self.synchronized_output {
+            queue!(out, terminal::EndSynchronizedUpdate)?;
+        }
+        Ok(())
+    }
 }
 
 impl Default for CrosstermDriver {
@@ -104,16 +587,24 @@ impl Default for CrosstermDriver {
 impl Driver for CrosstermDriver {
     fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         terminal::enable_raw_mode()?;
+        // Before crossterm reads input, so that it does not see the answer.
+        if self.theme_query {
+            self.theme = theme::query_background(THEME_QUERY_TIMEOUT);
+        }
         let mut stdout = io::stdout();
-        execute!(
-            stdout,
-            terminal::EnterAlternateScreen,
-            cursor::Hide,
-            terminal::Clear(ClearType::All)
-        )?;
-        if self.mouse_enabled {
-            execute!(stdout, event::EnableMouseCapture)?;
+        self.write_setup(&mut stdout)?;
+        // Terminals speaking the kitty keyboard protocol can then report
+        // modified keys (Ctrl+Arrow, Shift+Enter, ...) unambiguously.
+        if terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            execute!(
+                stdout,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+            )?;
+            self.key_enhanced = true;
         }
+        // Until the first frame arrives the grid fills the terminal.
+        let (w, h) = terminal::size()?;
+        self.mouse.resize(w as i32, h as i32);
         Ok(())
     }
 
@@ -135,57 +626,15 @@ impl Driver for CrosstermDriver {
             let ev = event::read()?;
 
             let msg = match ev {
-                Event::Key(KeyEvent {
-                    code, modifiers, ..
-                }) => to_key(code).map(|key| Msg::KeyDown {
-                    key,
-                    modifiers: to_mod_mask(modifiers),
-                    time: Instant::now(),
-                }),
+                Event::Key(ke) => translate_key(ke),
                 Event::Mouse(me) => {
-                    let pos = Point::new(me.column as i32, me.row as i32);
-                    let modifiers = to_mod_mask(me.modifiers);
-                    match me.kind {
-                        MouseEventKind::Down(btn) => {
-                            let action = match btn {
-                                MouseButton::Left => MouseAction::Main,
-                                MouseButton::Right => MouseAction::Secondary,
-                                MouseButton::Middle => MouseAction::Auxiliary,
-                            };
-                            Some(Msg::Mouse {
-                                action,
-                                pos,
-                                modifiers,
-                                time: Instant::now(),
-                            })
-                        }
-                        MouseEventKind::Up(_) => Some(Msg::Mouse {
-                            action: MouseAction::Release,
-                            pos,
-                            modifiers,
-                            time: Instant::now(),
-                        }),
-                        MouseEventKind::Moved | MouseEventKind::Drag(_) => Some(Msg::Mouse {
-                            action: MouseAction::Move,
-                            pos,
-                            modifiers,
-                            time: Instant::now(),
-                        }),
-                        MouseEventKind::ScrollUp => Some(Msg::Mouse {
-                            action: MouseAction::WheelUp,
-                            pos,
-                            modifiers,
-                            time: Instant::now(),
-                        }),
-                        MouseEventKind::ScrollDown => Some(Msg::Mouse {
-                            action: MouseAction::WheelDown,
-                            pos,
-                            modifiers,
-                            time: Instant::now(),
-                        }),
-                        _ => None,
+                    for m in translate_mouse(me, &mut self.mouse) {
+                        tx.send(m).ok();
                     }
+                    None
                 }
+                Event::FocusLost => self.mouse.left(ModMask::NONE),
+                Event::Paste(text) => Some(Msg::Paste(text)),
                 Event::Resize(w, h) => Some(Msg::Screen {
                     width: w as i32,
                     height: h as i32,
@@ -203,59 +652,491 @@ impl Driver for CrosstermDriver {
     }
 
     fn flush(&mut self, frame: Frame) -> Result<(), Box<dyn std::error::Error>> {
+        self.mouse.resize(frame.width, frame.height);
+        let mut buf = Vec::new();
+        self.write_frame(&mut buf, &frame)?;
         let mut stdout = io::stdout();
+        stdout.write_all(&buf)?;
+        stdout.flush()?;
+        Ok(())
+    }
 
-        for fc in &frame.cells {
-            let p = fc.pos;
-            let cell = &fc.cell;
-
-            // Move cursor.
-            execute!(stdout, cursor::MoveTo(p.x as u16, p.y as u16))?;
+    fn suspend(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut stdout = io::stdout();
+        if self.key_enhanced {
+            execute!(stdout, PopKeyboardEnhancementFlags)?;
+        }
+        self.write_teardown(&mut stdout)?;
+        terminal::disable_raw_mode()?;
+        Ok(())
+    }
 
-            // Set colours.
+    fn resume(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        self.write_setup(&mut stdout)?;
+        if self.key_enhanced {
             execute!(
                 stdout,
-                SetForegroundColor(to_ct_color(cell.style.fg)),
-                SetBackgroundColor(to_ct_color(cell.style.bg))
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
             )?;
+        }
+        // The setup cleared the screen and hid the cursor.
+        self.screen = Screen::new(0, 0);
+        self.cursor_shown = false;
+        Ok(())
+    }
 
-            // Set attributes.
-            let attrs = cell.style.attrs;
-            if attrs.contains(AttrMask::BOLD) {
-                execute!(stdout, style::SetAttribute(Attribute::Bold))?;
-            }
-            if attrs.contains(AttrMask::ITALIC) {
-                execute!(stdout, style::SetAttribute(Attribute::Italic))?;
-            }
-            if attrs.contains(AttrMask::UNDERLINE) {
-                execute!(stdout, style::SetAttribute(Attribute::Underlined))?;
-            }
-            if attrs.contains(AttrMask::REVERSE) {
-                execute!(stdout, style::SetAttribute(Attribute::Reverse))?;
-            }
-            if attrs.contains(AttrMask::DIM) {
-                execute!(stdout, style::SetAttribute(Attribute::Dim))?;
-            }
+    fn size(&self) -> Option<(i32, i32)> {
+        let (w, h) = terminal::size().ok()?;
+        Some((w as i32, h as i32))
+    }
+
+    fn close(&mut self) {
+        let mut stdout = io::stdout();
+        if self.key_enhanced {
+            let _ = execute!(stdout, PopKeyboardEnhancementFlags);
+            self.key_enhanced = false;
+        }
+        let _ = self.write_teardown(&mut stdout);
+        let _ = terminal::disable_raw_mode();
+    }
+
+    fn capabilities(&self) -> DriverCaps {
+        DriverCaps {
+            color_depth: self.color_depth,
+            ..detect_caps(self.mouse_enabled, |var| std::env::var(var).ok())
+        }
+    }
+
+    fn system_theme(&self) -> Option<SystemTheme> {
+        self.theme
+    }
+
+    fn clipboard(&mut self) -> Option<&mut dyn ClipboardProvider> {
+        Some(&mut self.clipboard)
+    }
+
+    fn effect_handlers(&mut self) -> Vec<Box<dyn EffectHandler>> {
+        vec![effect_handler(|t: &SetWindowTitle| {
+            execute!(io::stdout(), terminal::SetTitle(&t.0)).is_ok()
+        })]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::{Color as CtColor, SetBackgroundColor, SetForegroundColor};
+    use gruid_core::Style;
+
+    fn mouse_event(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
 
-            // Print character.
-            write!(stdout, "{}", cell.ch)?;
+    fn actions(msgs: Vec<Msg>) -> Vec<(MouseAction, Point)> {
+        msgs.into_iter()
+            .map(|m| match m {
+                Msg::Mouse { action, pos, .. } => (action, pos),
+                other => panic!("unexpected {other}"),
+            })
+            .collect()
+    }
 
-            // Reset attributes.
-            if attrs != AttrMask::NONE {
-                execute!(stdout, style::SetAttribute(Attribute::Reset))?;
+    #[test]
+    fn key_code_table() {
+        use crossterm::event::{MediaKeyCode, ModifierKeyCode};
+        // One row per KeyCode variant; `to_key` itself is exhaustive.
+        let table = [
+            (KeyCode::Backspace, Some(Key::Backspace)),
+            (KeyCode::Enter, Some(Key::Enter)),
+            (KeyCode::Left, Some(Key::ArrowLeft)),
+            (KeyCode::Right, Some(Key::ArrowRight)),
+            (KeyCode::Up, Some(Key::ArrowUp)),
+            (KeyCode::Down, Some(Key::ArrowDown)),
+            (KeyCode::Home, Some(Key::Home)),
+            (KeyCode::End, Some(Key::End)),
+            (KeyCode::PageUp, Some(Key::PageUp)),
+            (KeyCode::PageDown, Some(Key::PageDown)),
+            (KeyCode::Tab, Some(Key::Tab)),
+            (KeyCode::BackTab, Some(Key::Tab)),
+            (KeyCode::Delete, Some(Key::Delete)),
+            (KeyCode::Insert, Some(Key::Insert)),
+            (KeyCode::F(1), Some(Key::F(1))),
+            (KeyCode::F(12), Some(Key::F(12))),
+            (KeyCode::Char('x'), Some(Key::Char('x'))),
+            (KeyCode::Null, None),
+            (KeyCode::Esc, Some(Key::Escape)),
+            (KeyCode::CapsLock, None),
+            (KeyCode::ScrollLock, None),
+            (KeyCode::NumLock, None),
+            (KeyCode::PrintScreen, None),
+            (KeyCode::Pause, None),
+            (KeyCode::Menu, None),
+            (KeyCode::KeypadBegin, Some(Key::Enter)),
+            (KeyCode::Media(MediaKeyCode::Play), None),
+            (KeyCode::Modifier(ModifierKeyCode::LeftShift), None),
+        ];
+        for (code, key) in table {
+            assert_eq!(to_key(code), key, "{code:?}");
+        }
+    }
+
+    #[test]
+    fn special_keys_keep_modifiers() {
+        let down = |code, mods| match translate_key(KeyEvent::new(code, mods)) {
+            Some(Msg::KeyDown { key, modifiers, .. }) => (key, modifiers),
+            other => panic!("unexpected {other:?}"),
+        };
+        assert_eq!(
+            down(KeyCode::Left, KeyModifiers::CONTROL),
+            (Key::ArrowLeft, ModMask::CTRL)
+        );
+        assert_eq!(
+            down(KeyCode::Up, KeyModifiers::SHIFT | KeyModifiers::ALT),
+            (Key::ArrowUp, ModMask::SHIFT | ModMask::ALT)
+        );
+        assert_eq!(
+            down(KeyCode::F(5), KeyModifiers::SHIFT),
+            (Key::F(5), ModMask::SHIFT)
+        );
+        // Some terminals report Shift+Tab without the modifier.
+        assert_eq!(
+            down(KeyCode::BackTab, KeyModifiers::NONE),
+            (Key::Tab, ModMask::SHIFT)
+        );
+
+        let mut release = KeyEvent::new(KeyCode::Left, KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        assert!(translate_key(release).is_none());
+    }
+
+    #[test]
+    fn moves_beyond_grid_synthesize_leave() {
+        // An 80x24 grid in a wider terminal.
+        let mut t = MouseTracker::new(80, 24);
+        let mv = |x, y| mouse_event(MouseEventKind::Moved, x, y);
+        assert_eq!(
+            actions(translate_mouse(mv(79, 3), &mut t)),
+            [
+                (MouseAction::Enter, Point::new(79, 3)),
+                (MouseAction::Move, Point::new(79, 3))
+            ]
+        );
+        assert_eq!(
+            actions(translate_mouse(mv(80, 3), &mut t)),
+            [(MouseAction::Leave, Point::new(79, 3))]
+        );
+        assert!(translate_mouse(mv(100, 30), &mut t).is_empty());
+        assert!(
+            translate_mouse(
+                mouse_event(MouseEventKind::Down(MouseButton::Left), 90, 3),
+                &mut t
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            actions(translate_mouse(
+                mouse_event(MouseEventKind::Up(MouseButton::Left), 90, 3),
+                &mut t
+            )),
+            [(MouseAction::Release, Point::new(79, 3))]
+        );
+    }
+
+    #[test]
+    fn clicks_inside_grid_pass_through() {
+        let mut t = MouseTracker::new(10, 10);
+        assert_eq!(
+            actions(translate_mouse(
+                mouse_event(MouseEventKind::Down(MouseButton::Right), 4, 5),
+                &mut t
+            )),
+            [(MouseAction::Secondary, Point::new(4, 5))]
+        );
+        assert_eq!(
+            actions(translate_mouse(
+                mouse_event(MouseEventKind::Drag(MouseButton::Right), 5, 5),
+                &mut t
+            )),
+            [
+                (MouseAction::Enter, Point::new(5, 5)),
+                (MouseAction::SecondaryDrag, Point::new(5, 5))
+            ]
+        );
+    }
+
+    #[test]
+    fn drags_report_held_button() {
+        let mut t = MouseTracker::new(10, 10);
+        let mut at = |kind, x| actions(translate_mouse(mouse_event(kind, x, 1), &mut t));
+        at(MouseEventKind::Moved, 0);
+        assert_eq!(
+            at(MouseEventKind::Drag(MouseButton::Left), 1),
+            [(MouseAction::MainDrag, Point::new(1, 1))]
+        );
+        assert_eq!(
+            at(MouseEventKind::Drag(MouseButton::Middle), 2),
+            [(MouseAction::AuxiliaryDrag, Point::new(2, 1))]
+        );
+        assert_eq!(
+            at(MouseEventKind::Moved, 3),
+            [(MouseAction::Move, Point::new(3, 1))]
+        );
+        // A drag off the grid leaves it, like a plain move.
+        assert_eq!(
+            at(MouseEventKind::Drag(MouseButton::Left), 12),
+            [(MouseAction::Leave, Point::new(3, 1))]
+        );
+    }
+
+    #[test]
+    fn caps_from_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |k: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == k)
+                    .map(|(_, v)| v.to_string())
             }
+        };
+
+        let caps = detect_caps(
+            true,
+            env(&[
+                ("TERM", "xterm-256color"),
+                ("COLORTERM", "truecolor"),
+                ("LANG", "en_US.UTF-8"),
+            ]),
+        );
+        assert_eq!(caps.color_depth, ColorDepth::TrueColor);
+        assert!(caps.mouse && caps.unicode && caps.animation_ticks && !caps.key_release);
+        assert!(!caps.tiles && caps.clipboard);
+        assert_eq!(caps.platform, Platform::Terminal);
+        assert_eq!(caps.cell_aspect, 0.5);
+
+        let caps = detect_caps(false, env(&[("TERM", "screen-256color"), ("LANG", "C")]));
+        assert_eq!(caps.color_depth, ColorDepth::Ansi256);
+        assert!(!caps.mouse && !caps.unicode);
+
+        // LC_ALL overrides LANG; an empty one does not count.
+        let caps = detect_caps(true, env(&[("LC_ALL", "C"), ("LANG", "fr_FR.utf8")]));
+        assert!(!caps.unicode);
+        let caps = detect_caps(true, env(&[("LC_ALL", ""), ("LANG", "fr_FR.utf8")]));
+        assert!(caps.unicode);
+
+        let caps = detect_caps(true, env(&[("TERM", "vt100")]));
+        assert_eq!(caps.color_depth, ColorDepth::Ansi16);
+        let caps = detect_caps(true, env(&[("WT_SESSION", "1")]));
+        assert_eq!(caps.color_depth, ColorDepth::TrueColor);
+        assert!(caps.unicode);
+    }
+
+    fn frame(cells: &[(i32, char)]) -> Frame {
+        Frame {
+            cells: cells
+                .iter()
+                .map(|&(x, ch)| gruid_core::grid::FrameCell {
+                    cell: Cell::default().with_char(ch),
+                    pos: Point::new(x, 0),
+                })
+                .collect(),
+            width: 4,
+            height: 1,
+            time_ms: 0,
+            cursor: None,
         }
+    }
 
-        stdout.flush()?;
-        Ok(())
+    #[test]
+    fn frames_are_synchronized() {
+        let mut driver = CrosstermDriver::new();
+        let mut out = Vec::new();
+        driver
+            .write_frame(&mut out, &frame(&[(0, 'a'), (3, 'z')]))
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("\x1b[?2026h"), "{out:?}");
+        assert!(out.ends_with("\x1b[?2026l"), "{out:?}");
+        assert_eq!(out.matches("2026").count(), 2);
+        assert!(out.contains('a') && out.contains('z'));
+
+        let mut driver = CrosstermDriver::new().with_synchronized_output(false);
+        let mut out = Vec::new();
+        driver.write_frame(&mut out, &frame(&[(0, 'a')])).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains("2026"), "{out:?}");
+        assert!(out.contains('a'));
     }
 
-    fn close(&mut self) {
-        let mut stdout = io::stdout();
-        if self.mouse_enabled {
-            let _ = execute!(stdout, event::DisableMouseCapture);
+    #[test]
+    fn frames_emit_only_changes() {
+        let mut driver = CrosstermDriver::new().with_synchronized_output(false);
+        let red = Color::from_rgb(255, 0, 0);
+        let mut cells = Vec::new();
+        for y in 0..4 {
+            for x in (0..20).rev() {
+                let mut cell = Cell::default().with_char('#');
+                if x >= 10 {
+                    cell.style = cell.style.with_fg(red);
+                }
+                cells.push(gruid_core::grid::FrameCell {
+                    cell,
+                    pos: Point::new(x, y),
+                });
+            }
         }
-        let _ = execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen);
-        let _ = terminal::disable_raw_mode();
+        let frame = Frame {
+            cells,
+            width: 20,
+            height: 4,
+            time_ms: 0,
+            cursor: None,
+        };
+        let mut out = Vec::new();
+        driver.write_frame(&mut out, &frame).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches('#').count(), 80);
+        // Per row: a move and two colour changes; the background is set once.
+        assert!(out.matches('\x1b').count() <= 4 * 3 + 1, "{out:?}");
+    }
+
+    #[test]
+    fn attributes_reset_colours() {
+        let mut driver = CrosstermDriver::new()
+            .with_synchronized_output(false)
+            .with_color_mode(ColorMode::TrueColor);
+        let red = Color::from_rgb(255, 0, 0);
+        let style = Style::default().with_fg(red);
+        let mut frame = frame(&[(0, 'a'), (1, 'b'), (2, 'c')]);
+        frame.cells[0].cell.style = style.with_attrs(AttrMask::BOLD);
+        frame.cells[1].cell.style = style;
+        let mut out = Vec::new();
+        driver.write_frame(&mut out, &frame).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            cursor::MoveTo(0, 0),
+            style::SetAttribute(Attribute::Bold),
+            SetForegroundColor(CtColor::Rgb { r: 255, g: 0, b: 0 }),
+            SetBackgroundColor(CtColor::Reset),
+            style::Print('a'),
+            style::SetAttribute(Attribute::Reset),
+            SetForegroundColor(CtColor::Rgb { r: 255, g: 0, b: 0 }),
+            SetBackgroundColor(CtColor::Reset),
+            style::Print('b'),
+            SetForegroundColor(CtColor::Reset),
+            style::Print('c'),
+        )
+        .unwrap();
+        assert_eq!(out, String::from_utf8(expected).unwrap());
+    }
+
+    #[test]
+    fn cursor_follows_frames() {
+        let mut driver = CrosstermDriver::new().with_synchronized_output(false);
+        let mut write = |cells: &[(i32, char)], cursor| {
+            let mut f = frame(cells);
+            f.cursor = cursor;
+            let mut out = Vec::new();
+            driver.write_frame(&mut out, &f).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let (show, hide) = ("\x1b[?25h", "\x1b[?25l");
+
+        // Shown after the cells, moved to only if not already there.
+        let out = write(&[(0, 'a')], Some(Point::new(1, 0)));
+        assert!(out.ends_with(&format!("a{show}")), "{out:?}");
+        let out = write(&[(0, 'b')], Some(Point::new(3, 0)));
+        assert!(out.ends_with("b\x1b[1;4H"), "{out:?}");
+        assert_eq!(write(&[], Some(Point::new(2, 0))), "\x1b[1;3H");
+        // Hidden once, and outside the frame too.
+        assert_eq!(write(&[], None), hide);
+        assert_eq!(write(&[], None), "");
+        assert_eq!(write(&[], Some(Point::new(4, 0))), "");
+    }
+
+    #[test]
+    fn colour_modes_quantize_frames() {
+        let mut f = frame(&[(0, 'a')]);
+        f.cells[0].cell.style = Style::default().with_fg(Color::from_rgb(250, 10, 10));
+        let draw = |mode| {
+            let mut driver = CrosstermDriver::new()
+                .with_synchronized_output(false)
+                .with_color_mode(mode);
+            assert_eq!(driver.capabilities().color_depth, mode.depth(|_| None));
+            let mut out = Vec::new();
+            driver.write_frame(&mut out, &f).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert!(draw(ColorMode::TrueColor).contains("\x1b[38;2;250;10;10m"));
+        assert!(draw(ColorMode::Ansi256).contains("\x1b[38;5;196m"));
+        assert!(draw(ColorMode::Ansi16).contains("\x1b[91m"));
+    }
+
+    #[test]
+    fn osc52_encoding() {
+        let seq = |text| osc52(text, false).unwrap();
+        assert_eq!(seq("seed 42"), "\x1b]52;c;c2VlZCA0Mg==\x07");
+        assert_eq!(seq("a\nb"), "\x1b]52;c;YQpi\x07");
+        assert_eq!(seq(""), "\x1b]52;c;\x07");
+    }
+
+    #[test]
+    fn osc52_tmux_passthrough() {
+        assert_eq!(
+            osc52("seed 42", true).unwrap(),
+            "\x1bPtmux;\x1b\x1b]52;c;c2VlZCA0Mg==\x07\x1b\\"
+        );
+        let mut out = Vec::new();
+        Osc52 { tmux: true }.write(&mut out, "a\nb").unwrap();
+        assert_eq!(out, b"\x1bPtmux;\x1b\x1b]52;c;YQpi\x07\x1b\\");
+    }
+
+    #[test]
+    fn osc52_size_limit() {
+        // 75000 bytes encode to exactly the largest payload.
+        let fits = "x".repeat(75_000);
+        let seq = osc52(&fits, false).unwrap();
+        assert_eq!(seq.len(), OSC52_MAX_PAYLOAD + "\x1b]52;c;\x07".len());
+        let err = osc52(&format!("{fits}x"), true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let mut out = Vec::new();
+        assert!(
+            Osc52 { tmux: false }
+                .write(&mut out, &format!("{fits}x"))
+                .is_err()
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn bracketed_paste_toggled_in_setup_and_teardown() {
+        let sequences = |driver: &CrosstermDriver| {
+            let (mut setup, mut teardown) = (Vec::new(), Vec::new());
+            driver.write_setup(&mut setup).unwrap();
+            driver.write_teardown(&mut teardown).unwrap();
+            (
+                String::from_utf8(setup).unwrap(),
+                String::from_utf8(teardown).unwrap(),
+            )
+        };
+        let (setup, teardown) = sequences(&CrosstermDriver::new());
+        assert!(setup.contains("\x1b[?2004h"));
+        assert!(!setup.contains("\x1b[?2004l"));
+        assert!(teardown.contains("\x1b[?2004l"));
+        assert!(!teardown.contains("\x1b[?2004h"));
+
+        let (setup, teardown) = sequences(&CrosstermDriver::new().with_bracketed_paste(false));
+        assert!(!setup.contains("?2004"));
+        assert!(!teardown.contains("?2004"));
+        // The rest of the setup is still there.
+        assert!(setup.contains("\x1b[?1049h"));
+        assert!(teardown.contains("\x1b[?1049l"));
     }
 }
@@ -0,0 +1,214 @@
+//! Scroll detection: recognizing frames that shift whole rows, so they can
+//! be drawn with a terminal scroll region instead of rewriting every cell.
+
+use gruid_core::{Cell, grid::Frame};
+
+/// Stands for terminal cells whose contents are unknown, such as rows
+/// exposed by a scroll. Never equal to a cell a model draws.
+fn unknown() -> Cell {
+    Cell::default().with_char('\0')
+}
+
+/// Scrolled rows must save at least this many row rewrites.
+const MIN_SAVED_ROWS: i32 = 2;
+
+/// A row scroll within `top..bottom`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Scroll {
+    pub top: i32,
+    /// Exclusive.
+    pub bottom: i32,
+    /// Rows moved up if positive, down if negative.
+    pub n: i32,
+}
+
+/// The terminal contents as last written by the driver.
+pub(crate) struct Screen {
+    width: i32,
+    height: i32,
+    cells: Vec<Cell>,
+}
+
+impl Screen {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); (width.max(0) * height.max(0)) as usize],
+        }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    fn row(cells: &[Cell], width: i32, y: i32) -> &[Cell] {
+        let w = width as usize;
+        &cells[y as usize * w..(y as usize + 1) * w]
+    }
+
+    /// The screen contents once `frame` is applied.
+    pub fn next(&self, frame: &Frame) -> Vec<Cell> {
+        let mut next = self.cells.clone();
+        for fc in &frame.cells {
+            let p = fc.pos;
+            if p.x >= 0 && p.y >= 0 && p.x < self.width && p.y < self.height {
+                next[(p.y * self.width + p.x) as usize] = fc.cell;
+            }
+        }
+        next
+    }
+
+    /// Find the row scroll that best explains the change from the current
+    /// contents to `next`, if one saves enough work to be worth it.
+    ///
+    /// Only whole rows are compared: a pane scrolling next to a static
+    /// side bar is not recognized.
+    pub fn detect_scroll(&self, next: &[Cell]) -> Option<Scroll> {
+        let w = self.width;
+        let curr = |y| Self::row(&self.cells, w, y);
+        let new = |y| Self::row(next, w, y);
+        let changed: Vec<i32> = (0..self.height).filter(|&y| curr(y) != new(y)).collect();
+        let (&top, &last) = (changed.first()?, changed.last()?);
+        let bottom = last + 1;
+        let span = bottom - top;
+
+        let mut best: Option<(i32, Scroll)> = None;
+        for n in (1..span).flat_map(|n| [n, -n]) {
+            // Rows that changed but reappear `n` rows away.
+            let saved = (top..bottom)
+                .filter(|&y| {
+                    let src = y + n;
+                    src >= top && src < bottom && curr(y) != new(y) && curr(src) == new(y)
+                })
+                .count() as i32;
+            if saved < MIN_SAVED_ROWS || saved <= n.abs() {
+                continue;
+            }
+            if best.is_none_or(|(s, _)| saved > s) {
+                best = Some((saved, Scroll { top, bottom, n }));
+            }
+        }
+        best.map(|(_, s)| s)
+    }
+
+    /// Apply a scroll as the terminal does, marking exposed rows unknown.
+    pub fn scroll(&mut self, s: Scroll) {
+        let w = self.width as usize;
+        let n = s.n.unsigned_abs() as usize;
+        let (top, bottom) = (s.top as usize * w, s.bottom as usize * w);
+        let region = &mut self.cells[top..bottom];
+        if s.n > 0 {
+            region.copy_within(n * w.., 0);
+            let len = region.len();
+            region[len - n * w..].fill(unknown());
+        } else {
+            region.copy_within(..region.len() - n * w, n * w);
+            region[..n * w].fill(unknown());
+        }
+    }
+
+    /// Positions (as `(x, y)`) and cells where `next` differs from the
+    /// current contents, in row-major order.
+    pub fn diff<'a>(&'a self, next: &'a [Cell]) -> impl Iterator<Item = (i32, i32, Cell)> + 'a {
+        let w = self.width;
+        self.cells
+            .iter()
+            .zip(next)
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(move |(i, (_, &c))| (i as i32 % w, i as i32 / w, c))
+    }
+
+    /// Replace the contents with `next`.
+    pub fn set(&mut self, next: Vec<Cell>) {
+        self.cells = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::{Point, grid::FrameCell};
+
+    fn screen(rows: &[&str]) -> Screen {
+        let mut s = Screen::new(rows[0].len() as i32, rows.len() as i32);
+        s.cells = cells(rows);
+        s
+    }
+
+    fn cells(rows: &[&str]) -> Vec<Cell> {
+        rows.iter()
+            .flat_map(|r| r.chars().map(|c| Cell::default().with_char(c)))
+            .collect()
+    }
+
+    #[test]
+    fn detects_log_scroll() {
+        let before = screen(&["title", "one..", "two..", "three", "four.", "stat."]);
+        let after = cells(&["title", "two..", "three", "four.", "five.", "stat."]);
+        let s = before.detect_scroll(&after).unwrap();
+        assert_eq!(
+            s,
+            Scroll {
+                top: 1,
+                bottom: 5,
+                n: 1
+            }
+        );
+
+        let mut scrolled = screen(&["title", "one..", "two..", "three", "four.", "stat."]);
+        scrolled.scroll(s);
+        // Only the exposed row remains to be drawn.
+        let redraw: Vec<i32> = scrolled.diff(&after).map(|(_, y, _)| y).collect();
+        assert_eq!(redraw, [4; 5]);
+
+        // And the other way.
+        let s = screen(&["a", "b", "c", "d", "e"])
+            .detect_scroll(&cells(&["x", "y", "a", "b", "c"]))
+            .unwrap();
+        assert_eq!(
+            s,
+            Scroll {
+                top: 0,
+                bottom: 5,
+                n: -2
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_frames_that_are_not_scrolls() {
+        let before = screen(&["aaaa", "bbbb", "cccc", "dddd"]);
+        // Unrelated changes.
+        assert!(
+            before
+                .detect_scroll(&cells(&["aaaa", "xxxx", "yyyy", "dddd"]))
+                .is_none()
+        );
+        // A single shifted row saves too little.
+        assert!(
+            before
+                .detect_scroll(&cells(&["aaaa", "cccc", "zzzz", "dddd"]))
+                .is_none()
+        );
+        // No change at all.
+        assert!(before.detect_scroll(&before.cells).is_none());
+    }
+
+    #[test]
+    fn next_applies_frame() {
+        let s = screen(&["ab", "cd"]);
+        let frame = Frame {
+            cells: vec![FrameCell {
+                cell: Cell::default().with_char('X'),
+                pos: Point::new(1, 1),
+            }],
+            width: 2,
+            height: 2,
+            time_ms: 0,
+            cursor: None,
+        };
+        assert_eq!(s.next(&frame), cells(&["ab", "cX"]));
+    }
+}
@@ -0,0 +1,166 @@
+//! Terminal background detection: asking the terminal for its background
+//! colour with an OSC 11 query, to tell dark themes from light ones.
+//!
+//! This is best effort. Terminals that do not answer OSC 11, multiplexers
+//! that do not forward it, and non-Unix platforms give no theme. Keys typed
+//! while the answer is awaited are lost.
+
+use std::time::Duration;
+
+use gruid_core::messages::SystemTheme;
+
+/// The OSC 11 background colour query, followed by a primary device
+/// attributes (DA1) query that every terminal answers: its answer ends the
+/// wait early when the terminal ignores the first query.
+#[cfg_attr(not(unix), allow(dead_code))]
+const QUERY: &[u8] = b"\x1b]11;?\x07\x1b[c";
+
+/// How long to keep reading for the DA1 answer once the timeout has passed,
+/// so that late answers are consumed here instead of arriving as keys.
+#[cfg_attr(not(unix), allow(dead_code))]
+const DRAIN_LIMIT: Duration = Duration::from_secs(1);
+
+/// Background colour in an OSC 11 answer such as
+/// `ESC ] 11 ; rgb:1c1c/1c1c/1c1c BEL`, as 16-bit channels. Channels may
+/// have 1 to 4 hex digits, and the answer may end with BEL or ST.
+fn parse_osc11(buf: &[u8]) -> Option<[u16; 3]> {
+    const PREFIX: &[u8] = b"\x1b]11;rgb:";
+    let start = buf.windows(PREFIX.len()).position(|w| w == PREFIX)? + PREFIX.len();
+    let rest = &buf[start..];
+    let end = rest.iter().position(|&b| b == 0x07 || b == 0x1b)?;
+    let body = std::str::from_utf8(&rest[..end]).ok()?;
+    let mut rgb = [0u16; 3];
+    let mut channels = body.split('/');
+    for c in &mut rgb {
+        let hex = channels.next()?;
+        if hex.is_empty() || hex.len() > 4 {
+            return None;
+        }
+        let v = u32::from_str_radix(hex, 16).ok()?;
+        // Scale to 16 bits: "f" and "ffff" are both full intensity.
+        let max = (1u32 << (4 * hex.len())) - 1;
+        *c = (v * 0xffff / max) as u16;
+    }
+    channels.next().is_none().then_some(rgb)
+}
+
+/// Whether a background colour is dark, going by its perceived luminance.
+fn classify(rgb: [u16; 3]) -> SystemTheme {
+    let [r, g, b] = rgb.map(|c| c as f64 / 65535.0);
+    if 0.299 * r + 0.587 * g + 0.114 * b < 0.5 {
+        SystemTheme::Dark
+    } else {
+        SystemTheme::Light
+    }
+}
+
+/// Whether `buf` holds a complete DA1 answer, `ESC [ ? ... c`.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn has_da1(buf: &[u8]) -> bool {
+    buf.windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|i| buf[i..].contains(&b'c'))
+}
+
+/// Ask the terminal on standard input and output for its background colour,
+/// waiting at most `timeout` for the answer. The terminal must already be
+/// in raw mode.
+///
+/// Input is then read until the DA1 answer, for at most [`DRAIN_LIMIT`]
+/// more, so that an OSC 11 answer arriving late is not injected as
+/// keystrokes. Such a late answer gives no theme.
+#[cfg(unix)]
+pub(crate) fn query_background(timeout: Duration) -> Option<SystemTheme> {
+    use std::io::Write;
+    use std::time::Instant;
+
+    // SAFETY: isatty only inspects the descriptor.
+    let is_tty = |fd| unsafe { libc::isatty(fd) } == 1;
+    if !is_tty(libc::STDIN_FILENO) || !is_tty(libc::STDOUT_FILENO) {
+        return None;
+    }
+    let mut stdout = std::io::stdout();
+    stdout.write_all(QUERY).ok()?;
+    stdout.flush().ok()?;
+
+    let deadline = Instant::now() + timeout;
+    let drain_deadline = deadline + DRAIN_LIMIT;
+    let mut buf = Vec::new();
+    let mut answered = 0;
+    let mut chunk = [0u8; 64];
+    while !has_da1(&buf) {
+        if Instant::now() < deadline {
+            answered = buf.len();
+        }
+        let left = drain_deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        let mut fds = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ms = left.as_millis().max(1) as libc::c_int;
+        // SAFETY: `fds` is one valid pollfd.
+        if unsafe { libc::poll(&mut fds, 1, ms) } <= 0 {
+            break;
+        }
+        // SAFETY: `chunk` is writable for its whole length.
+        let n = unsafe { libc::read(libc::STDIN_FILENO, chunk.as_mut_ptr().cast(), chunk.len()) };
+        if n <= 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n as usize]);
+    }
+    if Instant::now() < deadline {
+        answered = buf.len();
+    }
+    parse_osc11(&buf[..answered]).map(classify)
+}
+
+/// Background detection needs Unix terminal I/O.
+#[cfg(not(unix))]
+pub(crate) fn query_background(_timeout: Duration) -> Option<SystemTheme> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_answers() {
+        let dark = b"\x1b]11;rgb:1c1c/1c1c/1c1c\x07\x1b[?62;22c";
+        assert_eq!(parse_osc11(dark), Some([0x1c1c; 3]));
+        assert!(has_da1(dark));
+        // ST terminator, short channels, and noise before the answer.
+        let light = b"x\x1b]11;rgb:ff/fa/f0\x1b\\";
+        assert_eq!(parse_osc11(light), Some([0xffff, 0xfafa, 0xf0f0]));
+        assert_eq!(
+            parse_osc11(b"\x1b]11;rgb:f/8/0\x07"),
+            Some([0xffff, 0x8888, 0])
+        );
+        assert!(!has_da1(light));
+        // Only the DA1 answer: the terminal ignored OSC 11.
+        assert_eq!(parse_osc11(b"\x1b[?1;2c"), None);
+        // Unterminated, malformed or with a missing channel.
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:ffff/ffff/ff"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:ffff/gg/ffff\x07"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:1/2/3/4\x07"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:12345/0/0\x07"), None);
+    }
+
+    #[test]
+    fn classify_backgrounds() {
+        assert_eq!(classify([0; 3]), SystemTheme::Dark);
+        assert_eq!(classify([0x1c1c; 3]), SystemTheme::Dark);
+        assert_eq!(classify([0xffff, 0xfafa, 0xf0f0]), SystemTheme::Light);
+        // Solarized light.
+        assert_eq!(classify([0xfdfd, 0xf6f6, 0xe3e3]), SystemTheme::Light);
+        // Pure blue is dark, pure green light.
+        assert_eq!(classify([0, 0, 0xffff]), SystemTheme::Dark);
+        assert_eq!(classify([0, 0xffff, 0]), SystemTheme::Light);
+    }
+}
@@ -0,0 +1,151 @@
+//! Packing glyph bitmaps into a single-channel texture atlas.
+
+use std::collections::HashMap;
+
+/// Where a bitmap was packed, in texels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasEntry {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl AtlasEntry {
+    /// The rectangle as `[x, y, w, h]`.
+    pub fn rect(&self) -> [f32; 4] {
+        [self.x as f32, self.y as f32, self.w as f32, self.h as f32]
+    }
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
+pub enum AtlasKey {
+    Char(char),
+    // Tile(char, u32, u32), // reserved for future tile-variant caching
+}
+
+/// Bitmaps packed left to right in rows, growing downwards.
+pub struct GlyphAtlas {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>, // single-channel (R8)
+    entries: HashMap<AtlasKey, AtlasEntry>,
+    cursor_x: u32,
+    cursor_y: u32,
+    row_height: u32,
+}
+
+impl GlyphAtlas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; (width * height) as usize],
+            entries: HashMap::new(),
+            cursor_x: 0,
+            cursor_y: 0,
+            row_height: 0,
+        }
+    }
+
+    /// Number of packed bitmaps.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing was packed yet.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Insert a glyph bitmap into the atlas. Returns the rect. A key
+    /// already present keeps its first bitmap.
+    pub fn insert(&mut self, key: AtlasKey, bitmap: &[u8], w: u32, h: u32) -> AtlasEntry {
+        if let Some(e) = self.entries.get(&key) {
+            return *e;
+        }
+
+        // Advance to next row if needed
+        if self.cursor_x + w > self.width {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_height;
+            self.row_height = 0;
+        }
+
+        // Grow atlas if needed (double height)
+        while self.cursor_y + h > self.height {
+            let old_h = self.height;
+            self.height *= 2;
+            self.data.resize((self.width * self.height) as usize, 0);
+            log::debug!(
+                "Atlas grew to {}x{} (was {})",
+                self.width,
+                old_h,
+                self.height
+            );
+        }
+
+        let x = self.cursor_x;
+        let y = self.cursor_y;
+
+        // Copy bitmap into atlas
+        for row in 0..h {
+            let src_start = (row * w) as usize;
+            let dst_start = ((y + row) * self.width + x) as usize;
+            let len = w as usize;
+            if src_start + len <= bitmap.len() && dst_start + len <= self.data.len() {
+                self.data[dst_start..dst_start + len]
+                    .copy_from_slice(&bitmap[src_start..src_start + len]);
+            }
+        }
+
+        self.cursor_x += w;
+        self.row_height = self.row_height.max(h);
+
+        let entry = AtlasEntry { x, y, w, h };
+        self.entries.insert(key, entry);
+        entry
+    }
+
+    pub fn get(&self, key: &AtlasKey) -> Option<&AtlasEntry> {
+        self.entries.get(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_rows_and_grows() {
+        let entry = |x, y, w, h| AtlasEntry { x, y, w, h };
+        let mut atlas = GlyphAtlas::new(8, 4);
+        let a = atlas.insert(AtlasKey::Char('a'), &[1; 15], 5, 3);
+        let b = atlas.insert(AtlasKey::Char('b'), &[2; 6], 3, 2);
+        assert_eq!(a, entry(0, 0, 5, 3));
+        assert_eq!(b, entry(5, 0, 3, 2));
+        // No room left on the row: the next one starts below the tallest.
+        let c = atlas.insert(AtlasKey::Char('c'), &[3; 8], 4, 2);
+        assert_eq!(c, entry(0, 3, 4, 2));
+        assert_eq!((atlas.width, atlas.height), (8, 8));
+        assert_eq!(atlas.data.len(), 64);
+
+        let row = |y: usize| &atlas.data[y * 8..y * 8 + 8];
+        assert_eq!(row(0), [1, 1, 1, 1, 1, 2, 2, 2]);
+        assert_eq!(row(2), [1, 1, 1, 1, 1, 0, 0, 0]);
+        assert_eq!(row(4), [3, 3, 3, 3, 0, 0, 0, 0]);
+        assert_eq!(atlas.get(&AtlasKey::Char('c')), Some(&c));
+    }
+
+    #[test]
+    fn keys_are_packed_once() {
+        let mut atlas = GlyphAtlas::new(16, 16);
+        assert!(atlas.is_empty());
+        let first = atlas.insert(AtlasKey::Char('x'), &[7; 4], 2, 2);
+        let again = atlas.insert(AtlasKey::Char('x'), &[9; 4], 2, 2);
+        assert_eq!(first, again);
+        assert_eq!(atlas.len(), 1);
+        assert_eq!(atlas.data[0], 7);
+        assert_eq!(atlas.get(&AtlasKey::Char('y')), None);
+    }
+}
@@ -0,0 +1,29 @@
+//! System clipboard through arboard.
+
+use gruid_core::ClipboardProvider;
+
+/// The system clipboard, opened on first use and then kept open: on X11 the
+/// contents are served by the process, and are lost when it is closed.
+#[derive(Default)]
+pub struct SystemClipboard(Option<arboard::Clipboard>);
+
+impl ClipboardProvider for SystemClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let clipboard = match &mut self.0 {
+            Some(c) => c,
+            None => self.0.insert(arboard::Clipboard::new()?),
+        };
+        clipboard.set_text(text)?;
+        Ok(())
+    }
+}
+
+/// The text on the system clipboard, if there is any. Read through a
+/// clipboard of its own, since the one writes go through belongs to the
+/// runner. Drivers read it for the keys [`is_paste_key`] accepts.
+///
+/// [`is_paste_key`]: crate::input::is_paste_key
+pub fn paste_text() -> Option<String> {
+    let text = arboard::Clipboard::new().ok()?.get_text().ok()?;
+    (!text.is_empty()).then_some(text)
+}
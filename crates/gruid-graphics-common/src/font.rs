@@ -0,0 +1,177 @@
+//! Glyph rasterization with fontdue, and the cell size it implies.
+
+use std::collections::HashMap;
+
+use fontdue::{Font, FontSettings};
+
+/// Font used when the driver configuration gives none.
+pub const FALLBACK_FONT: &[u8] = include_bytes!("builtin_font.ttf");
+
+/// Symbols a font must have for [`DriverCaps::unicode`](gruid_core::DriverCaps::unicode).
+pub const UNICODE_SAMPLE: &[char] = &['○', '●', '·', '─', '│', '█', '░', '→'];
+
+/// A rasterized glyph: one alpha value per pixel, row by row.
+pub struct Glyph {
+    pub bitmap: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    /// Left edge, relative to the cell's.
+    pub x: i32,
+    /// Distance from the baseline up to the bottom edge.
+    pub ymin: i32,
+}
+
+impl Glyph {
+    /// Top edge relative to the cell's, for a cell whose baseline is
+    /// `baseline` pixels from its top.
+    pub fn top(&self, baseline: i32) -> i32 {
+        baseline - self.ymin - self.height as i32
+    }
+}
+
+/// A font at a fixed pixel size, with a cache of its glyphs.
+pub struct CellFont {
+    font: Font,
+    size: f32,
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl CellFont {
+    /// Parse `data`, or the built-in font, for drawing at `size` pixels.
+    ///
+    /// # Panics
+    ///
+    /// If `data` is not a TTF or OTF font.
+    pub fn new(data: Option<&[u8]>, size: f32) -> Self {
+        let data = data.unwrap_or(FALLBACK_FONT);
+        let font = Font::from_bytes(data, FontSettings::default()).expect("failed to parse font");
+        Self {
+            font,
+            size,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// Cell size in pixels: the line height by the advance of 'M'.
+    pub fn cell_size(&self) -> (usize, usize) {
+        let metrics =
+            self.font
+                .horizontal_line_metrics(self.size)
+                .unwrap_or(fontdue::LineMetrics {
+                    ascent: self.size * 0.8,
+                    descent: -(self.size * 0.2),
+                    line_gap: 0.0,
+                    new_line_size: self.size,
+                });
+        let ch = (metrics.ascent - metrics.descent).ceil() as usize;
+        let cw = self.font.metrics('M', self.size).advance_width.ceil() as usize;
+        (cw.max(1), ch.max(1))
+    }
+
+    /// Distance in pixels from the top of a cell of height `cell_height`
+    /// to the baseline.
+    pub fn baseline(&self, cell_height: usize) -> i32 {
+        self.font
+            .horizontal_line_metrics(self.size)
+            .map(|m| m.ascent.ceil() as i32)
+            .unwrap_or(cell_height as i32)
+    }
+
+    /// Whether the font has the symbols of [`UNICODE_SAMPLE`].
+    pub fn has_unicode_symbols(&self) -> bool {
+        UNICODE_SAMPLE.iter().all(|&c| self.has_glyph(c))
+    }
+
+    /// Whether the font has a glyph for `ch`, rather than drawing its
+    /// missing-glyph symbol.
+    pub fn has_glyph(&self, ch: char) -> bool {
+        self.font.lookup_glyph_index(ch) != 0
+    }
+
+    /// The glyph of `ch`, rasterized on first use. Blank characters and
+    /// glyphs without ink give `None`.
+    pub fn glyph(&mut self, ch: char) -> Option<&Glyph> {
+        if ch == ' ' || ch == '\0' {
+            return None;
+        }
+        let glyph = self.glyphs.entry(ch).or_insert_with(|| {
+            let (metrics, bitmap) = self.font.rasterize(ch, self.size);
+            Glyph {
+                bitmap,
+                width: metrics.width,
+                height: metrics.height,
+                x: metrics.xmin,
+                ymin: metrics.ymin,
+            }
+        });
+        (glyph.width > 0 && glyph.height > 0).then_some(&*glyph)
+    }
+
+    /// The glyph of `ch` drawn in a `width` × `height` cell, cropped to it.
+    pub fn cell_bitmap(&mut self, ch: char, width: usize, height: usize) -> Option<Vec<u8>> {
+        let baseline = self.baseline(height);
+        let glyph = self.glyph(ch)?;
+        let top = glyph.top(baseline);
+        let mut out = vec![0u8; width * height];
+        for gy in 0..glyph.height {
+            for gx in 0..glyph.width {
+                let alpha = glyph.bitmap[gy * glyph.width + gx];
+                if alpha == 0 {
+                    continue;
+                }
+                let px = glyph.x + gx as i32;
+                let py = top + gy as i32;
+                if px < 0 || py < 0 || px >= width as i32 || py >= height as i32 {
+                    continue;
+                }
+                out[py as usize * width + px as usize] = alpha;
+            }
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_cell_sizes() {
+        // Sizes the drivers computed before sharing this code.
+        let sizes = [
+            (10.0, (7, 12)),
+            (12.0, (8, 14)),
+            (16.0, (10, 19)),
+            (18.0, (11, 21)),
+            (24.0, (15, 28)),
+            (27.0, (17, 32)),
+            (36.0, (22, 42)),
+        ];
+        for (size, want) in sizes {
+            assert_eq!(CellFont::new(None, size).cell_size(), want, "{size}px");
+        }
+        assert!(CellFont::new(None, 18.0).has_unicode_symbols());
+    }
+
+    #[test]
+    fn glyphs_fit_their_cell() {
+        let mut font = CellFont::new(None, 18.0);
+        let (w, h) = font.cell_size();
+        assert!(font.glyph(' ').is_none());
+        assert!(font.glyph('\0').is_none());
+
+        let baseline = font.baseline(h);
+        let g = font.glyph('M').unwrap();
+        assert!(g.top(baseline) >= 0 && g.top(baseline) + g.height as i32 <= h as i32);
+        assert!(g.x >= 0 && g.x + g.width as i32 <= w as i32);
+
+        let bitmap = font.cell_bitmap('M', w, h).unwrap();
+        assert_eq!(bitmap.len(), w * h);
+        let ink: u32 = bitmap.iter().map(|&a| a as u32).sum();
+        let g = font.glyph('M').unwrap();
+        assert_eq!(ink, g.bitmap.iter().map(|&a| a as u32).sum::<u32>());
+        // Cropped to a smaller cell.
+        let small = font.cell_bitmap('M', 2, 2).unwrap();
+        assert_eq!(small.len(), 4);
+    }
+}
@@ -0,0 +1,73 @@
+//! Animation frames at a fixed rate: when to send the model a
+//! [`Msg::Frame`], for drivers configured with a frame rate.
+
+use std::time::{Duration, Instant};
+
+use gruid_core::messages::Msg;
+
+/// Paces [`Msg::Frame`] messages. A frame late by more than an interval is
+/// sent once, and the following one an interval later: missed frames are
+/// not caught up with in a burst.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimer {
+    interval: Option<Duration>,
+    /// When the next frame is due, `None` before the first.
+    next: Option<Instant>,
+}
+
+impl FrameTimer {
+    /// Frames `frame_rate` times a second, or never if `None` or zero.
+    pub fn new(frame_rate: Option<u32>) -> Self {
+        Self {
+            interval: frame_rate
+                .filter(|&fps| fps > 0)
+                .map(|fps| Duration::from_secs(1) / fps),
+            next: None,
+        }
+    }
+
+    /// The frame to send at `now`, if one is due.
+    pub fn due(&mut self, now: Instant) -> Option<Msg> {
+        let interval = self.interval?;
+        if self.next.is_some_and(|next| now < next) {
+            return None;
+        }
+        let next = self.next.map_or(now, |next| next + interval);
+        self.next = Some(if next > now { next } else { now + interval });
+        Some(Msg::Frame { time: now })
+    }
+
+    /// When the next frame is due, for the event loop to wake then. `None`
+    /// without a frame rate, or before the first frame, which is due at
+    /// once.
+    pub fn next(&self) -> Option<Instant> {
+        self.next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_keep_their_rate_without_bursts() {
+        let ms = Duration::from_millis;
+        let t0 = Instant::now();
+        let mut frames = FrameTimer::new(Some(50));
+        assert!(matches!(frames.due(t0), Some(Msg::Frame { time }) if time == t0));
+        assert_eq!(frames.next(), Some(t0 + ms(20)));
+        assert!(frames.due(t0 + ms(19)).is_none());
+        // A little late: the rate holds.
+        assert!(frames.due(t0 + ms(25)).is_some());
+        assert_eq!(frames.next(), Some(t0 + ms(40)));
+        // Very late: one frame, then an interval's wait.
+        assert!(frames.due(t0 + ms(200)).is_some());
+        assert!(frames.due(t0 + ms(210)).is_none());
+        assert_eq!(frames.next(), Some(t0 + ms(220)));
+
+        for rate in [None, Some(0)] {
+            let mut off = FrameTimer::new(rate);
+            assert!(off.due(t0).is_none() && off.next().is_none());
+        }
+    }
+}
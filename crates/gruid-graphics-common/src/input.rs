@@ -0,0 +1,450 @@
+//! Translates winit input events into gruid [`Msg`] values, and the window
+//! theme into a [`SystemTheme`].
+
+use std::time::Instant;
+
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta};
+use winit::keyboard::{Key as WKey, ModifiersState, NamedKey};
+use winit::window::Theme;
+
+use gruid_core::{
+    Point,
+    messages::{Key, ModMask, MouseAction, MouseTracker, Msg, SystemTheme},
+};
+
+// ---------------------------------------------------------------------------
+// Keyboard
+// ---------------------------------------------------------------------------
+
+/// Translate a key press or release. Repeats of a held key are presses
+/// with `repeat` set; keys gruid has no name for give `None`.
+pub fn translate_keyboard(event: &KeyEvent) -> Option<Msg> {
+    translate_key_event(&event.logical_key, event.state, event.repeat)
+}
+
+/// Translate the parts of a [`KeyEvent`] gruid uses, which tests can build
+/// (a `KeyEvent` cannot be).
+pub fn translate_key_event(key: &WKey, state: ElementState, repeat: bool) -> Option<Msg> {
+    let key = translate_key(key)?;
+
+    // Modifier extraction — winit doesn't expose modifiers on KeyEvent
+    // directly in 0.30 in a simple way; we rely on the logical key already
+    // incorporating shift (e.g. 'A' vs 'a').  For Ctrl/Alt combos the
+    // character is already translated.  We pass NONE for now — a more
+    // complete implementation would track modifier state via
+    // WindowEvent::ModifiersChanged.
+    let modifiers = ModMask::NONE;
+
+    let time = Instant::now();
+    Some(match state {
+        ElementState::Pressed => Msg::KeyDown {
+            key,
+            modifiers,
+            time,
+            repeat,
+        },
+        ElementState::Released => Msg::KeyUp {
+            key,
+            modifiers,
+            time,
+        },
+    })
+}
+
+/// The gruid key for a winit logical key.
+pub fn translate_key(key: &WKey) -> Option<Key> {
+    let key = match key {
+        WKey::Named(named) => match named {
+            NamedKey::ArrowUp => Key::ArrowUp,
+            NamedKey::ArrowDown => Key::ArrowDown,
+            NamedKey::ArrowLeft => Key::ArrowLeft,
+            NamedKey::ArrowRight => Key::ArrowRight,
+            NamedKey::Escape => Key::Escape,
+            NamedKey::Enter => Key::Enter,
+            NamedKey::Tab => Key::Tab,
+            NamedKey::Space => Key::Space,
+            NamedKey::Backspace => Key::Backspace,
+            NamedKey::Delete => Key::Delete,
+            NamedKey::Home => Key::Home,
+            NamedKey::End => Key::End,
+            NamedKey::PageUp => Key::PageUp,
+            NamedKey::PageDown => Key::PageDown,
+            NamedKey::Insert => Key::Insert,
+            _ => return None,
+        },
+        WKey::Character(s) => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::Char(c),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Whether `key` pressed with `modifiers` asks to paste: the Paste key,
+/// Shift+Insert, or V with Ctrl (Cmd on macOS). Drivers read the clipboard
+/// then and send a [`Msg::Paste`] instead of the key.
+pub fn is_paste_key(key: &WKey, modifiers: ModifiersState) -> bool {
+    let command = if cfg!(target_os = "macos") {
+        ModifiersState::SUPER
+    } else {
+        ModifiersState::CONTROL
+    };
+    match key {
+        WKey::Named(NamedKey::Paste) => true,
+        WKey::Named(NamedKey::Insert) => modifiers == ModifiersState::SHIFT,
+        WKey::Character(c) => modifiers == command && c.eq_ignore_ascii_case("v"),
+        _ => false,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Mouse
+// ---------------------------------------------------------------------------
+
+/// Where the grid is drawn in the window, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridLayout {
+    /// Cell width.
+    pub cell_width: usize,
+    /// Cell height.
+    pub cell_height: usize,
+    /// Left margin before the first column, for a letterboxed grid.
+    pub offset_x: usize,
+    /// Top margin before the first row.
+    pub offset_y: usize,
+}
+
+impl GridLayout {
+    /// A grid of `cell_width` × `cell_height` cells at the window's
+    /// top-left corner.
+    pub fn new(cell_width: usize, cell_height: usize) -> Self {
+        Self {
+            cell_width,
+            cell_height,
+            offset_x: 0,
+            offset_y: 0,
+        }
+    }
+
+    /// Set the margins before the first column and row.
+    pub fn with_offset(mut self, x: usize, y: usize) -> Self {
+        self.offset_x = x;
+        self.offset_y = y;
+        self
+    }
+
+    /// The cell under a pixel position, without clamping: positions over
+    /// the window padding map to cells outside the grid.
+    pub fn pixel_to_grid(&self, px: f64, py: f64) -> Point {
+        Point::new(
+            ((px - self.offset_x as f64) / self.cell_width.max(1) as f64).floor() as i32,
+            ((py - self.offset_y as f64) / self.cell_height.max(1) as f64).floor() as i32,
+        )
+    }
+}
+
+/// Used before a renderer exists.
+impl Default for GridLayout {
+    fn default() -> Self {
+        Self::new(8, 16)
+    }
+}
+
+/// The press action of a winit mouse button.
+fn button_action(button: MouseButton) -> Option<MouseAction> {
+    match button {
+        MouseButton::Left => Some(MouseAction::Main),
+        MouseButton::Right => Some(MouseAction::Secondary),
+        MouseButton::Middle => Some(MouseAction::Auxiliary),
+        _ => None,
+    }
+}
+
+/// Translate a button press or release, keeping `held`, the driver's record
+/// of the button a drag is made with, up to date: the first press the
+/// grid reports sets it, and releasing that button clears it.
+pub fn translate_mouse_button(
+    btn_state: ElementState,
+    button: MouseButton,
+    held: &mut Option<MouseAction>,
+    tracker: &MouseTracker,
+) -> Option<Msg> {
+    let action = match btn_state {
+        ElementState::Pressed => button_action(button)?,
+        ElementState::Released => {
+            if held.is_some() && *held == button_action(button) {
+                *held = None;
+            }
+            MouseAction::Release
+        }
+    };
+    // Button events carry no position: use the tracked cursor.
+    let msg = tracker.button(action, None, ModMask::NONE);
+    if msg.is_some() && action != MouseAction::Release && held.is_none() {
+        *held = Some(action);
+    }
+    msg
+}
+
+/// Translate a cursor move, as a drag while `held` is set.
+pub fn translate_cursor_moved(
+    position: PhysicalPosition<f64>,
+    layout: GridLayout,
+    held: Option<MouseAction>,
+    tracker: &mut MouseTracker,
+) -> impl Iterator<Item = Msg> + use<> {
+    let pos = layout.pixel_to_grid(position.x, position.y);
+    tracker.dragged(pos, held, ModMask::NONE)
+}
+
+pub fn translate_cursor_left(tracker: &mut MouseTracker) -> Option<Msg> {
+    tracker.left(ModMask::NONE)
+}
+
+pub fn translate_mouse_wheel(delta: MouseScrollDelta, tracker: &MouseTracker) -> Option<Msg> {
+    let (_, y) = match delta {
+        MouseScrollDelta::LineDelta(x, y) => (x as f64, y as f64),
+        MouseScrollDelta::PixelDelta(pos) => (pos.x, pos.y),
+    };
+
+    let action = if y > 0.0 {
+        MouseAction::WheelUp
+    } else if y < 0.0 {
+        MouseAction::WheelDown
+    } else {
+        return None;
+    };
+
+    tracker.button(action, None, ModMask::NONE)
+}
+
+// ---------------------------------------------------------------------------
+// Theme
+// ---------------------------------------------------------------------------
+
+/// The gruid theme of a window theme, as given by `Window::theme` and
+/// `WindowEvent::ThemeChanged`.
+pub fn translate_theme(theme: Theme) -> SystemTheme {
+    match theme {
+        Theme::Dark => SystemTheme::Dark,
+        Theme::Light => SystemTheme::Light,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::SmolStr;
+
+    fn actions(msgs: impl IntoIterator<Item = Msg>) -> Vec<(MouseAction, Point)> {
+        msgs.into_iter()
+            .map(|m| match m {
+                Msg::Mouse { action, pos, .. } => (action, pos),
+                other => panic!("unexpected {other}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn themes() {
+        assert_eq!(translate_theme(Theme::Dark), SystemTheme::Dark);
+        assert_eq!(translate_theme(Theme::Light), SystemTheme::Light);
+    }
+
+    #[test]
+    fn key_events() {
+        let up = WKey::Named(NamedKey::ArrowUp);
+        let msg = translate_key_event(&up, ElementState::Pressed, false).unwrap();
+        assert!(
+            matches!(
+                msg,
+                Msg::KeyDown {
+                    key: Key::ArrowUp,
+                    ..
+                }
+            ) && !msg.is_repeat()
+        );
+        let msg = translate_key_event(&up, ElementState::Pressed, true).unwrap();
+        assert!(
+            matches!(
+                msg,
+                Msg::KeyDown {
+                    key: Key::ArrowUp,
+                    ..
+                }
+            ) && msg.is_repeat()
+        );
+        let msg = translate_key_event(&up, ElementState::Released, false).unwrap();
+        assert!(matches!(
+            msg,
+            Msg::KeyUp {
+                key: Key::ArrowUp,
+                ..
+            }
+        ));
+        let shift = WKey::Named(NamedKey::Shift);
+        assert!(translate_key_event(&shift, ElementState::Pressed, false).is_none());
+    }
+
+    #[test]
+    fn paste_keys() {
+        let command = if cfg!(target_os = "macos") {
+            ModifiersState::SUPER
+        } else {
+            ModifiersState::CONTROL
+        };
+        let v = WKey::Character(SmolStr::new("v"));
+        assert!(is_paste_key(&v, command));
+        assert!(is_paste_key(&WKey::Character(SmolStr::new("V")), command));
+        assert!(!is_paste_key(&v, ModifiersState::empty()));
+        assert!(!is_paste_key(&v, command | ModifiersState::SHIFT));
+        let insert = WKey::Named(NamedKey::Insert);
+        assert!(is_paste_key(&insert, ModifiersState::SHIFT));
+        assert!(!is_paste_key(&insert, ModifiersState::empty()));
+        assert!(is_paste_key(
+            &WKey::Named(NamedKey::Paste),
+            ModifiersState::empty()
+        ));
+    }
+
+    #[test]
+    fn key_table() {
+        let named = [
+            (NamedKey::ArrowUp, Key::ArrowUp),
+            (NamedKey::ArrowDown, Key::ArrowDown),
+            (NamedKey::ArrowLeft, Key::ArrowLeft),
+            (NamedKey::ArrowRight, Key::ArrowRight),
+            (NamedKey::Escape, Key::Escape),
+            (NamedKey::Enter, Key::Enter),
+            (NamedKey::Tab, Key::Tab),
+            (NamedKey::Space, Key::Space),
+            (NamedKey::Backspace, Key::Backspace),
+            (NamedKey::Delete, Key::Delete),
+            (NamedKey::Home, Key::Home),
+            (NamedKey::End, Key::End),
+            (NamedKey::PageUp, Key::PageUp),
+            (NamedKey::PageDown, Key::PageDown),
+            (NamedKey::Insert, Key::Insert),
+        ];
+        for (named, key) in named {
+            assert_eq!(translate_key(&WKey::Named(named)), Some(key), "{named:?}");
+        }
+        assert_eq!(translate_key(&WKey::Named(NamedKey::Shift)), None);
+
+        let chr = |s: &str| translate_key(&WKey::Character(SmolStr::new(s)));
+        assert_eq!(chr("a"), Some(Key::Char('a')));
+        assert_eq!(chr("A"), Some(Key::Char('A')));
+        assert_eq!(chr("é"), Some(Key::Char('é')));
+        // Dead-key compositions of several characters have no gruid key.
+        assert_eq!(chr("´e"), None);
+        assert_eq!(chr(""), None);
+    }
+
+    #[test]
+    fn cursor_over_padding_leaves_grid() {
+        // 10x5 grid of 8x16 cells in a larger window.
+        let mut t = MouseTracker::new(10, 5);
+        let at = |x, y| PhysicalPosition::new(x, y);
+        let layout = GridLayout::new(8, 16);
+        assert_eq!(
+            actions(translate_cursor_moved(at(17.0, 20.0), layout, None, &mut t)),
+            [
+                (MouseAction::Enter, Point::new(2, 1)),
+                (MouseAction::Move, Point::new(2, 1))
+            ]
+        );
+        assert!(actions(translate_cursor_moved(at(23.9, 31.0), layout, None, &mut t)).is_empty());
+        // Right of the last column.
+        assert_eq!(
+            actions(translate_cursor_moved(at(81.0, 20.0), layout, None, &mut t)),
+            [(MouseAction::Leave, Point::new(2, 1))]
+        );
+        let mut held = None;
+        assert!(
+            translate_mouse_button(ElementState::Pressed, MouseButton::Left, &mut held, &t)
+                .is_none()
+        );
+        // Presses off the grid start no drag.
+        assert_eq!(held, None);
+        assert!(translate_cursor_left(&mut t).is_none());
+    }
+
+    #[test]
+    fn letterbox_offset() {
+        let layout = GridLayout::new(8, 16).with_offset(20, 4);
+        assert_eq!(layout.pixel_to_grid(20.0, 4.0), Point::new(0, 0));
+        assert_eq!(layout.pixel_to_grid(19.5, 4.0), Point::new(-1, 0));
+        assert_eq!(layout.pixel_to_grid(43.0, 35.9), Point::new(2, 1));
+        // Zero-sized cells never divide by zero.
+        assert_eq!(
+            GridLayout::new(0, 0).pixel_to_grid(3.0, 2.0),
+            Point::new(3, 2)
+        );
+    }
+
+    #[test]
+    fn buttons_use_tracked_cursor() {
+        let mut t = MouseTracker::new(10, 5);
+        let layout = GridLayout::new(8, 16);
+        translate_cursor_moved(PhysicalPosition::new(40.0, 70.0), layout, None, &mut t)
+            .for_each(drop);
+        let mut held = None;
+        assert_eq!(
+            actions(translate_mouse_button(
+                ElementState::Pressed,
+                MouseButton::Right,
+                &mut held,
+                &t
+            )),
+            [(MouseAction::Secondary, Point::new(5, 4))]
+        );
+        assert_eq!(
+            actions(translate_mouse_wheel(
+                MouseScrollDelta::LineDelta(0.0, -1.0),
+                &t
+            )),
+            [(MouseAction::WheelDown, Point::new(5, 4))]
+        );
+        assert_eq!(
+            actions(translate_cursor_left(&mut t)),
+            [(MouseAction::Leave, Point::new(5, 4))]
+        );
+    }
+
+    #[test]
+    fn moves_with_button_held_are_drags() {
+        let mut t = MouseTracker::new(10, 5);
+        let layout = GridLayout::new(8, 16);
+        let mut held = None;
+        let at = |x| PhysicalPosition::new(x, 20.0);
+        translate_cursor_moved(at(4.0), layout, held, &mut t).for_each(drop);
+        translate_mouse_button(ElementState::Pressed, MouseButton::Left, &mut held, &t);
+        assert_eq!(held, Some(MouseAction::Main));
+        // A second button does not take over the drag.
+        translate_mouse_button(ElementState::Pressed, MouseButton::Right, &mut held, &t);
+        assert_eq!(
+            actions(translate_cursor_moved(at(12.0), layout, held, &mut t)),
+            [(MouseAction::MainDrag, Point::new(1, 1))]
+        );
+        translate_mouse_button(ElementState::Released, MouseButton::Right, &mut held, &t);
+        assert_eq!(held, Some(MouseAction::Main));
+        assert_eq!(
+            actions(translate_mouse_button(
+                ElementState::Released,
+                MouseButton::Left,
+                &mut held,
+                &t
+            )),
+            [(MouseAction::Release, Point::new(1, 1))]
+        );
+        assert_eq!(
+            actions(translate_cursor_moved(at(20.0), layout, held, &mut t)),
+            [(MouseAction::Move, Point::new(2, 1))]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Code shared by the gruid-winit and gruid-wgpu drivers. Not meant to be
+//! used directly: its API follows the drivers' needs.
+//!
+//! - [`input`]: winit events to gruid messages.
+//! - [`clipboard`]: the system clipboard, for copies and pastes.
+//! - [`font`]: glyph rasterization with fontdue and cell size.
+//! - [`atlas`]: packing glyphs into a texture atlas.
+//! - [`frames`]: animation frames at the configured rate.
+//! - [`tiles`]: [`TileManager`] bitmaps at the cell size.
+//! - [`title`]: the [`SetWindowTitle`](gruid_core::SetWindowTitle) handler.
+//! - [`visibility`]: whether the window is minimized or covered.
+//! - [`window`]: window attributes and the
+//!   [`SetFullscreen`](gruid_core::SetFullscreen) handler.
+//!
+//! The drivers keep what is specific to them: softbuffer blitting in
+//! gruid-winit, instance packing and GPU state in gruid-wgpu.
+
+pub mod atlas;
+pub mod clipboard;
+pub mod font;
+pub mod frames;
+pub mod input;
+pub mod tiles;
+pub mod title;
+pub mod visibility;
+pub mod window;
+
+use gruid_core::{
+    ColorDepth, DriverCaps, Platform, TileManager,
+    style::{Color, Style},
+};
+
+use font::CellFont;
+
+/// RGB of a foreground colour, light grey for [`Color::DEFAULT`].
+#[deprecated(note = "use `Style::rgb`, which also resolves REVERSE and DIM")]
+#[inline]
+pub fn fg_rgb(c: Color) -> (u8, u8, u8) {
+    Style::default().with_fg(c).rgb().0
+}
+
+/// RGB of a background colour, black for [`Color::DEFAULT`].
+#[deprecated(note = "use `Style::rgb`, which also resolves REVERSE and DIM")]
+#[inline]
+pub fn bg_rgb(c: Color) -> (u8, u8, u8) {
+    Style::default().with_bg(c).rgb().1
+}
+
+/// Capabilities of a window drawing `font` in cells of `cell_size` pixels.
+pub fn capabilities(font: &CellFont, cell_size: (usize, usize), tiles: bool) -> DriverCaps {
+    let (w, h) = cell_size;
+    DriverCaps {
+        mouse: true,
+        color_depth: ColorDepth::TrueColor,
+        cell_aspect: w as f32 / h as f32,
+        tiles,
+        animation_ticks: true,
+        key_release: true,
+        clipboard: true,
+        unicode: font.has_unicode_symbols(),
+        platform: Platform::Desktop,
+    }
+}
+
+/// Capabilities of a driver configured with these settings, before any
+/// window exists: the font is measured at its logical size, and tiles set
+/// the aspect ratio.
+pub fn config_capabilities(
+    font_data: Option<&[u8]>,
+    font_size: f32,
+    tile_manager: Option<&dyn TileManager>,
+) -> DriverCaps {
+    let font = CellFont::new(font_data, font_size);
+    let mut caps = capabilities(&font, font.cell_size(), false);
+    if let Some(tm) = tile_manager {
+        let (w, h) = tm.tile_size();
+        caps.tiles = true;
+        caps.cell_aspect = w.max(1) as f32 / h.max(1) as f32;
+    }
+    caps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::Cell;
+
+    struct Square;
+
+    impl TileManager for Square {
+        fn tile_size(&self) -> (usize, usize) {
+            (16, 16)
+        }
+
+        fn get_tile(&self, _cell: &Cell) -> Option<&[u8]> {
+            None
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn default_colours() {
+        assert_eq!(fg_rgb(Color::DEFAULT), (200, 200, 200));
+        assert_eq!(bg_rgb(Color::DEFAULT), (0, 0, 0));
+        let c = Color::from_rgb(1, 2, 3);
+        assert_eq!((fg_rgb(c), bg_rgb(c)), ((1, 2, 3), (1, 2, 3)));
+    }
+
+    #[test]
+    fn caps_from_config() {
+        let caps = config_capabilities(None, 18.0, None);
+        assert!(!caps.tiles && caps.unicode);
+        assert_eq!(caps.cell_aspect, 11.0 / 21.0);
+        let caps = config_capabilities(None, 18.0, Some(&Square));
+        assert!(caps.tiles);
+        assert_eq!(caps.cell_aspect, 1.0);
+    }
+}
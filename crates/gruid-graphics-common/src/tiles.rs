@@ -0,0 +1,138 @@
+//! Scaling a [`TileManager`]'s bitmaps to the cell size.
+
+use gruid_core::{Cell, TileManager};
+
+/// Tile scale for a configured value, 0 meaning one chosen from the
+/// monitor's scale factor.
+pub fn tile_scale(configured: u32, scale_factor: f64) -> u32 {
+    if configured > 0 {
+        configured
+    } else {
+        (scale_factor.round() as u32).max(1)
+    }
+}
+
+/// A tile manager whose tiles are drawn `scale` times their size.
+pub struct Tiles {
+    manager: Box<dyn TileManager>,
+    scale: usize,
+}
+
+impl Tiles {
+    /// Draw `manager`'s tiles scaled by `scale`, at least 1.
+    pub fn new(manager: Box<dyn TileManager>, scale: u32) -> Self {
+        Self {
+            manager,
+            scale: scale.max(1) as usize,
+        }
+    }
+
+    /// Give the tile manager back, to build another renderer.
+    pub fn into_manager(self) -> Box<dyn TileManager> {
+        self.manager
+    }
+
+    /// Cell size in pixels: the scaled tile size.
+    pub fn cell_size(&self) -> (usize, usize) {
+        let (tw, th) = self.manager.tile_size();
+        ((tw * self.scale).max(1), (th * self.scale).max(1))
+    }
+
+    /// The scaled tile for `cell`, if the manager has one.
+    pub fn tile(&self, cell: &Cell) -> Option<Tile<'_>> {
+        let bitmap = self.manager.get_tile(cell)?;
+        let (width, height) = self.manager.tile_size();
+        Some(Tile {
+            bitmap,
+            width,
+            height,
+            scale: self.scale,
+        })
+    }
+}
+
+/// A tile bitmap seen at its scaled size.
+pub struct Tile<'a> {
+    bitmap: &'a [u8],
+    width: usize,
+    height: usize,
+    scale: usize,
+}
+
+impl Tile<'_> {
+    /// Scaled width in pixels.
+    pub fn width(&self) -> usize {
+        self.width * self.scale
+    }
+
+    /// Scaled height in pixels.
+    pub fn height(&self) -> usize {
+        self.height * self.scale
+    }
+
+    /// Alpha at a scaled pixel. Pixels missing from a short bitmap are
+    /// transparent.
+    pub fn alpha(&self, x: usize, y: usize) -> u8 {
+        let (tx, ty) = (x / self.scale, y / self.scale);
+        if tx >= self.width {
+            return 0;
+        }
+        self.bitmap.get(ty * self.width + tx).copied().unwrap_or(0)
+    }
+
+    /// The scaled bitmap, row by row.
+    pub fn to_bitmap(&self) -> Vec<u8> {
+        let (w, h) = (self.width(), self.height());
+        let mut out = Vec::with_capacity(w * h);
+        for y in 0..h {
+            out.extend((0..w).map(|x| self.alpha(x, y)));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Checker(Vec<u8>);
+
+    impl TileManager for Checker {
+        fn tile_size(&self) -> (usize, usize) {
+            (2, 2)
+        }
+
+        fn get_tile(&self, cell: &Cell) -> Option<&[u8]> {
+            (cell.ch == '#').then_some(&self.0[..])
+        }
+    }
+
+    #[test]
+    fn scaled_tiles() {
+        let tiles = Tiles::new(Box::new(Checker(vec![255, 0, 0, 255])), 2);
+        assert_eq!(tiles.cell_size(), (4, 4));
+        assert!(tiles.tile(&Cell::default().with_char('.')).is_none());
+        let tile = tiles.tile(&Cell::default().with_char('#')).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(tile.to_bitmap(), [
+            255, 255, 0, 0,
+            255, 255, 0, 0,
+            0, 0, 255, 255,
+            0, 0, 255, 255,
+        ]);
+
+        // A short bitmap and a zero scale.
+        let tiles = Tiles::new(Box::new(Checker(vec![9, 8, 7])), 0);
+        let tile = tiles.tile(&Cell::default().with_char('#')).unwrap();
+        assert_eq!(tile.to_bitmap(), [9, 8, 7, 0]);
+    }
+
+    #[test]
+    fn auto_tile_scale() {
+        assert_eq!(tile_scale(3, 1.0), 3);
+        assert_eq!(tile_scale(0, 1.0), 1);
+        assert_eq!(tile_scale(0, 2.0), 2);
+        assert_eq!(tile_scale(0, 1.4), 1);
+        assert_eq!(tile_scale(0, 0.0), 1);
+    }
+}
@@ -0,0 +1,49 @@
+//! Carrying out [`SetWindowTitle`] requests.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use gruid_core::{
+    SetWindowTitle,
+    app::{EffectHandler, effect_handler},
+};
+use winit::window::Window;
+
+/// The window whose title [`SetWindowTitle`] sets, shared between the
+/// driver, which attaches it once created, and the handler.
+#[derive(Clone, Default)]
+pub struct WindowTitle(Rc<RefCell<Option<Arc<Window>>>>);
+
+impl WindowTitle {
+    /// Set the window titles go to.
+    pub fn attach(&self, window: Arc<Window>) {
+        *self.0.borrow_mut() = Some(window);
+    }
+
+    /// A handler setting the attached window's title. Requests made while
+    /// no window is attached are not consumed.
+    pub fn handler(&self) -> Box<dyn EffectHandler> {
+        let window = Rc::clone(&self.0);
+        effect_handler(move |t: &SetWindowTitle| match &*window.borrow() {
+            Some(w) => {
+                w.set_title(&t.0);
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::TypeId;
+
+    #[test]
+    fn no_window_passes_requests_on() {
+        let mut h = WindowTitle::default().handler();
+        assert_eq!(h.message_type(), TypeId::of::<SetWindowTitle>());
+        assert!(!h.handle(&SetWindowTitle("gruid".into())));
+    }
+}
@@ -0,0 +1,85 @@
+//! Whether the window can be seen, so that drivers stop rendering while it
+//! is minimized or covered.
+
+use std::time::Duration;
+
+use gruid_core::messages::Visibility;
+
+/// How often the event loop wakes to drain messages while the window is
+/// hidden.
+pub const HIDDEN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Occlusion and minimization of a window, from its events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowVisibility {
+    occluded: bool,
+    minimized: bool,
+}
+
+impl WindowVisibility {
+    /// Record a `WindowEvent::Occluded`. Returns the new visibility if it
+    /// changed.
+    pub fn set_occluded(&mut self, occluded: bool) -> Option<Visibility> {
+        self.update(|v| v.occluded = occluded)
+    }
+
+    /// Record the window's new size: platforms report minimizing as a
+    /// resize to zero. Returns the new visibility if it changed.
+    pub fn resized(&mut self, width: u32, height: u32) -> Option<Visibility> {
+        self.update(|v| v.minimized = width == 0 || height == 0)
+    }
+
+    fn update(&mut self, f: impl FnOnce(&mut Self)) -> Option<Visibility> {
+        let before = self.get();
+        f(self);
+        let after = self.get();
+        (after != before).then_some(after)
+    }
+
+    /// The current visibility.
+    pub fn get(&self) -> Visibility {
+        if self.occluded || self.minimized {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        }
+    }
+
+    /// Whether the window can be seen.
+    pub fn is_visible(&self) -> bool {
+        self.get() == Visibility::Visible
+    }
+
+    /// How long the event loop may sleep, `interval` when visible.
+    pub fn poll_interval(&self, interval: Duration) -> Duration {
+        if self.is_visible() {
+            interval
+        } else {
+            HIDDEN_POLL_INTERVAL.max(interval)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_while_occluded_or_minimized() {
+        let mut v = WindowVisibility::default();
+        assert!(v.is_visible());
+        assert_eq!(v.set_occluded(true), Some(Visibility::Hidden));
+        assert_eq!(v.set_occluded(true), None);
+        // Minimized while occluded: still hidden, no change.
+        assert_eq!(v.resized(0, 0), None);
+        assert_eq!(v.set_occluded(false), None);
+        assert!(!v.is_visible());
+        assert_eq!(v.resized(640, 480), Some(Visibility::Visible));
+        assert_eq!(v.resized(800, 600), None);
+
+        let poll = Duration::from_millis(16);
+        assert_eq!(v.poll_interval(poll), poll);
+        v.resized(0, 480);
+        assert_eq!(v.poll_interval(poll), HIDDEN_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,204 @@
+//! Window attributes from the drivers' configuration, and carrying out
+//! [`SetFullscreen`] requests.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use gruid_core::{
+    SetFullscreen,
+    app::{EffectHandler, effect_handler},
+};
+use winit::dpi::PhysicalSize;
+use winit::window::{Fullscreen, Icon, Window, WindowAttributes};
+
+/// The options of a driver's configuration that shape its window.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowOptions<'a> {
+    pub title: &'a str,
+    /// Borderless fullscreen on the current monitor.
+    pub fullscreen: bool,
+    pub decorations: bool,
+    pub resizable: bool,
+    /// RGBA pixels, width and height.
+    pub icon: Option<&'a (Vec<u8>, u32, u32)>,
+    /// Columns and rows the window cannot be shrunk below, if positive.
+    pub min_grid_size: (i32, i32),
+}
+
+/// Attributes of a window showing a grid of `size` physical pixels, in
+/// cells of `cell_size`. An icon winit rejects is logged and left out.
+pub fn window_attributes(
+    opts: &WindowOptions,
+    size: PhysicalSize<u32>,
+    cell_size: (usize, usize),
+) -> WindowAttributes {
+    let icon = opts.icon.and_then(|(rgba, w, h)| {
+        Icon::from_rgba(rgba.clone(), *w, *h)
+            .map_err(|e| log::warn!("window icon ignored: {e}"))
+            .ok()
+    });
+    let mut attrs = Window::default_attributes()
+        .with_title(opts.title)
+        .with_inner_size(size)
+        .with_resizable(opts.resizable)
+        .with_decorations(opts.decorations)
+        .with_window_icon(icon)
+        .with_fullscreen(fullscreen(opts.fullscreen));
+    if let Some(min) = min_inner_size(opts.min_grid_size, cell_size) {
+        attrs = attrs.with_min_inner_size(min);
+    }
+    attrs
+}
+
+/// The smallest inner size showing `min_grid_size` cells of `cell_size`,
+/// or `None` if either dimension is not positive.
+pub fn min_inner_size(
+    min_grid_size: (i32, i32),
+    cell_size: (usize, usize),
+) -> Option<PhysicalSize<u32>> {
+    let (cols, rows) = min_grid_size;
+    let (w, h) = cell_size;
+    (cols > 0 && rows > 0)
+        .then(|| PhysicalSize::new(cols as u32 * w as u32, rows as u32 * h as u32))
+}
+
+/// The grid of cells of `cell_size` filling an inner size of `width` by
+/// `height`, or `None` if not even one cell fits, as when minimized.
+pub fn grid_size(width: u32, height: u32, cell_size: (usize, usize)) -> Option<(i32, i32)> {
+    let (w, h) = cell_size;
+    if w == 0 || h == 0 {
+        return None;
+    }
+    let (cols, rows) = (width as usize / w, height as usize / h);
+    (cols > 0 && rows > 0).then_some((cols as i32, rows as i32))
+}
+
+fn fullscreen(on: bool) -> Option<Fullscreen> {
+    on.then_some(Fullscreen::Borderless(None))
+}
+
+/// Whether a window is fullscreen after `request`, from `current`.
+pub fn fullscreen_after(current: bool, request: SetFullscreen) -> bool {
+    match request {
+        SetFullscreen::On => true,
+        SetFullscreen::Off => false,
+        SetFullscreen::Toggle => !current,
+    }
+}
+
+/// The window [`SetFullscreen`] switches, shared between the driver, which
+/// attaches it once created, and the handler.
+#[derive(Clone, Default)]
+pub struct WindowFullscreen(Rc<RefCell<Option<Arc<Window>>>>);
+
+impl WindowFullscreen {
+    /// Set the window requests go to.
+    pub fn attach(&self, window: Arc<Window>) {
+        *self.0.borrow_mut() = Some(window);
+    }
+
+    /// A handler switching the attached window in or out of borderless
+    /// fullscreen. Requests made while no window is attached are not
+    /// consumed.
+    pub fn handler(&self) -> Box<dyn EffectHandler> {
+        let window = Rc::clone(&self.0);
+        effect_handler(move |req: &SetFullscreen| match &*window.borrow() {
+            Some(w) => {
+                let on = fullscreen_after(w.fullscreen().is_some(), *req);
+                w.set_fullscreen(fullscreen(on));
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::TypeId;
+
+    fn options() -> WindowOptions<'static> {
+        WindowOptions {
+            title: "gruid",
+            fullscreen: false,
+            decorations: true,
+            resizable: true,
+            icon: None,
+            min_grid_size: (0, 0),
+        }
+    }
+
+    #[test]
+    fn grid_follows_the_inner_size() {
+        assert_eq!(grid_size(805, 490, (10, 20)), Some((80, 24)));
+        assert_eq!(grid_size(0, 0, (10, 20)), None);
+        assert_eq!(grid_size(805, 19, (10, 20)), None);
+        assert_eq!(grid_size(805, 490, (0, 20)), None);
+    }
+
+    #[test]
+    fn attributes_follow_the_options() {
+        let size = PhysicalSize::new(800, 480);
+        let attrs = window_attributes(&options(), size, (10, 20));
+        assert_eq!(attrs.title, "gruid");
+        assert_eq!(attrs.inner_size, Some(size.into()));
+        assert!(attrs.resizable && attrs.decorations);
+        assert!(attrs.fullscreen.is_none() && attrs.window_icon.is_none());
+        assert_eq!(attrs.min_inner_size, None);
+
+        let icon = (vec![255; 4 * 16 * 16], 16, 16);
+        let opts = WindowOptions {
+            fullscreen: true,
+            decorations: false,
+            resizable: false,
+            icon: Some(&icon),
+            min_grid_size: (40, 12),
+            ..options()
+        };
+        let attrs = window_attributes(&opts, size, (10, 20));
+        assert!(!attrs.resizable && !attrs.decorations);
+        assert_eq!(attrs.fullscreen, Some(Fullscreen::Borderless(None)));
+        assert!(attrs.window_icon.is_some());
+        assert_eq!(
+            attrs.min_inner_size,
+            Some(PhysicalSize::new(400, 240).into())
+        );
+
+        // Pixels that do not match the size are left out.
+        let bad = (vec![0; 3], 1, 1);
+        let opts = WindowOptions {
+            icon: Some(&bad),
+            ..options()
+        };
+        assert!(
+            window_attributes(&opts, size, (10, 20))
+                .window_icon
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn min_size_needs_both_dimensions() {
+        assert_eq!(min_inner_size((0, 10), (8, 16)), None);
+        assert_eq!(min_inner_size((20, -1), (8, 16)), None);
+        assert_eq!(
+            min_inner_size((20, 10), (8, 16)),
+            Some(PhysicalSize::new(160, 160))
+        );
+    }
+
+    #[test]
+    fn fullscreen_requests() {
+        assert!(fullscreen_after(false, SetFullscreen::On));
+        assert!(fullscreen_after(true, SetFullscreen::On));
+        assert!(!fullscreen_after(true, SetFullscreen::Off));
+        assert!(fullscreen_after(false, SetFullscreen::Toggle));
+        assert!(!fullscreen_after(true, SetFullscreen::Toggle));
+
+        let mut h = WindowFullscreen::default().handler();
+        assert_eq!(h.message_type(), TypeId::of::<SetFullscreen>());
+        assert!(!h.handle(&SetFullscreen::Toggle));
+    }
+}
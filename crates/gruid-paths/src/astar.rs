@@ -3,6 +3,7 @@ use std::collections::BinaryHeap;
 use gruid_core::Point;
 
 use crate::PathRange;
+use crate::explored::LastSearch;
 use crate::pathrange::{NodeRef, UNREACHABLE};
 use crate::traits::AstarPather;
 
@@ -17,6 +18,19 @@ impl PathRange {
         from: Point,
         to: Point,
     ) -> Option<Vec<Point>> {
+        self.astar_search(pather, from, to, |_, _| true)
+    }
+
+    /// A* that only follows edges `from -> to` for which `keep(from, to)`
+    /// holds.
+    pub(crate) fn astar_search<P: AstarPather>(
+        &mut self,
+        pather: &P,
+        from: Point,
+        to: Point,
+        keep: impl Fn(Point, Point) -> bool,
+    ) -> Option<Vec<Point>> {
+        self.last_search = LastSearch::None;
         let start_idx = self.idx(from)?;
         let goal_idx = self.idx(to)?;
 
@@ -27,6 +41,7 @@ impl PathRange {
         // Bump generation to lazily invalidate all nodes.
         self.astar_generation = self.astar_generation.wrapping_add(1);
         let cur_gen = self.astar_generation;
+        self.last_search = LastSearch::Astar;
 
         // Initialise the start node.
         {
@@ -59,6 +74,7 @@ impl PathRange {
             }
 
             if ci == goal_idx {
+                self.astar_nodes[ci].open = false;
                 break 'search true;
             }
 
@@ -73,6 +89,9 @@ impl PathRange {
                 let Some(ni) = self.idx(np) else {
                     continue;
                 };
+                if !keep(current_point, np) {
+                    continue;
+                }
                 let tentative_g = current_g + pather.cost(current_point, np);
 
                 let n = &mut self.astar_nodes[ni];
@@ -109,6 +128,25 @@ impl PathRange {
             ci = self.astar_nodes[ci].parent;
         }
         path.reverse();
+        #[cfg(feature = "check-heuristic")]
+        self.check_heuristic(pather, &path);
         Some(path)
     }
+
+    /// Panic in debug builds if `pather` estimates the cost from a point
+    /// of `path`, just found by A*, to its end as more than the rest of
+    /// the path costs: the heuristic is then not admissible.
+    #[cfg(feature = "check-heuristic")]
+    fn check_heuristic<P: AstarPather>(&self, pather: &P, path: &[Point]) {
+        let g = |p| self.idx(p).map_or(0, |i| self.astar_nodes[i].g);
+        let Some(&to) = path.last() else { return };
+        for &p in path {
+            let (estimate, rest) = (pather.estimate(p, to), g(to) - g(p));
+            debug_assert!(
+                estimate <= rest,
+                "inadmissible heuristic: estimate from {p} to {to} is {estimate}, \
+                 but the path found costs {rest}",
+            );
+        }
+    }
 }
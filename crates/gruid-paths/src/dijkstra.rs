@@ -3,6 +3,7 @@ use std::collections::BinaryHeap;
 use gruid_core::Point;
 
 use crate::PathRange;
+use crate::explored::LastSearch;
 use crate::pathrange::{NodeRef, PathNode, UNREACHABLE};
 use crate::traits::WeightedPather;
 
@@ -25,6 +26,7 @@ impl PathRange {
 
         self.dijkstra_generation = self.dijkstra_generation.wrapping_add(1);
         let cur_gen = self.dijkstra_generation;
+        self.last_search = LastSearch::Dijkstra;
 
         let mut open: BinaryHeap<NodeRef> = BinaryHeap::new();
 
@@ -11,3 +11,202 @@ pub fn manhattan(a: Point, b: Point) -> i32 {
 pub fn chebyshev(a: Point, b: Point) -> i32 {
     (a.x - b.x).abs().max((a.y - b.y).abs())
 }
+
+/// Integer approximation of the Euclidean distance between two points,
+/// scaled by 100: [`Metric::ORTHOGONAL_STEP`] per orthogonal step and
+/// [`Metric::DIAGONAL_STEP`] per diagonal one, on the shortest 8-way path.
+#[inline]
+pub fn euclidean_approx(a: Point, b: Point) -> i32 {
+    let (dx, dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+    let diagonal = dx.min(dy);
+    Metric::ORTHOGONAL_STEP * (dx.max(dy) - diagonal) + Metric::DIAGONAL_STEP * diagonal
+}
+
+/// A distance between grid points, used as the A* heuristic.
+///
+/// A heuristic is admissible, so that A* finds shortest paths, when it
+/// never exceeds the cost of moving between two points. With each
+/// movement model, on any map:
+///
+/// | Movement | Step costs | Exact on open ground | Admissible, weaker | Inadmissible |
+/// |---|---|---|---|---|
+/// | 4-way | 1 | `Manhattan` | `Chebyshev` | `EuclideanApprox` |
+/// | 8-way | 1 | `Chebyshev` | | `Manhattan`, `EuclideanApprox` |
+/// | 8-way | 100 and 141 | `EuclideanApprox` | | |
+///
+/// Each metric is the cost of moving to a neighbour in its own movement
+/// model, so [`distance`](Self::distance) between neighbours is also the
+/// step cost. An inadmissible heuristic makes A* return paths that are not
+/// the shortest, in ways that are hard to tell from map quirks; the
+/// `check-heuristic` feature makes debug builds of
+/// [`PathRange::astar_path`](crate::PathRange::astar_path) panic on
+/// estimates exceeding the cost of the path found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Metric {
+    /// [`manhattan`], for 4-way movement.
+    Manhattan,
+    /// [`chebyshev`], for 8-way movement where diagonal steps cost the
+    /// same as orthogonal ones.
+    Chebyshev,
+    /// [`euclidean_approx`], for 8-way movement where diagonal steps cost
+    /// more.
+    EuclideanApprox,
+}
+
+impl Metric {
+    /// Cost of an orthogonal step with [`Metric::EuclideanApprox`].
+    pub const ORTHOGONAL_STEP: i32 = 100;
+    /// Cost of a diagonal step with [`Metric::EuclideanApprox`], about
+    /// `ORTHOGONAL_STEP * √2`.
+    pub const DIAGONAL_STEP: i32 = 141;
+
+    /// The exact metric for moves of cost 1, in 8 directions if
+    /// `diagonals`, or 4.
+    pub fn unit_moves(diagonals: bool) -> Self {
+        if diagonals {
+            Self::Chebyshev
+        } else {
+            Self::Manhattan
+        }
+    }
+
+    /// Distance from `a` to `b`.
+    #[inline]
+    pub fn distance(self, a: Point, b: Point) -> i32 {
+        match self {
+            Self::Manhattan => manhattan(a, b),
+            Self::Chebyshev => chebyshev(a, b),
+            Self::EuclideanApprox => euclidean_approx(a, b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AstarPather, PathRange, Pather, UNREACHABLE, WeightedPather};
+    use gruid_core::Range;
+    use rand::rngs::StdRng;
+    use rand::{RngExt, SeedableRng};
+
+    /// Moves over the open cells of a random map, with step costs given by
+    /// `steps`, and `heuristic` as estimate.
+    struct Cave {
+        open: Vec<bool>,
+        diagonals: bool,
+        steps: Metric,
+        heuristic: Metric,
+    }
+
+    const SIZE: i32 = 24;
+
+    impl Cave {
+        fn new(seed: u64, diagonals: bool, steps: Metric, heuristic: Metric) -> Self {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let open = (0..SIZE * SIZE)
+                .map(|_| rng.random_range(0..10) < 7)
+                .collect();
+            Self {
+                open,
+                diagonals,
+                steps,
+                heuristic,
+            }
+        }
+
+        fn is_open(&self, p: Point) -> bool {
+            Range::new(0, 0, SIZE, SIZE).contains(p) && self.open[(p.y * SIZE + p.x) as usize]
+        }
+    }
+
+    impl Pather for Cave {
+        fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+            if self.diagonals {
+                buf.extend(p.neighbors_8().into_iter().filter(|&q| self.is_open(q)));
+            } else {
+                buf.extend(p.neighbors_4().into_iter().filter(|&q| self.is_open(q)));
+            }
+        }
+    }
+
+    impl WeightedPather for Cave {
+        fn cost(&self, from: Point, to: Point) -> i32 {
+            self.steps.distance(from, to)
+        }
+    }
+
+    impl AstarPather for Cave {
+        fn estimate(&self, from: Point, to: Point) -> i32 {
+            self.heuristic.distance(from, to)
+        }
+    }
+
+    /// Whether `heuristic` never exceeds the cost of the shortest path,
+    /// over some random maps.
+    fn admissible(diagonals: bool, steps: Metric, heuristic: Metric) -> bool {
+        let mut pr = PathRange::new(Range::new(0, 0, SIZE, SIZE));
+        (0..20).all(|seed| {
+            let cave = Cave::new(seed, diagonals, steps, heuristic);
+            let goal = Point::new(SIZE / 2, SIZE / 2);
+            if !cave.is_open(goal) {
+                return true;
+            }
+            pr.dijkstra_map(&cave, &[goal], UNREACHABLE)
+                .iter()
+                .all(|n| cave.estimate(n.pos, goal) <= n.cost)
+        })
+    }
+
+    #[test]
+    fn admissible_pairings() {
+        use Metric::*;
+        assert!(admissible(false, Manhattan, Manhattan));
+        assert!(admissible(false, Manhattan, Chebyshev));
+        assert!(admissible(true, Chebyshev, Chebyshev));
+        assert!(admissible(true, EuclideanApprox, EuclideanApprox));
+        // The mismatches of the table are caught.
+        assert!(!admissible(false, Manhattan, EuclideanApprox));
+        assert!(!admissible(true, Chebyshev, Manhattan));
+        assert!(!admissible(true, Chebyshev, EuclideanApprox));
+    }
+
+    #[test]
+    fn euclidean_approx_steps() {
+        let o = Point::new(0, 0);
+        assert_eq!(euclidean_approx(o, Point::new(1, 0)), 100);
+        assert_eq!(euclidean_approx(o, Point::new(-1, 1)), 141);
+        assert_eq!(
+            euclidean_approx(Point::new(2, 5), Point::new(-1, -2)),
+            3 * 141 + 4 * 100
+        );
+        assert_eq!(Metric::unit_moves(true), Metric::Chebyshev);
+        assert_eq!(Metric::unit_moves(false), Metric::Manhattan);
+    }
+
+    #[test]
+    fn euclidean_approx_triangle_inequality() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut point = || Point::new(rng.random_range(-50..50), rng.random_range(-50..50));
+        for _ in 0..10_000 {
+            let (a, b, c) = (point(), point(), point());
+            let d = euclidean_approx;
+            assert!(d(a, c) <= d(a, b) + d(b, c), "{a} {b} {c}");
+            assert_eq!(d(a, b), d(b, a));
+        }
+    }
+
+    #[cfg(feature = "check-heuristic")]
+    #[test]
+    #[should_panic(expected = "inadmissible heuristic")]
+    fn astar_catches_inadmissible_heuristic() {
+        let mut pr = PathRange::new(Range::new(0, 0, SIZE, SIZE));
+        let cave = Cave {
+            open: vec![true; (SIZE * SIZE) as usize],
+            diagonals: true,
+            steps: Metric::Chebyshev,
+            heuristic: Metric::Manhattan,
+        };
+        pr.astar_path(&cave, Point::new(0, 0), Point::new(5, 5));
+    }
+}
@@ -0,0 +1,163 @@
+//! Introspection of the last priority-queue search, for debugging and
+//! visualization overlays.
+
+use gruid_core::Point;
+
+use crate::PathRange;
+use crate::pathrange::Node;
+
+/// Which node cache the last A*, JPS or Dijkstra query wrote to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LastSearch {
+    None,
+    Astar,
+    Dijkstra,
+}
+
+impl PathRange {
+    /// Node cache and generation of the last search, if any.
+    fn last_nodes(&self) -> Option<(&[Node], u32)> {
+        let (nodes, generation) = match self.last_search {
+            LastSearch::None => return None,
+            LastSearch::Astar => (&self.astar_nodes, self.astar_generation),
+            LastSearch::Dijkstra => (&self.dijkstra_nodes, self.dijkstra_generation),
+        };
+        Some((&nodes[..self.rng.len()], generation))
+    }
+
+    fn last_nodes_where(&self, open: bool) -> impl Iterator<Item = Point> + '_ {
+        self.last_nodes()
+            .into_iter()
+            .flat_map(move |(nodes, generation)| {
+                nodes
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, n)| n.generation == generation && n.open == open)
+            })
+            .map(|(i, _)| self.point(i))
+    }
+
+    /// Positions expanded (closed) by the last [`astar_path`](Self::astar_path),
+    /// [`jps_path`](Self::jps_path) or [`dijkstra_map`](Self::dijkstra_map)
+    /// query.
+    ///
+    /// This is a debugging aid: the result is only valid until the next such
+    /// query and comes in no particular order. JPS only records jump points,
+    /// not every cell it scanned. Empty before any query.
+    pub fn last_visited(&self) -> impl Iterator<Item = Point> + '_ {
+        self.last_nodes_where(false)
+    }
+
+    /// Positions still on the open list when the last search stopped.
+    ///
+    /// Same caveats as [`last_visited`](Self::last_visited).
+    pub fn last_frontier(&self) -> impl Iterator<Item = Point> + '_ {
+        self.last_nodes_where(true)
+    }
+
+    /// Number of positions in [`last_visited`](Self::last_visited).
+    pub fn visited_count(&self) -> usize {
+        self.last_visited().count()
+    }
+
+    /// Best known cost from the start to `p` in the last search, for both
+    /// visited and frontier positions. `None` if the search never reached
+    /// `p`.
+    pub fn g_cost_at(&self, p: Point) -> Option<i32> {
+        let (nodes, generation) = self.last_nodes()?;
+        let n = &nodes[self.idx(p)?];
+        (n.generation == generation).then_some(n.g)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gruid_core::{Point, Range};
+
+    use crate::{AstarPather, PathRange, Pather, WeightedPather, manhattan};
+
+    struct Open {
+        rng: Range,
+    }
+
+    impl Pather for Open {
+        fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+            for d in [
+                Point::new(1, 0),
+                Point::new(-1, 0),
+                Point::new(0, 1),
+                Point::new(0, -1),
+            ] {
+                let q = p + d;
+                if self.rng.contains(q) {
+                    buf.push(q);
+                }
+            }
+        }
+    }
+
+    impl WeightedPather for Open {
+        fn cost(&self, _from: Point, _to: Point) -> i32 {
+            1
+        }
+    }
+
+    impl AstarPather for Open {
+        fn estimate(&self, from: Point, to: Point) -> i32 {
+            manhattan(from, to)
+        }
+    }
+
+    #[test]
+    fn empty_before_any_query() {
+        let rng = Range::new(0, 0, 10, 10);
+        let mut pr = PathRange::new(rng);
+        assert_eq!(pr.visited_count(), 0);
+        assert_eq!(pr.last_frontier().count(), 0);
+        assert_eq!(pr.g_cost_at(Point::new(0, 0)), None);
+
+        // A range change forgets the previous search too.
+        pr.astar_path(&Open { rng }, Point::new(0, 0), Point::new(5, 5))
+            .unwrap();
+        assert!(pr.visited_count() > 0);
+        pr.set_range(rng);
+        assert_eq!(pr.visited_count(), 0);
+    }
+
+    #[test]
+    fn visited_contains_astar_path() {
+        let rng = Range::new(0, 0, 20, 20);
+        let mut pr = PathRange::new(rng);
+        let path = pr
+            .astar_path(&Open { rng }, Point::new(1, 1), Point::new(15, 12))
+            .unwrap();
+        let visited: Vec<Point> = pr.last_visited().collect();
+        for (i, p) in path.iter().enumerate() {
+            assert!(visited.contains(p), "{p:?} missing");
+            assert_eq!(pr.g_cost_at(*p), Some(i as i32));
+        }
+        assert!(pr.last_frontier().all(|p| !visited.contains(&p)));
+    }
+
+    #[test]
+    fn jps_expands_fewer_nodes_than_astar() {
+        let rng = Range::new(0, 0, 40, 40);
+        let mut pr = PathRange::new(rng);
+        let (from, to) = (Point::new(2, 3), Point::new(37, 30));
+        pr.astar_path(&Open { rng }, from, to).unwrap();
+        let astar = pr.visited_count();
+        pr.jps_path(from, to, |p| rng.contains(p), false).unwrap();
+        let jps = pr.visited_count();
+        assert!(jps > 0);
+        assert!(astar > jps, "astar {astar}, jps {jps}");
+    }
+
+    #[test]
+    fn dijkstra_visits_everything_reached() {
+        let rng = Range::new(0, 0, 8, 8);
+        let mut pr = PathRange::new(rng);
+        let reached = pr.dijkstra_map(&Open { rng }, &[Point::new(0, 0)], 3).len();
+        assert_eq!(pr.visited_count(), reached);
+        assert_eq!(pr.g_cost_at(Point::new(2, 1)), Some(3));
+    }
+}
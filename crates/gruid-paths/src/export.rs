@@ -0,0 +1,676 @@
+//! Computed maps and paths in a compact form, for save games and network
+//! sync.
+//!
+//! Big distance maps, such as an autoexplore Dijkstra map over the whole
+//! level, can be costly to compute again. [`PathRange::export_dijkstra`],
+//! [`export_bfs`](PathRange::export_bfs) and
+//! [`export_cc`](PathRange::export_cc) copy the last map computed into a
+//! snapshot, and the matching `import_*` methods put it back into a
+//! `PathRange` over the same range, where [`dijkstra_at`](PathRange::dijkstra_at)
+//! and friends answer as they did. With the `serde` feature, snapshots
+//! serialize, carrying the format version they were made with.
+//!
+//! Distances are stored row by row as the difference from the previous
+//! cell, with runs of equal differences counted once: distance maps
+//! mostly go up or down by the same step along a row, so they shrink a
+//! lot. A 200×200 Dijkstra map from a single source in 8-way moves,
+//! 160 000 bytes as `i32`s, takes 1 271 bytes without walls, and 20 195
+//! with a pillar every fourth cell of every fourth row. Noisy data costs
+//! more: up to two bytes per cell for small differences, and six for the
+//! largest ones.
+//!
+//! Paths get their own encoding, [`encode_path`], storing each unit step
+//! in half a byte.
+
+use std::fmt;
+
+use gruid_core::{Point, Range};
+
+use crate::PathRange;
+use crate::explored::LastSearch;
+use crate::pathrange::UNREACHABLE;
+
+/// Version of the snapshot format written by this crate.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Why a snapshot or encoded path could not be read back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot was made with another version of the format.
+    Version { found: u32, expected: u32 },
+    /// The snapshot was taken over another range than the `PathRange` it
+    /// is imported into.
+    RangeMismatch { snapshot: Range, range: Range },
+    /// The encoded data is truncated or malformed.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version { found, expected } => {
+                write!(f, "snapshot format version {found}, expected {expected}")
+            }
+            Self::RangeMismatch { snapshot, range } => write!(
+                f,
+                "snapshot of range {snapshot} cannot be imported into a PathRange over {range}"
+            ),
+            Self::Corrupt(what) => write!(f, "corrupt snapshot: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// A map of one `i32` per cell of a range, encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct EncodedMap {
+    version: u32,
+    range: Range,
+    data: Vec<u8>,
+}
+
+impl EncodedMap {
+    fn new(range: Range, values: &[i32]) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            range,
+            data: encode_i32s(&values[..range.len()]),
+        }
+    }
+
+    /// Decode into the first cells of `out`, for a `PathRange` over
+    /// `range`. `out` is left alone on error.
+    fn decode_into(&self, range: Range, out: &mut [i32]) -> Result<(), SnapshotError> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version {
+                found: self.version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+        if self.range != range {
+            return Err(SnapshotError::RangeMismatch {
+                snapshot: self.range,
+                range,
+            });
+        }
+        let values = decode_i32s(&self.data, range.len())?;
+        out[..values.len()].copy_from_slice(&values);
+        Ok(())
+    }
+}
+
+macro_rules! map_snapshot {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $name(EncodedMap);
+
+        impl $name {
+            /// The range of the `PathRange` the snapshot was taken from.
+            pub fn range(&self) -> Range {
+                self.0.range
+            }
+
+            /// Version of the format the snapshot was made with.
+            pub fn version(&self) -> u32 {
+                self.0.version
+            }
+
+            /// Size of the encoded map, in bytes.
+            pub fn encoded_len(&self) -> usize {
+                self.0.data.len()
+            }
+        }
+    };
+}
+
+map_snapshot!(
+    /// The costs of the last [`dijkstra_map`](PathRange::dijkstra_map),
+    /// from [`PathRange::export_dijkstra`].
+    DijkstraMapSnapshot
+);
+
+map_snapshot!(
+    /// The distances of the last [`bfs_map`](PathRange::bfs_map), from
+    /// [`PathRange::export_bfs`].
+    BfsMapSnapshot
+);
+
+map_snapshot!(
+    /// The connected-component labels of the last
+    /// [`cc_map_all`](PathRange::cc_map_all) or [`cc_map`](PathRange::cc_map),
+    /// from [`PathRange::export_cc`].
+    CcLabelsSnapshot
+);
+
+impl PathRange {
+    /// Snapshot the costs of the last [`dijkstra_map`](Self::dijkstra_map).
+    pub fn export_dijkstra(&self) -> DijkstraMapSnapshot {
+        DijkstraMapSnapshot(EncodedMap::new(self.rng, &self.dijkstra_map))
+    }
+
+    /// Make [`dijkstra_at`](Self::dijkstra_at) answer as it did for the
+    /// `PathRange` `snapshot` was taken from. The explored cells of the
+    /// last Dijkstra search are forgotten.
+    ///
+    /// Fails, changing nothing, if the snapshot is of another range or
+    /// format version, or is corrupt.
+    pub fn import_dijkstra(&mut self, snapshot: &DijkstraMapSnapshot) -> Result<(), SnapshotError> {
+        snapshot.0.decode_into(self.rng, &mut self.dijkstra_map)?;
+        self.dijkstra_results.clear();
+        if matches!(self.last_search, LastSearch::Dijkstra) {
+            self.last_search = LastSearch::None;
+        }
+        Ok(())
+    }
+
+    /// Snapshot the distances of the last [`bfs_map`](Self::bfs_map).
+    pub fn export_bfs(&self) -> BfsMapSnapshot {
+        BfsMapSnapshot(EncodedMap::new(self.rng, &self.bfs_map))
+    }
+
+    /// Make [`bfs_at`](Self::bfs_at) answer as it did for the `PathRange`
+    /// `snapshot` was taken from.
+    ///
+    /// Fails, changing nothing, like [`import_dijkstra`](Self::import_dijkstra).
+    pub fn import_bfs(&mut self, snapshot: &BfsMapSnapshot) -> Result<(), SnapshotError> {
+        snapshot.0.decode_into(self.rng, &mut self.bfs_map)?;
+        self.bfs_results.clear();
+        Ok(())
+    }
+
+    /// Snapshot the labels of the last [`cc_map_all`](Self::cc_map_all) or
+    /// [`cc_map`](Self::cc_map).
+    pub fn export_cc(&self) -> CcLabelsSnapshot {
+        CcLabelsSnapshot(EncodedMap::new(self.rng, &self.cc_labels))
+    }
+
+    /// Make [`cc_at`](Self::cc_at) answer as it did for the `PathRange`
+    /// `snapshot` was taken from.
+    ///
+    /// Fails, changing nothing, like [`import_dijkstra`](Self::import_dijkstra).
+    pub fn import_cc(&mut self, snapshot: &CcLabelsSnapshot) -> Result<(), SnapshotError> {
+        snapshot.0.decode_into(self.rng, &mut self.cc_labels)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Encodings
+// ---------------------------------------------------------------------------
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// Reads varints off the front of a byte slice.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn varint(&mut self) -> Result<u64, SnapshotError> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&b, rest) = self
+                .0
+                .split_first()
+                .ok_or(SnapshotError::Corrupt("truncated"))?;
+            self.0 = rest;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b < 0x80 {
+                return Ok(v);
+            }
+        }
+        Err(SnapshotError::Corrupt("varint too long"))
+    }
+
+    fn signed(&mut self) -> Result<i64, SnapshotError> {
+        self.varint().map(unzigzag)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        let (&b, rest) = self
+            .0
+            .split_first()
+            .ok_or(SnapshotError::Corrupt("truncated"))?;
+        self.0 = rest;
+        Ok(b)
+    }
+
+    fn finish(&self) -> Result<(), SnapshotError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(SnapshotError::Corrupt("trailing bytes"))
+        }
+    }
+}
+
+/// `v` with [`UNREACHABLE`] moved to -1, and the negative values one
+/// down to make room, so that walls next to small distances are small
+/// differences.
+fn unreachable_low(v: i32) -> i64 {
+    match v {
+        UNREACHABLE => -1,
+        v if v < 0 => v as i64 - 1,
+        v => v as i64,
+    }
+}
+
+/// The inverse of [`unreachable_low`], if `v` has one.
+fn unreachable_high(v: i64) -> Option<i32> {
+    match v {
+        -1 => Some(UNREACHABLE),
+        v if v < 0 => i32::try_from(v + 1).ok(),
+        v => i32::try_from(v).ok().filter(|&v| v != UNREACHABLE),
+    }
+}
+
+/// Encode `values` as their count, then runs of equal differences from
+/// the previous value (0 before the first): each run a zigzag varint
+/// difference and a varint length. Values go through [`unreachable_low`]
+/// first.
+fn encode_i32s(values: &[i32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, values.len() as u64);
+    let mut prev = 0i64;
+    let mut run: Option<(i64, u64)> = None;
+    for &v in values {
+        let v = unreachable_low(v);
+        let delta = v - prev;
+        prev = v;
+        match &mut run {
+            Some((d, n)) if *d == delta => *n += 1,
+            _ => {
+                if let Some((d, n)) = run {
+                    write_varint(&mut out, zigzag(d));
+                    write_varint(&mut out, n);
+                }
+                run = Some((delta, 1));
+            }
+        }
+    }
+    if let Some((d, n)) = run {
+        write_varint(&mut out, zigzag(d));
+        write_varint(&mut out, n);
+    }
+    out
+}
+
+/// Decode what [`encode_i32s`] wrote, which must be `expected` values:
+/// the count is checked before anything is allocated.
+fn decode_i32s(bytes: &[u8], expected: usize) -> Result<Vec<i32>, SnapshotError> {
+    let mut r = Reader(bytes);
+    let len = r.varint()?;
+    if len != expected as u64 {
+        return Err(SnapshotError::Corrupt(
+            "cell count does not match the range",
+        ));
+    }
+    let len = expected;
+    let mut values = Vec::with_capacity(len);
+    let mut prev = 0i64;
+    while values.len() < len {
+        let delta = r.signed()?;
+        let n = r.varint()?;
+        if n == 0 || n > (len - values.len()) as u64 {
+            return Err(SnapshotError::Corrupt("bad run length"));
+        }
+        for _ in 0..n {
+            prev = prev
+                .checked_add(delta)
+                .ok_or(SnapshotError::Corrupt("value out of range"))?;
+            let v = unreachable_high(prev).ok_or(SnapshotError::Corrupt("value out of range"))?;
+            values.push(v);
+        }
+    }
+    r.finish()?;
+    Ok(values)
+}
+
+/// Nibble of a step that is not a unit step: its difference follows the
+/// nibbles, as two zigzag varints.
+const ESCAPE: u8 = 15;
+
+/// The eight unit steps, by nibble.
+const STEPS: [Point; 8] = [
+    Point::new(-1, -1),
+    Point::new(0, -1),
+    Point::new(1, -1),
+    Point::new(-1, 0),
+    Point::new(1, 0),
+    Point::new(-1, 1),
+    Point::new(0, 1),
+    Point::new(1, 1),
+];
+
+/// Encode `path` compactly: its length and first point, then each step as
+/// a nibble, two to a byte. Steps to one of the eight neighbours, as in
+/// the paths of [`astar_path`](PathRange::astar_path), take half a byte;
+/// others, such as jumps, take a few more after the nibbles.
+pub fn encode_path(path: &[Point]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, path.len() as u64);
+    let Some(first) = path.first() else {
+        return out;
+    };
+    write_varint(&mut out, zigzag(first.x as i64));
+    write_varint(&mut out, zigzag(first.y as i64));
+    // In i64: steps between far apart points do not fit in an i32.
+    let deltas = path
+        .windows(2)
+        .map(|w| (w[1].x as i64 - w[0].x as i64, w[1].y as i64 - w[0].y as i64));
+    let mut escaped = Vec::new();
+    let codes: Vec<u8> = deltas
+        .map(
+            |d| match STEPS.iter().position(|s| (s.x as i64, s.y as i64) == d) {
+                Some(i) => i as u8,
+                None => {
+                    escaped.push(d);
+                    ESCAPE
+                }
+            },
+        )
+        .collect();
+    for pair in codes.chunks(2) {
+        out.push(pair[0] | pair.get(1).map_or(0, |c| c << 4));
+    }
+    for (dx, dy) in escaped {
+        write_varint(&mut out, zigzag(dx));
+        write_varint(&mut out, zigzag(dy));
+    }
+    out
+}
+
+/// Decode what [`encode_path`] wrote.
+pub fn decode_path(bytes: &[u8]) -> Result<Vec<Point>, SnapshotError> {
+    let mut r = Reader(bytes);
+    let len = r.varint()? as usize;
+    if len == 0 {
+        r.finish()?;
+        return Ok(Vec::new());
+    }
+    if len - 1 > bytes.len() * 2 {
+        return Err(SnapshotError::Corrupt("truncated"));
+    }
+    let coord = |r: &mut Reader| {
+        i32::try_from(r.signed()?).map_err(|_| SnapshotError::Corrupt("value out of range"))
+    };
+    let first = Point::new(coord(&mut r)?, coord(&mut r)?);
+    let mut codes = Vec::with_capacity(len - 1);
+    while codes.len() < len - 1 {
+        let b = r.byte()?;
+        codes.push(b & 0xf);
+        if codes.len() < len - 1 {
+            codes.push(b >> 4);
+        }
+    }
+    let mut path = Vec::with_capacity(len);
+    path.push(first);
+    let mut p = first;
+    for code in codes {
+        let (dx, dy) = match code {
+            ESCAPE => (r.signed()?, r.signed()?),
+            c => {
+                let s = STEPS
+                    .get(c as usize)
+                    .ok_or(SnapshotError::Corrupt("bad step"))?;
+                (s.x as i64, s.y as i64)
+            }
+        };
+        let step = |v: i32, d: i64| {
+            (v as i64)
+                .checked_add(d)
+                .and_then(|v| i32::try_from(v).ok())
+                .ok_or(SnapshotError::Corrupt("value out of range"))
+        };
+        p = Point::new(step(p.x, dx)?, step(p.y, dy)?);
+        path.push(p);
+    }
+    r.finish()?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{Pather, WeightedPather};
+
+    /// 8-way moves of cost 1 on open ground, except where `wall` says.
+    struct Open<F>(F);
+
+    impl<F: Fn(Point) -> bool> Pather for Open<F> {
+        fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+            for d in STEPS {
+                if !(self.0)(p + d) {
+                    buf.push(p + d);
+                }
+            }
+        }
+    }
+
+    impl<F: Fn(Point) -> bool> WeightedPather for Open<F> {
+        fn cost(&self, _from: Point, _to: Point) -> i32 {
+            1
+        }
+    }
+
+    fn pillars(p: Point) -> bool {
+        p.x % 4 == 2 && p.y % 4 == 2
+    }
+
+    #[test]
+    fn maps_round_trip() {
+        let rng = Range::new(0, 0, 30, 20);
+        let pather = Open(pillars);
+        let mut pr = PathRange::new(rng);
+        pr.dijkstra_map(&pather, &[Point::new(3, 4)], 25);
+        pr.bfs_map(&pather, &[Point::new(29, 19)], 12);
+        pr.cc_map_all(&pather);
+        let (dijkstra, bfs, cc) = (pr.export_dijkstra(), pr.export_bfs(), pr.export_cc());
+        assert_eq!(dijkstra.version(), SNAPSHOT_VERSION);
+        assert_eq!(dijkstra.range(), rng);
+
+        let mut back = PathRange::new(rng);
+        back.import_dijkstra(&dijkstra).unwrap();
+        back.import_bfs(&bfs).unwrap();
+        back.import_cc(&cc).unwrap();
+        for p in rng.iter() {
+            assert_eq!(back.dijkstra_at(p), pr.dijkstra_at(p), "{p:?}");
+            assert_eq!(back.bfs_at(p), pr.bfs_at(p), "{p:?}");
+            assert_eq!(back.cc_at(p), pr.cc_at(p), "{p:?}");
+        }
+        assert_eq!(back.dijkstra_at(Point::new(2, 2)), UNREACHABLE);
+        assert_eq!(back.dijkstra_at(Point::new(3, 4)), 0);
+    }
+
+    #[test]
+    fn range_mismatch_is_rejected() {
+        let pr = PathRange::new(Range::new(0, 0, 10, 10));
+        let snapshot = pr.export_dijkstra();
+        // Same size elsewhere is another map.
+        let mut other = PathRange::new(Range::new(5, 0, 15, 10));
+        other.dijkstra_map(&Open(|_| false), &[Point::new(5, 0)], 100);
+        let err = other.import_dijkstra(&snapshot).unwrap_err();
+        assert_eq!(
+            err,
+            SnapshotError::RangeMismatch {
+                snapshot: Range::new(0, 0, 10, 10),
+                range: Range::new(5, 0, 15, 10),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "snapshot of range [(0, 0)-(10, 10)) cannot be imported into a PathRange \
+             over [(5, 0)-(15, 10))"
+        );
+        // Nothing changed.
+        assert_eq!(other.dijkstra_at(Point::new(7, 0)), 2);
+
+        let mut old = pr.export_bfs();
+        old.0.version = 0;
+        let mut same = PathRange::new(Range::new(0, 0, 10, 10));
+        assert_eq!(
+            same.import_bfs(&old),
+            Err(SnapshotError::Version {
+                found: 0,
+                expected: SNAPSHOT_VERSION
+            })
+        );
+        let mut cut = pr.export_cc();
+        cut.0.data.pop();
+        assert!(matches!(
+            same.import_cc(&cut),
+            Err(SnapshotError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn run_length_encoding_is_lossless() {
+        let alternating: Vec<i32> = (0..1000).map(|i| if i % 2 == 0 { 0 } else { 7 }).collect();
+        let extremes: Vec<i32> = (0..100)
+            .map(|i| if i % 2 == 0 { i32::MIN } else { i32::MAX })
+            .collect();
+        let cases: [&[i32]; 6] = [
+            &[],
+            &[UNREACHABLE],
+            &[5, 5, 5, 5, 6, 7, 8, 8],
+            &alternating,
+            &extremes,
+            &[-1, UNREACHABLE, 0, i32::MIN, UNREACHABLE, -1],
+        ];
+        for values in cases {
+            let bytes = encode_i32s(values);
+            assert_eq!(decode_i32s(&bytes, values.len()).unwrap(), values);
+        }
+        // Alternating small steps: one byte of difference and one of run
+        // length per value, no worse.
+        assert_eq!(encode_i32s(&alternating).len(), 2 + 2 * 1000);
+        // A run longer than the count is caught.
+        assert!(decode_i32s(&[1, 2, 3], 1).is_err());
+        assert!(decode_i32s(&[2, 2], 2).is_err());
+        // So is a count other than expected, before anything is allocated.
+        assert!(decode_i32s(&encode_i32s(&[1, 2]), 3).is_err());
+    }
+
+    #[test]
+    fn huge_counts_are_rejected_up_front() {
+        // A count and a run of 2^40 cells, in a dozen bytes.
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, 1 << 40);
+        write_varint(&mut bytes, zigzag(1));
+        write_varint(&mut bytes, 1 << 40);
+        assert_eq!(
+            decode_i32s(&bytes, 100),
+            Err(SnapshotError::Corrupt(
+                "cell count does not match the range"
+            ))
+        );
+        let snapshot = BfsMapSnapshot(EncodedMap {
+            version: SNAPSHOT_VERSION,
+            range: Range::new(0, 0, 10, 10),
+            data: bytes,
+        });
+        let mut pr = PathRange::new(Range::new(0, 0, 10, 10));
+        assert!(matches!(
+            pr.import_bfs(&snapshot),
+            Err(SnapshotError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn smooth_maps_stay_small() {
+        let rng = Range::new(0, 0, 200, 200);
+        let raw = 4 * rng.len();
+        let mut pr = PathRange::new(rng);
+        pr.dijkstra_map(&Open(|_| false), &[Point::new(100, 100)], i32::MAX);
+        let open = pr.export_dijkstra().encoded_len();
+        pr.dijkstra_map(&Open(pillars), &[Point::new(100, 100)], i32::MAX);
+        let walled = pr.export_dijkstra().encoded_len();
+        // The module documentation gives 1 271 and 20 195 bytes.
+        assert!(open <= 1_300 && walled <= 20_500, "{open} {walled}");
+        assert!(open * 100 < raw && walled * 7 < raw);
+    }
+
+    #[test]
+    fn paths_round_trip() {
+        let mut pr = PathRange::new(Range::new(-5, -5, 40, 40));
+        let pather = crate::PathSnapshot::build(pr.range(), |p| (!pillars(p)).then_some(1))
+            .with_diagonals(true);
+        let path = pr
+            .astar_path(&pather, Point::new(-5, -5), Point::new(39, 30))
+            .unwrap();
+        let bytes = encode_path(&path);
+        assert_eq!(decode_path(&bytes).unwrap(), path);
+        // Two steps a byte, after the length and first point.
+        assert_eq!(bytes.len(), 3 + (path.len() - 1).div_ceil(2));
+
+        let jumps = [
+            Point::new(1, 1),
+            Point::new(2, 2),
+            Point::new(10, 2),
+            Point::new(10, -300),
+            Point::new(9, -300),
+        ];
+        let cases: [&[Point]; 4] = [&[], &[Point::new(i32::MIN, 7)], &jumps, &jumps[..3]];
+        for path in cases {
+            assert_eq!(decode_path(&encode_path(path)).unwrap(), path);
+        }
+        let mut cut = encode_path(&jumps);
+        cut.pop();
+        assert!(decode_path(&cut).is_err());
+    }
+
+    #[test]
+    fn paths_at_the_edges_of_i32() {
+        // Steps wider than an i32 still encode.
+        let far = [Point::new(i32::MIN, 0), Point::new(i32::MAX, 0)];
+        assert_eq!(decode_path(&encode_path(&far)).unwrap(), far);
+        // A step off the edge is corrupt, not an overflow.
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, 2);
+        write_varint(&mut bytes, zigzag(i32::MAX as i64));
+        write_varint(&mut bytes, zigzag(0));
+        bytes.push(ESCAPE);
+        write_varint(&mut bytes, zigzag(1));
+        write_varint(&mut bytes, zigzag(0));
+        assert_eq!(
+            decode_path(&bytes),
+            Err(SnapshotError::Corrupt("value out of range"))
+        );
+        // Same with a unit step.
+        let mut bytes = encode_path(&[Point::new(i32::MAX, 0), Point::new(i32::MAX - 1, 0)]);
+        *bytes.last_mut().unwrap() = 4;
+        assert!(decode_path(&bytes).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshots_serialize() {
+        let mut pr = PathRange::new(Range::new(0, 0, 12, 8));
+        pr.bfs_map(&Open(pillars), &[Point::new(0, 0)], 100);
+        let snapshot = pr.export_bfs();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let back: BfsMapSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, snapshot);
+        let mut other = PathRange::new(Range::new(0, 0, 12, 8));
+        other.import_bfs(&back).unwrap();
+        assert_eq!(
+            other.bfs_at(Point::new(11, 7)),
+            pr.bfs_at(Point::new(11, 7))
+        );
+    }
+}
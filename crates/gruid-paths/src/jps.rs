@@ -11,6 +11,8 @@ use std::collections::BinaryHeap;
 use gruid_core::Point;
 
 use crate::PathRange;
+use crate::distance::Metric;
+use crate::explored::LastSearch;
 use crate::pathrange::NodeRef;
 
 fn sign(n: i32) -> i32 {
@@ -94,6 +96,7 @@ impl PathRange {
         diags: bool,
     ) -> bool {
         path.clear();
+        self.last_search = LastSearch::None;
 
         if !self.rng.contains(from) || !self.rng.contains(to) {
             return false;
@@ -105,6 +108,7 @@ impl PathRange {
 
         self.astar_generation = self.astar_generation.wrapping_add(1);
         let cur_gen = self.astar_generation;
+        self.last_search = LastSearch::Astar;
 
         // Mark start as closed.
         let Some(si) = self.idx(from) else {
@@ -233,11 +237,7 @@ impl PathRange {
             return; // closed with better cost
         }
         let parent_idx = self.idx(parent).unwrap_or(usize::MAX);
-        let delta = p - to;
-        let dx = delta.x.abs();
-        let dy = delta.y.abs();
-        let h = if diags { dx.max(dy) } else { dx + dy };
-        let rank = cost + h;
+        let rank = cost + Metric::unit_moves(diags).distance(p, to);
 
         let nb = &mut self.astar_nodes[pi];
         nb.g = cost;
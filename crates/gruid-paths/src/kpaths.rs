@@ -0,0 +1,303 @@
+//! Alternative near-optimal paths.
+//!
+//! [`PathRange::k_paths`] finds several short paths between two points that
+//! avoid each other where they can, so that agents sharing a route do not
+//! all walk the same cells. It re-runs A* with a cost penalty on the cells
+//! of the paths found so far: cheap, and usually diverse enough for patrol
+//! routes. Penalties only make moves dearer, so the paths never go where
+//! the pather does not allow.
+
+use std::collections::HashMap;
+
+use gruid_core::Point;
+
+use crate::PathRange;
+use crate::traits::{AstarPather, Pather, WeightedPather};
+
+/// A* runs per requested path before [`PathRange::k_paths`] gives up
+/// finding new ones.
+const ATTEMPTS_PER_PATH: usize = 4;
+
+/// Alternatives considered by [`PathRange::random_near_optimal_path`].
+const RANDOM_CANDIDATES: usize = 8;
+
+/// How strongly [`PathRange::k_paths`] pushes each path away from the
+/// previous ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiversityPenalty {
+    /// Extra cost of entering a cell, for each earlier path through it, in
+    /// the pather's cost units. Larger values accept longer detours.
+    /// Penalties add up to at most `i32::MAX` per cell.
+    pub cost: u32,
+}
+
+impl Default for DiversityPenalty {
+    fn default() -> Self {
+        Self { cost: 2 }
+    }
+}
+
+/// Pather adding the penalty of the cells it enters to their cost.
+struct PenaltyPather<'a, P> {
+    pather: &'a P,
+    penalties: &'a HashMap<Point, i32>,
+}
+
+impl<P: Pather> Pather for PenaltyPather<'_, P> {
+    fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+        self.pather.neighbors(p, buf);
+    }
+}
+
+impl<P: WeightedPather> WeightedPather for PenaltyPather<'_, P> {
+    fn cost(&self, from: Point, to: Point) -> i32 {
+        let penalty = self.penalties.get(&to).copied().unwrap_or(0);
+        self.pather.cost(from, to).saturating_add(penalty)
+    }
+}
+
+impl<P: AstarPather> AstarPather for PenaltyPather<'_, P> {
+    fn estimate(&self, from: Point, to: Point) -> i32 {
+        self.pather.estimate(from, to)
+    }
+}
+
+/// Cost of `path` for `pather`.
+fn path_cost<P: WeightedPather>(pather: &P, path: &[Point]) -> i32 {
+    path.windows(2).map(|w| pather.cost(w[0], w[1])).sum()
+}
+
+impl PathRange {
+    /// Find up to `k` distinct paths from `from` to `to`, with their costs,
+    /// sorted by cost.
+    ///
+    /// The first path is the one [`astar_path`](Self::astar_path) returns.
+    /// Each next search adds `diversity` to the cost of the cells used by
+    /// the paths found so far, so later paths take other cells when the
+    /// detour is cheap enough. Searches finding an earlier path again raise
+    /// its penalties further; after a few of them in a row, the paths found
+    /// so far are returned, so fewer than `k` come back when the map offers
+    /// no alternative. Results are deterministic.
+    ///
+    /// Unlike [`astar_path`](Self::astar_path), this allocates on each call.
+    pub fn k_paths<P: AstarPather>(
+        &mut self,
+        pather: &P,
+        from: Point,
+        to: Point,
+        k: usize,
+        diversity: DiversityPenalty,
+    ) -> Vec<(Vec<Point>, i32)> {
+        let mut paths: Vec<(Vec<Point>, i32)> = Vec::new();
+        let mut penalties: HashMap<Point, i32> = HashMap::new();
+        let step = i32::try_from(diversity.cost).unwrap_or(i32::MAX);
+        for _ in 0..k.saturating_mul(ATTEMPTS_PER_PATH) {
+            if paths.len() >= k {
+                break;
+            }
+            let pp = PenaltyPather {
+                pather,
+                penalties: &penalties,
+            };
+            let Some(path) = self.astar_path(&pp, from, to) else {
+                break;
+            };
+            // The endpoints are not penalised: a path without cells in
+            // between, from a point to itself or a neighbour, leaves the
+            // next searches unchanged.
+            let inner = path
+                .get(1..path.len().saturating_sub(1))
+                .unwrap_or_default();
+            let stuck = inner.is_empty();
+            for &p in inner {
+                let penalty = penalties.entry(p).or_default();
+                *penalty = penalty.saturating_add(step);
+            }
+            if paths.iter().all(|(q, _)| *q != path) {
+                let cost = path_cost(pather, &path);
+                paths.push((path, cost));
+            }
+            if stuck {
+                break;
+            }
+        }
+        // Stable: the plain optimum stays first among equal costs.
+        paths.sort_by_key(|&(_, cost)| cost);
+        paths
+    }
+
+    /// A random path from `from` to `to` costing at most `slack` more than
+    /// the shortest one, or `None` if there is no path.
+    ///
+    /// The path is picked among the alternatives found by
+    /// [`k_paths`](Self::k_paths) within the cost limit, so different
+    /// agents can take different routes between the same points. `choose`
+    /// gets their number `n` and returns the index of the one to take,
+    /// below `n`; with `rand`, `|n| rng.random_range(0..n)` picks
+    /// uniformly. Larger indices take the last one.
+    pub fn random_near_optimal_path<P: AstarPather>(
+        &mut self,
+        pather: &P,
+        from: Point,
+        to: Point,
+        slack: i32,
+        choose: impl FnOnce(usize) -> usize,
+    ) -> Option<Vec<Point>> {
+        let mut paths = self.k_paths(
+            pather,
+            from,
+            to,
+            RANDOM_CANDIDATES,
+            DiversityPenalty::default(),
+        );
+        let max = paths.first()?.1.saturating_add(slack.max(0));
+        paths.retain(|&(_, cost)| cost <= max);
+        let i = choose(paths.len()).min(paths.len() - 1);
+        Some(paths.swap_remove(i).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manhattan;
+    use rand::rngs::StdRng;
+    use rand::{RngExt, SeedableRng};
+
+    /// 4-way moves over the non-'#' cells of a map.
+    struct Map(&'static [&'static str]);
+
+    impl Map {
+        fn at(&self, p: Point) -> Option<u8> {
+            let row = self.0.get(usize::try_from(p.y).ok()?)?.as_bytes();
+            row.get(usize::try_from(p.x).ok()?).copied()
+        }
+
+        fn find(&self, c: u8) -> Point {
+            (0..self.0.len() as i32)
+                .flat_map(|y| (0..self.0[0].len() as i32).map(move |x| Point::new(x, y)))
+                .find(|&p| self.at(p) == Some(c))
+                .unwrap()
+        }
+
+        fn range(&self) -> gruid_core::Range {
+            gruid_core::Range::new(0, 0, self.0[0].len() as i32, self.0.len() as i32)
+        }
+    }
+
+    impl Pather for Map {
+        fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+            buf.extend(
+                p.neighbors_4()
+                    .into_iter()
+                    .filter(|&q| self.at(q).is_some_and(|c| c != b'#')),
+            );
+        }
+    }
+
+    impl WeightedPather for Map {
+        fn cost(&self, _from: Point, _to: Point) -> i32 {
+            1
+        }
+    }
+
+    impl AstarPather for Map {
+        fn estimate(&self, from: Point, to: Point) -> i32 {
+            manhattan(from, to)
+        }
+    }
+
+    const ROOM: Map = Map(&[
+        "##########",
+        "#S.......#",
+        "#...##...#",
+        "#...##...#",
+        "#.......G#",
+        "##########",
+    ]);
+
+    const CORRIDORS: Map = Map(&[
+        "#########",
+        "#.......#",
+        "#S#####G#",
+        "#.......#",
+        "#########",
+    ]);
+
+    #[test]
+    fn k_paths_are_distinct_and_sorted() {
+        let (from, to) = (ROOM.find(b'S'), ROOM.find(b'G'));
+        let mut pr = PathRange::new(ROOM.range());
+        let plain = pr.astar_path(&ROOM, from, to).unwrap();
+        let paths = pr.k_paths(&ROOM, from, to, 5, DiversityPenalty::default());
+        assert_eq!(paths.len(), 5);
+        assert_eq!(paths[0].0, plain);
+        assert_eq!(paths[0].1, plain.len() as i32 - 1);
+        for (i, (path, cost)) in paths.iter().enumerate() {
+            assert_eq!(*cost, path_cost(&ROOM, path));
+            assert!(paths[..i].iter().all(|(q, _)| q != path));
+            // Only moves the pather allows.
+            assert!(path.windows(2).all(|w| {
+                let mut buf = Vec::new();
+                ROOM.neighbors(w[0], &mut buf);
+                buf.contains(&w[1])
+            }));
+        }
+        assert!(paths.windows(2).all(|w| w[0].1 <= w[1].1));
+        // Deterministic.
+        assert_eq!(
+            pr.k_paths(&ROOM, from, to, 5, DiversityPenalty::default()),
+            paths
+        );
+    }
+
+    #[test]
+    fn k_paths_one_per_corridor() {
+        let (from, to) = (CORRIDORS.find(b'S'), CORRIDORS.find(b'G'));
+        let mut pr = PathRange::new(CORRIDORS.range());
+        let paths = pr.k_paths(&CORRIDORS, from, to, 2, DiversityPenalty::default());
+        assert_eq!(paths.len(), 2);
+        let rows: Vec<i32> = paths.iter().map(|(path, _)| path[3].y).collect();
+        assert!(rows.contains(&1) && rows.contains(&3), "{rows:?}");
+        // There are no others.
+        let paths = pr.k_paths(&CORRIDORS, from, to, 5, DiversityPenalty::default());
+        assert_eq!(paths.len(), 2);
+    }
+
+    #[test]
+    fn random_path_within_slack() {
+        let (from, to) = (CORRIDORS.find(b'S'), CORRIDORS.find(b'G'));
+        let mut pr = PathRange::new(CORRIDORS.range());
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut rows = Vec::new();
+        for _ in 0..20 {
+            let path = pr
+                .random_near_optimal_path(&CORRIDORS, from, to, 0, |n| rng.random_range(0..n))
+                .unwrap();
+            assert_eq!(path_cost(&CORRIDORS, &path), 8);
+            rows.push(path[3].y);
+        }
+        assert!(rows.contains(&1) && rows.contains(&3));
+        let wall = Point::new(0, 0);
+        assert_eq!(
+            pr.random_near_optimal_path(&CORRIDORS, from, wall, 5, |n| rng.random_range(0..n)),
+            None
+        );
+    }
+
+    #[test]
+    fn same_point_and_neighbour() {
+        let from = ROOM.find(b'S');
+        let mut pr = PathRange::new(ROOM.range());
+        let paths = pr.k_paths(&ROOM, from, from, 3, DiversityPenalty::default());
+        assert_eq!(paths, vec![(vec![from], 0)]);
+        assert_eq!(
+            pr.random_near_optimal_path(&ROOM, from, from, 5, |n| n),
+            Some(vec![from])
+        );
+        let next = from + Point::new(1, 0);
+        let paths = pr.k_paths(&ROOM, from, next, 3, DiversityPenalty::default());
+        assert_eq!(paths, vec![(vec![from, next], 1)]);
+    }
+}
@@ -8,9 +8,23 @@
 //! - **BFS** unweighted distance maps ([`PathRange::bfs_map`])
 //! - **Jump Point Search** optimised uniform-cost pathfinding ([`PathRange::jps_path`])
 //! - **Connected Components** labelling ([`PathRange::cc_map_all`], [`PathRange::cc_map`])
+//! - **Goal bounding** precomputed edge pruning for repeated A* queries on
+//!   static maps ([`PathRange::build_goal_bounds`])
+//! - **Region-limited** variants that never expand outside a [`Range`](gruid_core::Range)
+//!   or mask ([`PathRange::astar_path_within`], [`RegionPather`], [`MaskPather`])
+//! - **Alternative paths** that are near-optimal but avoid each other
+//!   ([`PathRange::k_paths`], [`PathRange::random_near_optimal_path`])
+//! - **Stateful A\*** over positions paired with a small state, such as the
+//!   keys held ([`astar_path_stateful`], [`is_solvable`])
+//! - **Concurrent queries** on a frozen map from several threads
+//!   ([`PathSnapshot`], each thread with its own [`SearchScratch`])
+//! - **Saving computed maps and paths** in a compact form, for save games
+//!   and network sync ([`PathRange::export_dijkstra`], [`encode_path`])
 //!
 //! All algorithms operate through [`PathRange`], which owns and reuses internal
-//! caches so that repeated queries incur zero allocations after warm-up.
+//! caches so that repeated queries incur zero allocations after warm-up. The
+//! cells explored by the last search can be inspected for debugging with
+//! [`PathRange::last_visited`] and friends.
 //!
 //! # Trait hierarchy
 //!
@@ -19,18 +33,41 @@
 //! | [`Pather`] | BFS, connected components |
 //! | [`WeightedPather`] : [`Pather`] | Dijkstra |
 //! | [`AstarPather`] : [`WeightedPather`] | A* |
+//!
+//! # Metrics
+//!
+//! The A* heuristic must match the movement model, or paths are not the
+//! shortest: [`manhattan`] for 4-way moves, [`chebyshev`] for 8-way moves
+//! of equal cost, and [`euclidean_approx`] for 8-way moves where diagonals
+//! cost more. [`Metric`] names them, and tells which is admissible for
+//! which movement model.
 
 mod astar;
 mod bfs;
 mod cc;
 mod dijkstra;
 mod distance;
+mod explored;
+mod export;
+mod goal_bounds;
 mod jps;
+mod kpaths;
 mod neighbors;
 mod pathrange;
+mod region;
+mod snapshot;
+mod stateful;
 mod traits;
 
-pub use distance::{chebyshev, manhattan};
+pub use distance::{Metric, chebyshev, euclidean_approx, manhattan};
+pub use export::{
+    BfsMapSnapshot, CcLabelsSnapshot, DijkstraMapSnapshot, SNAPSHOT_VERSION, SnapshotError,
+    decode_path, encode_path,
+};
+pub use kpaths::DiversityPenalty;
 pub use neighbors::Neighbors;
 pub use pathrange::{PathNode, PathRange, UNREACHABLE};
+pub use region::{MaskPather, RegionPather, clamp_path_to_region};
+pub use snapshot::{PathSnapshot, SearchScratch};
+pub use stateful::{DoorId, astar_path_stateful, is_solvable};
 pub use traits::{AstarPather, Pather, WeightedPather};
@@ -1,5 +1,8 @@
 use gruid_core::{Point, Range};
 
+use crate::explored::LastSearch;
+use crate::goal_bounds::GoalBounds;
+
 /// A position with an associated cost, returned from Dijkstra / BFS map queries.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -12,7 +15,7 @@ pub struct PathNode {
 // Internal node for A*/Dijkstra priority-queue searches
 // ---------------------------------------------------------------------------
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub(crate) struct Node {
     pub(crate) g: i32,
     pub(crate) f: i32,
@@ -34,7 +37,7 @@ impl Default for Node {
 }
 
 /// Reference into the node array, ordered by `f` for use in `BinaryHeap`.
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub(crate) struct NodeRef {
     pub(crate) idx: usize,
     pub(crate) f: i32,
@@ -76,6 +79,8 @@ pub struct PathRange {
     pub(crate) dijkstra_generation: u32,
     pub(crate) dijkstra_results: Vec<PathNode>,
     pub(crate) dijkstra_map: Vec<i32>,
+    // which node cache the last A*/JPS/Dijkstra query used
+    pub(crate) last_search: LastSearch,
     // BFS caches
     pub(crate) bfs_map: Vec<i32>,
     pub(crate) bfs_queue: Vec<usize>,
@@ -85,6 +90,8 @@ pub struct PathRange {
     pub(crate) cc_stack: Vec<usize>,
     // shared scratch buffer for neighbor queries
     pub(crate) nbuf: Vec<Point>,
+    // precomputed goal bounds, if built
+    pub(crate) goal_bounds: Option<GoalBounds>,
 }
 
 impl PathRange {
@@ -101,12 +108,14 @@ impl PathRange {
             dijkstra_generation: 0,
             dijkstra_results: Vec::new(),
             dijkstra_map: vec![UNREACHABLE; len],
+            last_search: LastSearch::None,
             bfs_map: vec![UNREACHABLE; len],
             bfs_queue: Vec::new(),
             bfs_results: Vec::new(),
             cc_labels: vec![-1; len],
             cc_stack: Vec::new(),
             nbuf: Vec::with_capacity(8),
+            goal_bounds: None,
         }
     }
 
@@ -120,6 +129,8 @@ impl PathRange {
         let old_capacity = self.astar_nodes.len();
         self.rng = rng;
         self.width = rng.width().max(0) as usize;
+        self.last_search = LastSearch::None;
+        self.goal_bounds = None;
 
         if new_len <= old_capacity {
             // Fits within existing capacity — just bump generations so
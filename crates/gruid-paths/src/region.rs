@@ -0,0 +1,338 @@
+//! Region-limited pathfinding.
+//!
+//! [`RegionPather`] and [`MaskPather`] wrap another pather and drop every
+//! neighbor outside an allowed area, so searches never expand beyond it.
+//! The filtering happens in place in the neighbor buffer, without extra
+//! allocation.
+
+use std::borrow::Cow;
+
+use gruid_core::{Point, Range};
+
+use crate::PathRange;
+use crate::pathrange::PathNode;
+use crate::traits::{AstarPather, Pather, WeightedPather};
+
+// ---------------------------------------------------------------------------
+// Wrapper pathers
+// ---------------------------------------------------------------------------
+
+/// Pather adapter that only allows moves inside a [`Range`].
+pub struct RegionPather<'a, P> {
+    pather: &'a P,
+    region: Range,
+}
+
+impl<'a, P> RegionPather<'a, P> {
+    /// Wrap `pather`, restricting it to `region`.
+    pub fn new(pather: &'a P, region: Range) -> Self {
+        Self { pather, region }
+    }
+
+    /// Whether `p` is inside the allowed region.
+    #[inline]
+    pub fn allows(&self, p: Point) -> bool {
+        self.region.contains(p)
+    }
+}
+
+/// Pather adapter that only allows moves onto cells whose mask entry is
+/// `true`.
+///
+/// The mask is row-major over `range`, one entry per cell; points outside
+/// `range` or past the end of the mask are never allowed.
+pub struct MaskPather<'a, P> {
+    pather: &'a P,
+    range: Range,
+    mask: &'a [bool],
+}
+
+impl<'a, P> MaskPather<'a, P> {
+    /// Wrap `pather`, restricting it to the cells of `range` set in `mask`.
+    pub fn new(pather: &'a P, range: Range, mask: &'a [bool]) -> Self {
+        Self {
+            pather,
+            range,
+            mask,
+        }
+    }
+
+    /// Whether `p` is allowed by the mask.
+    #[inline]
+    pub fn allows(&self, p: Point) -> bool {
+        if !self.range.contains(p) {
+            return false;
+        }
+        let q = p - self.range.min;
+        let i = (q.y * self.range.width() + q.x) as usize;
+        self.mask.get(i).copied().unwrap_or(false)
+    }
+}
+
+macro_rules! impl_filtered_pather {
+    ($ty:ident) => {
+        impl<P: Pather> Pather for $ty<'_, P> {
+            fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+                self.pather.neighbors(p, buf);
+                buf.retain(|&q| self.allows(q));
+            }
+        }
+
+        impl<P: WeightedPather> WeightedPather for $ty<'_, P> {
+            fn cost(&self, from: Point, to: Point) -> i32 {
+                self.pather.cost(from, to)
+            }
+        }
+
+        impl<P: AstarPather> AstarPather for $ty<'_, P> {
+            fn estimate(&self, from: Point, to: Point) -> i32 {
+                self.pather.estimate(from, to)
+            }
+        }
+    };
+}
+
+impl_filtered_pather!(RegionPather);
+impl_filtered_pather!(MaskPather);
+
+/// Keep only the sources accepted by `allows`, borrowing when nothing is
+/// filtered out.
+fn allowed_sources(sources: &[Point], allows: impl Fn(Point) -> bool) -> Cow<'_, [Point]> {
+    if sources.iter().all(|&p| allows(p)) {
+        Cow::Borrowed(sources)
+    } else {
+        Cow::Owned(sources.iter().copied().filter(|&p| allows(p)).collect())
+    }
+}
+
+/// Return the longest prefix of `path` that stays inside `region`, or
+/// `None` if the path is empty or starts outside it.
+pub fn clamp_path_to_region(path: &[Point], region: Range) -> Option<Vec<Point>> {
+    let n = path.iter().take_while(|&&p| region.contains(p)).count();
+    if n == 0 {
+        return None;
+    }
+    Some(path[..n].to_vec())
+}
+
+// ---------------------------------------------------------------------------
+// PathRange entry points
+// ---------------------------------------------------------------------------
+
+impl PathRange {
+    /// Like [`astar_path`](Self::astar_path), but the search never leaves
+    /// `region`. Returns `None` immediately if `from` or `to` lies outside.
+    pub fn astar_path_within<P: AstarPather>(
+        &mut self,
+        pather: &P,
+        from: Point,
+        to: Point,
+        region: Range,
+    ) -> Option<Vec<Point>> {
+        let rp = RegionPather::new(pather, region);
+        if !rp.allows(from) || !rp.allows(to) {
+            return None;
+        }
+        self.astar_path(&rp, from, to)
+    }
+
+    /// Like [`astar_path`](Self::astar_path), but only through cells set in
+    /// `mask` (row-major over [`range`](Self::range)). Returns `None`
+    /// immediately if `from` or `to` is masked out.
+    pub fn astar_path_masked<P: AstarPather>(
+        &mut self,
+        pather: &P,
+        from: Point,
+        to: Point,
+        mask: &[bool],
+    ) -> Option<Vec<Point>> {
+        let mp = MaskPather::new(pather, self.rng, mask);
+        if !mp.allows(from) || !mp.allows(to) {
+            return None;
+        }
+        self.astar_path(&mp, from, to)
+    }
+
+    /// Like [`dijkstra_map`](Self::dijkstra_map), restricted to `region`.
+    /// Sources outside the region are ignored.
+    pub fn dijkstra_map_within<P: WeightedPather>(
+        &mut self,
+        pather: &P,
+        sources: &[Point],
+        max_cost: i32,
+        region: Range,
+    ) -> &[PathNode] {
+        let rp = RegionPather::new(pather, region);
+        let sources = allowed_sources(sources, |p| rp.allows(p));
+        self.dijkstra_map(&rp, &sources, max_cost)
+    }
+
+    /// Like [`dijkstra_map`](Self::dijkstra_map), restricted to the cells
+    /// set in `mask`. Masked-out sources are ignored.
+    pub fn dijkstra_map_masked<P: WeightedPather>(
+        &mut self,
+        pather: &P,
+        sources: &[Point],
+        max_cost: i32,
+        mask: &[bool],
+    ) -> &[PathNode] {
+        let mp = MaskPather::new(pather, self.rng, mask);
+        let sources = allowed_sources(sources, |p| mp.allows(p));
+        self.dijkstra_map(&mp, &sources, max_cost)
+    }
+
+    /// Like [`bfs_map`](Self::bfs_map), restricted to `region`. Sources
+    /// outside the region are ignored.
+    pub fn bfs_map_within<P: Pather>(
+        &mut self,
+        pather: &P,
+        sources: &[Point],
+        max_dist: i32,
+        region: Range,
+    ) -> &[PathNode] {
+        let rp = RegionPather::new(pather, region);
+        let sources = allowed_sources(sources, |p| rp.allows(p));
+        self.bfs_map(&rp, &sources, max_dist)
+    }
+
+    /// Like [`bfs_map`](Self::bfs_map), restricted to the cells set in
+    /// `mask`. Masked-out sources are ignored.
+    pub fn bfs_map_masked<P: Pather>(
+        &mut self,
+        pather: &P,
+        sources: &[Point],
+        max_dist: i32,
+        mask: &[bool],
+    ) -> &[PathNode] {
+        let mp = MaskPather::new(pather, self.rng, mask);
+        let sources = allowed_sources(sources, |p| mp.allows(p));
+        self.bfs_map(&mp, &sources, max_dist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manhattan;
+
+    /// Open 4-way grid, optionally limited to a baked-in region.
+    struct Open {
+        bounds: Range,
+    }
+
+    impl Pather for Open {
+        fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+            for q in p.neighbors_4() {
+                if self.bounds.contains(q) {
+                    buf.push(q);
+                }
+            }
+        }
+    }
+
+    impl WeightedPather for Open {
+        fn cost(&self, _from: Point, _to: Point) -> i32 {
+            1
+        }
+    }
+
+    impl AstarPather for Open {
+        fn estimate(&self, from: Point, to: Point) -> i32 {
+            manhattan(from, to)
+        }
+    }
+
+    const MAP: Range = Range {
+        min: Point::new(0, 0),
+        max: Point::new(20, 20),
+    };
+
+    #[test]
+    fn astar_within_stays_in_region() {
+        let region = Range::new(2, 2, 8, 8);
+        let mut pr = PathRange::new(MAP);
+        let pather = Open { bounds: MAP };
+        let path = pr
+            .astar_path_within(&pather, Point::new(2, 2), Point::new(7, 7), region)
+            .unwrap();
+        assert!(path.iter().all(|&p| region.contains(p)));
+        assert_eq!(path.len(), 11);
+    }
+
+    #[test]
+    fn astar_within_goal_outside_is_none() {
+        let region = Range::new(2, 2, 8, 8);
+        let mut pr = PathRange::new(MAP);
+        let pather = Open { bounds: MAP };
+        assert!(
+            pr.astar_path_within(&pather, Point::new(3, 3), Point::new(10, 3), region)
+                .is_none()
+        );
+        assert!(
+            pr.astar_path_within(&pather, Point::new(0, 0), Point::new(3, 3), region)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn within_matches_constrained_pather() {
+        let region = Range::new(1, 3, 12, 9);
+        let baked = Open { bounds: region };
+        let pather = Open { bounds: MAP };
+        let mut a = PathRange::new(MAP);
+        let mut b = PathRange::new(MAP);
+
+        let from = Point::new(1, 3);
+        let to = Point::new(11, 8);
+        assert_eq!(
+            a.astar_path_within(&pather, from, to, region),
+            b.astar_path(&baked, from, to)
+        );
+
+        let sources = [Point::new(5, 5), Point::new(15, 15)];
+        let got: Vec<PathNode> = a.bfs_map_within(&pather, &sources, 100, region).to_vec();
+        let want: Vec<PathNode> = b.bfs_map(&baked, &sources[..1], 100).to_vec();
+        assert_eq!(got, want);
+
+        let got: Vec<PathNode> = a
+            .dijkstra_map_within(&pather, &sources, 100, region)
+            .to_vec();
+        let want: Vec<PathNode> = b.dijkstra_map(&baked, &sources[..1], 100).to_vec();
+        assert_eq!(got, want);
+        assert!(got.iter().all(|n| region.contains(n.pos)));
+    }
+
+    #[test]
+    fn masked_search() {
+        // Only column x == 0 and row y == 19 are open.
+        let mask: Vec<bool> = MAP.iter().map(|p| p.x == 0 || p.y == 19).collect();
+        let mut pr = PathRange::new(MAP);
+        let pather = Open { bounds: MAP };
+        let path = pr
+            .astar_path_masked(&pather, Point::new(0, 0), Point::new(19, 19), &mask)
+            .unwrap();
+        assert_eq!(path.len(), 39);
+        assert!(path.iter().all(|&p| p.x == 0 || p.y == 19));
+        assert!(
+            pr.astar_path_masked(&pather, Point::new(0, 0), Point::new(5, 5), &mask)
+                .is_none()
+        );
+        let reached = pr.bfs_map_masked(&pather, &[Point::new(0, 0)], 100, &mask);
+        assert_eq!(reached.len(), 39);
+    }
+
+    #[test]
+    fn clamp_path() {
+        let region = Range::new(0, 0, 3, 3);
+        let path = [
+            Point::new(0, 0),
+            Point::new(1, 0),
+            Point::new(2, 0),
+            Point::new(3, 0),
+            Point::new(2, 0),
+        ];
+        assert_eq!(clamp_path_to_region(&path, region).unwrap(), &path[..3]);
+        assert!(clamp_path_to_region(&path[3..], region).is_none());
+        assert!(clamp_path_to_region(&[], region).is_none());
+    }
+}
@@ -0,0 +1,512 @@
+//! Read-only path queries that can run on several threads at once.
+//!
+//! [`PathRange`](crate::PathRange) keeps its caches inside, so every query
+//! needs `&mut`. A [`PathSnapshot`] instead freezes the map's costs once,
+//! and its queries take `&self` together with a [`SearchScratch`] owned by
+//! the caller: threads share the snapshot and each keeps its own scratch.
+//!
+//! On targets without threads, such as `wasm32-unknown-unknown`, the
+//! snapshot works the same, one query after the other.
+
+use std::collections::{BinaryHeap, VecDeque};
+
+use gruid_core::{Point, Range};
+
+use crate::distance::Metric;
+use crate::pathrange::{Node, NodeRef, PathNode, UNREACHABLE};
+use crate::traits::{AstarPather, Pather, WeightedPather};
+
+/// Cost of impassable cells in the snapshot.
+const BLOCKED: i32 = -1;
+
+/// Immutable costs of a map, for concurrent path queries.
+///
+/// Moving into a cell costs what the closure given to
+/// [`build`](Self::build) returned for it. Movement is 4-way unless
+/// [`with_diagonals`](Self::with_diagonals) is set. The snapshot is also a
+/// [`AstarPather`], so [`PathRange`](crate::PathRange) searches on it give
+/// the same results as its own queries.
+///
+/// ```
+/// use gruid_core::{Point, Range};
+/// use gruid_paths::{PathSnapshot, SearchScratch};
+///
+/// let snapshot = PathSnapshot::build(Range::new(0, 0, 10, 10), |p| (p.x != 5).then_some(1));
+/// let mut scratch = SearchScratch::new();
+/// assert!(snapshot.astar_path(&mut scratch, Point::new(0, 0), Point::new(9, 0)).is_none());
+/// let path = snapshot.astar_path(&mut scratch, Point::new(0, 0), Point::new(4, 9)).unwrap();
+/// assert_eq!(path.len(), 14);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathSnapshot {
+    rng: Range,
+    width: usize,
+    costs: Vec<i32>,
+    diagonals: bool,
+}
+
+/// Search buffers for [`PathSnapshot`] queries, one per thread.
+///
+/// Creating one does not allocate; buffers grow on first use and are
+/// reused by later queries, on any snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct SearchScratch {
+    nodes: Vec<Node>,
+    generation: u32,
+    open: BinaryHeap<NodeRef>,
+    queue: VecDeque<usize>,
+    results: Vec<PathNode>,
+    nbuf: Vec<Point>,
+}
+
+impl SearchScratch {
+    /// Empty buffers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a search over `len` cells, returning its generation.
+    fn begin(&mut self, len: usize) -> u32 {
+        if self.nodes.len() < len {
+            self.nodes.resize(len, Node::default());
+        }
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            // Stale nodes would look current after a wrap.
+            self.nodes.fill(Node::default());
+            self.generation = 1;
+        }
+        self.open.clear();
+        self.queue.clear();
+        self.results.clear();
+        self.generation
+    }
+}
+
+impl PathSnapshot {
+    /// Freeze the costs of the cells of `rng`: `cost(p)` is the cost of
+    /// moving into `p`, or `None` if it is impassable. Costs below 1 count
+    /// as 1.
+    pub fn build(rng: Range, cost: impl Fn(Point) -> Option<i32>) -> Self {
+        let width = rng.width().max(0) as usize;
+        let costs = rng
+            .iter()
+            .map(|p| cost(p).map_or(BLOCKED, |c| c.max(1)))
+            .collect();
+        Self {
+            rng,
+            width,
+            costs,
+            diagonals: false,
+        }
+    }
+
+    /// Allow diagonal moves, with the Chebyshev distance as A* estimate.
+    pub fn with_diagonals(mut self, diagonals: bool) -> Self {
+        self.diagonals = diagonals;
+        self
+    }
+
+    /// The grid rectangle covered.
+    pub fn range(&self) -> Range {
+        self.rng
+    }
+
+    /// The cost of moving into `p`, or `None` if it is impassable or out
+    /// of range.
+    pub fn cost_at(&self, p: Point) -> Option<i32> {
+        let c = self.costs[self.idx(p)?];
+        (c != BLOCKED).then_some(c)
+    }
+
+    /// Shortest path from `from` to `to`, both included, as
+    /// [`PathRange::astar_path`](crate::PathRange::astar_path) finds it.
+    pub fn astar_path(
+        &self,
+        scratch: &mut SearchScratch,
+        from: Point,
+        to: Point,
+    ) -> Option<Vec<Point>> {
+        let start = self.idx(from)?;
+        let goal = self.idx(to)?;
+        if start == goal {
+            return Some(vec![from]);
+        }
+        let cur_gen = scratch.begin(self.costs.len());
+        let SearchScratch {
+            nodes, open, nbuf, ..
+        } = scratch;
+
+        let f = self.estimate(from, to);
+        nodes[start] = Node {
+            g: 0,
+            f,
+            parent: usize::MAX,
+            generation: cur_gen,
+            open: true,
+        };
+        open.push(NodeRef { idx: start, f });
+
+        let found = loop {
+            let Some(current) = open.pop() else {
+                break false;
+            };
+            let ci = current.idx;
+            if nodes[ci].generation != cur_gen || !nodes[ci].open {
+                continue;
+            }
+            nodes[ci].open = false;
+            if ci == goal {
+                break true;
+            }
+            let current_g = nodes[ci].g;
+            let cp = self.point(ci);
+
+            nbuf.clear();
+            self.neighbors(cp, nbuf);
+            for &np in nbuf.iter() {
+                let ni = self.idx(np).unwrap();
+                let tentative = current_g + self.costs[ni];
+                let n = &mut nodes[ni];
+                if n.generation == cur_gen && tentative >= n.g {
+                    continue;
+                }
+                n.generation = cur_gen;
+                n.g = tentative;
+                n.f = tentative + self.estimate(np, to);
+                n.parent = ci;
+                n.open = true;
+                open.push(NodeRef { idx: ni, f: n.f });
+            }
+        };
+        if !found {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let mut ci = goal;
+        while ci != usize::MAX {
+            path.push(self.point(ci));
+            ci = nodes[ci].parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Breadth-first distance map from `sources` into `scratch`, up to
+    /// `max_dist` steps, ignoring costs. Returns the cells reached, in the
+    /// order [`PathRange::bfs_map`](crate::PathRange::bfs_map) gives them.
+    pub fn bfs_map_into<'s>(
+        &self,
+        scratch: &'s mut SearchScratch,
+        sources: &[Point],
+        max_dist: i32,
+    ) -> &'s [PathNode] {
+        let cur_gen = scratch.begin(self.costs.len());
+        let SearchScratch {
+            nodes,
+            queue,
+            results,
+            nbuf,
+            ..
+        } = scratch;
+
+        for &src in sources {
+            let Some(si) = self.idx(src) else {
+                continue;
+            };
+            if nodes[si].generation == cur_gen {
+                continue;
+            }
+            nodes[si].generation = cur_gen;
+            nodes[si].g = 0;
+            queue.push_back(si);
+            results.push(PathNode { pos: src, cost: 0 });
+        }
+
+        while let Some(ci) = queue.pop_front() {
+            let nd = nodes[ci].g + 1;
+            nbuf.clear();
+            self.neighbors(self.point(ci), nbuf);
+            for &np in nbuf.iter() {
+                let ni = self.idx(np).unwrap();
+                if nodes[ni].generation == cur_gen || nd > max_dist {
+                    continue;
+                }
+                nodes[ni].generation = cur_gen;
+                nodes[ni].g = nd;
+                queue.push_back(ni);
+                results.push(PathNode { pos: np, cost: nd });
+            }
+        }
+        results
+    }
+
+    /// Cost of the cheapest path from each cell to the nearest of `goals`,
+    /// up to `max_cost`, into `scratch`. Returns the cells reached, by
+    /// increasing cost; [`dist_at`](Self::dist_at) looks one up.
+    ///
+    /// This is [`PathRange::dijkstra_map`](crate::PathRange::dijkstra_map)
+    /// with moves reversed, so that monsters can roll down the map into a
+    /// goal: a cell's cost is paid when entering it on the way to a goal,
+    /// the start cell being free.
+    pub fn dijkstra_to<'s>(
+        &self,
+        scratch: &'s mut SearchScratch,
+        goals: &[Point],
+        max_cost: i32,
+    ) -> &'s [PathNode] {
+        let cur_gen = scratch.begin(self.costs.len());
+        let SearchScratch {
+            nodes,
+            open,
+            results,
+            nbuf,
+            ..
+        } = scratch;
+
+        for &goal in goals {
+            let Some(gi) = self.idx(goal) else {
+                continue;
+            };
+            nodes[gi] = Node {
+                g: 0,
+                f: 0,
+                parent: usize::MAX,
+                generation: cur_gen,
+                open: true,
+            };
+            open.push(NodeRef { idx: gi, f: 0 });
+        }
+
+        while let Some(current) = open.pop() {
+            let ci = current.idx;
+            if nodes[ci].generation != cur_gen || !nodes[ci].open {
+                continue;
+            }
+            nodes[ci].open = false;
+            let current_g = nodes[ci].g;
+            let cp = self.point(ci);
+            results.push(PathNode {
+                pos: cp,
+                cost: current_g,
+            });
+
+            nbuf.clear();
+            self.neighbors(cp, nbuf);
+            // Moving from the neighbour into `cp` costs what `cp` does.
+            let tentative = current_g + self.costs[ci].max(1);
+            if tentative > max_cost {
+                continue;
+            }
+            for &np in nbuf.iter() {
+                let ni = self.idx(np).unwrap();
+                let n = &mut nodes[ni];
+                if n.generation == cur_gen && tentative >= n.g {
+                    continue;
+                }
+                n.generation = cur_gen;
+                n.g = tentative;
+                n.f = tentative;
+                n.open = true;
+                open.push(NodeRef {
+                    idx: ni,
+                    f: tentative,
+                });
+            }
+        }
+        results
+    }
+
+    /// The cost at `p` in the last [`dijkstra_to`](Self::dijkstra_to) or
+    /// distance in the last [`bfs_map_into`](Self::bfs_map_into) made with
+    /// `scratch`, or [`UNREACHABLE`].
+    pub fn dist_at(&self, scratch: &SearchScratch, p: Point) -> i32 {
+        match self.idx(p).and_then(|i| scratch.nodes.get(i)) {
+            Some(n) if n.generation == scratch.generation && scratch.generation != 0 => n.g,
+            _ => UNREACHABLE,
+        }
+    }
+
+    fn idx(&self, p: Point) -> Option<usize> {
+        if !self.rng.contains(p) {
+            return None;
+        }
+        let x = (p.x - self.rng.min.x) as usize;
+        let y = (p.y - self.rng.min.y) as usize;
+        Some(y * self.width + x)
+    }
+
+    fn point(&self, idx: usize) -> Point {
+        let x = (idx % self.width) as i32 + self.rng.min.x;
+        let y = (idx / self.width) as i32 + self.rng.min.y;
+        Point::new(x, y)
+    }
+}
+
+impl Pather for PathSnapshot {
+    /// Passable cells around `p`: north, east, south and west, and with
+    /// diagonals, all eight by rows.
+    fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+        const CARDINAL: [Point; 4] = [
+            Point::new(0, -1),
+            Point::new(1, 0),
+            Point::new(0, 1),
+            Point::new(-1, 0),
+        ];
+        let mut push = |q: Point| {
+            if self.cost_at(q).is_some() {
+                buf.push(q);
+            }
+        };
+        if self.diagonals {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx != 0 || dy != 0 {
+                        push(p + Point::new(dx, dy));
+                    }
+                }
+            }
+        } else {
+            for d in CARDINAL {
+                push(p + d);
+            }
+        }
+    }
+}
+
+impl WeightedPather for PathSnapshot {
+    fn cost(&self, _from: Point, to: Point) -> i32 {
+        self.cost_at(to).unwrap_or(UNREACHABLE)
+    }
+}
+
+impl AstarPather for PathSnapshot {
+    fn estimate(&self, from: Point, to: Point) -> i32 {
+        Metric::unit_moves(self.diagonals).distance(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathRange;
+
+    /// A cave with walls and costlier rubble, from a fixed pattern.
+    fn snapshot(diagonals: bool) -> PathSnapshot {
+        PathSnapshot::build(Range::new(0, 0, 40, 30), |p| {
+            let h = (p.x * 7 + p.y * 13 + p.x * p.y) % 11;
+            match h {
+                0 | 1 => None,
+                2 => Some(3),
+                _ => Some(1),
+            }
+        })
+        .with_diagonals(diagonals)
+    }
+
+    fn passable(s: &PathSnapshot) -> Vec<Point> {
+        s.range()
+            .iter()
+            .filter(|&p| s.cost_at(p).is_some())
+            .collect()
+    }
+
+    /// The snapshot with moves reversed, for cross-checking
+    /// [`PathSnapshot::dijkstra_to`].
+    struct Reversed<'a>(&'a PathSnapshot);
+
+    impl Pather for Reversed<'_> {
+        fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+            self.0.neighbors(p, buf);
+        }
+    }
+
+    impl WeightedPather for Reversed<'_> {
+        fn cost(&self, from: Point, _to: Point) -> i32 {
+            self.0.cost_at(from).unwrap_or(1)
+        }
+    }
+
+    #[test]
+    fn matches_path_range() {
+        for diagonals in [false, true] {
+            let s = snapshot(diagonals);
+            let cells = passable(&s);
+            let mut pr = PathRange::new(s.range());
+            let mut scratch = SearchScratch::new();
+            for (k, &from) in cells.iter().enumerate().step_by(37) {
+                let to = cells[(k * 7919) % cells.len()];
+                assert_eq!(
+                    s.astar_path(&mut scratch, from, to),
+                    pr.astar_path(&s, from, to),
+                    "{from:?} -> {to:?}"
+                );
+                let want = pr.bfs_map(&s, &[from, to], 12).to_vec();
+                assert_eq!(s.bfs_map_into(&mut scratch, &[from, to], 12), want);
+                for n in &want {
+                    assert_eq!(s.dist_at(&scratch, n.pos), n.cost);
+                }
+
+                let want = pr.dijkstra_map(&Reversed(&s), &[to], 40).to_vec();
+                assert_eq!(s.dijkstra_to(&mut scratch, &[to], 40), want);
+                for n in &want {
+                    assert_eq!(s.dist_at(&scratch, n.pos), n.cost, "{:?}", n.pos);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dijkstra_to_pays_for_cells_entered() {
+        // Rubble at x = 1 and 2 on a corridor.
+        let s = PathSnapshot::build(Range::new(0, 0, 4, 1), |p| {
+            Some(if p.x == 1 || p.x == 2 { 5 } else { 1 })
+        });
+        let mut scratch = SearchScratch::new();
+        s.dijkstra_to(&mut scratch, &[Point::new(0, 0)], 100);
+        let dist: Vec<i32> = (0..4)
+            .map(|x| s.dist_at(&scratch, Point::new(x, 0)))
+            .collect();
+        // From x = 3: enter 2 (5), 1 (5) and 0 (1).
+        assert_eq!(dist, [0, 1, 6, 11]);
+        assert_eq!(s.dist_at(&scratch, Point::new(9, 9)), UNREACHABLE);
+        assert_eq!(s.dist_at(&SearchScratch::new(), Point::ZERO), UNREACHABLE);
+    }
+
+    #[test]
+    fn parallel_queries() {
+        use rayon::prelude::*;
+
+        let s = snapshot(true);
+        let cells = passable(&s);
+        let agents: Vec<(Point, Point)> = (0..64)
+            .map(|i| {
+                (
+                    cells[i * 13 % cells.len()],
+                    cells[(i * 977 + 5) % cells.len()],
+                )
+            })
+            .collect();
+        let mut pr = PathRange::new(s.range());
+        let want: Vec<_> = agents
+            .iter()
+            .map(|&(from, to)| pr.astar_path(&s, from, to))
+            .collect();
+
+        let mut scratch = SearchScratch::new();
+        let got: Vec<_> = agents
+            .iter()
+            .map(|&(from, to)| s.astar_path(&mut scratch, from, to))
+            .collect();
+        assert_eq!(got, want);
+
+        let got: Vec<_> = agents
+            .par_iter()
+            .map_init(SearchScratch::new, |scratch, &(from, to)| {
+                s.astar_path(scratch, from, to)
+            })
+            .collect();
+        assert_eq!(got, want);
+    }
+}
@@ -0,0 +1,349 @@
+//! A* over positions paired with a small discrete state.
+//!
+//! [`astar_path_stateful`] searches `(Point, S)` nodes, for puzzles where
+//! what is reachable depends on more than the position: keys held, levers
+//! pulled, whether the player is swimming. It is the flexible, slow sibling
+//! of the [`PathRange`](crate::PathRange) searches: nodes live in a hash
+//! map allocated for each call, and the number of states multiplies the
+//! search space, so a node limit bounds it.
+//!
+//! [`is_solvable`] builds on it to check that a level with locked doors can
+//! be finished, picking up keys on the way.
+//!
+//! # Example
+//!
+//! A level where the door `1` only opens with the key `0`, picked up by
+//! walking over it, and the state is whether it was:
+//!
+//! ```
+//! use gruid_core::Point;
+//! use gruid_paths::{astar_path_stateful, manhattan};
+//!
+//! let map = ["#######", "#@.1.>#", "#.#####", "#..0###", "#######"];
+//! let at = |p: Point| {
+//!     let row = map.get(usize::try_from(p.y).ok()?)?.as_bytes();
+//!     row.get(usize::try_from(p.x).ok()?).copied()
+//! };
+//! let find = |c| {
+//!     (0..5)
+//!         .flat_map(|y| (0..7).map(move |x| Point::new(x, y)))
+//!         .find(|&p| at(p) == Some(c))
+//!         .unwrap()
+//! };
+//! let (start, exit, key) = (find(b'@'), find(b'>'), find(b'0'));
+//!
+//! let path = astar_path_stateful(
+//!     (start, false),
+//!     |p, _| p == exit,
+//!     |p, key, buf| {
+//!         for q in p.neighbors_4() {
+//!             match at(q) {
+//!                 None | Some(b'#') => {}
+//!                 Some(b'1') if !key => {}
+//!                 Some(b'0') => buf.push((q, true, 1)),
+//!                 Some(_) => buf.push((q, key, 1)),
+//!             }
+//!         }
+//!     },
+//!     |p, _| manhattan(p, exit),
+//!     10_000,
+//! )
+//! .unwrap();
+//! assert!(path.contains(&(key, true)));
+//! assert_eq!(path.last(), Some(&(exit, true)));
+//! ```
+
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+use gruid_core::Point;
+
+use crate::pathrange::NodeRef;
+use crate::traits::Pather;
+
+/// Find a least-cost path from `from` to any node satisfying `goal`.
+///
+/// `successors(p, s, buf)` appends the nodes reachable in one step from
+/// `(p, s)`, with the step cost, which must be positive. `estimate(p, s)`
+/// must never overestimate the remaining cost; a constant 0 turns the
+/// search into Dijkstra's.
+///
+/// Returns the path, both ends included, or `None` if no goal is reachable
+/// or the goal was not found after expanding `max_nodes` nodes.
+///
+/// Unlike the [`PathRange`](crate::PathRange) searches, this allocates a
+/// hash map of the visited nodes on each call: prefer those when the
+/// position alone is enough.
+pub fn astar_path_stateful<S: Copy + Eq + Hash>(
+    from: (Point, S),
+    goal: impl Fn(Point, S) -> bool,
+    mut successors: impl FnMut(Point, S, &mut Vec<(Point, S, i32)>),
+    estimate: impl Fn(Point, S) -> i32,
+    max_nodes: usize,
+) -> Option<Vec<(Point, S)>> {
+    struct Node<S> {
+        key: (Point, S),
+        g: i32,
+        parent: usize,
+        open: bool,
+    }
+
+    let mut nodes = vec![Node {
+        key: from,
+        g: 0,
+        parent: usize::MAX,
+        open: true,
+    }];
+    let mut index: HashMap<(Point, S), usize> = HashMap::from([(from, 0)]);
+    let mut open = BinaryHeap::from([NodeRef {
+        idx: 0,
+        f: estimate(from.0, from.1),
+    }]);
+    let mut buf = Vec::new();
+    let mut expanded = 0;
+
+    let found = loop {
+        let ci = open.pop()?.idx;
+        if !nodes[ci].open {
+            // Stale entry.
+            continue;
+        }
+        nodes[ci].open = false;
+        let (p, s) = nodes[ci].key;
+        if goal(p, s) {
+            break ci;
+        }
+        if expanded >= max_nodes {
+            return None;
+        }
+        expanded += 1;
+
+        buf.clear();
+        successors(p, s, &mut buf);
+        let g = nodes[ci].g;
+        for &(q, t, cost) in &buf {
+            let tentative = g + cost;
+            let ni = match index.get(&(q, t)) {
+                Some(&ni) if tentative >= nodes[ni].g => continue,
+                Some(&ni) => ni,
+                None => {
+                    nodes.push(Node {
+                        key: (q, t),
+                        g: 0,
+                        parent: usize::MAX,
+                        open: false,
+                    });
+                    index.insert((q, t), nodes.len() - 1);
+                    nodes.len() - 1
+                }
+            };
+            let n = &mut nodes[ni];
+            n.g = tentative;
+            n.parent = ci;
+            n.open = true;
+            open.push(NodeRef {
+                idx: ni,
+                f: tentative + estimate(q, t),
+            });
+        }
+    };
+
+    let mut path = Vec::new();
+    let mut ci = found;
+    while ci != usize::MAX {
+        path.push(nodes[ci].key);
+        ci = nodes[ci].parent;
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Identifier pairing locked doors with the keys opening them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DoorId(pub u32);
+
+/// Whether `exit` can be reached from `start`, moving as `pather` allows,
+/// when each door of `doors` can only be crossed while holding a key of
+/// `keys` with the same [`DoorId`]. Keys are picked up by stepping on them
+/// and never used up.
+///
+/// Meant for validating generated levels. Doors and keys must use at most
+/// 64 distinct ids; the search covers each reachable cell once per set of
+/// keys held, so it stays cheap with a handful of them.
+///
+/// # Panics
+///
+/// If more than 64 distinct ids are used.
+pub fn is_solvable<P: Pather>(
+    pather: &P,
+    start: Point,
+    exit: Point,
+    keys: &[(Point, DoorId)],
+    doors: &[(Point, DoorId)],
+) -> bool {
+    let mut ids: Vec<DoorId> = keys.iter().chain(doors).map(|&(_, id)| id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    assert!(ids.len() <= 64, "at most 64 distinct door ids");
+    let bit = |id| 1u64 << ids.binary_search(&id).unwrap();
+    let mut key_bits: HashMap<Point, u64> = HashMap::new();
+    for &(p, id) in keys {
+        *key_bits.entry(p).or_default() |= bit(id);
+    }
+    let mut door_bits: HashMap<Point, u64> = HashMap::new();
+    for &(p, id) in doors {
+        *door_bits.entry(p).or_default() |= bit(id);
+    }
+
+    let held = key_bits.get(&start).copied().unwrap_or(0);
+    let mut nbuf = Vec::new();
+    let successors = |p, held: u64, buf: &mut Vec<(Point, u64, i32)>| {
+        nbuf.clear();
+        pather.neighbors(p, &mut nbuf);
+        for &q in &nbuf {
+            let locks = door_bits.get(&q).copied().unwrap_or(0);
+            if locks & !held != 0 {
+                continue;
+            }
+            buf.push((q, held | key_bits.get(&q).copied().unwrap_or(0), 1));
+        }
+    };
+    astar_path_stateful(
+        (start, held),
+        |p, _| p == exit,
+        successors,
+        |_, _| 0,
+        usize::MAX,
+    )
+    .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manhattan;
+
+    /// 4-way moves over the non-'#' cells of a map.
+    struct Map(&'static [&'static str]);
+
+    impl Map {
+        fn at(&self, p: Point) -> Option<u8> {
+            let row = self.0.get(usize::try_from(p.y).ok()?)?.as_bytes();
+            row.get(usize::try_from(p.x).ok()?).copied()
+        }
+
+        fn find(&self, c: u8) -> Vec<Point> {
+            let mut found = Vec::new();
+            for (y, row) in self.0.iter().enumerate() {
+                for (x, b) in row.bytes().enumerate() {
+                    if b == c {
+                        found.push(Point::new(x as i32, y as i32));
+                    }
+                }
+            }
+            found
+        }
+    }
+
+    impl Pather for Map {
+        fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+            buf.extend(
+                p.neighbors_4()
+                    .into_iter()
+                    .filter(|&q| self.at(q).is_some_and(|c| c != b'#')),
+            );
+        }
+    }
+
+    // Keys 'a' and 'b' open doors 'A' and 'B'. The key for 'B' is behind
+    // 'A', so both detours are needed, in order.
+    const LEVEL: Map = Map(&[
+        "#########",
+        "#@..B..>#",
+        "#.#######",
+        "#.#a###b#",
+        "#......A#",
+        "#########",
+    ]);
+
+    fn level_ids(c: u8) -> Vec<(Point, DoorId)> {
+        LEVEL
+            .find(c)
+            .into_iter()
+            .map(|p| (p, DoorId(c.to_ascii_lowercase() as u32)))
+            .collect()
+    }
+
+    #[test]
+    fn detour_for_keys() {
+        let start = LEVEL.find(b'@')[0];
+        let exit = LEVEL.find(b'>')[0];
+        let keys = [level_ids(b'a'), level_ids(b'b')].concat();
+        let doors = [level_ids(b'A'), level_ids(b'B')].concat();
+        assert!(is_solvable(&LEVEL, start, exit, &keys, &doors));
+        // Without key 'a', door 'A' hides key 'b'.
+        assert!(!is_solvable(&LEVEL, start, exit, &keys[1..], &doors));
+        // Without doors, the exit is straight ahead.
+        assert!(is_solvable(&LEVEL, start, exit, &[], &[]));
+    }
+
+    #[test]
+    fn path_picks_up_keys() {
+        let start = LEVEL.find(b'@')[0];
+        let exit = LEVEL.find(b'>')[0];
+        let mut nbuf = Vec::new();
+        let path = astar_path_stateful(
+            (start, 0u8),
+            |p, _| p == exit,
+            |p, keys, buf| {
+                nbuf.clear();
+                LEVEL.neighbors(p, &mut nbuf);
+                for &q in &nbuf {
+                    match LEVEL.at(q) {
+                        Some(b'A') if keys & 1 == 0 => {}
+                        Some(b'B') if keys & 2 == 0 => {}
+                        Some(b'a') => buf.push((q, keys | 1, 1)),
+                        Some(b'b') => buf.push((q, keys | 2, 1)),
+                        _ => buf.push((q, keys, 1)),
+                    }
+                }
+            },
+            |p, _| manhattan(p, exit),
+            usize::MAX,
+        )
+        .unwrap();
+        let a = LEVEL.find(b'a')[0];
+        let b = LEVEL.find(b'b')[0];
+        let pos = |k| path.iter().position(|&n| n == k).unwrap();
+        assert!(pos((a, 1)) < pos((b, 3)));
+        assert_eq!(path.first(), Some(&(start, 0)));
+        assert_eq!(path.last(), Some(&(exit, 3)));
+        // Consecutive steps are adjacent.
+        assert!(path.windows(2).all(|w| manhattan(w[0].0, w[1].0) == 1));
+    }
+
+    #[test]
+    fn node_limit_bounds_search() {
+        // A state that never repeats makes the search space infinite.
+        let successors = |p: Point, n: u32, buf: &mut Vec<(Point, u32, i32)>| {
+            for q in p.neighbors_4() {
+                buf.push((q, n + 1, 1));
+            }
+        };
+        let never = |_, _| false;
+        let from = (Point::new(0, 0), 0);
+        assert_eq!(
+            astar_path_stateful(from, never, successors, |_, _| 0, 500),
+            None
+        );
+        // The same search finds a goal within the limit.
+        let goal = |p: Point, _| p == Point::new(3, 0);
+        let path = astar_path_stateful(from, goal, successors, |_, _| 0, 500).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(
+            astar_path_stateful(from, goal, successors, |_, _| 0, 10),
+            None
+        );
+    }
+}
@@ -0,0 +1,614 @@
+//! An unbounded map made of fixed-size chunks, generated on demand.
+//!
+//! [`ChunkedGrid`] stores square [`Chunk`]s of `N × N` cells in a hash map,
+//! so that a map has no bounds and only the parts visited take memory.
+//! Chunks are generated the first time a cell in them is needed, by a
+//! generator given the chunk to fill. Each chunk has its own
+//! [seed](Chunk::seed), derived from the grid's and the chunk's
+//! coordinates, so a generator seeding its randomness from it produces the
+//! same world whatever order the chunks are visited in.
+//!
+//! [`unload_outside`](ChunkedGrid::unload_outside) drops chunks far from
+//! the player to bound memory. Chunks changed since they were generated
+//! are [modified](Chunk::is_modified): a persistence hook can keep them
+//! (with the `serde` feature, serialized) and [`insert`](ChunkedGrid::insert)
+//! them back when they are needed again, instead of generating them anew.
+//!
+//! FOV, path finding and map generation work on contiguous grids:
+//! [`window`](ChunkedGrid::window) copies a range of the world out into an
+//! [`RlGrid`](crate::RlGrid).
+//!
+//! ```
+//! use gruid_core::{Point, Range};
+//! use gruid_rl::chunked::{Chunk, ChunkCoord, ChunkedGrid};
+//! use gruid_rl::RlCell;
+//!
+//! // Walls on the edges of every chunk.
+//! let mut walls = |_: ChunkCoord, chunk: &mut Chunk<16>| {
+//!     for p in Range::new(0, 0, 16, 16).iter() {
+//!         if p.x == 0 || p.y == 0 {
+//!             chunk.set(p, RlCell(1));
+//!         }
+//!     }
+//! };
+//! let mut world = ChunkedGrid::<16>::new(42);
+//! assert_eq!(world.at(Point::new(-16, 5)), None);
+//! assert_eq!(world.at_or_generate(Point::new(-16, 5), &mut walls), RlCell(1));
+//! assert_eq!(world.at(Point::new(-15, 5)), Some(RlCell(0)));
+//!
+//! world.set(Point::new(-15, 5), RlCell(2));
+//! let view = world.window(Range::new(-17, 4, -14, 6));
+//! assert_eq!(view.at(Point::new(2, 1)), Some(RlCell(2)));
+//! ```
+
+use std::collections::HashMap;
+
+use gruid_core::seed::sub_seed;
+use gruid_core::{Point, Range};
+
+use crate::grid::{Cell, Grid};
+
+/// Coordinates of a chunk: the chunk at `(x, y)` covers the cells from
+/// `(x * N, y * N)` to `(x * N + N - 1, y * N + N - 1)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkCoord {
+    /// Create chunk coordinates.
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+/// A square block of `N × N` cells of a [`ChunkedGrid`].
+///
+/// Positions given to a chunk are local, from `(0, 0)` to `(N - 1, N - 1)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk<const N: usize = 32> {
+    coord: ChunkCoord,
+    seed: u64,
+    cells: Vec<Cell>,
+    modified: bool,
+    /// Whether a generator is filling the chunk: its writes are not
+    /// modifications.
+    generating: bool,
+}
+
+impl<const N: usize> Chunk<N> {
+    /// An empty chunk at `coord`, filled with `Cell(0)`, with the seed it
+    /// has in a grid seeded with `grid_seed`.
+    pub fn new(coord: ChunkCoord, grid_seed: u64) -> Self {
+        Self {
+            coord,
+            seed: sub_seed(grid_seed, &format!("chunk {},{}", coord.x, coord.y)),
+            cells: vec![Cell::default(); N * N],
+            modified: false,
+            generating: false,
+        }
+    }
+
+    /// Coordinates of the chunk.
+    pub fn coord(&self) -> ChunkCoord {
+        self.coord
+    }
+
+    /// Seed for generating this chunk: the grid's seed and the chunk's
+    /// coordinates put through [`sub_seed`].
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// World range covered by the chunk, cut at the bounds of `i32` for
+    /// chunks at their edges.
+    pub fn range(&self) -> Range {
+        let n = N as i32;
+        let min = Point::new(
+            self.coord.x.saturating_mul(n),
+            self.coord.y.saturating_mul(n),
+        );
+        Range::new(
+            min.x,
+            min.y,
+            min.x.saturating_add(n),
+            min.y.saturating_add(n),
+        )
+    }
+
+    /// Whether the chunk was changed since it was generated.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    fn index(p: Point) -> Option<usize> {
+        let n = N as i32;
+        (p.x >= 0 && p.y >= 0 && p.x < n && p.y < n).then(|| (p.y * n + p.x) as usize)
+    }
+
+    /// The cell at local position `p`, or `None` outside the chunk.
+    pub fn at(&self, p: Point) -> Option<Cell> {
+        Self::index(p).map(|i| self.cells[i])
+    }
+
+    /// Set the cell at local position `p`, marking the chunk as modified
+    /// unless a generator is filling it. Does nothing outside the chunk.
+    pub fn set(&mut self, p: Point, cell: Cell) {
+        if let Some(i) = Self::index(p) {
+            self.cells[i] = cell;
+            self.modified |= !self.generating;
+        }
+    }
+
+    /// Set every cell, marking the chunk as modified unless a generator is
+    /// filling it.
+    pub fn fill(&mut self, cell: Cell) {
+        self.cells.fill(cell);
+        self.modified |= !self.generating;
+    }
+
+    /// Iterate over the local positions and cells of the chunk, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, Cell)> + '_ {
+        let n = N as i32;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, &c)| (Point::new(i as i32 % n, i as i32 / n), c))
+    }
+}
+
+/// An unbounded grid of [`Chunk`]s of `N × N` cells, generated on demand.
+///
+/// See the [module documentation](self). All positions are world
+/// positions, and may be negative.
+#[derive(Debug, Clone)]
+pub struct ChunkedGrid<const N: usize = 32> {
+    seed: u64,
+    chunks: HashMap<ChunkCoord, Chunk<N>>,
+}
+
+impl<const N: usize> ChunkedGrid<N> {
+    /// An empty grid whose chunks derive their seeds from `seed`.
+    ///
+    /// # Panics
+    ///
+    /// If `N` is zero or its square does not fit an `i32`.
+    pub fn new(seed: u64) -> Self {
+        assert!(N > 0 && N <= 46_340, "chunk size {N} out of range");
+        Self {
+            seed,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// The seed of the grid.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Coordinates of the chunk containing world position `p`.
+    pub fn chunk_of(p: Point) -> ChunkCoord {
+        let n = N as i32;
+        ChunkCoord::new(p.x.div_euclid(n), p.y.div_euclid(n))
+    }
+
+    /// Position of world position `p` within its chunk.
+    fn local(p: Point) -> Point {
+        let n = N as i32;
+        Point::new(p.x.rem_euclid(n), p.y.rem_euclid(n))
+    }
+
+    /// Number of chunks in memory.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Whether no chunk is in memory.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The cell at `p`, or `None` if its chunk is not in memory.
+    pub fn at(&self, p: Point) -> Option<Cell> {
+        self.chunks
+            .get(&Self::chunk_of(p))
+            .and_then(|c| c.at(Self::local(p)))
+    }
+
+    /// The cell at `p`, generating its chunk first with `generator` if it
+    /// is not in memory.
+    pub fn at_or_generate(
+        &mut self,
+        p: Point,
+        generator: &mut impl FnMut(ChunkCoord, &mut Chunk<N>),
+    ) -> Cell {
+        self.chunk_or_generate(Self::chunk_of(p), generator)
+            .at(Self::local(p))
+            .unwrap_or_default()
+    }
+
+    /// Set the cell at `p`, marking its chunk as modified. Returns `false`,
+    /// doing nothing, if the chunk is not in memory.
+    pub fn set(&mut self, p: Point, cell: Cell) -> bool {
+        match self.chunks.get_mut(&Self::chunk_of(p)) {
+            Some(c) => {
+                c.set(Self::local(p), cell);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The chunk at `coord`, if in memory.
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&Chunk<N>> {
+        self.chunks.get(&coord)
+    }
+
+    /// The chunk at `coord`, generated with `generator` if it is not in
+    /// memory. The generator fills an empty chunk; what it sets does not
+    /// count as a modification. It may also replace the chunk with one
+    /// saved earlier, which stays modified if it was.
+    pub fn chunk_or_generate(
+        &mut self,
+        coord: ChunkCoord,
+        generator: &mut impl FnMut(ChunkCoord, &mut Chunk<N>),
+    ) -> &mut Chunk<N> {
+        let seed = self.seed;
+        self.chunks.entry(coord).or_insert_with(|| {
+            let mut chunk = Chunk::new(coord, seed);
+            chunk.generating = true;
+            generator(coord, &mut chunk);
+            chunk.generating = false;
+            chunk
+        })
+    }
+
+    /// Generate the chunks overlapping `range` that are not in memory.
+    pub fn generate_range(
+        &mut self,
+        range: Range,
+        generator: &mut impl FnMut(ChunkCoord, &mut Chunk<N>),
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let min = Self::chunk_of(range.min);
+        let max = Self::chunk_of(range.max.shift(-1, -1));
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.chunk_or_generate(ChunkCoord::new(x, y), generator);
+            }
+        }
+    }
+
+    /// Put `chunk` in memory, replacing the one at its coordinates, for
+    /// example to restore a chunk saved when it was unloaded.
+    pub fn insert(&mut self, chunk: Chunk<N>) {
+        self.chunks.insert(chunk.coord, chunk);
+    }
+
+    /// Iterate over the chunks in memory, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk<N>> {
+        self.chunks.values()
+    }
+
+    /// Drop the chunks that do not overlap `keep`.
+    pub fn unload_outside(&mut self, keep: Range) {
+        self.unload_outside_with(keep, |_| {});
+    }
+
+    /// Drop the chunks that do not overlap `keep`, passing each to
+    /// `evicted` first, in coordinate order. Saving the
+    /// [modified](Chunk::is_modified) ones there, to
+    /// [`insert`](Self::insert) them back later, keeps changes across
+    /// unloading.
+    pub fn unload_outside_with(&mut self, keep: Range, mut evicted: impl FnMut(Chunk<N>)) {
+        let mut out: Vec<ChunkCoord> = self
+            .chunks
+            .values()
+            .filter(|c| !c.range().overlaps(keep))
+            .map(|c| c.coord)
+            .collect();
+        out.sort_unstable();
+        for coord in out {
+            if let Some(chunk) = self.chunks.remove(&coord) {
+                evicted(chunk);
+            }
+        }
+    }
+
+    /// Copy the cells of `range` into a new [`RlGrid`](Grid) of its size,
+    /// whose `(0, 0)` is `range.min`. Cells of chunks not in memory are
+    /// `Cell(0)`; see [`generate_range`](Self::generate_range).
+    pub fn window(&self, range: Range) -> Grid {
+        let grid = Grid::new(range.width(), range.height());
+        for chunk in self.chunks.values() {
+            let shared = chunk.range().intersect(range);
+            if shared.is_empty() {
+                continue;
+            }
+            let origin = chunk.range().min;
+            for p in shared.iter() {
+                let local = Point::new(p.x - origin.x, p.y - origin.y);
+                if let Some(cell) = chunk.at(local) {
+                    grid.set(Point::new(p.x - range.min.x, p.y - range.min.y), cell);
+                }
+            }
+        }
+        grid
+    }
+}
+
+#[cfg(feature = "serde")]
+mod chunked_serde {
+    use super::*;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct ChunkData {
+        coord: ChunkCoord,
+        seed: u64,
+        modified: bool,
+        cells: Vec<Cell>,
+    }
+
+    impl<const N: usize> serde::Serialize for Chunk<N> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            ChunkData {
+                coord: self.coord,
+                seed: self.seed,
+                modified: self.modified,
+                cells: self.cells.clone(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, const N: usize> serde::Deserialize<'de> for Chunk<N> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = ChunkData::deserialize(deserializer)?;
+            if data.cells.len() != N * N {
+                return Err(serde::de::Error::custom(format!(
+                    "expected {} cells for a {N}x{N} chunk, got {}",
+                    N * N,
+                    data.cells.len()
+                )));
+            }
+            Ok(Chunk {
+                coord: data.coord,
+                seed: data.seed,
+                cells: data.cells,
+                modified: data.modified,
+                generating: false,
+            })
+        }
+    }
+
+    /// The loaded chunks, in coordinate order so that output is stable.
+    #[derive(serde::Serialize)]
+    struct GridRef<'a, const N: usize> {
+        seed: u64,
+        chunks: Vec<&'a Chunk<N>>,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(bound = "")]
+    struct GridData<const N: usize> {
+        seed: u64,
+        chunks: Vec<Chunk<N>>,
+    }
+
+    impl<const N: usize> serde::Serialize for ChunkedGrid<N> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut chunks: Vec<&Chunk<N>> = self.chunks.values().collect();
+            chunks.sort_unstable_by_key(|c| c.coord);
+            GridRef {
+                seed: self.seed,
+                chunks,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, const N: usize> serde::Deserialize<'de> for ChunkedGrid<N> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = GridData::<N>::deserialize(deserializer)?;
+            let mut grid = ChunkedGrid::new(data.seed);
+            for chunk in data.chunks {
+                if grid.chunks.contains_key(&chunk.coord) {
+                    return Err(serde::de::Error::custom(format!(
+                        "duplicate chunk at {:?}",
+                        chunk.coord
+                    )));
+                }
+                grid.insert(chunk);
+            }
+            Ok(grid)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{RngExt, SeedableRng};
+
+    /// Scatters walls from the chunk's seed, counting the chunks made.
+    fn noise(made: &mut usize) -> impl FnMut(ChunkCoord, &mut Chunk<8>) + '_ {
+        move |_, chunk| {
+            *made += 1;
+            let mut rng = StdRng::seed_from_u64(chunk.seed());
+            for p in Range::new(0, 0, 8, 8).iter() {
+                chunk.set(p, Cell(rng.random_range(0..3)));
+            }
+        }
+    }
+
+    #[test]
+    fn generation_ignores_access_order() {
+        let points: Vec<Point> = Range::new(-20, -20, 20, 20).iter().collect();
+        let mut made = 0;
+        let mut a = ChunkedGrid::<8>::new(7);
+        let fwd: Vec<Cell> = points
+            .iter()
+            .map(|&p| a.at_or_generate(p, &mut noise(&mut made)))
+            .collect();
+        // 40 cells from -20 span chunks -3..=2 on each axis.
+        assert_eq!(made, 36);
+        assert_eq!(a.len(), 36);
+
+        let mut b = ChunkedGrid::<8>::new(7);
+        let mut made = 0;
+        let mut back: Vec<Cell> = points
+            .iter()
+            .rev()
+            .map(|&p| b.at_or_generate(p, &mut noise(&mut made)))
+            .collect();
+        back.reverse();
+        assert_eq!(fwd, back);
+        assert!(!a.chunks().any(Chunk::is_modified));
+
+        let mut c = ChunkedGrid::<8>::new(8);
+        let other: Vec<Cell> = points
+            .iter()
+            .map(|&p| c.at_or_generate(p, &mut noise(&mut made)))
+            .collect();
+        assert_ne!(fwd, other);
+    }
+
+    #[test]
+    fn modified_chunks_survive_unloading() {
+        let mut made = 0;
+        let mut world = ChunkedGrid::<8>::new(1);
+        let p = Point::new(-3, 10);
+        let q = Point::new(100, 0);
+        world.at_or_generate(p, &mut noise(&mut made));
+        world.at_or_generate(q, &mut noise(&mut made));
+        assert!(world.set(p, Cell(9)));
+        assert!(!world.set(Point::new(500, 500), Cell(9)));
+
+        let mut saved = HashMap::new();
+        world.unload_outside_with(Range::new(90, -10, 110, 10), |chunk| {
+            if chunk.is_modified() {
+                saved.insert(chunk.coord(), chunk);
+            }
+        });
+        assert_eq!(world.len(), 1);
+        assert_eq!(world.at(p), None);
+        assert_eq!(saved.len(), 1);
+
+        // Coming back: saved chunks are restored, others generated.
+        let mut restore = |coord: ChunkCoord, chunk: &mut Chunk<8>| match saved.remove(&coord) {
+            Some(kept) => *chunk = kept,
+            None => noise(&mut made)(coord, chunk),
+        };
+        assert_eq!(world.at_or_generate(p, &mut restore), Cell(9));
+    }
+
+    #[test]
+    fn restored_chunks_stay_modified() {
+        let mut made = 0;
+        let mut world = ChunkedGrid::<8>::new(1);
+        let p = Point::new(-3, 10);
+        let far = Range::new(90, -10, 110, 10);
+        world.at_or_generate(p, &mut noise(&mut made));
+        world.set(p, Cell(9));
+
+        let mut saved = HashMap::new();
+        for round in 0..2 {
+            world.unload_outside_with(far, |chunk| {
+                if chunk.is_modified() {
+                    saved.insert(chunk.coord(), chunk);
+                }
+            });
+            assert_eq!(saved.len(), 1, "round {round}");
+            let mut restore = |coord: ChunkCoord, chunk: &mut Chunk<8>| match saved.remove(&coord) {
+                Some(kept) => *chunk = kept,
+                None => noise(&mut made)(coord, chunk),
+            };
+            assert_eq!(
+                world.at_or_generate(p, &mut restore),
+                Cell(9),
+                "round {round}"
+            );
+            assert!(
+                world
+                    .chunk(ChunkedGrid::<8>::chunk_of(p))
+                    .unwrap()
+                    .is_modified()
+            );
+        }
+    }
+
+    #[test]
+    fn ranges_of_edge_chunks() {
+        let edge = Chunk::<8>::new(ChunkCoord::new(i32::MAX / 8, i32::MIN / 8), 0);
+        let rg = edge.range();
+        assert_eq!(rg.min, Point::new(i32::MAX / 8 * 8, i32::MIN));
+        assert_eq!(rg.max, Point::new(i32::MAX, i32::MIN + 8));
+    }
+
+    #[test]
+    fn window_spans_chunks() {
+        let mut world = ChunkedGrid::<4>::new(0);
+        let mut coords = |coord: ChunkCoord, chunk: &mut Chunk<4>| {
+            chunk.fill(Cell(coord.x * 10 + coord.y));
+        };
+        let range = Range::new(-2, -1, 5, 6);
+        world.generate_range(range, &mut coords);
+        // Chunks -1..=1 by -1..=1.
+        assert_eq!(world.len(), 9);
+        world.set(Point::new(0, 0), Cell(77));
+
+        let view = world.window(range);
+        assert_eq!(view.size(), Point::new(7, 7));
+        for (p, cell) in view.iter() {
+            let w = Point::new(p.x + range.min.x, p.y + range.min.y);
+            assert_eq!(Some(cell), world.at(w), "{w:?}");
+        }
+        assert_eq!(view.at(Point::new(2, 1)), Some(Cell(77)));
+        assert_eq!(view.at(Point::new(0, 0)), Some(Cell(-11)));
+        assert_eq!(view.at(Point::new(6, 6)), Some(Cell(11)));
+
+        // Cells of missing chunks are zero.
+        let far = world.window(Range::new(6, 6, 10, 10));
+        assert_eq!(far.at(Point::new(1, 1)), Some(Cell(11)));
+        assert_eq!(far.at(Point::new(2, 2)), Some(Cell(0)));
+    }
+
+    #[test]
+    fn unloading_bounds_memory() {
+        let mut made = 0;
+        let mut world = ChunkedGrid::<8>::new(3);
+        for x in 0..1000 {
+            let pos = Point::new(x, x / 2);
+            let view = Range::new(pos.x - 10, pos.y - 10, pos.x + 11, pos.y + 11);
+            world.generate_range(view, &mut noise(&mut made));
+            world.unload_outside(view);
+            // A 21×21 view overlaps at most 4×4 chunks of 8.
+            assert!(world.len() <= 16, "{} chunks at {x}", world.len());
+        }
+        assert!(made > 100);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut made = 0;
+        let mut world = ChunkedGrid::<8>::new(5);
+        world.generate_range(Range::new(-8, 0, 8, 8), &mut noise(&mut made));
+        world.set(Point::new(-1, 1), Cell(42));
+        let json = serde_json::to_string(&world).unwrap();
+        let back: ChunkedGrid<8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.seed(), 5);
+        assert_eq!(back.len(), 2);
+        assert_eq!(back.at(Point::new(-1, 1)), Some(Cell(42)));
+        assert!(back.chunk(ChunkCoord::new(-1, 0)).unwrap().is_modified());
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+        // Chunks of another size are refused.
+        assert!(serde_json::from_str::<ChunkedGrid<4>>(&json).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Game time with a day and night cycle, for outdoor maps.
+//!
+//! A [`GameClock`] counts ticks, by turns or by the delays of an
+//! [`EventQueue`](crate::EventQueue), and splits them into days of a
+//! configurable length. Each day goes through the [`DayPhase`]s at the
+//! fractions of the day given by [`DayPhases`]. The ambient light follows
+//! a smooth curve: a plateau during the day, another, lower, at night, and
+//! ramps at dawn and dusk.
+//!
+//! The ambient light feeds the rest of the game through two helpers:
+//! [`effective_sight_radius`] shrinks the range of the [`FOV`](crate::FOV)
+//! in the dark, and [`apply_ambient`] tints colours toward a night tint in
+//! the draw path.
+//!
+//! ```
+//! use gruid_rl::clock::{DayPhase, GameClock};
+//!
+//! let mut clock = GameClock::new(100);
+//! assert_eq!(clock.phase(), DayPhase::Night);
+//! assert_eq!(clock.advance(30), Some(DayPhase::Day));
+//! assert_eq!(clock.ambient_light(), 255);
+//! clock.advance(100);
+//! assert_eq!((clock.days_elapsed(), clock.time_of_day()), (1, 0.3));
+//! ```
+
+use std::fmt;
+
+use gruid_core::style::Color;
+
+/// A part of the day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DayPhase {
+    /// The light rises.
+    Dawn,
+    /// Full light.
+    Day,
+    /// The light falls.
+    Dusk,
+    /// Least light.
+    Night,
+}
+
+/// The name of the phase for players, as in a status bar.
+impl fmt::Display for DayPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dawn => "Dawn",
+            Self::Day => "Daytime",
+            Self::Dusk => "Dusk",
+            Self::Night => "Night",
+        })
+    }
+}
+
+/// Where each phase starts, as a fraction of the day from midnight. Night
+/// lasts from `night` to the next day's `dawn`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DayPhases {
+    pub dawn: f32,
+    pub day: f32,
+    pub dusk: f32,
+    pub night: f32,
+}
+
+impl Default for DayPhases {
+    /// Dawn from 5:00, day from 7:00, dusk from 18:00 and night from 20:00.
+    fn default() -> Self {
+        Self {
+            dawn: 5.0 / 24.0,
+            day: 7.0 / 24.0,
+            dusk: 18.0 / 24.0,
+            night: 20.0 / 24.0,
+        }
+    }
+}
+
+impl DayPhases {
+    /// The phase at `time`, a fraction of the day.
+    pub fn at(&self, time: f32) -> DayPhase {
+        if time < self.dawn || time >= self.night {
+            DayPhase::Night
+        } else if time < self.day {
+            DayPhase::Dawn
+        } else if time < self.dusk {
+            DayPhase::Day
+        } else {
+            DayPhase::Dusk
+        }
+    }
+
+    /// Light at `time`, from 0 at night to 1 during the day.
+    fn daylight(&self, time: f32) -> f32 {
+        let ramp = |from: f32, to: f32| smoothstep((time - from) / (to - from));
+        match self.at(time) {
+            DayPhase::Night => 0.0,
+            DayPhase::Dawn => ramp(self.dawn, self.day),
+            DayPhase::Day => 1.0,
+            DayPhase::Dusk => 1.0 - ramp(self.dusk, self.night),
+        }
+    }
+}
+
+/// 0 at 0 and 1 at 1, with flat ends, so that ramps join the plateaus
+/// without a kink.
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Ticks split into days, with the ambient light of the time of day.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameClock {
+    ticks: u64,
+    ticks_per_day: u64,
+    phases: DayPhases,
+    night_light: u8,
+    day_light: u8,
+    night_tint: Color,
+}
+
+impl GameClock {
+    /// A clock at midnight of the first day, with days of `ticks_per_day`
+    /// ticks, the [default phases](DayPhases::default), an ambient light of
+    /// 40 at night and 255 during the day, and a dark blue night tint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ticks_per_day` is zero.
+    pub fn new(ticks_per_day: u64) -> Self {
+        assert!(ticks_per_day > 0, "days must last at least a tick");
+        Self {
+            ticks: 0,
+            ticks_per_day,
+            phases: DayPhases::default(),
+            night_light: 40,
+            day_light: 255,
+            night_tint: Color::from_rgb(70, 80, 140),
+        }
+    }
+
+    /// Set where the phases start (builder).
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 <= dawn <= day <= dusk <= night <= 1`.
+    pub fn with_phases(mut self, phases: DayPhases) -> Self {
+        let DayPhases {
+            dawn,
+            day,
+            dusk,
+            night,
+        } = phases;
+        assert!(
+            0.0 <= dawn && dawn <= day && day <= dusk && dusk <= night && night <= 1.0,
+            "phases out of order: {phases:?}"
+        );
+        self.phases = phases;
+        self
+    }
+
+    /// Set the ambient light at night and during the day (builder).
+    pub fn with_light(mut self, night: u8, day: u8) -> Self {
+        self.night_light = night;
+        self.day_light = day;
+        self
+    }
+
+    /// Set the colour [`tint`](Self::tint) multiplies colours by at night
+    /// (builder).
+    pub fn with_night_tint(mut self, tint: Color) -> Self {
+        self.night_tint = tint;
+        self
+    }
+
+    /// Set the time, in ticks from the start (builder).
+    pub fn with_ticks(mut self, ticks: u64) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    /// Let `ticks` pass. Returns the new phase if it changed.
+    pub fn advance(&mut self, ticks: u64) -> Option<DayPhase> {
+        let before = self.phase();
+        self.ticks = self.ticks.saturating_add(ticks);
+        let after = self.phase();
+        (after != before).then_some(after)
+    }
+
+    /// Ticks from the start.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn ticks_per_day(&self) -> u64 {
+        self.ticks_per_day
+    }
+
+    pub fn phases(&self) -> &DayPhases {
+        &self.phases
+    }
+
+    /// Whole days since the start.
+    pub fn days_elapsed(&self) -> u64 {
+        self.ticks / self.ticks_per_day
+    }
+
+    /// The fraction of the day gone, from 0 at midnight to below 1.
+    pub fn time_of_day(&self) -> f32 {
+        ((self.ticks % self.ticks_per_day) as f64 / self.ticks_per_day as f64) as f32
+    }
+
+    pub fn phase(&self) -> DayPhase {
+        self.phases.at(self.time_of_day())
+    }
+
+    /// Ambient light now: the night light at night, the day light during
+    /// the day, and in between at dawn and dusk.
+    pub fn ambient_light(&self) -> u8 {
+        let t = self.phases.daylight(self.time_of_day());
+        let (night, day) = (self.night_light as f32, self.day_light as f32);
+        (night + (day - night) * t).round() as u8
+    }
+
+    /// `color` in the ambient light now: see [`apply_ambient`].
+    pub fn tint(&self, color: Color) -> Color {
+        apply_ambient(color, self.ambient_light(), self.night_tint)
+    }
+}
+
+/// The sight radius of a creature seeing up to `base` cells in full light,
+/// in `ambient` light: in proportion to the light, but at least a cell.
+pub fn effective_sight_radius(base: i32, ambient: u8) -> i32 {
+    if base <= 0 {
+        return 0;
+    }
+    ((base * ambient as i32 + 127) / 255).max(1)
+}
+
+/// `color` in `ambient` light: unchanged in full light, and multiplied by
+/// `night_tint` in none, each channel moving between the two in proportion
+/// to the light. [`Color::DEFAULT`] is left alone, as the driver picks it;
+/// a colour dimmed to black becomes it too.
+pub fn apply_ambient(color: Color, ambient: u8, night_tint: Color) -> Color {
+    if color == Color::DEFAULT {
+        return color;
+    }
+    let a = ambient as u32;
+    let channel = |c: u8, tint: u8| {
+        // The channel's factor, in 255ths of 255ths.
+        let k = tint as u32 * (255 - a) + 255 * a;
+        ((c as u32 * k + 65025 / 2) / 65025) as u8
+    };
+    Color::from_rgb(
+        channel(color.r(), night_tint.r()),
+        channel(color.g(), night_tint.g()),
+        channel(color.b(), night_tint.b()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ambient light at each tick of a day of `n` ticks.
+    fn day(clock: &GameClock) -> Vec<u8> {
+        let n = clock.ticks_per_day();
+        (0..n)
+            .map(|t| clock.clone().with_ticks(t).ambient_light())
+            .collect()
+    }
+
+    #[test]
+    fn light_follows_a_smooth_curve() {
+        let clock = GameClock::new(2400);
+        let light = day(&clock);
+        let at = |hour: usize| light[hour * 100];
+        assert_eq!((at(0), at(4), at(12), at(22)), (40, 40, 255, 40));
+        // No jumps: the ramps spread 215 levels over 200 ticks each.
+        assert!(light.windows(2).all(|w| w[0].abs_diff(w[1]) <= 2));
+        // Around midnight too.
+        assert_eq!(light[0], light[2399]);
+        // Rising through dawn, falling through dusk.
+        assert!(light[500..=700].windows(2).all(|w| w[0] <= w[1]));
+        assert!(light[1800..=2000].windows(2).all(|w| w[0] >= w[1]));
+        assert!(at(6) > 40 && at(6) < 255 && at(19) > 40 && at(19) < 255);
+    }
+
+    #[test]
+    fn phase_names() {
+        let names = [
+            DayPhase::Dawn,
+            DayPhase::Day,
+            DayPhase::Dusk,
+            DayPhase::Night,
+        ]
+        .map(|p| p.to_string());
+        assert_eq!(names, ["Dawn", "Daytime", "Dusk", "Night"]);
+    }
+
+    #[test]
+    fn phases_start_at_their_bounds() {
+        let phases = DayPhases {
+            dawn: 0.25,
+            day: 0.5,
+            dusk: 0.5,
+            night: 0.75,
+        };
+        let cases = [
+            (0.0, DayPhase::Night),
+            (0.249, DayPhase::Night),
+            (0.25, DayPhase::Dawn),
+            // An empty day: dusk follows dawn at once.
+            (0.5, DayPhase::Dusk),
+            (0.75, DayPhase::Night),
+            (0.999, DayPhase::Night),
+        ];
+        for (t, phase) in cases {
+            assert_eq!(phases.at(t), phase, "{t}");
+        }
+        let mut clock = GameClock::new(4).with_phases(phases);
+        let seen: Vec<_> = (0..5).map(|_| clock.advance(1)).collect();
+        use DayPhase::*;
+        assert_eq!(
+            seen,
+            [Some(Dawn), Some(Dusk), Some(Night), None, Some(Dawn)]
+        );
+        assert_eq!(clock.days_elapsed(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "phases out of order")]
+    fn phases_must_be_ordered() {
+        let _ = GameClock::new(10).with_phases(DayPhases {
+            dawn: 0.5,
+            day: 0.4,
+            dusk: 0.6,
+            night: 0.8,
+        });
+    }
+
+    #[test]
+    fn sight_and_tint_endpoints() {
+        assert_eq!(effective_sight_radius(8, 255), 8);
+        assert_eq!(effective_sight_radius(8, 128), 4);
+        assert_eq!(effective_sight_radius(8, 0), 1);
+        assert_eq!(effective_sight_radius(0, 255), 0);
+
+        let tint = Color::from_rgb(128, 255, 0);
+        let c = Color::from_rgb(200, 100, 50);
+        assert_eq!(apply_ambient(c, 255, tint), c);
+        assert_eq!(apply_ambient(c, 0, tint), Color::from_rgb(100, 100, 0));
+        assert_eq!(apply_ambient(Color::DEFAULT, 0, tint), Color::DEFAULT);
+
+        let noon = GameClock::new(24).with_ticks(12);
+        let midnight = GameClock::new(24).with_light(0, 255);
+        assert_eq!(noon.tint(c), c);
+        assert_eq!(
+            midnight.tint(Color::from_rgb(255, 255, 255)),
+            Color::from_rgb(70, 80, 140)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let clock = GameClock::new(240)
+            .with_light(10, 200)
+            .with_ticks(3 * 240 + 190);
+        assert_eq!(clock.phase(), DayPhase::Dusk);
+        let json = serde_json::to_string(&clock).unwrap();
+        let back: GameClock = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, clock);
+        assert_eq!(back.ambient_light(), clock.ambient_light());
+        assert_eq!(back.days_elapsed(), 3);
+    }
+}
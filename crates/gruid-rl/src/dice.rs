@@ -0,0 +1,393 @@
+//! Dice expressions such as `2d6+3` or `4d6kh3`.
+//!
+//! Grammar (whitespace is ignored):
+//!
+//! ```text
+//! expr  = dice [modifier] | integer
+//! dice  = [count] "d" sides [("kh" | "kl") keep]
+//! modifier = ("+" | "-") integer
+//! ```
+//!
+//! `kh` keeps the highest `keep` dice, `kl` the lowest.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rand::{Rng, RngExt};
+
+/// Largest number of dice accepted in one expression.
+pub const MAX_DICE: u32 = 1000;
+
+/// Which dice to keep after rolling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    /// Keep the `n` highest dice.
+    Highest(u32),
+    /// Keep the `n` lowest dice.
+    Lowest(u32),
+}
+
+/// A parsed dice expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct DiceExpr {
+    /// Number of dice rolled.
+    pub count: u32,
+    /// Number of sides per die.
+    pub sides: u32,
+    /// Optional keep-highest/lowest rule.
+    pub keep: Option<Keep>,
+    /// Constant added to the total.
+    pub modifier: i32,
+}
+
+impl DiceExpr {
+    /// Parse an expression like `"2d6+3"`.
+    pub fn parse(s: &str) -> Result<Self, DiceError> {
+        s.parse()
+    }
+
+    /// Number of dice that count towards the total.
+    fn kept(&self) -> u32 {
+        match self.keep {
+            Some(Keep::Highest(k) | Keep::Lowest(k)) => k,
+            None => self.count,
+        }
+    }
+
+    /// Roll the dice.
+    pub fn roll(&self, rng: &mut impl Rng) -> i32 {
+        let mut rolls: Vec<i32> = (0..self.count)
+            .map(|_| rng.random_range(1..=self.sides as i32))
+            .collect();
+        let kept = match self.keep {
+            None => &rolls[..],
+            Some(Keep::Highest(k)) => {
+                rolls.sort_unstable_by(|a, b| b.cmp(a));
+                &rolls[..k as usize]
+            }
+            Some(Keep::Lowest(k)) => {
+                rolls.sort_unstable();
+                &rolls[..k as usize]
+            }
+        };
+        kept.iter().sum::<i32>() + self.modifier
+    }
+
+    /// Smallest possible result.
+    pub fn min(&self) -> i32 {
+        self.kept() as i32 + self.modifier
+    }
+
+    /// Largest possible result.
+    pub fn max(&self) -> i32 {
+        (self.kept() * self.sides) as i32 + self.modifier
+    }
+
+    /// Expected result.
+    pub fn mean(&self) -> f64 {
+        let m = self.modifier as f64;
+        let (n, s) = (self.count, self.sides);
+        let Some(keep) = self.keep else {
+            return n as f64 * (s as f64 + 1.0) / 2.0 + m;
+        };
+        // Sum of the expected values of the kept order statistics, with
+        // E[X_(j)] = Σ_x P(X_(j) >= x), where X_(j) is the j-th smallest
+        // roll: at least n - j + 1 dice must show x or more.
+        let order = |j: u32| -> f64 {
+            (1..=s)
+                .map(|x| {
+                    let p = (s - x + 1) as f64 / s as f64;
+                    (n - j + 1..=n).map(|m| binomial(n, m, p)).sum::<f64>()
+                })
+                .sum()
+        };
+        let sum: f64 = match keep {
+            Keep::Highest(k) => (n - k + 1..=n).map(order).sum(),
+            Keep::Lowest(k) => (1..=k).map(order).sum(),
+        };
+        sum + m
+    }
+}
+
+/// P(exactly `m` successes in `n` trials of probability `p`).
+fn binomial(n: u32, m: u32, p: f64) -> f64 {
+    let mut c = 1.0;
+    for i in 0..m {
+        c = c * (n - i) as f64 / (i + 1) as f64;
+    }
+    c * p.powi(m as i32) * (1.0 - p).powi((n - m) as i32)
+}
+
+impl fmt::Display for DiceExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count == 0 {
+            return write!(f, "{}", self.modifier);
+        }
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.keep {
+            Some(Keep::Highest(k)) => write!(f, "kh{k}")?,
+            Some(Keep::Lowest(k)) => write!(f, "kl{k}")?,
+            None => {}
+        }
+        match self.modifier {
+            0 => Ok(()),
+            m if m > 0 => write!(f, "+{m}"),
+            m => write!(f, "{m}"),
+        }
+    }
+}
+
+/// Errors from parsing a [`DiceExpr`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiceError {
+    /// The expression is empty.
+    Empty,
+    /// Unexpected input at the given byte offset (whitespace removed).
+    Syntax { expr: String, pos: usize },
+    /// Dice must have at least one side.
+    ZeroSides,
+    /// More dice kept than rolled, or none kept.
+    InvalidKeep { keep: u32, count: u32 },
+    /// A number, or the largest total, is too large.
+    Overflow,
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "dice: empty expression"),
+            Self::Syntax { expr, pos } => {
+                write!(f, "dice: unexpected input at offset {pos} in \"{expr}\"")
+            }
+            Self::ZeroSides => write!(f, "dice: dice must have at least one side"),
+            Self::InvalidKeep { keep, count } => {
+                write!(f, "dice: cannot keep {keep} of {count} dice")
+            }
+            Self::Overflow => write!(f, "dice: number too large"),
+        }
+    }
+}
+
+impl std::error::Error for DiceError {}
+
+/// Tiny cursor over the whitespace-free expression.
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.s[self.pos..].starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn number(&mut self) -> Result<Option<u32>, DiceError> {
+        let digits = self.s[self.pos..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if digits == 0 {
+            return Ok(None);
+        }
+        let n = self.s[self.pos..self.pos + digits]
+            .parse()
+            .map_err(|_| DiceError::Overflow)?;
+        self.pos += digits;
+        Ok(Some(n))
+    }
+
+    fn error(&self) -> DiceError {
+        DiceError::Syntax {
+            expr: self.s.to_string(),
+            pos: self.pos,
+        }
+    }
+}
+
+impl FromStr for DiceExpr {
+    type Err = DiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let compact: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if compact.is_empty() {
+            return Err(DiceError::Empty);
+        }
+        let mut p = Parser {
+            s: &compact,
+            pos: 0,
+        };
+
+        let lead = p.number()?;
+        if !p.eat("d") {
+            // A plain constant, possibly signed.
+            let negative = lead.is_none() && p.eat("-");
+            let value = match lead {
+                Some(n) => n,
+                None => p.number()?.ok_or_else(|| p.error())?,
+            };
+            if p.pos != compact.len() {
+                return Err(p.error());
+            }
+            let value = i32::try_from(value).map_err(|_| DiceError::Overflow)?;
+            return Ok(Self {
+                count: 0,
+                sides: 0,
+                keep: None,
+                modifier: if negative { -value } else { value },
+            });
+        }
+
+        let count = lead.unwrap_or(1);
+        let sides = p.number()?.ok_or_else(|| p.error())?;
+        if sides == 0 {
+            return Err(DiceError::ZeroSides);
+        }
+        if count > MAX_DICE || count.checked_mul(sides).is_none_or(|t| t > i32::MAX as u32) {
+            return Err(DiceError::Overflow);
+        }
+
+        let keep = if p.eat("kh") {
+            Some(Keep::Highest(p.number()?.ok_or_else(|| p.error())?))
+        } else if p.eat("kl") {
+            Some(Keep::Lowest(p.number()?.ok_or_else(|| p.error())?))
+        } else {
+            None
+        };
+        if let Some(Keep::Highest(k) | Keep::Lowest(k)) = keep
+            && (k == 0 || k > count)
+        {
+            return Err(DiceError::InvalidKeep { keep: k, count });
+        }
+
+        let modifier = if p.eat("+") {
+            p.number()?.ok_or_else(|| p.error())? as i64
+        } else if p.eat("-") {
+            -(p.number()?.ok_or_else(|| p.error())? as i64)
+        } else {
+            0
+        };
+        if p.pos != compact.len() {
+            return Err(p.error());
+        }
+        let modifier = i32::try_from(modifier).map_err(|_| DiceError::Overflow)?;
+
+        let expr = Self {
+            count,
+            sides,
+            keep,
+            modifier,
+        };
+        // The largest total must fit too, for `roll` and `max`.
+        if expr.kept() as i64 * sides as i64 + modifier as i64 > i32::MAX as i64 {
+            return Err(DiceError::Overflow);
+        }
+        Ok(expr)
+    }
+}
+
+impl TryFrom<String> for DiceExpr {
+    type Error = DiceError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<DiceExpr> for String {
+    fn from(d: DiceExpr) -> Self {
+        d.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn min_max_mean_table() {
+        let cases: &[(&str, i32, i32, f64)] = &[
+            ("1d6", 1, 6, 3.5),
+            ("2d6+3", 5, 15, 10.0),
+            ("d20", 1, 20, 10.5),
+            ("3d4-2", 1, 10, 5.5),
+            ("5", 5, 5, 5.0),
+            ("-2", -2, -2, -2.0),
+            ("2d20kh1", 1, 20, 13.825),
+            ("2d20kl1", 1, 20, 7.175),
+            ("4d6kh3", 3, 18, 12.2446),
+        ];
+        for &(expr, min, max, mean) in cases {
+            let d = DiceExpr::parse(expr).unwrap();
+            assert_eq!(d.min(), min, "{expr}");
+            assert_eq!(d.max(), max, "{expr}");
+            assert!((d.mean() - mean).abs() < 1e-3, "{expr}: {}", d.mean());
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases: &[(&str, DiceError)] = &[
+            ("", DiceError::Empty),
+            ("  ", DiceError::Empty),
+            ("2d0", DiceError::ZeroSides),
+            ("2d6kh3", DiceError::InvalidKeep { keep: 3, count: 2 }),
+            ("2d6kl0", DiceError::InvalidKeep { keep: 0, count: 2 }),
+            ("99999d6", DiceError::Overflow),
+            ("1d99999999999", DiceError::Overflow),
+            ("1d2147483647+1", DiceError::Overflow),
+            ("2d1073741824", DiceError::Overflow),
+            ("3d1000kh2+2147481648", DiceError::Overflow),
+        ];
+        for (expr, err) in cases {
+            assert_eq!(DiceExpr::parse(expr).unwrap_err(), *err, "{expr}");
+        }
+        for expr in ["d", "2d", "2d6+", "2d6x", "abc", "2d6kh", "1d6+2+3", "+"] {
+            assert!(
+                matches!(DiceExpr::parse(expr), Err(DiceError::Syntax { .. })),
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn rolls_stay_in_range() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for expr in ["2d6+3", "4d6kh3", "3d8kl1-1", "7"] {
+            let d = DiceExpr::parse(expr).unwrap();
+            let n = 4000;
+            let mut sum = 0i64;
+            for _ in 0..n {
+                let r = d.roll(&mut rng);
+                assert!(r >= d.min() && r <= d.max(), "{expr}: {r}");
+                sum += r as i64;
+            }
+            let avg = sum as f64 / n as f64;
+            assert!((avg - d.mean()).abs() < 0.25, "{expr}: {avg}");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for expr in ["2d6+3", "1d20kh1", "4d6kl3-2", "12", "-3"] {
+            let d = DiceExpr::parse(expr).unwrap();
+            assert_eq!(d.to_string(), expr);
+        }
+        assert_eq!(DiceExpr::parse(" 2 d 6 + 1 ").unwrap().to_string(), "2d6+1");
+        // Right at the limit.
+        let d = DiceExpr::parse("3d1000kh2+2147481647").unwrap();
+        assert_eq!(d.max(), i32::MAX);
+        assert_eq!(
+            DiceExpr::parse("1d2147483647-5").unwrap().max(),
+            i32::MAX - 5
+        );
+    }
+}
@@ -0,0 +1,485 @@
+//! Timed status effects: poison, haste, shields that wear off.
+//!
+//! An [`Effects`] tracker holds the effects active on each entity, keyed by
+//! a caller-chosen kind. Applying a kind an entity already has follows the
+//! [`Stacking`] rule of the new application. Time is counted in abstract
+//! ticks: [`Effects::tick`] advances it, by turns or by the delays of an
+//! [`EventQueue`](crate::EventQueue), and returns the [`EffectEvent`]s this
+//! caused. Like [`Triggers`](crate::Triggers), the tracker stores no
+//! callbacks, so it is saved with the rest of the game.
+//!
+//! ```
+//! use gruid_rl::effects::{EffectDef, EffectEvent, Effects, Stacking};
+//!
+//! const POISON: u16 = 0;
+//! let mut effects = Effects::new();
+//! let bite = EffectDef::new(POISON, 1, 3).with_stacking(Stacking::Stack { max: 3 });
+//! effects.apply("player", bite);
+//! effects.apply("player", bite);
+//! assert_eq!(effects.total_magnitude("player", POISON), 2);
+//!
+//! effects.tick(2);
+//! let events = effects.tick(1);
+//! assert_eq!(events, [EffectEvent::Expired { entity: "player", kind: POISON }]);
+//! assert_eq!(effects.active("player", POISON), None);
+//! ```
+
+/// How a new application of a kind combines with one already active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stacking {
+    /// Replace the magnitude and restart the duration.
+    #[default]
+    Refresh,
+    /// Keep the magnitude and add the duration to what remains.
+    Extend,
+    /// Add a stack with its own magnitude and duration, up to `max`
+    /// stacks. Past that, the stack closest to expiry is replaced.
+    Stack { max: u32 },
+    /// Keep the strongest magnitude: a stronger application replaces the
+    /// effect, an equal one lengthens it if it lasts longer, and a weaker
+    /// one is ignored.
+    Strongest,
+}
+
+/// An application of an effect of kind `K`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectDef<K> {
+    pub kind: K,
+    /// Strength of the effect, such as damage per turn.
+    pub magnitude: i32,
+    /// Ticks until it expires.
+    pub duration: u32,
+    pub stacking: Stacking,
+}
+
+impl<K> EffectDef<K> {
+    /// An effect with [`Stacking::Refresh`].
+    pub fn new(kind: K, magnitude: i32, duration: u32) -> Self {
+        Self {
+            kind,
+            magnitude,
+            duration,
+            stacking: Stacking::Refresh,
+        }
+    }
+
+    /// Set the stacking rule (builder).
+    pub fn with_stacking(mut self, stacking: Stacking) -> Self {
+        self.stacking = stacking;
+        self
+    }
+}
+
+/// An effect active on an entity, all stacks together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActiveEffect<K> {
+    pub kind: K,
+    /// Sum of the magnitudes of the stacks, saturating at the bounds of
+    /// `i32`.
+    pub magnitude: i32,
+    /// Ticks until the last stack expires.
+    pub remaining: u32,
+    /// Number of stacks, 1 but with [`Stacking::Stack`].
+    pub stacks: u32,
+}
+
+/// What applying or ticking did to an effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EffectEvent<E, K> {
+    /// The entity did not have the effect.
+    Applied { entity: E, kind: K },
+    /// The effect was strengthened or lengthened, without a new stack.
+    Refreshed { entity: E, kind: K },
+    /// A stack was added or expired, leaving `stacks` of them.
+    StackChanged { entity: E, kind: K, stacks: u32 },
+    /// The last stack expired.
+    Expired { entity: E, kind: K },
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Stack {
+    magnitude: i32,
+    remaining: u32,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Slot<E, K> {
+    entity: E,
+    kind: K,
+    stacks: Vec<Stack>,
+}
+
+impl<E, K: Copy> Slot<E, K> {
+    fn active(&self) -> ActiveEffect<K> {
+        ActiveEffect {
+            kind: self.kind,
+            magnitude: self
+                .stacks
+                .iter()
+                .fold(0, |sum, s| sum.saturating_add(s.magnitude)),
+            remaining: self.stacks.iter().map(|s| s.remaining).max().unwrap_or(0),
+            stacks: self.stacks.len() as u32,
+        }
+    }
+}
+
+/// Timed effects of kinds `K` on entities `E`.
+///
+/// Events come in the order the effects were first applied, which does not
+/// depend on how entities or kinds compare. An effect lasting `duration`
+/// ticks is active until ticks adding up to `duration` have passed: one of
+/// 3 applied before a turn's damage is dealt lasts three turns. A duration
+/// of 0 expires on the next tick.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Effects<E, K> {
+    /// In order of first application.
+    slots: Vec<Slot<E, K>>,
+}
+
+impl<E, K> Default for Effects<E, K> {
+    fn default() -> Self {
+        Self { slots: Vec::new() }
+    }
+}
+
+impl<E: Copy + PartialEq, K: Copy + PartialEq> Effects<E, K> {
+    /// An empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn slot(&self, entity: E, kind: K) -> Option<&Slot<E, K>> {
+        self.slots
+            .iter()
+            .find(|s| s.entity == entity && s.kind == kind)
+    }
+
+    /// Apply `def` to `entity`. Returns what it did, or `None` for a
+    /// weaker [`Stacking::Strongest`] application, which is ignored.
+    pub fn apply(&mut self, entity: E, def: EffectDef<K>) -> Option<EffectEvent<E, K>> {
+        let kind = def.kind;
+        let new = Stack {
+            magnitude: def.magnitude,
+            remaining: def.duration,
+        };
+        let Some(slot) = self
+            .slots
+            .iter_mut()
+            .find(|s| s.entity == entity && s.kind == kind)
+        else {
+            self.slots.push(Slot {
+                entity,
+                kind,
+                stacks: vec![new],
+            });
+            return Some(EffectEvent::Applied { entity, kind });
+        };
+        let refreshed = EffectEvent::Refreshed { entity, kind };
+        match def.stacking {
+            Stacking::Refresh => {
+                slot.stacks = vec![new];
+                Some(refreshed)
+            }
+            Stacking::Extend => {
+                let remaining = slot.active().remaining;
+                slot.stacks = vec![Stack {
+                    magnitude: slot.stacks[0].magnitude,
+                    remaining: remaining.saturating_add(def.duration),
+                }];
+                Some(refreshed)
+            }
+            Stacking::Stack { max } => {
+                if slot.stacks.len() < max.max(1) as usize {
+                    slot.stacks.push(new);
+                    let stacks = slot.stacks.len() as u32;
+                    return Some(EffectEvent::StackChanged {
+                        entity,
+                        kind,
+                        stacks,
+                    });
+                }
+                // The first stack closest to expiry.
+                let oldest = slot
+                    .stacks
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, s)| s.remaining)
+                    .map(|(i, _)| i)
+                    .unwrap_or(0);
+                slot.stacks[oldest] = new;
+                Some(refreshed)
+            }
+            Stacking::Strongest => {
+                let current = slot.active();
+                if new.magnitude > current.magnitude {
+                    slot.stacks = vec![new];
+                } else if new.magnitude == current.magnitude && new.remaining > current.remaining {
+                    slot.stacks = vec![Stack {
+                        magnitude: current.magnitude,
+                        remaining: new.remaining,
+                    }];
+                } else {
+                    return None;
+                }
+                Some(refreshed)
+            }
+        }
+    }
+
+    /// Advance time by `elapsed` ticks for every entity, and return the
+    /// stacks and effects that expired.
+    pub fn tick(&mut self, elapsed: u32) -> Vec<EffectEvent<E, K>> {
+        self.tick_where(elapsed, |_| true)
+    }
+
+    /// Advance time by `elapsed` ticks for `entity` only, as when it acts
+    /// on its own schedule.
+    pub fn tick_entity(&mut self, entity: E, elapsed: u32) -> Vec<EffectEvent<E, K>> {
+        self.tick_where(elapsed, |e| e == entity)
+    }
+
+    fn tick_where(&mut self, elapsed: u32, f: impl Fn(E) -> bool) -> Vec<EffectEvent<E, K>> {
+        let mut events = Vec::new();
+        for slot in self.slots.iter_mut().filter(|s| f(s.entity)) {
+            let before = slot.stacks.len();
+            for s in &mut slot.stacks {
+                s.remaining = s.remaining.saturating_sub(elapsed);
+            }
+            slot.stacks.retain(|s| s.remaining > 0);
+            let (entity, kind) = (slot.entity, slot.kind);
+            if slot.stacks.is_empty() {
+                events.push(EffectEvent::Expired { entity, kind });
+            } else if slot.stacks.len() != before {
+                let stacks = slot.stacks.len() as u32;
+                events.push(EffectEvent::StackChanged {
+                    entity,
+                    kind,
+                    stacks,
+                });
+            }
+        }
+        self.slots.retain(|s| !s.stacks.is_empty());
+        events
+    }
+
+    /// The effect of `kind` on `entity`, if active.
+    pub fn active(&self, entity: E, kind: K) -> Option<ActiveEffect<K>> {
+        self.slot(entity, kind).map(Slot::active)
+    }
+
+    /// Whether `entity` is under an effect of `kind`.
+    pub fn has(&self, entity: E, kind: K) -> bool {
+        self.slot(entity, kind).is_some()
+    }
+
+    /// Sum of the magnitudes of the stacks of `kind` on `entity`, 0 if
+    /// there is none.
+    pub fn total_magnitude(&self, entity: E, kind: K) -> i32 {
+        self.active(entity, kind).map_or(0, |a| a.magnitude)
+    }
+
+    /// The effects active on `entity`, in order of application.
+    pub fn of(&self, entity: E) -> impl Iterator<Item = ActiveEffect<K>> + '_ {
+        self.slots
+            .iter()
+            .filter(move |s| s.entity == entity)
+            .map(Slot::active)
+    }
+
+    /// End the effect of `kind` on `entity`, without an event. Returns it,
+    /// if it was active.
+    pub fn remove(&mut self, entity: E, kind: K) -> Option<ActiveEffect<K>> {
+        let i = self
+            .slots
+            .iter()
+            .position(|s| s.entity == entity && s.kind == kind)?;
+        Some(self.slots.remove(i).active())
+    }
+
+    /// End the effects of `kind` on every entity. Returns the entities
+    /// that had one, in order of application.
+    pub fn dispel(&mut self, kind: K) -> Vec<E> {
+        let mut dispelled = Vec::new();
+        self.slots.retain(|s| {
+            let keep = s.kind != kind;
+            if !keep {
+                dispelled.push(s.entity);
+            }
+            keep
+        });
+        dispelled
+    }
+
+    /// End all effects on `entity`, as when it dies.
+    pub fn clear_entity(&mut self, entity: E) {
+        self.slots.retain(|s| s.entity != entity);
+    }
+
+    /// Whether no effect is active.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POISON: u16 = 1;
+    const HASTE: u16 = 2;
+
+    fn poison(magnitude: i32, duration: u32, stacking: Stacking) -> EffectDef<u16> {
+        EffectDef::new(POISON, magnitude, duration).with_stacking(stacking)
+    }
+
+    fn state(e: &Effects<char, u16>) -> Option<(i32, u32, u32)> {
+        e.active('@', POISON)
+            .map(|a| (a.magnitude, a.remaining, a.stacks))
+    }
+
+    #[test]
+    fn stacking_rules_over_overlaps() {
+        use EffectEvent::*;
+        let applied = Some(Applied {
+            entity: '@',
+            kind: POISON,
+        });
+        let refreshed = Some(Refreshed {
+            entity: '@',
+            kind: POISON,
+        });
+
+        let mut e = Effects::new();
+        assert_eq!(e.apply('@', poison(2, 5, Stacking::Refresh)), applied);
+        e.tick(3);
+        assert_eq!(e.apply('@', poison(1, 4, Stacking::Refresh)), refreshed);
+        assert_eq!(state(&e), Some((1, 4, 1)));
+
+        let mut e = Effects::new();
+        e.apply('@', poison(2, 5, Stacking::Extend));
+        e.tick(3);
+        assert_eq!(e.apply('@', poison(9, 4, Stacking::Extend)), refreshed);
+        assert_eq!(state(&e), Some((2, 6, 1)));
+
+        let mut e = Effects::new();
+        let stack = Stacking::Stack { max: 2 };
+        e.apply('@', poison(1, 5, stack));
+        e.tick(1);
+        assert_eq!(
+            e.apply('@', poison(2, 3, stack)),
+            Some(StackChanged {
+                entity: '@',
+                kind: POISON,
+                stacks: 2
+            })
+        );
+        assert_eq!(state(&e), Some((3, 4, 2)));
+        // Full: the stack of 2 expiring first is replaced.
+        assert_eq!(e.apply('@', poison(4, 6, stack)), refreshed);
+        assert_eq!(state(&e), Some((5, 6, 2)));
+        assert_eq!(
+            e.tick(4),
+            [StackChanged {
+                entity: '@',
+                kind: POISON,
+                stacks: 1
+            }]
+        );
+        assert_eq!(state(&e), Some((4, 2, 1)));
+        // Huge magnitudes saturate instead of overflowing.
+        e.apply('@', poison(i32::MAX, 5, stack));
+        assert_eq!(state(&e), Some((i32::MAX, 5, 2)));
+
+        let mut e = Effects::new();
+        e.apply('@', poison(3, 5, Stacking::Strongest));
+        assert_eq!(e.apply('@', poison(2, 9, Stacking::Strongest)), None);
+        assert_eq!(e.apply('@', poison(3, 2, Stacking::Strongest)), None);
+        assert_eq!(state(&e), Some((3, 5, 1)));
+        assert_eq!(e.apply('@', poison(3, 7, Stacking::Strongest)), refreshed);
+        assert_eq!(state(&e), Some((3, 7, 1)));
+        assert_eq!(e.apply('@', poison(4, 1, Stacking::Strongest)), refreshed);
+        assert_eq!(state(&e), Some((4, 1, 1)));
+    }
+
+    #[test]
+    fn expiry_at_duration_boundary() {
+        let mut e = Effects::new();
+        e.apply('@', poison(1, 3, Stacking::Refresh));
+        let mut turns = 0;
+        while e.has('@', POISON) {
+            turns += 1;
+            assert!(turns <= 3);
+            e.tick(1);
+        }
+        assert_eq!(turns, 3);
+
+        e.apply('@', poison(1, 3, Stacking::Refresh));
+        assert!(e.tick(2).is_empty());
+        assert_eq!(e.tick(1).len(), 1);
+        // Overshooting expires too, and zero lasts until the next tick.
+        e.apply('@', poison(1, 3, Stacking::Refresh));
+        assert_eq!(e.tick(10).len(), 1);
+        e.apply('@', poison(1, 0, Stacking::Refresh));
+        assert!(e.has('@', POISON));
+        assert_eq!(e.tick(0).len(), 1);
+        assert!(e.is_empty());
+    }
+
+    #[test]
+    fn same_tick_expiries_in_application_order() {
+        use EffectEvent::*;
+        let mut e = Effects::new();
+        e.apply('b', EffectDef::new(HASTE, 1, 2));
+        e.apply('a', poison(1, 2, Stacking::Refresh));
+        e.apply('b', poison(1, 2, Stacking::Stack { max: 3 }));
+        e.apply('b', poison(1, 5, Stacking::Stack { max: 3 }));
+        e.apply('a', EffectDef::new(HASTE, 1, 9));
+        // Refreshing does not move an effect in the order.
+        e.apply('b', EffectDef::new(HASTE, 1, 2));
+        assert_eq!(
+            e.tick(2),
+            [
+                Expired {
+                    entity: 'b',
+                    kind: HASTE
+                },
+                Expired {
+                    entity: 'a',
+                    kind: POISON
+                },
+                StackChanged {
+                    entity: 'b',
+                    kind: POISON,
+                    stacks: 1
+                },
+            ]
+        );
+
+        assert_eq!(e.tick_entity('a', 6).len(), 0);
+        assert_eq!(e.active('b', POISON).unwrap().remaining, 3);
+        assert_eq!(e.of('a').map(|a| a.kind).collect::<Vec<_>>(), [HASTE]);
+        assert_eq!(e.dispel(HASTE), ['a']);
+        assert_eq!(e.remove('b', POISON).map(|a| a.magnitude), Some(1));
+        assert!(e.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut e = Effects::new();
+        e.apply(1u32, poison(2, 5, Stacking::Stack { max: 3 }));
+        e.apply(1, poison(1, 3, Stacking::Stack { max: 3 }));
+        e.apply(2, EffectDef::new(HASTE, 1, 4));
+        let json = serde_json::to_string(&e).unwrap();
+        let mut back: Effects<u32, u16> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.active(1, POISON), e.active(1, POISON));
+        assert_eq!(back.tick(3), e.tick(3));
+    }
+}
@@ -0,0 +1,708 @@
+//! Entity storage with stable ids and a spatial index.
+//!
+//! [`Entities`] stores one payload per entity, together with its position.
+//! Positions live in the store, not in the payload, and only change through
+//! [`Entities::move_to`], so the spatial index answering [`Entities::at`],
+//! [`Entities::in_range`] and [`Entities::nearest`] never goes stale.
+//!
+//! Ids are generational: removing an entity invalidates its [`EntityId`],
+//! and an id kept after the removal does not refer to whatever entity later
+//! reuses the slot. Iteration is in insertion order, so game logic running
+//! over the store stays reproducible.
+//!
+//! ```
+//! use gruid_core::{Point, Range};
+//! use gruid_rl::entities::Entities;
+//!
+//! let mut monsters = Entities::new();
+//! let orc = monsters.insert(Point::new(2, 3), "orc");
+//! let rat = monsters.insert(Point::new(8, 3), "rat");
+//!
+//! monsters.move_to(orc, Point::new(3, 3));
+//! assert_eq!(monsters.at(Point::new(3, 3)).collect::<Vec<_>>(), [orc]);
+//! assert_eq!(monsters.in_range(Range::new(0, 0, 5, 5)).count(), 1);
+//! assert_eq!(monsters.nearest(Point::new(9, 4), |_, _| true), Some(rat));
+//!
+//! assert_eq!(monsters.remove(rat), Some("rat"));
+//! assert_eq!(monsters.get(rat), None);
+//! ```
+
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+use gruid_core::{Point, Range};
+use gruid_paths::chebyshev;
+
+/// Identifies an entity in an [`Entities`] store.
+///
+/// Ids order by slot, then generation: that order is stable but unrelated
+/// to insertion order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+/// Where an entity id points to.
+#[derive(Debug, Clone)]
+struct Slot {
+    /// Generation of the live entity, or of the next one to use the slot.
+    generation: u32,
+    /// Index in `Entities::entries` of the live entity.
+    dense: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    id: EntityId,
+    pos: Point,
+    value: T,
+}
+
+/// A store of entities with payload `T`, each at a position.
+///
+/// See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Entities<T> {
+    slots: Vec<Slot>,
+    /// Free slots, reused last freed first.
+    free: Vec<u32>,
+    /// In insertion order, with holes left by removals.
+    entries: Vec<Option<Entry<T>>>,
+    /// Number of live entities.
+    live: usize,
+    /// Entities at each occupied cell, in order of arrival.
+    index: HashMap<Point, Vec<EntityId>>,
+}
+
+impl<T> Default for Entities<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            entries: Vec::new(),
+            live: 0,
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Entities<T> {
+    /// An empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of entities.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Add an entity at `pos` and return its id.
+    pub fn insert(&mut self, pos: Point, value: T) -> EntityId {
+        let dense = self.entries.len();
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.dense = Some(dense);
+                EntityId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("too many entities");
+                self.slots.push(Slot {
+                    generation: 0,
+                    dense: Some(dense),
+                });
+                EntityId {
+                    index,
+                    generation: 0,
+                }
+            }
+        };
+        self.entries.push(Some(Entry { id, pos, value }));
+        self.live += 1;
+        self.index.entry(pos).or_default().push(id);
+        id
+    }
+
+    /// Remove an entity and return its payload, or `None` if `id` is stale.
+    pub fn remove(&mut self, id: EntityId) -> Option<T> {
+        let dense = self.dense(id)?;
+        let entry = self.entries[dense].take()?;
+        self.live -= 1;
+        self.unindex(id, entry.pos);
+        let slot = &mut self.slots[id.index as usize];
+        slot.dense = None;
+        // A slot whose generations are exhausted is retired rather than
+        // risking an id coming back to life.
+        if let Some(generation) = slot.generation.checked_add(1) {
+            slot.generation = generation;
+            self.free.push(id.index);
+        }
+        if self.entries.len() > 2 * self.live + 16 {
+            self.compact();
+        }
+        Some(entry.value)
+    }
+
+    /// Whether `id` refers to an entity of the store.
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.dense(id).is_some()
+    }
+
+    /// The payload of an entity.
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        self.entry(id).map(|e| &e.value)
+    }
+
+    /// The payload of an entity, mutably.
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        let dense = self.dense(id)?;
+        self.entries[dense].as_mut().map(|e| &mut e.value)
+    }
+
+    /// The position of an entity.
+    pub fn pos(&self, id: EntityId) -> Option<Point> {
+        self.entry(id).map(|e| e.pos)
+    }
+
+    /// Move an entity to `to`. Returns `false` if `id` is stale.
+    ///
+    /// Several entities may share a cell: blocking moves is up to the
+    /// caller, for example by checking [`at`](Self::at) first.
+    pub fn move_to(&mut self, id: EntityId, to: Point) -> bool {
+        let Some(entry) = self.dense(id).and_then(|i| self.entries[i].as_mut()) else {
+            return false;
+        };
+        let from = std::mem::replace(&mut entry.pos, to);
+        if from != to {
+            self.unindex(id, from);
+            self.index.entry(to).or_default().push(id);
+        }
+        true
+    }
+
+    /// Remove the entities for which `f` returns `false`.
+    pub fn retain(&mut self, mut f: impl FnMut(EntityId, &mut T) -> bool) {
+        let dropped: Vec<EntityId> = self
+            .iter_mut()
+            .filter_map(|(id, _, value)| (!f(id, value)).then_some(id))
+            .collect();
+        for id in dropped {
+            self.remove(id);
+        }
+    }
+
+    /// Entities with their positions and payloads, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, Point, &T)> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|e| (e.id, e.pos, &e.value))
+    }
+
+    /// Entities with their positions and mutable payloads, in insertion
+    /// order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, Point, &mut T)> {
+        self.entries
+            .iter_mut()
+            .flatten()
+            .map(|e| (e.id, e.pos, &mut e.value))
+    }
+
+    /// Ids of the entities, in insertion order.
+    pub fn ids(&self) -> impl Iterator<Item = EntityId> {
+        self.entries.iter().flatten().map(|e| e.id)
+    }
+
+    /// Entities at `p`, in the order they arrived there.
+    pub fn at(&self, p: Point) -> impl Iterator<Item = EntityId> {
+        self.index.get(&p).into_iter().flatten().copied()
+    }
+
+    /// Entities inside `rg`, in insertion order.
+    ///
+    /// The ids are collected up front, so the store can be modified while
+    /// going through them.
+    pub fn in_range(&self, rg: Range) -> impl Iterator<Item = EntityId> + use<T> {
+        let ids: Vec<EntityId> = if rg.len() < self.live {
+            // Small range: look its cells up in the index.
+            let mut found: Vec<(usize, EntityId)> = rg
+                .iter()
+                .flat_map(|p| self.at(p))
+                .filter_map(|id| Some((self.dense(id)?, id)))
+                .collect();
+            found.sort_unstable_by_key(|&(dense, _)| dense);
+            found.into_iter().map(|(_, id)| id).collect()
+        } else {
+            self.entries
+                .iter()
+                .flatten()
+                .filter(|e| rg.contains(e.pos))
+                .map(|e| e.id)
+                .collect()
+        };
+        ids.into_iter()
+    }
+
+    /// The entity closest to `p` in Chebyshev distance among those for
+    /// which `filter` returns `true`, or `None` if there is none. Ties go
+    /// to the entity inserted first.
+    pub fn nearest(
+        &self,
+        p: Point,
+        mut filter: impl FnMut(EntityId, &T) -> bool,
+    ) -> Option<EntityId> {
+        // Search rings of growing radius in the index while that is
+        // cheaper than going through every entity.
+        let mut budget = self.live;
+        for r in 0.. {
+            let cells = if r == 0 { 1 } else { 8 * r as usize };
+            if cells > budget {
+                break;
+            }
+            budget -= cells;
+            let best = ring(p, r)
+                .flat_map(|q| self.at(q))
+                .filter_map(|id| {
+                    let e = self.entry(id)?;
+                    filter(id, &e.value).then_some((self.dense(id)?, id))
+                })
+                .min_by_key(|&(dense, _)| dense);
+            if let Some((_, id)) = best {
+                return Some(id);
+            }
+        }
+        self.entries
+            .iter()
+            .flatten()
+            .filter(|e| filter(e.id, &e.value))
+            .min_by_key(|e| chebyshev(p, e.pos))
+            .map(|e| e.id)
+    }
+
+    fn dense(&self, id: EntityId) -> Option<usize> {
+        let slot = self.slots.get(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.dense
+    }
+
+    fn entry(&self, id: EntityId) -> Option<&Entry<T>> {
+        self.entries[self.dense(id)?].as_ref()
+    }
+
+    fn unindex(&mut self, id: EntityId, pos: Point) {
+        if let Some(ids) = self.index.get_mut(&pos) {
+            ids.retain(|&other| other != id);
+            if ids.is_empty() {
+                self.index.remove(&pos);
+            }
+        }
+    }
+
+    /// Drop the holes left in `entries` by removals.
+    fn compact(&mut self) {
+        self.entries.retain(Option::is_some);
+        for (dense, e) in self.entries.iter().flatten().enumerate() {
+            self.slots[e.id.index as usize].dense = Some(dense);
+        }
+    }
+}
+
+impl<T> Index<EntityId> for Entities<T> {
+    type Output = T;
+
+    /// Panics if `id` is stale.
+    fn index(&self, id: EntityId) -> &T {
+        self.get(id).expect("stale entity id")
+    }
+}
+
+impl<T> IndexMut<EntityId> for Entities<T> {
+    /// Panics if `id` is stale.
+    fn index_mut(&mut self, id: EntityId) -> &mut T {
+        self.get_mut(id).expect("stale entity id")
+    }
+}
+
+/// The cells at Chebyshev distance `r` from `p`.
+fn ring(p: Point, r: i32) -> impl Iterator<Item = Point> {
+    let rows = (-r..=r).flat_map(move |dx| {
+        let bottom = (r > 0).then_some(Point::new(p.x + dx, p.y + r));
+        std::iter::once(Point::new(p.x + dx, p.y - r)).chain(bottom)
+    });
+    let sides = (1 - r..r)
+        .flat_map(move |dy| [Point::new(p.x - r, p.y + dy), Point::new(p.x + r, p.y + dy)]);
+    rows.chain(sides)
+}
+
+#[cfg(feature = "serde")]
+mod entities_serde {
+    use super::*;
+
+    /// Saved form of a store: the spatial index is rebuilt on load, from
+    /// the positions and, for cells holding several entities, their order
+    /// of arrival.
+    #[derive(serde::Serialize)]
+    struct SavedRef<'a, T> {
+        generations: Vec<u32>,
+        free: &'a [u32],
+        entities: Vec<(EntityId, Point, &'a T)>,
+        stacks: Vec<&'a [EntityId]>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Saved<T> {
+        generations: Vec<u32>,
+        free: Vec<u32>,
+        entities: Vec<(EntityId, Point, T)>,
+        stacks: Vec<Vec<EntityId>>,
+    }
+
+    impl<T: serde::Serialize> serde::Serialize for Entities<T> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            // By position: the index's order changes from run to run.
+            let mut stacks: Vec<(Point, &[EntityId])> = self
+                .index
+                .iter()
+                .filter(|(_, ids)| ids.len() > 1)
+                .map(|(&p, ids)| (p, ids.as_slice()))
+                .collect();
+            stacks.sort_unstable_by_key(|&(p, _)| (p.y, p.x));
+            SavedRef {
+                generations: self.slots.iter().map(|s| s.generation).collect(),
+                free: &self.free,
+                entities: self.iter().collect(),
+                stacks: stacks.into_iter().map(|(_, ids)| ids).collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Entities<T> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            use serde::de::Error;
+
+            let saved = Saved::<T>::deserialize(deserializer)?;
+            let mut store = Entities {
+                slots: saved
+                    .generations
+                    .into_iter()
+                    .map(|generation| Slot {
+                        generation,
+                        dense: None,
+                    })
+                    .collect(),
+                free: Vec::new(),
+                entries: Vec::with_capacity(saved.entities.len()),
+                live: 0,
+                index: HashMap::new(),
+            };
+            for (id, pos, value) in saved.entities {
+                let dense = store.entries.len();
+                match store.slots.get_mut(id.index as usize) {
+                    Some(slot) if slot.generation == id.generation && slot.dense.is_none() => {
+                        slot.dense = Some(dense);
+                    }
+                    _ => return Err(D::Error::custom("invalid or duplicate entity id")),
+                }
+                store.entries.push(Some(Entry { id, pos, value }));
+                store.live += 1;
+                store.index.entry(pos).or_default().push(id);
+            }
+            for stack in saved.stacks {
+                let cell = stack
+                    .first()
+                    .and_then(|&id| store.pos(id))
+                    .and_then(|p| store.index.get_mut(&p));
+                match cell {
+                    Some(ids) if is_permutation(ids, &stack) => *ids = stack,
+                    _ => return Err(D::Error::custom("invalid entity stack")),
+                }
+            }
+            let mut freed = vec![false; store.slots.len()];
+            for index in saved.free {
+                match store.slots.get(index as usize) {
+                    Some(slot) if slot.dense.is_none() && !freed[index as usize] => {
+                        freed[index as usize] = true;
+                        store.free.push(index);
+                    }
+                    _ => return Err(D::Error::custom("invalid or duplicate free entity slot")),
+                }
+            }
+            Ok(store)
+        }
+    }
+
+    /// Whether `stack` lists the ids of `ids` in some order.
+    fn is_permutation(ids: &[EntityId], stack: &[EntityId]) -> bool {
+        let (mut a, mut b) = (ids.to_vec(), stack.to_vec());
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the index against the positions of the entities.
+    fn assert_consistent<T>(store: &Entities<T>) {
+        let mut indexed = 0;
+        for (&p, ids) in &store.index {
+            assert!(!ids.is_empty());
+            for &id in ids {
+                assert_eq!(store.pos(id), Some(p));
+            }
+            indexed += ids.len();
+        }
+        assert_eq!(indexed, store.len());
+        assert_eq!(store.iter().count(), store.len());
+        for (id, p, _) in store.iter() {
+            assert!(store.at(p).any(|other| other == id));
+        }
+    }
+
+    #[test]
+    fn stale_ids_stay_dead() {
+        let mut store = Entities::new();
+        let a = store.insert(Point::new(1, 1), 'a');
+        let b = store.insert(Point::new(2, 2), 'b');
+        assert_eq!(store.remove(a), Some('a'));
+        assert_eq!(store.remove(a), None);
+        // The slot is reused, with a new generation.
+        let c = store.insert(Point::new(1, 1), 'c');
+        assert_eq!(c.index, a.index);
+        assert_ne!(c, a);
+        assert!(!store.contains(a));
+        assert_eq!(store.get(a), None);
+        assert_eq!(store.get_mut(a), None);
+        assert_eq!(store.pos(a), None);
+        assert!(!store.move_to(a, Point::new(5, 5)));
+        assert_eq!(store.remove(a), None);
+        assert_eq!(store.pos(c), Some(Point::new(1, 1)));
+        assert_eq!(store.get(c), Some(&'c'));
+        store[c] = 'C';
+        assert_eq!(store[c], 'C');
+        // Insertion order, not slot order.
+        let order: Vec<char> = store.iter().map(|(_, _, &v)| v).collect();
+        assert_eq!(order, ['b', 'C']);
+        assert_eq!(store.ids().collect::<Vec<_>>(), [b, c]);
+    }
+
+    #[test]
+    fn exhausted_slot_is_retired() {
+        let mut store = Entities::new();
+        let a = store.insert(Point::ZERO, ());
+        store.slots[a.index as usize].generation = u32::MAX;
+        let a = EntityId {
+            generation: u32::MAX,
+            ..a
+        };
+        assert_eq!(store.remove(a), Some(()));
+        let b = store.insert(Point::ZERO, ());
+        assert_ne!(b.index, a.index);
+    }
+
+    #[test]
+    fn index_follows_moves_and_removals() {
+        let mut store = Entities::new();
+        let p = Point::new(3, 3);
+        let ids: Vec<EntityId> = (0..40)
+            .map(|i| store.insert(Point::new(i % 7, i / 7), i))
+            .collect();
+        assert_consistent(&store);
+        store.move_to(ids[0], p);
+        store.move_to(ids[1], p);
+        // Moving in place changes nothing.
+        assert!(store.move_to(ids[1], p));
+        let arrived: Vec<EntityId> = store.at(p).collect();
+        assert_eq!(arrived, [ids[24], ids[0], ids[1]]);
+        assert_eq!(store.at(Point::new(0, 0)).count(), 0);
+        assert_consistent(&store);
+        // Enough removals to compact the entries.
+        for (i, &id) in ids.iter().enumerate().skip(2) {
+            if i % 2 == 0 || i > 9 {
+                assert_eq!(store.remove(id), Some(i as i32));
+            }
+        }
+        assert!(store.entries.len() < 40);
+        assert_consistent(&store);
+        assert_eq!(store.at(p).collect::<Vec<_>>(), [ids[0], ids[1]]);
+        store.move_to(ids[1], Point::new(9, 9));
+        assert_eq!(store.at(p).collect::<Vec<_>>(), [ids[0]]);
+        assert_consistent(&store);
+        let values: Vec<i32> = store.iter().map(|(_, _, &v)| v).collect();
+        assert_eq!(values, [0, 1, 3, 5, 7, 9]);
+    }
+
+    #[test]
+    fn in_range_small_and_large() {
+        let mut store = Entities::new();
+        let ids: Vec<EntityId> = (0..20)
+            .map(|i| store.insert(Point::new(i, i % 3), i))
+            .collect();
+        // Move one back so index order differs from insertion order.
+        store.move_to(ids[10], Point::new(0, 0));
+        let small: Vec<EntityId> = store.in_range(Range::new(0, 0, 2, 2)).collect();
+        assert_eq!(small, [ids[0], ids[1], ids[10]]);
+        let large: Vec<EntityId> = store.in_range(Range::new(0, 1, 100, 2)).collect();
+        let want: Vec<EntityId> = (0..20)
+            .filter(|i| i % 3 == 1 && *i != 10)
+            .map(|i| ids[i as usize])
+            .collect();
+        assert_eq!(large, want);
+        // The store can change while going through the results.
+        for id in store.in_range(Range::new(0, 0, 5, 5)) {
+            store.remove(id);
+        }
+        assert_eq!(store.len(), 14);
+        store.retain(|_, v| *v % 2 == 0);
+        assert_eq!(store.len(), 6);
+        assert_consistent(&store);
+    }
+
+    #[test]
+    fn nearest_with_filter() {
+        let mut store = Entities::new();
+        let far = store.insert(Point::new(30, 30), "hostile");
+        let tie1 = store.insert(Point::new(12, 10), "friendly");
+        let tie2 = store.insert(Point::new(8, 8), "friendly");
+        let near = store.insert(Point::new(11, 10), "hostile");
+        let p = Point::new(10, 10);
+        assert_eq!(store.nearest(p, |_, _| true), Some(near));
+        assert_eq!(store.nearest(p, |_, &v| v == "friendly"), Some(tie1));
+        store.remove(tie1);
+        assert_eq!(store.nearest(p, |_, &v| v == "friendly"), Some(tie2));
+        assert_eq!(store.nearest(p, |id, _| id != near), Some(tie2));
+        assert_eq!(store.nearest(p, |_, &v| v == "neutral"), None);
+        // Far away, past the ring search.
+        assert_eq!(store.nearest(p, |id, _| id == far), Some(far));
+        // The ring search and the scan agree on a crowded store.
+        let mut crowd = Entities::new();
+        for i in 0..200 {
+            crowd.insert(Point::new(i * 7 % 31, i * 11 % 29), i);
+        }
+        for q in [Point::new(0, 0), Point::new(15, 14), Point::new(40, -3)] {
+            let want = crowd
+                .iter()
+                .filter(|(_, _, v)| *v % 3 == 0)
+                .min_by_key(|&(_, pos, _)| chebyshev(q, pos))
+                .map(|(id, _, _)| id);
+            assert_eq!(crowd.nearest(q, |_, v| v % 3 == 0), want);
+        }
+    }
+
+    #[test]
+    fn ring_cells() {
+        let p = Point::new(5, 5);
+        assert_eq!(ring(p, 0).collect::<Vec<_>>(), [p]);
+        for r in 1..4 {
+            let cells: Vec<Point> = ring(p, r).collect();
+            assert_eq!(cells.len(), 8 * r as usize);
+            assert!(cells.iter().all(|&q| chebyshev(p, q) == r));
+            let mut dedup = cells.clone();
+            dedup.sort();
+            dedup.dedup();
+            assert_eq!(dedup.len(), cells.len());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut store = Entities::new();
+        let a = store.insert(Point::new(1, 2), "a".to_string());
+        let b = store.insert(Point::new(3, 4), "b".to_string());
+        let c = store.insert(Point::new(3, 4), "c".to_string());
+        store.remove(a);
+        // b arrives back after c.
+        store.move_to(b, Point::new(3, 5));
+        store.move_to(b, Point::new(3, 4));
+        let json = serde_json::to_string(&store).unwrap();
+        let mut back: Entities<String> = serde_json::from_str(&json).unwrap();
+        assert_consistent(&back);
+        assert_eq!(back.ids().collect::<Vec<_>>(), [b, c]);
+        assert_eq!(back.at(Point::new(3, 4)).collect::<Vec<_>>(), [c, b]);
+        assert!(!back.contains(a));
+        // Ids keep being allocated as in the original.
+        assert_eq!(
+            back.insert(Point::ZERO, "d".into()),
+            store.insert(Point::ZERO, "d".into())
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_output_is_reproducible() {
+        // Many stacks, so that hash order would show.
+        let build = || {
+            let mut store = Entities::new();
+            for i in 0..64 {
+                let p = Point::new(i % 16, i / 16);
+                store.insert(p, i);
+                store.insert(p, -i);
+            }
+            serde_json::to_string(&store).unwrap()
+        };
+        assert_eq!(build(), build());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_corrupted_input() {
+        let mut store = Entities::new();
+        let a = store.insert(Point::new(1, 2), 'a');
+        store.insert(Point::new(3, 4), 'b');
+        store.insert(Point::new(3, 4), 'c');
+        let d = store.insert(Point::new(5, 5), 'd');
+        store.remove(a);
+        store.remove(d);
+        let json = serde_json::to_string(&store).unwrap();
+        assert!(json.contains(r#""free":[0,3]"#), "{json}");
+        assert!(
+            json.contains(r#""stacks":[[{"index":1,"generation":0},{"index":2,"generation":0}]]"#)
+        );
+        assert!(serde_json::from_str::<Entities<char>>(&json).is_ok());
+        for (what, from, to) in [
+            ("occupied free slot", r#""free":[0,3]"#, r#""free":[0,1]"#),
+            ("unknown free slot", r#""free":[0,3]"#, r#""free":[0,9]"#),
+            ("duplicate free slot", r#""free":[0,3]"#, r#""free":[0,0]"#),
+            (
+                "duplicate entity",
+                r#"[{"index":2,"generation":0},{"x""#,
+                r#"[{"index":1,"generation":0},{"x""#,
+            ),
+            (
+                "stale entity",
+                r#"[{"index":2,"generation":0},{"x""#,
+                r#"[{"index":2,"generation":1},{"x""#,
+            ),
+            ("incomplete stack", r#",{"index":2,"generation":0}]]"#, "]]"),
+            (
+                "stack repeating an entity",
+                r#"{"index":2,"generation":0}]]"#,
+                r#"{"index":1,"generation":0}]]"#,
+            ),
+        ] {
+            let bad = json.replace(from, to);
+            assert_ne!(bad, json, "{what}");
+            assert!(
+                serde_json::from_str::<Entities<char>>(&bad).is_err(),
+                "{what}"
+            );
+        }
+    }
+}
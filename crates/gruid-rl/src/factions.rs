@@ -0,0 +1,296 @@
+//! Faction relationships and target selection for monster AI.
+//!
+//! A [`FactionTable`] records how each faction regards every other one, and
+//! [`FactionTable::select_target`] picks the best hostile target for an
+//! actor from a list of candidates.
+//!
+//! ```
+//! use gruid_core::Point;
+//! use gruid_rl::factions::{FactionId, FactionTable, Relation, TargetPolicy};
+//!
+//! const PLAYER: FactionId = FactionId(0);
+//! const GUARDS: FactionId = FactionId(1);
+//! const BANDITS: FactionId = FactionId(2);
+//!
+//! let mut table = FactionTable::new(3);
+//! table.set_relation(PLAYER, BANDITS, Relation::Hostile);
+//! table.set_relation(GUARDS, BANDITS, Relation::Hostile);
+//! table.set_relation(PLAYER, GUARDS, Relation::Allied);
+//!
+//! let candidates = [
+//!     (0, Point::new(5, 5), PLAYER),
+//!     (1, Point::new(2, 0), BANDITS),
+//!     (2, Point::new(1, 1), GUARDS),
+//! ];
+//! let target = table.select_target(Point::new(0, 0), GUARDS, candidates, TargetPolicy::Nearest);
+//! assert_eq!(target, Some(1));
+//! ```
+
+use gruid_core::Point;
+use gruid_paths::chebyshev;
+
+/// Identifies a faction: an index into a [`FactionTable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct FactionId(pub u16);
+
+impl FactionId {
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// How one faction regards another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Relation {
+    Allied,
+    #[default]
+    Neutral,
+    Hostile,
+}
+
+/// The relations between a fixed number of factions.
+///
+/// Every faction is allied with itself, and other pairs start out
+/// [`Neutral`](Relation::Neutral). Relations are symmetric unless the table
+/// is made [asymmetric](Self::with_asymmetric), in which case
+/// [`set_relation`](Self::set_relation) only sets how `a` regards `b`: town
+/// guards may attack thieves that do not fight back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FactionTable {
+    len: usize,
+    asymmetric: bool,
+    /// Row-major: how faction `a` regards `b` is at `a * len + b`.
+    relations: Vec<Relation>,
+}
+
+impl FactionTable {
+    /// A table of `n` mutually neutral factions, numbered `0..n`.
+    pub fn new(n: usize) -> Self {
+        let mut relations = vec![Relation::Neutral; n * n];
+        for i in 0..n {
+            relations[i * n + i] = Relation::Allied;
+        }
+        Self {
+            len: n,
+            asymmetric: false,
+            relations,
+        }
+    }
+
+    /// Make [`set_relation`](Self::set_relation) one-way. Relations already
+    /// set are kept.
+    pub fn with_asymmetric(mut self, asymmetric: bool) -> Self {
+        self.asymmetric = asymmetric;
+        self
+    }
+
+    /// Number of factions.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the table has no factions.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether relations are set one way only.
+    pub fn is_asymmetric(&self) -> bool {
+        self.asymmetric
+    }
+
+    fn slot(&self, a: FactionId, b: FactionId) -> usize {
+        assert!(
+            a.index() < self.len && b.index() < self.len,
+            "faction out of range: {a:?}, {b:?} (table has {})",
+            self.len
+        );
+        a.index() * self.len + b.index()
+    }
+
+    /// Set how `a` regards `b`, and `b` regards `a` unless the table is
+    /// asymmetric.
+    ///
+    /// # Panics
+    ///
+    /// If either faction is out of range.
+    pub fn set_relation(&mut self, a: FactionId, b: FactionId, relation: Relation) {
+        let ab = self.slot(a, b);
+        self.relations[ab] = relation;
+        if !self.asymmetric {
+            let ba = self.slot(b, a);
+            self.relations[ba] = relation;
+        }
+    }
+
+    /// How `a` regards `b`.
+    ///
+    /// # Panics
+    ///
+    /// If either faction is out of range.
+    pub fn relation(&self, a: FactionId, b: FactionId) -> Relation {
+        self.relations[self.slot(a, b)]
+    }
+
+    /// Whether `a` is hostile to `b`.
+    pub fn is_hostile(&self, a: FactionId, b: FactionId) -> bool {
+        self.relation(a, b) == Relation::Hostile
+    }
+
+    /// Pick the target an actor of faction `me_faction` at `me_pos` should
+    /// go after, among `candidates` given as `(entity, position, faction)`.
+    ///
+    /// Only candidates `me_faction` is hostile to are considered. Distances
+    /// are Chebyshev distances. Remaining ties go to the smallest entity,
+    /// so the result does not depend on the order of `candidates`.
+    pub fn select_target<E: Ord + Copy>(
+        &self,
+        me_pos: Point,
+        me_faction: FactionId,
+        candidates: impl IntoIterator<Item = (E, Point, FactionId)>,
+        policy: TargetPolicy<'_, E>,
+    ) -> Option<E> {
+        let dist = |p| chebyshev(me_pos, p);
+        candidates
+            .into_iter()
+            .filter(|&(_, _, f)| self.is_hostile(me_faction, f))
+            .filter_map(|(e, p, _)| {
+                let key = match policy {
+                    TargetPolicy::Nearest => (0, dist(p)),
+                    TargetPolicy::LowestHp(hp) => (hp(e), dist(p)),
+                    TargetPolicy::NearestVisible(los) => {
+                        if !los(me_pos, p) {
+                            return None;
+                        }
+                        (0, dist(p))
+                    }
+                };
+                Some((key, e))
+            })
+            .min()
+            .map(|(_, e)| e)
+    }
+}
+
+/// How [`FactionTable::select_target`] ranks hostile candidates.
+#[derive(Clone, Copy)]
+pub enum TargetPolicy<'a, E> {
+    /// The closest one.
+    Nearest,
+    /// The one with the lowest key, usually hit points, then the closest.
+    LowestHp(&'a dyn Fn(E) -> i32),
+    /// The closest one in line of sight, as told by a `(from, to)` closure
+    /// (for instance backed by [`FOV`](crate::fov::FOV)).
+    NearestVisible(&'a dyn Fn(Point, Point) -> bool),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: FactionId = FactionId(0);
+    const B: FactionId = FactionId(1);
+    const C: FactionId = FactionId(2);
+
+    #[test]
+    fn symmetric_and_asymmetric_relations() {
+        let mut t = FactionTable::new(3);
+        assert_eq!(t.relation(A, A), Relation::Allied);
+        assert_eq!(t.relation(A, B), Relation::Neutral);
+        t.set_relation(A, B, Relation::Hostile);
+        assert_eq!(t.relation(B, A), Relation::Hostile);
+        t.set_relation(B, C, Relation::Allied);
+        assert_eq!(t.relation(C, B), Relation::Allied);
+
+        let mut t = t.with_asymmetric(true);
+        t.set_relation(C, A, Relation::Hostile);
+        assert!(t.is_hostile(C, A));
+        assert_eq!(t.relation(A, C), Relation::Neutral);
+        // Earlier symmetric settings are kept.
+        assert!(t.is_hostile(B, A));
+    }
+
+    #[test]
+    #[should_panic(expected = "faction out of range")]
+    fn out_of_range_faction_panics() {
+        FactionTable::new(2).relation(A, C);
+    }
+
+    fn table() -> FactionTable {
+        let mut t = FactionTable::new(3);
+        t.set_relation(A, B, Relation::Hostile);
+        t.set_relation(A, C, Relation::Allied);
+        t
+    }
+
+    #[test]
+    fn policies_break_ties_by_entity() {
+        let t = table();
+        let me = Point::new(0, 0);
+        let candidates = [
+            (5, Point::new(3, 0), B),
+            (2, Point::new(0, 3), B),
+            (1, Point::new(1, 1), C),
+            (4, Point::new(-3, 2), B),
+            (7, Point::new(9, 9), B),
+        ];
+        // 2, 4 and 5 are all at distance 3; the ally at 1 is ignored.
+        assert_eq!(
+            t.select_target(me, A, candidates, TargetPolicy::Nearest),
+            Some(2)
+        );
+        let mut reversed = candidates;
+        reversed.reverse();
+        assert_eq!(
+            t.select_target(me, A, reversed, TargetPolicy::Nearest),
+            Some(2)
+        );
+
+        let hp = |e: i32| if e == 7 || e == 4 { 1 } else { 5 };
+        assert_eq!(
+            t.select_target(me, A, candidates, TargetPolicy::LowestHp(&hp)),
+            Some(4)
+        );
+
+        // Nobody is hostile to C.
+        assert_eq!(
+            t.select_target(me, C, candidates, TargetPolicy::Nearest),
+            None
+        );
+    }
+
+    #[test]
+    fn nearest_visible_filters_by_los() {
+        let t = table();
+        let me = Point::new(0, 0);
+        let candidates = [
+            (0, Point::new(1, 0), B),
+            (1, Point::new(0, 4), B),
+            (2, Point::new(6, 6), B),
+        ];
+        // A wall along x = 1 hides everything to the east.
+        let los = |from: Point, to: Point| from.x.max(to.x) < 1;
+        assert_eq!(
+            t.select_target(me, A, candidates, TargetPolicy::NearestVisible(&los)),
+            Some(1)
+        );
+        let blind = |_: Point, _: Point| false;
+        assert_eq!(
+            t.select_target(me, A, candidates, TargetPolicy::NearestVisible(&blind)),
+            None
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let t = table().with_asymmetric(true);
+        let json = serde_json::to_string(&t).unwrap();
+        let back: FactionTable = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, t);
+    }
+}
@@ -9,6 +9,21 @@
 //!
 //! Both algorithms are symmetric (under certain conditions) with expansive
 //! walls, and fast.
+//!
+//! [`FOV::vision_maps`] runs the ray-based algorithm for many sources in one
+//! call (e.g. every monster each turn) and keeps the per-source results in a
+//! [`VisionResults`] for later queries.
+//!
+//! Both only store a window of the range around the sources: the cells
+//! within their maximum cost or depth. On a huge map, a computation costs
+//! in time and memory what its window does, not the whole range, and
+//! queries outside the window answer as for unreached cells: [`FOV::at`]
+//! gives `None` and [`FOV::visible`] `false`.
+//!
+//! For soft fog edges, both algorithms also give visibility levels, zero
+//! exactly where a cell is not visible: [`FOV::visibility_level`] derives
+//! them from ray costs with a [`Falloff`] curve, and
+//! [`FOV::compute_soft_edges`] dims the outer rings of SSC results.
 
 use gruid_core::{Point, Range};
 
@@ -33,6 +48,44 @@ pub enum FovShape {
     Circle,
 }
 
+/// How ray-based visibility levels decrease with the ray cost, relative
+/// to the lighter's maximum cost. See [`FOV::visibility_level`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Falloff {
+    /// Levels decrease evenly from the source to the maximum cost.
+    Linear,
+    /// Levels decrease with the square of the cost: slowly near the source,
+    /// faster near the maximum.
+    Quadratic,
+    /// Full level, except over the given number of cost units before the
+    /// maximum, where levels decrease evenly. This is the default, over 3
+    /// units.
+    Edge(i32),
+}
+
+impl Default for Falloff {
+    fn default() -> Self {
+        Falloff::Edge(3)
+    }
+}
+
+impl Falloff {
+    /// Brightness in `0.0..=1.0` of a cell lit at `cost`.
+    fn brightness(self, cost: i32, max_cost: i32) -> f64 {
+        let remaining = (max_cost - cost).max(0) as f64;
+        let f = match self {
+            Falloff::Linear => remaining / max_cost.max(1) as f64,
+            Falloff::Quadratic => {
+                let t = cost.max(0) as f64 / max_cost.max(1) as f64;
+                1.0 - t * t
+            }
+            Falloff::Edge(n) if n <= 0 => 1.0,
+            Falloff::Edge(n) => remaining / n as f64,
+        };
+        f.clamp(0.0, 1.0)
+    }
+}
+
 /// Trait for providing the cost of light passing through a cell.
 ///
 /// Matches Go gruid's `Lighter` interface with `Cost(src, from, to)` and
@@ -105,14 +158,68 @@ fn abs(x: i32) -> i32 {
     x.abs()
 }
 
+/// Per-source results of [`FOV::vision_maps`].
+///
+/// Each source's lighted nodes are stored contiguously, sorted by position,
+/// so lookups are a binary search within that source's nodes.
+#[derive(Debug, Clone, Default)]
+pub struct VisionResults {
+    nodes: Vec<LightNode>,
+    /// `nodes[start..end]` for each source, in input order.
+    spans: Vec<(usize, usize)>,
+}
+
+impl VisionResults {
+    /// Number of sources.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Whether there are no sources.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Lighted nodes of source `i`, sorted by row then column. Empty if `i`
+    /// is out of bounds or the source was outside the range.
+    pub fn lighted(&self, i: usize) -> &[LightNode] {
+        self.spans
+            .get(i)
+            .map_or(&[][..], |&(start, end)| &self.nodes[start..end])
+    }
+
+    /// The ray cost from source `i` to `p`, like [`FOV::at`] right after
+    /// computing that source's vision map.
+    pub fn visible_from(&self, i: usize, p: Point) -> Option<i32> {
+        let nodes = self.lighted(i);
+        nodes
+            .binary_search_by_key(&(p.y, p.x), |n| (n.pos.y, n.pos.x))
+            .ok()
+            .map(|j| nodes[j].cost)
+    }
+
+    /// Whether source `i` sees `target` at all.
+    pub fn sees(&self, i: usize, target: Point) -> bool {
+        self.visible_from(i, target).is_some()
+    }
+}
+
 /// Field of Vision computation.
 pub struct FOV {
     /// The rectangular range of valid positions.
     range: Range,
-    /// Cost map for ray-based FOV. 0 = not visited, >0 = cost+1.
+    /// Part of the range covered by `costs`: the cells within reach of the
+    /// sources of the last ray-based computation.
+    window: Range,
+    /// Cost map for ray-based FOV over `window`. 0 = not visited, >0 = cost+1.
     /// (Uses Go's convention: stored value = actual_cost + 1, so 0 means unvisited.)
     costs: Vec<i32>,
-    /// Visibility map for SSC FOV.
+    /// Set when a light map had sources far apart: `costs` then only holds
+    /// the last group of them, and `lighted`, sorted, holds them all.
+    sparse: bool,
+    /// Part of the range covered by `shadow_casting` and `soft`.
+    ssc_window: Range,
+    /// Visibility map for SSC FOV over `ssc_window`.
     shadow_casting: Vec<bool>,
     /// Cached list of lighted nodes from the last `vision_map`/`light_map` call.
     lighted: Vec<LightNode>,
@@ -122,43 +229,93 @@ pub struct FOV {
     ray_cache: Vec<LightNode>,
     /// Source point from the last vision_map call.
     src: Point,
-    /// Passable function for SSC (stored during computation).
     /// Tiles buffer for SSC scan.
     tiles_buf: Vec<Point>,
-    /// Capacity (for lazy allocation).
-    capacity: usize,
+    /// Per-source results of the last `vision_maps` call.
+    multi: VisionResults,
+    /// Maximum cost of the last ray-based computation.
+    max_cost: i32,
+    /// Curve of ray-based visibility levels.
+    falloff: Falloff,
+    /// SSC visibility levels from the last `compute_soft_edges`.
+    soft: Vec<u8>,
+    /// Levels of the last `compute_soft_edges`, 0 if SSC results changed
+    /// since.
+    soft_levels: u8,
+    /// Sources, depth and shape of the last SSC computation, telling cells
+    /// out of sight range from those hidden by walls.
+    ssc_srcs: Vec<Point>,
+    ssc_depth: i32,
+    ssc_shape: FovShape,
+    /// Number of cost cells reset so far.
+    #[cfg(test)]
+    cleared: usize,
+}
+
+/// The cells of `range` within Chebyshev distance `radius` of `src`.
+fn window_around(range: Range, src: Point, radius: i32) -> Range {
+    let r = radius.max(0);
+    range.intersect(Range::new(
+        src.x.saturating_sub(r),
+        src.y.saturating_sub(r),
+        src.x.saturating_add(r).saturating_add(1),
+        src.y.saturating_add(r).saturating_add(1),
+    ))
+}
+
+/// Index of `p` in a buffer covering `window`, row by row.
+fn window_idx(window: Range, p: Point) -> usize {
+    let q = p - window.min;
+    (q.y * window.width() + q.x) as usize
 }
 
 impl FOV {
-    /// Create a new FOV for the given range.
+    /// Create a new FOV for the given range. Buffers are allocated on
+    /// first use, to the size of the window computations need.
     pub fn new(range: Range) -> Self {
-        let w = range.width();
-        let h = range.height();
-        let cap = (w * h) as usize;
         Self {
             range,
+            window: Range::default(),
             costs: Vec::new(),
+            sparse: false,
+            ssc_window: Range::default(),
             shadow_casting: Vec::new(),
             lighted: Vec::new(),
             visibles: Vec::new(),
             ray_cache: Vec::new(),
             src: Point::ZERO,
             tiles_buf: Vec::new(),
-            capacity: cap,
+            multi: VisionResults::default(),
+            max_cost: 0,
+            falloff: Falloff::default(),
+            soft: Vec::new(),
+            soft_levels: 0,
+            ssc_srcs: Vec::new(),
+            ssc_depth: 0,
+            ssc_shape: FovShape::Square,
+            #[cfg(test)]
+            cleared: 0,
         }
     }
 
-    /// Change the range and reset internal buffers if needed.
+    /// Use `falloff` for [`visibility_level`](Self::visibility_level).
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Change the range, dropping previous results. Buffers are kept for
+    /// reuse.
     pub fn set_range(&mut self, range: Range) {
-        let w = range.width();
-        let h = range.height();
-        let cap = (w * h) as usize;
         self.range = range;
-        if cap > self.capacity {
-            self.capacity = cap;
-            self.costs = Vec::new();
-            self.shadow_casting = Vec::new();
-        }
+        self.window = Range::default();
+        self.costs.clear();
+        self.sparse = false;
+        self.lighted.clear();
+        self.ssc_window = Range::default();
+        self.shadow_casting.clear();
+        self.visibles.clear();
+        self.soft_levels = 0;
     }
 
     /// Return the current range.
@@ -166,21 +323,45 @@ impl FOV {
         self.range
     }
 
+    /// Index of `p` in `costs`.
     fn idx(&self, p: Point) -> usize {
-        let q = p - self.range.min;
-        let w = self.range.width();
-        (q.y * w + q.x) as usize
+        window_idx(self.window, p)
+    }
+
+    /// Index of `p` in `shadow_casting` and `soft`.
+    fn ssc_idx(&self, p: Point) -> usize {
+        window_idx(self.ssc_window, p)
     }
 
-    fn ensure_costs(&mut self) {
-        if self.costs.len() < self.capacity {
-            self.costs.resize(self.capacity, 0);
+    /// Make `costs` cover `window`, with nothing reached.
+    fn reset_costs(&mut self, window: Range) {
+        #[cfg(test)]
+        {
+            self.cleared += window.len();
         }
+        self.window = window;
+        self.costs.clear();
+        self.costs.resize(window.len(), 0);
     }
 
-    fn ensure_shadow_casting(&mut self) {
-        if self.shadow_casting.len() < self.capacity {
-            self.shadow_casting.resize(self.capacity, false);
+    /// Make `shadow_casting` cover `window`, with nothing visible.
+    fn reset_shadow_casting(&mut self, window: Range) {
+        self.ssc_window = window;
+        self.shadow_casting.clear();
+        self.shadow_casting.resize(window.len(), false);
+        self.visibles.clear();
+    }
+
+    /// Stored cost of `p` (cost + 1, or 0 if not reached).
+    fn stored(&self, p: Point) -> i32 {
+        if self.window.contains(p) {
+            self.costs[self.idx(p)]
+        } else if self.sparse {
+            self.lighted
+                .binary_search_by_key(&(p.y, p.x), |n| (n.pos.y, n.pos.x))
+                .map_or(0, |i| self.lighted[i].cost + 1)
+        } else {
+            0
         }
     }
 
@@ -192,19 +373,19 @@ impl FOV {
     /// individually with [`at`](Self::at).
     pub fn vision_map(&mut self, lt: &impl Lighter, src: Point) -> &[LightNode] {
         self.lighted.clear();
+        self.sparse = false;
         if !src.in_range(&self.range) {
+            self.reset_costs(Range::default());
             return &self.lighted;
         }
-        self.ensure_costs();
-        for c in &mut self.costs {
-            *c = 0;
-        }
+        let max_cost = lt.max_cost(src);
+        self.max_cost = max_cost;
+        self.reset_costs(window_around(self.range, src, max_cost));
         self.src = src;
         let src_idx = self.idx(src);
         self.costs[src_idx] = 1; // cost 0 stored as 1
         self.lighted.push(LightNode { pos: src, cost: 0 });
 
-        let max_cost = lt.max_cost(src);
         for d in 1..=max_cost {
             let rg = self.range.intersect(Range::new(
                 src.x - d,
@@ -262,17 +443,17 @@ impl FOV {
 
         // Primary parent: diagonal toward source
         let p0 = to + r;
-        let c0 = self.costs[self.idx(p0)];
+        let c0 = self.stored(p0);
 
         // Secondary parent (only for non-axis, non-diagonal positions)
         let (has_p1, p1_cost, p1) = if q.x == 0 || q.y == 0 || abs(q.x) == abs(q.y) {
             (false, 0, Point::ZERO)
         } else if abs(q.x) > abs(q.y) {
             let p1 = to + Point::new(r.x, 0);
-            (true, self.costs[self.idx(p1)], p1)
+            (true, self.stored(p1), p1)
         } else {
             let p1 = to + Point::new(0, r.y);
-            (true, self.costs[self.idx(p1)], p1)
+            (true, self.stored(p1), p1)
         };
 
         // Collect valid parents
@@ -311,15 +492,32 @@ impl FOV {
     }
 
     /// Query the total ray cost at `p` from the last `vision_map`/`light_map`.
-    /// Returns `None` if the position was not reached.
+    /// Returns `None` if the position was not reached, which includes
+    /// positions out of the range or beyond the maximum cost of the sources.
     pub fn at(&self, p: Point) -> Option<i32> {
-        if !p.in_range(&self.range) || self.costs.is_empty() {
-            return None;
-        }
-        let cost = self.costs[self.idx(p)];
+        let cost = self.stored(p);
         if cost <= 0 { None } else { Some(cost - 1) }
     }
 
+    /// Visibility level of `p` in `0..=max`, from the last
+    /// `vision_map`/`light_map`: 0 if `p` was not reached, `max` at the
+    /// source, and in between, at least 1, decreasing with the ray cost as
+    /// the [`Falloff`] set with [`with_falloff`](Self::with_falloff) says.
+    /// For a light map, costs are relative to the largest maximum cost of
+    /// the sources.
+    ///
+    /// A level above 0 means exactly that [`at`](Self::at) is `Some`.
+    pub fn visibility_level(&self, p: Point, max: u8) -> u8 {
+        let Some(cost) = self.at(p) else {
+            return 0;
+        };
+        if max == 0 {
+            return 0;
+        }
+        let f = self.falloff.brightness(cost, self.max_cost);
+        1 + (f * (max - 1) as f64).round() as u8
+    }
+
     /// Iterate over all lighted nodes from the last `vision_map` call.
     pub fn iter_lighted(&self) -> impl Iterator<Item = LightNode> + '_ {
         self.lighted.iter().copied()
@@ -365,53 +563,139 @@ impl FOV {
         Some(&self.ray_cache)
     }
 
+    /// Drop the results of the last [`vision_map`](Self::vision_map) or
+    /// [`light_map`](Self::light_map) call, in time proportional to the
+    /// cells they could reach rather than to the range area. Afterwards
+    /// [`at`](Self::at) reports nothing reached.
+    ///
+    /// Computations do this themselves; it is exposed for callers that loop
+    /// over many sources and want to drop results early.
+    pub fn clear_touched(&mut self) {
+        self.reset_costs(Range::default());
+        self.sparse = false;
+        self.lighted.clear();
+    }
+
+    // ── Batched vision maps ────────────────────────────────────────
+
+    /// Compute an independent ray-based vision map for each of `sources`,
+    /// as [`vision_map`](Self::vision_map) would, and return them all.
+    ///
+    /// Each source only resets the window it can reach, and results are
+    /// stored in a reused slab, so a call with small-radius sources costs
+    /// little more than the cells they see. Afterwards,
+    /// [`at`](Self::at), [`from`](Self::from) and [`ray`](Self::ray) refer to
+    /// the last source.
+    pub fn vision_maps(&mut self, lt: &impl Lighter, sources: &[Point]) -> &VisionResults {
+        let mut res = std::mem::take(&mut self.multi);
+        res.nodes.clear();
+        res.spans.clear();
+        for &src in sources {
+            let start = res.nodes.len();
+            res.nodes.extend_from_slice(self.vision_map(lt, src));
+            res.nodes[start..].sort_unstable_by_key(|n| (n.pos.y, n.pos.x));
+            res.spans.push((start, res.nodes.len()));
+        }
+        self.multi = res;
+        &self.multi
+    }
+
     // ── Multi-source light map ─────────────────────────────────────
 
     /// Build a lighting map with given light sources.
+    ///
+    /// Sources whose windows overlap are computed together, as light from
+    /// one can carry on from the cells another lit. Groups of sources far
+    /// apart are computed separately, each in its own window, so scattered
+    /// lights on a huge map cost what their surroundings do.
     pub fn light_map(&mut self, lt: &impl Lighter, srcs: &[Point]) -> &[LightNode] {
-        self.ensure_costs();
-        for c in &mut self.costs {
-            *c = 0;
-        }
+        self.lighted.clear();
+        self.sparse = false;
+        self.max_cost = 0;
+        let mut lights: Vec<(Point, i32, Range)> = Vec::new();
         for &src in srcs {
-            if !src.in_range(&self.range) {
-                continue;
+            if src.in_range(&self.range) {
+                let max_cost = lt.max_cost(src);
+                self.max_cost = self.max_cost.max(max_cost);
+                lights.push((src, max_cost, window_around(self.range, src, max_cost)));
             }
-            self.src = src;
-            let src_idx = self.idx(src);
-            self.costs[src_idx] = 1;
-            let max_cost = lt.max_cost(src);
-            for d in 1..=max_cost {
-                let rg = self.range.intersect(Range::new(
-                    src.x - d,
-                    src.y - d + 1,
-                    src.x + d + 1,
-                    src.y + d,
-                ));
-                if src.y + d < self.range.max.y {
-                    for x in rg.min.x..rg.max.x {
-                        self.light_update(lt, Point::new(x, src.y + d));
+        }
+        // Group the sources whose windows overlap, transitively.
+        let mut group: Vec<usize> = (0..lights.len()).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in 0..lights.len() {
+                for j in i + 1..lights.len() {
+                    if group[i] != group[j] && !lights[i].2.intersect(lights[j].2).is_empty() {
+                        let g = group[i].min(group[j]);
+                        group[i] = g;
+                        group[j] = g;
+                        changed = true;
                     }
                 }
-                if src.y - d >= self.range.min.y {
-                    for x in rg.min.x..rg.max.x {
-                        self.light_update(lt, Point::new(x, src.y - d));
-                    }
+            }
+        }
+        let mut groups: Vec<usize> = group.clone();
+        groups.sort_unstable();
+        groups.dedup();
+        if groups.is_empty() {
+            self.reset_costs(Range::default());
+        }
+        for (k, &g) in groups.iter().enumerate() {
+            let members = || lights.iter().zip(&group).filter(move |(_, h)| **h == g);
+            let window = members().fold(Range::default(), |w, (l, _)| w.union(l.2));
+            self.reset_costs(window);
+            for (&(src, max_cost, _), _) in members() {
+                self.light_source(lt, src, max_cost);
+            }
+            self.collect_lighted();
+            if k + 1 < groups.len() {
+                self.sparse = true;
+            }
+        }
+        if self.sparse {
+            self.lighted.sort_unstable_by_key(|n| (n.pos.y, n.pos.x));
+        }
+        if let Some(&(src, _, _)) = lights.last() {
+            self.src = src;
+        }
+        &self.lighted
+    }
+
+    /// Light from `src`, on top of the costs from the previous sources.
+    fn light_source(&mut self, lt: &impl Lighter, src: Point, max_cost: i32) {
+        self.src = src;
+        let src_idx = self.idx(src);
+        self.costs[src_idx] = 1;
+        for d in 1..=max_cost {
+            let rg = self.range.intersect(Range::new(
+                src.x - d,
+                src.y - d + 1,
+                src.x + d + 1,
+                src.y + d,
+            ));
+            if src.y + d < self.range.max.y {
+                for x in rg.min.x..rg.max.x {
+                    self.light_update(lt, Point::new(x, src.y + d));
                 }
-                if src.x + d < self.range.max.x {
-                    for y in rg.min.y..rg.max.y {
-                        self.light_update(lt, Point::new(src.x + d, y));
-                    }
+            }
+            if src.y - d >= self.range.min.y {
+                for x in rg.min.x..rg.max.x {
+                    self.light_update(lt, Point::new(x, src.y - d));
                 }
-                if src.x - d >= self.range.min.x {
-                    for y in rg.min.y..rg.max.y {
-                        self.light_update(lt, Point::new(src.x - d, y));
-                    }
+            }
+            if src.x + d < self.range.max.x {
+                for y in rg.min.y..rg.max.y {
+                    self.light_update(lt, Point::new(src.x + d, y));
+                }
+            }
+            if src.x - d >= self.range.min.x {
+                for y in rg.min.y..rg.max.y {
+                    self.light_update(lt, Point::new(src.x - d, y));
                 }
             }
         }
-        self.compute_lighted();
-        &self.lighted
     }
 
     fn light_update(&mut self, lt: &impl Lighter, to: Point) {
@@ -427,21 +711,15 @@ impl FOV {
         self.costs[idx] = n.cost;
     }
 
-    fn compute_lighted(&mut self) {
-        self.lighted.clear();
-        let w = self.range.width();
-        let h = self.range.height();
-        let mut i = 0usize;
-        for y in 0..h {
-            for x in 0..w {
-                let c = self.costs[i];
-                if c > 0 {
-                    self.lighted.push(LightNode {
-                        pos: Point::new(x + self.range.min.x, y + self.range.min.y),
-                        cost: c - 1,
-                    });
-                }
-                i += 1;
+    /// Append the cells lit in the window to `lighted`, row by row.
+    fn collect_lighted(&mut self) {
+        let w = self.window;
+        for (p, &c) in w.iter().zip(&self.costs) {
+            if c > 0 {
+                self.lighted.push(LightNode {
+                    pos: p,
+                    cost: c - 1,
+                });
             }
         }
     }
@@ -460,20 +738,19 @@ impl FOV {
         passable: impl Fn(Point) -> bool,
         diags: bool,
     ) -> &[Point] {
+        self.set_ssc_reach(&[src], max_depth, FovShape::Square);
         if !src.in_range(&self.range) {
-            self.visibles.clear();
+            self.reset_shadow_casting(Range::default());
             return &self.visibles;
         }
-        self.ensure_shadow_casting();
-        for v in &mut self.shadow_casting {
-            *v = false;
-        }
-        self.visibles.clear();
+        // Depths below 1 still scan the first row.
+        self.reset_shadow_casting(window_around(self.range, src, max_depth.max(1)));
         self.ssc_internal(src, max_depth, &passable, diags);
         &self.visibles
     }
 
-    /// Multi-source SSC.
+    /// Multi-source SSC. Buffers cover the smallest rectangle around the
+    /// windows of all sources.
     pub fn ssc_light_map(
         &mut self,
         srcs: &[Point],
@@ -481,11 +758,14 @@ impl FOV {
         passable: impl Fn(Point) -> bool,
         diags: bool,
     ) -> &[Point] {
-        self.ensure_shadow_casting();
-        for v in &mut self.shadow_casting {
-            *v = false;
-        }
-        self.visibles.clear();
+        self.set_ssc_reach(srcs, max_depth, FovShape::Square);
+        let window = srcs
+            .iter()
+            .filter(|src| src.in_range(&self.range))
+            .fold(Range::default(), |w, &src| {
+                w.union(window_around(self.range, src, max_depth.max(1)))
+            });
+        self.reset_shadow_casting(window);
         for &src in srcs {
             if src.in_range(&self.range) {
                 self.ssc_internal(src, max_depth, &passable, diags);
@@ -500,6 +780,7 @@ impl FOV {
     /// [`ssc_light_map`](Self::ssc_light_map) to clip the square boundary
     /// to a circle centered on `center` with the given `radius`.
     pub fn retain_circular(&mut self, center: Point, radius: i32) {
+        self.set_ssc_reach(&[center], radius, FovShape::Circle);
         let r_sq = (radius as i64) * (radius as i64);
         self.visibles.retain(|&p| {
             let dx = (p.x - center.x) as i64;
@@ -513,10 +794,8 @@ impl FOV {
                 *v = false;
             }
             for &p in &self.visibles {
-                if p.in_range(&self.range) {
-                    let idx = self.idx(p);
-                    self.shadow_casting[idx] = true;
-                }
+                let idx = self.ssc_idx(p);
+                self.shadow_casting[idx] = true;
             }
         }
     }
@@ -546,6 +825,7 @@ impl FOV {
         diags: bool,
     ) -> &[Point] {
         self.ssc_light_map(srcs, radius, passable, diags);
+        self.ssc_shape = FovShape::Circle;
         // For multi-source, clip each point against its nearest source.
         // Simplification: clip against the radius from any source.
         let r_sq = (radius as i64) * (radius as i64);
@@ -561,10 +841,8 @@ impl FOV {
                 *v = false;
             }
             for &p in &self.visibles {
-                if p.in_range(&self.range) {
-                    let idx = self.idx(p);
-                    self.shadow_casting[idx] = true;
-                }
+                let idx = self.ssc_idx(p);
+                self.shadow_casting[idx] = true;
             }
         }
         &self.visibles
@@ -577,7 +855,7 @@ impl FOV {
         passable: &impl Fn(Point) -> bool,
         diags: bool,
     ) {
-        let idx = self.idx(src);
+        let idx = self.ssc_idx(src);
         if !self.shadow_casting[idx] {
             self.shadow_casting[idx] = true;
             self.visibles.push(src);
@@ -589,7 +867,7 @@ impl FOV {
 
     fn reveal(&mut self, qt: Quadrant, tile: Point) {
         let p = qt.transform(tile);
-        let idx = self.idx(p);
+        let idx = self.ssc_idx(p);
         if !self.shadow_casting[idx] {
             self.shadow_casting[idx] = true;
             self.visibles.push(p);
@@ -688,17 +966,103 @@ impl FOV {
     }
 
     /// Query whether `p` is visible from the last `ssc_vision_map` call.
+    /// Positions out of the range or beyond the depth are not.
     pub fn visible(&self, p: Point) -> bool {
-        if !p.in_range(&self.range) || self.shadow_casting.is_empty() {
-            return false;
-        }
-        self.shadow_casting[self.idx(p)]
+        self.ssc_window.contains(p) && self.shadow_casting[self.ssc_idx(p)]
     }
 
     /// Iterate over all visible points from the last `ssc_vision_map` call.
     pub fn iter_visible(&self) -> impl Iterator<Item = Point> + '_ {
         self.visibles.iter().copied()
     }
+
+    /// Give the visible cells of the last SSC computation levels in
+    /// `1..=levels` for [`soft_level`](Self::soft_level), dimming the edges
+    /// of the field of vision: visible cells next to a cell out of sight
+    /// range get about a third of `levels`, the visible cells next to those
+    /// about two thirds, and the others `levels`. `levels` below 1 count as
+    /// 1.
+    ///
+    /// Cells hidden by walls within sight range do not dim their visible
+    /// neighbors, so walls keep sharp edges, and non-visible cells keep
+    /// level 0. Computing SSC results again resets the levels.
+    pub fn compute_soft_edges(&mut self, levels: u8) {
+        let levels = levels.max(1);
+        let outer = levels.div_ceil(3);
+        let inner = ((levels as u16 * 2).div_ceil(3)) as u8;
+        self.soft.clear();
+        self.soft.resize(self.ssc_window.len(), 0);
+        self.soft_levels = levels;
+        for i in 0..self.visibles.len() {
+            let p = self.visibles[i];
+            let idx = self.ssc_idx(p);
+            self.soft[idx] = if self.next_to(p, |q| !self.visible(q) && self.beyond_reach(q)) {
+                outer
+            } else {
+                levels
+            };
+        }
+        for i in 0..self.visibles.len() {
+            let p = self.visibles[i];
+            let idx = self.ssc_idx(p);
+            if self.soft[idx] == levels
+                && self.next_to(p, |q| {
+                    self.visible(q) && self.soft[self.ssc_idx(q)] == outer
+                })
+            {
+                self.soft[idx] = inner;
+            }
+        }
+    }
+
+    /// Record the reach of new SSC results, dropping their levels.
+    fn set_ssc_reach(&mut self, srcs: &[Point], depth: i32, shape: FovShape) {
+        self.soft_levels = 0;
+        self.ssc_srcs.clear();
+        self.ssc_srcs.extend_from_slice(srcs);
+        self.ssc_depth = depth;
+        self.ssc_shape = shape;
+    }
+
+    /// Whether `p` is out of sight range of every source of the last SSC
+    /// computation, rather than possibly hidden by a wall.
+    fn beyond_reach(&self, p: Point) -> bool {
+        let d = self.ssc_depth as i64;
+        self.ssc_srcs.iter().all(|&s| {
+            let (dx, dy) = ((p.x - s.x) as i64, (p.y - s.y) as i64);
+            match self.ssc_shape {
+                FovShape::Square => dx.abs().max(dy.abs()) > d,
+                FovShape::Circle => dx * dx + dy * dy > d * d,
+            }
+        })
+    }
+
+    /// Whether a neighbor of `p` within the range satisfies `f`.
+    fn next_to(&self, p: Point, f: impl Fn(Point) -> bool) -> bool {
+        p.neighbors_8()
+            .into_iter()
+            .any(|q| q.in_range(&self.range) && f(q))
+    }
+
+    /// Visibility level of `p` from the last SSC computation: 0 if it is
+    /// not visible, its level if [`compute_soft_edges`](Self::compute_soft_edges)
+    /// was called since, and 1 otherwise.
+    pub fn soft_level(&self, p: Point) -> u8 {
+        if !self.visible(p) {
+            0
+        } else if self.soft_levels == 0 {
+            1
+        } else {
+            self.soft[self.ssc_idx(p)]
+        }
+    }
+
+    /// Iterate over the visible points of the last SSC computation with
+    /// their [`soft_level`](Self::soft_level), in the order of
+    /// [`iter_visible`](Self::iter_visible).
+    pub fn iter_levels(&self) -> impl Iterator<Item = (Point, u8)> + '_ {
+        self.visibles.iter().map(|&p| (p, self.soft_level(p)))
+    }
 }
 
 // ── SSC helper types ──────────────────────────────────────────────
@@ -1190,4 +1554,303 @@ mod tests {
             "ray from (0,0) to (5,0) should have 6 nodes"
         );
     }
+
+    fn random_walls(seed: u64, range: Range, max_cost: i32) -> SimpleWalls {
+        use rand::{RngExt, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let walls = range
+            .iter()
+            .filter(|_| rng.random_range(0..4) == 0)
+            .collect();
+        SimpleWalls { walls, max_cost }
+    }
+
+    #[test]
+    fn test_vision_maps_match_individual_maps() {
+        let range = Range::new(0, 0, 30, 20);
+        for seed in 0..5 {
+            let lt = random_walls(seed, range, 6);
+            let sources: Vec<Point> = (0..16)
+                .map(|i| Point::new((i * 7 + seed as i32) % 30, (i * 5) % 20))
+                .chain([Point::new(-1, 3)])
+                .collect();
+            let mut batch = FOV::new(range);
+            let results = batch.vision_maps(&lt, &sources).clone();
+            assert_eq!(results.len(), sources.len());
+
+            let mut single = FOV::new(range);
+            for (i, &src) in sources.iter().enumerate() {
+                single.vision_map(&lt, src);
+                assert_eq!(results.lighted(i).len(), single.iter_lighted().count());
+                for p in range.iter() {
+                    assert_eq!(results.visible_from(i, p), single.at(p), "{seed} {src} {p}");
+                }
+            }
+            // The FOV itself is left on the last source.
+            for p in range.iter() {
+                assert_eq!(batch.at(p), single.at(p));
+            }
+        }
+    }
+
+    #[test]
+    fn test_vision_maps_clear_only_touched_cells() {
+        let range = Range::new(0, 0, 80, 21);
+        let lt = SimpleWalls {
+            walls: Vec::new(),
+            max_cost: 2,
+        };
+        let sources: Vec<Point> = (0..16).map(|i| Point::new(i * 5 + 2, 10)).collect();
+        let mut fov = FOV::new(range);
+        let results = fov.vision_maps(&lt, &sources);
+        assert!(results.sees(0, Point::new(3, 11)));
+        assert!(!results.sees(0, Point::new(7, 10)));
+        // Each source only resets the 5x5 window it can reach: a full
+        // clear per source would have been 16 * 80 * 21 cells.
+        assert_eq!(fov.cleared, 16 * 25);
+
+        // Nor is there a full wipe after a range change.
+        fov.set_range(range);
+        fov.cleared = 0;
+        fov.vision_map(&lt, Point::new(40, 10));
+        assert_eq!(fov.cleared, 25);
+        fov.clear_touched();
+        assert_eq!(fov.at(Point::new(40, 10)), None);
+    }
+
+    /// Ray costs over the whole range, as computed before windows: a light
+    /// map with each source in turn, on a buffer covering the range.
+    fn unwindowed_costs(range: Range, lt: &impl Lighter, srcs: &[Point]) -> Vec<Option<i32>> {
+        let mut costs = vec![0i32; range.len()];
+        let idx = |p: Point| ((p.y - range.min.y) * range.width() + p.x - range.min.x) as usize;
+        for &src in srcs.iter().filter(|src| src.in_range(&range)) {
+            costs[idx(src)] = 1;
+            let max_cost = lt.max_cost(src);
+            let mut ring: Vec<Point> = Vec::new();
+            for d in 1..=max_cost {
+                ring.clear();
+                let rg = range.intersect(Range::new(
+                    src.x - d,
+                    src.y - d + 1,
+                    src.x + d + 1,
+                    src.y + d,
+                ));
+                for x in rg.min.x..rg.max.x {
+                    ring.extend([Point::new(x, src.y + d), Point::new(x, src.y - d)]);
+                }
+                for y in rg.min.y..rg.max.y {
+                    ring.extend([Point::new(src.x + d, y), Point::new(src.x - d, y)]);
+                }
+                for &to in ring.iter().filter(|p| p.in_range(&range)) {
+                    let q = src - to;
+                    let r = Point::new(sign(q.x), sign(q.y));
+                    let mut parents = vec![to + r];
+                    if q.x != 0 && q.y != 0 && abs(q.x) != abs(q.y) {
+                        parents.push(if abs(q.x) > abs(q.y) {
+                            to + Point::new(r.x, 0)
+                        } else {
+                            to + Point::new(0, r.y)
+                        });
+                    }
+                    let best = parents
+                        .iter()
+                        .filter(|p| p.in_range(&range) && costs[idx(**p)] > 0)
+                        .map(|&p| costs[idx(p)].saturating_add(lt.cost(src, p, to)))
+                        .min();
+                    if let Some(c) = best.filter(|&c| c < i32::MAX) {
+                        let cur = costs[idx(to)];
+                        if cur == 0 || c < cur {
+                            costs[idx(to)] = c;
+                        }
+                    }
+                }
+            }
+        }
+        costs.into_iter().map(|c| (c > 0).then(|| c - 1)).collect()
+    }
+
+    #[test]
+    fn test_windows_match_unwindowed_costs() {
+        let range = Range::new(0, 0, 40, 30);
+        for seed in 0..6 {
+            let lt = random_walls(seed, range, 5);
+            let mut fov = FOV::new(range);
+            let srcs: &[&[Point]] = &[
+                &[Point::new(20, 15)],
+                &[Point::new(1, 2)],
+                // Far apart, then overlapping in a chain, then out of range.
+                &[Point::new(3, 3), Point::new(35, 25), Point::new(3, 26)],
+                &[
+                    Point::new(10, 10),
+                    Point::new(18, 12),
+                    Point::new(26, 14),
+                    Point::new(3, 27),
+                ],
+                &[
+                    Point::new(30, 5),
+                    Point::new(-4, 5),
+                    Point::new(31, 6),
+                    Point::new(5, 20),
+                ],
+            ];
+            for srcs in srcs {
+                let want = unwindowed_costs(range, &lt, srcs);
+                if let [src] = srcs {
+                    fov.vision_map(&lt, *src);
+                } else {
+                    let n = fov.light_map(&lt, srcs).len();
+                    assert_eq!(n, want.iter().flatten().count());
+                    let lighted: Vec<Point> = fov.iter_lighted().map(|n| n.pos).collect();
+                    assert!(lighted.iter().all(|p| fov.at(*p).is_some()));
+                }
+                for (p, &cost) in range.iter().zip(&want) {
+                    assert_eq!(fov.at(p), cost, "{seed} {srcs:?} {p}");
+                }
+                assert_eq!(fov.at(Point::new(-1, 0)), None);
+                assert_eq!(fov.at(Point::new(40, 29)), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ssc_window_queries() {
+        let range = Range::new(0, 0, 40, 30);
+        let lt = random_walls(3, range, 5);
+        let passable = |p: Point| !lt.walls.contains(&p);
+        let mut fov = FOV::new(range);
+        fov.ssc_light_map(&[Point::new(4, 4), Point::new(30, 20)], 5, passable, true);
+        let visibles: Vec<Point> = fov.iter_visible().collect();
+        for p in range.iter() {
+            assert_eq!(fov.visible(p), visibles.contains(&p), "{p}");
+        }
+        fov.ssc_vision_map(Point::new(20, 15), 0, passable, true);
+        assert!(fov.visible(Point::new(20, 15)));
+        assert!(!fov.visible(Point::new(4, 4)));
+        assert!(!fov.visible(Point::new(-1, 0)));
+    }
+
+    #[test]
+    fn test_window_cost_on_huge_map() {
+        struct Open;
+        impl Lighter for Open {
+            fn cost(&self, _src: Point, _from: Point, _to: Point) -> i32 {
+                1
+            }
+            fn max_cost(&self, _src: Point) -> i32 {
+                10
+            }
+        }
+        let range = Range::new(0, 0, 1000, 1000);
+        let src = Point::new(500, 500);
+        let mut fov = FOV::new(range);
+        for _ in 0..20 {
+            fov.vision_map(&Open, src);
+        }
+        assert_eq!(fov.costs.capacity(), 21 * 21);
+        let want = unwindowed_costs(range, &Open, &[src]);
+        for (p, &cost) in range.iter().zip(&want) {
+            assert_eq!(fov.at(p), cost);
+        }
+    }
+
+    // ── Visibility levels ──────────────────────────────────────────
+
+    fn open_field(max_cost: i32) -> SimpleWalls {
+        SimpleWalls {
+            walls: vec![],
+            max_cost,
+        }
+    }
+
+    /// Levels along the row of `src`, going east.
+    fn row_levels(level: impl Fn(Point) -> u8, src: Point, len: i32) -> Vec<u8> {
+        (0..len).map(|d| level(src.shift(d, 0))).collect()
+    }
+
+    #[test]
+    fn levels_decrease_with_distance() {
+        let src = Point::new(10, 10);
+        let mut fov = FOV::new(Range::new(0, 0, 21, 21)).with_falloff(Falloff::Linear);
+        fov.vision_map(&open_field(8), src);
+        let levels = row_levels(|p| fov.visibility_level(p, 255), src, 11);
+        assert_eq!(levels[0], 255);
+        assert!(
+            levels[..9].windows(2).all(|w| w[0] > w[1] && w[1] > 0),
+            "{levels:?}"
+        );
+        assert_eq!(levels[9..], [0, 0]);
+
+        // The default falloff only dims the last few costs.
+        let mut fov = FOV::new(Range::new(0, 0, 21, 21));
+        fov.vision_map(&open_field(8), src);
+        let levels = row_levels(|p| fov.visibility_level(p, 7), src, 10);
+        assert_eq!(levels, [7, 7, 7, 7, 7, 7, 5, 3, 1, 0]);
+
+        let mut fov = FOV::new(Range::new(0, 0, 21, 21));
+        fov.ssc_vision_map(src, 5, |_| true, true);
+        fov.compute_soft_edges(9);
+        let levels = row_levels(|p| fov.soft_level(p), src, 7);
+        assert_eq!(levels, [9, 9, 9, 9, 6, 3, 0]);
+        // Deterministic.
+        let before: Vec<_> = fov.iter_levels().collect();
+        fov.compute_soft_edges(9);
+        assert_eq!(fov.iter_levels().collect::<Vec<_>>(), before);
+    }
+
+    #[test]
+    fn levels_sharp_behind_walls() {
+        let src = Point::new(10, 10);
+        let walls: Vec<Point> = (0..21).map(|y| Point::new(13, y)).collect();
+        let mut fov = FOV::new(Range::new(0, 0, 21, 21));
+        let lighter = SimpleWalls {
+            walls: walls.clone(),
+            max_cost: 8,
+        };
+        fov.vision_map(&lighter, src);
+        let levels = row_levels(|p| fov.visibility_level(p, 7), src, 6);
+        assert_eq!(levels, [7, 7, 7, 7, 0, 0]);
+
+        fov.ssc_vision_map(src, 5, |p| !walls.contains(&p), true);
+        fov.compute_soft_edges(9);
+        let levels = row_levels(|p| fov.soft_level(p), src, 6);
+        assert_eq!(levels, [9, 9, 9, 9, 0, 0]);
+        // The range edge is still soft.
+        let levels = row_levels(|p| fov.soft_level(p), src.shift(-5, 0), 3);
+        assert_eq!(levels, [3, 6, 9]);
+    }
+
+    #[test]
+    fn levels_threshold_to_visibility() {
+        let range = Range::new(0, 0, 30, 20);
+        let walls: Vec<Point> = range
+            .iter()
+            .filter(|p| (p.x * 7 + p.y * 13) % 11 == 0)
+            .collect();
+        let src = Point::new(15, 10);
+        let lighter = SimpleWalls {
+            walls: walls.clone(),
+            max_cost: 9,
+        };
+        for falloff in [Falloff::Linear, Falloff::Quadratic, Falloff::Edge(4)] {
+            let mut fov = FOV::new(range).with_falloff(falloff);
+            fov.vision_map(&lighter, src);
+            for p in range {
+                assert_eq!(fov.visibility_level(p, 4) > 0, fov.at(p).is_some(), "{p}");
+            }
+        }
+
+        let mut fov = FOV::new(range);
+        fov.ssc_vision_map_circular(src, 9, |p| !walls.contains(&p), false);
+        let levels = |fov: &FOV| -> Vec<(Point, u8)> { fov.iter_levels().collect() };
+        // Without soft edges, levels are binary.
+        assert!(levels(&fov).iter().all(|&(_, l)| l == 1));
+        for n in [1, 2, 5, 255] {
+            fov.compute_soft_edges(n);
+            for p in range {
+                assert_eq!(fov.soft_level(p) > 0, fov.visible(p), "{p}");
+                assert!(fov.soft_level(p) <= n);
+            }
+            assert_eq!(levels(&fov).len(), fov.iter_visible().count());
+        }
+    }
 }
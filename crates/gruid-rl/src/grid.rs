@@ -374,6 +374,93 @@ impl Grid {
         n
     }
 
+    /// Count the neighbours of `p` equal to `cell`: the 4 orthogonal ones,
+    /// plus the 4 diagonal ones if `diagonal` is set. `p` itself is not
+    /// counted. Neighbours outside the grid count as `cell` if
+    /// `out_of_range` is set, as different cells otherwise.
+    pub fn count_neighbors(&self, p: Point, cell: Cell, diagonal: bool, out_of_range: bool) -> u32 {
+        const ORTHOGONAL: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+        const DIAGONAL: [(i32, i32); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+        let diagonals = if diagonal { &DIAGONAL[..] } else { &[] };
+        ORTHOGONAL
+            .iter()
+            .chain(diagonals)
+            .filter(|&&(dx, dy)| match self.at(Point::new(p.x + dx, p.y + dy)) {
+                Some(c) => c == cell,
+                None => out_of_range,
+            })
+            .count() as u32
+    }
+
+    /// Write `f(p, cell, self)` to `dst` for every cell of this grid.
+    ///
+    /// `f` reads this grid through its third argument and never sees the
+    /// values already written, even if `dst` shares its buffer: it is a
+    /// one-pass transform, as a cellular automaton step needs. Positions
+    /// outside `dst` are skipped.
+    pub fn map_into(&self, dst: &Grid, f: impl Fn(Point, Cell, &Grid) -> Cell) {
+        let cells: Vec<(Point, Cell)> = self.iter().map(|(p, c)| (p, f(p, c, self))).collect();
+        for (p, c) in cells {
+            dst.set(p, c);
+        }
+    }
+
+    /// Apply a cellular automaton `rule` `reps` times, using `temp` as the
+    /// second buffer.
+    ///
+    /// `rule` receives each position, its cell, and the number of its 8
+    /// neighbours equal to `count_cell`, those outside the grid included,
+    /// as for closed caves. See [`apply_rule_with`](Self::apply_rule_with)
+    /// for rules needing other counts.
+    pub fn apply_rule(
+        &mut self,
+        temp: &mut Grid,
+        rule: impl Fn(Point, Cell, u32) -> Cell,
+        count_cell: Cell,
+        reps: u32,
+    ) {
+        self.apply_rule_with(temp, reps, |p, c, src| {
+            rule(p, c, src.count_neighbors(p, count_cell, true, true))
+        });
+    }
+
+    /// Apply `rule` `reps` times, each step reading the previous one's
+    /// result: `rule` receives each position, its cell, and the whole
+    /// previous grid.
+    ///
+    /// Steps alternate between this grid and `temp`, but the result always
+    /// ends up in this grid's buffer, so slices and clones of it see it.
+    /// `temp`'s content is unspecified afterwards. A `temp` of another
+    /// size, or sharing this grid's buffer, is replaced by a new grid.
+    pub fn apply_rule_with(
+        &mut self,
+        temp: &mut Grid,
+        reps: u32,
+        rule: impl Fn(Point, Cell, &Grid) -> Cell,
+    ) {
+        if reps == 0 {
+            return;
+        }
+        if temp.size() != self.size() || Rc::ptr_eq(&temp.buf, &self.buf) {
+            *temp = Grid::new(self.width(), self.height());
+        }
+        for _ in 0..reps {
+            self.map_into(temp, &rule);
+            std::mem::swap(self, temp);
+        }
+        if reps % 2 == 1 {
+            std::mem::swap(self, temp);
+            self.copy_from(temp);
+        }
+    }
+
+    /// Positions of the cells equal to `cell`, in row-major order with
+    /// **relative** coords.
+    pub fn find(&self, cell: Cell) -> impl Iterator<Item = Point> + use<> {
+        self.iter()
+            .filter_map(move |(p, c)| (c == cell).then_some(p))
+    }
+
     /// Iterate over `(Point, Cell)` pairs in row-major order with **relative** coords.
     pub fn iter(&self) -> GridIter {
         let buf = self.buf.borrow();
@@ -742,4 +829,77 @@ mod tests {
         let copied = g.copy_from(&g);
         assert_eq!(copied, Point::new(10, 10));
     }
+
+    #[test]
+    fn test_count_neighbors_at_boundary() {
+        let g = Grid::new(4, 3);
+        g.fill(Cell(1));
+        let corner = Point::new(0, 0);
+        let edge = Point::new(2, 0);
+        let inner = Point::new(1, 1);
+        // Outside cells counting as different.
+        assert_eq!(g.count_neighbors(corner, Cell(1), false, false), 2);
+        assert_eq!(g.count_neighbors(corner, Cell(1), true, false), 3);
+        assert_eq!(g.count_neighbors(edge, Cell(1), true, false), 5);
+        assert_eq!(g.count_neighbors(inner, Cell(1), true, false), 8);
+        // Outside cells counting as the counted one.
+        assert_eq!(g.count_neighbors(corner, Cell(1), false, true), 4);
+        assert_eq!(g.count_neighbors(corner, Cell(1), true, true), 8);
+        assert_eq!(g.count_neighbors(edge, Cell(0), true, true), 3);
+        assert_eq!(g.count_neighbors(inner, Cell(0), true, true), 0);
+        // Slices do not see the cells around them.
+        let s = g.slice(Range::new(1, 1, 3, 2));
+        assert_eq!(s.count_neighbors(Point::new(0, 0), Cell(1), true, false), 1);
+    }
+
+    #[test]
+    fn test_map_into_reads_source_only() {
+        let g = Grid::new(5, 1);
+        g.set(Point::new(0, 0), Cell(1));
+        // Shift right by one: with in-place writes, the 1 would spread
+        // along the whole row.
+        let shift = |p: Point, _, src: &Grid| src.at(Point::new(p.x - 1, p.y)).unwrap_or_default();
+        g.map_into(&g.clone(), shift);
+        assert_eq!(g.find(Cell(1)).collect::<Vec<_>>(), [Point::new(1, 0)]);
+
+        let dst = Grid::new(5, 1);
+        g.map_into(&dst, shift);
+        assert_eq!(dst.find(Cell(1)).collect::<Vec<_>>(), [Point::new(2, 0)]);
+        assert_eq!(g.find(Cell(1)).collect::<Vec<_>>(), [Point::new(1, 0)]);
+    }
+
+    #[test]
+    fn test_apply_rule_result_in_own_buffer() {
+        for reps in 0..4 {
+            let mut g = Grid::new(6, 2);
+            let view = g.clone();
+            let mut temp = Grid::new(1, 1);
+            // Each step sees the previous one's result.
+            g.apply_rule(&mut temp, |_, c, _| Cell(c.0 + 1), Cell(0), reps);
+            assert_eq!(view.count(Cell(reps as i32)), 12, "{reps} reps");
+        }
+        // Outside cells count: a single floor cell fills in.
+        let mut g = Grid::new(1, 1);
+        g.apply_rule(
+            &mut Grid::new(1, 1),
+            |_, c, n| if n >= 5 { Cell(1) } else { c },
+            Cell(1),
+            1,
+        );
+        assert_eq!(g.at(Point::new(0, 0)), Some(Cell(1)));
+    }
+
+    #[test]
+    fn test_find() {
+        let g = Grid::new(3, 3);
+        g.set(Point::new(2, 0), Cell(4));
+        g.set(Point::new(0, 2), Cell(4));
+        let s = g.slice(Range::new(0, 1, 3, 3));
+        assert_eq!(
+            g.find(Cell(4)).collect::<Vec<_>>(),
+            [Point::new(2, 0), Point::new(0, 2)]
+        );
+        assert_eq!(s.find(Cell(4)).collect::<Vec<_>>(), [Point::new(0, 1)]);
+        assert_eq!(g.find(Cell(9)).count(), 0);
+    }
 }
@@ -1,13 +1,34 @@
-//! Roguelike utilities for gruid: FOV, map generation, event queue.
+//! Roguelike utilities for gruid: FOV, map generation, event queue, random
+//! tables, dice, factions, trigger regions, timed status effects, entity
+//! storage, unbounded chunked maps, terrain flags shared by FOV and
+//! pathfinding, and a day and night clock.
 
+pub mod chunked;
+pub mod clock;
+pub mod dice;
+pub mod effects;
+pub mod entities;
 pub mod events;
+pub mod factions;
 pub mod fov;
 pub mod grid;
 pub mod mapgen;
+pub mod tables;
+pub mod terrain;
+pub mod triggers;
 pub mod vault;
 
+pub use chunked::{Chunk, ChunkCoord, ChunkedGrid};
+pub use clock::{DayPhase, DayPhases, GameClock};
+pub use dice::{DiceError, DiceExpr};
+pub use effects::{EffectDef, EffectEvent, Effects, Stacking};
+pub use entities::{Entities, EntityId};
 pub use events::EventQueue;
-pub use fov::{CircularLighter, FOV, FovShape};
+pub use factions::{FactionId, FactionTable, Relation, TargetPolicy};
+pub use fov::{CircularLighter, FOV, Falloff, FovShape, VisionResults};
 pub use grid::{Cell as RlCell, Grid as RlGrid};
-pub use mapgen::MapGen;
+pub use mapgen::{MapGen, Symmetry};
+pub use tables::{Weight, WeightedTable};
+pub use terrain::{MoveAdapter, SightAdapter, TerrainFlags, TerrainMask};
+pub use triggers::{TriggerEvent, TriggerShape, Triggers};
 pub use vault::Vault;
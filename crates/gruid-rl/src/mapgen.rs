@@ -4,10 +4,26 @@
 //! - **Random Walk Cave**: uses a drunk-walk approach to carve open space.
 //! - **Cellular Automata Cave**: initializes random walls then smooths
 //!   with cellular automata rules.
+//!
+//! Both generators honour optional constraints set on [`MapGen`]:
+//! a [`Symmetry`] (only the fundamental half is generated, its image is
+//! written at the same time), a solid border, and clear zones that are
+//! forced open and connected to the main area after generation.
+//!
+//! Rivers and roads are carved into an existing map along least-cost
+//! paths with [`MapGen::carve_path`] and [`MapGen::carve_river`].
+//!
+//! Generation passes can be composed into a [`pipeline::Pipeline`] of
+//! named, independently seeded stages.
+
+pub mod pipeline;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
 
 use crate::grid::{Cell, Grid};
-use gruid_core::Point;
-use gruid_paths::PathRange;
+use gruid_core::{Point, Range};
+use gruid_paths::{AstarPather, PathRange, Pather, WeightedPather, manhattan};
 use rand::{Rng, RngExt};
 
 /// Trait for choosing a random neighbor during random-walk cave generation.
@@ -57,16 +73,141 @@ impl Default for CellularAutomataRule {
     }
 }
 
+/// Symmetry constraint for generated maps.
+///
+/// Transforms are relative to the generator's grid: for a grid of size
+/// `(w, h)`, `MirrorX` maps `(x, y)` to `(w-1-x, y)`, `MirrorY` maps it to
+/// `(x, h-1-y)` and `Rotational180` to `(w-1-x, h-1-y)`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Symmetry {
+    /// No constraint.
+    #[default]
+    None,
+    /// Left-right mirror symmetry.
+    MirrorX,
+    /// Top-bottom mirror symmetry.
+    MirrorY,
+    /// Point symmetry around the grid centre.
+    Rotational180,
+}
+
+impl Symmetry {
+    /// Image of `p` under this symmetry in a grid of the given `size`.
+    pub fn apply(self, p: Point, size: Point) -> Point {
+        match self {
+            Self::None => p,
+            Self::MirrorX => Point::new(size.x - 1 - p.x, p.y),
+            Self::MirrorY => Point::new(p.x, size.y - 1 - p.y),
+            Self::Rotational180 => Point::new(size.x - 1 - p.x, size.y - 1 - p.y),
+        }
+    }
+}
+
+/// How [`MapGen::carve_path`] lays out a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CarveConfig {
+    /// Width of the path in cells, measured across it.
+    pub width: i32,
+    /// Cell flanking the path on both sides, if any.
+    pub bank: Option<Cell>,
+    /// Width of each bank in cells.
+    pub bank_width: i32,
+    /// Extra cost for each change of direction. Non-zero values favour
+    /// long straight stretches and gentle turns, as roads have.
+    pub turn_penalty: i32,
+    /// `(floor, bridge)`: where the path crosses `floor` cells, write
+    /// `bridge` instead of the path cell, and leave them out of the banks,
+    /// so existing corridors stay passable.
+    pub bridge: Option<(Cell, Cell)>,
+}
+
+impl Default for CarveConfig {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            bank: None,
+            bank_width: 1,
+            turn_penalty: 0,
+            bridge: None,
+        }
+    }
+}
+
+/// How [`MapGen::carve_river`] lays out a river.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RiverConfig {
+    /// How each stretch of the river is carved.
+    pub carve: CarveConfig,
+    /// Number of intermediate waypoints the river is drawn through.
+    pub waypoints: usize,
+    /// Largest distance, in cells, waypoints are moved off the straight
+    /// line across the map.
+    pub meander: i32,
+}
+
+impl Default for RiverConfig {
+    fn default() -> Self {
+        Self {
+            carve: CarveConfig::default(),
+            waypoints: 3,
+            meander: 4,
+        }
+    }
+}
+
 /// Map generator operating on an [`Grid`] of [`Cell`] values.
 pub struct MapGen<R: Rng> {
     pub rng: R,
     pub grid: Grid,
+    symmetry: Symmetry,
+    border: Option<(Cell, i32)>,
+    clear_zones: Vec<(Point, i32)>,
 }
 
 impl<R: Rng> MapGen<R> {
     /// Create a new MapGen with the given grid.
     pub fn with_grid(grid: Grid, rng: R) -> Self {
-        Self { rng, grid }
+        Self {
+            rng,
+            grid,
+            symmetry: Symmetry::None,
+            border: None,
+            clear_zones: Vec::new(),
+        }
+    }
+
+    /// Constrain generated maps to the given symmetry (builder).
+    ///
+    /// Generators only draw random decisions for the fundamental half of
+    /// the grid and write each result to both a cell and its image, so
+    /// maps are symmetric cell-for-cell without post-mirroring.
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Force a solid border of `wall` cells, `thickness` cells wide, after
+    /// generation (builder).
+    pub fn with_border(mut self, wall: Cell, thickness: i32) -> Self {
+        self.border = Some((wall, thickness.max(0)));
+        self
+    }
+
+    /// Force floor discs of the given `(center, radius)` after generation,
+    /// carving tunnels to the main area when a zone ends up isolated
+    /// (builder).
+    ///
+    /// Zones are mirrored under the current symmetry, and never carve into
+    /// the border.
+    pub fn with_clear_zones(mut self, zones: &[(Point, i32)]) -> Self {
+        self.clear_zones = zones.to_vec();
+        self
+    }
+
+    /// The symmetry constraint in use.
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
     }
 
     /// Keep only the connected component reachable from `p`, filling
@@ -105,7 +246,10 @@ impl<R: Rng> MapGen<R> {
     /// Performs `walks` random walks starting from random positions.
     /// Each walk carves out cells by setting them to `cell`.
     /// The walk continues until the proportion of `cell` cells reaches
-    /// `fill_pct` (0.0–1.0) of the total area.
+    /// `fill_pct` (0.0–1.0) of the total area, or the whole interior if
+    /// that is less. Walks stay inside the configured border, if any, and
+    /// the other constraints are applied afterwards, with `cell` as the
+    /// floor.
     ///
     /// Returns the number of cells carved.
     pub fn random_walk_cave(
@@ -120,21 +264,28 @@ impl<R: Rng> MapGen<R> {
         let w = sz.x;
         let h = sz.y;
         let total = (w * h) as usize;
-        let target = (total as f64 * fill_pct) as usize;
+        // The border would wall over what is dug in it.
+        let interior = self.interior();
+        let target = ((total as f64 * fill_pct) as usize).min(interior.len());
         let already_dug = self.grid.count(cell);
-        let mut digs = already_dug;
+        let mut digs = interior
+            .iter()
+            .filter(|&p| self.grid.at(p) == Some(cell))
+            .count();
 
-        let remaining = target - already_dug;
+        let remaining = target.saturating_sub(digs);
         let wlk_max = remaining.checked_div(walks).unwrap_or(remaining);
 
         while digs < target {
             // Start each walk from a random position (matching Go).
-            let mut pos = Point::new(self.rng.random_range(0..w), self.rng.random_range(0..h));
+            let mut pos = Point::new(
+                self.rng.random_range(interior.min.x..interior.max.x),
+                self.rng.random_range(interior.min.y..interior.max.y),
+            );
             if self.grid.at(pos) == Some(cell) {
                 continue;
             }
-            self.grid.set(pos, cell);
-            digs += 1;
+            digs += self.carve(pos, cell);
             let mut wlk_digs = 1;
             let mut out_digs = 0;
             let mut last_in_range = pos;
@@ -143,18 +294,15 @@ impl<R: Rng> MapGen<R> {
                 let q = walker.neighbor(pos, &mut self.rng);
                 // If current pos is out of range but next is in range and
                 // not yet dug, snap back to last known good position.
-                if !self.grid.contains(pos)
-                    && self.grid.contains(q)
-                    && self.grid.at(q) != Some(cell)
+                if !interior.contains(pos) && interior.contains(q) && self.grid.at(q) != Some(cell)
                 {
                     pos = last_in_range;
                     continue;
                 }
                 pos = q;
-                if self.grid.contains(pos) {
+                if interior.contains(pos) {
                     if self.grid.at(pos) != Some(cell) {
-                        self.grid.set(pos, cell);
-                        digs += 1;
+                        digs += self.carve(pos, cell);
                         wlk_digs += 1;
                     }
                     last_in_range = pos;
@@ -168,7 +316,8 @@ impl<R: Rng> MapGen<R> {
             }
         }
 
-        digs - already_dug
+        self.apply_constraints(cell);
+        self.grid.count(cell).saturating_sub(already_dug)
     }
 
     /// Generate a cave using cellular automata.
@@ -176,6 +325,12 @@ impl<R: Rng> MapGen<R> {
     /// 1. Initialize each cell randomly: `wall_init_pct` chance of being `wall`,
     ///    otherwise `ground`.
     /// 2. Apply each rule in `rules` for its specified number of repetitions.
+    /// 3. Apply configured constraints (border, clear zones).
+    ///
+    /// With a [`Symmetry`], only fundamental cells are initialized at
+    /// random, and mirrored. The rules then update the whole map, reading
+    /// wall counts across the seam, so it stays symmetric without
+    /// artifacts.
     ///
     /// Returns the number of ground cells in the final grid.
     pub fn cellular_automata_cave(
@@ -193,91 +348,515 @@ impl<R: Rng> MapGen<R> {
         // Step 1: random initialization (using relative coords).
         for y in 0..h {
             for x in 0..w {
+                let p = Point::new(x, y);
+                if !self.is_fundamental(p) {
+                    continue;
+                }
                 let r: f64 = self.rng.random();
                 let c = if r < wall_init_pct { wall } else { ground };
-                self.grid.set(Point::new(x, y), c);
+                self.grid.set(p, c);
+                self.grid.set(self.mirror(p), c);
             }
         }
 
         // Step 2: apply rules.
-        let mut scratch = vec![Cell::default(); (w * h) as usize];
-
+        let mut temp = Grid::new(w, h);
         for rule in rules {
             let use_w1 = rule.w_cutoff1 > 0;
             let use_w2 = rule.w_cutoff2 < 25;
+            let oor = rule.walls_out_of_range;
+            self.grid
+                .apply_rule_with(&mut temp, rule.reps as u32, |p, _, src| {
+                    let is_wall = (use_w1 && count_walls(src, p, 1, wall, oor) >= rule.w_cutoff1)
+                        || (use_w2 && count_walls(src, p, 2, wall, oor) <= rule.w_cutoff2);
+                    if is_wall { wall } else { ground }
+                });
+        }
+
+        self.apply_constraints(ground);
+        self.grid.count(ground)
+    }
 
-            for _ in 0..rule.reps {
-                for y in 0..h {
-                    for x in 0..w {
-                        let p = Point::new(x, y);
-                        let idx = (y * w + x) as usize;
-
-                        let is_wall = match (use_w1, use_w2) {
-                            (true, true) => {
-                                let w1 = self.count_walls(p, 1, wall, rule.walls_out_of_range);
-                                let w2 = self.count_walls(p, 2, wall, rule.walls_out_of_range);
-                                w1 >= rule.w_cutoff1 || w2 <= rule.w_cutoff2
-                            }
-                            (true, false) => {
-                                let w1 = self.count_walls(p, 1, wall, rule.walls_out_of_range);
-                                w1 >= rule.w_cutoff1
-                            }
-                            (false, true) => {
-                                let w2 = self.count_walls(p, 2, wall, rule.walls_out_of_range);
-                                w2 <= rule.w_cutoff2
-                            }
-                            (false, false) => false,
-                        };
-
-                        scratch[idx] = if is_wall { wall } else { ground };
+    /// Carve a path from `from` to `to` with `cell`, along the least-cost
+    /// 4-connected route.
+    ///
+    /// `cost` gives the cost of entering each cell, `None` for cells the
+    /// path must not touch: neither the route nor its width or banks are
+    /// carved into them. Costs below 1 count as 1. Rivers can follow low
+    /// elevation this way, and roads prefer existing floor.
+    ///
+    /// Unlike the cave generators, carving ignores the [`Symmetry`]: carve
+    /// the mirrored path too for a symmetric map.
+    ///
+    /// Returns the centre line of the path, or `None`, carving nothing, if
+    /// `to` cannot be reached.
+    pub fn carve_path(
+        &mut self,
+        from: Point,
+        to: Point,
+        cell: Cell,
+        config: &CarveConfig,
+        cost: impl Fn(Point) -> Option<i32>,
+    ) -> Option<Vec<Point>> {
+        let path = self.least_cost_path(from, to, config.turn_penalty, &cost)?;
+        self.carve_along(&path, cell, config, &cost);
+        Some(path)
+    }
+
+    /// Carve a river with `cell` between random points on two opposite
+    /// edges of the map.
+    ///
+    /// The river meanders through waypoints moved randomly off the straight
+    /// line between its ends. If a waypoint cannot be reached it is
+    /// skipped, and if the chosen exit cannot be reached another point of
+    /// the far edge is used, so the river always runs edge to edge when
+    /// `cost` allows it at all.
+    ///
+    /// Returns the centre line, from the entry to the exit, or `None`,
+    /// carving nothing, if no two opposite edges are connected.
+    pub fn carve_river(
+        &mut self,
+        cell: Cell,
+        config: &RiverConfig,
+        cost: impl Fn(Point) -> Option<i32>,
+    ) -> Option<Vec<Point>> {
+        let size = self.grid.size();
+        if size.x <= 0 || size.y <= 0 {
+            return None;
+        }
+        // Work as if flowing along x, transposing for a vertical river.
+        let vertical = self.rng.random_bool(0.5);
+        let (len, span) = if vertical {
+            (size.y, size.x)
+        } else {
+            (size.x, size.y)
+        };
+        let at = |along: i32, across: i32| {
+            if vertical {
+                Point::new(across, along)
+            } else {
+                Point::new(along, across)
+            }
+        };
+        let entry_across = self.rng.random_range(0..span);
+        let exit_across = self.rng.random_range(0..span);
+        let mut waypoints = Vec::with_capacity(config.waypoints);
+        for k in 1..=config.waypoints {
+            let t = k as i32 * (len - 1) / (config.waypoints as i32 + 1);
+            let straight = entry_across + (exit_across - entry_across) * t / (len - 1).max(1);
+            let m = config.meander.max(0);
+            let across = (straight + self.rng.random_range(-m..=m)).clamp(0, span - 1);
+            waypoints.push(at(t, across));
+        }
+
+        // Label the passable cells by connected component, so that paths
+        // are only searched between cells known to be connected.
+        let rg = self.grid.range_();
+        let mut components = PathRange::new(rg);
+        components.cc_map_all(&CostPather { rg, cost: &cost });
+        let component = |p: Point| cost(p).and(components.cc_at(p));
+
+        // Entry: the first cell of the near edge that reaches the far one,
+        // starting from the random pick; exit: the first cell it reaches,
+        // starting from the other.
+        let (start, exit, river) = (0..span)
+            .map(|i| at(0, (entry_across + i) % span))
+            .find_map(|start| {
+                let c = component(start)?;
+                let exit = (0..span)
+                    .map(|i| at(len - 1, (exit_across + i) % span))
+                    .find(|&e| component(e) == Some(c))?;
+                Some((start, exit, c))
+            })?;
+        let turn = config.carve.turn_penalty;
+        let mut path = vec![start];
+        for &w in waypoints
+            .iter()
+            .filter(|&&w| component(w) == Some(river))
+            .chain([&exit])
+        {
+            let last = *path.last().expect("path starts with the entry");
+            let leg = self
+                .least_cost_path(last, w, turn, &cost)
+                .expect("waypoint connected to the entry");
+            path.extend_from_slice(&leg[1..]);
+        }
+        self.carve_along(&path, cell, &config.carve, &cost);
+        Some(path)
+    }
+
+    /// The least-cost 4-connected path from `from` to `to`.
+    fn least_cost_path(
+        &self,
+        from: Point,
+        to: Point,
+        turn_penalty: i32,
+        cost: &impl Fn(Point) -> Option<i32>,
+    ) -> Option<Vec<Point>> {
+        let rg = self.grid.range_();
+        if !rg.contains(from) || !rg.contains(to) || cost(from).is_none() || cost(to).is_none() {
+            return None;
+        }
+        if turn_penalty > 0 {
+            return turn_aware_path(rg, from, to, turn_penalty, cost);
+        }
+        PathRange::new(rg).astar_path(&CostPather { rg, cost }, from, to)
+    }
+
+    /// Carve `path` with its width, banks and bridges.
+    fn carve_along(
+        &self,
+        path: &[Point],
+        cell: Cell,
+        config: &CarveConfig,
+        cost: &impl Fn(Point) -> Option<i32>,
+    ) {
+        let width = config.width.max(1);
+        // Offsets of the brush around each centre cell, biased towards
+        // positive coordinates for even widths.
+        let lo = -(width - 1) / 2;
+        let hi = width / 2;
+        let brush = |p: Point, extra: i32| {
+            Range::new(
+                p.x + lo - extra,
+                p.y + lo - extra,
+                p.x + hi + extra + 1,
+                p.y + hi + extra + 1,
+            )
+            .intersect(self.grid.range_())
+        };
+        let passable = |q: Point| cost(q).is_some();
+        let is_floor = |q: Point| {
+            config
+                .bridge
+                .is_some_and(|(floor, _)| self.grid.at(q) == Some(floor))
+        };
+
+        let body: HashSet<Point> = path
+            .iter()
+            .flat_map(|&p| brush(p, 0))
+            .filter(|&q| passable(q))
+            .collect();
+        if let Some(bank) = config.bank {
+            let bw = config.bank_width.max(0);
+            for &p in path {
+                for q in brush(p, bw) {
+                    if passable(q) && !body.contains(&q) && !is_floor(q) {
+                        self.grid.set(q, bank);
                     }
                 }
+            }
+        }
+        for q in body {
+            match config.bridge {
+                Some((floor, bridge)) if self.grid.at(q) == Some(floor) => self.grid.set(q, bridge),
+                _ => self.grid.set(q, cell),
+            }
+        }
+    }
+
+    /// Image of `p` under the configured symmetry.
+    fn mirror(&self, p: Point) -> Point {
+        self.symmetry.apply(p, self.grid.size())
+    }
 
-                // Copy scratch back to grid.
-                for y in 0..h {
-                    for x in 0..w {
-                        let idx = (y * w + x) as usize;
-                        self.grid.set(Point::new(x, y), scratch[idx]);
+    /// Whether `p` belongs to the fundamental domain of the symmetry, that
+    /// is, it does not come after its image in row-major order.
+    fn is_fundamental(&self, p: Point) -> bool {
+        let m = self.mirror(p);
+        (p.y, p.x) <= (m.y, m.x)
+    }
+
+    /// Set `p` and its image to `cell`, returning how many cells changed.
+    fn carve(&self, p: Point, cell: Cell) -> usize {
+        let mut n = 0;
+        for q in [p, self.mirror(p)] {
+            if self.grid.contains(q) && self.grid.at(q) != Some(cell) {
+                self.grid.set(q, cell);
+                n += 1;
+            }
+        }
+        n
+    }
+
+    /// The grid range minus the configured border.
+    fn interior(&self) -> Range {
+        let rg = self.grid.range_();
+        match self.border {
+            Some((_, t)) => Range::new(t, t, rg.max.x - t, rg.max.y - t),
+            None => rg,
+        }
+    }
+
+    /// Apply border and clear-zone constraints, in that order.
+    fn apply_constraints(&self, ground: Cell) {
+        if let Some((wall, _)) = self.border {
+            let interior = self.interior();
+            for p in self.grid.range_() {
+                if !interior.contains(p) {
+                    self.grid.set(p, wall);
+                }
+            }
+        }
+        if self.clear_zones.is_empty() {
+            return;
+        }
+        let interior = self.interior();
+        let mut centers = Vec::new();
+        for &(c, radius) in &self.clear_zones {
+            for center in [c, self.mirror(c)] {
+                let rg = Range::new(
+                    center.x - radius,
+                    center.y - radius,
+                    center.x + radius + 1,
+                    center.y + radius + 1,
+                )
+                .intersect(interior);
+                for p in rg {
+                    let d = p - center;
+                    if d.x * d.x + d.y * d.y <= radius * radius {
+                        self.carve(p, ground);
                     }
                 }
+                if interior.contains(center) {
+                    centers.push(center);
+                }
             }
         }
+        self.connect_zones(&centers, ground);
+    }
 
-        self.grid.count(ground)
+    /// Carve L-shaped tunnels from isolated zone centers to the largest
+    /// connected floor area.
+    fn connect_zones(&self, centers: &[Point], ground: Cell) {
+        if centers.is_empty() {
+            return;
+        }
+        let rg = self.grid.range_();
+        let mut pr = PathRange::new(rg);
+        let pather = GroundPather {
+            grid: &self.grid,
+            ground,
+        };
+        pr.cc_map_all(&pather);
+
+        // Anchor the main area on a cell of the largest floor component.
+        let mut sizes = vec![0usize; rg.len()];
+        for p in rg {
+            if self.grid.at(p) == Some(ground) {
+                if let Some(id) = pr.cc_at(p) {
+                    sizes[id] += 1;
+                }
+            }
+        }
+        let Some(main) = sizes
+            .iter()
+            .enumerate()
+            .max_by_key(|&(id, &n)| (n, std::cmp::Reverse(id)))
+            .map(|(id, _)| id)
+        else {
+            return;
+        };
+        let Some(anchor) = rg
+            .iter()
+            .find(|&p| pr.cc_at(p) == Some(main) && self.grid.at(p) == Some(ground))
+        else {
+            return;
+        };
+
+        // Each tunnel connects at least one more center to the main area.
+        for _ in 0..centers.len() * 2 {
+            let target = pr.cc_at(anchor);
+            let Some(&from) = centers.iter().find(|&&c| pr.cc_at(c) != target) else {
+                break;
+            };
+            let Some(to) = rg
+                .iter()
+                .filter(|&p| pr.cc_at(p) == target && self.grid.at(p) == Some(ground))
+                .min_by_key(|&p| gruid_paths::manhattan(p, from))
+            else {
+                break;
+            };
+            self.carve_tunnel(from, to, ground);
+            pr.cc_map_all(&pather);
+        }
     }
 
-    /// Count wall cells within Chebyshev distance `radius` of `center`.
-    /// Matches Go: includes the center cell itself in the count.
-    fn count_walls(&self, center: Point, radius: i32, wall: Cell, walls_out_of_range: bool) -> i32 {
-        let mut count = 0;
-        let rg = gruid_core::Range::new(
-            center.x - radius,
-            center.y - radius,
-            center.x + radius + 1,
-            center.y + radius + 1,
-        );
-        let grid_rg = self.grid.range_();
-
-        if walls_out_of_range {
-            let orig_size = rg.size();
-            let clamped = rg.intersect(grid_rg);
-            let clamped_size = clamped.size();
-            // Out-of-range cells count as walls.
-            count += orig_size.x * orig_size.y - clamped_size.x * clamped_size.y;
-        }
-
-        // Count in-range walls using a slice of the grid.
+    /// Carve a horizontal-then-vertical tunnel from `from` to `to`.
+    fn carve_tunnel(&self, from: Point, to: Point, ground: Cell) {
+        let mut p = from;
+        self.carve(p, ground);
+        while p.x != to.x {
+            p.x += (to.x - p.x).signum();
+            self.carve(p, ground);
+        }
+        while p.y != to.y {
+            p.y += (to.y - p.y).signum();
+            self.carve(p, ground);
+        }
+    }
+}
+
+/// Count wall cells within Chebyshev distance `radius` of `center`.
+/// Matches Go: includes the center cell itself in the count.
+fn count_walls(
+    grid: &Grid,
+    center: Point,
+    radius: i32,
+    wall: Cell,
+    walls_out_of_range: bool,
+) -> i32 {
+    let mut count = 0;
+    let rg = gruid_core::Range::new(
+        center.x - radius,
+        center.y - radius,
+        center.x + radius + 1,
+        center.y + radius + 1,
+    );
+    let grid_rg = grid.range_();
+
+    if walls_out_of_range {
+        let orig_size = rg.size();
         let clamped = rg.intersect(grid_rg);
-        let sub = self.grid.slice(clamped);
-        count += sub.count(wall) as i32;
-        count
+        let clamped_size = clamped.size();
+        // Out-of-range cells count as walls.
+        count += orig_size.x * orig_size.y - clamped_size.x * clamped_size.y;
+    }
+
+    // Count in-range walls using a slice of the grid.
+    let clamped = rg.intersect(grid_rg);
+    let sub = grid.slice(clamped);
+    count += sub.count(wall) as i32;
+    count
+}
+
+/// 4-directional pather over cells equal to `ground`.
+struct GroundPather<'a> {
+    grid: &'a Grid,
+    ground: Cell,
+}
+
+/// 4-directional pather over the cells a cost closure allows.
+struct CostPather<'a, F> {
+    rg: Range,
+    cost: &'a F,
+}
+
+impl<F: Fn(Point) -> Option<i32>> Pather for CostPather<'_, F> {
+    fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+        // No neighbours for impassable cells, so that connected components
+        // do not join through them.
+        if (self.cost)(p).is_none() {
+            return;
+        }
+        for q in p.neighbors_4() {
+            if self.rg.contains(q) && (self.cost)(q).is_some() {
+                buf.push(q);
+            }
+        }
+    }
+}
+
+impl<F: Fn(Point) -> Option<i32>> WeightedPather for CostPather<'_, F> {
+    fn cost(&self, _from: Point, to: Point) -> i32 {
+        (self.cost)(to).unwrap_or(1).max(1)
+    }
+}
+
+impl<F: Fn(Point) -> Option<i32>> AstarPather for CostPather<'_, F> {
+    fn estimate(&self, from: Point, to: Point) -> i32 {
+        manhattan(from, to)
+    }
+}
+
+/// A* over (cell, direction of arrival) states, charging `turn_penalty`
+/// for each change of direction.
+fn turn_aware_path(
+    rg: Range,
+    from: Point,
+    to: Point,
+    turn_penalty: i32,
+    cost: &impl Fn(Point) -> Option<i32>,
+) -> Option<Vec<Point>> {
+    const DIRS: [Point; 4] = [
+        Point::new(1, 0),
+        Point::new(-1, 0),
+        Point::new(0, 1),
+        Point::new(0, -1),
+    ];
+    // Direction 4 is the start, which has none.
+    let states = |i: usize| i * 5;
+    let idx = |p: Point| ((p.y - rg.min.y) * rg.width() + p.x - rg.min.x) as usize;
+    let point = |i: usize| {
+        let i = i as i32;
+        Point::new(rg.min.x + i % rg.width(), rg.min.y + i / rg.width())
+    };
+    let n = rg.len() * 5;
+    let mut dist = vec![i32::MAX; n];
+    let mut parent = vec![usize::MAX; n];
+    let mut open = BinaryHeap::new();
+    let start = states(idx(from)) + 4;
+    dist[start] = 0;
+    open.push(Reverse((manhattan(from, to), 0, start)));
+    while let Some(Reverse((_, g, s))) = open.pop() {
+        if g > dist[s] {
+            continue;
+        }
+        let (ci, dir) = (s / 5, s % 5);
+        let p = point(ci);
+        if p == to {
+            let mut path = vec![p];
+            let mut s = s;
+            while parent[s] != usize::MAX {
+                s = parent[s];
+                path.push(point(s / 5));
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for (d, &step) in DIRS.iter().enumerate() {
+            let q = p + step;
+            if !rg.contains(q) {
+                continue;
+            }
+            let Some(c) = cost(q) else {
+                continue;
+            };
+            let turn = if dir != 4 && dir != d {
+                turn_penalty
+            } else {
+                0
+            };
+            let ng = g + c.max(1) + turn;
+            let ns = states(idx(q)) + d;
+            if ng < dist[ns] {
+                dist[ns] = ng;
+                parent[ns] = s;
+                open.push(Reverse((ng + manhattan(q, to), ng, ns)));
+            }
+        }
+    }
+    None
+}
+
+impl Pather for GroundPather<'_> {
+    fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+        if self.grid.at(p) != Some(self.ground) {
+            return;
+        }
+        for q in p.neighbors_4() {
+            if self.grid.at(q) == Some(self.ground) {
+                buf.push(q);
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use gruid_paths::Pather;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
 
     /// Simple pather for testing: treats Cell(0) as passable with 4-directional movement.
     struct FloorPather<'a> {
@@ -328,11 +907,51 @@ mod tests {
         // should count all 9 cells (including center).
         let grid = Grid::new(3, 3);
         grid.fill(Cell(1));
-        let mg = MapGen::with_grid(grid, rand::rng());
-        let count = mg.count_walls(Point::new(1, 1), 1, Cell(1), false);
+        let count = count_walls(&grid, Point::new(1, 1), 1, Cell(1), false);
         assert_eq!(count, 9); // 3x3 = 9 including center
     }
 
+    #[test]
+    fn test_cellular_automata_unchanged_per_seed() {
+        // Grids generated before the rules used Grid::apply_rule_with.
+        let fingerprint = |g: &Grid| {
+            g.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, (_, c)| {
+                (h ^ c.0 as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+        };
+        let rules = [
+            CellularAutomataRule::default(),
+            CellularAutomataRule {
+                w_cutoff1: 5,
+                w_cutoff2: 25,
+                walls_out_of_range: false,
+                reps: 3,
+            },
+            CellularAutomataRule {
+                w_cutoff1: 0,
+                w_cutoff2: 3,
+                walls_out_of_range: true,
+                reps: 1,
+            },
+        ];
+        let want = [
+            (1, Symmetry::None, 685, 0x8165_4f7f_53a6_d098),
+            (2, Symmetry::MirrorX, 622, 0xd0e2_fa4a_1027_3c13),
+            (3, Symmetry::MirrorY, 542, 0x787d_230e_936f_2b87),
+            (4, Symmetry::Rotational180, 720, 0x1135_6034_d1bb_ec95),
+        ];
+        for (seed, sym, ground, fp) in want {
+            let mut mg = MapGen::with_grid(Grid::new(40, 25), StdRng::seed_from_u64(seed))
+                .with_symmetry(sym)
+                .with_border(Cell(1), 1);
+            assert_eq!(
+                mg.cellular_automata_cave(Cell(1), Cell(0), 0.45, &rules),
+                ground
+            );
+            assert_eq!(fingerprint(&mg.grid), fp, "seed {seed}");
+        }
+    }
+
     #[test]
     fn test_keep_connected() {
         // 5x5 grid with two disconnected floor regions:
@@ -371,4 +990,291 @@ mod tests {
         assert_eq!(mg.grid.at(Point::new(0, 0)), Some(Cell(0)));
         assert_eq!(mg.grid.at(Point::new(1, 1)), Some(Cell(0)));
     }
+
+    fn arena(symmetry: Symmetry, seed: u64) -> MapGen<StdRng> {
+        let grid = Grid::new(31, 20);
+        grid.fill(Cell(1));
+        MapGen::with_grid(grid, StdRng::seed_from_u64(seed))
+            .with_symmetry(symmetry)
+            .with_border(Cell(1), 2)
+            .with_clear_zones(&[(Point::new(5, 5), 2)])
+    }
+
+    fn assert_symmetric(mg: &MapGen<StdRng>) {
+        let size = mg.grid.size();
+        for p in mg.grid.range_() {
+            let q = mg.symmetry().apply(p, size);
+            assert_eq!(mg.grid.at(p), mg.grid.at(q), "{p} vs {q}");
+        }
+    }
+
+    #[test]
+    fn test_symmetry_apply() {
+        let size = Point::new(10, 6);
+        let p = Point::new(2, 1);
+        assert_eq!(Symmetry::None.apply(p, size), p);
+        assert_eq!(Symmetry::MirrorX.apply(p, size), Point::new(7, 1));
+        assert_eq!(Symmetry::MirrorY.apply(p, size), Point::new(2, 4));
+        assert_eq!(Symmetry::Rotational180.apply(p, size), Point::new(7, 4));
+    }
+
+    #[test]
+    fn test_cellular_automata_symmetric() {
+        let rules = vec![CellularAutomataRule::default()];
+        for sym in [
+            Symmetry::MirrorX,
+            Symmetry::MirrorY,
+            Symmetry::Rotational180,
+        ] {
+            let mut mg = arena(sym, 7);
+            mg.cellular_automata_cave(Cell(1), Cell(0), 0.45, &rules);
+            assert_symmetric(&mg);
+        }
+    }
+
+    #[test]
+    fn test_random_walk_symmetric() {
+        for sym in [
+            Symmetry::MirrorX,
+            Symmetry::MirrorY,
+            Symmetry::Rotational180,
+        ] {
+            let mut mg = arena(sym, 11);
+            mg.random_walk_cave(&FourDirectionWalker, Cell(0), 0.4, 10);
+            assert_symmetric(&mg);
+        }
+    }
+
+    #[test]
+    fn test_random_walk_fills_inside_border() {
+        for seed in 0..4 {
+            let mut mg = arena(Symmetry::None, seed).with_clear_zones(&[]);
+            mg.random_walk_cave(&FourDirectionWalker, Cell(0), 0.4, 10);
+            let target = (31.0 * 20.0 * 0.4) as usize;
+            assert!(mg.grid.count(Cell(0)) >= target, "seed {seed}");
+        }
+        // More than fits inside the border: all of the interior.
+        let mut mg = arena(Symmetry::None, 0).with_clear_zones(&[]);
+        mg.random_walk_cave(&FourDirectionWalker, Cell(0), 0.9, 10);
+        assert_eq!(mg.grid.count(Cell(0)), 27 * 16);
+    }
+
+    #[test]
+    fn test_border_is_solid() {
+        let mut mg = arena(Symmetry::Rotational180, 3);
+        mg.cellular_automata_cave(Cell(1), Cell(0), 0.45, &[CellularAutomataRule::default()]);
+        let interior = Range::new(2, 2, 29, 18);
+        for p in mg.grid.range_() {
+            if !interior.contains(p) {
+                assert_eq!(mg.grid.at(p), Some(Cell(1)), "{p}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_clear_zones_open_and_connected() {
+        for seed in 0..8 {
+            let mut mg = arena(Symmetry::Rotational180, seed);
+            mg.cellular_automata_cave(Cell(1), Cell(0), 0.6, &[CellularAutomataRule::default()]);
+            let a = Point::new(5, 5);
+            let b = mg.symmetry().apply(a, mg.grid.size());
+            for center in [a, b] {
+                for p in mg.grid.range_() {
+                    let d = p - center;
+                    if d.x * d.x + d.y * d.y <= 4 {
+                        assert_eq!(mg.grid.at(p), Some(Cell(0)), "seed {seed}: {p}");
+                    }
+                }
+            }
+            let mut pr = PathRange::new(mg.grid.range_());
+            pr.cc_map_all(&FloorPather { grid: &mg.grid });
+            assert_eq!(pr.cc_at(a), pr.cc_at(b), "seed {seed}");
+            // And both are part of the main floor area.
+            let mut sizes = std::collections::HashMap::new();
+            for p in mg.grid.range_() {
+                if mg.grid.at(p) == Some(Cell(0)) {
+                    *sizes.entry(pr.cc_at(p)).or_insert(0) += 1;
+                }
+            }
+            let largest = sizes.iter().max_by_key(|&(_, &n)| n).map(|(&id, _)| id);
+            assert_eq!(pr.cc_at(a), largest.flatten(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_constraints_deterministic_per_seed() {
+        let rules = vec![CellularAutomataRule::default()];
+        let mut a = arena(Symmetry::MirrorX, 42);
+        let mut b = arena(Symmetry::MirrorX, 42);
+        a.cellular_automata_cave(Cell(1), Cell(0), 0.45, &rules);
+        b.cellular_automata_cave(Cell(1), Cell(0), 0.45, &rules);
+        assert!(a.grid.iter().eq(b.grid.iter()));
+    }
+
+    const WALL: Cell = Cell(1);
+    const FLOOR: Cell = Cell(0);
+    const WATER: Cell = Cell(2);
+    const BRIDGE: Cell = Cell(3);
+    const BANK: Cell = Cell(4);
+    const ROCK: Cell = Cell(5);
+
+    fn walled(w: i32, h: i32, seed: u64) -> MapGen<StdRng> {
+        let grid = Grid::new(w, h);
+        grid.fill(WALL);
+        MapGen::with_grid(grid, StdRng::seed_from_u64(seed))
+    }
+
+    fn snapshot(grid: &Grid) -> Grid {
+        let copy = Grid::new(grid.width(), grid.height());
+        copy.copy_from(grid);
+        copy
+    }
+
+    fn assert_continuous(path: &[Point]) {
+        for w in path.windows(2) {
+            assert_eq!(
+                manhattan(w[0], w[1]),
+                1,
+                "gap between {:?} and {:?}",
+                w[0],
+                w[1]
+            );
+        }
+    }
+
+    #[test]
+    fn test_river_runs_edge_to_edge() {
+        for seed in 0..10 {
+            let mut mg = walled(40, 20, seed);
+            let path = mg
+                .carve_river(WATER, &RiverConfig::default(), |_| Some(1))
+                .unwrap();
+            assert_continuous(&path);
+            let (a, b) = (path[0], *path.last().unwrap());
+            let across_x = a.x == 0 && b.x == 39;
+            let across_y = a.y == 0 && b.y == 19;
+            assert!(across_x || across_y, "seed {seed}: {a:?} to {b:?}");
+            assert!(path.iter().all(|&p| mg.grid.at(p) == Some(WATER)));
+        }
+    }
+
+    #[test]
+    fn test_river_deterministic_per_seed() {
+        let mut a = walled(30, 30, 7);
+        let mut b = walled(30, 30, 7);
+        let cost = |p: Point| Some(1 + (p.x * 7 + p.y * 3) % 5);
+        let pa = a.carve_river(WATER, &RiverConfig::default(), cost);
+        let pb = b.carve_river(WATER, &RiverConfig::default(), cost);
+        assert_eq!(pa, pb);
+        assert!(a.grid.iter().eq(b.grid.iter()));
+    }
+
+    #[test]
+    fn test_carve_path_width_and_banks() {
+        let mut mg = walled(20, 11, 0);
+        let config = CarveConfig {
+            width: 3,
+            bank: Some(BANK),
+            ..CarveConfig::default()
+        };
+        let path = mg
+            .carve_path(Point::new(0, 5), Point::new(19, 5), WATER, &config, |_| {
+                Some(1)
+            })
+            .unwrap();
+        assert_eq!(path.len(), 20);
+        for x in 0..20 {
+            let column: Vec<_> = (2..9)
+                .map(|y| mg.grid.at(Point::new(x, y)).unwrap())
+                .collect();
+            assert_eq!(
+                column,
+                [WALL, BANK, WATER, WATER, WATER, BANK, WALL],
+                "x = {x}"
+            );
+        }
+        assert_eq!(mg.grid.count(WATER), 60);
+    }
+
+    #[test]
+    fn test_bridge_over_corridor() {
+        let mut mg = walled(30, 11, 0);
+        for y in 0..11 {
+            mg.grid.set(Point::new(15, y), FLOOR);
+        }
+        let config = CarveConfig {
+            bank: Some(BANK),
+            bridge: Some((FLOOR, BRIDGE)),
+            ..CarveConfig::default()
+        };
+        mg.carve_path(Point::new(0, 5), Point::new(29, 5), WATER, &config, |_| {
+            Some(1)
+        })
+        .unwrap();
+        assert_eq!(mg.grid.at(Point::new(15, 5)), Some(BRIDGE));
+        assert_eq!(mg.grid.count(BRIDGE), 1);
+        assert_eq!(mg.grid.at(Point::new(14, 5)), Some(WATER));
+        // The corridor is left intact on both sides of the bridge.
+        assert_eq!(mg.grid.at(Point::new(15, 4)), Some(FLOOR));
+        assert_eq!(mg.grid.at(Point::new(15, 6)), Some(FLOOR));
+        assert_eq!(mg.grid.at(Point::new(14, 4)), Some(BANK));
+    }
+
+    #[test]
+    fn test_road_avoids_impassable() {
+        let mut mg = walled(30, 20, 3);
+        // A rock wall with a single gap.
+        for y in 0..20 {
+            if y != 17 {
+                mg.grid.set(Point::new(15, y), ROCK);
+            }
+        }
+        let before = snapshot(&mg.grid);
+        let cost = |p: Point| (before.at(p) != Some(ROCK)).then_some(1);
+        let config = CarveConfig {
+            width: 2,
+            bank: Some(BANK),
+            ..CarveConfig::default()
+        };
+        let path = mg
+            .carve_path(Point::new(0, 2), Point::new(29, 2), FLOOR, &config, cost)
+            .unwrap();
+        assert_continuous(&path);
+        assert!(path.contains(&Point::new(15, 17)));
+        for p in before.range_() {
+            if before.at(p) == Some(ROCK) {
+                assert_eq!(mg.grid.at(p), Some(ROCK), "{p:?}");
+            }
+        }
+
+        // Fully blocked: nothing is carved.
+        mg.grid.set(Point::new(15, 17), ROCK);
+        let frozen = snapshot(&mg.grid);
+        let cost = |p: Point| (frozen.at(p) != Some(ROCK)).then_some(1);
+        let blocked = mg.carve_path(Point::new(0, 2), Point::new(29, 2), FLOOR, &config, cost);
+        assert_eq!(blocked, None);
+        assert!(mg.grid.iter().eq(frozen.iter()));
+    }
+
+    #[test]
+    fn test_turn_penalty_straightens_path() {
+        let turns = |path: &[Point]| {
+            path.windows(3)
+                .filter(|w| w[1] - w[0] != w[2] - w[1])
+                .count()
+        };
+        let mut mg = walled(20, 20, 0);
+        let config = CarveConfig {
+            turn_penalty: 5,
+            ..CarveConfig::default()
+        };
+        let path = mg
+            .carve_path(Point::new(0, 0), Point::new(19, 19), FLOOR, &config, |_| {
+                Some(1)
+            })
+            .unwrap();
+        assert_continuous(&path);
+        assert_eq!(path.len(), 39);
+        assert_eq!(turns(&path), 1);
+    }
 }
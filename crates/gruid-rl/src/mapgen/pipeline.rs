@@ -0,0 +1,392 @@
+//! Map generation pipelines: named stages run in order on a [`MapGen`].
+//!
+//! Each stage gets its own random number generator, seeded from the
+//! pipeline seed and the stage name alone, so adding, removing or
+//! reordering stages leaves the randomness of the others untouched and
+//! maps stay comparable across versions.
+//!
+//! A stage registered with [`Pipeline::try_stage`] can fail, for example
+//! when a connectivity check does not pass. The [`Retry`] policy then runs
+//! the stage again, or the whole pipeline, up to a bounded number of
+//! attempts. In debug mode, a copy of the grid is kept after each stage
+//! for inspection.
+//!
+//! ```
+//! use gruid_rl::grid::{Cell, Grid};
+//! use gruid_rl::mapgen::pipeline::{Pipeline, Retry};
+//! use gruid_rl::mapgen::{CellularAutomataRule, MapGen};
+//! use rand::SeedableRng;
+//! use rand::rngs::StdRng;
+//!
+//! let (wall, ground) = (Cell(1), Cell(0));
+//! let mut pipeline = Pipeline::new(42).with_retry(Retry::Restart, 5).with_debug(true);
+//! pipeline
+//!     .stage("caves", move |mg| {
+//!         mg.cellular_automata_cave(wall, ground, 0.45, &[CellularAutomataRule::default()]);
+//!     })
+//!     .try_stage("check", move |mg| {
+//!         if mg.grid.count(ground) < 100 {
+//!             return Err("too few floor cells".into());
+//!         }
+//!         Ok(())
+//!     });
+//!
+//! let mut mg = MapGen::with_grid(Grid::new(40, 20), StdRng::seed_from_u64(0));
+//! pipeline.run(&mut mg).unwrap();
+//! assert_eq!(pipeline.snapshots().len(), 2);
+//! ```
+
+use std::fmt;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use super::MapGen;
+use crate::grid::Grid;
+
+/// What a stage does: `Err` holds why it failed.
+pub type StageFn = Box<dyn FnMut(&mut MapGen<StdRng>) -> Result<(), String>>;
+
+/// What to run again when a stage fails.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Retry {
+    /// Run the failed stage again, on the grid it started from.
+    #[default]
+    Stage,
+    /// Run the whole pipeline again, on the grid it started from.
+    Restart,
+}
+
+/// A stage that still failed once its attempts ran out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageError {
+    /// Name of the stage.
+    pub stage: String,
+    /// Why it failed the last time.
+    pub message: String,
+    /// Attempts made: of the stage with [`Retry::Stage`], of the pipeline
+    /// with [`Retry::Restart`].
+    pub attempts: usize,
+}
+
+impl fmt::Display for StageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mapgen: stage \"{}\" failed after {} attempt(s): {}",
+            self.stage, self.attempts, self.message
+        )
+    }
+}
+
+impl std::error::Error for StageError {}
+
+struct Stage {
+    name: String,
+    run: StageFn,
+}
+
+/// Named map generation stages, run in order with independent seeds.
+pub struct Pipeline {
+    seed: u64,
+    stages: Vec<Stage>,
+    retry: Retry,
+    max_attempts: usize,
+    debug: bool,
+    snapshots: Vec<(String, Grid)>,
+    retries: usize,
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("seed", &self.seed)
+            .field(
+                "stages",
+                &self.stages.iter().map(|s| &s.name).collect::<Vec<_>>(),
+            )
+            .field("retry", &self.retry)
+            .field("max_attempts", &self.max_attempts)
+            .field("debug", &self.debug)
+            .finish()
+    }
+}
+
+impl Pipeline {
+    /// Create an empty pipeline with the given master seed. Failed stages
+    /// are not retried.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            stages: Vec::new(),
+            retry: Retry::Stage,
+            max_attempts: 1,
+            debug: false,
+            snapshots: Vec::new(),
+            retries: 0,
+        }
+    }
+
+    /// Retry failed stages as `retry` says, making at most `max_attempts`
+    /// attempts in all (builder).
+    pub fn with_retry(mut self, retry: Retry, max_attempts: usize) -> Self {
+        self.retry = retry;
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Keep a copy of the grid after each stage (builder).
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Add a stage that cannot fail.
+    pub fn stage(
+        &mut self,
+        name: &str,
+        mut f: impl FnMut(&mut MapGen<StdRng>) + 'static,
+    ) -> &mut Self {
+        self.try_stage(name, move |mg| {
+            f(mg);
+            Ok(())
+        })
+    }
+
+    /// Add a stage that can fail, triggering the [`Retry`] policy.
+    pub fn try_stage(
+        &mut self,
+        name: &str,
+        f: impl FnMut(&mut MapGen<StdRng>) -> Result<(), String> + 'static,
+    ) -> &mut Self {
+        self.stages.push(Stage {
+            name: name.to_string(),
+            run: Box::new(f),
+        });
+        self
+    }
+
+    /// Names of the stages, in order.
+    pub fn stages(&self) -> impl Iterator<Item = &str> {
+        self.stages.iter().map(|s| s.name.as_str())
+    }
+
+    /// Run the stages in order on `mg`, replacing its random number
+    /// generator before each one.
+    ///
+    /// Attempt `n` (from 0) of a stage is seeded from the master seed, the
+    /// stage name and `n`, so a retry draws a different map. On failure,
+    /// the grid is left as the last attempt made it.
+    pub fn run(&mut self, mg: &mut MapGen<StdRng>) -> Result<(), StageError> {
+        self.snapshots.clear();
+        self.retries = 0;
+        let initial = copy(&mg.grid);
+        let mut restarts = 0;
+        'run: loop {
+            for stage in &mut self.stages {
+                let before =
+                    (self.retry == Retry::Stage && self.max_attempts > 1).then(|| copy(&mg.grid));
+                let mut attempt = 0;
+                while let Err(message) = {
+                    mg.rng = StdRng::seed_from_u64(stage_seed(
+                        self.seed,
+                        &stage.name,
+                        restarts + attempt,
+                    ));
+                    (stage.run)(mg)
+                } {
+                    let attempts = match self.retry {
+                        Retry::Stage => attempt + 1,
+                        Retry::Restart => restarts + 1,
+                    };
+                    if attempts >= self.max_attempts {
+                        return Err(StageError {
+                            stage: stage.name.clone(),
+                            message,
+                            attempts,
+                        });
+                    }
+                    self.retries += 1;
+                    match self.retry {
+                        Retry::Stage => {
+                            if let Some(grid) = &before {
+                                mg.grid.copy_from(grid);
+                            }
+                            attempt += 1;
+                        }
+                        Retry::Restart => {
+                            mg.grid.copy_from(&initial);
+                            self.snapshots.clear();
+                            restarts += 1;
+                            continue 'run;
+                        }
+                    }
+                }
+                if self.debug {
+                    self.snapshots.push((stage.name.clone(), copy(&mg.grid)));
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    /// The grid after each stage of the last successful [`run`](Self::run)
+    /// in debug mode, with the stage name.
+    pub fn snapshots(&self) -> &[(String, Grid)] {
+        &self.snapshots
+    }
+
+    /// Attempts that failed and were retried in the last run.
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+}
+
+/// A copy of `grid` that shares nothing with it.
+fn copy(grid: &Grid) -> Grid {
+    let out = Grid::new(grid.width(), grid.height());
+    out.copy_from(grid);
+    out
+}
+
+/// Seed of attempt `attempt` of stage `name`. Stable across platforms and
+/// releases: FNV-1a over the name, mixed with splitmix64.
+fn stage_seed(seed: u64, name: &str, attempt: usize) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in name.bytes() {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    let mut z = seed ^ h ^ (attempt as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Cell;
+    use crate::mapgen::CellularAutomataRule;
+    use gruid_core::Point;
+    use rand::RngExt;
+    use std::cell::Cell as StdCell;
+    use std::rc::Rc;
+
+    fn mapgen() -> MapGen<StdRng> {
+        MapGen::with_grid(Grid::new(30, 20), StdRng::seed_from_u64(0))
+    }
+
+    fn caves(mg: &mut MapGen<StdRng>) {
+        mg.cellular_automata_cave(Cell(1), Cell(0), 0.45, &[CellularAutomataRule::default()]);
+    }
+
+    /// Scatters a few `Cell(2)`.
+    fn scatter(mg: &mut MapGen<StdRng>) {
+        for _ in 0..20 {
+            let p = Point::new(mg.rng.random_range(0..30), mg.rng.random_range(0..20));
+            mg.grid.set(p, Cell(2));
+        }
+    }
+
+    fn cells(grid: &Grid) -> Vec<Cell> {
+        grid.iter().map(|(_, c)| c).collect()
+    }
+
+    #[test]
+    fn stage_seeds_ignore_other_stages() {
+        let mut a = Pipeline::new(9);
+        a.stage("caves", caves).stage("scatter", scatter);
+        let mut b = Pipeline::new(9);
+        b.stage("caves", caves)
+            .stage("noise", |mg| {
+                let _: u64 = mg.rng.random();
+            })
+            .stage("scatter", scatter);
+        let (mut ma, mut mb) = (mapgen(), mapgen());
+        a.run(&mut ma).unwrap();
+        b.run(&mut mb).unwrap();
+        assert_eq!(cells(&ma.grid), cells(&mb.grid));
+
+        // Another seed gives another map.
+        let mut c = Pipeline::new(10);
+        c.stage("caves", caves).stage("scatter", scatter);
+        let mut mc = mapgen();
+        c.run(&mut mc).unwrap();
+        assert_ne!(cells(&ma.grid), cells(&mc.grid));
+    }
+
+    /// A pipeline whose "first" stage marks the grid and "flaky" stage
+    /// fails `fails` times, with run counts of each.
+    fn flaky(
+        retry: Retry,
+        max_attempts: usize,
+        fails: usize,
+    ) -> (Pipeline, [Rc<StdCell<usize>>; 2]) {
+        let runs: [Rc<StdCell<usize>>; 2] = Default::default();
+        let mut p = Pipeline::new(1).with_retry(retry, max_attempts);
+        let first = Rc::clone(&runs[0]);
+        let second = Rc::clone(&runs[1]);
+        p.stage("first", move |mg| {
+            first.set(first.get() + 1);
+            mg.grid.set(Point::new(0, 0), Cell(1));
+        })
+        .try_stage("flaky", move |mg| {
+            second.set(second.get() + 1);
+            // Each attempt starts from the grid the stage started from.
+            assert_eq!(mg.grid.at(Point::new(1, 0)), Some(Cell(0)));
+            mg.grid.set(Point::new(1, 0), Cell(1));
+            if second.get() <= fails {
+                return Err(format!("attempt {}", second.get()));
+            }
+            Ok(())
+        });
+        (p, runs)
+    }
+
+    #[test]
+    fn retries_are_counted() {
+        let (mut p, runs) = flaky(Retry::Stage, 3, 2);
+        p.run(&mut mapgen()).unwrap();
+        assert_eq!(p.retries(), 2);
+        assert_eq!((runs[0].get(), runs[1].get()), (1, 3));
+
+        let (mut p, runs) = flaky(Retry::Restart, 3, 2);
+        p.run(&mut mapgen()).unwrap();
+        assert_eq!(p.retries(), 2);
+        assert_eq!((runs[0].get(), runs[1].get()), (3, 3));
+
+        let (mut p, runs) = flaky(Retry::Stage, 2, 5);
+        let err = p.run(&mut mapgen()).unwrap_err();
+        assert_eq!(
+            err,
+            StageError {
+                stage: "flaky".into(),
+                message: "attempt 2".into(),
+                attempts: 2,
+            }
+        );
+        assert_eq!(p.retries(), 1);
+        assert_eq!(runs[1].get(), 2);
+    }
+
+    #[test]
+    fn debug_keeps_snapshots() {
+        let mut p = Pipeline::new(3).with_debug(true);
+        p.stage("caves", caves)
+            .stage("fill", |mg| mg.grid.fill(Cell(2)));
+        let mut mg = mapgen();
+        p.run(&mut mg).unwrap();
+        let names: Vec<_> = p.snapshots().iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["caves", "fill"]);
+        // Copies, not views of the generator's grid.
+        let caves = &p.snapshots()[0].1;
+        assert!(caves.count(Cell(0)) > 0 && caves.count(Cell(2)) == 0);
+        assert_eq!(cells(&p.snapshots()[1].1), cells(&mg.grid));
+
+        let mut quiet = Pipeline::new(3);
+        quiet.stage("caves", |_| {});
+        quiet.run(&mut mapgen()).unwrap();
+        assert!(quiet.snapshots().is_empty());
+    }
+}
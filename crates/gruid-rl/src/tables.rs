@@ -0,0 +1,341 @@
+//! Weighted random tables with depth scaling.
+//!
+//! A [`WeightedTable`] maps items to weights, optionally varying with dungeon
+//! depth, and picks items with a caller-provided RNG so results are
+//! reproducible from a seed.
+//!
+//! ```
+//! use gruid_rl::tables::WeightedTable;
+//! use rand::SeedableRng;
+//! use rand::rngs::StdRng;
+//!
+//! let table = WeightedTable::builder()
+//!     .add("rat", 40)
+//!     .add("goblin", 30)
+//!     .add_scaled("wolf", |depth| (depth * 5).max(0) as u32)
+//!     .build();
+//! let mut rng = StdRng::seed_from_u64(7);
+//! assert!(table.pick_for(3, &mut rng).is_some());
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use rand::{Rng, RngExt};
+
+/// The weight of a table entry, possibly depending on depth.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weight {
+    /// The same weight at every depth.
+    Fixed(u32),
+    /// `base + per_depth * depth`, clamped at zero, and zero outside
+    /// `min_depth..=max_depth`.
+    Linear {
+        base: i32,
+        per_depth: i32,
+        min_depth: i32,
+        max_depth: i32,
+    },
+    /// An arbitrary function of depth. Not serializable.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn Fn(i32) -> u32 + Send + Sync>),
+}
+
+impl Weight {
+    /// The effective weight at `depth`.
+    pub fn at(&self, depth: i32) -> u32 {
+        match self {
+            Self::Fixed(w) => *w,
+            Self::Linear {
+                base,
+                per_depth,
+                min_depth,
+                max_depth,
+            } => {
+                if depth < *min_depth || depth > *max_depth {
+                    return 0;
+                }
+                base.saturating_add(per_depth.saturating_mul(depth)).max(0) as u32
+            }
+            Self::Custom(f) => f(depth),
+        }
+    }
+}
+
+impl fmt::Debug for Weight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed(w) => f.debug_tuple("Fixed").field(w).finish(),
+            Self::Linear {
+                base,
+                per_depth,
+                min_depth,
+                max_depth,
+            } => f
+                .debug_struct("Linear")
+                .field("base", base)
+                .field("per_depth", per_depth)
+                .field("min_depth", min_depth)
+                .field("max_depth", max_depth)
+                .finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// An item with its weight.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry<T> {
+    pub item: T,
+    pub weight: Weight,
+}
+
+/// A table of items picked at random in proportion to their weights.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct WeightedTable<T> {
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> Default for WeightedTable<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> WeightedTable<T> {
+    /// Start building a table.
+    pub fn builder() -> WeightedTableBuilder<T> {
+        WeightedTableBuilder {
+            table: Self::default(),
+        }
+    }
+
+    /// The table entries, in insertion order.
+    pub fn entries(&self) -> &[Entry<T>] {
+        &self.entries
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the table has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sum of the weights at `depth`.
+    pub fn total_weight(&self, depth: i32) -> u64 {
+        self.entries.iter().map(|e| e.weight.at(depth) as u64).sum()
+    }
+
+    /// Pick an item using the depth-0 weights. Returns `None` if the table
+    /// is empty or all weights are zero.
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<&T> {
+        self.pick_for(0, rng)
+    }
+
+    /// Pick an item using the weights at `depth`.
+    pub fn pick_for(&self, depth: i32, rng: &mut impl Rng) -> Option<&T> {
+        let weights: Vec<u64> = self
+            .entries
+            .iter()
+            .map(|e| e.weight.at(depth) as u64)
+            .collect();
+        pick_index(&weights, rng).map(|i| &self.entries[i].item)
+    }
+
+    /// Pick up to `n` distinct entries using the depth-0 weights.
+    pub fn pick_n_distinct(&self, n: usize, rng: &mut impl Rng) -> Vec<&T> {
+        self.pick_n_distinct_for(0, n, rng)
+    }
+
+    /// Pick up to `n` distinct entries using the weights at `depth`, without
+    /// replacement. Fewer items are returned when fewer than `n` entries
+    /// have a non-zero weight.
+    pub fn pick_n_distinct_for(&self, depth: i32, n: usize, rng: &mut impl Rng) -> Vec<&T> {
+        let mut weights: Vec<u64> = self
+            .entries
+            .iter()
+            .map(|e| e.weight.at(depth) as u64)
+            .collect();
+        let mut picked = Vec::with_capacity(n.min(weights.len()));
+        while picked.len() < n {
+            let Some(i) = pick_index(&weights, rng) else {
+                break;
+            };
+            weights[i] = 0;
+            picked.push(&self.entries[i].item);
+        }
+        picked
+    }
+}
+
+/// Pick an index in proportion to `weights`.
+fn pick_index(weights: &[u64], rng: &mut impl Rng) -> Option<usize> {
+    let total: u64 = weights.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut r = rng.random_range(0..total);
+    for (i, &w) in weights.iter().enumerate() {
+        if r < w {
+            return Some(i);
+        }
+        r -= w;
+    }
+    None
+}
+
+/// Builder for [`WeightedTable`].
+pub struct WeightedTableBuilder<T> {
+    table: WeightedTable<T>,
+}
+
+impl<T> WeightedTableBuilder<T> {
+    /// Add an item with a fixed weight.
+    pub fn add(self, item: T, weight: u32) -> Self {
+        self.add_weight(item, Weight::Fixed(weight))
+    }
+
+    /// Add an item whose weight is computed from the depth.
+    pub fn add_scaled(self, item: T, f: impl Fn(i32) -> u32 + Send + Sync + 'static) -> Self {
+        self.add_weight(item, Weight::Custom(Arc::new(f)))
+    }
+
+    /// Add an item with an explicit [`Weight`].
+    pub fn add_weight(mut self, item: T, weight: Weight) -> Self {
+        self.table.entries.push(Entry { item, weight });
+        self
+    }
+
+    /// Finish building.
+    pub fn build(self) -> WeightedTable<T> {
+        self.table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn monsters() -> WeightedTable<&'static str> {
+        WeightedTable::builder()
+            .add("rat", 40)
+            .add("goblin", 30)
+            .add("snake", 20)
+            .add("wolf", 10)
+            .build()
+    }
+
+    #[test]
+    fn distribution_matches_weights() {
+        let table = monsters();
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 20_000;
+        let mut counts = [0usize; 4];
+        for _ in 0..n {
+            let m = *table.pick(&mut rng).unwrap();
+            let i = ["rat", "goblin", "snake", "wolf"]
+                .iter()
+                .position(|&x| x == m)
+                .unwrap();
+            counts[i] += 1;
+        }
+        let expected = [0.4, 0.3, 0.2, 0.1].map(|p| p * n as f64);
+        let chi2: f64 = counts
+            .iter()
+            .zip(expected)
+            .map(|(&o, e)| (o as f64 - e).powi(2) / e)
+            .sum();
+        // 3 degrees of freedom, p = 0.001.
+        assert!(chi2 < 16.27, "chi2 = {chi2}, counts = {counts:?}");
+    }
+
+    #[test]
+    fn depth_scaling_changes_weights() {
+        let table = WeightedTable::builder()
+            .add("rat", 10)
+            .add_scaled("dragon", |d| if d >= 5 { 1000 } else { 0 })
+            .add_weight(
+                "goblin",
+                Weight::Linear {
+                    base: -10,
+                    per_depth: 10,
+                    min_depth: 0,
+                    max_depth: 3,
+                },
+            )
+            .build();
+        assert_eq!(table.total_weight(0), 10);
+        assert_eq!(table.total_weight(2), 20);
+        assert_eq!(table.total_weight(4), 10);
+        assert_eq!(table.total_weight(5), 1010);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            assert_eq!(table.pick_for(0, &mut rng), Some(&"rat"));
+        }
+        let dragons = (0..1000)
+            .filter(|_| table.pick_for(5, &mut rng) == Some(&"dragon"))
+            .count();
+        assert!(dragons > 950, "{dragons}");
+    }
+
+    #[test]
+    fn distinct_picks_never_repeat() {
+        let table = monsters();
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let mut picks = table.pick_n_distinct(3, &mut rng);
+            assert_eq!(picks.len(), 3);
+            picks.sort();
+            picks.dedup();
+            assert_eq!(picks.len(), 3);
+        }
+        assert_eq!(table.pick_n_distinct(10, &mut rng).len(), 4);
+
+        let empty: WeightedTable<u8> = WeightedTable::default();
+        assert!(empty.pick(&mut rng).is_none());
+        assert!(empty.pick_n_distinct(2, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn same_seed_same_picks() {
+        let table = monsters();
+        let a: Vec<_> = {
+            let mut rng = StdRng::seed_from_u64(9);
+            (0..50).map(|_| table.pick(&mut rng).copied()).collect()
+        };
+        let b: Vec<_> = {
+            let mut rng = StdRng::seed_from_u64(9);
+            (0..50).map(|_| table.pick(&mut rng).copied()).collect()
+        };
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let json = r#"[
+            {"item": "rat", "weight": {"Fixed": 3}},
+            {"item": "orc", "weight": {"Linear": {"base": 0, "per_depth": 2, "min_depth": 2, "max_depth": 9}}}
+        ]"#;
+        let table: WeightedTable<String> = serde_json::from_str(json).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.total_weight(1), 3);
+        assert_eq!(table.total_weight(4), 11);
+        let back = serde_json::to_string(&table).unwrap();
+        let again: WeightedTable<String> = serde_json::from_str(&back).unwrap();
+        assert_eq!(again.total_weight(4), 11);
+    }
+}
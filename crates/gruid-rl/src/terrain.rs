@@ -0,0 +1,461 @@
+//! Terrain flags shared by field of view and pathfinding.
+//!
+//! A [`TerrainFlags`] grid holds, for each cell, a [`TerrainMask`] telling
+//! what the terrain there blocks. Games keep one next to their map, built
+//! with [`TerrainFlags::from_grid`] or updated cell by cell, and feed both
+//! FOV and pathfinding from it, so that the two cannot disagree on what a
+//! door or deep water does:
+//!
+//! - [`TerrainFlags::sight`] is a [`Lighter`] for [`FOV`](crate::FOV).
+//! - [`TerrainFlags::moves`] is a [`Pather`] for
+//!   [`PathRange`](gruid_paths::PathRange) searches, for movers blocked by
+//!   some of the flags: walkers by [`TerrainMask::BLOCKS_MOVE`], flyers by
+//!   [`TerrainMask::BLOCKS_FLY`] only.
+//! - [`TerrainFlags::as_passable`] gives the `passable` closure of jump
+//!   point search and shadow casting.
+//!
+//! Cells outside the grid block all movement.
+//!
+//! ```
+//! use gruid_core::{Point, Range};
+//! use gruid_paths::PathRange;
+//! use gruid_rl::terrain::{TerrainFlags, TerrainMask};
+//! use gruid_rl::FOV;
+//!
+//! let rg = Range::new(0, 0, 5, 1);
+//! let mut flags = TerrainFlags::new(rg);
+//! // Deep water: flyers cross it, and it hides nothing.
+//! flags.set(Point::new(2, 0), TerrainMask::BLOCKS_MOVE);
+//!
+//! let mut fov = FOV::new(rg);
+//! fov.vision_map(&flags.sight(4), Point::new(0, 0));
+//! assert!(fov.at(Point::new(4, 0)).is_some());
+//!
+//! let mut paths = PathRange::new(rg);
+//! let (from, to) = (Point::new(0, 0), Point::new(4, 0));
+//! assert!(paths.astar_path(&flags.moves(TerrainMask::BLOCKS_MOVE), from, to).is_none());
+//! assert!(paths.astar_path(&flags.moves(TerrainMask::BLOCKS_FLY), from, to).is_some());
+//! ```
+
+use std::ops::{BitAnd, BitOr};
+
+use gruid_core::{Point, Range};
+use gruid_paths::{AstarPather, Pather, WeightedPather};
+
+use crate::fov::Lighter;
+use crate::grid::{Cell, Grid};
+
+/// What the terrain of a cell blocks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerrainMask(pub u32);
+
+impl TerrainMask {
+    pub const NONE: Self = Self(0);
+    /// Blocks walking movers.
+    pub const BLOCKS_MOVE: Self = Self(1 << 0);
+    /// Blocks line of sight.
+    pub const BLOCKS_SIGHT: Self = Self(1 << 1);
+    /// Blocks flying movers.
+    pub const BLOCKS_FLY: Self = Self(1 << 2);
+    /// Blocks everything: a wall.
+    pub const SOLID: Self = Self(Self::BLOCKS_MOVE.0 | Self::BLOCKS_SIGHT.0 | Self::BLOCKS_FLY.0);
+
+    /// Game-defined flag number `n`, from 0 to 23, such as "burns" or
+    /// "blocks swimming".
+    #[inline]
+    pub const fn custom(n: u32) -> Self {
+        assert!(n < 24, "custom terrain flags go from 0 to 23");
+        Self(1 << (8 + n))
+    }
+
+    /// Whether this mask contains all the bits from `other`.
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    /// Whether this mask shares any bit with `other`.
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        (self.0 & other.0) != 0
+    }
+
+    /// Whether the mask is empty.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for TerrainMask {
+    type Output = Self;
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for TerrainMask {
+    type Output = Self;
+    #[inline]
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// A grid of [`TerrainMask`]s over a range of positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerrainFlags {
+    range: Range,
+    cells: Vec<TerrainMask>,
+}
+
+impl TerrainFlags {
+    /// Flags over `range`, blocking nothing.
+    pub fn new(range: Range) -> Self {
+        Self {
+            range,
+            cells: vec![TerrainMask::NONE; range.len()],
+        }
+    }
+
+    /// Flags over the range of `grid`, mapping each of its cells with `f`.
+    pub fn from_grid(grid: &Grid, f: impl FnMut(Cell) -> TerrainMask) -> Self {
+        let mut flags = Self::new(grid.range_());
+        flags.update_from(grid, f);
+        flags
+    }
+
+    /// Set the flags of the cells of `grid` within the range with `f`,
+    /// after the map changed.
+    pub fn update_from(&mut self, grid: &Grid, mut f: impl FnMut(Cell) -> TerrainMask) {
+        for (p, c) in grid.iter() {
+            self.set(p, f(c));
+        }
+    }
+
+    /// The range of positions covered.
+    pub fn range_(&self) -> Range {
+        self.range
+    }
+
+    fn idx(&self, p: Point) -> Option<usize> {
+        self.range.contains(p).then(|| {
+            let q = p - self.range.min;
+            (q.y * self.range.width() + q.x) as usize
+        })
+    }
+
+    /// The flags at `p`, or `None` outside the range.
+    #[inline]
+    pub fn at(&self, p: Point) -> Option<TerrainMask> {
+        self.idx(p).map(|i| self.cells[i])
+    }
+
+    /// Set the flags at `p`. Does nothing outside the range.
+    #[inline]
+    pub fn set(&mut self, p: Point, mask: TerrainMask) {
+        if let Some(i) = self.idx(p) {
+            self.cells[i] = mask;
+        }
+    }
+
+    /// Add the flags of `mask` at `p`, such as when a door closes.
+    pub fn insert(&mut self, p: Point, mask: TerrainMask) {
+        if let Some(i) = self.idx(p) {
+            self.cells[i] = self.cells[i] | mask;
+        }
+    }
+
+    /// Remove the flags of `mask` at `p`, such as when a door opens.
+    pub fn remove(&mut self, p: Point, mask: TerrainMask) {
+        if let Some(i) = self.idx(p) {
+            self.cells[i] = TerrainMask(self.cells[i].0 & !mask.0);
+        }
+    }
+
+    /// Whether `p` has any of the flags of `mask`, or is outside the range.
+    #[inline]
+    pub fn blocks(&self, p: Point, mask: TerrainMask) -> bool {
+        self.at(p).is_none_or(|m| m.intersects(mask))
+    }
+
+    /// Whether `p` is within the range and has none of the flags of
+    /// `mask`: the `passable` closure of
+    /// [`PathRange::jps_path`](gruid_paths::PathRange::jps_path) or
+    /// [`FOV::ssc_vision_map`](crate::FOV::ssc_vision_map).
+    pub fn as_passable(&self, mask: TerrainMask) -> impl Fn(Point) -> bool + '_ {
+        move |p| !self.blocks(p, mask)
+    }
+
+    /// A [`Lighter`] seeing up to `max_sight` cells away, stopped by
+    /// [`TerrainMask::BLOCKS_SIGHT`].
+    pub fn sight(&self, max_sight: i32) -> SightAdapter<'_> {
+        SightAdapter {
+            flags: self,
+            max_sight,
+        }
+    }
+
+    /// A 4-way [`Pather`] for movers blocked by any of the flags of
+    /// `blocked_by`.
+    pub fn moves(&self, blocked_by: TerrainMask) -> MoveAdapter<'_> {
+        MoveAdapter {
+            flags: self,
+            blocked_by,
+            diagonals: false,
+        }
+    }
+}
+
+/// Light propagation over [`TerrainFlags`]; see [`TerrainFlags::sight`].
+///
+/// Light does not go past a cell blocking sight, but lights it. Cells
+/// outside the range are transparent, the [`FOV`](crate::FOV) range
+/// bounding the light anyway.
+#[derive(Debug, Clone, Copy)]
+pub struct SightAdapter<'a> {
+    flags: &'a TerrainFlags,
+    max_sight: i32,
+}
+
+impl SightAdapter<'_> {
+    /// Set the sight distance (builder).
+    pub fn with_max_sight(mut self, max_sight: i32) -> Self {
+        self.max_sight = max_sight;
+        self
+    }
+}
+
+impl Lighter for SightAdapter<'_> {
+    fn cost(&self, _src: Point, from: Point, _to: Point) -> i32 {
+        match self.flags.at(from) {
+            Some(m) if m.intersects(TerrainMask::BLOCKS_SIGHT) => i32::MAX,
+            _ => 1,
+        }
+    }
+
+    fn max_cost(&self, _src: Point) -> i32 {
+        self.max_sight
+    }
+}
+
+const CARDINALS: [Point; 4] = [
+    Point::new(1, 0),
+    Point::new(-1, 0),
+    Point::new(0, 1),
+    Point::new(0, -1),
+];
+
+const DIAGONALS: [Point; 4] = [
+    Point::new(-1, -1),
+    Point::new(1, -1),
+    Point::new(-1, 1),
+    Point::new(1, 1),
+];
+
+/// Movement over [`TerrainFlags`] at unit cost; see
+/// [`TerrainFlags::moves`].
+#[derive(Debug, Clone, Copy)]
+pub struct MoveAdapter<'a> {
+    flags: &'a TerrainFlags,
+    blocked_by: TerrainMask,
+    diagonals: bool,
+}
+
+impl MoveAdapter<'_> {
+    /// Allow diagonal moves too (builder). Diagonal moves may cut corners
+    /// between two blocked cells.
+    pub fn with_diagonals(mut self, diagonals: bool) -> Self {
+        self.diagonals = diagonals;
+        self
+    }
+}
+
+impl Pather for MoveAdapter<'_> {
+    fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
+        let diagonals = if self.diagonals { &DIAGONALS[..] } else { &[] };
+        for &d in CARDINALS.iter().chain(diagonals) {
+            let q = p + d;
+            if !self.flags.blocks(q, self.blocked_by) {
+                buf.push(q);
+            }
+        }
+    }
+}
+
+impl WeightedPather for MoveAdapter<'_> {
+    fn cost(&self, _from: Point, _to: Point) -> i32 {
+        1
+    }
+}
+
+impl AstarPather for MoveAdapter<'_> {
+    fn estimate(&self, from: Point, to: Point) -> i32 {
+        gruid_paths::Metric::unit_moves(self.diagonals).distance(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fov::FOV;
+    use crate::mapgen::{CellularAutomataRule, MapGen};
+    use gruid_paths::PathRange;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const WALL: Cell = Cell(0);
+    const FLOOR: Cell = Cell(1);
+    const WATER: Cell = Cell(2);
+    const GLASS: Cell = Cell(3);
+    const FOG: Cell = Cell(4);
+
+    fn terrain(c: Cell) -> TerrainMask {
+        match c {
+            WALL => TerrainMask::SOLID,
+            WATER => TerrainMask::BLOCKS_MOVE,
+            GLASS => TerrainMask::BLOCKS_MOVE | TerrainMask::BLOCKS_FLY,
+            FOG => TerrainMask::BLOCKS_SIGHT,
+            _ => TerrainMask::NONE,
+        }
+    }
+
+    #[test]
+    fn masks() {
+        let burns = TerrainMask::custom(0);
+        let m = TerrainMask::BLOCKS_MOVE | burns;
+        assert!(m.contains(burns) && m.intersects(TerrainMask::SOLID));
+        assert!(!m.contains(TerrainMask::SOLID));
+        assert!(!burns.intersects(TerrainMask::SOLID));
+        assert_eq!(TerrainMask::custom(23).0, 1 << 31);
+
+        let p = Point::new(1, 1);
+        let mut flags = TerrainFlags::new(Range::new(1, 1, 3, 2));
+        flags.insert(p, m);
+        flags.remove(p, TerrainMask::BLOCKS_MOVE);
+        assert_eq!(flags.at(p), Some(burns));
+        assert_eq!(flags.at(Point::ZERO), None);
+        assert!(flags.blocks(Point::ZERO, TerrainMask::NONE));
+        assert!(!flags.blocks(Point::new(2, 1), TerrainMask::SOLID));
+    }
+
+    /// Each terrain between two floor cells, in a corridor: whether
+    /// walkers and flyers cross it, and whether it can be seen through.
+    #[test]
+    fn adapters_follow_the_flags() {
+        let cases = [
+            (FLOOR, true, true, true),
+            (WALL, false, false, false),
+            (WATER, false, true, true),
+            (GLASS, false, false, true),
+            (FOG, true, true, false),
+        ];
+        for (cell, walks, flies, sees) in cases {
+            let map = Grid::new(3, 1);
+            map.fill(FLOOR);
+            map.set(Point::new(1, 0), cell);
+            let flags = TerrainFlags::from_grid(&map, terrain);
+            let (from, to) = (Point::new(0, 0), Point::new(2, 0));
+            let mut paths = PathRange::new(map.range_());
+            for (mask, expected) in [
+                (TerrainMask::BLOCKS_MOVE, walks),
+                (TerrainMask::BLOCKS_FLY, flies),
+            ] {
+                for diagonals in [false, true] {
+                    let mover = flags.moves(mask).with_diagonals(diagonals);
+                    let path = paths.astar_path(&mover, from, to);
+                    assert_eq!(path.is_some(), expected, "{cell:?} {mask:?}");
+                }
+                let jps = paths.jps_path(from, to, flags.as_passable(mask), false);
+                assert_eq!(jps.is_some(), expected, "{cell:?} {mask:?}");
+            }
+            let mut fov = FOV::new(map.range_());
+            fov.vision_map(&flags.sight(5), from);
+            assert_eq!(fov.at(to).is_some(), sees, "{cell:?}");
+            // The blocking cell itself is seen.
+            assert!(fov.at(Point::new(1, 0)).is_some());
+            fov.ssc_vision_map(from, 5, flags.as_passable(TerrainMask::BLOCKS_SIGHT), false);
+            assert_eq!(fov.visible(to), sees, "{cell:?}");
+        }
+    }
+
+    #[test]
+    fn diagonal_moves() {
+        let mut flags = TerrainFlags::new(Range::new(0, 0, 3, 3));
+        let centre = Point::new(1, 1);
+        let mut buf = Vec::new();
+        flags
+            .moves(TerrainMask::BLOCKS_MOVE)
+            .neighbors(centre, &mut buf);
+        assert_eq!(buf.len(), 4);
+        buf.clear();
+        flags.set(Point::new(0, 0), TerrainMask::BLOCKS_MOVE);
+        let mover = flags.moves(TerrainMask::BLOCKS_MOVE).with_diagonals(true);
+        mover.neighbors(centre, &mut buf);
+        assert_eq!(buf.len(), 7);
+        assert!(!buf.contains(&Point::new(0, 0)));
+        assert_eq!(mover.estimate(Point::ZERO, Point::new(2, 1)), 2);
+        buf.clear();
+        // Out of range cells are never neighbours.
+        mover.neighbors(Point::ZERO, &mut buf);
+        assert_eq!(buf, [Point::new(1, 0), Point::new(0, 1), Point::new(1, 1)]);
+    }
+
+    /// The lighter of the roguelike example, over its map.
+    struct MapLighter<'a> {
+        map: &'a Grid,
+    }
+
+    impl Lighter for MapLighter<'_> {
+        fn cost(&self, _src: Point, from: Point, _to: Point) -> i32 {
+            if self.map.at(from) == Some(WALL) {
+                i32::MAX
+            } else {
+                1
+            }
+        }
+        fn max_cost(&self, _src: Point) -> i32 {
+            8
+        }
+    }
+
+    /// On caves generated like those of the roguelike example, the flags
+    /// give the same FOV and paths as closures over the map.
+    #[test]
+    fn flags_agree_with_map_closures() {
+        for seed in 0..4 {
+            let map = Grid::new(60, 20);
+            map.fill(WALL);
+            let mut mg = MapGen::with_grid(map.clone(), StdRng::seed_from_u64(seed));
+            let rule = CellularAutomataRule {
+                w_cutoff1: 5,
+                w_cutoff2: 25,
+                walls_out_of_range: true,
+                reps: 4,
+            };
+            mg.cellular_automata_cave(WALL, FLOOR, 0.45, &[rule]);
+            let flags = TerrainFlags::from_grid(&map, terrain);
+            let floors: Vec<Point> = map.find(FLOOR).step_by(17).collect();
+
+            let rg = map.range_();
+            let (mut old, mut new) = (FOV::new(rg), FOV::new(rg));
+            let (mut old_paths, mut new_paths) = (PathRange::new(rg), PathRange::new(rg));
+            for &src in &floors {
+                old.vision_map(&MapLighter { map: &map }, src);
+                new.vision_map(&flags.sight(8), src);
+                for p in rg.iter() {
+                    assert_eq!(old.at(p), new.at(p), "seed {seed} from {src} at {p}");
+                }
+                let to = floors[0];
+                let passable = |p| map.at(p) == Some(FLOOR);
+                assert_eq!(
+                    old_paths.jps_path(src, to, passable, false),
+                    new_paths.jps_path(src, to, flags.as_passable(TerrainMask::BLOCKS_MOVE), false)
+                );
+            }
+        }
+    }
+}
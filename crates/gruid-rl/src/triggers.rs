@@ -0,0 +1,356 @@
+//! Trigger regions: pressure plates, level exits and scripted areas.
+//!
+//! A [`Triggers`] manager holds regions tagged with caller-chosen ids. It
+//! stores no callbacks: [`Triggers::update`] is told how an entity moved
+//! and returns the [`TriggerEvent`]s it caused, which the game then acts
+//! upon. This keeps the manager serializable with the rest of a save.
+//!
+//! ```
+//! use gruid_core::{Point, Range};
+//! use gruid_rl::triggers::{TriggerEvent, TriggerShape, Triggers};
+//!
+//! let mut triggers = Triggers::new();
+//! triggers.add(TriggerShape::Range(Range::new(5, 0, 10, 5)), "throne room");
+//! triggers.add_one_shot(TriggerShape::Points([Point::new(6, 1)].into()), "trap");
+//!
+//! let events = triggers.update(0, Point::new(4, 1), Point::new(5, 1));
+//! assert_eq!(events, [TriggerEvent::Entered { trigger: "throne room", entity: 0 }]);
+//! let events = triggers.update(0, Point::new(5, 1), Point::new(6, 1));
+//! assert_eq!(events.len(), 2);
+//! // The trap only fires once.
+//! assert_eq!(triggers.triggers_at(Point::new(6, 1)).collect::<Vec<_>>(), ["throne room"]);
+//! ```
+
+use std::collections::BTreeSet;
+
+use gruid_core::{Point, Range};
+
+/// The cells a trigger covers.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerShape {
+    /// A set of cells.
+    Points(BTreeSet<Point>),
+    /// A rectangle.
+    Range(Range),
+    /// The cells for which a function returns `true`. Function pointers
+    /// cannot be saved: serializing a manager holding such a trigger fails.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Predicate(fn(Point) -> bool),
+}
+
+impl TriggerShape {
+    /// Whether the shape covers `p`.
+    pub fn contains(&self, p: Point) -> bool {
+        match self {
+            Self::Points(points) => points.contains(&p),
+            Self::Range(rg) => rg.contains(p),
+            Self::Predicate(f) => f(p),
+        }
+    }
+}
+
+/// What a move did with respect to a trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerEvent<T, E> {
+    /// The entity moved into the trigger.
+    Entered { trigger: T, entity: E },
+    /// The entity moved out of the trigger.
+    Exited { trigger: T, entity: E },
+    /// The entity moved within the trigger.
+    StillInside { trigger: T, entity: E },
+}
+
+impl<T: Copy, E> TriggerEvent<T, E> {
+    /// The trigger concerned.
+    pub fn trigger(&self) -> T {
+        match *self {
+            Self::Entered { trigger, .. }
+            | Self::Exited { trigger, .. }
+            | Self::StillInside { trigger, .. } => trigger,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Trigger<T> {
+    id: T,
+    shape: TriggerShape,
+    enabled: bool,
+    one_shot: bool,
+}
+
+/// A set of trigger regions, identified by ids of type `T`.
+///
+/// Moves are jumps: only the cells an entity leaves and reaches count, so a
+/// teleport across a trigger neither enters nor exits it. As long as a
+/// trigger stays enabled and every move of an entity is reported, its
+/// `Entered` and `Exited` events alternate. Disabled triggers report
+/// nothing.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triggers<T> {
+    /// In order of addition, which is the order of events.
+    triggers: Vec<Trigger<T>>,
+}
+
+impl<T> Default for Triggers<T> {
+    fn default() -> Self {
+        Self {
+            triggers: Vec::new(),
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> Triggers<T> {
+    /// An empty manager.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, shape: TriggerShape, id: T, one_shot: bool) {
+        let trigger = Trigger {
+            id,
+            shape,
+            enabled: true,
+            one_shot,
+        };
+        match self.triggers.iter_mut().find(|t| t.id == id) {
+            Some(t) => *t = trigger,
+            None => self.triggers.push(trigger),
+        }
+    }
+
+    /// Add an enabled trigger, replacing any trigger with the same id.
+    pub fn add(&mut self, shape: TriggerShape, id: T) {
+        self.insert(shape, id, false);
+    }
+
+    /// Add a trigger that is removed once an entity first enters it,
+    /// replacing any trigger with the same id.
+    pub fn add_one_shot(&mut self, shape: TriggerShape, id: T) {
+        self.insert(shape, id, true);
+    }
+
+    /// Remove a trigger. Returns whether it existed.
+    pub fn remove(&mut self, id: T) -> bool {
+        let len = self.triggers.len();
+        self.triggers.retain(|t| t.id != id);
+        self.triggers.len() != len
+    }
+
+    /// Whether a trigger with this id exists.
+    pub fn contains(&self, id: T) -> bool {
+        self.triggers.iter().any(|t| t.id == id)
+    }
+
+    /// Enable or disable a trigger. Returns whether it exists.
+    pub fn set_enabled(&mut self, id: T, enabled: bool) -> bool {
+        match self.triggers.iter_mut().find(|t| t.id == id) {
+            Some(t) => {
+                t.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a trigger exists and is enabled.
+    pub fn is_enabled(&self, id: T) -> bool {
+        self.triggers.iter().any(|t| t.id == id && t.enabled)
+    }
+
+    /// Number of triggers, enabled or not.
+    pub fn len(&self) -> usize {
+        self.triggers.len()
+    }
+
+    /// Whether there are no triggers.
+    pub fn is_empty(&self) -> bool {
+        self.triggers.is_empty()
+    }
+
+    /// The enabled triggers covering `p`, in order of addition.
+    pub fn triggers_at(&self, p: Point) -> impl Iterator<Item = T> + '_ {
+        self.triggers
+            .iter()
+            .filter(move |t| t.enabled && t.shape.contains(p))
+            .map(|t| t.id)
+    }
+
+    /// Report that `entity` moved from `old` to `new`, and return the
+    /// events this caused: exits first, then moves within triggers, then
+    /// entries, each in order of addition. One-shot triggers entered are
+    /// removed.
+    pub fn update<E: Copy>(
+        &mut self,
+        entity: E,
+        old: Point,
+        new: Point,
+    ) -> Vec<TriggerEvent<T, E>> {
+        let mut exited = Vec::new();
+        let mut inside = Vec::new();
+        let mut entered = Vec::new();
+        for t in self.triggers.iter().filter(|t| t.enabled) {
+            let trigger = t.id;
+            match (t.shape.contains(old), t.shape.contains(new)) {
+                (true, false) => exited.push(TriggerEvent::Exited { trigger, entity }),
+                (true, true) => inside.push(TriggerEvent::StillInside { trigger, entity }),
+                (false, true) => entered.push(TriggerEvent::Entered { trigger, entity }),
+                (false, false) => {}
+            }
+        }
+        if !entered.is_empty() {
+            self.triggers.retain(|t| {
+                !(t.one_shot
+                    && entered
+                        .iter()
+                        .any(|e: &TriggerEvent<T, E>| e.trigger() == t.id))
+            });
+        }
+        exited.extend(inside);
+        exited.extend(entered);
+        exited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(ps: &[(i32, i32)]) -> TriggerShape {
+        TriggerShape::Points(ps.iter().map(|&(x, y)| Point::new(x, y)).collect())
+    }
+
+    #[test]
+    fn enter_exit_pairing_across_steps() {
+        let mut t = Triggers::new();
+        t.add(TriggerShape::Range(Range::new(2, 0, 5, 1)), 'r');
+        let mut log = Vec::new();
+        let mut pos = Point::new(0, 0);
+        for x in 1..8 {
+            let next = Point::new(x, 0);
+            log.extend(t.update(1, pos, next));
+            pos = next;
+        }
+        use TriggerEvent::*;
+        assert_eq!(
+            log,
+            [
+                Entered {
+                    trigger: 'r',
+                    entity: 1
+                },
+                StillInside {
+                    trigger: 'r',
+                    entity: 1
+                },
+                StillInside {
+                    trigger: 'r',
+                    entity: 1
+                },
+                Exited {
+                    trigger: 'r',
+                    entity: 1
+                },
+            ]
+        );
+        // Standing still inside is still inside.
+        assert_eq!(t.update(1, Point::new(3, 0), Point::new(3, 0)).len(), 1);
+    }
+
+    #[test]
+    fn teleports_only_see_endpoints() {
+        let mut t = Triggers::new();
+        t.add(TriggerShape::Range(Range::new(10, 0, 12, 10)), 0);
+        t.add(points(&[(20, 5)]), 1);
+        // Jumping over trigger 0 does not touch it.
+        let events = t.update((), Point::new(0, 5), Point::new(20, 5));
+        assert_eq!(
+            events,
+            [TriggerEvent::Entered {
+                trigger: 1,
+                entity: ()
+            }]
+        );
+        // Teleporting from one trigger into another exits, then enters.
+        let events = t.update((), Point::new(20, 5), Point::new(11, 5));
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|e| (matches!(e, TriggerEvent::Exited { .. }), e.trigger()))
+            .collect();
+        assert_eq!(kinds, [(true, 1), (false, 0)]);
+    }
+
+    #[test]
+    fn overlapping_triggers_and_enabled_flags() {
+        let mut t = Triggers::new();
+        t.add(TriggerShape::Range(Range::new(0, 0, 10, 10)), "room");
+        t.add(points(&[(5, 5)]), "plate");
+        t.add(TriggerShape::Predicate(|p| p.x == 5), "column");
+        fn at(t: &Triggers<&'static str>, x: i32, y: i32) -> Vec<&'static str> {
+            t.triggers_at(Point::new(x, y)).collect()
+        }
+        assert_eq!(at(&t, 5, 5), ["room", "plate", "column"]);
+
+        let events = t.update('@', Point::new(4, 5), Point::new(5, 5));
+        let ids: Vec<_> = events.iter().map(|e| e.trigger()).collect();
+        // The room is still inside, reported before the entries.
+        assert_eq!(ids, ["room", "plate", "column"]);
+        assert!(matches!(events[0], TriggerEvent::StillInside { .. }));
+
+        assert!(t.set_enabled("plate", false));
+        assert!(!t.is_enabled("plate"));
+        assert_eq!(at(&t, 5, 5), ["room", "column"]);
+        let events = t.update('@', Point::new(5, 5), Point::new(5, 6));
+        let ids: Vec<_> = events.iter().map(|e| e.trigger()).collect();
+        assert_eq!(ids, ["room", "column"]);
+
+        assert!(t.remove("column"));
+        assert!(!t.remove("column"));
+        assert!(!t.set_enabled("column", true));
+        assert_eq!(t.len(), 2);
+    }
+
+    #[test]
+    fn one_shot_removed_after_entry() {
+        let mut t = Triggers::new();
+        t.add_one_shot(points(&[(1, 0)]), 7);
+        t.add(points(&[(1, 0)]), 8);
+        // Moving elsewhere does not consume it.
+        assert!(t.update(0, Point::new(0, 0), Point::new(0, 1)).is_empty());
+        assert!(t.contains(7));
+        let events = t.update(0, Point::new(0, 0), Point::new(1, 0));
+        assert_eq!(events.len(), 2);
+        assert!(!t.contains(7));
+        assert!(t.contains(8));
+        // Re-entering only fires the permanent trigger.
+        t.update(0, Point::new(1, 0), Point::new(0, 0));
+        let events = t.update(0, Point::new(0, 0), Point::new(1, 0));
+        assert_eq!(
+            events,
+            [TriggerEvent::Entered {
+                trigger: 8,
+                entity: 0
+            }]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut t = Triggers::new();
+        t.add(points(&[(1, 2), (3, 4)]), 1u32);
+        t.add_one_shot(TriggerShape::Range(Range::new(0, 0, 2, 2)), 2);
+        t.set_enabled(1, false);
+        let json = serde_json::to_string(&t).unwrap();
+        let back: Triggers<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert!(!back.is_enabled(1) && back.is_enabled(2));
+
+        t.add(TriggerShape::Predicate(|_| true), 3);
+        assert!(serde_json::to_string(&t).is_err());
+    }
+}
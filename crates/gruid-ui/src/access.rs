@@ -0,0 +1,139 @@
+//! Plain descriptions of widget state, for screen readers and UI tests.
+//!
+//! Widgets describe themselves with an [`AccessibilityNode`]: structured
+//! data without markup and without sentences, that the host formats in its
+//! own words and language. An [`Announcer`] tells when a description
+//! changed, so that only changes get announced.
+
+/// What kind of widget an [`AccessibilityNode`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// A list of entries to choose from.
+    Menu,
+    /// Scrollable text.
+    Pager,
+    /// A single-line text field.
+    TextInput,
+    /// A recording being played back.
+    Replay,
+    /// A progress indicator.
+    Progress,
+    /// A checkbox.
+    Toggle,
+    /// A choice among values shown one at a time.
+    Picker,
+}
+
+/// The state of a widget, as plain text and numbers.
+///
+/// Fields a widget has no use for are left empty. Texts have their markup
+/// stripped. Positions are 1-based, as they would be read out.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccessibilityNode {
+    /// The kind of widget.
+    pub role: Role,
+    /// Name of the widget: its box title or prompt.
+    pub label: Option<String>,
+    /// The current value: the active menu entry, the first visible pager
+    /// line, or the text input content. Never set for masked inputs.
+    pub value: Option<String>,
+    /// Position of the active entry, top line or current frame.
+    pub index: Option<usize>,
+    /// Number of entries, lines or frames, when known.
+    pub count: Option<usize>,
+    /// Current page, when there are several.
+    pub page: Option<usize>,
+    /// Number of pages, when there are several.
+    pub pages: Option<usize>,
+    /// Cursor position in the value, in characters from its start.
+    pub cursor: Option<usize>,
+    /// Completion, in percent.
+    pub progress: Option<u8>,
+    /// Whether the active entry cannot be invoked.
+    pub disabled: bool,
+    /// Whether the value is hidden, as in a password input.
+    pub masked: bool,
+    /// Whether playback is running.
+    pub playing: bool,
+    /// State of a toggle.
+    pub checked: Option<bool>,
+    /// Why the value was refused, for form fields.
+    pub error: Option<String>,
+}
+
+impl AccessibilityNode {
+    /// A node for a widget of the given role, with every field empty.
+    pub fn new(role: Role) -> Self {
+        Self {
+            role,
+            label: None,
+            value: None,
+            index: None,
+            count: None,
+            page: None,
+            pages: None,
+            cursor: None,
+            progress: None,
+            disabled: false,
+            masked: false,
+            playing: false,
+            checked: None,
+            error: None,
+        }
+    }
+}
+
+/// Remembers the last description given, to announce only changes.
+///
+/// ```
+/// use gruid_ui::{AccessibilityNode, Announcer, Role};
+///
+/// let mut announcer = Announcer::new();
+/// let node = AccessibilityNode::new(Role::Menu);
+/// assert!(announcer.changed(node.clone()).is_some());
+/// assert!(announcer.changed(node).is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Announcer {
+    last: Option<AccessibilityNode>,
+}
+
+impl Announcer {
+    /// An announcer that has announced nothing yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `node` if it differs from the previous one, remembering it.
+    pub fn changed(&mut self, node: AccessibilityNode) -> Option<&AccessibilityNode> {
+        if self.last.as_ref() == Some(&node) {
+            return None;
+        }
+        self.last = Some(node);
+        self.last.as_ref()
+    }
+
+    /// Forget the previous description, so the next one is announced
+    /// whatever it is: for instance when focus moves to another widget.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announces_changes_only() {
+        let mut announcer = Announcer::new();
+        let mut node = AccessibilityNode::new(Role::Pager);
+        node.index = Some(1);
+        assert_eq!(announcer.changed(node.clone()), Some(&node));
+        assert_eq!(announcer.changed(node.clone()), None);
+        node.index = Some(2);
+        assert_eq!(announcer.changed(node.clone()), Some(&node));
+        announcer.reset();
+        assert_eq!(announcer.changed(node.clone()), Some(&node));
+    }
+}
@@ -0,0 +1,441 @@
+//! Key bindings shared across widgets.
+//!
+//! A [`KeyBindings`] registry maps logical [`KeyAction`]s to keys once for
+//! all widgets, so that a player's preference (arrows, vi keys, WASD)
+//! applies everywhere. Each widget's keys struct is built from it with
+//! `from_bindings`, and given to the widget's config with `with_keys`:
+//!
+//! ```
+//! # use gruid_core::Grid;
+//! # use gruid_ui::{KeyBindings, Menu, MenuConfig, MenuKeys, MenuStyle};
+//! # let cfg = MenuConfig {
+//! #     grid: Grid::new(20, 5),
+//! #     entries: Vec::new(),
+//! #     provider: None,
+//! #     keys: MenuKeys::default(),
+//! #     box_: None,
+//! #     style: MenuStyle::default(),
+//! #     reorderable: false,
+//! # };
+//! let kb = KeyBindings::wasd();
+//! let menu = Menu::new(cfg.with_keys(MenuKeys::from_bindings(&kb)));
+//! ```
+//!
+//! Bindings can be overridden for one [`KeyContext`] only: widgets taking
+//! text, such as [`TextInput`](crate::TextInput), keep letters out of
+//! their bindings so that they can be typed.
+
+use std::collections::BTreeMap;
+
+use gruid_core::messages::Key;
+
+/// A logical action of the widgets, bound to keys by [`KeyBindings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyAction {
+    NavUp,
+    NavDown,
+    NavLeft,
+    NavRight,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    Top,
+    Bottom,
+    /// Back to the start of the line, in a pager scrolled horizontally.
+    LineStart,
+    Confirm,
+    Cancel,
+    /// For the game's own use: no widget searches.
+    Search,
+    Help,
+    Copy,
+    /// Pick up or drop an entry of a reorderable menu.
+    Grab,
+    Pause,
+    Faster,
+    Slower,
+    /// Pan mode of a scrolled replay.
+    Pan,
+    /// The menu of anchors of a pager.
+    Anchors,
+    NextSection,
+    PrevSection,
+    NextField,
+    /// Shift+Tab does it too, whatever the bindings.
+    PrevField,
+    /// Switch a toggle of a form.
+    Toggle,
+}
+
+impl KeyAction {
+    /// Every action.
+    pub const ALL: [Self; 27] = [
+        Self::NavUp,
+        Self::NavDown,
+        Self::NavLeft,
+        Self::NavRight,
+        Self::PageUp,
+        Self::PageDown,
+        Self::HalfPageUp,
+        Self::HalfPageDown,
+        Self::Top,
+        Self::Bottom,
+        Self::LineStart,
+        Self::Confirm,
+        Self::Cancel,
+        Self::Search,
+        Self::Help,
+        Self::Copy,
+        Self::Grab,
+        Self::Pause,
+        Self::Faster,
+        Self::Slower,
+        Self::Pan,
+        Self::Anchors,
+        Self::NextSection,
+        Self::PrevSection,
+        Self::NextField,
+        Self::PrevField,
+        Self::Toggle,
+    ];
+}
+
+/// The widgets whose keys come from [`KeyBindings`], each with the actions
+/// it uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyContext {
+    /// [`Menu`](crate::Menu) and [`ContextMenu`](crate::ContextMenu).
+    Menu,
+    Pager,
+    TextInput,
+    Form,
+    Replay,
+}
+
+impl KeyContext {
+    /// Every context.
+    pub const ALL: [Self; 5] = [
+        Self::Menu,
+        Self::Pager,
+        Self::TextInput,
+        Self::Form,
+        Self::Replay,
+    ];
+
+    /// The actions the widget has keys for.
+    pub fn actions(self) -> &'static [KeyAction] {
+        use KeyAction::*;
+        match self {
+            Self::Menu => &[
+                NavUp, NavDown, NavLeft, NavRight, Confirm, Cancel, PageUp, PageDown, Grab,
+            ],
+            Self::Pager => &[
+                NavUp,
+                NavDown,
+                NavLeft,
+                NavRight,
+                LineStart,
+                PageUp,
+                PageDown,
+                HalfPageUp,
+                HalfPageDown,
+                Top,
+                Bottom,
+                Cancel,
+                Copy,
+                Anchors,
+                NextSection,
+                PrevSection,
+            ],
+            Self::TextInput => &[Confirm, Cancel],
+            Self::Form => &[
+                NextField, PrevField, Confirm, Cancel, Toggle, NavLeft, NavRight,
+            ],
+            Self::Replay => &[
+                Cancel, Pause, Faster, Slower, NavRight, NavLeft, NavUp, NavDown, Help, Pan,
+            ],
+        }
+    }
+
+    /// Whether `a` and `b` may share keys in this context without
+    /// conflicting.
+    fn may_share(self, a: KeyAction, b: KeyAction) -> bool {
+        // The anchors key takes over only when the pager has anchors.
+        let pair = (a.min(b), a.max(b));
+        self == Self::Pager && pair == (KeyAction::Top, KeyAction::Anchors)
+    }
+}
+
+/// A key bound to two actions of the same widget, found by
+/// [`KeyBindings::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub context: KeyContext,
+    pub key: Key,
+    /// The two actions, in the order of [`KeyContext::actions`].
+    pub actions: (KeyAction, KeyAction),
+}
+
+/// Keys of each [`KeyAction`], with overrides for some [`KeyContext`]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyBindings {
+    keys: BTreeMap<KeyAction, Vec<Key>>,
+    overrides: BTreeMap<KeyContext, BTreeMap<KeyAction, Vec<Key>>>,
+}
+
+impl KeyBindings {
+    /// Bindings without any key.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The default keys of the widgets: arrows, with vi letters alongside
+    /// where they do not get in the way of typing.
+    pub fn default_arrows() -> Self {
+        use KeyAction::*;
+        let ch = Key::Char;
+        Self::new()
+            .with(NavUp, vec![Key::ArrowUp, ch('k')])
+            .with(NavDown, vec![Key::ArrowDown, ch('j')])
+            .with(NavLeft, vec![Key::ArrowLeft, ch('h')])
+            .with(NavRight, vec![Key::ArrowRight, ch('l')])
+            .with(PageUp, vec![Key::PageUp])
+            .with(PageDown, vec![Key::PageDown])
+            .with(HalfPageUp, vec![ch('u')])
+            .with(HalfPageDown, vec![ch('d')])
+            .with(Top, vec![Key::Home, ch('g')])
+            .with(Bottom, vec![Key::End, ch('G')])
+            .with(LineStart, vec![ch('^'), ch('0')])
+            .with(Confirm, vec![Key::Enter])
+            .with(Cancel, vec![Key::Escape, ch('q')])
+            .with(Search, vec![ch('/')])
+            .with(Help, vec![ch('?')])
+            .with(Copy, vec![ch('y')])
+            .with(Grab, vec![ch('m')])
+            .with(Pause, vec![ch(' '), ch('p'), ch('P')])
+            .with(Faster, vec![ch('+'), ch('}')])
+            .with(Slower, vec![ch('-'), ch('{')])
+            .with(Pan, vec![ch('v')])
+            .with(Anchors, vec![ch('g')])
+            .with(NextSection, vec![ch('}')])
+            .with(PrevSection, vec![ch('{')])
+            .with(NextField, vec![Key::Tab])
+            .with(PrevField, Vec::new())
+            .with(Toggle, vec![ch(' ')])
+            .with_in(KeyContext::Pager, PageUp, vec![Key::PageUp, ch('b')])
+            .with_in(KeyContext::Pager, PageDown, vec![Key::PageDown, ch('f')])
+            .with_in(
+                KeyContext::Replay,
+                Cancel,
+                vec![Key::Escape, ch('q'), ch('Q')],
+            )
+            .with_in(KeyContext::TextInput, Cancel, vec![Key::Escape])
+            .with_in(KeyContext::Form, Cancel, vec![Key::Escape])
+            .with_in(KeyContext::Form, NavLeft, vec![Key::ArrowLeft])
+            .with_in(KeyContext::Form, NavRight, vec![Key::ArrowRight])
+    }
+
+    /// The defaults, with vi paging (`b` and `f`) in every widget.
+    pub fn vi() -> Self {
+        let ch = Key::Char;
+        Self::default_arrows()
+            .with(KeyAction::PageUp, vec![Key::PageUp, ch('b')])
+            .with(KeyAction::PageDown, vec![Key::PageDown, ch('f')])
+    }
+
+    /// The defaults, with `w`, `a`, `s` and `d` instead of the vi letters
+    /// for moving, and `W` and `S` for half pages.
+    pub fn wasd() -> Self {
+        use KeyAction::*;
+        let ch = Key::Char;
+        Self::default_arrows()
+            .with(NavUp, vec![Key::ArrowUp, ch('w')])
+            .with(NavLeft, vec![Key::ArrowLeft, ch('a')])
+            .with(NavDown, vec![Key::ArrowDown, ch('s')])
+            .with(NavRight, vec![Key::ArrowRight, ch('d')])
+            .with(HalfPageUp, vec![ch('W')])
+            .with(HalfPageDown, vec![ch('S')])
+    }
+
+    /// The keys of `action` in `context`: those set for the context, if
+    /// any, or else those set for all.
+    pub fn keys(&self, context: KeyContext, action: KeyAction) -> &[Key] {
+        self.overrides
+            .get(&context)
+            .and_then(|o| o.get(&action))
+            .or_else(|| self.keys.get(&action))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Bind `action` to `keys` in every context without an override.
+    pub fn set(&mut self, action: KeyAction, keys: Vec<Key>) {
+        self.keys.insert(action, keys);
+    }
+
+    /// Bind `action` to `keys` in `context` only.
+    pub fn set_in(&mut self, context: KeyContext, action: KeyAction, keys: Vec<Key>) {
+        self.overrides
+            .entry(context)
+            .or_default()
+            .insert(action, keys);
+    }
+
+    /// [`set`](Self::set), as a builder.
+    pub fn with(mut self, action: KeyAction, keys: Vec<Key>) -> Self {
+        self.set(action, keys);
+        self
+    }
+
+    /// [`set_in`](Self::set_in), as a builder.
+    pub fn with_in(mut self, context: KeyContext, action: KeyAction, keys: Vec<Key>) -> Self {
+        self.set_in(context, action, keys);
+        self
+    }
+
+    /// Take the bindings of `other`, replacing those of the same actions
+    /// and contexts, as when loading a player's changes over a preset.
+    pub fn merge(&mut self, other: &KeyBindings) {
+        for (&action, keys) in &other.keys {
+            self.set(action, keys.clone());
+        }
+        for (&context, overrides) in &other.overrides {
+            for (&action, keys) in overrides {
+                self.set_in(context, action, keys.clone());
+            }
+        }
+    }
+
+    /// The keys bound to two actions of the same widget, which only the
+    /// first of them would get.
+    pub fn validate(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for context in KeyContext::ALL {
+            let actions = context.actions();
+            for (i, &a) in actions.iter().enumerate() {
+                for &b in &actions[i + 1..] {
+                    if context.may_share(a, b) {
+                        continue;
+                    }
+                    let b_keys = self.keys(context, b);
+                    for key in self.keys(context, a).iter().filter(|k| b_keys.contains(k)) {
+                        conflicts.push(Conflict {
+                            context,
+                            key: key.clone(),
+                            actions: (a, b),
+                        });
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FormKeys, MenuKeys, PagerKeys, ReplayKeys, TextInputKeys};
+
+    #[test]
+    fn presets_bind_every_action() {
+        for (name, kb) in [
+            ("arrows", KeyBindings::default_arrows()),
+            ("vi", KeyBindings::vi()),
+            ("wasd", KeyBindings::wasd()),
+        ] {
+            for context in KeyContext::ALL {
+                for &action in context.actions() {
+                    // Shift+Tab moves back in forms without a binding.
+                    if action != KeyAction::PrevField {
+                        let keys = kb.keys(context, action);
+                        assert!(!keys.is_empty(), "{name}: {action:?} in {context:?}");
+                    }
+                }
+            }
+            for action in KeyAction::ALL {
+                assert!(kb.keys.contains_key(&action), "{name}: {action:?}");
+            }
+            assert_eq!(kb.validate(), [], "{name}");
+        }
+    }
+
+    #[test]
+    fn arrows_give_the_widget_defaults() {
+        let kb = KeyBindings::default_arrows();
+        assert_eq!(MenuKeys::from_bindings(&kb), MenuKeys::default());
+        assert_eq!(PagerKeys::from_bindings(&kb), PagerKeys::default());
+        assert_eq!(TextInputKeys::from_bindings(&kb), TextInputKeys::default());
+        assert_eq!(FormKeys::from_bindings(&kb), FormKeys::default());
+        assert_eq!(ReplayKeys::from_bindings(&kb), ReplayKeys::default());
+    }
+
+    #[test]
+    fn overrides_and_merge() {
+        use KeyAction::*;
+        let mut kb = KeyBindings::wasd();
+        assert_eq!(
+            MenuKeys::from_bindings(&kb).left,
+            [Key::ArrowLeft, Key::Char('a')]
+        );
+        // Forms keep their letter-free override.
+        assert_eq!(FormKeys::from_bindings(&kb).prev_choice, [Key::ArrowLeft]);
+
+        let player = KeyBindings::new()
+            .with(Cancel, vec![Key::Char('x')])
+            .with_in(KeyContext::Menu, Confirm, vec![Key::Char(' ')]);
+        kb.merge(&player);
+        assert_eq!(kb.keys(KeyContext::Pager, Cancel), [Key::Char('x')]);
+        assert_eq!(
+            kb.keys(KeyContext::Replay, Cancel),
+            [Key::Escape, Key::Char('q'), Key::Char('Q')]
+        );
+        assert_eq!(kb.keys(KeyContext::Menu, Confirm), [Key::Char(' ')]);
+        assert_eq!(kb.keys(KeyContext::Form, Confirm), [Key::Enter]);
+        assert_eq!(KeyBindings::new().keys(KeyContext::Menu, Confirm), []);
+    }
+
+    #[test]
+    fn conflicts_within_a_widget() {
+        use KeyAction::*;
+        let kb = KeyBindings::default_arrows()
+            // 'h' also moves left: a conflict in every context with both.
+            .with(Help, vec![Key::Char('h')])
+            // 'p' pauses the replay, and only the pager copies.
+            .with(Copy, vec![Key::Char('p')])
+            // Forms do not move left with 'h', but confirm with it now.
+            .with_in(KeyContext::Form, Confirm, vec![Key::Char('h'), Key::Enter]);
+        let conflicts = kb.validate();
+        assert_eq!(
+            conflicts,
+            [Conflict {
+                context: KeyContext::Replay,
+                key: Key::Char('h'),
+                actions: (NavLeft, Help),
+            }]
+        );
+        // Only widgets using both actions are concerned, and the pager's
+        // shared anchors key is allowed.
+        let kb = kb.with_in(KeyContext::Form, NavLeft, vec![Key::Char('h')]);
+        let conflicts = kb.validate();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].context, KeyContext::Form);
+        assert_eq!(conflicts[0].actions, (Confirm, NavLeft));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let kb = KeyBindings::wasd().with_in(KeyContext::Menu, KeyAction::Grab, vec![Key::F(2)]);
+        let json = serde_json::to_string(&kb).unwrap();
+        let back: KeyBindings = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, kb);
+    }
+}
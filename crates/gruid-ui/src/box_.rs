@@ -12,6 +12,16 @@ pub enum Alignment {
 }
 
 /// Decoration for a box drawn around a widget.
+///
+/// Besides the border, a box can clear its interior ([`with_fill`]), inset
+/// the content area ([`with_padding`]) and cast a one-cell drop shadow to
+/// the right and below ([`with_shadow`]). Widgets should use
+/// [`content_range`] to find where their content goes.
+///
+/// [`with_fill`]: Self::with_fill
+/// [`with_padding`]: Self::with_padding
+/// [`with_shadow`]: Self::with_shadow
+/// [`content_range`]: Self::content_range
 #[derive(Debug, Clone)]
 pub struct BoxDecor {
     /// Style for the box border characters.
@@ -24,6 +34,13 @@ pub struct BoxDecor {
     pub align_title: Alignment,
     /// Alignment of the footer on the bottom border.
     pub align_footer: Alignment,
+    /// Cell the interior is cleared to before drawing, if any.
+    pub fill: Option<Cell>,
+    /// Blank columns (`x`) and rows (`y`) between the border and the
+    /// content, on each side.
+    pub padding: Point,
+    /// Style of the drop shadow, if any.
+    pub shadow: Option<Style>,
 }
 
 impl BoxDecor {
@@ -35,54 +52,131 @@ impl BoxDecor {
             footer: StyledText::text(""),
             align_title: Alignment::Center,
             align_footer: Alignment::Center,
+            fill: None,
+            padding: Point::ZERO,
+            shadow: None,
         }
     }
 
-    /// Draw the box border into the grid, using relative coordinates.
-    /// Returns the inner range (relative, the area inside the border).
+    /// Clear the interior to `cell` when drawing, so nothing previously
+    /// drawn shows through gaps in the content.
+    pub fn with_fill(mut self, cell: Cell) -> Self {
+        self.fill = Some(cell);
+        self
+    }
+
+    /// Inset the content by `x` columns and `y` rows inside the border.
+    /// Title and footer are inset horizontally too.
+    pub fn with_padding(mut self, x: i32, y: i32) -> Self {
+        self.padding = Point::new(x.max(0), y.max(0));
+        self
+    }
+
+    /// Draw a one-cell shadow along the right and bottom edges of the box.
+    pub fn with_shadow(mut self, style: Style) -> Self {
+        self.shadow = Some(style);
+        self
+    }
+
+    /// Space taken by the decoration before (top-left) and after
+    /// (bottom-right) the content.
+    pub(crate) fn insets(&self) -> (Point, Point) {
+        let before = Point::new(1 + self.padding.x, 1 + self.padding.y);
+        let s = self.shadow.is_some() as i32;
+        (before, before.shift(s, s))
+    }
+
+    /// The title without markup or surrounding spaces, if there is one,
+    /// to name the widget in accessibility descriptions.
+    pub(crate) fn label(&self) -> Option<String> {
+        let title = self.title.plain();
+        let title = title.trim();
+        (!title.is_empty()).then(|| title.to_string())
+    }
+
+    /// The outer range of the box within `outer`: everything but the
+    /// shadow.
+    fn frame_range(&self, outer: Range) -> Range {
+        let s = self.shadow.is_some() as i32;
+        outer.shift(0, 0, -s, -s)
+    }
+
+    /// Where content goes when the box (shadow included) is drawn in
+    /// `outer`: inside the border and padding.
+    pub fn content_range(&self, outer: Range) -> Range {
+        let (before, after) = self.insets();
+        outer.shift(before.x, before.y, -after.x, -after.y)
+    }
+
+    /// Draw the box filling the grid, using relative coordinates. With a
+    /// shadow, the last column and row are left for it.
+    ///
+    /// Returns the relative [`content_range`](Self::content_range).
     pub fn draw(&self, grid: &Grid) -> Range {
-        let w = grid.width();
-        let h = grid.height();
+        self.draw_at(grid, grid.range_())
+    }
+
+    /// Like [`draw`](Self::draw), but in the relative range `rg` of `grid`.
+    /// Parts falling outside `grid`, such as the shadow of a box in a
+    /// corner, are clipped.
+    pub fn draw_at(&self, grid: &Grid, rg: Range) -> Range {
+        let frame = self.frame_range(rg);
+        let (w, h) = (frame.width(), frame.height());
         if w < 2 || h < 2 {
-            return grid.range_();
+            return rg;
         }
-
-        let x1 = w;
-        let y1 = h;
+        let Point { x: x0, y: y0 } = frame.min;
+        let Point { x: x1, y: y1 } = frame.max;
         let s = self.style;
 
+        // Shadow first, so the box overlaps it.
+        if let Some(shadow) = self.shadow {
+            let cell = Cell::default().with_char(' ').with_style(shadow);
+            for y in (y0 + 1)..=y1 {
+                grid.set(Point::new(x1, y), cell);
+            }
+            for x in (x0 + 1)..x1 {
+                grid.set(Point::new(x, y1), cell);
+            }
+        }
+
+        if let Some(cell) = self.fill {
+            grid.slice(frame.shift(1, 1, -1, -1)).fill(cell);
+        }
+
         // Corners
-        set(grid, Point::new(0, 0), '\u{250c}', s);
-        set(grid, Point::new(x1 - 1, 0), '\u{2510}', s);
-        set(grid, Point::new(0, y1 - 1), '\u{2514}', s);
+        set(grid, Point::new(x0, y0), '\u{250c}', s);
+        set(grid, Point::new(x1 - 1, y0), '\u{2510}', s);
+        set(grid, Point::new(x0, y1 - 1), '\u{2514}', s);
         set(grid, Point::new(x1 - 1, y1 - 1), '\u{2518}', s);
 
         // Top and bottom borders
-        for x in 1..(x1 - 1) {
-            set(grid, Point::new(x, 0), '\u{2500}', s);
+        for x in (x0 + 1)..(x1 - 1) {
+            set(grid, Point::new(x, y0), '\u{2500}', s);
             set(grid, Point::new(x, y1 - 1), '\u{2500}', s);
         }
 
         // Left and right borders
-        for y in 1..(y1 - 1) {
-            set(grid, Point::new(0, y), '\u{2502}', s);
+        for y in (y0 + 1)..(y1 - 1) {
+            set(grid, Point::new(x0, y), '\u{2502}', s);
             set(grid, Point::new(x1 - 1, y), '\u{2502}', s);
         }
 
+        let px = self.padding.x;
+
         // Draw title on top border using StyledText::iter for markup support.
         if !self.title.content().is_empty() {
-            let top_line = grid.slice(Range::new(1, 0, x1 - 1, 1));
+            let top_line = grid.slice(Range::new(x0 + 1 + px, y0, x1 - 1 - px, y0 + 1));
             draw_text_line(&self.title, &top_line, self.align_title);
         }
 
         // Draw footer on bottom border using StyledText::iter for markup support.
         if !self.footer.content().is_empty() {
-            let bot_line = grid.slice(Range::new(1, y1 - 1, x1 - 1, y1));
+            let bot_line = grid.slice(Range::new(x0 + 1 + px, y1 - 1, x1 - 1 - px, y1));
             draw_text_line(&self.footer, &bot_line, self.align_footer);
         }
 
-        // Inner range (relative)
-        Range::new(1, 1, x1 - 1, y1 - 1)
+        self.content_range(rg)
     }
 }
 
@@ -111,3 +205,58 @@ fn draw_text_line(stt: &StyledText, gd: &Grid, align: Alignment) {
     let shifted = gd.slice(Range::new(offset, 0, w, gd.height()));
     stt.draw(&shifted);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::Color;
+
+    fn rows(grid: &Grid) -> Vec<String> {
+        (0..grid.height())
+            .map(|y| {
+                (0..grid.width())
+                    .map(|x| grid.at(Point::new(x, y)).ch)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn filled_padded_shadowed_box_in_corner() {
+        let grid = Grid::new(10, 5);
+        grid.fill(Cell::default().with_char('x'));
+        let shadow = Style::default().with_bg(Color::from_rgb(20, 20, 20));
+        let mut bd = BoxDecor::new()
+            .with_fill(Cell::default().with_char('.'))
+            .with_padding(1, 0)
+            .with_shadow(shadow);
+        bd.title = StyledText::text("ab");
+
+        // A 6x4 box in the top-right corner: the shadow column falls off
+        // the grid.
+        let content = bd.draw_at(&grid, Range::new(4, 0, 11, 5));
+        assert_eq!(content, Range::new(6, 1, 8, 3));
+        assert_eq!(
+            rows(&grid),
+            [
+                "xxxx┌─ab─┐",
+                "xxxx│....│",
+                "xxxx│....│",
+                "xxxx└────┘",
+                "xxxxx     ",
+            ]
+        );
+        assert_eq!(grid.at(Point::new(5, 4)).style, shadow);
+        assert_eq!(grid.at(Point::new(4, 4)).ch, 'x');
+        assert_eq!(bd.content_range(Range::new(4, 0, 11, 5)), content);
+    }
+
+    #[test]
+    fn plain_box_content_is_inside_border() {
+        let bd = BoxDecor::new();
+        let grid = Grid::new(5, 4);
+        assert_eq!(bd.draw(&grid), Range::new(1, 1, 4, 3));
+        assert_eq!(bd.content_range(grid.range_()), Range::new(1, 1, 4, 3));
+        assert_eq!(grid.at(Point::new(2, 2)).ch, ' ');
+    }
+}
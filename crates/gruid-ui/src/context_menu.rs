@@ -0,0 +1,310 @@
+//! Small popup menu anchored at a grid cell.
+
+use gruid_core::messages::{MouseAction, Msg};
+use gruid_core::{Cell, Grid, Point, Range, Style};
+
+use crate::{
+    AccessibilityNode, BoxDecor, Menu, MenuAction, MenuConfig, MenuEntry, MenuKeys, MenuStyle,
+};
+
+/// Configuration for a [`ContextMenu`] widget.
+#[derive(Debug, Clone)]
+pub struct ContextMenuConfig {
+    /// Grid the popup is placed in, usually the whole screen.
+    pub grid: Grid,
+    /// Cell the popup opens next to, such as the one right-clicked.
+    pub anchor: Point,
+    /// The menu entries.
+    pub entries: Vec<MenuEntry>,
+    /// Key bindings.
+    pub keys: MenuKeys,
+    /// Box drawn around the entries.
+    pub box_: BoxDecor,
+    /// Visual style of the entries. The layout is always a single column.
+    pub style: MenuStyle,
+    /// Style of the glyph drawn on the border next to the anchor, pointing
+    /// at it, if any.
+    pub connector: Option<Style>,
+}
+
+impl ContextMenuConfig {
+    /// Use `keys`, such as [`MenuKeys::from_bindings`] of the player's
+    /// preset.
+    pub fn with_keys(mut self, keys: MenuKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
+/// A popup menu opened next to a grid cell, sized to its entries.
+///
+/// The popup goes to the right of the anchor, its top row level with it.
+/// When it would overflow the grid it flips to the left of the anchor, or
+/// upwards so that its bottom row is level with it; with no room on either
+/// side it is pushed back inside the grid, over the anchor.
+///
+/// It handles input like a [`Menu`], returning the same actions: a button
+/// pressed outside it returns [`MenuAction::Quit`]. Mouse positions are
+/// relative to the configured grid, like [`bounds`](Self::bounds), which
+/// hosts can use to tell whether a click belongs to the open popup.
+#[derive(Debug, Clone)]
+pub struct ContextMenu {
+    grid: Grid,
+    anchor: Point,
+    bounds: Range,
+    menu: Menu,
+    /// Shadow width, excluded from the box frame.
+    shadow: i32,
+    connector: Option<Style>,
+}
+
+impl ContextMenu {
+    /// Create a new context menu from the given configuration.
+    pub fn new(config: ContextMenuConfig) -> Self {
+        let (before, after) = config.box_.insets();
+        let text_w = config
+            .entries
+            .iter()
+            .map(|e| e.text.size().x)
+            .max()
+            .unwrap_or(0);
+        let size = Point::new(
+            text_w + before.x + after.x,
+            config.entries.len() as i32 + before.y + after.y,
+        );
+        let bounds = place(config.grid.size(), config.anchor, size);
+        let menu = Menu::new(MenuConfig {
+            grid: config.grid.slice(bounds),
+            entries: config.entries,
+            provider: None,
+            keys: config.keys,
+            box_: Some(config.box_.clone()),
+            style: MenuStyle {
+                layout: Point::new(1, 0),
+                ..config.style
+            },
+            reorderable: false,
+        });
+        Self {
+            grid: config.grid,
+            anchor: config.anchor,
+            bounds,
+            menu,
+            shadow: config.box_.shadow.is_some() as i32,
+            connector: config.connector,
+        }
+    }
+
+    /// Process an input message and return the resulting action.
+    pub fn update(&mut self, msg: Msg) -> MenuAction {
+        match msg {
+            Msg::KeyDown { .. } => self.menu.update(msg),
+            Msg::Mouse { action, pos, .. } => {
+                let press = matches!(
+                    action,
+                    MouseAction::Main | MouseAction::Auxiliary | MouseAction::Secondary
+                );
+                if press && !self.bounds.contains(pos) {
+                    return MenuAction::Quit;
+                }
+                self.menu.update(self.bounds.rel_msg(msg))
+            }
+            _ => MenuAction::Pass,
+        }
+    }
+
+    /// Draw the popup into its grid. Returns its range in the grid.
+    pub fn draw(&self) -> Range {
+        self.menu.draw();
+        if let (Some(style), Some(p)) = (self.connector, self.connector_pos()) {
+            let ch = if p.x < self.anchor.x { '▶' } else { '◀' };
+            self.grid
+                .set(p, Cell::default().with_char(ch).with_style(style));
+        }
+        self.bounds
+    }
+
+    /// Range covered by the popup, shadow included, relative to the
+    /// configured grid.
+    pub fn bounds(&self) -> Range {
+        self.bounds
+    }
+
+    /// Whether `p` is inside the popup.
+    pub fn contains(&self, p: Point) -> bool {
+        self.bounds.contains(p)
+    }
+
+    /// The cell the popup was opened next to.
+    pub fn anchor(&self) -> Point {
+        self.anchor
+    }
+
+    /// Index of the active entry.
+    pub fn active(&self) -> usize {
+        self.menu.active()
+    }
+
+    /// Key of the active entry, if it has one.
+    pub fn active_key(&self) -> Option<u64> {
+        self.menu.active_key()
+    }
+
+    /// The menu entries.
+    pub fn entries(&self) -> &[MenuEntry] {
+        self.menu.entries()
+    }
+
+    /// Return the last action.
+    pub fn action(&self) -> MenuAction {
+        self.menu.action()
+    }
+
+    /// Describe the popup for accessibility, like [`Menu::describe`].
+    pub fn describe(&self) -> AccessibilityNode {
+        self.menu.describe()
+    }
+
+    /// Border cell next to the anchor, when the popup is beside it.
+    fn connector_pos(&self) -> Option<Point> {
+        let frame = self.bounds.shift(0, 0, -self.shadow, -self.shadow);
+        if frame.is_empty() || !(frame.min.y..frame.max.y).contains(&self.anchor.y) {
+            return None;
+        }
+        let x = if frame.min.x == self.anchor.x + 1 {
+            frame.min.x
+        } else if frame.max.x + self.shadow == self.anchor.x {
+            frame.max.x - 1
+        } else {
+            return None;
+        };
+        Some(Point::new(x, self.anchor.y))
+    }
+}
+
+/// Range of a popup of `size` opened next to `anchor` in a grid of
+/// `grid_size`.
+fn place(grid_size: Point, anchor: Point, size: Point) -> Range {
+    let w = size.x.min(grid_size.x);
+    let h = size.y.min(grid_size.y);
+    let x = if anchor.x + 1 + w <= grid_size.x {
+        anchor.x + 1
+    } else if anchor.x - w >= 0 {
+        anchor.x - w
+    } else {
+        (anchor.x + 1).min(grid_size.x - w).max(0)
+    };
+    let y = if anchor.y + h <= grid_size.y {
+        anchor.y
+    } else if anchor.y + 1 - h >= 0 {
+        anchor.y + 1 - h
+    } else {
+        (grid_size.y - h).max(0)
+    };
+    Range::new(x, y, x + w, y + h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StyledText;
+    use gruid_core::messages::Key;
+    use std::time::Instant;
+
+    fn make(anchor: Point) -> ContextMenu {
+        let entry = |text: &str, key: char| {
+            let mut e = MenuEntry::new(StyledText::text(text));
+            e.keys.push(Key::Char(key));
+            e
+        };
+        ContextMenu::new(ContextMenuConfig {
+            grid: Grid::new(20, 10),
+            anchor,
+            entries: vec![entry("Walk here", 'w'), entry("Examine", 'e')],
+            keys: MenuKeys::default(),
+            box_: BoxDecor::new(),
+            style: MenuStyle::default(),
+            connector: Some(Style::default()),
+        })
+    }
+
+    fn mouse(action: MouseAction, x: i32, y: i32) -> Msg {
+        Msg::Mouse {
+            action,
+            pos: Point::new(x, y),
+            modifiers: Default::default(),
+            time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn flips_at_edges() {
+        // 11x4 popups in a 20x10 grid.
+        let cases = [
+            // Room everywhere: right of the anchor, going down.
+            ((2, 2), Range::new(3, 2, 14, 6)),
+            // Right edge: flips left.
+            ((15, 2), Range::new(4, 2, 15, 6)),
+            // Bottom edge: flips up.
+            ((2, 8), Range::new(3, 5, 14, 9)),
+            // Bottom-right corner: both.
+            ((19, 9), Range::new(8, 6, 19, 10)),
+            // Top-left corner.
+            ((0, 0), Range::new(1, 0, 12, 4)),
+            // Room on neither side: pushed inside, over the anchor.
+            ((9, 2), Range::new(9, 2, 20, 6)),
+        ];
+        for ((x, y), want) in cases {
+            let cm = make(Point::new(x, y));
+            assert_eq!(cm.bounds(), want, "anchor {x},{y}");
+        }
+        // Too tall for the grid both ways: pushed to the top.
+        assert_eq!(
+            place(Point::new(20, 5), Point::new(0, 2), Point::new(5, 4)),
+            Range::new(1, 1, 6, 5)
+        );
+    }
+
+    #[test]
+    fn connector_points_at_anchor() {
+        let cm = make(Point::new(2, 3));
+        cm.draw();
+        assert_eq!(cm.grid.at(Point::new(3, 3)).ch, '◀');
+        assert_eq!(cm.grid.at(Point::new(4, 4)).ch, 'W');
+        let cm = make(Point::new(19, 9));
+        cm.draw();
+        assert_eq!(cm.grid.at(Point::new(18, 9)).ch, '▶');
+        // Over the anchor, there is nowhere to point from.
+        assert_eq!(make(Point::new(9, 2)).connector_pos(), None);
+    }
+
+    #[test]
+    fn click_outside_dismisses() {
+        let mut cm = make(Point::new(2, 2));
+        assert_eq!(cm.update(mouse(MouseAction::Move, 0, 0)), MenuAction::Pass);
+        assert_eq!(
+            cm.update(mouse(MouseAction::Secondary, 15, 2)),
+            MenuAction::Quit
+        );
+        assert_eq!(cm.update(mouse(MouseAction::Main, 2, 2)), MenuAction::Quit);
+        // Clicks on the border do nothing; on an entry, they invoke it.
+        assert_eq!(cm.update(mouse(MouseAction::Main, 3, 2)), MenuAction::Pass);
+        assert_eq!(cm.update(mouse(MouseAction::Move, 5, 4)), MenuAction::Move);
+        assert_eq!(cm.active(), 1);
+        assert_eq!(
+            cm.update(mouse(MouseAction::Main, 5, 4)),
+            MenuAction::Invoke
+        );
+    }
+
+    #[test]
+    fn shortcut_invokes() {
+        let mut cm = make(Point::new(2, 2));
+        let key = |k| Msg::key(Key::Char(k));
+        assert_eq!(cm.update(key('e')), MenuAction::Invoke);
+        assert_eq!(cm.active(), 1);
+        assert_eq!(cm.update(key('w')), MenuAction::Invoke);
+        assert_eq!(cm.active(), 0);
+        assert_eq!(cm.update(Msg::key(Key::Escape)), MenuAction::Quit);
+    }
+}
@@ -0,0 +1,207 @@
+use std::cell::{Cell as StdCell, RefCell};
+use std::rc::Rc;
+
+use gruid_core::{AttrMask, Cell, Color, Grid, Range, Style};
+
+use crate::StyledText;
+
+/// A label whose text comes from a closure evaluated on every draw, for
+/// status readouts such as HP or turn counters.
+///
+/// The wrapped layout is cached and only redone when the closure returns a
+/// different string, so static values cost little per frame. With
+/// [`with_flash`](Self::with_flash), a change is highlighted for a number of
+/// draws.
+///
+/// The closure usually needs game state the model also owns. Share it
+/// through an `Rc<RefCell<T>>`, as [`bound`](Self::bound) does:
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use gruid_ui::{DynLabel, StyledText};
+///
+/// struct Player { hp: i32 }
+///
+/// let player = Rc::new(RefCell::new(Player { hp: 10 }));
+/// let hp = DynLabel::bound(8, 1, player.clone(), |p| {
+///     StyledText::textf(format!("HP {}", p.hp))
+/// });
+/// player.borrow_mut().hp -= 3;
+/// let grid = gruid_core::Grid::new(8, 1);
+/// hp.draw(&grid);
+/// assert_eq!(grid.at(gruid_core::Point::new(3, 0)).ch, '7');
+/// ```
+pub struct DynLabel {
+    width: i32,
+    height: i32,
+    source: Box<dyn Fn() -> StyledText>,
+    flash: Option<(Style, u32)>,
+    /// Text and style of the last draw, with its wrapped layout.
+    cache: RefCell<Option<(String, Style, StyledText)>>,
+    /// Draws left with the flash style.
+    flashing: StdCell<u32>,
+    #[cfg(test)]
+    layouts: StdCell<usize>,
+}
+
+impl DynLabel {
+    /// A `width` × `height` label showing the text returned by `source`.
+    pub fn new(width: i32, height: i32, source: Box<dyn Fn() -> StyledText>) -> Self {
+        Self {
+            width,
+            height,
+            source,
+            flash: None,
+            cache: RefCell::new(None),
+            flashing: StdCell::new(0),
+            #[cfg(test)]
+            layouts: StdCell::new(0),
+        }
+    }
+
+    /// A label rendering shared state with `f`.
+    pub fn bound<T: 'static>(
+        width: i32,
+        height: i32,
+        state: Rc<RefCell<T>>,
+        f: fn(&T) -> StyledText,
+    ) -> Self {
+        Self::new(width, height, Box::new(move || f(&state.borrow())))
+    }
+
+    /// Highlight the label with `style` for the `draws` draws following a
+    /// change of text. Non-default colours and attributes of `style`
+    /// override the text's own.
+    pub fn with_flash(mut self, style: Style, draws: u32) -> Self {
+        self.flash = Some((style, draws));
+        self
+    }
+
+    /// Whether the flash highlight is showing.
+    pub fn is_flashing(&self) -> bool {
+        self.flashing.get() > 0
+    }
+
+    /// Evaluate the closure and draw the result into `grid`, wrapped to the
+    /// label's width and truncated to its height. Returns the label's range.
+    pub fn draw(&self, grid: &Grid) -> Range {
+        let stt = (self.source)();
+        let mut cache = self.cache.borrow_mut();
+        let unchanged = cache
+            .as_ref()
+            .is_some_and(|(s, st, _)| s == stt.content() && *st == stt.style());
+        if !unchanged {
+            if cache.is_some()
+                && let Some((_, draws)) = self.flash
+            {
+                self.flashing.set(draws);
+            }
+            #[cfg(test)]
+            self.layouts.set(self.layouts.get() + 1);
+            let layout = stt.format(self.width.max(0) as usize);
+            *cache = Some((stt.content().to_string(), stt.style(), layout));
+        }
+        let (_, _, layout) = cache.as_ref().expect("layout cached above");
+
+        let rg = Range::new(0, 0, self.width, self.height);
+        let area = grid.slice(rg);
+        area.fill(Cell::default().with_char(' ').with_style(layout.style()));
+        layout.draw(&area);
+
+        let left = self.flashing.get();
+        if left > 0 {
+            self.flashing.set(left - 1);
+            if let Some((flash, _)) = self.flash {
+                area.map_cells(|_, c| c.with_style(overlay(c.style, flash)));
+            }
+        }
+        rg
+    }
+}
+
+/// `base` with the non-default parts of `top`.
+pub(crate) fn overlay(mut base: Style, top: Style) -> Style {
+    if top.fg != Color::DEFAULT {
+        base.fg = top.fg;
+    }
+    if top.bg != Color::DEFAULT {
+        base.bg = top.bg;
+    }
+    if top.attrs != AttrMask::NONE {
+        base.attrs = top.attrs;
+    }
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::Point;
+
+    fn row(grid: &Grid, y: i32) -> String {
+        (0..grid.width())
+            .map(|x| grid.at(Point::new(x, y)).ch)
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_text_skips_layout() {
+        let turn = Rc::new(RefCell::new(1));
+        let label = DynLabel::bound(10, 1, turn.clone(), |t| {
+            StyledText::textf(format!("Turn {t}"))
+        });
+        let grid = Grid::new(10, 1);
+        for _ in 0..5 {
+            label.draw(&grid);
+        }
+        assert_eq!(label.layouts.get(), 1);
+        assert_eq!(row(&grid, 0), "Turn 1    ");
+
+        *turn.borrow_mut() = 2;
+        label.draw(&grid);
+        label.draw(&grid);
+        assert_eq!(label.layouts.get(), 2);
+        assert_eq!(row(&grid, 0), "Turn 2    ");
+    }
+
+    #[test]
+    fn flash_counts_down_per_draw() {
+        let hp = Rc::new(RefCell::new(10));
+        let red = Style::default().with_bg(Color::from_rgb(200, 0, 0));
+        let label = DynLabel::bound(6, 1, hp.clone(), |hp| StyledText::textf(format!("HP {hp}")))
+            .with_flash(red, 2);
+        let grid = Grid::new(6, 1);
+        let bg = |grid: &Grid| grid.at(Point::new(0, 0)).style.bg;
+
+        // The first draw is not a change.
+        label.draw(&grid);
+        assert!(!label.is_flashing());
+        assert_eq!(bg(&grid), Color::DEFAULT);
+
+        *hp.borrow_mut() = 7;
+        label.draw(&grid);
+        assert_eq!(bg(&grid), red.bg);
+        assert!(label.is_flashing());
+        label.draw(&grid);
+        assert_eq!(bg(&grid), red.bg);
+        assert!(!label.is_flashing());
+        label.draw(&grid);
+        assert_eq!(bg(&grid), Color::DEFAULT);
+    }
+
+    #[test]
+    fn oversized_content_is_wrapped_and_truncated() {
+        let label = DynLabel::new(
+            6,
+            2,
+            Box::new(|| StyledText::text("one two three four five")),
+        );
+        let grid = Grid::new(8, 3);
+        grid.fill(Cell::default().with_char('.'));
+        assert_eq!(label.draw(&grid), Range::new(0, 0, 6, 2));
+        assert_eq!(row(&grid, 0), "one   ..");
+        assert_eq!(row(&grid, 1), "two   ..");
+        assert_eq!(row(&grid, 2), "........");
+    }
+}
@@ -0,0 +1,939 @@
+//! Form grouping labelled inputs, toggles, pickers and menus, with focus
+//! traversal and validation.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use gruid_core::messages::{Key, ModMask, MouseAction, Msg};
+use gruid_core::{Cell, Grid, Point, Range, Style};
+
+use crate::{
+    AccessibilityNode, BoxDecor, KeyAction, KeyBindings, KeyContext, Menu, MenuConfig, MenuEntry,
+    MenuKeys, MenuStyle, Role, StyledText, TextInput, TextInputConfig, TextInputKeys,
+    TextInputStyle,
+};
+
+/// Value of a form field.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldValue {
+    /// Content of a text input.
+    Text(String),
+    /// State of a toggle.
+    Bool(bool),
+    /// Index of the selected choice of a picker, or of the active entry of
+    /// a menu.
+    Choice(usize),
+}
+
+/// Checks a field value, returning the message to show under the field
+/// when it is invalid.
+pub type Validator = Box<dyn Fn(&FieldValue) -> Result<(), String>>;
+
+/// Kind of widget of a [`FormField`].
+#[derive(Debug, Clone)]
+enum FieldKind {
+    Input {
+        content: String,
+        mask: Option<char>,
+    },
+    Toggle(bool),
+    Picker {
+        choices: Vec<String>,
+        selected: usize,
+    },
+    Menu {
+        entries: Vec<MenuEntry>,
+        height: i32,
+    },
+}
+
+/// Description of a field, turned into a widget by [`Form::new`].
+pub struct FormField {
+    name: String,
+    label: StyledText,
+    kind: FieldKind,
+    disabled: bool,
+    validator: Option<Validator>,
+    range: Option<Range>,
+}
+
+impl fmt::Debug for FormField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormField")
+            .field("name", &self.name)
+            .field("label", &self.label)
+            .field("kind", &self.kind)
+            .field("disabled", &self.disabled)
+            .field("validator", &self.validator.is_some())
+            .field("range", &self.range)
+            .finish()
+    }
+}
+
+impl FormField {
+    fn new(name: &str, label: StyledText, kind: FieldKind) -> Self {
+        Self {
+            name: name.to_string(),
+            label,
+            kind,
+            disabled: false,
+            validator: None,
+            range: None,
+        }
+    }
+
+    /// A single-line text input, initially holding `content`.
+    pub fn input(name: &str, label: StyledText, content: &str) -> Self {
+        Self::new(
+            name,
+            label,
+            FieldKind::Input {
+                content: content.to_string(),
+                mask: None,
+            },
+        )
+    }
+
+    /// A checkbox, switched with the toggle keys or a click.
+    pub fn toggle(name: &str, label: StyledText, on: bool) -> Self {
+        Self::new(name, label, FieldKind::Toggle(on))
+    }
+
+    /// A choice among `choices` shown one at a time, cycled with the
+    /// previous and next choice keys, or by clicking on either side.
+    pub fn picker(name: &str, label: StyledText, choices: Vec<String>, selected: usize) -> Self {
+        let selected = selected.min(choices.len().saturating_sub(1));
+        Self::new(name, label, FieldKind::Picker { choices, selected })
+    }
+
+    /// A menu `height` rows high, whose value is its active entry.
+    pub fn menu(name: &str, label: StyledText, entries: Vec<MenuEntry>, height: i32) -> Self {
+        Self::new(
+            name,
+            label,
+            FieldKind::Menu {
+                entries,
+                height: height.max(1),
+            },
+        )
+    }
+
+    /// Hide the content of an input behind `mask`, as for passwords. No
+    /// effect on other fields.
+    pub fn with_mask(mut self, mask: char) -> Self {
+        if let FieldKind::Input { mask: m, .. } = &mut self.kind {
+            *m = Some(mask);
+        }
+        self
+    }
+
+    /// Check the value on submission. A line is reserved under the field
+    /// for the error message.
+    pub fn with_validator(
+        mut self,
+        validator: impl Fn(&FieldValue) -> Result<(), String> + 'static,
+    ) -> Self {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Start disabled: the field is skipped by focus traversal, ignores
+    /// input and is not validated.
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Place the field, label included, at `range` relative to the form
+    /// content, instead of below the previous field. The error line, if
+    /// any, is the one below it.
+    pub fn with_range(mut self, range: Range) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    fn height(&self) -> i32 {
+        match self.kind {
+            FieldKind::Menu { height, .. } => height,
+            _ => 1,
+        }
+    }
+}
+
+/// Configuration for a [`Form`] widget.
+#[derive(Debug)]
+pub struct FormConfig {
+    /// Grid to draw into.
+    pub grid: Grid,
+    /// The fields, in focus order.
+    pub fields: Vec<FormField>,
+    /// Key bindings.
+    pub keys: FormKeys,
+    /// Optional box decoration.
+    pub box_: Option<BoxDecor>,
+    /// Visual style.
+    pub style: FormStyle,
+}
+
+impl FormConfig {
+    /// Use `keys`, such as [`FormKeys::from_bindings`] of the player's
+    /// preset.
+    pub fn with_keys(mut self, keys: FormKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
+/// Key bindings for a form. Shift+Tab always moves focus backwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormKeys {
+    /// Keys moving focus to the next enabled field.
+    pub next: Vec<Key>,
+    /// Keys moving focus to the previous enabled field.
+    pub prev: Vec<Key>,
+    /// Keys submitting the form.
+    pub submit: Vec<Key>,
+    /// Keys cancelling the form.
+    pub cancel: Vec<Key>,
+    /// Keys switching a toggle.
+    pub toggle: Vec<Key>,
+    /// Keys selecting the previous choice of a picker.
+    pub prev_choice: Vec<Key>,
+    /// Keys selecting the next choice of a picker.
+    pub next_choice: Vec<Key>,
+}
+
+impl Default for FormKeys {
+    fn default() -> Self {
+        Self {
+            next: vec![Key::Tab],
+            prev: Vec::new(),
+            submit: vec![Key::Enter],
+            cancel: vec![Key::Escape],
+            toggle: vec![Key::Char(' ')],
+            prev_choice: vec![Key::ArrowLeft],
+            next_choice: vec![Key::ArrowRight],
+        }
+    }
+}
+
+impl FormKeys {
+    /// The keys bound in `bindings` for the [`KeyContext::Form`] actions.
+    pub fn from_bindings(bindings: &KeyBindings) -> Self {
+        use KeyAction::*;
+        let keys = |action| bindings.keys(KeyContext::Form, action).to_vec();
+        Self {
+            next: keys(NextField),
+            prev: keys(PrevField),
+            submit: keys(Confirm),
+            cancel: keys(Cancel),
+            toggle: keys(Toggle),
+            prev_choice: keys(NavLeft),
+            next_choice: keys(NavRight),
+        }
+    }
+}
+
+/// Visual style for a form.
+#[derive(Debug, Clone, Default)]
+pub struct FormStyle {
+    /// Style for labels.
+    pub label: Style,
+    /// Style for the label of the focused field.
+    pub focused: Style,
+    /// Style for the labels of disabled fields.
+    pub disabled: Style,
+    /// Style for validation errors.
+    pub error: Style,
+    /// Style of inputs, also used for toggles and pickers. Only the focused
+    /// input shows its cursor.
+    pub input: TextInputStyle,
+    /// Style of menus. The layout is always a single column.
+    pub menu: MenuStyle,
+}
+
+/// Actions returned by [`Form::update`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormAction {
+    /// No meaningful action.
+    Pass,
+    /// Focus moved to another field.
+    Focus,
+    /// The value of the focused field changed.
+    Change,
+    /// Submission was refused: some fields are invalid, see
+    /// [`Form::errors`]. Focus moved to the first of them.
+    Invalid,
+    /// The form was submitted with these values.
+    Submit(HashMap<String, FieldValue>),
+    /// The form was cancelled. The values are those at cancellation.
+    Cancel(HashMap<String, FieldValue>),
+}
+
+enum Widget {
+    Input(Box<TextInput>),
+    Toggle(bool),
+    Picker {
+        choices: Vec<String>,
+        selected: usize,
+    },
+    Menu(Box<Menu>),
+}
+
+struct Field {
+    name: String,
+    label: StyledText,
+    widget: Widget,
+    disabled: bool,
+    validator: Option<Validator>,
+    /// Range of the label and widget.
+    range: Range,
+    /// Range of the widget alone.
+    widget_range: Range,
+    error: Option<String>,
+}
+
+impl Field {
+    fn value(&self) -> FieldValue {
+        match &self.widget {
+            Widget::Input(input) => FieldValue::Text(input.content().to_string()),
+            Widget::Toggle(on) => FieldValue::Bool(*on),
+            Widget::Picker { selected, .. } => FieldValue::Choice(*selected),
+            Widget::Menu(menu) => FieldValue::Choice(menu.active()),
+        }
+    }
+
+    fn validate(&mut self) -> bool {
+        self.error = match &self.validator {
+            Some(f) if !self.disabled => f(&self.value()).err(),
+            _ => None,
+        };
+        self.error.is_none()
+    }
+}
+
+/// A group of labelled fields: text inputs, toggles, pickers and menus.
+///
+/// Fields flow down the form one after the other, labels in a column on
+/// the left, unless placed with [`FormField::with_range`]. Tab and
+/// Shift+Tab move focus between enabled fields, and a click focuses the
+/// field under the pointer before it handles the click. Other input goes
+/// to the focused field.
+///
+/// Submitting runs the validators of the enabled fields: failures are shown
+/// under their fields and block submission. Values can be read and set by
+/// field name, for prefilling, or for keeping snapshots to undo changes.
+pub struct Form {
+    grid: Grid,
+    fields: Vec<Field>,
+    keys: FormKeys,
+    box_: Option<BoxDecor>,
+    style: FormStyle,
+    focus: Option<usize>,
+    action: FormAction,
+}
+
+impl Form {
+    /// Create a new form from the given configuration. Focus starts on the
+    /// first enabled field.
+    pub fn new(config: FormConfig) -> Self {
+        let content = match config.box_ {
+            Some(ref box_decor) => box_decor.content_range(config.grid.range_()),
+            None => config.grid.range_(),
+        };
+        let label_w = config
+            .fields
+            .iter()
+            .map(|f| f.label.size().x)
+            .max()
+            .unwrap_or(0);
+        let widget_x = if label_w > 0 { label_w + 1 } else { 0 };
+        let mut y = 0;
+        let mut fields = Vec::with_capacity(config.fields.len());
+        for f in config.fields {
+            let height = f.height();
+            let range = match f.range {
+                Some(rg) => Range::new(
+                    rg.min.x + content.min.x,
+                    rg.min.y + content.min.y,
+                    rg.max.x + content.min.x,
+                    rg.max.y + content.min.y,
+                ),
+                None => {
+                    let rg = content.lines(y, y + height);
+                    y += height + f.validator.is_some() as i32;
+                    rg
+                }
+            }
+            .intersect(content);
+            let widget_range = range.columns(widget_x, range.width());
+            let grid = config.grid.slice(widget_range);
+            let widget = match f.kind {
+                FieldKind::Input { content, mask } => {
+                    Widget::Input(Box::new(TextInput::new(TextInputConfig {
+                        grid,
+                        content,
+                        prompt: None,
+                        keys: TextInputKeys {
+                            confirm: Vec::new(),
+                            cancel: Vec::new(),
+                        },
+                        box_: None,
+                        style: TextInputStyle {
+                            mask,
+                            ..config.style.input.clone()
+                        },
+                    })))
+                }
+                FieldKind::Toggle(on) => Widget::Toggle(on),
+                FieldKind::Picker { choices, selected } => Widget::Picker { choices, selected },
+                FieldKind::Menu { entries, .. } => Widget::Menu(Box::new(Menu::new(MenuConfig {
+                    grid,
+                    entries,
+                    provider: None,
+                    keys: MenuKeys {
+                        invoke: Vec::new(),
+                        quit: Vec::new(),
+                        ..MenuKeys::default()
+                    },
+                    box_: None,
+                    style: MenuStyle {
+                        layout: Point::new(1, 0),
+                        ..config.style.menu.clone()
+                    },
+                    reorderable: false,
+                }))),
+            };
+            fields.push(Field {
+                name: f.name,
+                label: f.label,
+                widget,
+                disabled: f.disabled,
+                validator: f.validator,
+                range,
+                widget_range,
+                error: None,
+            });
+        }
+        let focus = fields.iter().position(|f| !f.disabled);
+        Self {
+            grid: config.grid,
+            fields,
+            keys: config.keys,
+            box_: config.box_,
+            style: config.style,
+            focus,
+            action: FormAction::Pass,
+        }
+    }
+
+    /// Process an input message and return the resulting action.
+    pub fn update(&mut self, msg: Msg) -> FormAction {
+        self.action = FormAction::Pass;
+        match msg {
+            Msg::KeyDown {
+                ref key, modifiers, ..
+            } => {
+                let back_tab = *key == Key::Tab && modifiers.contains(ModMask::SHIFT);
+                if back_tab || self.keys.prev.contains(key) {
+                    self.step_focus(false);
+                } else if self.keys.next.contains(key) {
+                    self.step_focus(true);
+                } else if self.keys.cancel.contains(key) {
+                    self.action = FormAction::Cancel(self.values());
+                } else if self.keys.submit.contains(key) {
+                    self.submit();
+                } else if let Some(i) = self.focus {
+                    self.forward(i, msg);
+                }
+            }
+            Msg::Mouse {
+                action: MouseAction::Main,
+                pos,
+                ..
+            } => {
+                let hit = self
+                    .fields
+                    .iter()
+                    .position(|f| !f.disabled && f.range.contains(pos));
+                if let Some(i) = hit {
+                    if self.focus != Some(i) {
+                        self.focus = Some(i);
+                        self.action = FormAction::Focus;
+                    }
+                    if self.fields[i].widget_range.contains(pos) {
+                        self.forward(i, msg);
+                    }
+                }
+            }
+            Msg::Mouse { pos, .. } => {
+                if let Some(i) = self.focus
+                    && self.fields[i].widget_range.contains(pos)
+                {
+                    self.forward(i, msg);
+                }
+            }
+            _ => {}
+        }
+        self.action.clone()
+    }
+
+    /// Draw the form into its grid. Returns the grid range.
+    pub fn draw(&self) -> Range {
+        if let Some(ref box_decor) = self.box_ {
+            box_decor.draw(&self.grid);
+        }
+        for (i, f) in self.fields.iter().enumerate() {
+            let focused = self.focus == Some(i);
+            let style = if f.disabled {
+                self.style.disabled
+            } else if focused {
+                self.style.focused
+            } else {
+                self.style.label
+            };
+            let label_range = f.range.columns(0, f.range.width() - f.widget_range.width());
+            f.label
+                .clone()
+                .with_style(style)
+                .draw(&self.grid.slice(label_range));
+            let grid = self.grid.slice(f.widget_range);
+            let text = |s: String| StyledText::textf(s).with_style(self.style.input.text);
+            match &f.widget {
+                Widget::Input(input) => input.draw_cursor(&grid, focused),
+                Widget::Toggle(on) => {
+                    text(format!("[{}]", if *on { 'x' } else { ' ' })).draw(&grid);
+                }
+                Widget::Picker { choices, selected } => {
+                    let choice = choices.get(*selected).map_or("", String::as_str);
+                    text(format!("< {choice} >")).draw(&grid);
+                }
+                Widget::Menu(menu) => {
+                    menu.draw();
+                }
+            }
+            if let Some(ref err) = f.error {
+                let line = Range::new(
+                    f.widget_range.min.x,
+                    f.range.max.y,
+                    f.widget_range.max.x,
+                    f.range.max.y + 1,
+                );
+                let grid = self.grid.slice(line);
+                grid.fill(Cell::default().with_style(self.style.error));
+                StyledText::text(err)
+                    .with_style(self.style.error)
+                    .draw(&grid);
+            }
+        }
+        self.grid.range_()
+    }
+
+    /// Return the last action.
+    pub fn action(&self) -> FormAction {
+        self.action.clone()
+    }
+
+    /// Index of the focused field, if any field is enabled.
+    pub fn focused(&self) -> Option<usize> {
+        self.focus
+    }
+
+    /// Name of the focused field.
+    pub fn focused_name(&self) -> Option<&str> {
+        self.focus.map(|i| self.fields[i].name.as_str())
+    }
+
+    /// Focus the field `name`. Returns false if there is no such enabled
+    /// field.
+    pub fn set_focus(&mut self, name: &str) -> bool {
+        match self.find(name) {
+            Some(i) if !self.fields[i].disabled => {
+                self.focus = Some(i);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Value of the field `name`.
+    pub fn value(&self, name: &str) -> Option<FieldValue> {
+        self.find(name).map(|i| self.fields[i].value())
+    }
+
+    /// Set the value of the field `name`. Returns false if there is no such
+    /// field, or if the value does not fit it: text for inputs, a boolean
+    /// for toggles, and a valid index for pickers and menus.
+    pub fn set_value(&mut self, name: &str, value: FieldValue) -> bool {
+        let Some(i) = self.find(name) else {
+            return false;
+        };
+        let f = &mut self.fields[i];
+        match (&mut f.widget, value) {
+            (Widget::Input(input), FieldValue::Text(s)) => input.set_content(&s),
+            (Widget::Toggle(on), FieldValue::Bool(b)) => *on = b,
+            (Widget::Picker { choices, selected }, FieldValue::Choice(c)) if c < choices.len() => {
+                *selected = c;
+            }
+            (Widget::Menu(menu), FieldValue::Choice(c)) if c < menu.entries().len() => {
+                menu.set_active(c);
+            }
+            _ => return false,
+        }
+        if f.error.is_some() {
+            f.validate();
+        }
+        true
+    }
+
+    /// Values of all fields, by name.
+    pub fn values(&self) -> HashMap<String, FieldValue> {
+        self.fields
+            .iter()
+            .map(|f| (f.name.clone(), f.value()))
+            .collect()
+    }
+
+    /// Set the values of the fields named in `values`, ignoring the others.
+    pub fn set_values(&mut self, values: &HashMap<String, FieldValue>) {
+        for (name, value) in values {
+            self.set_value(name, value.clone());
+        }
+    }
+
+    /// Enable or disable the field `name`. Focus leaves a field being
+    /// disabled for the next enabled one.
+    pub fn set_disabled(&mut self, name: &str, disabled: bool) {
+        let Some(i) = self.find(name) else {
+            return;
+        };
+        self.fields[i].disabled = disabled;
+        if disabled {
+            self.fields[i].error = None;
+            if self.focus == Some(i) {
+                self.step_focus(true);
+                if self.focus == Some(i) {
+                    self.focus = None;
+                }
+            }
+        } else if self.focus.is_none() {
+            self.focus = Some(i);
+        }
+    }
+
+    /// Validate the enabled fields, showing errors under the invalid ones.
+    /// Returns whether all are valid.
+    pub fn validate(&mut self) -> bool {
+        let mut valid = true;
+        for f in &mut self.fields {
+            valid &= f.validate();
+        }
+        valid
+    }
+
+    /// Error messages of the invalid fields from the last validation, as
+    /// (name, message) pairs in field order.
+    pub fn errors(&self) -> Vec<(&str, &str)> {
+        self.fields
+            .iter()
+            .filter_map(|f| Some((f.name.as_str(), f.error.as_deref()?)))
+            .collect()
+    }
+
+    /// Describe the focused field for accessibility, with its label and
+    /// its error, if any.
+    pub fn describe(&self) -> Option<AccessibilityNode> {
+        let f = &self.fields[self.focus?];
+        let mut node = match &f.widget {
+            Widget::Input(input) => input.describe(),
+            Widget::Toggle(on) => {
+                let mut node = AccessibilityNode::new(Role::Toggle);
+                node.checked = Some(*on);
+                node
+            }
+            Widget::Picker { choices, selected } => {
+                let mut node = AccessibilityNode::new(Role::Picker);
+                node.value = choices.get(*selected).cloned();
+                node.index = Some(selected + 1);
+                node.count = Some(choices.len());
+                node
+            }
+            Widget::Menu(menu) => menu.describe(),
+        };
+        let label = f.label.plain();
+        node.label = Some(label.trim().to_string()).filter(|l| !l.is_empty());
+        node.error = f.error.clone();
+        Some(node)
+    }
+
+    // -- private helpers --
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name == name)
+    }
+
+    fn step_focus(&mut self, forward: bool) {
+        let n = self.fields.len();
+        if n == 0 {
+            return;
+        }
+        let start = self.focus.unwrap_or(if forward { n - 1 } else { 0 });
+        for k in 1..=n {
+            let i = if forward {
+                (start + k) % n
+            } else {
+                (start + n - k) % n
+            };
+            if !self.fields[i].disabled {
+                if self.focus != Some(i) {
+                    self.focus = Some(i);
+                    self.action = FormAction::Focus;
+                }
+                return;
+            }
+        }
+    }
+
+    fn submit(&mut self) {
+        if self.validate() {
+            self.action = FormAction::Submit(self.values());
+        } else {
+            self.focus = self.fields.iter().position(|f| f.error.is_some());
+            self.action = FormAction::Invalid;
+        }
+    }
+
+    /// Pass `msg` to field `i`, relative to its widget for mouse messages.
+    fn forward(&mut self, i: usize, msg: Msg) {
+        let f = &mut self.fields[i];
+        let msg = f.widget_range.rel_msg(msg);
+        let click = matches!(
+            msg,
+            Msg::Mouse {
+                action: MouseAction::Main,
+                ..
+            }
+        );
+        let key = match msg {
+            Msg::KeyDown { ref key, .. } => Some(key),
+            _ => None,
+        };
+        let before = f.value();
+        match &mut f.widget {
+            Widget::Input(input) => {
+                input.update(msg.clone());
+            }
+            Widget::Toggle(on) => {
+                if click || key.is_some_and(|k| self.keys.toggle.contains(k)) {
+                    *on = !*on;
+                }
+            }
+            Widget::Picker { choices, selected } => {
+                let n = choices.len().max(1);
+                let prev = match msg {
+                    Msg::Mouse { pos, .. } if click => Some(pos.x == 0),
+                    _ => key.and_then(|k| {
+                        if self.keys.prev_choice.contains(k) {
+                            Some(true)
+                        } else if self.keys.next_choice.contains(k) {
+                            Some(false)
+                        } else {
+                            None
+                        }
+                    }),
+                };
+                match prev {
+                    Some(true) => *selected = (*selected + n - 1) % n,
+                    Some(false) => *selected = (*selected + 1) % n,
+                    None => {}
+                }
+            }
+            Widget::Menu(menu) => {
+                menu.update(msg.clone());
+            }
+        }
+        if f.value() != before {
+            if f.error.is_some() {
+                f.validate();
+            }
+            self.action = FormAction::Change;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn make() -> Form {
+        Form::new(FormConfig {
+            grid: Grid::new(30, 12),
+            fields: vec![
+                FormField::input("name", StyledText::text("Name"), "").with_validator(
+                    |v| match v {
+                        FieldValue::Text(s) if s.is_empty() => Err("required".to_string()),
+                        _ => Ok(()),
+                    },
+                ),
+                FormField::toggle("hard", StyledText::text("Hardcore"), false),
+                FormField::picker(
+                    "class",
+                    StyledText::text("Class"),
+                    vec!["Fighter".into(), "Mage".into(), "Thief".into()],
+                    0,
+                )
+                .with_disabled(true),
+                FormField::menu(
+                    "seed",
+                    StyledText::text("Seed"),
+                    vec![
+                        MenuEntry::new(StyledText::text("Random")),
+                        MenuEntry::new(StyledText::text("Daily")),
+                    ],
+                    2,
+                ),
+                FormField::input("pass", StyledText::text("Password"), "")
+                    .with_mask('*')
+                    .with_validator(|v| match v {
+                        FieldValue::Text(s) if s.len() < 3 => Err("too short".to_string()),
+                        _ => Ok(()),
+                    }),
+            ],
+            keys: FormKeys::default(),
+            box_: None,
+            style: FormStyle::default(),
+        })
+    }
+
+    fn click(x: i32, y: i32) -> Msg {
+        Msg::Mouse {
+            action: MouseAction::Main,
+            pos: Point::new(x, y),
+            modifiers: Default::default(),
+            time: Instant::now(),
+        }
+    }
+
+    fn type_text(form: &mut Form, s: &str) {
+        for ch in s.chars() {
+            form.update(Msg::key(Key::Char(ch)));
+        }
+    }
+
+    #[test]
+    fn traversal_skips_disabled() {
+        let mut form = make();
+        let tab = || Msg::key(Key::Tab);
+        let back_tab = || Msg::key_mod(Key::Tab, ModMask::SHIFT);
+        assert_eq!(form.focused_name(), Some("name"));
+        assert_eq!(form.update(tab()), FormAction::Focus);
+        assert_eq!(form.focused_name(), Some("hard"));
+        form.update(tab());
+        assert_eq!(form.focused_name(), Some("seed"));
+        form.update(tab());
+        assert_eq!(form.focused_name(), Some("pass"));
+        form.update(tab());
+        assert_eq!(form.focused_name(), Some("name"));
+        form.update(back_tab());
+        assert_eq!(form.focused_name(), Some("pass"));
+        form.update(back_tab());
+        form.update(back_tab());
+        assert_eq!(form.focused_name(), Some("hard"));
+
+        form.set_disabled("class", false);
+        form.update(tab());
+        assert_eq!(form.focused_name(), Some("class"));
+        form.set_disabled("class", true);
+        assert_eq!(form.focused_name(), Some("seed"));
+        assert!(!form.set_focus("class"));
+    }
+
+    #[test]
+    fn click_focuses_and_forwards() {
+        let mut form = make();
+        // Labels are 8 wide: widgets start at column 9. The name and
+        // password inputs have an error line below them.
+        assert_eq!(form.update(click(10, 2)), FormAction::Change);
+        assert_eq!(form.focused_name(), Some("hard"));
+        assert_eq!(form.value("hard"), Some(FieldValue::Bool(true)));
+        // A click on the label focuses without changing the value.
+        assert_eq!(form.update(click(1, 0)), FormAction::Focus);
+        type_text(&mut form, "Ada");
+        form.update(click(10, 0));
+        type_text(&mut form, "l");
+        assert_eq!(form.value("name"), Some(FieldValue::Text("Alda".into())));
+        // Clicks on a disabled field do nothing.
+        assert_eq!(form.update(click(10, 3)), FormAction::Pass);
+        assert_eq!(form.focused_name(), Some("name"));
+        // The menu gets the click relative to itself.
+        form.update(click(10, 5));
+        assert_eq!(form.focused_name(), Some("seed"));
+        assert_eq!(form.value("seed"), Some(FieldValue::Choice(1)));
+        form.update(Msg::key(Key::ArrowUp));
+        assert_eq!(form.value("seed"), Some(FieldValue::Choice(0)));
+    }
+
+    #[test]
+    fn validation_blocks_submit() {
+        let mut form = make();
+        form.update(Msg::key(Key::Tab));
+        assert_eq!(form.update(Msg::key(Key::Enter)), FormAction::Invalid);
+        assert_eq!(
+            form.errors(),
+            vec![("name", "required"), ("pass", "too short")]
+        );
+        assert_eq!(form.focused_name(), Some("name"));
+        form.draw();
+        let row = |form: &Form, y| -> String {
+            (9..18).map(|x| form.grid.at(Point::new(x, y)).ch).collect()
+        };
+        assert_eq!(row(&form, 1), "required ");
+        assert_eq!(row(&form, 7), "too short");
+        let node = form.describe().unwrap();
+        assert_eq!(node.label.as_deref(), Some("Name"));
+        assert_eq!(node.error.as_deref(), Some("required"));
+
+        // Fixing a field clears its error as soon as it changes.
+        type_text(&mut form, "Bo");
+        assert_eq!(form.errors(), vec![("pass", "too short")]);
+        form.set_focus("pass");
+        type_text(&mut form, "secret");
+        assert!(form.errors().is_empty());
+        form.draw();
+        assert_eq!(row(&form, 6), "******_  ");
+        let FormAction::Submit(values) = form.update(Msg::key(Key::Enter)) else {
+            panic!("not submitted: {:?}", form.errors());
+        };
+        assert_eq!(values["name"], FieldValue::Text("Bo".into()));
+        assert_eq!(values["pass"], FieldValue::Text("secret".into()));
+    }
+
+    #[test]
+    fn values_round_trip() {
+        let mut form = make();
+        let prefill: HashMap<String, FieldValue> = [
+            ("name".to_string(), FieldValue::Text("Rogue".into())),
+            ("hard".to_string(), FieldValue::Bool(true)),
+            ("class".to_string(), FieldValue::Choice(2)),
+            ("seed".to_string(), FieldValue::Choice(1)),
+        ]
+        .into_iter()
+        .collect();
+        form.set_values(&prefill);
+        let values = form.values();
+        for (name, v) in &prefill {
+            assert_eq!(&values[name], v);
+        }
+        assert_eq!(values["pass"], FieldValue::Text(String::new()));
+        // Values that do not fit the field are refused.
+        assert!(!form.set_value("hard", FieldValue::Text("yes".into())));
+        assert!(!form.set_value("class", FieldValue::Choice(3)));
+        assert!(!form.set_value("nope", FieldValue::Bool(true)));
+        assert_eq!(form.value("class"), Some(FieldValue::Choice(2)));
+        let FormAction::Cancel(values) = form.update(Msg::key(Key::Escape)) else {
+            panic!("not cancelled");
+        };
+        assert_eq!(values["name"], FieldValue::Text("Rogue".into()));
+    }
+}
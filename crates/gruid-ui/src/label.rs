@@ -50,9 +50,10 @@ impl Label {
             }
         }
 
-        if self.box_.is_some() {
-            h += 2; // border top + bottom
-            w += 2; // border left + right
+        if let Some(ref box_decor) = self.box_ {
+            let (before, after) = box_decor.insets();
+            h += before.y + after.y;
+            w += before.x + after.x;
         }
 
         if !self.adjust_width {
@@ -72,9 +73,7 @@ impl Label {
         let draw_grid = grid.slice(draw_rg);
 
         let content_grid = if let Some(ref box_decor) = self.box_ {
-            box_decor.draw(&draw_grid);
-            let rg = draw_grid.range_();
-            draw_grid.slice(rg.shift(1, 1, -1, -1))
+            draw_grid.slice(box_decor.draw(&draw_grid))
         } else {
             draw_grid.clone()
         };
@@ -1,17 +1,43 @@
-//! UI widgets for gruid: menu, pager, text input, label, styled text, replay.
+//! UI widgets for gruid: menu, context menu, pager, text input, form,
+//! label, live-updating label, loading screen, spinner, styled text,
+//! replay.
+//!
+//! Interactive widgets describe their state as an [`AccessibilityNode`],
+//! for screen readers and UI tests.
+//!
+//! The keys of all widgets can be configured at once from a
+//! [`KeyBindings`] registry, with presets for arrows, vi keys and WASD.
 
+mod access;
+mod bindings;
 mod box_;
+mod context_menu;
+mod dyn_label;
+mod form;
 mod label;
+mod loading;
 mod menu;
 mod pager;
 pub mod replay;
+mod spinner;
 mod styled_text;
 mod text_input;
 
+pub use access::{AccessibilityNode, Announcer, Role};
+pub use bindings::{Conflict, KeyAction, KeyBindings, KeyContext};
 pub use box_::{Alignment, BoxDecor};
+pub use context_menu::{ContextMenu, ContextMenuConfig};
+pub use dyn_label::DynLabel;
+pub use form::{
+    FieldValue, Form, FormAction, FormConfig, FormField, FormKeys, FormStyle, Validator,
+};
 pub use label::Label;
-pub use menu::{Menu, MenuAction, MenuConfig, MenuEntry, MenuKeys, MenuStyle};
-pub use pager::{Pager, PagerAction, PagerConfig, PagerKeys, PagerStyle};
-pub use replay::{Replay, ReplayAction, ReplayConfig, ReplayKeys};
+pub use loading::{LoadingScreen, LoadingStyle};
+pub use menu::{Menu, MenuAction, MenuConfig, MenuEntry, MenuKeys, MenuProvider, MenuStyle};
+pub use pager::{Pager, PagerAction, PagerConfig, PagerKeys, PagerStyle, SectionLabel};
+pub use replay::{
+    Replay, ReplayAction, ReplayConfig, ReplayEvent, ReplayKeys, SeekEvents, ViewFit,
+};
+pub use spinner::{Spinner, SpinnerGlyphs};
 pub use styled_text::StyledText;
 pub use text_input::{TextInput, TextInputAction, TextInputConfig, TextInputKeys, TextInputStyle};
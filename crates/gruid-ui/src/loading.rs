@@ -0,0 +1,169 @@
+use gruid_core::{Cell, Grid, Msg, Point, Range, Style};
+
+use crate::{AccessibilityNode, Role, Spinner, SpinnerGlyphs};
+
+/// Styles used by [`LoadingScreen`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadingStyle {
+    /// Style for the status label and spinner.
+    pub label: Style,
+    /// Style for the completed part of the progress bar.
+    pub bar: Style,
+    /// Style for the remaining part of the progress bar.
+    pub empty: Style,
+}
+
+/// A splash widget showing a progress bar and a status label, centered in
+/// the grid.
+///
+/// Feed it the [`Msg::Progress`] messages produced by an
+/// [`Effect::CmdProgress`](gruid_core::Effect::CmdProgress) command.
+#[derive(Debug, Clone)]
+pub struct LoadingScreen {
+    /// Completed fraction, in `0.0..=1.0`.
+    pub fraction: f32,
+    /// Status text drawn above the bar.
+    pub label: String,
+    /// Maximum bar width in cells (clamped to the grid width).
+    pub bar_width: i32,
+    /// Spinner drawn before the label, in the label style, if any. Only
+    /// its glyphs and frame are used.
+    pub spinner: Option<Spinner>,
+    /// Widget styles.
+    pub style: LoadingStyle,
+}
+
+impl LoadingScreen {
+    /// Create a loading screen with an empty bar and the given label.
+    pub fn new(label: &str) -> Self {
+        Self {
+            fraction: 0.0,
+            label: label.to_string(),
+            bar_width: 40,
+            spinner: None,
+            style: LoadingStyle::default(),
+        }
+    }
+
+    /// Enable or disable the spinner, of [`SpinnerGlyphs::Line`]
+    /// (builder).
+    pub fn with_spinner(mut self, spinner: bool) -> Self {
+        self.spinner = spinner.then(|| Spinner::new(SpinnerGlyphs::Line));
+        self
+    }
+
+    /// Enable the spinner, with `glyphs` (builder).
+    pub fn with_spinner_glyphs(mut self, glyphs: SpinnerGlyphs) -> Self {
+        self.spinner = Some(Spinner::new(glyphs));
+        self
+    }
+
+    /// Set the styles (builder).
+    pub fn with_style(mut self, style: LoadingStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Handle a message. Progress messages update the bar and label and
+    /// advance the spinner; returns whether `msg` was a progress message.
+    pub fn update(&mut self, msg: &Msg) -> bool {
+        match msg {
+            Msg::Progress { fraction, label } => {
+                self.fraction = fraction.clamp(0.0, 1.0);
+                self.label.clone_from(label);
+                self.tick();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Describe the progress for accessibility. The spinner is left out.
+    pub fn describe(&self) -> AccessibilityNode {
+        let mut node = AccessibilityNode::new(Role::Progress);
+        node.label = (!self.label.is_empty()).then(|| self.label.clone());
+        node.progress = Some((self.fraction.clamp(0.0, 1.0) * 100.0).round() as u8);
+        node
+    }
+
+    /// Advance the spinner animation by one frame.
+    pub fn tick(&mut self) {
+        if let Some(spinner) = &mut self.spinner {
+            spinner.advance();
+        }
+    }
+
+    /// Draw the widget centered in `grid`. Returns the range drawn.
+    pub fn draw(&self, grid: &Grid) -> Range {
+        let size = grid.size();
+        let bar_w = self.bar_width.min(size.x).max(0);
+        let top = ((size.y - 2) / 2).max(0);
+
+        let mut text = String::new();
+        if let Some(spinner) = &self.spinner {
+            text.push(spinner.glyph());
+            text.push(' ');
+        }
+        text.push_str(&self.label);
+        let text_w = text.chars().count() as i32;
+        let text_x = ((size.x - text_w) / 2).max(0);
+        for (i, ch) in text.chars().enumerate() {
+            let cell = Cell::default().with_char(ch).with_style(self.style.label);
+            grid.set(Point::new(text_x + i as i32, top), cell);
+        }
+
+        let bar_x = (size.x - bar_w) / 2;
+        let filled = (self.fraction * bar_w as f32).round() as i32;
+        for i in 0..bar_w {
+            let cell = if i < filled {
+                Cell::default().with_char('█').with_style(self.style.bar)
+            } else {
+                Cell::default().with_char('░').with_style(self.style.empty)
+            };
+            grid.set(Point::new(bar_x + i, top + 1), cell);
+        }
+
+        let x0 = text_x.min(bar_x);
+        let x1 = (text_x + text_w).max(bar_x + bar_w).min(size.x);
+        Range::new(x0, top, x1, (top + 2).min(size.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_updates_bar() {
+        let mut ls = LoadingScreen::new("Loading");
+        ls.bar_width = 10;
+        assert!(ls.update(&Msg::Progress {
+            fraction: 0.5,
+            label: "Carving".into(),
+        }));
+        assert!(!ls.update(&Msg::Init));
+        assert_eq!(ls.label, "Carving");
+        let node = ls.describe();
+        assert_eq!(node.label.as_deref(), Some("Carving"));
+        assert_eq!(node.progress, Some(50));
+
+        let grid = Grid::new(20, 4);
+        let rg = ls.draw(&grid);
+        assert_eq!(rg, Range::new(5, 1, 15, 3));
+        let row: String = (5..15).map(|x| grid.at(Point::new(x, 2)).ch).collect();
+        assert_eq!(row, "█████░░░░░");
+        let label: String = (6..13).map(|x| grid.at(Point::new(x, 1)).ch).collect();
+        assert_eq!(label, "Carving");
+    }
+
+    #[test]
+    fn spinner_advances() {
+        let mut ls = LoadingScreen::new("x").with_spinner(true);
+        let grid = Grid::new(10, 2);
+        ls.draw(&grid);
+        let first = grid.at(Point::new(3, 0)).ch;
+        ls.tick();
+        ls.draw(&grid);
+        assert_ne!(grid.at(Point::new(3, 0)).ch, first);
+    }
+}
@@ -1,11 +1,20 @@
 //! Interactive menu widget with keyboard and mouse support.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use gruid_core::messages::{Key, MouseAction, Msg};
+use gruid_core::messages::{Key, ModMask, MouseAction, Msg};
 use gruid_core::{Cell, Grid, Point, Range, Style};
 
-use crate::{BoxDecor, StyledText};
+use crate::dyn_label::overlay;
+use crate::{AccessibilityNode, BoxDecor, KeyAction, KeyBindings, KeyContext, Role, StyledText};
+
+/// How long an entry dragged to the edge of a paged menu must stay there
+/// before the page turns.
+const DRAG_PAGE_DELAY: Duration = Duration::from_millis(400);
 
 /// An item placed in the 2D table. Maps a logical grid position to an entry.
 #[derive(Debug, Clone)]
@@ -18,6 +27,22 @@ struct Item {
     page: Point,
 }
 
+/// An entry being moved in a [reorderable](MenuConfig::reorderable) menu.
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    /// Index of the grabbed entry.
+    from: usize,
+    /// Slot it would be dropped into.
+    to: usize,
+    /// Grabbed with the mouse rather than the grab key.
+    mouse: bool,
+    /// Whether it ever left its slot, so that releasing it is not a click.
+    moved: bool,
+    /// Page edge the pointer rests on (`true` for the next page), and since
+    /// when.
+    edge: Option<(bool, Instant)>,
+}
+
 /// Internal layout classification.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum LayoutKind {
@@ -26,19 +51,118 @@ enum LayoutKind {
     Table,
 }
 
+/// Entries of a menu built on demand, for lists too long to build upfront.
+///
+/// A menu with a provider lays out [`len`](Self::len) slots but only builds
+/// the entries it draws or describes, keeping those of the current page and
+/// its neighbours. Navigation asks [`disabled`](Self::disabled) and
+/// [`shortcut`](Self::shortcut) instead, so it never builds an entry.
+///
+/// The provider is owned by the menu: one that changes, for example
+/// through shared state, must be followed by [`Menu::provider_changed`].
+pub trait MenuProvider {
+    /// Number of entries.
+    fn len(&self) -> usize;
+
+    /// Whether there are no entries.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Build entry `i`, for `i < len()`.
+    fn entry(&self, i: usize) -> MenuEntry;
+
+    /// Width of every entry in a [line](MenuStyle::layout) menu. Without
+    /// one, the widest entry of the first page is used.
+    fn width_hint(&self) -> Option<i32> {
+        None
+    }
+
+    /// Whether entry `i` is disabled, as in [`MenuEntry::disabled`]. The
+    /// default is that none is.
+    fn disabled(&self, _i: usize) -> bool {
+        false
+    }
+
+    /// Index of the entry that `key` is a [shortcut](MenuEntry::keys) for,
+    /// if any. The default has no shortcuts.
+    fn shortcut(&self, _key: &Key) -> Option<usize> {
+        None
+    }
+}
+
+/// A provider shared by clones of a menu.
+#[derive(Clone)]
+struct Provider(Rc<dyn MenuProvider>);
+
+impl fmt::Debug for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Provider")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+/// Entries built by a provider, for the current page and its neighbours.
+#[derive(Debug, Clone, Default)]
+struct EntryCache {
+    /// Page the cache was last pruned for.
+    page: usize,
+    entries: HashMap<usize, MenuEntry>,
+}
+
 /// Configuration for a [`Menu`] widget.
-#[derive(Debug, Clone)]
 pub struct MenuConfig {
     /// Grid to draw into.
     pub grid: Grid,
     /// The menu entries.
     pub entries: Vec<MenuEntry>,
+    /// Builds the entries on demand instead, in which case `entries` is
+    /// ignored. A menu with a provider is never
+    /// [reorderable](Self::reorderable).
+    pub provider: Option<Box<dyn MenuProvider>>,
     /// Key bindings.
     pub keys: MenuKeys,
     /// Optional box decoration.
     pub box_: Option<BoxDecor>,
     /// Visual style.
     pub style: MenuStyle,
+    /// Whether entries can be moved, by dragging them with the mouse or
+    /// with the [grab](MenuKeys::grab) key and the arrows; see
+    /// [`MenuAction::Reordered`].
+    ///
+    /// Disabled entries cannot be grabbed, but other entries can be moved
+    /// to any slot, shifting disabled ones like the rest.
+    ///
+    /// In a paged menu, an entry dragged to the first or last row (or
+    /// column) turns the page once held there a moment. Drivers send no
+    /// mouse events while the pointer rests, so the menu notices the delay
+    /// on the next message with a [time](Msg::time), such as
+    /// [`Msg::Frame`], or when the model calls [`Menu::tick`].
+    pub reorderable: bool,
+}
+
+impl MenuConfig {
+    /// Use `keys`, such as [`MenuKeys::from_bindings`] of the player's
+    /// preset.
+    pub fn with_keys(mut self, keys: MenuKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
+impl fmt::Debug for MenuConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MenuConfig")
+            .field("grid", &self.grid)
+            .field("entries", &self.entries)
+            .field("provider", &self.provider.as_ref().map(|p| p.len()))
+            .field("keys", &self.keys)
+            .field("box_", &self.box_)
+            .field("style", &self.style)
+            .field("reorderable", &self.reorderable)
+            .finish()
+    }
 }
 
 /// A single entry in a menu.
@@ -50,6 +174,9 @@ pub struct MenuEntry {
     pub disabled: bool,
     /// Shortcut keys that invoke this entry.
     pub keys: Vec<Key>,
+    /// Stable identity used by [`Menu::set_entries_keyed`] to keep the
+    /// cursor on the same logical entry when the list is rebuilt.
+    pub key: Option<u64>,
 }
 
 impl MenuEntry {
@@ -59,12 +186,19 @@ impl MenuEntry {
             text,
             disabled: false,
             keys: Vec::new(),
+            key: None,
         }
     }
+
+    /// Set the stable identity key (builder).
+    pub fn with_key(mut self, key: u64) -> Self {
+        self.key = Some(key);
+        self
+    }
 }
 
 /// Key bindings for menu navigation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MenuKeys {
     pub up: Vec<Key>,
     pub down: Vec<Key>,
@@ -74,6 +208,10 @@ pub struct MenuKeys {
     pub quit: Vec<Key>,
     pub page_up: Vec<Key>,
     pub page_down: Vec<Key>,
+    /// Picks up and drops the active entry in a reorderable menu. In
+    /// between, the movement keys move it, and quit puts it back. Shift
+    /// with a movement key moves the active entry by one slot directly.
+    pub grab: Vec<Key>,
 }
 
 impl Default for MenuKeys {
@@ -87,6 +225,26 @@ impl Default for MenuKeys {
             quit: vec![Key::Escape, Key::Char('q')],
             page_up: vec![Key::PageUp],
             page_down: vec![Key::PageDown],
+            grab: vec![Key::Char('m')],
+        }
+    }
+}
+
+impl MenuKeys {
+    /// The keys bound in `bindings` for the [`KeyContext::Menu`] actions.
+    pub fn from_bindings(bindings: &KeyBindings) -> Self {
+        use KeyAction::*;
+        let keys = |action| bindings.keys(KeyContext::Menu, action).to_vec();
+        Self {
+            up: keys(NavUp),
+            down: keys(NavDown),
+            left: keys(NavLeft),
+            right: keys(NavRight),
+            invoke: keys(Confirm),
+            quit: keys(Cancel),
+            page_up: keys(PageUp),
+            page_down: keys(PageDown),
+            grab: keys(Grab),
         }
     }
 }
@@ -102,6 +260,8 @@ pub struct MenuStyle {
     pub disabled: Style,
     /// Style for page number indicator.
     pub page_num: Style,
+    /// Style for an entry being moved in a reorderable menu.
+    pub dragged: Style,
 }
 
 impl Default for MenuStyle {
@@ -111,6 +271,7 @@ impl Default for MenuStyle {
             active: Style::default(),
             disabled: Style::default(),
             page_num: Style::default(),
+            dragged: Style::default(),
         }
     }
 }
@@ -126,6 +287,9 @@ pub enum MenuAction {
     Invoke,
     /// The user requested to quit/close the menu.
     Quit,
+    /// The entry at index `from` was moved to index `to`, shifting the ones
+    /// in between. The menu's own entries already reflect the change.
+    Reordered { from: usize, to: usize },
 }
 
 /// An interactive menu widget.
@@ -133,6 +297,8 @@ pub enum MenuAction {
 pub struct Menu {
     grid: Grid,
     entries: Vec<MenuEntry>,
+    provider: Option<Provider>,
+    cache: RefCell<EntryCache>,
     keys: MenuKeys,
     box_: Option<BoxDecor>,
     style: MenuStyle,
@@ -147,6 +313,10 @@ pub struct Menu {
     pages: Point,
     /// Computed layout (clamped copy of style.layout).
     layout: Point,
+    /// Raw index of the entry under the mouse pointer.
+    hover: Option<usize>,
+    reorderable: bool,
+    drag: Option<Drag>,
 }
 
 impl Menu {
@@ -154,7 +324,14 @@ impl Menu {
     pub fn new(config: MenuConfig) -> Self {
         let mut m = Self {
             grid: config.grid,
-            entries: config.entries,
+            entries: if config.provider.is_some() {
+                Vec::new()
+            } else {
+                config.entries
+            },
+            reorderable: config.reorderable && config.provider.is_none(),
+            provider: config.provider.map(|p| Provider(Rc::from(p))),
+            cache: RefCell::default(),
             keys: config.keys,
             box_: config.box_,
             style: config.style,
@@ -164,6 +341,8 @@ impl Menu {
             points: Vec::new(),
             pages: Point::ZERO,
             layout: Point::ZERO,
+            hover: None,
+            drag: None,
         };
         m.place_items();
         m.cursor_at_first_choice();
@@ -172,13 +351,57 @@ impl Menu {
 
     /// Process an input message and return the resulting action.
     pub fn update(&mut self, msg: Msg) -> MenuAction {
+        let now = msg.time();
+        let action = self.handle(msg);
+        match now {
+            Some(now) if action == MenuAction::Pass => self.tick(now),
+            Some(now) => {
+                self.tick(now);
+                self.action = action;
+                action
+            }
+            None => action,
+        }
+    }
+
+    /// Turn the page if an entry dragged with the mouse has rested on a
+    /// page edge long enough by `now`, returning [`MenuAction::Move`] if it
+    /// did. [`update`](Self::update) calls it for messages with a time;
+    /// call it from a timer to turn pages without them.
+    pub fn tick(&mut self, now: Instant) -> MenuAction {
+        let Some(Drag {
+            mouse: true,
+            edge: Some((forward, since)),
+            ..
+        }) = self.drag
+        else {
+            return MenuAction::Pass;
+        };
+        if now.saturating_duration_since(since) < DRAG_PAGE_DELAY {
+            return MenuAction::Pass;
+        }
+        self.drag_page(forward);
+        if let Some(drag) = self.drag.as_mut() {
+            // Held on, the pointer turns the next page after as long.
+            drag.edge = Some((forward, now));
+        }
+        self.action = MenuAction::Move;
+        self.action
+    }
+
+    fn handle(&mut self, msg: Msg) -> MenuAction {
         self.action = MenuAction::Pass;
-        if self.entries.is_empty() {
+        if self.is_empty() {
             return MenuAction::Pass;
         }
 
         match msg {
-            Msg::KeyDown { ref key, .. } => {
+            Msg::KeyDown {
+                ref key, modifiers, ..
+            } => {
+                if self.reorderable && self.reorder_key(key, modifiers) {
+                    return self.action;
+                }
                 if self.keys.quit.contains(key) {
                     self.action = MenuAction::Quit;
                 } else if self.keys.down.contains(key) {
@@ -197,36 +420,35 @@ impl Menu {
                     if !self.current_disabled() {
                         self.action = MenuAction::Invoke;
                     }
-                } else {
-                    // Check per-entry shortcut keys.
-                    for (i, entry) in self.entries.iter().enumerate() {
-                        if !entry.disabled && entry.keys.contains(key) {
-                            self.active = self.idx_to_pos(i);
-                            self.action = MenuAction::Invoke;
-                            break;
-                        }
-                    }
+                } else if let Some(i) = self.shortcut(key) {
+                    self.active = self.idx_to_pos(i);
+                    self.action = MenuAction::Invoke;
                 }
             }
-            Msg::Mouse { action, pos, .. } => {
+            Msg::Mouse {
+                action, pos, time, ..
+            } => {
+                if self.reorderable && self.reorder_mouse(action, pos, time) {
+                    return self.action;
+                }
                 let outer = self.visible_range();
                 let inner = self.content_range();
                 let p = pos;
                 match action {
-                    MouseAction::Move => {
+                    _ if action.is_move() => {
+                        self.hover = None;
                         if inner.contains(p) {
                             self.move_to_point(p);
                         }
                     }
-                    MouseAction::WheelDown => {
-                        if inner.contains(p) {
-                            self.page_down();
-                        }
+                    MouseAction::Leave => {
+                        self.hover = None;
                     }
-                    MouseAction::WheelUp => {
-                        if inner.contains(p) {
-                            self.page_up();
-                        }
+                    MouseAction::WheelDown if inner.contains(p) => {
+                        self.page_down();
+                    }
+                    MouseAction::WheelUp if inner.contains(p) => {
+                        self.page_up();
                     }
                     MouseAction::Main => {
                         if !outer.contains(p) {
@@ -280,31 +502,28 @@ impl Menu {
             if it.page != active_page {
                 continue;
             }
-            let entry = &self.entries[it.i];
-            let mut st = entry.text.style();
-            let is_active = pos == self.active && !entry.disabled;
-
-            if is_active {
-                if self.style.active.fg != gruid_core::Color::DEFAULT {
-                    st.fg = self.style.active.fg;
-                }
-                if self.style.active.bg != gruid_core::Color::DEFAULT {
-                    st.bg = self.style.active.bg;
+            // While dragging, entries are shown where a drop would put them.
+            self.with_entry(self.entry_at_slot(it.i), |entry| {
+                let mut st = entry.text.style();
+                let is_dragged = self.drag.is_some_and(|d| d.to == it.i);
+                let is_active = pos == self.active && !entry.disabled && self.drag.is_none();
+
+                if is_dragged {
+                    st = overlay(st, self.style.dragged);
+                } else if is_active {
+                    st = overlay(st, self.style.active);
                 }
-                if self.style.active.attrs != gruid_core::AttrMask::NONE {
-                    st.attrs = self.style.active.attrs;
-                }
-            }
 
-            // Fill the item area and draw text into it.
-            let item_grid = grid.slice(it.bounds);
-            let fill_cell = Cell::default().with_char(' ').with_style(st);
-            item_grid.fill(fill_cell);
-            if is_active {
-                entry.text.clone().with_style(st).draw(&item_grid);
-            } else {
-                entry.text.draw(&item_grid);
-            }
+                // Fill the item area and draw text into it.
+                let item_grid = grid.slice(it.bounds);
+                let fill_cell = Cell::default().with_char(' ').with_style(st);
+                item_grid.fill(fill_cell);
+                if is_active || is_dragged {
+                    entry.text.clone().with_style(st).draw(&item_grid);
+                } else {
+                    entry.text.draw(&item_grid);
+                }
+            });
         }
 
         pgr
@@ -321,9 +540,52 @@ impl Menu {
         self.table.get(&self.active).map_or(0, |it| it.i)
     }
 
+    /// Raw index of the entry being moved in a reorderable menu, as it was
+    /// before the move started.
+    pub fn dragged(&self) -> Option<usize> {
+        self.drag.map(|d| d.from)
+    }
+
+    /// The menu entries, in their current order. Empty for a menu with a
+    /// [provider](MenuConfig::provider).
+    pub fn entries(&self) -> &[MenuEntry] {
+        &self.entries
+    }
+
+    /// Number of entries, including those of a provider.
+    pub fn len(&self) -> usize {
+        match self.provider {
+            Some(ref p) => p.0.len(),
+            None => self.entries.len(),
+        }
+    }
+
+    /// Whether the menu has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Lay the menu out again after its [provider](MenuConfig::provider)
+    /// changed, dropping the entries it built. The cursor stays on the same
+    /// index if it still exists.
+    pub fn provider_changed(&mut self) {
+        self.cache.get_mut().entries.clear();
+        self.hover = None;
+        self.place_items();
+        if !self.table.contains_key(&self.active) {
+            self.cursor_at_last_choice();
+        }
+    }
+
+    /// Raw index of the entry under the mouse pointer, if any. Cleared when
+    /// the pointer moves off the entries or leaves the grid.
+    pub fn hovered(&self) -> Option<usize> {
+        self.hover
+    }
+
     /// Set the active entry by raw index (counting disabled entries).
     pub fn set_active(&mut self, i: usize) {
-        if i < self.entries.len() {
+        if i < self.len() {
             self.active = self.idx_to_pos(i);
         }
     }
@@ -334,16 +596,10 @@ impl Menu {
     /// This matches Go's `Menu.ActiveInvokable()`.
     pub fn active_invokable(&self) -> Option<usize> {
         let raw = self.active();
-        if raw >= self.entries.len() || self.entries[raw].disabled {
+        if raw >= self.len() || self.disabled(raw) {
             return None;
         }
-        let mut count = 0usize;
-        for e in &self.entries[..=raw] {
-            if !e.disabled {
-                count += 1;
-            }
-        }
-        Some(count - 1)
+        Some((0..raw).filter(|&i| !self.disabled(i)).count())
     }
 
     /// Set the active entry to the `i`-th invokable (non-disabled) entry.
@@ -353,15 +609,8 @@ impl Menu {
     ///
     /// This matches Go's `Menu.SetActiveInvokable(i)`.
     pub fn set_active_invokable(&mut self, i: usize) {
-        let mut n: isize = -1;
-        for (j, e) in self.entries.iter().enumerate() {
-            if !e.disabled {
-                n += 1;
-            }
-            if n == i as isize {
-                self.active = self.idx_to_pos(j);
-                return;
-            }
+        if let Some(j) = (0..self.len()).filter(|&j| !self.disabled(j)).nth(i) {
+            self.active = self.idx_to_pos(j);
         }
     }
 
@@ -370,18 +619,65 @@ impl Menu {
         self.action
     }
 
-    /// Replace the entries.
+    /// Replace the entries. A menu with a [provider](MenuConfig::provider)
+    /// drops it.
     pub fn set_entries(&mut self, entries: Vec<MenuEntry>) {
         self.entries = entries;
+        self.provider = None;
+        self.cache.get_mut().entries.clear();
+        self.hover = None;
+        self.drag = None;
         self.place_items();
         if !self.table.contains_key(&self.active) {
             self.cursor_at_last_choice();
         }
     }
 
+    /// Replace the entries, keeping the cursor on the same logical entry.
+    ///
+    /// If the previously active entry had a [`key`](MenuEntry::key), the
+    /// entry with the same key is re-selected, wherever it moved to. If the
+    /// key vanished, the nearest following selectable entry is chosen, then
+    /// the nearest preceding one, so the cursor stays close to where it was.
+    /// Without a previous key this behaves like
+    /// [`set_entries`](Self::set_entries).
+    pub fn set_entries_keyed(&mut self, entries: Vec<MenuEntry>) {
+        let Some(key) = self.active_key() else {
+            self.set_entries(entries);
+            return;
+        };
+        let prev = self.active();
+        self.entries = entries;
+        self.provider = None;
+        self.cache.get_mut().entries.clear();
+        self.hover = None;
+        self.drag = None;
+        self.place_items();
+        if let Some(j) = self.entries.iter().position(|e| e.key == Some(key)) {
+            self.active = self.idx_to_pos(j);
+            return;
+        }
+        let n = self.entries.len();
+        let start = prev.min(n.saturating_sub(1));
+        let next = (start..n).find(|&i| !self.disabled(i));
+        let prev = (0..start).rev().find(|&i| !self.disabled(i));
+        match next.or(prev) {
+            Some(j) => self.active = self.idx_to_pos(j),
+            None => self.cursor_at_first_choice(),
+        }
+    }
+
+    /// Key of the currently active entry, if it has one.
+    pub fn active_key(&self) -> Option<u64> {
+        self.table
+            .get(&self.active)
+            .and_then(|it| self.with_entry(it.i, |e| e.key))
+    }
+
     /// Replace the box decoration.
     pub fn set_box(&mut self, box_: Option<BoxDecor>) {
         self.box_ = box_;
+        self.drag = None;
         self.place_items();
     }
 
@@ -417,6 +713,25 @@ impl Menu {
         (p + 1) as usize
     }
 
+    /// Describe the menu for accessibility: its title, the active entry
+    /// and its position, and the page when there are several.
+    pub fn describe(&self) -> AccessibilityNode {
+        let mut node = AccessibilityNode::new(Role::Menu);
+        node.label = self.box_.as_ref().and_then(BoxDecor::label);
+        node.count = Some(self.len());
+        let i = self.active();
+        if i < self.len() {
+            node.value = Some(self.with_entry(i, |e| e.text.plain()));
+            node.index = Some(i + 1);
+            node.disabled = self.disabled(i);
+        }
+        if self.page_count() > 1 {
+            node.page = Some(self.page() + 1);
+            node.pages = Some(self.page_count());
+        }
+        node
+    }
+
     // ---------------------------------------------------------------
     // Private helpers: index/position conversion
     // ---------------------------------------------------------------
@@ -436,7 +751,7 @@ impl Menu {
     fn current_disabled(&self) -> bool {
         self.table
             .get(&self.active)
-            .is_none_or(|it| self.entries[it.i].disabled)
+            .is_none_or(|it| self.disabled(it.i))
     }
 
     fn cur_page(&self) -> Point {
@@ -446,27 +761,90 @@ impl Menu {
     }
 
     // ---------------------------------------------------------------
-    // Cursor placement
+    // Private helpers: entry access
     // ---------------------------------------------------------------
 
-    fn cursor_at_first_choice(&mut self) {
-        let mut j = 0;
-        for (i, e) in self.entries.iter().enumerate() {
-            if !e.disabled {
-                j = i;
+    fn disabled(&self, i: usize) -> bool {
+        match self.provider {
+            Some(ref p) => p.0.disabled(i),
+            None => self.entries[i].disabled,
+        }
+    }
+
+    /// The enabled entry that `key` is a shortcut for.
+    fn shortcut(&self, key: &Key) -> Option<usize> {
+        match self.provider {
+            Some(ref p) => p.0.shortcut(key).filter(|&i| i < self.len()),
+            None => self
+                .entries
+                .iter()
+                .position(|e| !e.disabled && e.keys.contains(key)),
+        }
+        .filter(|&i| !self.disabled(i))
+    }
+
+    /// Call `f` with entry `i`, building it with the provider if needed.
+    /// Built entries off the current page and its neighbours are dropped
+    /// whenever the page changes.
+    fn with_entry<R>(&self, i: usize, f: impl FnOnce(&MenuEntry) -> R) -> R {
+        let Some(ref provider) = self.provider else {
+            return f(&self.entries[i]);
+        };
+        let mut cache = self.cache.borrow_mut();
+        let page = self.page();
+        if cache.page != page {
+            cache
+                .entries
+                .retain(|&j, _| self.page_of(j).is_some_and(|pg| pg.abs_diff(page) <= 1));
+            cache.page = page;
+        }
+        f(cache
+            .entries
+            .entry(i)
+            .or_insert_with(|| provider.0.entry(i)))
+    }
+
+    /// Page of entry `i`, counted like [`page`](Self::page).
+    fn page_of(&self, i: usize) -> Option<usize> {
+        let it = self.table.get(self.points.get(i)?)?;
+        Some(if self.pages.y > 0 {
+            it.page.y as usize
+        } else {
+            it.page.x as usize
+        })
+    }
+
+    /// Width of the widest provider entry that fits on a first line of
+    /// `w` cells, keeping the entries built on the way.
+    fn sampled_width(&self, w: i32) -> i32 {
+        let mut max = 1;
+        let mut to = 0;
+        for i in 0..self.len() {
+            let tw = self.with_entry(i, |e| e.text.size().x);
+            to += tw;
+            if i > 0 && to > w {
                 break;
             }
+            max = max.max(tw);
         }
+        max
+    }
+
+    // ---------------------------------------------------------------
+    // Cursor placement
+    // ---------------------------------------------------------------
+
+    fn cursor_at_first_choice(&mut self) {
+        let j = (0..self.len()).find(|&i| !self.disabled(i)).unwrap_or(0);
         self.active = self.idx_to_pos(j);
     }
 
     fn cursor_at_last_choice(&mut self) {
-        let mut j = self.entries.len().saturating_sub(1);
-        for (i, e) in self.entries.iter().enumerate() {
-            if !e.disabled {
-                j = i;
-            }
-        }
+        let n = self.len();
+        let j = (0..n)
+            .rev()
+            .find(|&i| !self.disabled(i))
+            .unwrap_or(n.saturating_sub(1));
         self.active = self.idx_to_pos(j);
     }
 
@@ -483,7 +861,7 @@ impl Menu {
             q = q + d;
             match self.table.get(&q) {
                 None => break,
-                Some(it) if !self.entries[it.i].disabled => break,
+                Some(it) if !self.disabled(it.i) => break,
                 _ => {} // disabled: keep going
             }
         }
@@ -511,7 +889,7 @@ impl Menu {
         let cur_i = it.i;
         match (d.x, d.y) {
             (0, 1) => {
-                for i in (cur_i + 1)..self.entries.len() {
+                for i in (cur_i + 1)..self.len() {
                     let q = self.idx_to_pos(i);
                     if self.table[&q].page.y > cur_page.y {
                         return Some(q);
@@ -519,7 +897,7 @@ impl Menu {
                 }
             }
             (1, 0) => {
-                for i in (cur_i + 1)..self.entries.len() {
+                for i in (cur_i + 1)..self.len() {
                     let q = self.idx_to_pos(i);
                     if self.table[&q].page.x > cur_page.x {
                         return Some(q);
@@ -570,7 +948,7 @@ impl Menu {
             // walk forward to find the first entry on that same page.
             let target_page = self.table[&q].page;
             let mut first = q;
-            for i in 0..self.entries.len() {
+            for i in 0..self.len() {
                 let p = self.idx_to_pos(i);
                 if self.table[&p].page == target_page {
                     first = p;
@@ -601,10 +979,11 @@ impl Menu {
                     rg = rg.union(it.bounds);
                 }
             }
-            if self.box_.is_some() {
-                rg = rg.shift(-1, -1, 1, 1);
+            if let Some(ref bd) = self.box_ {
+                let (before, after) = bd.insets();
+                rg = rg.shift(-before.x, -before.y, after.x, after.y);
             }
-            return rg;
+            return rg.intersect(self.grid.range_());
         }
 
         // Count visible rows on current page (column 0 only)
@@ -618,11 +997,12 @@ impl Menu {
             }
             h += 1;
         }
-        if self.box_.is_some() {
-            h += 2;
+        if let Some(ref bd) = self.box_ {
+            let (before, after) = bd.insets();
+            h += before.y + after.y;
         }
         let max = self.grid.size();
-        Range::new(0, 0, max.x, h)
+        Range::new(0, 0, max.x, h.min(max.y))
     }
 
     fn visible_range(&self) -> Range {
@@ -631,10 +1011,9 @@ impl Menu {
 
     fn content_range(&self) -> Range {
         let outer = self.visible_range();
-        if self.box_.is_some() {
-            outer.shift(1, 1, -1, -1)
-        } else {
-            outer
+        match self.box_ {
+            Some(ref bd) => bd.content_range(outer),
+            None => outer,
         }
     }
 
@@ -645,6 +1024,7 @@ impl Menu {
             .map_or(Point::ZERO, |it| it.page);
         for (&q, it) in &self.table {
             if it.page == page && it.bounds.contains(p) {
+                self.hover = Some(it.i);
                 if q == self.active {
                     return;
                 }
@@ -663,7 +1043,7 @@ impl Menu {
         for (&q, it) in &self.table {
             if it.page == page && it.bounds.contains(p) {
                 self.active = q;
-                if self.entries[it.i].disabled {
+                if self.disabled(it.i) {
                     self.action = MenuAction::Move;
                 } else {
                     self.action = MenuAction::Invoke;
@@ -673,6 +1053,232 @@ impl Menu {
         }
     }
 
+    /// Raw index of the entry drawn at `p` on the current page, if any.
+    fn slot_at(&self, p: Point) -> Option<usize> {
+        let page = self.cur_page();
+        self.table
+            .values()
+            .find(|it| it.page == page && it.bounds.contains(p))
+            .map(|it| it.i)
+    }
+
+    // ---------------------------------------------------------------
+    // Reordering
+    // ---------------------------------------------------------------
+
+    /// Index of the entry shown in `slot`, previewing the drop while an
+    /// entry is dragged.
+    fn entry_at_slot(&self, slot: usize) -> usize {
+        let Some(d) = self.drag else {
+            return slot;
+        };
+        if slot == d.to {
+            d.from
+        } else if d.from <= slot && slot < d.to {
+            slot + 1
+        } else if d.to < slot && slot <= d.from {
+            slot - 1
+        } else {
+            slot
+        }
+    }
+
+    /// Handle a key in a reorderable menu. Returns whether it was used.
+    fn reorder_key(&mut self, key: &Key, mods: ModMask) -> bool {
+        let dir = if self.keys.up.contains(key) {
+            Some(Point::new(0, -1))
+        } else if self.keys.down.contains(key) {
+            Some(Point::new(0, 1))
+        } else if self.keys.left.contains(key) {
+            Some(Point::new(-1, 0))
+        } else if self.keys.right.contains(key) {
+            Some(Point::new(1, 0))
+        } else {
+            None
+        };
+        if self.drag.is_some_and(|d| !d.mouse) {
+            if let Some(d) = dir {
+                self.drag_step(d);
+            } else if self.keys.page_down.contains(key) {
+                self.drag_page(true);
+            } else if self.keys.page_up.contains(key) {
+                self.drag_page(false);
+            } else if self.keys.grab.contains(key) || self.keys.invoke.contains(key) {
+                self.drop_drag();
+            } else if self.keys.quit.contains(key) {
+                self.cancel_drag();
+            }
+            // Other keys are ignored while an entry is held.
+            return true;
+        }
+        if let Some(d) = dir
+            && mods.contains(ModMask::SHIFT)
+        {
+            if self.grab(false) {
+                self.drag_step(d);
+                self.drop_drag();
+            }
+            return true;
+        }
+        if self.keys.grab.contains(key) {
+            self.grab(false);
+            return true;
+        }
+        false
+    }
+
+    /// Handle a mouse event in a reorderable menu. Returns whether it was
+    /// used.
+    fn reorder_mouse(&mut self, action: MouseAction, p: Point, time: Instant) -> bool {
+        let Some(mut drag) = self.drag else {
+            // Pressing Main on an enabled entry picks it up; clicks are told
+            // apart from drags on release.
+            if action != MouseAction::Main || !self.content_range().contains(p) {
+                return false;
+            }
+            let Some(slot) = self.slot_at(p).filter(|&i| !self.disabled(i)) else {
+                return false;
+            };
+            self.set_active(slot);
+            return self.grab(true);
+        };
+        if !drag.mouse {
+            // The mouse takes over from a keyboard move.
+            self.cancel_drag();
+            return false;
+        }
+        match action {
+            _ if action.is_move() => {
+                if let Some(slot) = self.slot_at(p) {
+                    self.set_drag_to(slot);
+                }
+                drag = self.drag.expect("still dragging");
+                // The page turns in `tick`, once the pointer rested.
+                drag.edge = match (self.page_edge(p), drag.edge) {
+                    (None, _) => None,
+                    (Some(fwd), Some((prev, since))) if fwd == prev => Some((fwd, since)),
+                    (Some(fwd), _) => Some((fwd, time)),
+                };
+                self.drag = Some(drag);
+            }
+            MouseAction::Release => {
+                if drag.moved {
+                    self.drop_drag();
+                } else {
+                    self.drag = None;
+                    self.action = MenuAction::Invoke;
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Whether `p` is on the edge of the current page towards the next
+    /// (`true`) or previous (`false`) page, if there is one.
+    fn page_edge(&self, p: Point) -> Option<bool> {
+        let inner = self.content_range();
+        let page = self.cur_page();
+        let (pos, min, max, cur, last) = if self.pages.y > 0 {
+            (p.y, inner.min.y, inner.max.y, page.y, self.pages.y)
+        } else if self.pages.x > 0 {
+            (p.x, inner.min.x, inner.max.x, page.x, self.pages.x)
+        } else {
+            return None;
+        };
+        if pos <= min && cur > 0 {
+            Some(false)
+        } else if pos >= max - 1 && cur < last {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Pick up the active entry. Returns whether it could be.
+    fn grab(&mut self, mouse: bool) -> bool {
+        if self.current_disabled() {
+            return false;
+        }
+        let i = self.active();
+        self.drag = Some(Drag {
+            from: i,
+            to: i,
+            mouse,
+            moved: false,
+            edge: None,
+        });
+        self.action = MenuAction::Move;
+        true
+    }
+
+    fn set_drag_to(&mut self, slot: usize) {
+        let Some(drag) = self.drag.as_mut() else {
+            return;
+        };
+        if drag.to == slot {
+            return;
+        }
+        drag.to = slot;
+        drag.moved = true;
+        self.active = self.idx_to_pos(slot);
+        self.action = MenuAction::Move;
+    }
+
+    /// Move the held entry one slot in direction `d`. The slot may be on
+    /// another page, but there is no wrapping around.
+    fn drag_step(&mut self, d: Point) {
+        let Some(drag) = self.drag else {
+            return;
+        };
+        if let Some(it) = self.table.get(&(self.idx_to_pos(drag.to) + d)) {
+            let slot = it.i;
+            self.set_drag_to(slot);
+        }
+    }
+
+    /// Move the held entry to the first slot of the next page, or the last
+    /// slot of the previous one.
+    fn drag_page(&mut self, forward: bool) {
+        let Some(drag) = self.drag else {
+            return;
+        };
+        let page = |i: usize| self.table[&self.idx_to_pos(i)].page;
+        let cur = page(drag.to);
+        let slot = if forward {
+            (drag.to + 1..self.len()).find(|&i| page(i) != cur)
+        } else {
+            (0..drag.to).rev().find(|&i| page(i) != cur)
+        };
+        if let Some(slot) = slot {
+            self.set_drag_to(slot);
+        }
+    }
+
+    /// Drop the held entry into its slot and lay the menu out again.
+    fn drop_drag(&mut self) {
+        let Some(Drag { from, to, .. }) = self.drag.take() else {
+            return;
+        };
+        if from == to {
+            return;
+        }
+        let entry = self.entries.remove(from);
+        self.entries.insert(to, entry);
+        self.hover = None;
+        self.place_items();
+        self.active = self.idx_to_pos(to);
+        self.action = MenuAction::Reordered { from, to };
+    }
+
+    /// Put the held entry back where it was.
+    fn cancel_drag(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            self.active = self.idx_to_pos(drag.from);
+            self.action = MenuAction::Move;
+        }
+    }
+
     // ---------------------------------------------------------------
     // Layout engine
     // ---------------------------------------------------------------
@@ -680,7 +1286,7 @@ impl Menu {
     fn update_layout(&mut self) {
         self.layout = self.style.layout;
         let gs = self.grid.size();
-        let n = self.entries.len() as i32;
+        let n = self.len() as i32;
         if self.layout.y > gs.y {
             self.layout.y = gs.y;
         }
@@ -693,7 +1299,7 @@ impl Menu {
     }
 
     fn get_layout(&self, w: i32, _h: i32) -> (LayoutKind, i32, i32) {
-        let n = self.entries.len() as i32;
+        let n = self.len() as i32;
         let mut lines = self.layout.y;
         let nw = w;
         if lines <= 0 {
@@ -722,22 +1328,26 @@ impl Menu {
         self.update_layout();
 
         // Compute draw-grid height.
-        let mut h = self.entries.len() as i32;
+        let n = self.len();
+        let mut h = n as i32;
         if self.layout.y > 0 {
             h = self.layout.y;
         }
-        if self.box_.is_some() {
-            h += 2;
+        if let Some(ref bd) = self.box_ {
+            let (before, after) = bd.insets();
+            h += before.y + after.y;
         }
         let gs = self.grid.size();
         let draw_h = h.min(gs.y);
 
         // Inner dimensions (inside box).
-        let (inner_x, inner_y, inner_w, inner_h) = if self.box_.is_some() {
-            (1, 1, (gs.x - 2).max(0), (draw_h - 2).max(0))
-        } else {
-            (0, 0, gs.x, draw_h)
+        let outer = Range::new(0, 0, gs.x, draw_h);
+        let inner = match self.box_ {
+            Some(ref bd) => bd.content_range(outer),
+            None => outer,
         };
+        let (inner_x, inner_y, inner_w, inner_h) =
+            (inner.min.x, inner.min.y, inner.width(), inner.height());
 
         let (kind, col_w, columns) = self.get_layout(inner_w, inner_h);
 
@@ -750,7 +1360,7 @@ impl Menu {
 
         match kind {
             LayoutKind::Column => {
-                for i in 0..self.entries.len() {
+                for i in 0..n {
                     let row_in_page = (i as i32) % ch;
                     let page_y = (i as i32) / ch;
                     let pos = Point::new(0, i as i32);
@@ -772,11 +1382,16 @@ impl Menu {
                 }
             }
             LayoutKind::Line => {
+                // Provider entries are not built to be measured.
+                let fixed = self.provider.as_ref().map(|p| {
+                    p.0.width_hint()
+                        .unwrap_or_else(|| self.sampled_width(inner_w))
+                });
                 let mut to = 0i32;
                 let mut hpage = 0i32;
-                for i in 0..self.entries.len() {
+                for i in 0..n {
                     let from = to;
-                    let tw = self.entries[i].text.size().x;
+                    let tw = fixed.unwrap_or_else(|| self.entries[i].text.size().x);
                     to += tw;
                     let (from, new_to) = if from > 0 && to > inner_w {
                         hpage += 1;
@@ -800,7 +1415,7 @@ impl Menu {
             }
             LayoutKind::Table => {
                 let h = ch;
-                for i in 0..self.entries.len() {
+                for i in 0..n {
                     let page = (i as i32) / (columns * h);
                     let pageidx = (i as i32) % (columns * h);
                     let ln = pageidx % h;
@@ -849,9 +1464,11 @@ mod tests {
         Menu::new(MenuConfig {
             grid: Grid::new(20, height),
             entries,
+            provider: None,
             keys: MenuKeys::default(),
             box_: None,
             style: MenuStyle::default(),
+            reorderable: false,
         })
     }
 
@@ -882,9 +1499,11 @@ mod tests {
         let mut menu = Menu::new(MenuConfig {
             grid: Grid::new(20, 10),
             entries,
+            provider: None,
             keys: MenuKeys::default(),
             box_: None,
             style: MenuStyle::default(),
+            reorderable: false,
         });
 
         assert_eq!(menu.active(), 0);
@@ -893,6 +1512,25 @@ mod tests {
         assert_eq!(menu.active(), 3);
     }
 
+    #[test]
+    fn describe_active_entry() {
+        let mut menu = make_menu(10, 4);
+        menu.entries[5].disabled = true;
+        let node = menu.describe();
+        assert_eq!(node.role, Role::Menu);
+        assert_eq!(node.value.as_deref(), Some("Item 0"));
+        assert_eq!((node.index, node.count), (Some(1), Some(10)));
+        assert_eq!((node.page, node.pages), (Some(1), Some(3)));
+        assert!(!node.disabled);
+
+        menu.set_active(5);
+        let node = menu.describe();
+        assert_eq!(node.value.as_deref(), Some("Item 5"));
+        assert_eq!(node.index, Some(6));
+        assert_eq!(node.page, Some(2));
+        assert!(node.disabled);
+    }
+
     #[test]
     fn pagination() {
         let mut menu = make_menu(10, 3); // 3 rows visible
@@ -947,6 +1585,64 @@ mod tests {
         assert_eq!(menu.page(), 0);
     }
 
+    fn keyed(keys: &[u64]) -> Vec<MenuEntry> {
+        keys.iter()
+            .map(|&k| {
+                MenuEntry::new(StyledText::new(&format!("Item {k}"), Style::default())).with_key(k)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keyed_removal_selects_successor() {
+        let mut menu = make_menu(0, 10);
+        menu.set_entries(keyed(&[1, 2, 3, 4]));
+        menu.set_active(1);
+        assert_eq!(menu.active_key(), Some(2));
+
+        menu.set_entries_keyed(keyed(&[1, 3, 4]));
+        assert_eq!(menu.active_key(), Some(3));
+
+        // Removing the last entry falls back to the preceding one.
+        menu.set_active(2);
+        menu.set_entries_keyed(keyed(&[1, 3]));
+        assert_eq!(menu.active_key(), Some(3));
+
+        menu.set_entries_keyed(Vec::new());
+        assert_eq!(menu.active_key(), None);
+        assert_eq!(menu.page(), 0);
+    }
+
+    #[test]
+    fn keyed_reorder_follows_key_across_pages() {
+        let mut menu = make_menu(0, 3);
+        menu.set_entries(keyed(&[0, 1, 2, 3, 4, 5, 6, 7]));
+        menu.set_active(1);
+        assert_eq!(menu.page(), 0);
+
+        menu.set_entries_keyed(keyed(&[7, 6, 5, 4, 3, 2, 1, 0]));
+        assert_eq!(menu.active_key(), Some(1));
+        assert_eq!(menu.active(), 6);
+        assert_eq!(menu.page(), 2);
+
+        // Shrinking the page count keeps the cursor on an existing page.
+        menu.set_entries_keyed(keyed(&[1, 2]));
+        assert_eq!(menu.active(), 0);
+        assert_eq!(menu.page_count(), 1);
+        assert_eq!(menu.page(), 0);
+    }
+
+    #[test]
+    fn keyed_insertion_keeps_logical_entry() {
+        let mut menu = make_menu(0, 10);
+        menu.set_entries(keyed(&[10, 20, 30]));
+        menu.set_active(1);
+
+        menu.set_entries_keyed(keyed(&[5, 10, 20, 30]));
+        assert_eq!(menu.active_key(), Some(20));
+        assert_eq!(menu.active(), 2);
+    }
+
     #[test]
     fn invoke_action() {
         let mut menu = make_menu(3, 10);
@@ -972,9 +1668,11 @@ mod tests {
         let mut menu = Menu::new(MenuConfig {
             grid: Grid::new(20, 10),
             entries,
+            provider: None,
             keys: MenuKeys::default(),
             box_: None,
             style: MenuStyle::default(),
+            reorderable: false,
         });
 
         // active=0 ("Item 0", enabled) → invokable index 0
@@ -1009,9 +1707,11 @@ mod tests {
         let mut menu = Menu::new(MenuConfig {
             grid: Grid::new(20, 10),
             entries,
+            provider: None,
             keys: MenuKeys::default(),
             box_: None,
             style: MenuStyle::default(),
+            reorderable: false,
         });
 
         menu.set_active_invokable(0);
@@ -1080,6 +1780,32 @@ mod tests {
         // Moving to same entry → Pass
         let action = menu.update(mouse_msg(MouseAction::Move, 2, 3));
         assert_eq!(action, MenuAction::Pass);
+
+        // Drags highlight like moves.
+        let action = menu.update(mouse_msg(MouseAction::MainDrag, 5, 1));
+        assert_eq!(action, MenuAction::Move);
+        assert_eq!(menu.active(), 1);
+    }
+
+    #[test]
+    fn mouse_leave_clears_hover() {
+        let mut menu = make_menu(5, 10);
+        assert_eq!(menu.hovered(), None);
+        menu.update(mouse_msg(MouseAction::Move, 5, 3));
+        assert_eq!(menu.hovered(), Some(3));
+
+        let action = menu.update(mouse_msg(MouseAction::Leave, 5, 3));
+        assert_eq!(action, MenuAction::Pass);
+        assert_eq!(menu.hovered(), None);
+        // The selection itself stays put.
+        assert_eq!(menu.active(), 3);
+
+        menu.update(mouse_msg(MouseAction::Enter, 5, 1));
+        menu.update(mouse_msg(MouseAction::Move, 5, 1));
+        assert_eq!(menu.hovered(), Some(1));
+        // Below the last entry: nothing hovered.
+        menu.update(mouse_msg(MouseAction::Move, 5, 8));
+        assert_eq!(menu.hovered(), None);
     }
 
     #[test]
@@ -1100,9 +1826,11 @@ mod tests {
         let mut menu = Menu::new(MenuConfig {
             grid: Grid::new(20, 10),
             entries,
+            provider: None,
             keys: MenuKeys::default(),
             box_: None,
             style: MenuStyle::default(),
+            reorderable: false,
         });
 
         // Click on disabled entry → Move (not Invoke)
@@ -1111,6 +1839,40 @@ mod tests {
         assert_eq!(menu.active(), 2);
     }
 
+    #[test]
+    fn box_padding_moves_content() {
+        let boxed = |bd: BoxDecor| {
+            let grid = Grid::new(20, 10);
+            let menu = Menu::new(MenuConfig {
+                grid: grid.clone(),
+                entries: (0..3)
+                    .map(|i| {
+                        MenuEntry::new(StyledText::new(&format!("Item {i}"), Style::default()))
+                    })
+                    .collect(),
+                provider: None,
+                keys: MenuKeys::default(),
+                box_: Some(bd),
+                style: MenuStyle::default(),
+                reorderable: false,
+            });
+            (menu.draw(), grid)
+        };
+
+        // Zero padding places items exactly as a plain box does.
+        let (rg, grid) = boxed(BoxDecor::new().with_padding(0, 0));
+        assert_eq!(rg, Range::new(0, 0, 20, 5));
+        assert_eq!(grid.at(Point::new(1, 1)).ch, 'I');
+        assert_eq!(grid.at(Point::new(1, 3)).ch, 'I');
+
+        let (rg, grid) = boxed(BoxDecor::new().with_padding(2, 1));
+        assert_eq!(rg, Range::new(0, 0, 20, 7));
+        assert_eq!(grid.at(Point::new(1, 2)).ch, ' ');
+        assert_eq!(grid.at(Point::new(3, 2)).ch, 'I');
+        assert_eq!(grid.at(Point::new(3, 4)).ch, 'I');
+        assert_eq!(grid.at(Point::new(0, 6)).ch, '└');
+    }
+
     #[test]
     fn mouse_click_on_box_border() {
         let mut menu = Menu::new(MenuConfig {
@@ -1118,9 +1880,11 @@ mod tests {
             entries: (0..3)
                 .map(|i| MenuEntry::new(StyledText::new(&format!("Item {i}"), Style::default())))
                 .collect(),
+            provider: None,
             keys: MenuKeys::default(),
             box_: Some(BoxDecor::new()),
             style: MenuStyle::default(),
+            reorderable: false,
         });
 
         // Click on top border (y=0) - inside outer but outside inner → Pass (not Quit)
@@ -1131,4 +1895,277 @@ mod tests {
         let action = menu.update(mouse_msg(MouseAction::Main, 5, 20));
         assert_eq!(action, MenuAction::Quit);
     }
+
+    fn reorder_menu(keys: &[u64], height: i32) -> Menu {
+        let mut menu = make_menu(0, height);
+        menu.reorderable = true;
+        menu.set_entries(keyed(keys));
+        menu
+    }
+
+    fn order(menu: &Menu) -> Vec<u64> {
+        menu.entries().iter().filter_map(|e| e.key).collect()
+    }
+
+    #[test]
+    fn keyboard_reorder_round_trip() {
+        let mut menu = reorder_menu(&[0, 1, 2, 3, 4], 10);
+        menu.update(Msg::key(Key::Char('m')));
+        assert_eq!(menu.dragged(), Some(0));
+        menu.update(Msg::key(Key::ArrowDown));
+        menu.update(Msg::key(Key::ArrowDown));
+        // The drop is previewed, but entries only change on release.
+        let grid = menu.grid.clone();
+        menu.draw();
+        assert_eq!(grid.at(Point::new(5, 0)).ch, '1');
+        assert_eq!(grid.at(Point::new(5, 2)).ch, '0');
+        assert_eq!(order(&menu), [0, 1, 2, 3, 4]);
+
+        let action = menu.update(Msg::key(Key::Char('m')));
+        assert_eq!(action, MenuAction::Reordered { from: 0, to: 2 });
+        assert_eq!(order(&menu), [1, 2, 0, 3, 4]);
+        assert_eq!(menu.active_key(), Some(0));
+        assert_eq!(menu.dragged(), None);
+
+        // Shift+arrow moves the active entry directly.
+        let up = || Msg::key_mod(Key::ArrowUp, ModMask::SHIFT);
+        assert_eq!(menu.update(up()), MenuAction::Reordered { from: 2, to: 1 });
+        assert_eq!(menu.update(up()), MenuAction::Reordered { from: 1, to: 0 });
+        assert_eq!(order(&menu), [0, 1, 2, 3, 4]);
+        // No wrapping at the top.
+        assert_eq!(menu.update(up()), MenuAction::Move);
+        assert_eq!(order(&menu), [0, 1, 2, 3, 4]);
+
+        // Quit puts the entry back.
+        menu.update(Msg::key(Key::Char('m')));
+        menu.update(Msg::key(Key::ArrowDown));
+        assert_eq!(menu.update(Msg::key(Key::Escape)), MenuAction::Move);
+        assert_eq!(order(&menu), [0, 1, 2, 3, 4]);
+        assert_eq!(menu.active(), 0);
+
+        // Disabled entries cannot be grabbed.
+        let mut entries = keyed(&[0, 1]);
+        entries[1].disabled = true;
+        menu.set_entries(entries);
+        menu.set_active(1);
+        assert_eq!(menu.update(Msg::key(Key::Char('m'))), MenuAction::Pass);
+        assert_eq!(menu.dragged(), None);
+    }
+
+    #[test]
+    fn mouse_drag_turns_page_at_edge() {
+        let t0 = Instant::now();
+        let at = |action, y, ms| Msg::Mouse {
+            action,
+            pos: Point::new(5, y),
+            modifiers: Default::default(),
+            time: t0 + Duration::from_millis(ms),
+        };
+        let mut menu = reorder_menu(&[0, 1, 2, 3, 4, 5, 6, 7], 3);
+        assert_eq!(menu.update(at(MouseAction::Main, 1, 0)), MenuAction::Move);
+        assert_eq!(menu.dragged(), Some(1));
+
+        // Resting on the bottom row turns the page after a delay. Drivers
+        // send one move into the cell, then nothing until the pointer
+        // moves again: frames, or a timer calling `tick`, turn the page.
+        let frame = |ms| Msg::Frame {
+            time: t0 + Duration::from_millis(ms),
+        };
+        menu.update(at(MouseAction::Move, 2, 100));
+        assert_eq!(menu.update(frame(300)), MenuAction::Pass);
+        assert_eq!(menu.page(), 0);
+        assert_eq!(menu.update(frame(550)), MenuAction::Move);
+        assert_eq!(menu.page(), 1);
+
+        menu.update(at(MouseAction::Move, 0, 600));
+        // On the previous page's edge now, but released before the delay.
+        assert_eq!(menu.tick(t0 + Duration::from_millis(900)), MenuAction::Pass);
+        assert_eq!(menu.page(), 1);
+        let action = menu.update(at(MouseAction::Release, 0, 950));
+        assert_eq!(action, MenuAction::Reordered { from: 1, to: 3 });
+        assert_eq!(order(&menu), [0, 2, 3, 1, 4, 5, 6, 7]);
+        assert_eq!(menu.active_key(), Some(1));
+        assert_eq!(menu.page(), 1);
+
+        // A press and release without moving is a click.
+        menu.update(at(MouseAction::Main, 1, 1000));
+        let action = menu.update(at(MouseAction::Release, 1, 1050));
+        assert_eq!(action, MenuAction::Invoke);
+        assert_eq!(menu.active_key(), Some(4));
+        assert_eq!(order(&menu), [0, 2, 3, 1, 4, 5, 6, 7]);
+
+        // Held on the edge, the pointer keeps turning pages, from a timer.
+        menu.update(at(MouseAction::Main, 1, 1100));
+        menu.update(at(MouseAction::Move, 2, 1150));
+        assert_eq!(
+            menu.tick(t0 + Duration::from_millis(1600)),
+            MenuAction::Move
+        );
+        assert_eq!(menu.page(), 2);
+    }
+
+    /// Entry `i` of the provider tests, all of the same width so that line
+    /// layouts match: every 7th from the 4th is disabled, and entries 5,
+    /// 15, 25… have the shortcuts 'a', 'b', 'c'….
+    fn sample_entry(i: usize) -> MenuEntry {
+        let mut e = MenuEntry::new(StyledText::new(&format!("Item {i:03}"), Style::default()));
+        e.disabled = i % 7 == 3;
+        if i % 10 == 5 {
+            e.keys.push(Key::Char((b'a' + (i / 10) as u8) as char));
+        }
+        e
+    }
+
+    /// Provides [`sample_entry`]s, counting the ones built.
+    struct Counting {
+        n: usize,
+        built: Rc<std::cell::Cell<usize>>,
+    }
+
+    impl MenuProvider for Counting {
+        fn len(&self) -> usize {
+            self.n
+        }
+
+        fn entry(&self, i: usize) -> MenuEntry {
+            self.built.set(self.built.get() + 1);
+            sample_entry(i)
+        }
+
+        fn disabled(&self, i: usize) -> bool {
+            i % 7 == 3
+        }
+
+        fn shortcut(&self, key: &Key) -> Option<usize> {
+            match *key {
+                Key::Char(c @ 'a'..='z') => Some((c as usize - 'a' as usize) * 10 + 5),
+                _ => None,
+            }
+        }
+    }
+
+    fn provider_menu(n: usize, grid: Grid, layout: Point) -> (Menu, Rc<std::cell::Cell<usize>>) {
+        let built = Rc::new(std::cell::Cell::new(0));
+        let menu = Menu::new(MenuConfig {
+            grid,
+            entries: Vec::new(),
+            provider: Some(Box::new(Counting {
+                n,
+                built: built.clone(),
+            })),
+            keys: MenuKeys::default(),
+            box_: None,
+            style: MenuStyle {
+                layout,
+                ..MenuStyle::default()
+            },
+            reorderable: false,
+        });
+        (menu, built)
+    }
+
+    #[test]
+    fn provider_builds_only_nearby_pages() {
+        let (mut menu, built) = provider_menu(1000, Grid::new(20, 10), Point::new(1, 0));
+        assert_eq!(menu.len(), 1000);
+        assert_eq!(menu.page_count(), 100);
+        assert_eq!(built.get(), 0);
+
+        // Moving around without drawing builds nothing.
+        menu.update(Msg::key(Key::ArrowUp));
+        assert_eq!(menu.active(), 999);
+        assert_eq!(menu.active_invokable(), Some(856));
+        menu.update(Msg::key(Key::Char('y')));
+        assert_eq!(menu.active(), 245);
+        assert_eq!(menu.action(), MenuAction::Invoke);
+        menu.set_active_invokable(0);
+        assert_eq!(menu.active(), 0);
+        assert_eq!(built.get(), 0);
+
+        menu.draw();
+        assert_eq!(built.get(), 10);
+        let cached = |menu: &Menu| menu.cache.borrow().entries.len();
+        for page in 1..100 {
+            menu.update(Msg::key(Key::PageDown));
+            menu.draw();
+            assert_eq!(menu.page(), page);
+            assert!(
+                cached(&menu) <= 30,
+                "{} built at page {page}",
+                cached(&menu)
+            );
+        }
+        for page in (0..99).rev() {
+            menu.update(Msg::key(Key::PageUp));
+            menu.draw();
+            assert_eq!(menu.page(), page);
+            assert!(
+                cached(&menu) <= 30,
+                "{} built at page {page}",
+                cached(&menu)
+            );
+        }
+        // Each page was built on the way down, and all but the one before
+        // the last again on the way up.
+        assert_eq!(built.get(), 1000 + 980);
+
+        menu.provider_changed();
+        assert_eq!(cached(&menu), 0);
+        assert_eq!(menu.active(), 0);
+    }
+
+    #[test]
+    fn provider_matches_entries() {
+        let keys = [
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::PageDown,
+            Key::ArrowRight,
+            Key::PageDown,
+            Key::ArrowLeft,
+            Key::ArrowUp,
+            Key::PageUp,
+            Key::Char('c'),
+            Key::Enter,
+            Key::Char('e'),
+            Key::PageDown,
+            Key::PageDown,
+            Key::PageDown,
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::ArrowUp,
+            Key::Char('x'),
+        ];
+        // Column, line and table layouts.
+        for layout in [Point::new(1, 0), Point::new(0, 1), Point::new(3, 4)] {
+            let grid = Grid::new(30, 10);
+            let mut full = Menu::new(MenuConfig {
+                grid: grid.clone(),
+                entries: (0..57).map(sample_entry).collect(),
+                provider: None,
+                keys: MenuKeys::default(),
+                box_: None,
+                style: MenuStyle {
+                    layout,
+                    ..MenuStyle::default()
+                },
+                reorderable: false,
+            });
+            let lazy_grid = Grid::new(30, 10);
+            let (mut lazy, _) = provider_menu(57, lazy_grid.clone(), layout);
+            assert_eq!(lazy.page_count(), full.page_count(), "{layout:?}");
+            for key in &keys {
+                let action = full.update(Msg::key(key.clone()));
+                assert_eq!(lazy.update(Msg::key(key.clone())), action, "{key:?}");
+                assert_eq!(lazy.active(), full.active(), "{layout:?} {key:?}");
+                assert_eq!(lazy.active_invokable(), full.active_invokable());
+                assert_eq!(lazy.page(), full.page());
+                assert_eq!(lazy.describe(), full.describe());
+                assert_eq!(lazy.draw(), full.draw());
+                assert!(lazy_grid.iter().eq(grid.iter()), "{layout:?} {key:?}");
+            }
+        }
+    }
 }
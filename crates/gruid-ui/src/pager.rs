@@ -1,9 +1,33 @@
 //! Scrollable text pager widget with keyboard, mouse, and seeking support.
-
-use gruid_core::messages::{Key, MouseAction, Msg};
-use gruid_core::{Cell, Grid, Point, Range, Style};
-
-use crate::{BoxDecor, StyledText};
+//!
+//! Dragging with the main button over the content selects text the way a
+//! terminal does, flowing from line to line. The copy key then reports
+//! [`PagerAction::Copy`], and the model puts
+//! [`Pager::selection_text`] on the clipboard:
+//!
+//! ```
+//! # use gruid_core::{Effect, Msg};
+//! # use gruid_ui::{Pager, PagerAction};
+//! fn update_pager(pager: &mut Pager, msg: Msg) -> Option<Effect> {
+//!     match pager.update(msg) {
+//!         PagerAction::Copy => pager.selection_text().map(gruid_core::app::set_clipboard),
+//!         _ => None,
+//!     }
+//! }
+//! ```
+//!
+//! Long documents can be given anchors with [`Pager::set_anchors`]: the
+//! anchors key then opens a menu of them, the section keys step from one to
+//! the next, and the current section is named in the box.
+
+use gruid_core::messages::{Key, ModMask, MouseAction, Msg};
+use gruid_core::{AttrMask, Cell, Grid, Point, Range, Style};
+
+use crate::dyn_label::overlay;
+use crate::{
+    AccessibilityNode, BoxDecor, KeyAction, KeyBindings, KeyContext, Menu, MenuAction, MenuConfig,
+    MenuEntry, MenuKeys, MenuStyle, Role, StyledText,
+};
 
 /// Horizontal scroll step (columns per left/right key press), matching Go gruid.
 const SCROLL_STEP_X: i32 = 8;
@@ -23,8 +47,17 @@ pub struct PagerConfig {
     pub style: PagerStyle,
 }
 
+impl PagerConfig {
+    /// Use `keys`, such as [`PagerKeys::from_bindings`] of the player's
+    /// preset.
+    pub fn with_keys(mut self, keys: PagerKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
 /// Key bindings for pager navigation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PagerKeys {
     pub up: Vec<Key>,
     pub down: Vec<Key>,
@@ -39,6 +72,19 @@ pub struct PagerKeys {
     pub top: Vec<Key>,
     pub bottom: Vec<Key>,
     pub quit: Vec<Key>,
+    /// Keys that copy the selection. Ctrl+C always does, when the driver
+    /// lets it through.
+    pub copy: Vec<Key>,
+    /// Keys that open (and close) the menu of anchors. They take over from
+    /// other bindings only when the pager has anchors, so the default `g`
+    /// still goes to the top of documents without any.
+    pub anchors: Vec<Key>,
+    /// Keys that scroll to the next anchor, from the last back to the
+    /// first.
+    pub next_section: Vec<Key>,
+    /// Keys that scroll to the start of the current section, or to the
+    /// previous anchor when already there.
+    pub prev_section: Vec<Key>,
 }
 
 impl Default for PagerKeys {
@@ -56,15 +102,80 @@ impl Default for PagerKeys {
             top: vec![Key::Home, Key::Char('g')],
             bottom: vec![Key::End, Key::Char('G')],
             quit: vec![Key::Escape, Key::Char('q')],
+            copy: vec![Key::Char('y')],
+            anchors: vec![Key::Char('g')],
+            next_section: vec![Key::Char('}')],
+            prev_section: vec![Key::Char('{')],
+        }
+    }
+}
+
+impl PagerKeys {
+    /// The keys bound in `bindings` for the [`KeyContext::Pager`] actions.
+    pub fn from_bindings(bindings: &KeyBindings) -> Self {
+        use KeyAction::*;
+        let keys = |action| bindings.keys(KeyContext::Pager, action).to_vec();
+        Self {
+            up: keys(NavUp),
+            down: keys(NavDown),
+            left: keys(NavLeft),
+            right: keys(NavRight),
+            start: keys(LineStart),
+            page_up: keys(PageUp),
+            page_down: keys(PageDown),
+            half_page_up: keys(HalfPageUp),
+            half_page_down: keys(HalfPageDown),
+            top: keys(Top),
+            bottom: keys(Bottom),
+            quit: keys(Cancel),
+            copy: keys(Copy),
+            anchors: keys(Anchors),
+            next_section: keys(NextSection),
+            prev_section: keys(PrevSection),
         }
     }
 }
 
+/// Where a pager with a box names the current section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SectionLabel {
+    /// Before the line numbers in the footer, unless the box has a footer
+    /// of its own.
+    #[default]
+    Footer,
+    /// After the box title.
+    Title,
+    /// Nowhere.
+    Hidden,
+}
+
 /// Visual style for a pager.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct PagerStyle {
     /// Style for the line-number indicator shown in the box footer.
     pub line_num: Style,
+    /// Style laid over selected text: its non-default colours and
+    /// attributes replace the text's own. Reverse video by default.
+    pub selected: Style,
+    /// Where the name of the current section is shown.
+    pub section: SectionLabel,
+    /// Style of the menu of anchors. The active entry is in reverse video
+    /// by default.
+    pub anchor_menu: MenuStyle,
+}
+
+impl Default for PagerStyle {
+    fn default() -> Self {
+        Self {
+            line_num: Style::default(),
+            selected: Style::default().with_attrs(AttrMask::REVERSE),
+            section: SectionLabel::Footer,
+            anchor_menu: MenuStyle {
+                active: Style::default().with_attrs(AttrMask::REVERSE),
+                ..MenuStyle::default()
+            },
+        }
+    }
 }
 
 /// Actions returned by [`Pager::update`].
@@ -76,16 +187,84 @@ pub enum PagerAction {
     Scroll,
     /// The user requested to quit/close the pager.
     Quit,
+    /// The user asked to copy the selection, available from
+    /// [`Pager::selection_text`].
+    Copy,
+}
+
+/// A text selection in content coordinates: `x` is a column of the
+/// formatted line, horizontal scroll included, and `y` a line index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Selection {
+    anchor: Point,
+    head: Point,
+    /// The main button is still held.
+    dragging: bool,
+    /// The pointer left the anchor cell: this is no mere click.
+    moved: bool,
+}
+
+impl Selection {
+    /// First and last selected positions, in reading order.
+    fn bounds(&self) -> (Point, Point) {
+        let key = |p: Point| (p.y, p.x);
+        if key(self.anchor) <= key(self.head) {
+            (self.anchor, self.head)
+        } else {
+            (self.head, self.anchor)
+        }
+    }
+
+    /// Selected columns of `line`, end excluded.
+    fn columns(&self, line: i32) -> Option<(i32, i32)> {
+        if !self.moved {
+            return None;
+        }
+        let (start, end) = self.bounds();
+        if line < start.y || line > end.y {
+            return None;
+        }
+        let from = if line == start.y { start.x } else { 0 };
+        let to = if line == end.y { end.x + 1 } else { i32::MAX };
+        Some((from, to))
+    }
+}
+
+/// The open menu of anchors. It draws into a grid of its own, copied over
+/// the pager's, since [`Pager::draw`] may be given another grid than the
+/// configured one.
+#[derive(Debug, Clone)]
+struct AnchorMenu {
+    menu: Menu,
+    grid: Grid,
+    /// Where it goes, relative to the pager's grid.
+    bounds: Range,
 }
 
 /// A scrollable text pager widget.
 #[derive(Debug, Clone)]
 pub struct Pager {
     lines: Vec<StyledText>,
+    /// The content before wrapping, kept to re-wrap it for another width.
+    /// `None` for lines given by [`set_lines`](Self::set_lines).
+    content: Option<StyledText>,
+    /// Index in `lines` of the first row of each content line.
+    starts: Vec<usize>,
+    /// Section names and their content lines, in order.
+    anchors: Vec<(String, usize)>,
+    anchor_menu: Option<AnchorMenu>,
+    /// The anchor last scrolled to, with the scroll position it gave.
+    /// While the pager stays there, that is the current section, even if
+    /// the end of the content kept its line from reaching the top row.
+    jumped: Option<(usize, i32)>,
     grid: Grid,
     keys: PagerKeys,
     box_: Option<BoxDecor>,
     line_num_style: Style,
+    selected_style: Style,
+    section_label: SectionLabel,
+    anchor_menu_style: MenuStyle,
+    selection: Option<Selection>,
     scroll_y: i32,
     scroll_x: i32,
     action: PagerAction,
@@ -94,15 +273,24 @@ pub struct Pager {
 impl Pager {
     /// Create a new pager from the given configuration.
     pub fn new(config: PagerConfig) -> Self {
-        let width = config.grid.width().max(0) as usize;
-        let formatted = config.content.format(width.saturating_sub(2).max(1));
-        let lines = formatted.lines();
+        let width = wrap_width(&config.grid);
+        let lines = config.content.format(width).lines();
+        let starts = line_starts(&config.content, width);
         Self {
             lines,
+            content: Some(config.content),
+            starts,
+            anchors: Vec::new(),
+            anchor_menu: None,
+            jumped: None,
             grid: config.grid,
             keys: config.keys,
             box_: config.box_,
             line_num_style: config.style.line_num,
+            selected_style: config.style.selected,
+            section_label: config.style.section,
+            anchor_menu_style: config.style.anchor_menu,
+            selection: None,
             scroll_y: 0,
             scroll_x: 0,
             action: PagerAction::Pass,
@@ -112,11 +300,29 @@ impl Pager {
     /// Process an input message and return the resulting action.
     pub fn update(&mut self, msg: Msg) -> PagerAction {
         self.action = PagerAction::Pass;
+        if self.anchor_menu.is_some() {
+            self.update_anchor_menu(msg);
+            return self.action;
+        }
         let nlines = self.visible_height();
 
         match msg {
-            Msg::KeyDown { ref key, .. } => {
-                if self.keys.up.contains(key) {
+            Msg::KeyDown {
+                ref key, modifiers, ..
+            } => {
+                let ctrl_c = *key == Key::Char('c') && modifiers.contains(ModMask::CTRL);
+                let anchored = !self.anchors.is_empty();
+                if anchored && self.keys.anchors.contains(key) {
+                    self.open_anchor_menu();
+                } else if anchored && self.keys.next_section.contains(key) {
+                    self.step_section(true);
+                } else if anchored && self.keys.prev_section.contains(key) {
+                    self.step_section(false);
+                } else if self.keys.copy.contains(key) || ctrl_c {
+                    if self.has_selection() {
+                        self.action = PagerAction::Copy;
+                    }
+                } else if self.keys.up.contains(key) {
                     self.up(1);
                 } else if self.keys.down.contains(key) {
                     self.down(1);
@@ -149,6 +355,9 @@ impl Pager {
                 }
             }
             Msg::Mouse { action, pos, .. } => {
+                if self.select_mouse(action, pos) {
+                    return self.action;
+                }
                 let (h, bh) = self.height();
                 let nlines_vis = h - bh;
                 let grid_range = self.grid.range_().lines(0, h);
@@ -161,12 +370,7 @@ impl Pager {
                 } else {
                     match action {
                         MouseAction::Main => {
-                            let rel_y = pos.y - self.grid.bounds().min.y;
-                            if rel_y > nlines_vis / 2 {
-                                self.down(nlines_vis - 1);
-                            } else {
-                                self.up(nlines_vis - 1);
-                            }
+                            self.click(pos, nlines_vis);
                         }
                         MouseAction::WheelUp => {
                             self.up(1);
@@ -189,37 +393,55 @@ impl Pager {
     /// When a box is present and no explicit footer has been set, a
     /// line-number indicator (e.g. `"0-4/9"` or `"0-4/9+8"` when
     /// scrolled horizontally) is shown in the footer — matching Go
-    /// gruid's behaviour.
+    /// gruid's behaviour. The current section, if any, is named where
+    /// [`PagerStyle::section`] says.
     pub fn draw(&self, grid: &Grid) {
         let (h, bh) = self.height();
         let nlines = h - bh;
 
         let inner_range = if let Some(ref box_decor) = self.box_ {
-            // Auto-generate footer with line numbers when no explicit footer
-            // is set and content doesn't all fit.
-            if box_decor.footer.content().is_empty() && nlines < self.lines.len() as i32 {
-                let lnum = if self.scroll_x > 0 {
-                    format!(
-                        "{}-{}/{}+{}",
-                        self.scroll_y,
-                        self.scroll_y + nlines - 1,
-                        self.lines.len() as i32 - 1,
-                        self.scroll_x,
-                    )
+            let mut box_clone = box_decor.clone();
+            let section = self.section();
+            if let (Some(name), SectionLabel::Title) = (section, self.section_label) {
+                box_clone.title = if box_decor.title.content().is_empty() {
+                    StyledText::new(name, box_decor.title.style())
                 } else {
-                    format!(
-                        "{}-{}/{}",
-                        self.scroll_y,
-                        self.scroll_y + nlines - 1,
-                        self.lines.len() as i32 - 1,
-                    )
+                    box_clone
+                        .title
+                        .with_textf(format!("{} · {name}", box_decor.title.content()))
                 };
-                let mut box_clone = box_decor.clone();
-                box_clone.footer = StyledText::new(&lnum, self.line_num_style);
-                box_clone.draw(grid)
-            } else {
-                box_decor.draw(grid)
             }
+            // Auto-generate footer with the section and line numbers when
+            // no explicit footer is set.
+            if box_decor.footer.content().is_empty() {
+                let mut footer = Vec::new();
+                if let (Some(name), SectionLabel::Footer) = (section, self.section_label) {
+                    footer.push(name.to_string());
+                }
+                // Line numbers only when content doesn't all fit.
+                if nlines < self.lines.len() as i32 {
+                    footer.push(if self.scroll_x > 0 {
+                        format!(
+                            "{}-{}/{}+{}",
+                            self.scroll_y,
+                            self.scroll_y + nlines - 1,
+                            self.lines.len() as i32 - 1,
+                            self.scroll_x,
+                        )
+                    } else {
+                        format!(
+                            "{}-{}/{}",
+                            self.scroll_y,
+                            self.scroll_y + nlines - 1,
+                            self.lines.len() as i32 - 1,
+                        )
+                    });
+                }
+                if !footer.is_empty() {
+                    box_clone.footer = StyledText::new(&footer.join(" · "), self.line_num_style);
+                }
+            }
+            box_clone.draw(grid)
         } else {
             grid.range_()
         };
@@ -253,6 +475,16 @@ impl Pager {
                         }
                     }
                 });
+                if let Some((from, to)) = self.selection.and_then(|s| s.columns(line_idx as i32)) {
+                    let from = (from - scroll_x).max(0);
+                    let to = to.saturating_sub(scroll_x).min(vis_w as i32);
+                    for col in from..to {
+                        let p = Point::new(start.x + col, y);
+                        if let Some(cell) = grid.contains(p).then(|| grid.at(p)) {
+                            grid.set(p, cell.with_style(overlay(cell.style, self.selected_style)));
+                        }
+                    }
+                }
             } else {
                 // Clear rows past the content
                 for col in 0..vis_w {
@@ -264,6 +496,11 @@ impl Pager {
                 }
             }
         }
+
+        if let Some(am) = &self.anchor_menu {
+            am.menu.draw();
+            grid.slice(am.bounds).copy_from(&am.grid);
+        }
     }
 
     /// Return the last action.
@@ -276,10 +513,17 @@ impl Pager {
         self.lines.len()
     }
 
-    /// Replace the lines.
+    /// Replace the lines. Any selection is cleared. The lines are used as
+    /// they are, also after [`set_grid`](Self::set_grid), and anchors refer
+    /// to them by index.
     pub fn set_lines(&mut self, lines: Vec<StyledText>) {
         let nlines = self.visible_height();
         self.lines = lines;
+        self.content = None;
+        self.starts = (0..self.lines.len()).collect();
+        self.anchor_menu = None;
+        self.jumped = None;
+        self.selection = None;
         if self.scroll_y + nlines > self.lines.len() as i32 {
             self.scroll_y = self.lines.len() as i32 - nlines;
             if self.scroll_y < 0 {
@@ -304,18 +548,131 @@ impl Pager {
         }
     }
 
+    /// Draw into `grid` from now on, re-wrapping the content to its width.
+    /// The content line at the top stays there, and anchors follow the new
+    /// wrapping. Any selection is cleared, and the menu of anchors closed.
+    pub fn set_grid(&mut self, grid: Grid) {
+        let top = self.content_line(self.scroll_y.max(0) as usize);
+        let jumped = self
+            .jumped
+            .filter(|&(_, y)| y == self.scroll_y)
+            .map(|(i, _)| i);
+        self.grid = grid;
+        if let Some(content) = &self.content {
+            let width = wrap_width(&self.grid);
+            self.lines = content.format(width).lines();
+            self.starts = line_starts(content, width);
+        }
+        self.selection = None;
+        self.anchor_menu = None;
+        let y = self.starts.get(top).copied().unwrap_or(0);
+        self.set_cursor(Point::new(self.scroll_x, y as i32));
+        self.jumped = jumped.map(|i| (i, self.scroll_y));
+    }
+
+    /// Set the anchors: a section name for each, with the index of the
+    /// content line it starts at, counted before wrapping. Anchors are kept
+    /// in line order.
+    pub fn set_anchors(&mut self, anchors: Vec<(String, usize)>) {
+        self.anchors = anchors;
+        self.anchors.sort_by_key(|&(_, line)| line);
+        self.anchor_menu = None;
+        self.jumped = None;
+    }
+
+    /// The anchors, in line order.
+    pub fn anchors(&self) -> &[(String, usize)] {
+        &self.anchors
+    }
+
+    /// The name of the section the top row is in, if it is past an anchor.
+    pub fn section(&self) -> Option<&str> {
+        self.current_anchor().map(|i| self.anchors[i].0.as_str())
+    }
+
+    /// Whether the menu of anchors is open.
+    pub fn anchor_menu_open(&self) -> bool {
+        self.anchor_menu.is_some()
+    }
+
+    /// Whether some text is selected.
+    pub fn has_selection(&self) -> bool {
+        self.selection.is_some_and(|s| s.moved)
+    }
+
+    /// Clear the selection.
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// The selected text, markup stripped, with lines joined by `\n`.
+    /// Trailing spaces are dropped from each line, as terminals do. `None`
+    /// if nothing, or only blank space, is selected.
+    pub fn selection_text(&self) -> Option<String> {
+        let sel = self.selection.filter(|s| s.moved)?;
+        let (start, end) = sel.bounds();
+        let last = (end.y as usize).min(self.lines.len().checked_sub(1)?);
+        let text = (start.y as usize..=last)
+            .map(|i| {
+                let chars = line_chars(&self.lines[i]);
+                let (from, to) = sel.columns(i as i32).expect("line within selection");
+                let from = (from.max(0) as usize).min(chars.len());
+                let to = (to.max(0) as usize).clamp(from, chars.len());
+                chars[from..to]
+                    .iter()
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        (!text.is_empty()).then_some(text)
+    }
+
     /// Replace the box decoration.
     pub fn set_box(&mut self, box_: Option<BoxDecor>) {
         self.box_ = box_;
     }
 
+    /// Describe the pager for accessibility: its title, and the top
+    /// visible line with its position, and the page. While the menu of
+    /// anchors is open, that menu is described instead.
+    pub fn describe(&self) -> AccessibilityNode {
+        if let Some(am) = &self.anchor_menu {
+            return am.menu.describe();
+        }
+        let mut node = AccessibilityNode::new(Role::Pager);
+        node.label = self.box_.as_ref().and_then(BoxDecor::label);
+        let n = self.lines.len();
+        node.count = Some(n);
+        let top = (self.scroll_y.max(0) as usize).min(n.saturating_sub(1));
+        if let Some(line) = self.lines.get(top) {
+            node.value = Some(line.plain());
+            node.index = Some(top + 1);
+        }
+        let h = self.visible_height().max(0) as usize;
+        if h > 0 && n > h {
+            let pages = n.div_ceil(h);
+            // A page started reads as the next one, since paging keeps one
+            // line of overlap; scrolled to the bottom reads as the last.
+            let page = if top + h >= n {
+                pages
+            } else {
+                top.div_ceil(h) + 1
+            };
+            node.page = Some(page.min(pages));
+            node.pages = Some(pages);
+        }
+        node
+    }
+
     /// Return a [`Range`] representing the currently visible viewport.
     ///
     /// `min` is `(scroll_x, scroll_y)` and `max` is the exclusive far
     /// corner, matching Go gruid's `Pager.View()`.
     pub fn view(&self) -> Range {
         let size = self.grid.size();
-        let bh = if self.box_.is_some() { 2 } else { 0 };
+        let bh = self.box_height();
         let mut h = size.y;
         if h > bh + self.lines.len() as i32 {
             h = bh + self.lines.len() as i32;
@@ -333,14 +690,101 @@ impl Pager {
 
     // -- private helpers matching Go gruid's Pager methods --
 
+    /// Where the content is drawn, in relative grid coordinates.
+    fn content_range(&self) -> Range {
+        let outer = self.grid.range_();
+        let inner = match self.box_ {
+            Some(ref bd) => bd.content_range(outer),
+            None => outer,
+        };
+        inner.lines(0, self.visible_height())
+    }
+
+    /// The content position under the relative grid position `p`, clamped
+    /// to the visible content.
+    fn content_pos(&self, p: Point) -> Point {
+        let inner = self.content_range();
+        let x = p.x.clamp(inner.min.x, (inner.max.x - 1).max(inner.min.x));
+        let y = p.y.clamp(inner.min.y, (inner.max.y - 1).max(inner.min.y));
+        Point::new(
+            x - inner.min.x + self.scroll_x,
+            y - inner.min.y + self.scroll_y,
+        )
+    }
+
+    /// Handle a mouse event for text selection. Returns whether it was
+    /// used.
+    fn select_mouse(&mut self, action: MouseAction, p: Point) -> bool {
+        let dragging = self.selection.is_some_and(|s| s.dragging);
+        match action {
+            MouseAction::Main if self.content_range().contains(p) => {
+                let at = self.content_pos(p);
+                self.selection = Some(Selection {
+                    anchor: at,
+                    head: at,
+                    dragging: true,
+                    moved: false,
+                });
+                true
+            }
+            _ if dragging && action.is_move() => {
+                // Dragging past the top or bottom scrolls the content.
+                let inner = self.content_range();
+                if p.y < inner.min.y {
+                    self.up(1);
+                } else if p.y >= inner.max.y {
+                    self.down(1);
+                }
+                let head = self.content_pos(p);
+                if let Some(sel) = &mut self.selection {
+                    sel.moved |= head != sel.anchor;
+                    sel.head = head;
+                }
+                true
+            }
+            MouseAction::Release | MouseAction::Leave if dragging => {
+                let sel = self.selection.take().expect("dragging");
+                if sel.moved {
+                    self.selection = Some(Selection {
+                        dragging: false,
+                        ..sel
+                    });
+                } else if action == MouseAction::Release {
+                    // A click without drag pages, as it always did.
+                    self.click(p, self.visible_height());
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Page down when clicking the bottom half, up otherwise.
+    fn click(&mut self, p: Point, nlines: i32) {
+        let rel_y = p.y - self.grid.bounds().min.y;
+        if rel_y > nlines / 2 {
+            self.down(nlines - 1);
+        } else {
+            self.up(nlines - 1);
+        }
+    }
+
     fn visible_height(&self) -> i32 {
         let (h, bh) = self.height();
         h - bh
     }
 
+    /// Rows taken by the box decoration, if any.
+    fn box_height(&self) -> i32 {
+        self.box_.as_ref().map_or(0, |bd| {
+            let (before, after) = bd.insets();
+            before.y + after.y
+        })
+    }
+
     fn height(&self) -> (i32, i32) {
         let mut h = self.grid.height();
-        let bh = if self.box_.is_some() { 2 } else { 0 };
+        let bh = self.box_height();
         if h > bh + self.lines.len() as i32 {
             h = bh + self.lines.len() as i32;
         }
@@ -405,6 +849,160 @@ impl Pager {
             self.action = PagerAction::Scroll;
         }
     }
+
+    // -- anchors --
+
+    /// The content line that row `row` of the wrapped lines belongs to.
+    fn content_line(&self, row: usize) -> usize {
+        self.starts.partition_point(|&s| s <= row).saturating_sub(1)
+    }
+
+    /// The row anchor `i` starts at in the wrapped lines.
+    fn anchor_row(&self, i: usize) -> i32 {
+        let line = self.anchors[i].1;
+        let row = self.starts.get(line).copied().unwrap_or(self.lines.len());
+        row.min(self.lines.len().saturating_sub(1)) as i32
+    }
+
+    fn current_anchor(&self) -> Option<usize> {
+        if let Some((i, y)) = self.jumped
+            && y == self.scroll_y
+        {
+            return Some(i);
+        }
+        (0..self.anchors.len())
+            .rev()
+            .find(|&i| self.anchor_row(i) <= self.scroll_y)
+    }
+
+    /// Scroll so that anchor `i` is on the top row, or as close as the end
+    /// of the content allows.
+    fn jump(&mut self, i: usize) {
+        let y = self.scroll_y;
+        self.set_cursor(Point::new(self.scroll_x, self.anchor_row(i)));
+        self.jumped = Some((i, self.scroll_y));
+        if self.scroll_y != y {
+            self.action = PagerAction::Scroll;
+        }
+    }
+
+    /// Jump to the next anchor, or back to the start of the current section
+    /// (the previous anchor when already there), wrapping around.
+    fn step_section(&mut self, forward: bool) {
+        let n = self.anchors.len();
+        let jumped = self.jumped.filter(|&(_, y)| y == self.scroll_y);
+        let i = match jumped {
+            Some((j, _)) if forward => (j + 1) % n,
+            Some((j, _)) => (j + n - 1) % n,
+            None if forward => (0..n)
+                .find(|&i| self.anchor_row(i) > self.scroll_y)
+                .unwrap_or(0),
+            None => (0..n)
+                .rev()
+                .find(|&i| self.anchor_row(i) < self.scroll_y)
+                .unwrap_or(n - 1),
+        };
+        self.jump(i);
+    }
+
+    /// Open the menu of anchors at the top of the content, on the current
+    /// section. It is not opened if the content area is too small for it.
+    fn open_anchor_menu(&mut self) {
+        let inner = self.content_range();
+        let names = self
+            .anchors
+            .iter()
+            .map(|(name, _)| name.chars().count() as i32);
+        let w = (names.max().unwrap_or(0) + 2).min(inner.width());
+        let h = (self.anchors.len() as i32 + 2).min(inner.height());
+        if w < 3 || h < 3 {
+            return;
+        }
+        let x = inner.min.x + (inner.width() - w) / 2;
+        let bounds = Range::new(x, inner.min.y, x + w, inner.min.y + h);
+        let grid = Grid::new(w, h);
+        let mut box_ = BoxDecor::new().with_fill(Cell::default().with_char(' '));
+        if let Some(bd) = &self.box_ {
+            box_.style = bd.style;
+        }
+        let mut menu = Menu::new(MenuConfig {
+            grid: grid.clone(),
+            entries: self
+                .anchors
+                .iter()
+                .map(|(name, _)| MenuEntry::new(StyledText::text(name)))
+                .collect(),
+            provider: None,
+            keys: MenuKeys::default(),
+            box_: Some(box_),
+            style: self.anchor_menu_style.clone(),
+            reorderable: false,
+        });
+        if let Some(i) = self.current_anchor() {
+            menu.set_active(i);
+        }
+        self.anchor_menu = Some(AnchorMenu { menu, grid, bounds });
+    }
+
+    /// Handle a message while the menu of anchors is open: invoking an
+    /// entry jumps to it, and quitting leaves the scroll as it was.
+    fn update_anchor_menu(&mut self, msg: Msg) {
+        let Some(am) = &mut self.anchor_menu else {
+            return;
+        };
+        match msg {
+            Msg::KeyDown { ref key, .. } if self.keys.anchors.contains(key) => {
+                self.anchor_menu = None;
+            }
+            Msg::KeyDown { .. } | Msg::Mouse { .. } => match am.menu.update(am.bounds.rel_msg(msg))
+            {
+                MenuAction::Invoke => {
+                    let i = am.menu.active();
+                    self.anchor_menu = None;
+                    self.jump(i);
+                }
+                MenuAction::Quit => self.anchor_menu = None,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// The width content is wrapped to in `grid`.
+fn wrap_width(grid: &Grid) -> usize {
+    (grid.width().max(0) as usize).saturating_sub(2).max(1)
+}
+
+/// Index of the first wrapped row of each line of `content`, once wrapped
+/// to `width`.
+fn line_starts(content: &StyledText, width: usize) -> Vec<usize> {
+    let mut row = 0;
+    content
+        .lines()
+        .iter()
+        .map(|line| {
+            let start = row;
+            row += line.format(width).lines().len().max(1);
+            start
+        })
+        .collect()
+}
+
+/// The characters of a formatted line by column, markup stripped, with
+/// gaps filled with spaces.
+fn line_chars(line: &StyledText) -> Vec<char> {
+    let mut chars = Vec::new();
+    line.iter(|p, cell| {
+        if p.y == 0 && p.x >= 0 {
+            let x = p.x as usize;
+            if chars.len() <= x {
+                chars.resize(x + 1, ' ');
+            }
+            chars[x] = cell.ch;
+        }
+    });
+    chars
 }
 
 #[cfg(test)]
@@ -441,6 +1039,26 @@ mod tests {
         assert_eq!(pager.view().min.y, 0);
     }
 
+    #[test]
+    fn describe_pages() {
+        let mut pager = make_pager(12, 5);
+        let node = pager.describe();
+        assert_eq!(node.value.as_deref(), Some("Line 0"));
+        assert_eq!((node.index, node.count), (Some(1), Some(12)));
+        assert_eq!((node.page, node.pages), (Some(1), Some(3)));
+
+        pager.update(Msg::key(Key::PageDown));
+        let node = pager.describe();
+        assert_eq!(node.value.as_deref(), Some("Line 4"));
+        assert_eq!(node.page, Some(2));
+
+        pager.update(Msg::key(Key::End));
+        assert_eq!(pager.describe().page, Some(3));
+
+        let node = make_pager(3, 5).describe();
+        assert_eq!((node.page, node.pages), (None, None));
+    }
+
     #[test]
     fn page_up_down() {
         let mut pager = make_pager(30, 5);
@@ -526,16 +1144,22 @@ mod tests {
         assert_eq!(pager.view().min.y, 1);
     }
 
+    fn mouse(action: MouseAction, x: i32, y: i32) -> Msg {
+        Msg::Mouse {
+            action,
+            pos: Point::new(x, y),
+            modifiers: Default::default(),
+            time: std::time::Instant::now(),
+        }
+    }
+
     #[test]
     fn mouse_click_page_down() {
         let mut pager = make_pager(30, 6);
         // Click in the bottom half (y = 4, nlines = 6, half = 3)
-        pager.update(Msg::Mouse {
-            action: MouseAction::Main,
-            pos: Point::new(5, 4),
-            modifiers: Default::default(),
-            time: std::time::Instant::now(),
-        });
+        pager.update(mouse(MouseAction::Main, 5, 4));
+        assert_eq!(pager.action(), PagerAction::Pass);
+        pager.update(mouse(MouseAction::Release, 5, 4));
         assert_eq!(pager.action(), PagerAction::Scroll);
         assert_eq!(pager.view().min.y, 5); // nlines-1 = 5
     }
@@ -546,12 +1170,8 @@ mod tests {
         // First scroll down.
         pager.set_cursor(Point::new(0, 10));
         // Click in the top half (y = 1, nlines = 6, half = 3)
-        pager.update(Msg::Mouse {
-            action: MouseAction::Main,
-            pos: Point::new(5, 1),
-            modifiers: Default::default(),
-            time: std::time::Instant::now(),
-        });
+        pager.update(mouse(MouseAction::Main, 5, 1));
+        pager.update(mouse(MouseAction::Release, 5, 1));
         assert_eq!(pager.action(), PagerAction::Scroll);
         assert_eq!(pager.view().min.y, 5); // 10 - 5
     }
@@ -753,4 +1373,206 @@ mod tests {
             }
         }
     }
+
+    fn drag(pager: &mut Pager, from: (i32, i32), to: (i32, i32)) {
+        pager.update(mouse(MouseAction::Main, from.0, from.1));
+        pager.update(mouse(MouseAction::MainDrag, to.0, to.1));
+        pager.update(mouse(MouseAction::Release, to.0, to.1));
+    }
+
+    #[test]
+    fn selection_flows_across_wrapped_lines() {
+        let red = Style::default().with_fg(gruid_core::Color::from_rgb(255, 0, 0));
+        let content =
+            StyledText::new("alpha @rbeta@N gamma delta", Style::default()).with_markup('r', red);
+        // Wrapped at width 10: "alpha beta", "gamma", "delta".
+        let mut pager = Pager::new(PagerConfig {
+            content,
+            grid: Grid::new(12, 5),
+            keys: PagerKeys::default(),
+            box_: Some(BoxDecor::new()),
+            style: PagerStyle::default(),
+        });
+        assert_eq!(pager.lines(), 3);
+        assert_eq!(pager.selection_text(), None);
+
+        // The box border is at x = 0 and y = 0: content starts at (1, 1).
+        drag(&mut pager, (7, 1), (2, 3));
+        assert!(pager.has_selection());
+        assert_eq!(pager.selection_text().as_deref(), Some("beta\ngamma\nde"));
+        // Dragging backwards selects the same text.
+        drag(&mut pager, (2, 3), (7, 1));
+        assert_eq!(pager.selection_text().as_deref(), Some("beta\ngamma\nde"));
+
+        // Past the end of a line selects up to the end of the text.
+        drag(&mut pager, (9, 2), (11, 2));
+        assert_eq!(pager.selection_text(), None, "no text selected");
+        drag(&mut pager, (4, 2), (11, 2));
+        assert_eq!(pager.selection_text().as_deref(), Some("ma"));
+
+        assert_eq!(pager.update(Msg::key(Key::Char('y'))), PagerAction::Copy);
+        let ctrl_c = Msg::key_mod(Key::Char('c'), ModMask::CTRL);
+        assert_eq!(pager.update(ctrl_c), PagerAction::Copy);
+
+        // New content clears the selection.
+        pager.set_lines(StyledText::text("other").lines());
+        assert!(!pager.has_selection());
+        assert_eq!(pager.update(Msg::key(Key::Char('y'))), PagerAction::Pass);
+    }
+
+    #[test]
+    fn selection_respects_horizontal_scroll() {
+        let mut pager = Pager::new(PagerConfig {
+            content: StyledText::text("0123456789abcdefghij\nABCDEFGHIJKLMNOPQRST"),
+            grid: Grid::new(22, 4),
+            keys: PagerKeys::default(),
+            box_: None,
+            style: PagerStyle::default(),
+        });
+        pager.set_cursor(Point::new(8, 0));
+        drag(&mut pager, (2, 0), (3, 1));
+        assert_eq!(
+            pager.selection_text().as_deref(),
+            Some("abcdefghij\nABCDEFGHIJKL")
+        );
+
+        // Selected cells are drawn in reverse video, others are not.
+        let grid = Grid::new(22, 4);
+        pager.draw(&grid);
+        let reversed = |x, y| {
+            grid.at(Point::new(x, y))
+                .style
+                .attrs
+                .contains(AttrMask::REVERSE)
+        };
+        assert!(!reversed(1, 0) && reversed(2, 0) && reversed(21, 0));
+        assert!(reversed(0, 1) && reversed(3, 1) && !reversed(4, 1));
+    }
+
+    /// Four headed sections whose bodies wrap to several rows, but for the
+    /// last.
+    fn sectioned(width: i32, height: i32) -> Pager {
+        let body = "one two three four five six seven eight nine ten eleven twelve";
+        let content = ["Intro", body, "Usage", body, "Keys", body, "End", "bye"].join("\n");
+        let mut pager = Pager::new(PagerConfig {
+            content: StyledText::text(&content),
+            grid: Grid::new(width, height),
+            keys: PagerKeys::default(),
+            box_: None,
+            style: PagerStyle::default(),
+        });
+        pager.set_anchors(
+            [("Intro", 0), ("Usage", 2), ("Keys", 4), ("End", 6)]
+                .map(|(name, line)| (name.to_string(), line))
+                .into(),
+        );
+        pager
+    }
+
+    fn top(pager: &Pager) -> String {
+        pager.describe().value.unwrap_or_default()
+    }
+
+    #[test]
+    fn anchor_jumps_follow_rewrapping() {
+        let mut pager = sectioned(30, 5);
+        for width in [30, 14] {
+            pager.set_grid(Grid::new(width, 5));
+            pager.update(Msg::key(Key::Char('g')));
+            assert!(pager.anchor_menu_open());
+            pager.update(Msg::key(Key::ArrowDown));
+            pager.update(Msg::key(Key::ArrowDown));
+            let action = pager.update(Msg::key(Key::Enter));
+            assert!(!pager.anchor_menu_open());
+            assert_eq!(action, PagerAction::Scroll);
+            assert_eq!(top(&pager), "Keys", "width {width}");
+            assert_eq!(pager.section(), Some("Keys"));
+
+            // Opening again starts on the current section.
+            pager.update(Msg::key(Key::Char('g')));
+            pager.update(Msg::key(Key::ArrowUp));
+            pager.update(Msg::key(Key::Enter));
+            assert_eq!(top(&pager), "Usage", "width {width}");
+            pager.update(Msg::key(Key::Home));
+        }
+
+        // Re-wrapping keeps the top line in view.
+        pager.update(Msg::key(Key::Char('}')));
+        assert_eq!(top(&pager), "Usage");
+        pager.set_grid(Grid::new(40, 5));
+        assert_eq!(top(&pager), "Usage");
+        pager.set_grid(Grid::new(9, 5));
+        assert_eq!(top(&pager), "Usage");
+        assert_eq!(pager.section(), Some("Usage"));
+    }
+
+    #[test]
+    fn section_keys_step_from_between_anchors() {
+        let mut pager = sectioned(30, 4);
+        assert_eq!(pager.section(), Some("Intro"));
+        pager.update(Msg::key(Key::ArrowDown));
+        assert_eq!(pager.section(), Some("Intro"));
+        assert_eq!(pager.update(Msg::key(Key::Char('}'))), PagerAction::Scroll);
+        assert_eq!(top(&pager), "Usage");
+
+        // Inside a section, back goes to its start, then to the one before.
+        pager.update(Msg::key(Key::ArrowDown));
+        pager.update(Msg::key(Key::ArrowDown));
+        assert_eq!(pager.section(), Some("Usage"));
+        pager.update(Msg::key(Key::Char('{')));
+        assert_eq!(top(&pager), "Usage");
+        pager.update(Msg::key(Key::Char('{')));
+        assert_eq!(top(&pager), "Intro");
+
+        // Before the first it wraps to the last, which the end of the
+        // content keeps off the top row; it still counts as current, so
+        // the next step goes round to the first.
+        pager.update(Msg::key(Key::Char('{')));
+        assert_eq!(pager.section(), Some("End"));
+        assert_ne!(top(&pager), "End");
+        pager.update(Msg::key(Key::Char('}')));
+        assert_eq!(top(&pager), "Intro");
+
+        // From between two anchors, forward goes to the next one.
+        pager.update(Msg::key(Key::Char('}')));
+        pager.update(Msg::key(Key::Char('}')));
+        pager.update(Msg::key(Key::ArrowUp));
+        assert_eq!(pager.section(), Some("Usage"));
+        pager.update(Msg::key(Key::Char('}')));
+        assert_eq!(top(&pager), "Keys");
+    }
+
+    #[test]
+    fn cancelled_anchor_menu_keeps_scroll() {
+        let mut pager = sectioned(30, 8);
+        pager.set_box(Some(crate::BoxDecor::new()));
+        pager.update(Msg::key(Key::PageDown));
+        let view = pager.view();
+
+        for close in [
+            Msg::key(Key::Escape),
+            Msg::key(Key::Char('g')),
+            mouse(MouseAction::Main, 1, 6),
+        ] {
+            assert_eq!(pager.update(Msg::key(Key::Char('g'))), PagerAction::Pass);
+            assert!(pager.anchor_menu_open());
+            // Keys go to the menu, not the pager.
+            pager.update(Msg::key(Key::ArrowDown));
+            pager.update(Msg::key(Key::End));
+            assert_eq!(pager.view(), view);
+            let grid = Grid::new(30, 8);
+            pager.draw(&grid);
+            assert_eq!(pager.update(close), PagerAction::Pass);
+            assert!(!pager.anchor_menu_open());
+            assert_eq!(pager.view(), view);
+        }
+
+        // The pager handles keys again, and names the section in its footer.
+        assert_eq!(pager.update(Msg::key(Key::ArrowDown)), PagerAction::Scroll);
+        assert_eq!(pager.view().min.y, view.min.y + 1);
+        let grid = Grid::new(30, 8);
+        pager.draw(&grid);
+        let footer: String = (0..30).map(|x| grid.at(Point::new(x, 7)).ch).collect();
+        assert!(footer.contains("Usage · 6-11/"), "{footer}");
+    }
 }
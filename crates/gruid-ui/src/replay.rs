@@ -2,28 +2,114 @@
 //!
 //! [`Replay`] implements [`Model`] and can serve as the main application
 //! model for session playback with speed control, pause, seeking, and undo.
+//!
+//! Recordings made at another size than the viewer are shown according to
+//! [`ReplayConfig::fit`]: clipped, centered, scrolled or downscaled (see
+//! [`ViewFit`]). The view only changes how the replay grid is drawn: the
+//! grid itself always holds the whole recording.
+//!
+//! The [metadata](gruid_core::recording::RecordingMeta) of a recording, if
+//! it has any, is shown at the top of the help overlay.
+//!
+//! [Events](gruid_core::recording::RecordedEvent) recorded with the frames
+//! are emitted as [`ReplayEvent`] messages when playback reaches them, at
+//! their recorded time scaled by the playback speed, so that a host model
+//! can play the recorded sounds or show the toasts. Stepping back plays no
+//! events; seeking forward skips them or delivers them at once, flagged as
+//! catch-up, as set by [`ReplayConfig::seek_events`]. Events recorded after
+//! the last frame are not played.
 
 use std::io::Read;
 use std::time::Duration;
 
-use gruid_core::app::Effect;
+use gruid_core::app::{Effect, emit, set_clipboard};
+use gruid_core::geom::{Point, Range};
 use gruid_core::grid::{Frame, FrameCell, Grid};
-use gruid_core::messages::{Key, MouseAction, Msg};
-use gruid_core::recording::FrameDecoder;
+use gruid_core::messages::{Key, ModMask, MouseAction, Msg};
+use gruid_core::recording::{FrameDecoder, Record, RecordedEvent, RecordingMeta};
+use gruid_core::{Cell, Style};
 
 use crate::pager::{Pager, PagerAction, PagerConfig, PagerKeys, PagerStyle};
-use crate::{BoxDecor, StyledText};
+use crate::{AccessibilityNode, BoxDecor, KeyAction, KeyBindings, KeyContext, Role, StyledText};
+
+/// Private tick message for replay auto-advance: the frame index and the
+/// number of events before the next frame fired when it was scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReplayTick(usize, usize);
+
+/// A recorded event reached by playback, emitted by [`Replay::update`] as
+/// a custom message for the host model to route to its sound or toast
+/// handlers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayEvent {
+    /// Tag the application recorded the event with.
+    pub tag: u16,
+    /// Data recorded with the event.
+    pub payload: Vec<u8>,
+    /// Whether the event was skipped over by seeking forward, and is
+    /// delivered with the others of the span at once. Handlers may want to
+    /// keep only the last music change, say, rather than play every sound.
+    pub catch_up: bool,
+}
+
+impl ReplayEvent {
+    fn new(event: &RecordedEvent, catch_up: bool) -> Self {
+        Self {
+            tag: event.tag,
+            payload: event.payload.clone(),
+            catch_up,
+        }
+    }
+}
+
+/// What happens to the events of the span skipped by seeking forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeekEvents {
+    /// Drop them.
+    #[default]
+    Suppress,
+    /// Emit them all at once, with [`ReplayEvent::catch_up`] set.
+    CatchUp,
+}
+
+/// Playback speeds reachable with the speed keys, slowest first.
+pub const SPEED_LADDER: [f32; 10] = [0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0];
+
+/// Index of 1x in [`SPEED_LADDER`].
+const NORMAL_SPEED: usize = 3;
+
+/// Longest recorded gap between two frames that is honoured, in ms.
+const MAX_GAP_MS: u64 = 2000;
 
-/// Private tick message for replay auto-advance.
-#[derive(Debug, Clone, Copy)]
-struct ReplayTick(usize);
+/// Shortest wait between two frames, in ms, so fast playback doesn't
+/// busy-spin.
+const MIN_DELAY_MS: u64 = 4;
+
+/// Wait before showing the next frame, given the recorded gap and the
+/// playback speed.
+///
+/// The gap is capped at 2 s *before* scaling, so a long idle pause in the
+/// recording takes at most `2 s / speed`: 8 s at 0.25x. The result is
+/// never below the 4 ms minimum.
+fn frame_delay_ms(gap_ms: u64, speed: f32) -> u64 {
+    let d = gap_ms.min(MAX_GAP_MS) as f64 / speed as f64;
+    (d.round() as u64).max(MIN_DELAY_MS)
+}
+
+/// Wait between the steps at `from_ms` and `to_ms` of the gap after the
+/// frame shown at `start_ms`, events being steps too. The gap is capped as
+/// in [`frame_delay_ms`], so that events do not make it longer.
+fn step_delay_ms(start_ms: u64, from_ms: u64, to_ms: u64, speed: f32) -> u64 {
+    let at = |t: u64| t.saturating_sub(start_ms).min(MAX_GAP_MS);
+    frame_delay_ms(at(to_ms).saturating_sub(at(from_ms)), speed)
+}
 
 // ---------------------------------------------------------------------------
 // Key bindings
 // ---------------------------------------------------------------------------
 
 /// Key bindings for the replay widget.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReplayKeys {
     pub quit: Vec<Key>,
     pub pause: Vec<Key>,
@@ -34,6 +120,9 @@ pub struct ReplayKeys {
     pub forward: Vec<Key>,
     pub backward: Vec<Key>,
     pub help: Vec<Key>,
+    /// Toggle pan mode, in which the frame and seek keys pan the view
+    /// instead. Only used with [`ViewFit::Scroll`].
+    pub pan: Vec<Key>,
 }
 
 impl Default for ReplayKeys {
@@ -48,6 +137,27 @@ impl Default for ReplayKeys {
             forward: vec![Key::ArrowUp, Key::Char('k')],
             backward: vec![Key::ArrowDown, Key::Char('j')],
             help: vec![Key::Char('?')],
+            pan: vec![Key::Char('v')],
+        }
+    }
+}
+
+impl ReplayKeys {
+    /// The keys bound in `bindings` for the [`KeyContext::Replay`] actions.
+    pub fn from_bindings(bindings: &KeyBindings) -> Self {
+        use KeyAction::*;
+        let keys = |action| bindings.keys(KeyContext::Replay, action).to_vec();
+        Self {
+            quit: keys(Cancel),
+            pause: keys(Pause),
+            speed_more: keys(Faster),
+            speed_less: keys(Slower),
+            frame_next: keys(NavRight),
+            frame_prev: keys(NavLeft),
+            forward: keys(NavUp),
+            backward: keys(NavDown),
+            help: keys(Help),
+            pan: keys(Pan),
         }
     }
 }
@@ -74,9 +184,49 @@ pub enum ReplayAction {
 }
 
 // ---------------------------------------------------------------------------
-// Internal tick message
+// View
 // ---------------------------------------------------------------------------
 
+/// How a recording is shown when its size differs from the view's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewFit {
+    /// Draw at the top-left corner, cutting what does not fit.
+    #[default]
+    Clip,
+    /// Center a recording smaller than the view, letterboxed in the border
+    /// style. A larger one is clipped, as with [`Clip`](Self::Clip).
+    Center,
+    /// Show a viewport over a recording larger than the view, with a
+    /// position indicator. Shift+arrows pan it, as do the frame and seek
+    /// keys in pan mode, toggled with [`ReplayKeys::pan`].
+    Scroll,
+    /// Show every Nth cell of each row and column of a recording larger
+    /// than the view, N being as small as possible. This is a rough
+    /// preview, marked as approximate.
+    Downscale,
+}
+
+/// Offset centering a recording of size `rec` in a view of size `view`,
+/// along the axes where it is smaller.
+fn center_offset(rec: Point, view: Point) -> Point {
+    Point::new((view.x - rec.x).max(0) / 2, (view.y - rec.y).max(0) / 2)
+}
+
+/// Clamp the top-left corner of a `view`-sized viewport so that it stays
+/// within a recording of size `rec`.
+fn clamp_scroll(scroll: Point, rec: Point, view: Point) -> Point {
+    Point::new(
+        scroll.x.clamp(0, (rec.x - view.x).max(0)),
+        scroll.y.clamp(0, (rec.y - view.y).max(0)),
+    )
+}
+
+/// Smallest cell step making a recording of size `rec` fit in `view`.
+fn downscale_factor(rec: Point, view: Point) -> i32 {
+    let axis = |r: i32, v: i32| (r + v.max(1) - 1) / v.max(1);
+    axis(rec.x, view.x).max(axis(rec.y, view.y)).max(1)
+}
+
 // ---------------------------------------------------------------------------
 // Replay
 // ---------------------------------------------------------------------------
@@ -86,6 +236,22 @@ pub struct ReplayConfig<R: Read> {
     pub grid: Grid,
     pub decoder: FrameDecoder<R>,
     pub keys: ReplayKeys,
+    /// How to show recordings of another size than `grid`.
+    pub fit: ViewFit,
+    /// Style of the letterbox around centered recordings, and of the
+    /// scroll and downscale indicators.
+    pub border: Style,
+    /// What happens to the recorded events skipped by seeking forward.
+    pub seek_events: SeekEvents,
+}
+
+impl<R: Read> ReplayConfig<R> {
+    /// Use `keys`, such as [`ReplayKeys::from_bindings`] of the player's
+    /// preset.
+    pub fn with_keys(mut self, keys: ReplayKeys) -> Self {
+        self.keys = keys;
+        self
+    }
 }
 
 /// Replays a recorded session frame-by-frame.
@@ -96,47 +262,78 @@ pub struct ReplayConfig<R: Read> {
 pub struct Replay<R: Read> {
     decoder: FrameDecoder<R>,
     frames: Vec<Frame>,
+    /// The events recorded before each frame, after the previous one.
+    events: Vec<Vec<RecordedEvent>>,
+    /// Events decoded that no frame follows yet.
+    pending: Vec<RecordedEvent>,
+    /// Number of the events before the next frame already emitted.
+    fired: usize,
+    /// Events to emit with the effect of the next update.
+    out: Vec<ReplayEvent>,
+    seek_events: SeekEvents,
     grid: Grid,
     undo: Vec<Vec<FrameCell>>,
     fidx: usize,
     auto_play: bool,
-    /// Speed multiplier (1 = normal, 2 = 2x, etc.)
-    speed: u32,
+    /// Index of the current speed in [`SPEED_LADDER`].
+    speed: usize,
     action: ReplayAction,
     is_init: bool,
     keys: ReplayKeys,
     dirty: bool,
     help: bool,
     help_pager: Option<Pager>,
+    fit: ViewFit,
+    border: Style,
+    /// Size of the view, that of the configured grid until a
+    /// [`Msg::Screen`].
+    view: Point,
+    /// Top-left corner of the viewport in [`ViewFit::Scroll`].
+    scroll: Point,
+    pan: bool,
 }
 
 impl<R: Read> Replay<R> {
     /// Create a new replay from configuration.
     pub fn new(cfg: ReplayConfig<R>) -> Self {
+        let view = cfg.grid.size();
         Self {
             decoder: cfg.decoder,
             frames: Vec::new(),
+            events: Vec::new(),
+            pending: Vec::new(),
+            fired: 0,
+            out: Vec::new(),
+            seek_events: cfg.seek_events,
             grid: cfg.grid,
             undo: Vec::new(),
             fidx: 0,
             auto_play: true,
-            speed: 1,
+            speed: NORMAL_SPEED,
             action: ReplayAction::None,
             is_init: false,
             keys: cfg.keys,
             dirty: true,
             help: false,
             help_pager: None,
+            fit: cfg.fit,
+            border: cfg.border,
+            view,
+            scroll: Point::ZERO,
+            pan: false,
         }
     }
 
-    /// Set the current frame index.
+    /// Set the current frame index. Events skipped forward are handled
+    /// as set by [`ReplayConfig::seek_events`], and emitted by the next
+    /// [`update`](Self::update).
     pub fn set_frame(&mut self, n: usize) {
         while self.fidx < n {
             self.decode_next();
             if self.fidx >= self.frames.len() {
                 break;
             }
+            self.pass_events(true);
             self.fidx += 1;
             self.apply_next();
         }
@@ -145,6 +342,7 @@ impl<R: Read> Replay<R> {
                 break;
             }
             self.fidx -= 1;
+            self.fired = 0;
             self.apply_previous();
         }
         self.dirty = true;
@@ -171,18 +369,25 @@ impl<R: Read> Replay<R> {
                 if self.fidx >= self.frames.len() {
                     break;
                 }
+                self.pass_events(true);
                 self.fidx += 1;
                 self.apply_next();
             }
         } else {
             while self.fidx > 1 && (self.frames[self.fidx - 1].time_ms as i64) > target_time {
                 self.fidx -= 1;
+                self.fired = 0;
                 self.apply_previous();
             }
         }
         self.dirty = true;
     }
 
+    /// The metadata of the recording, if it has any.
+    pub fn metadata(&self) -> Option<&RecordingMeta> {
+        self.decoder.metadata()
+    }
+
     /// The current frame index.
     pub fn frame_index(&self) -> usize {
         self.fidx
@@ -193,9 +398,43 @@ impl<R: Read> Replay<R> {
         self.auto_play
     }
 
-    /// Current speed multiplier.
-    pub fn speed(&self) -> u32 {
-        self.speed
+    /// Current speed multiplier (1.0 = normal, 0.5 = half speed).
+    pub fn speed(&self) -> f32 {
+        SPEED_LADDER[self.speed]
+    }
+
+    /// Set the speed multiplier, snapping to the nearest entry of
+    /// [`SPEED_LADDER`] (so old integer speeds like `2` or `8` map exactly).
+    pub fn set_speed(&mut self, speed: f32) {
+        let log = speed.max(f32::MIN_POSITIVE).log2();
+        self.speed = (0..SPEED_LADDER.len())
+            .min_by(|&a, &b| {
+                let da = (SPEED_LADDER[a].log2() - log).abs();
+                let db = (SPEED_LADDER[b].log2() - log).abs();
+                da.total_cmp(&db)
+            })
+            .unwrap_or(NORMAL_SPEED);
+    }
+
+    /// Human-readable speed, e.g. `"x0.5"` or `"x4"`.
+    pub fn speed_label(&self) -> String {
+        format!("x{}", self.speed())
+    }
+
+    /// Describe the replay for accessibility: the current frame and
+    /// whether it is playing. The number of frames is not known before
+    /// the recording has been read through, so it is left out. While the
+    /// help overlay is shown, it is described instead.
+    pub fn describe(&self) -> AccessibilityNode {
+        if self.help
+            && let Some(pager) = &self.help_pager
+        {
+            return pager.describe();
+        }
+        let mut node = AccessibilityNode::new(Role::Replay);
+        node.index = (self.fidx > 0).then_some(self.fidx);
+        node.playing = self.auto_play;
+        node
     }
 
     /// Whether the help overlay is currently shown.
@@ -203,15 +442,124 @@ impl<R: Read> Replay<R> {
         self.help
     }
 
+    /// The view policy.
+    pub fn fit(&self) -> ViewFit {
+        self.fit
+    }
+
+    /// Whether pan mode is on.
+    pub fn is_pan_mode(&self) -> bool {
+        self.pan
+    }
+
+    /// Top-left corner of the viewport over the recording, with
+    /// [`ViewFit::Scroll`].
+    pub fn scroll_offset(&self) -> Point {
+        self.scroll
+    }
+
+    /// Size of the recording at the current frame: that of the grid before
+    /// the first frame, or if frames carry no size.
+    fn recording_size(&self) -> Point {
+        match self.fidx.checked_sub(1).and_then(|i| self.frames.get(i)) {
+            Some(f) if f.width > 0 && f.height > 0 => Point::new(f.width, f.height),
+            _ => self.grid.size(),
+        }
+    }
+
+    /// Move the viewport by `(dx, dy)`, within the recording.
+    fn pan_by(&mut self, dx: i32, dy: i32) {
+        let scroll = self.scroll.shift(dx, dy);
+        self.scroll = clamp_scroll(scroll, self.recording_size(), self.view);
+        self.dirty = true;
+    }
+
+    /// Handle the keys of [`ViewFit::Scroll`], returning whether `key` was
+    /// one of them. Shift+arrows always pan; in pan mode, the frame and
+    /// seek keys do too, and the quit keys leave pan mode.
+    fn update_pan_key(&mut self, key: &Key, modifiers: ModMask) -> bool {
+        if self.fit != ViewFit::Scroll {
+            return false;
+        }
+        if key_in(key, &self.keys.pan) || self.pan && key_in(key, &self.keys.quit) {
+            self.pan = !self.pan;
+            self.dirty = true;
+            return true;
+        }
+        let (dx, dy) = match key {
+            Key::ArrowLeft if modifiers.contains(ModMask::SHIFT) => (-1, 0),
+            Key::ArrowRight if modifiers.contains(ModMask::SHIFT) => (1, 0),
+            Key::ArrowUp if modifiers.contains(ModMask::SHIFT) => (0, -1),
+            Key::ArrowDown if modifiers.contains(ModMask::SHIFT) => (0, 1),
+            _ if !self.pan => return false,
+            _ if key_in(key, &self.keys.frame_prev) => (-1, 0),
+            _ if key_in(key, &self.keys.frame_next) => (1, 0),
+            _ if key_in(key, &self.keys.forward) => (0, -1),
+            _ if key_in(key, &self.keys.backward) => (0, 1),
+            _ => return false,
+        };
+        self.pan_by(dx, dy);
+        true
+    }
+
     fn decode_next(&mut self) {
         if self.fidx >= self.frames.len() {
-            // Try to read one more frame.
-            if let Ok(Some(frame)) = self.decoder.decode() {
-                self.frames.push(frame);
+            // Try to read one more frame, with the events before it.
+            while let Ok(Some(record)) = self.decoder.decode_record() {
+                match record {
+                    Record::Event(event) => self.pending.push(event),
+                    Record::Frame(frame) => {
+                        self.frames.push(frame);
+                        self.events.push(std::mem::take(&mut self.pending));
+                        break;
+                    }
+                }
             }
         }
     }
 
+    /// Pass the events before the next frame that were not emitted yet,
+    /// as the frame is about to be shown: they are emitted when playing,
+    /// and handled as [`seek_events`](ReplayConfig::seek_events) says when
+    /// `seeking`.
+    fn pass_events(&mut self, seeking: bool) {
+        let fired = std::mem::take(&mut self.fired);
+        if seeking && self.seek_events == SeekEvents::Suppress {
+            return;
+        }
+        if let Some(events) = self.events.get(self.fidx) {
+            let rest = events.get(fired..).unwrap_or_default();
+            self.out
+                .extend(rest.iter().map(|e| ReplayEvent::new(e, seeking)));
+        }
+    }
+
+    /// Emit the next events due before the next frame, those recorded at
+    /// the same time together. Returns `false` if none is left.
+    fn fire_due_events(&mut self) -> bool {
+        let Some(due) = self.events.get(self.fidx).and_then(|e| e.get(self.fired..)) else {
+            return false;
+        };
+        let Some(time) = due.first().map(|e| e.time_ms) else {
+            return false;
+        };
+        let n = due.iter().take_while(|e| e.time_ms == time).count();
+        self.out
+            .extend(due[..n].iter().map(|e| ReplayEvent::new(e, false)));
+        self.fired += n;
+        true
+    }
+
+    /// Add the events to emit to `effect`.
+    fn with_events(&mut self, effect: Option<Effect>) -> Option<Effect> {
+        if self.out.is_empty() {
+            return effect;
+        }
+        let mut effects: Vec<Effect> = self.out.drain(..).map(emit).collect();
+        effects.extend(effect);
+        Some(Effect::Batch(effects))
+    }
+
     fn apply_next(&mut self) {
         if self.fidx == 0 || self.fidx > self.frames.len() {
             return;
@@ -255,6 +603,7 @@ impl<R: Read> Replay<R> {
                     self.action = ReplayAction::None;
                     return;
                 }
+                self.pass_events(false);
                 self.fidx += 1;
             }
             ReplayAction::Previous => {
@@ -263,15 +612,16 @@ impl<R: Read> Replay<R> {
                     return;
                 }
                 self.fidx -= 1;
+                self.fired = 0;
             }
             ReplayAction::TogglePause => {
                 self.auto_play = !self.auto_play;
             }
             ReplayAction::SpeedMore => {
-                self.speed = (self.speed * 2).min(64);
+                self.speed = (self.speed + 1).min(SPEED_LADDER.len() - 1);
             }
             ReplayAction::SpeedLess => {
-                self.speed = (self.speed / 2).max(1);
+                self.speed = self.speed.saturating_sub(1);
             }
             _ => {}
         }
@@ -290,35 +640,48 @@ impl<R: Read> Replay<R> {
         }
     }
 
-    fn tick_effect(&self) -> Option<Effect> {
+    /// Wait before the next step of playback: the next event due, or the
+    /// next frame.
+    fn tick_delay_ms(&mut self) -> u64 {
+        // The next frame, with the events before it, sets the wait.
+        self.decode_next();
+        let prev = self.fidx.checked_sub(1).and_then(|i| self.frames.get(i));
+        let (Some(prev), Some(next)) = (prev, self.frames.get(self.fidx)) else {
+            return MIN_DELAY_MS;
+        };
+        let events = &self.events[self.fidx];
+        let from = match self.fired.checked_sub(1) {
+            Some(i) => events[i].time_ms,
+            None => prev.time_ms,
+        };
+        let to = events.get(self.fired).map_or(next.time_ms, |e| e.time_ms);
+        step_delay_ms(prev.time_ms, from, to, self.speed())
+    }
+
+    fn tick_effect(&mut self) -> Option<Effect> {
         if !self.auto_play || self.fidx > self.frames.len() {
             return None;
         }
 
-        let delay_ms = if self.fidx > 0 && self.fidx < self.frames.len() {
-            let prev_t = self.frames[self.fidx - 1].time_ms;
-            let curr_t = self.frames[self.fidx].time_ms;
-            let d = curr_t.saturating_sub(prev_t);
-            // Cap at 2 seconds
-            let d = d.min(2000);
-            // Apply speed
-            let d = d / self.speed as u64;
-            // Minimum interval ~4ms
-            d.max(4)
-        } else {
-            4
-        };
-
-        let fidx = self.fidx;
-        Some(Effect::Cmd(Box::new(move || {
-            std::thread::sleep(Duration::from_millis(delay_ms));
-            Some(Msg::custom(ReplayTick(fidx)))
-        })))
+        let after = Duration::from_millis(self.tick_delay_ms());
+        let tick = ReplayTick(self.fidx, self.fired);
+        Some(Effect::Tick {
+            after,
+            msg: Msg::custom(tick),
+        })
     }
 
     /// Build the help lines describing all key bindings.
     fn build_help_lines(&self) -> Vec<StyledText> {
         let mut lines = Vec::new();
+        if let Some(meta) = self.metadata() {
+            let mut about = format!("{} {}", meta.app_name, meta.app_version);
+            if let Some(seed) = meta.seed {
+                about.push_str(&format!(", seed {seed}"));
+            }
+            lines.push(StyledText::textf(about.trim().to_string()));
+            lines.push(StyledText::text(""));
+        }
         let fmt_line = |title: &str, keys: &[Key]| -> StyledText {
             let keys_str: String = keys
                 .iter()
@@ -336,12 +699,30 @@ impl<R: Read> Replay<R> {
         lines.push(fmt_line("Forward", &self.keys.forward));
         lines.push(fmt_line("Backward", &self.keys.backward));
         lines.push(fmt_line("Help", &self.keys.help));
+        let view_line =
+            |title: &str, text: &str| StyledText::textf(format!("{:<30} {}", title, text));
+        match self.fit {
+            ViewFit::Clip => {}
+            ViewFit::Center => lines.push(view_line("View", "centered")),
+            ViewFit::Scroll => {
+                lines.push(view_line("View", "scrolling"));
+                lines.push(fmt_line("Pan mode", &self.keys.pan));
+                lines.push(view_line("Pan", "Shift+arrows"));
+                lines.push(view_line("Pan (pan mode)", "frame and seek keys"));
+                lines.push(fmt_line("Leave pan mode", &self.keys.quit));
+            }
+            ViewFit::Downscale => {
+                let n = downscale_factor(self.recording_size(), self.view);
+                let text = format!("1 cell in {n} per axis (approximate)");
+                lines.push(view_line("View", &text));
+            }
+        }
         lines
     }
 
     /// Enter help mode, constructing the pager.
     fn enter_help(&mut self) {
-        let gs = self.grid.size();
+        let gs = self.view;
         let mut quit_keys: Vec<Key> = vec![Key::Escape];
         for k in &self.keys.help {
             if !quit_keys.contains(k) {
@@ -378,11 +759,14 @@ impl<R: Read> Replay<R> {
     /// Process a message when in help mode.
     fn update_help(&mut self, msg: Msg) -> Option<Effect> {
         if let Some(ref mut pager) = self.help_pager {
-            let action = pager.update(msg);
-            if action == PagerAction::Quit {
-                self.help = false;
-                self.help_pager = None;
-                self.dirty = true;
+            match pager.update(msg) {
+                PagerAction::Quit => {
+                    self.help = false;
+                    self.help_pager = None;
+                    self.dirty = true;
+                }
+                PagerAction::Copy => return pager.selection_text().map(set_clipboard),
+                _ => {}
             }
         }
         None
@@ -391,7 +775,7 @@ impl<R: Read> Replay<R> {
     /// Handle mouse messages.
     fn update_mouse(&mut self, action: MouseAction, pos: gruid_core::Point) {
         // Only respond to clicks inside the grid bounds.
-        if !self.grid.range_().contains(pos) {
+        if !Range::new(0, 0, self.view.x, self.view.y).contains(pos) {
             return;
         }
         match action {
@@ -410,8 +794,14 @@ impl<R: Read> Replay<R> {
         }
     }
 
-    /// Process a message, returning an optional effect.
+    /// Process a message, returning an optional effect. It emits the
+    /// [`ReplayEvent`]s playback reached.
     pub fn update(&mut self, msg: Msg) -> Option<Effect> {
+        let effect = self.update_replay(msg);
+        self.with_events(effect)
+    }
+
+    fn update_replay(&mut self, msg: Msg) -> Option<Effect> {
         // If in help mode, delegate to pager.
         if self.help {
             return self.update_help(msg);
@@ -425,7 +815,10 @@ impl<R: Read> Replay<R> {
                 self.decode_next();
                 return self.tick_effect();
             }
-            Msg::KeyDown { key, .. } => {
+            Msg::KeyDown { key, modifiers, .. } => {
+                if self.update_pan_key(&key, modifiers) {
+                    return None;
+                }
                 if key_in(&key, &self.keys.quit) {
                     if self.is_init {
                         return Some(Effect::End);
@@ -454,11 +847,20 @@ impl<R: Read> Replay<R> {
             Msg::Mouse { action, pos, .. } => {
                 self.update_mouse(action, pos);
             }
-            _ if msg.downcast_ref::<ReplayTick>().is_some() => {
-                let tick = msg.downcast_ref::<ReplayTick>().unwrap();
-                if self.auto_play && self.fidx == tick.0 {
-                    self.action = ReplayAction::Next;
+            Msg::Screen { width, height, .. } => {
+                self.view = Point::new(width, height);
+                self.scroll = clamp_scroll(self.scroll, self.recording_size(), self.view);
+                self.dirty = true;
+            }
+            Msg::Custom(c)
+                if self.auto_play
+                    && c.downcast_ref::<ReplayTick>()
+                        == Some(&ReplayTick(self.fidx, self.fired)) =>
+            {
+                if self.fire_due_events() {
+                    return self.tick_effect();
                 }
+                self.action = ReplayAction::Next;
             }
             _ => {}
         }
@@ -481,15 +883,77 @@ impl<R: Read> Replay<R> {
                 return;
             }
         }
-        grid.copy_from(&self.grid);
+        match self.fit {
+            ViewFit::Clip => {
+                grid.copy_from(&self.grid);
+            }
+            ViewFit::Center => self.draw_centered(grid),
+            ViewFit::Scroll => self.draw_scrolled(grid),
+            ViewFit::Downscale => self.draw_downscaled(grid),
+        }
+    }
+
+    fn draw_centered(&self, grid: &Grid) {
+        let rec = self.recording_size();
+        let off = center_offset(rec, grid.size());
+        if off == Point::ZERO {
+            grid.copy_from(&self.grid);
+            return;
+        }
+        grid.fill(Cell::default().with_style(self.border));
+        let dst = Range::new(off.x, off.y, off.x + rec.x, off.y + rec.y).intersect(grid.range_());
+        grid.slice(dst)
+            .copy_from(&self.grid.slice(Range::new(0, 0, rec.x, rec.y)));
+        if off.x > 0 && off.y > 0 {
+            let mut b = BoxDecor::new();
+            b.style = self.border;
+            b.draw_at(grid, dst.shift(-1, -1, 1, 1));
+        }
+    }
+
+    fn draw_scrolled(&self, grid: &Grid) {
+        let view = grid.size();
+        let rec = self.recording_size();
+        let scroll = clamp_scroll(self.scroll, rec, view);
+        grid.copy_from(
+            &self
+                .grid
+                .slice(Range::new(scroll.x, scroll.y, rec.x, rec.y)),
+        );
+        if rec.x > view.x || rec.y > view.y || self.pan {
+            let mode = if self.pan { " PAN" } else { "" };
+            let label = format!(" {},{}/{}x{}{} ", scroll.x, scroll.y, rec.x, rec.y, mode);
+            self.draw_indicator(grid, &label, view.y - 1);
+        }
+    }
+
+    fn draw_downscaled(&self, grid: &Grid) {
+        let view = grid.size();
+        let rec = self.recording_size();
+        let n = downscale_factor(rec, view);
+        if n == 1 {
+            grid.copy_from(&self.grid);
+            return;
+        }
+        for y in 0..view.y.min((rec.y + n - 1) / n) {
+            for x in 0..view.x.min((rec.x + n - 1) / n) {
+                grid.set(Point::new(x, y), self.grid.at(Point::new(x * n, y * n)));
+            }
+        }
+        self.draw_indicator(grid, &format!(" ~1/{n} "), 0);
+    }
+
+    /// Draw `label` right-aligned on row `y`, in the border style.
+    fn draw_indicator(&self, grid: &Grid, label: &str, y: i32) {
+        let w = label.chars().count() as i32;
+        let x = (grid.size().x - w).max(0);
+        StyledText::new(label, self.border).draw(&grid.slice(Range::new(x, y, x + w, y + 1)));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use gruid_core::cell::Cell;
-    use gruid_core::geom::Point;
     use gruid_core::recording::FrameEncoder;
     fn make_test_frames() -> Vec<u8> {
         let mut buf = Vec::new();
@@ -504,6 +968,7 @@ mod tests {
                     width: 10,
                     height: 5,
                     time_ms: i as u64 * 100,
+                    cursor: None,
                 };
                 enc.encode(&frame).unwrap();
             }
@@ -518,6 +983,9 @@ mod tests {
             grid,
             decoder,
             keys: ReplayKeys::default(),
+            fit: ViewFit::Clip,
+            border: Style::default(),
+            seek_events: SeekEvents::default(),
         })
     }
 
@@ -559,16 +1027,59 @@ mod tests {
         let data = make_test_frames();
         let mut replay = make_replay(&data, 10, 5);
 
-        assert_eq!(replay.speed(), 1);
+        assert_eq!(replay.speed(), 1.0);
         replay.action = ReplayAction::SpeedMore;
         replay.handle_action();
-        assert_eq!(replay.speed(), 2);
+        assert_eq!(replay.speed(), 2.0);
         replay.action = ReplayAction::SpeedMore;
         replay.handle_action();
-        assert_eq!(replay.speed(), 4);
+        assert_eq!(replay.speed(), 4.0);
         replay.action = ReplayAction::SpeedLess;
         replay.handle_action();
-        assert_eq!(replay.speed(), 2);
+        assert_eq!(replay.speed(), 2.0);
+    }
+
+    #[test]
+    fn replay_speed_ladder_clamps() {
+        let data = make_test_frames();
+        let mut replay = make_replay(&data, 10, 5);
+        for _ in 0..20 {
+            replay.action = ReplayAction::SpeedLess;
+            replay.handle_action();
+        }
+        assert_eq!(replay.speed(), 0.125);
+        assert_eq!(replay.speed_label(), "x0.125");
+        replay.action = ReplayAction::SpeedMore;
+        replay.handle_action();
+        assert_eq!(replay.speed_label(), "x0.25");
+        for _ in 0..20 {
+            replay.action = ReplayAction::SpeedMore;
+            replay.handle_action();
+        }
+        assert_eq!(replay.speed(), 64.0);
+        assert_eq!(replay.speed_label(), "x64");
+
+        replay.set_speed(8.0);
+        assert_eq!(replay.speed_label(), "x8");
+        replay.set_speed(0.3);
+        assert_eq!(replay.speed(), 0.25);
+        replay.set_speed(1000.0);
+        assert_eq!(replay.speed(), 64.0);
+        replay.set_speed(0.0);
+        assert_eq!(replay.speed(), 0.125);
+    }
+
+    #[test]
+    fn frame_delay_scaling() {
+        assert_eq!(frame_delay_ms(100, 1.0), 100);
+        assert_eq!(frame_delay_ms(100, 0.25), 400);
+        // The 2 s cap applies before scaling.
+        assert_eq!(frame_delay_ms(10_000, 1.0), 2000);
+        assert_eq!(frame_delay_ms(10_000, 0.25), 8000);
+        // Fast playback never drops below the minimum or truncates to 0.
+        assert_eq!(frame_delay_ms(100, 64.0), 4);
+        assert_eq!(frame_delay_ms(0, 0.125), 4);
+        assert_eq!(frame_delay_ms(300, 64.0), 5);
     }
 
     #[test]
@@ -590,6 +1101,171 @@ mod tests {
         assert_eq!(replay.grid.at(Point::new(2, 0)).ch, ' ');
     }
 
+    /// The frames of [`make_test_frames`], with an event at 180 ms and two
+    /// at 260 ms.
+    fn make_test_events() -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut enc = FrameEncoder::new(&mut buf);
+            for i in 0..5 {
+                let time_ms = i as u64 * 100;
+                match i {
+                    2 => enc.event(180, 1, b"a").unwrap(),
+                    3 => {
+                        enc.event(260, 2, b"").unwrap();
+                        enc.event(260, 3, b"").unwrap();
+                    }
+                    _ => {}
+                }
+                let frame = Frame {
+                    cells: vec![],
+                    width: 10,
+                    height: 5,
+                    time_ms,
+                    cursor: None,
+                };
+                enc.encode(&frame).unwrap();
+            }
+        }
+        buf
+    }
+
+    fn emitted_events(effect: Option<Effect>) -> Vec<ReplayEvent> {
+        match effect {
+            Some(Effect::Batch(effects)) => effects
+                .into_iter()
+                .flat_map(|e| emitted_events(Some(e)))
+                .collect(),
+            Some(Effect::Emit(msg)) => msg
+                .downcast_ref::<ReplayEvent>()
+                .cloned()
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Auto-play to the end, returning the wall-clock time of each frame
+    /// change, as `(time, None)`, and of each event, as `(time, Some(tag))`.
+    fn play(replay: &mut Replay<&[u8]>) -> Vec<(u64, Option<u16>)> {
+        let mut log = Vec::new();
+        let mut now = 0;
+        replay.update(Msg::Init);
+        while replay.frame_index() < 5 {
+            now += replay.tick_delay_ms();
+            let fidx = replay.frame_index();
+            let tick = ReplayTick(fidx, replay.fired);
+            for e in emitted_events(replay.update(Msg::custom(tick))) {
+                assert!(!e.catch_up);
+                log.push((now, Some(e.tag)));
+            }
+            if replay.frame_index() != fidx {
+                log.push((now, None));
+            }
+        }
+        log
+    }
+
+    #[test]
+    fn events_play_at_their_time() {
+        let data = make_test_events();
+        let mut replay = make_replay(&data, 10, 5);
+        // The first frame is shown after the minimum delay, and each step
+        // comes at its recorded time after it.
+        assert_eq!(
+            play(&mut replay),
+            [
+                (4, None),
+                (104, None),
+                (184, Some(1)),
+                (204, None),
+                (264, Some(2)),
+                (264, Some(3)),
+                (304, None),
+                (404, None),
+            ]
+        );
+
+        let mut replay = make_replay(&data, 10, 5);
+        replay.set_speed(4.0);
+        assert_eq!(
+            play(&mut replay),
+            [
+                (4, None),
+                (29, None),
+                (49, Some(1)),
+                (54, None),
+                (69, Some(2)),
+                (69, Some(3)),
+                (79, None),
+                (104, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn seeking_suppresses_or_catches_up_events() {
+        let data = make_test_events();
+        let mut replay = make_replay(&data, 10, 5);
+        replay.set_frame(5);
+        assert_eq!(
+            emitted_events(replay.update(Msg::custom(ReplayTick(9, 9)))),
+            []
+        );
+
+        let mut replay = make_replay(&data, 10, 5);
+        replay.seek_events = SeekEvents::CatchUp;
+        replay.set_frame(3);
+        let events = emitted_events(replay.update(Msg::custom(ReplayTick(9, 9))));
+        let tags: Vec<(u16, bool)> = events.iter().map(|e| (e.tag, e.catch_up)).collect();
+        assert_eq!(tags, [(1, true)]);
+        assert_eq!(events[0].payload, b"a");
+
+        // Going back and forth again plays them again.
+        replay.set_frame(1);
+        replay.set_frame(4);
+        let events = emitted_events(replay.update(Msg::custom(ReplayTick(9, 9))));
+        let tags: Vec<u16> = events.iter().map(|e| e.tag).collect();
+        assert_eq!(tags, [1, 2, 3]);
+    }
+
+    #[test]
+    fn recordings_without_events_emit_none() {
+        let data = make_test_frames();
+        let mut replay = make_replay(&data, 10, 5);
+        let log = play(&mut replay);
+        assert!(log.iter().all(|(_, tag)| tag.is_none()));
+        assert_eq!(log.len(), 5);
+    }
+
+    #[test]
+    fn replay_help_shows_metadata() {
+        let frame = Frame {
+            cells: vec![],
+            width: 10,
+            height: 5,
+            time_ms: 0,
+            cursor: None,
+        };
+        let mut data = Vec::new();
+        let meta = RecordingMeta::new("Shamogu", "0.3").with_seed(42);
+        let mut enc = FrameEncoder::new(&mut data).with_metadata(meta.clone());
+        enc.encode(&frame).unwrap();
+        let mut replay = make_replay(&data, 40, 10);
+        assert_eq!(replay.metadata(), Some(&meta));
+        let _ = replay.update(Msg::Init);
+        let _ = replay.update(Msg::key(Key::Char('?')));
+        let node = replay.describe();
+        assert_eq!(node.value.as_deref(), Some("Shamogu 0.3, seed 42"));
+
+        let plain = make_test_frames();
+        let mut replay = make_replay(&plain, 40, 10);
+        assert!(replay.metadata().is_none());
+        let _ = replay.update(Msg::Init);
+        let _ = replay.update(Msg::key(Key::Char('?')));
+        assert!(replay.describe().value.unwrap().starts_with("Quit"));
+    }
+
     #[test]
     fn replay_help_key_default() {
         let keys = ReplayKeys::default();
@@ -660,6 +1336,15 @@ mod tests {
         });
         assert!(!replay.is_auto_play());
 
+        // Dragging is only a move, and does not toggle.
+        let _ = replay.update(Msg::Mouse {
+            action: MouseAction::MainDrag,
+            pos: Point::new(2, 1),
+            modifiers: Default::default(),
+            time: std::time::Instant::now(),
+        });
+        assert!(!replay.is_auto_play());
+
         // Click again to resume.
         let _ = replay.update(Msg::Mouse {
             action: MouseAction::Main,
@@ -727,6 +1412,7 @@ mod tests {
                 width: 5,
                 height: 5,
                 time_ms: 0,
+                cursor: None,
             })
             .unwrap();
             enc.encode(&Frame {
@@ -737,6 +1423,7 @@ mod tests {
                 width: 15,
                 height: 12,
                 time_ms: 100,
+                cursor: None,
             })
             .unwrap();
         }
@@ -772,4 +1459,188 @@ mod tests {
         assert!(lines[8].content().contains("Help"));
         assert!(lines[8].content().contains("?"));
     }
+
+    /// A single 15x12 frame marking its corners with 'a' to 'd'.
+    fn make_big_frame() -> Vec<u8> {
+        let mut buf = Vec::new();
+        let corners = [(0, 0, 'a'), (14, 0, 'b'), (0, 11, 'c'), (14, 11, 'd')];
+        FrameEncoder::new(&mut buf)
+            .encode(&Frame {
+                cells: corners
+                    .iter()
+                    .map(|&(x, y, ch)| FrameCell {
+                        pos: Point::new(x, y),
+                        cell: Cell::default().with_char(ch),
+                    })
+                    .collect(),
+                width: 15,
+                height: 12,
+                time_ms: 0,
+                cursor: None,
+            })
+            .unwrap();
+        buf
+    }
+
+    fn make_fit_replay(data: &[u8], w: i32, h: i32, fit: ViewFit) -> Replay<&[u8]> {
+        Replay::new(ReplayConfig {
+            grid: Grid::new(w, h),
+            decoder: FrameDecoder::new(data),
+            keys: ReplayKeys::default(),
+            fit,
+            border: Style::default(),
+            seek_events: SeekEvents::default(),
+        })
+    }
+
+    fn shift(key: Key) -> Msg {
+        Msg::key_mod(key, ModMask::SHIFT)
+    }
+
+    #[test]
+    fn view_centering() {
+        assert_eq!(
+            center_offset(Point::new(4, 2), Point::new(10, 6)),
+            Point::new(3, 2)
+        );
+        // Larger axes are not offset.
+        assert_eq!(
+            center_offset(Point::new(12, 3), Point::new(10, 6)),
+            Point::new(0, 1)
+        );
+
+        let data = make_test_frames();
+        let mut replay = make_fit_replay(&data, 10, 5, ViewFit::Center);
+        replay.set_frame(1);
+        let mut view = Grid::new(16, 9);
+        replay.draw(&mut view);
+        // The 10x5 recording starts at (3, 2), framed by a box.
+        assert_eq!(view.at(Point::new(3, 2)).ch, 'A');
+        assert_eq!(view.at(Point::new(2, 1)).ch, '\u{250c}');
+        assert_eq!(view.at(Point::new(13, 7)).ch, '\u{2518}');
+    }
+
+    #[test]
+    fn view_scroll_clamps_at_edges() {
+        let (rec, view) = (Point::new(15, 12), Point::new(5, 5));
+        assert_eq!(
+            clamp_scroll(Point::new(-3, 20), rec, view),
+            Point::new(0, 7)
+        );
+        assert_eq!(clamp_scroll(Point::new(4, 4), rec, rec), Point::ZERO);
+
+        let data = make_big_frame();
+        let mut replay = make_fit_replay(&data, 5, 5, ViewFit::Scroll);
+        let _ = replay.update(Msg::Init);
+        replay.set_frame(1);
+        for _ in 0..20 {
+            let _ = replay.update(shift(Key::ArrowRight));
+            let _ = replay.update(shift(Key::ArrowDown));
+        }
+        assert_eq!(replay.scroll_offset(), Point::new(10, 7));
+        let mut view = Grid::new(5, 5);
+        replay.draw(&mut view);
+        // The indicator, cut to the view, covers the bottom row, 'd' included.
+        assert_eq!(view.at(Point::new(4, 3)).ch, ' ');
+        let bottom: String = (0..5).map(|x| view.at(Point::new(x, 4)).ch).collect();
+        assert_eq!(bottom, " 10,7");
+        for _ in 0..20 {
+            let _ = replay.update(shift(Key::ArrowLeft));
+        }
+        assert_eq!(replay.scroll_offset(), Point::new(0, 7));
+        // Growing the view to the whole recording resets the scroll.
+        let _ = replay.update(Msg::Screen {
+            width: 15,
+            height: 12,
+            time: std::time::Instant::now(),
+        });
+        assert_eq!(replay.scroll_offset(), Point::ZERO);
+    }
+
+    #[test]
+    fn view_transform_keeps_full_grid() {
+        let data = make_big_frame();
+        for fit in [ViewFit::Center, ViewFit::Scroll, ViewFit::Downscale] {
+            let mut replay = make_fit_replay(&data, 5, 5, fit);
+            replay.set_frame(1);
+            assert_eq!(replay.grid.size(), Point::new(15, 12), "{fit:?}");
+            assert_eq!(replay.grid.at(Point::new(14, 11)).ch, 'd', "{fit:?}");
+            replay.set_frame(0);
+            assert_eq!(replay.grid.at(Point::new(14, 11)).ch, ' ', "{fit:?}");
+        }
+
+        // Downscaling by 3 shows cells (0, 0), (3, 0)... and the marker.
+        assert_eq!(downscale_factor(Point::new(15, 12), Point::new(5, 5)), 3);
+        assert_eq!(downscale_factor(Point::new(4, 4), Point::new(5, 5)), 1);
+        let mut replay = make_fit_replay(&data, 10, 5, ViewFit::Downscale);
+        replay.set_frame(1);
+        let mut view = Grid::new(10, 5);
+        replay.draw(&mut view);
+        let top: String = (0..10).map(|x| view.at(Point::new(x, 0)).ch).collect();
+        assert_eq!(top, "a    ~1/3 ");
+        // Only 5x4 cells are used.
+        assert_eq!(view.at(Point::new(5, 1)).ch, ' ');
+        assert_eq!(view.at(Point::new(0, 4)).ch, ' ');
+    }
+
+    #[test]
+    fn view_pan_key_routing() {
+        let data = make_big_frame();
+        let mut replay = make_fit_replay(&data, 5, 5, ViewFit::Scroll);
+        let _ = replay.update(Msg::Init);
+        let _ = replay.update(Msg::key(Key::Char(' ')));
+        // Normal mode: arrows step frames, Shift+arrows pan.
+        let _ = replay.update(Msg::key(Key::ArrowRight));
+        assert_eq!(replay.frame_index(), 1);
+        let _ = replay.update(shift(Key::ArrowRight));
+        assert_eq!(
+            (replay.frame_index(), replay.scroll_offset()),
+            (1, Point::new(1, 0))
+        );
+
+        // Pan mode: frame and seek keys pan, other keys work as usual.
+        let _ = replay.update(Msg::key(Key::Char('v')));
+        assert!(replay.is_pan_mode());
+        let _ = replay.update(Msg::key(Key::ArrowRight));
+        let _ = replay.update(Msg::key(Key::Char('j')));
+        assert_eq!(
+            (replay.frame_index(), replay.scroll_offset()),
+            (1, Point::new(2, 1))
+        );
+        let _ = replay.update(Msg::key(Key::Char('+')));
+        assert_eq!(replay.speed(), 2.0);
+
+        // Quit keys leave pan mode first.
+        assert!(replay.update(Msg::key(Key::Escape)).is_none());
+        assert!(!replay.is_pan_mode());
+        let _ = replay.update(Msg::key(Key::ArrowLeft));
+        assert_eq!(replay.frame_index(), 0);
+        assert!(matches!(
+            replay.update(Msg::key(Key::Escape)),
+            Some(Effect::End)
+        ));
+
+        // Other views have no pan keys.
+        let mut replay = make_fit_replay(&data, 5, 5, ViewFit::Clip);
+        let _ = replay.update(Msg::Init);
+        let _ = replay.update(Msg::key(Key::Char('v')));
+        assert!(!replay.is_pan_mode());
+        let _ = replay.update(shift(Key::ArrowRight));
+        assert_eq!(replay.scroll_offset(), Point::ZERO);
+    }
+
+    #[test]
+    fn view_help_lists_pan_keys() {
+        let data = make_big_frame();
+        let replay = make_fit_replay(&data, 20, 10, ViewFit::Scroll);
+        let lines: Vec<String> = replay
+            .build_help_lines()
+            .iter()
+            .map(|l| l.content().to_string())
+            .collect();
+        assert_eq!(lines.len(), 14);
+        assert!(lines[9].contains("scrolling"));
+        assert!(lines[10].starts_with("Pan mode") && lines[10].ends_with("Char(v)"));
+        assert!(lines[11].contains("Shift+arrows"));
+    }
 }
@@ -0,0 +1,238 @@
+use std::time::{Duration, Instant};
+
+use gruid_core::{Cell, Grid, Msg, Point, Range, Style};
+
+use crate::{AccessibilityNode, Role};
+
+/// Glyphs a [`Spinner`] cycles through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpinnerGlyphs {
+    /// Braille dots turning: `⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏`.
+    Braille,
+    /// A turning line: `|/-\`.
+    Line,
+    /// A dot growing and shrinking: `.oOo`.
+    Dots,
+    /// Any glyphs. An empty list draws a space.
+    Custom(Vec<char>),
+}
+
+impl SpinnerGlyphs {
+    /// The glyphs, in order.
+    pub fn glyphs(&self) -> &[char] {
+        match self {
+            Self::Braille => &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'],
+            Self::Line => &['|', '/', '-', '\\'],
+            Self::Dots => &['.', 'o', 'O', 'o'],
+            Self::Custom(glyphs) => glyphs,
+        }
+    }
+}
+
+/// An animated "working" indicator: a glyph cycling in place, followed by
+/// an optional label.
+///
+/// The animation moves either a glyph per [`advance`](Self::advance), for
+/// setups without a timer, or by the time elapsed, from the timestamps of
+/// the messages given to [`update`](Self::update) (keys, mouse events and
+/// [`Msg::Frame`]) or from [`set_time`](Self::set_time). Use one or the
+/// other: a time sets the glyph whatever the advances before it.
+#[derive(Debug, Clone)]
+pub struct Spinner {
+    pub glyphs: SpinnerGlyphs,
+    /// Text drawn after the glyph, separated by a space.
+    pub label: String,
+    pub style: Style,
+    /// How long each glyph shows, when animated by time.
+    pub interval: Duration,
+    frame: usize,
+    /// Time of the first timestamp seen.
+    start: Option<Instant>,
+}
+
+impl Spinner {
+    /// A spinner with `glyphs`, no label, the default style and a glyph
+    /// every 100 ms when animated by time.
+    pub fn new(glyphs: SpinnerGlyphs) -> Self {
+        Self {
+            glyphs,
+            label: String::new(),
+            style: Style::default(),
+            interval: Duration::from_millis(100),
+            frame: 0,
+            start: None,
+        }
+    }
+
+    /// Set the label (builder).
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    /// Set the style (builder).
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set how long each glyph shows (builder).
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Move to the next glyph, back to the first after the last.
+    pub fn advance(&mut self) {
+        self.frame = (self.frame + 1) % self.glyphs.glyphs().len().max(1);
+    }
+
+    /// Show the glyph for `now`: the first one at the first time given,
+    /// then one more every [`interval`](Self::interval).
+    pub fn set_time(&mut self, now: Instant) {
+        let start = *self.start.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(start).as_nanos();
+        let steps = elapsed / self.interval.as_nanos().max(1);
+        self.frame = (steps % self.glyphs.glyphs().len().max(1) as u128) as usize;
+    }
+
+    /// Follow the time of `msg`, if it has one (see [`Msg::time`]).
+    /// Returns whether the glyph changed.
+    pub fn update(&mut self, msg: &Msg) -> bool {
+        let Some(time) = msg.time() else {
+            return false;
+        };
+        let before = self.frame;
+        self.set_time(time);
+        self.frame != before
+    }
+
+    /// The glyph shown.
+    pub fn glyph(&self) -> char {
+        self.glyphs.glyphs().get(self.frame).copied().unwrap_or(' ')
+    }
+
+    /// Width of the glyph and label, in cells.
+    pub fn width(&self) -> i32 {
+        let label = self.label.chars().count() as i32;
+        if label == 0 { 1 } else { 2 + label }
+    }
+
+    /// Describe the spinner for accessibility: its label, without the
+    /// glyph.
+    pub fn describe(&self) -> AccessibilityNode {
+        let mut node = AccessibilityNode::new(Role::Progress);
+        node.label = (!self.label.is_empty()).then(|| self.label.clone());
+        node
+    }
+
+    /// Draw the glyph and label from `p` in `grid`. Returns the range
+    /// drawn, clipped to the grid.
+    pub fn draw_at(&self, grid: &Grid, p: Point) -> Range {
+        let mut x = p.x;
+        let mut put = |ch| {
+            grid.set(
+                Point::new(x, p.y),
+                Cell::default().with_char(ch).with_style(self.style),
+            );
+            x += 1;
+        };
+        put(self.glyph());
+        if !self.label.is_empty() {
+            put(' ');
+            self.label.chars().for_each(put);
+        }
+        Range::new(p.x, p.y, p.x + self.width(), p.y + 1).intersect(grid.range_())
+    }
+
+    /// Draw a modal "please wait" over the whole of `grid`: its cells
+    /// restyled with `dim`, and the spinner centered on top. Returns the
+    /// range of the spinner.
+    pub fn overlay_centered(&self, grid: &Grid, dim: Style) -> Range {
+        grid.map_cells(|_, cell| cell.with_style(dim));
+        self.draw_at(grid, self.centered(grid.size()))
+    }
+
+    /// Where the spinner starts when centered in `size`: the extra column
+    /// or row of an odd leftover goes right and below.
+    fn centered(&self, size: Point) -> Point {
+        Point::new(
+            ((size.x - self.width()) / 2).max(0),
+            ((size.y - 1) / 2).max(0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_wraps_around() {
+        let mut s = Spinner::new(SpinnerGlyphs::Line);
+        let seen: String = (0..6)
+            .map(|_| {
+                let g = s.glyph();
+                s.advance();
+                g
+            })
+            .collect();
+        assert_eq!(seen, "|/-\\|/");
+
+        let mut empty = Spinner::new(SpinnerGlyphs::Custom(Vec::new()));
+        empty.advance();
+        assert_eq!(empty.glyph(), ' ');
+    }
+
+    #[test]
+    fn time_sets_the_phase() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+        let mut s = Spinner::new(SpinnerGlyphs::Dots).with_interval(ms(50));
+        s.advance();
+        // The first time seen starts the animation over.
+        assert!(s.update(&Msg::Frame { time: t0 }));
+        assert_eq!(s.glyph(), '.');
+        assert!(!s.update(&Msg::Frame { time: t0 + ms(49) }));
+        assert!(s.update(&Msg::key(gruid_core::Key::Enter).with_time(t0 + ms(120))));
+        assert_eq!(s.glyph(), 'O');
+        // Four glyphs a lap: 10 steps in is the third glyph again.
+        s.set_time(t0 + ms(500));
+        assert_eq!(s.glyph(), 'O');
+        assert!(!s.update(&Msg::Init));
+    }
+
+    #[test]
+    fn draws_glyph_and_label() {
+        let s = Spinner::new(SpinnerGlyphs::Braille).with_label("Saving");
+        let grid = Grid::new(12, 1);
+        assert_eq!(s.draw_at(&grid, Point::new(1, 0)), Range::new(1, 0, 9, 1));
+        let row: String = (1..9).map(|x| grid.at(Point::new(x, 0)).ch).collect();
+        assert_eq!(row, "⠋ Saving");
+        assert_eq!(s.describe().label.as_deref(), Some("Saving"));
+        // Clipped by the grid.
+        assert_eq!(s.draw_at(&grid, Point::new(8, 0)), Range::new(8, 0, 12, 1));
+    }
+
+    #[test]
+    fn overlay_centers_in_odd_and_even_regions() {
+        let dim = Style::default().with_attrs(gruid_core::AttrMask::DIM);
+        let s = Spinner::new(SpinnerGlyphs::Line).with_label("Wait");
+        // The spinner is 6 cells wide and a row high.
+        let cases = [
+            ((10, 4), Range::new(2, 1, 8, 2)),
+            ((11, 5), Range::new(2, 2, 8, 3)),
+            ((7, 3), Range::new(0, 1, 6, 2)),
+            ((4, 1), Range::new(0, 0, 4, 1)),
+        ];
+        for ((w, h), want) in cases {
+            let grid = Grid::new(w, h);
+            grid.set(Point::new(w - 1, h - 1), Cell::default().with_char('#'));
+            assert_eq!(s.overlay_centered(&grid, dim), want, "{w}x{h}");
+            let corner = grid.at(Point::new(w - 1, h - 1));
+            if !want.contains(Point::new(w - 1, h - 1)) {
+                assert_eq!((corner.ch, corner.style), ('#', dim));
+            }
+        }
+    }
+}
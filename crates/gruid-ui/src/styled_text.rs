@@ -12,8 +12,8 @@ use gruid_core::{Cell, Grid, Point, Range, Style};
 /// | `@X`     | If `X` is a key in the markups map, switch to that style (zero-width). |
 /// | `@N`     | Reset to the base style (zero-width). |
 /// | `@@`     | Emit a literal `@` character. |
-/// | `@?`     | For any other char `?`, the `@` is consumed and `?` is emitted in the current style. |
-/// | `@` at end | Ignored. |
+/// | `@?`     | For any other char `?`, reset to the base style (zero-width). |
+/// | `@` at end of a line | Ignored. |
 ///
 /// This markup protocol is compatible with Go gruid's `StyledText`.
 #[derive(Debug, Clone)]
@@ -129,6 +129,27 @@ impl StyledText {
         &self.text
     }
 
+    /// The text as displayed, without markup sequences.
+    pub fn plain(&self) -> String {
+        let markup = self.markups.is_some();
+        let mut procm = false;
+        let mut plain = String::with_capacity(self.text.len());
+        for ch in self.text.chars() {
+            if ch == '\r' {
+                continue;
+            }
+            if markup {
+                if proc_markup(procm, ch) {
+                    procm = !procm;
+                    continue;
+                }
+                procm = false;
+            }
+            plain.push(ch);
+        }
+        plain
+    }
+
     /// Return the base style.
     pub fn style(&self) -> Style {
         self.style
@@ -165,8 +186,8 @@ impl StyledText {
     /// - `@X` where X is a markup key → switch style (zero-width)
     /// - `@N` → reset to base style (zero-width)
     /// - `@@` → emit literal `@`
-    /// - `@?` → consume `@`, emit `?` in current style
-    /// - `@` at end of string → ignored
+    /// - `@?` → reset to base style (zero-width)
+    /// - `@` at end of a line → ignored
     pub fn iter(&self, mut callback: impl FnMut(Point, Cell)) -> Point {
         let mut x: i32 = 0;
         let mut y: i32 = 0;
@@ -260,7 +281,7 @@ impl StyledText {
         if width == 0 {
             return self.clone();
         }
-        let width = width as i32;
+        let width = width.min(i32::MAX as usize) as i32;
         let mut s = String::new();
         let mut wordbuf = String::new();
         let mut col: i32 = 0;
@@ -285,20 +306,15 @@ impl StyledText {
             if markup {
                 if proc_markup(procm, ch) {
                     procm = !procm;
-                    match ch {
-                        '\n' | ' ' => {}
-                        _ => {
-                            if wlen == 0 {
-                                s.push(ch);
-                            } else {
-                                wordbuf.push(ch);
-                            }
-                            continue;
-                        }
+                    // A markup after a space goes with the next word.
+                    if wlen == 0 && !wantspace {
+                        s.push(ch);
+                    } else {
+                        wordbuf.push(ch);
                     }
-                } else {
-                    procm = false;
+                    continue;
                 }
+                procm = false;
             }
             if ch == ' ' {
                 if start {
@@ -327,9 +343,11 @@ impl StyledText {
             if ch == '\n' {
                 if wlen > 0 {
                     do_last_word(&mut s, &wordbuf, wantspace, wlen, col, width);
-                    wordbuf.clear();
-                    wlen = 0;
+                } else {
+                    s.push_str(&wordbuf);
                 }
+                wordbuf.clear();
+                wlen = 0;
                 s.push('\n');
                 col = 0;
                 wantspace = false;
@@ -342,6 +360,8 @@ impl StyledText {
         }
         if wlen > 0 {
             do_last_word(&mut s, &wordbuf, wantspace, wlen, col, width);
+        } else {
+            s.push_str(&wordbuf);
         }
         // Trim trailing spaces and newlines
         let trimmed = s.trim_end_matches([' ', '\n']);
@@ -378,6 +398,9 @@ impl StyledText {
         let mut from = 0usize;
 
         for (i, r) in self.text.char_indices() {
+            if r == '\r' {
+                continue;
+            }
             if r == '\n' {
                 // do_newline inline
                 procm = false;
@@ -396,25 +419,23 @@ impl StyledText {
             }
             if procm {
                 procm = false;
-                if r != '@' && r != ' ' && r != '\r' {
+                if r != '@' {
                     markup_rune = r;
                 }
             } else if r == '@' {
                 procm = true;
             }
         }
-        // Handle remaining text after the last newline
-        if from != self.text.len() {
-            let mut line = self.text[from..].replace('\r', "");
-            if markup_rune_start != 'N' {
-                line = format!("@{}{}", markup_rune_start, line);
-            }
-            stts.push(StyledText {
-                text: line,
-                style: self.style,
-                markups: self.markups.clone(),
-            });
+        // The text after the last newline, even if empty, as `split` does.
+        let mut line = self.text[from..].replace('\r', "");
+        if markup_rune_start != 'N' {
+            line = format!("@{}{}", markup_rune_start, line);
         }
+        stts.push(StyledText {
+            text: line,
+            style: self.style,
+            markups: self.markups.clone(),
+        });
 
         stts
     }
@@ -460,8 +481,15 @@ impl StyledText {
 /// so that `@@` falls through to emit a literal `@`.
 ///
 /// When `procm` is false, returns true only for `@` (to start markup processing).
+///
+/// A newline always ends the line: after `@`, it returns false and the `@` is
+/// ignored.
 fn proc_markup(procm: bool, r: char) -> bool {
-    if procm { r != '@' } else { r == '@' }
+    if procm {
+        r != '@' && r != '\n'
+    } else {
+        r == '@'
+    }
 }
 
 /// Append a newline or a space to the string builder.
@@ -482,6 +510,15 @@ mod tests {
     // Ported from Go styledtext_test.go
     // -----------------------------------------------------------------------
 
+    #[test]
+    fn plain_strips_markup() {
+        let stt = StyledText::text("@rRed@N and a@@b\r\nline")
+            .with_markup('r', Style::default().with_fg(Color::from_rgb(255, 0, 0)));
+        assert_eq!(stt.plain(), "Red and a@b\nline");
+        // Without markups, '@' is plain text.
+        assert_eq!(StyledText::text("a@rb").plain(), "a@rb");
+    }
+
     #[test]
     fn test_size_empty() {
         let stt = StyledText::text("");
@@ -657,6 +694,19 @@ mod tests {
         assert_eq!(cells[1].style, base);
     }
 
+    #[test]
+    fn test_at_before_newline() {
+        let red = Style::default().with_fg(Color::from_rgb(255, 0, 0));
+        let stt = StyledText::text("@ra@\nb@").with_markup('r', red);
+        assert_eq!(stt.plain(), "a\nb");
+        assert_eq!(stt.size(), Point::new(1, 2));
+        let lines = stt.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].content(), "@rb@");
+        assert_eq!(stt.format(10).content(), "@ra@\nb@");
+        assert_eq!(StyledText::text("").with_markup('r', red).lines().len(), 1);
+    }
+
     #[test]
     fn test_at_unrecognized_char() {
         // @z where z is not a registered markup key → z is consumed (not emitted),
@@ -3,7 +3,7 @@
 use gruid_core::messages::{Key, MouseAction, Msg};
 use gruid_core::{Cell, Grid, Point, Style};
 
-use crate::{BoxDecor, StyledText};
+use crate::{AccessibilityNode, BoxDecor, KeyAction, KeyBindings, KeyContext, Role, StyledText};
 
 /// Configuration for a [`TextInput`] widget.
 #[derive(Debug, Clone)]
@@ -22,8 +22,17 @@ pub struct TextInputConfig {
     pub style: TextInputStyle,
 }
 
+impl TextInputConfig {
+    /// Use `keys`, such as [`TextInputKeys::from_bindings`] of the player's
+    /// preset.
+    pub fn with_keys(mut self, keys: TextInputKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
 /// Key bindings for text input.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TextInputKeys {
     /// Keys that confirm/submit the input.
     pub confirm: Vec<Key>,
@@ -40,13 +49,50 @@ impl Default for TextInputKeys {
     }
 }
 
+impl TextInputKeys {
+    /// The keys bound in `bindings` for the [`KeyContext::TextInput`] actions.
+    pub fn from_bindings(bindings: &KeyBindings) -> Self {
+        use KeyAction::*;
+        let keys = |action| bindings.keys(KeyContext::TextInput, action).to_vec();
+        Self {
+            confirm: keys(Confirm),
+            cancel: keys(Cancel),
+        }
+    }
+}
+
+/// Minimum number of characters kept visible on each side of the cursor
+/// when the content is wider than the input.
+const SCROLL_MARGIN: usize = 2;
+
 /// Visual style for text input.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct TextInputStyle {
     /// Style for the text.
     pub text: Style,
     /// Style for the cursor.
     pub cursor: Style,
+    /// Glyph drawn in the first cell when content is scrolled out on the
+    /// left, in the text style.
+    pub more_left: char,
+    /// Glyph drawn in the last cell when content is cut on the right.
+    pub more_right: char,
+    /// Glyph drawn in place of each character, to hide the content of
+    /// password inputs. It is also kept out of
+    /// [`describe`](TextInput::describe).
+    pub mask: Option<char>,
+}
+
+impl Default for TextInputStyle {
+    fn default() -> Self {
+        Self {
+            text: Style::default(),
+            cursor: Style::default(),
+            more_left: '<',
+            more_right: '>',
+            mask: None,
+        }
+    }
 }
 
 /// Actions returned by [`TextInput::update`].
@@ -62,12 +108,33 @@ pub enum TextInputAction {
     Cancel,
 }
 
+/// Pasted `text` as one line: line breaks become spaces, and other control
+/// characters are dropped.
+fn single_line(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
 /// A single-line text input widget.
+///
+/// Pasted text, from [`Msg::Paste`], is inserted at the cursor, its lines
+/// joined with spaces.
+///
+/// Content wider than the input scrolls horizontally to keep the cursor in
+/// view, with a few characters of context around it.
 #[derive(Debug, Clone)]
 pub struct TextInput {
     grid: Grid,
     content: String,
     cursor: usize,
+    /// Character index of the first column of the input area.
+    scroll: usize,
     prompt: Option<StyledText>,
     keys: TextInputKeys,
     box_: Option<BoxDecor>,
@@ -95,16 +162,19 @@ impl TextInput {
             }
             s
         };
-        Self {
+        let mut input = Self {
             grid: config.grid,
             content: config.content,
             cursor,
+            scroll: 0,
             prompt: config.prompt,
             keys: config.keys,
             box_: config.box_,
             style,
             action: TextInputAction::Pass,
-        }
+        };
+        input.scroll_to_cursor();
+        input
     }
 
     /// Process an input message and return the resulting action.
@@ -124,43 +194,35 @@ impl TextInput {
                             self.cursor += ch.len_utf8();
                             self.action = TextInputAction::Change;
                         }
-                        Key::Backspace => {
-                            if self.cursor > 0 {
-                                let prev = self.content[..self.cursor]
-                                    .char_indices()
-                                    .next_back()
-                                    .map(|(i, _)| i)
-                                    .unwrap_or(0);
-                                self.content.remove(prev);
-                                self.cursor = prev;
-                                self.action = TextInputAction::Change;
-                            }
+                        Key::Backspace if self.cursor > 0 => {
+                            let prev = self.content[..self.cursor]
+                                .char_indices()
+                                .next_back()
+                                .map(|(i, _)| i)
+                                .unwrap_or(0);
+                            self.content.remove(prev);
+                            self.cursor = prev;
+                            self.action = TextInputAction::Change;
                         }
-                        Key::Delete => {
-                            if self.cursor < self.content.len() {
-                                self.content.remove(self.cursor);
-                                self.action = TextInputAction::Change;
-                            }
+                        Key::Delete if self.cursor < self.content.len() => {
+                            self.content.remove(self.cursor);
+                            self.action = TextInputAction::Change;
                         }
-                        Key::ArrowLeft => {
-                            if self.cursor > 0 {
-                                let prev = self.content[..self.cursor]
-                                    .char_indices()
-                                    .next_back()
-                                    .map(|(i, _)| i)
-                                    .unwrap_or(0);
-                                self.cursor = prev;
-                            }
+                        Key::ArrowLeft if self.cursor > 0 => {
+                            let prev = self.content[..self.cursor]
+                                .char_indices()
+                                .next_back()
+                                .map(|(i, _)| i)
+                                .unwrap_or(0);
+                            self.cursor = prev;
                         }
-                        Key::ArrowRight => {
-                            if self.cursor < self.content.len() {
-                                let next = self.content[self.cursor..]
-                                    .char_indices()
-                                    .nth(1)
-                                    .map(|(i, _)| self.cursor + i)
-                                    .unwrap_or(self.content.len());
-                                self.cursor = next;
-                            }
+                        Key::ArrowRight if self.cursor < self.content.len() => {
+                            let next = self.content[self.cursor..]
+                                .char_indices()
+                                .nth(1)
+                                .map(|(i, _)| self.cursor + i)
+                                .unwrap_or(self.content.len());
+                            self.cursor = next;
                         }
                         Key::Home => {
                             self.cursor = 0;
@@ -172,6 +234,14 @@ impl TextInput {
                     }
                 }
             }
+            Msg::Paste(ref text) => {
+                let text = single_line(text);
+                if !text.is_empty() {
+                    self.content.insert_str(self.cursor, &text);
+                    self.cursor += text.len();
+                    self.action = TextInputAction::Change;
+                }
+            }
             Msg::Mouse {
                 action: MouseAction::Main,
                 pos,
@@ -182,7 +252,9 @@ impl TextInput {
                     let prompt_len = self.prompt_char_len();
                     let click_col = (pos.x - inner.min.x) as usize;
                     if click_col >= prompt_len {
-                        let text_col = click_col - prompt_len + self.compute_scroll();
+                        // Clicking an indicator selects the hidden
+                        // character under it, scrolling it into view.
+                        let text_col = click_col - prompt_len + self.scroll;
                         let chars: Vec<char> = self.content.chars().collect();
                         let target = text_col.min(chars.len());
                         // Convert char position to byte offset
@@ -193,11 +265,19 @@ impl TextInput {
             _ => {}
         }
 
+        self.scroll_to_cursor();
         self.action
     }
 
-    /// Draw the text input into the given grid.
+    /// Draw the text input into the given grid, showing the terminal
+    /// cursor at the caret with [`Grid::set_cursor`].
     pub fn draw(&self, grid: &Grid) {
+        self.draw_cursor(grid, true);
+    }
+
+    /// Draw the text input, with the cursor only if `cursor` is set, as
+    /// for an input without focus.
+    pub(crate) fn draw_cursor(&self, grid: &Grid, cursor: bool) {
         let inner_range = if let Some(ref box_decor) = self.box_ {
             box_decor.draw(grid)
         } else {
@@ -228,9 +308,10 @@ impl TextInput {
             return;
         }
 
-        let scroll = self.compute_scroll();
+        let scroll = self.scroll;
         let cursor_char_pos = self.content[..self.cursor].chars().count();
         let chars: Vec<char> = self.content.chars().collect();
+        let (more_left, more_right) = overflow(input_w, scroll, chars.len());
 
         for col in 0..input_w {
             let char_idx = scroll + col;
@@ -240,15 +321,30 @@ impl TextInput {
                 break;
             }
 
-            let is_cursor = char_idx == cursor_char_pos;
+            let indicator = if col == 0 && more_left {
+                Some(self.style.more_left)
+            } else if col == input_w - 1 && more_right {
+                Some(self.style.more_right)
+            } else {
+                None
+            };
+            if let Some(ch) = indicator {
+                grid.set(p, Cell::default().with_char(ch).with_style(self.style.text));
+                continue;
+            }
+
+            let is_cursor = cursor && char_idx == cursor_char_pos;
             let style = if is_cursor {
+                // The terminal cursor too, for screen readers and input
+                // methods that follow it.
+                grid.set_cursor(Some(p));
                 self.style.cursor
             } else {
                 self.style.text
             };
 
             let ch = if char_idx < chars.len() {
-                chars[char_idx]
+                self.style.mask.unwrap_or(chars[char_idx])
             } else if is_cursor {
                 '_'
             } else {
@@ -268,6 +364,7 @@ impl TextInput {
     pub fn set_content(&mut self, s: &str) {
         self.content = s.to_string();
         self.cursor = self.content.len();
+        self.scroll_to_cursor();
     }
 
     /// Return the last action.
@@ -278,26 +375,45 @@ impl TextInput {
     /// Set the cursor byte position.
     pub fn set_cursor(&mut self, pos: usize) {
         self.cursor = pos.min(self.content.len());
+        self.scroll_to_cursor();
     }
 
     /// Replace the box decoration.
     pub fn set_box(&mut self, box_: Option<BoxDecor>) {
         self.box_ = box_;
+        self.scroll_to_cursor();
     }
 
     /// Set the prompt.
     pub fn set_prompt(&mut self, prompt: Option<StyledText>) {
         self.prompt = prompt;
+        self.scroll_to_cursor();
+    }
+
+    /// Describe the input for accessibility: its prompt, or its box title
+    /// without one, the content unless masked, and the cursor position.
+    pub fn describe(&self) -> AccessibilityNode {
+        let mut node = AccessibilityNode::new(Role::TextInput);
+        node.label = self
+            .prompt
+            .as_ref()
+            .map(|p| p.plain().trim().to_string())
+            .filter(|p| !p.is_empty())
+            .or_else(|| self.box_.as_ref().and_then(BoxDecor::label));
+        node.masked = self.style.mask.is_some();
+        if !node.masked {
+            node.value = Some(self.content.clone());
+        }
+        node.cursor = Some(self.content[..self.cursor].chars().count());
+        node
     }
 
     // -- private helpers --
 
     fn inner_range(&self) -> gruid_core::Range {
-        if let Some(ref _box_decor) = self.box_ {
-            let r = self.grid.range_();
-            gruid_core::Range::new(r.min.x + 1, r.min.y + 1, r.max.x - 1, r.max.y - 1)
-        } else {
-            self.grid.range_()
+        match self.box_ {
+            Some(ref box_decor) => box_decor.content_range(self.grid.range_()),
+            None => self.grid.range_(),
         }
     }
 
@@ -307,19 +423,51 @@ impl TextInput {
             .map_or(0, |p| p.content().chars().count())
     }
 
-    fn compute_scroll(&self) -> usize {
+    fn input_width(&self) -> usize {
         let inner = self.inner_range();
         let vis_w = (inner.max.x - inner.min.x) as usize;
-        let input_w = vis_w.saturating_sub(self.prompt_char_len());
-        let cursor_char_pos = self.content[..self.cursor].chars().count();
-        if cursor_char_pos >= input_w {
-            cursor_char_pos - input_w + 1
+        vis_w.saturating_sub(self.prompt_char_len())
+    }
+
+    /// Adjust the scroll offset so the cursor is visible, with
+    /// [`SCROLL_MARGIN`] characters around it unless it is near an end of
+    /// the content.
+    fn scroll_to_cursor(&mut self) {
+        let w = self.input_width();
+        let n = self.content.chars().count();
+        let c = self.content[..self.cursor].chars().count();
+        // Positions run up to `n`, the cell after the last character.
+        let max_scroll = (n + 1).saturating_sub(w);
+        let margin = if w >= 3 {
+            SCROLL_MARGIN.min((w - 3) / 2)
         } else {
             0
+        };
+        // Visible positions, indicator cells excluded.
+        let span = |scroll: usize| {
+            let (left, right) = overflow(w, scroll, n);
+            (scroll + left as usize, scroll + w - right as usize)
+        };
+        self.scroll = self.scroll.min(max_scroll);
+        while self.scroll > 0 && c < span(self.scroll).0 + margin {
+            self.scroll -= 1;
+        }
+        while self.scroll < max_scroll && c + margin >= span(self.scroll).1 {
+            self.scroll += 1;
         }
     }
 }
 
+/// Whether an input `width` cells wide, scrolled by `scroll` over `len`
+/// characters, shows the left and right indicators. Inputs narrower than 3
+/// cells have no room for them.
+fn overflow(width: usize, scroll: usize, len: usize) -> (bool, bool) {
+    if width < 3 {
+        return (false, false);
+    }
+    (scroll > 0, len > scroll + width)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -368,6 +516,26 @@ mod tests {
         assert_eq!(input.content(), "XhelloY");
     }
 
+    #[test]
+    fn paste_inserts_at_cursor() {
+        let mut input = make_input("ab");
+        input.update(Msg::key(Key::ArrowLeft));
+        let action = input.update(Msg::Paste("xé".to_string()));
+        assert_eq!(action, TextInputAction::Change);
+        assert_eq!(input.content(), "axéb");
+        input.update(Msg::key(Key::Char('!')));
+        assert_eq!(input.content(), "axé!b");
+
+        // Lines are joined, control characters dropped.
+        let mut input = make_input("");
+        input.update(Msg::Paste("one\r\ntwo\nthree\r\x07\tend\n".to_string()));
+        assert_eq!(input.content(), "one two three  end ");
+        assert_eq!(
+            input.update(Msg::Paste("\x1b".to_string())),
+            TextInputAction::Pass
+        );
+    }
+
     #[test]
     fn confirm_cancel() {
         let mut input = make_input("test");
@@ -421,6 +589,7 @@ mod tests {
             style: TextInputStyle {
                 text: text_style,
                 cursor: Style::default(), // triggers auto-reverse
+                ..Default::default()
             },
         });
         // Cursor should have fg/bg swapped
@@ -440,11 +609,26 @@ mod tests {
             style: TextInputStyle {
                 text: Style::default(),
                 cursor: cursor_style, // explicit — no auto-reverse
+                ..Default::default()
             },
         });
         assert_eq!(input.style.cursor, cursor_style);
     }
 
+    #[test]
+    fn terminal_cursor_at_caret() {
+        let mut input = make_input("abc");
+        input.update(Msg::key(Key::ArrowLeft));
+        let grid = Grid::new(30, 3);
+        let slice = grid.slice(gruid_core::Range::new(5, 1, 25, 2));
+        input.draw(&slice);
+        assert_eq!(grid.cursor(), Some(Point::new(7, 1)));
+        // Not without focus.
+        grid.set_cursor(None);
+        input.draw_cursor(&slice, false);
+        assert_eq!(grid.cursor(), None);
+    }
+
     #[test]
     fn set_cursor_and_box() {
         let mut input = make_input("abc");
@@ -455,4 +639,174 @@ mod tests {
         input.set_box(None);
         assert!(input.box_.is_none());
     }
+
+    #[test]
+    fn masked_input_hides_content() {
+        let mut input = make_input("hunter2");
+        input.set_prompt(Some(StyledText::new("Password: ", Style::default())));
+        input.style.mask = Some('*');
+        let grid = Grid::new(20, 1);
+        input.draw(&grid);
+        let row: String = (0..20).map(|x| grid.at(Point::new(x, 0)).ch).collect();
+        assert!(!row.contains("hunter2"), "{row}");
+        assert!(row.contains("*******"), "{row}");
+
+        let node = input.describe();
+        assert_eq!(node.label.as_deref(), Some("Password:"));
+        assert!(node.masked);
+        assert_eq!(node.value, None);
+        assert_eq!(node.cursor, Some(7));
+
+        input.style.mask = None;
+        assert_eq!(input.describe().value.as_deref(), Some("hunter2"));
+    }
+
+    fn scrolling_input(width: i32, content: &str) -> TextInput {
+        TextInput::new(TextInputConfig {
+            grid: Grid::new(width, 1),
+            content: content.to_string(),
+            prompt: None,
+            keys: TextInputKeys::default(),
+            box_: None,
+            style: TextInputStyle {
+                cursor: Style::default().with_fg(gruid_core::Color::from_rgb(255, 0, 0)),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn click(input: &mut TextInput, x: i32) {
+        input.update(Msg::Mouse {
+            action: MouseAction::Main,
+            pos: Point::new(x, 0),
+            modifiers: Default::default(),
+            time: std::time::Instant::now(),
+        });
+    }
+
+    /// The drawn row, and the column of the cursor.
+    fn drawn(input: &TextInput) -> (String, usize) {
+        let grid = Grid::new(input.grid.size().x, 1);
+        input.draw(&grid);
+        let cells: Vec<Cell> = (0..grid.size().x)
+            .map(|x| grid.at(Point::new(x, 0)))
+            .collect();
+        let cursor: Vec<usize> = (0..cells.len())
+            .filter(|&x| cells[x].style == input.style.cursor)
+            .collect();
+        assert_eq!(cursor.len(), 1, "one cursor cell");
+        (cells.iter().map(|c| c.ch).collect(), cursor[0])
+    }
+
+    #[test]
+    fn scroll_keeps_cursor_visible() {
+        let mut input = scrolling_input(10, "");
+        // Distinct characters, so each drawn one has a single index.
+        let mut next_char = (0x100..).filter_map(char::from_u32);
+        let mut seed = 7u32;
+        for step in 0..3000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let r = (seed >> 16) as usize;
+            match r % 9 {
+                0..=2 => {
+                    input.update(Msg::key(Key::Char(next_char.next().unwrap())));
+                }
+                3 => {
+                    input.update(Msg::key(Key::Backspace));
+                }
+                4 => {
+                    input.update(Msg::key(Key::Delete));
+                }
+                5 => {
+                    input.update(Msg::key(Key::ArrowLeft));
+                }
+                6 => {
+                    input.update(Msg::key(Key::ArrowRight));
+                }
+                7 => {
+                    let key = [Key::Home, Key::End][r / 9 % 2].clone();
+                    input.update(Msg::key(key));
+                }
+                _ => click(&mut input, (r / 9 % 10) as i32),
+            }
+
+            let chars: Vec<char> = input.content().chars().collect();
+            let c = input.content[..input.cursor].chars().count();
+            let (row, col) = drawn(&input);
+            let row: Vec<char> = row.chars().collect();
+            let index = |ch| chars.iter().position(|&x| x == ch);
+            let shown: Vec<usize> = row.iter().filter_map(|&ch| index(ch)).collect();
+            let (first, last) = match (shown.first(), shown.last()) {
+                (Some(&f), Some(&l)) => (f, l),
+                _ => (0, 0),
+            };
+            // Indicators exactly when characters are hidden on that side.
+            assert_eq!(row[0] == '<', first > 0, "step {step}: {row:?}");
+            assert_eq!(
+                row[9] == '>',
+                last + 1 < chars.len(),
+                "step {step}: {row:?}"
+            );
+            // The cursor cell shows the cursor's character, with context.
+            assert_eq!(
+                row[col],
+                chars.get(c).copied().unwrap_or('_'),
+                "step {step}"
+            );
+            for i in c.saturating_sub(SCROLL_MARGIN)..(c + SCROLL_MARGIN + 1).min(chars.len()) {
+                assert!(shown.contains(&i), "step {step}: {i} hidden near {c}");
+            }
+        }
+    }
+
+    #[test]
+    fn home_and_end_scroll_fully() {
+        let mut input = scrolling_input(8, "0123456789abcdef");
+        assert_eq!(drawn(&input), ("<abcdef_".into(), 7));
+        input.update(Msg::key(Key::Home));
+        assert_eq!(drawn(&input), ("0123456>".into(), 0));
+        // The margin keeps two characters after the cursor.
+        for _ in 0..5 {
+            input.update(Msg::key(Key::ArrowRight));
+        }
+        assert_eq!(drawn(&input), ("<234567>".into(), 4));
+        input.update(Msg::key(Key::End));
+        assert_eq!(drawn(&input), ("<abcdef_".into(), 7));
+        // Deleting scrolls back as the content shrinks.
+        for _ in 0..10 {
+            input.update(Msg::key(Key::Backspace));
+        }
+        assert_eq!(drawn(&input), ("012345_ ".into(), 6));
+    }
+
+    #[test]
+    fn click_maps_through_scroll() {
+        let mut input = scrolling_input(8, "0123456789abcdef");
+        input.set_cursor(8);
+        assert_eq!(drawn(&input), ("<6789ab>".into(), 3));
+        click(&mut input, 2);
+        assert_eq!(input.cursor, 7);
+        assert_eq!(drawn(&input), ("<56789a>".into(), 3));
+        // The indicators select the hidden character under them.
+        click(&mut input, 7);
+        assert_eq!(input.cursor, 11);
+        assert_eq!(drawn(&input), ("<89abcd>".into(), 4));
+        click(&mut input, 0);
+        assert_eq!(input.cursor, 7);
+        assert_eq!(drawn(&input), ("<56789a>".into(), 3));
+        // Past the end of the content.
+        input.update(Msg::key(Key::End));
+        click(&mut input, 7);
+        assert_eq!(input.cursor, 16);
+    }
+
+    #[test]
+    fn scroll_with_prompt() {
+        let mut input = scrolling_input(10, "abcdefghij");
+        input.set_prompt(Some(StyledText::new("> ", Style::default())));
+        assert_eq!(drawn(&input), ("> <efghij_".into(), 9));
+        click(&mut input, 4);
+        assert_eq!(input.cursor, 5);
+        assert_eq!(drawn(&input), ("> <defghij".into(), 5));
+    }
 }
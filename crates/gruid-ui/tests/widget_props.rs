@@ -0,0 +1,159 @@
+//! Property tests: styled text and menus take any text and any grid,
+//! including empty ones, without panicking.
+
+use std::time::Instant;
+
+use gruid_core::{Color, Grid, Key, ModMask, MouseAction, Msg, Point, Range, Style};
+use gruid_ui::{BoxDecor, Menu, MenuConfig, MenuEntry, MenuKeys, MenuStyle, StyledText};
+use proptest::prelude::*;
+
+/// Text mixing words, spaces, line breaks and markup characters.
+fn text() -> impl Strategy<Value = String> {
+    let chars = vec![
+        'a', 'b', 'c', ' ', ' ', '\n', '\r', '@', '@', 'N', 'r', 'é', '字', '\t',
+    ];
+    prop::collection::vec(prop::sample::select(chars), 0..40)
+        .prop_map(|chars| chars.into_iter().collect())
+}
+
+fn styled_text() -> impl Strategy<Value = StyledText> {
+    (text(), any::<bool>()).prop_map(|(s, markup)| {
+        let stt = StyledText::text(&s);
+        if markup {
+            stt.with_markup('r', Style::default().with_fg(Color::from_rgb(255, 0, 0)))
+        } else {
+            stt
+        }
+    })
+}
+
+/// A grid, or a slice of one, of up to 9×9 cells.
+fn grid() -> impl Strategy<Value = Grid> {
+    (0..10i32, 0..10i32, 0..3i32).prop_map(|(w, h, inset)| {
+        Grid::new(w + inset, h + inset).slice(Range::new(inset, inset, w + inset, h + inset))
+    })
+}
+
+fn width() -> impl Strategy<Value = usize> {
+    prop_oneof![10 => 0..20usize, 1 => Just(usize::MAX)]
+}
+
+fn msg() -> impl Strategy<Value = Msg> {
+    let keys = vec![
+        Key::ArrowUp,
+        Key::ArrowDown,
+        Key::ArrowLeft,
+        Key::ArrowRight,
+        Key::PageUp,
+        Key::PageDown,
+        Key::Home,
+        Key::End,
+        Key::Enter,
+        Key::Escape,
+        Key::Char('a'),
+        Key::Char(' '),
+    ];
+    let actions = vec![
+        MouseAction::Main,
+        MouseAction::Secondary,
+        MouseAction::WheelUp,
+        MouseAction::WheelDown,
+        MouseAction::Release,
+        MouseAction::Move,
+    ];
+    let pos = (-2..12i32, -2..12i32).prop_map(|(x, y)| Point::new(x, y));
+    prop_oneof![
+        prop::sample::select(keys).prop_map(Msg::key),
+        (prop::sample::select(actions), pos).prop_map(|(action, pos)| Msg::Mouse {
+            action,
+            pos,
+            modifiers: ModMask::NONE,
+            time: Instant::now(),
+        }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn styled_text_measures_what_it_draws(stt in styled_text(), grid in grid()) {
+        let size = stt.size();
+        let mut drawn = 0;
+        let extent = stt.iter(|p, _| {
+            assert!((0..size.x).contains(&p.x) && (0..size.y).contains(&p.y), "{p} in {size}");
+            drawn += 1;
+        });
+        prop_assert_eq!(extent, size);
+        prop_assert_eq!(drawn, stt.plain().chars().filter(|&c| c != '\n').count());
+        let rg = stt.draw(&grid);
+        prop_assert!(rg.in_range(grid.range_()));
+        prop_assert!(rg.in_range(Range::new(0, 0, size.x, size.y)));
+    }
+
+    #[test]
+    fn styled_text_lines_split_the_text(stt in styled_text()) {
+        let lines = stt.lines();
+        prop_assert_eq!(lines.len(), stt.content().matches('\n').count() + 1);
+        prop_assert!(lines.iter().all(|l| l.size().y <= 1));
+        let plain: Vec<String> = lines.iter().map(|l| l.plain()).collect();
+        prop_assert_eq!(plain.join("\n"), stt.plain());
+    }
+
+    #[test]
+    fn styled_text_format_wraps_words(stt in styled_text(), w in width()) {
+        let formatted = stt.format(w);
+        let again = formatted.format(w);
+        prop_assert_eq!(again.content(), formatted.content());
+        let words = |t: &StyledText| {
+            t.plain().split_whitespace().map(str::to_string).collect::<Vec<_>>()
+        };
+        prop_assert_eq!(words(&formatted), words(&stt));
+        if w > 0 {
+            for line in formatted.lines() {
+                // Only a word longer than the width overflows it.
+                prop_assert!(
+                    line.size().x as usize <= w || !line.plain().trim().contains(' '),
+                    "{:?} wider than {}",
+                    line.content(),
+                    w
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn menu_takes_any_grid_and_input(
+        entries in prop::collection::vec((text(), any::<bool>()), 0..12),
+        grid in grid(),
+        boxed in any::<bool>(),
+        layout in (0..4i32, 0..4i32),
+        msgs in prop::collection::vec(msg(), 0..20),
+    ) {
+        let entries = entries
+            .into_iter()
+            .map(|(s, disabled)| MenuEntry {
+                disabled,
+                ..MenuEntry::new(StyledText::text(&s))
+            })
+            .collect();
+        let mut menu = Menu::new(MenuConfig {
+            grid: grid.clone(),
+            entries,
+            provider: None,
+            keys: MenuKeys::default(),
+            box_: boxed.then(BoxDecor::new),
+            style: MenuStyle {
+                layout: Point::new(layout.0, layout.1),
+                ..MenuStyle::default()
+            },
+            reorderable: false,
+        });
+        prop_assert!(menu.draw().in_range(grid.range_()));
+        for msg in msgs {
+            menu.update(msg);
+            prop_assert!(menu.is_empty() || menu.active() < menu.len());
+            prop_assert!(menu.draw().in_range(grid.range_()));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! A cursor blinking once per second, driven by a polled subscription
+//! ([`every`]) since the browser has no threads. Build and serve it with:
+//!
+//! ```sh
+//! cargo build --example blink --target wasm32-unknown-unknown --release
+//! wasm-bindgen --target web --out-dir examples/pkg \
+//!     target/wasm32-unknown-unknown/release/examples/blink.wasm
+//! python3 -m http.server -d examples
+//! ```
+//!
+//! then open <http://localhost:8000/blink.html>.
+
+use std::time::Duration;
+
+use gruid_core::app::every;
+use gruid_core::{
+    AppRunner, Cell, Color, Effect, EventLoopDriver, Grid, Key, Model, Msg, Point, Style,
+};
+use gruid_web::{WebConfig, WebDriver};
+
+const WIDTH: i32 = 40;
+const HEIGHT: i32 = 3;
+const PROMPT: &str = "Name: ";
+
+/// Message of the blink subscription.
+struct Blink;
+
+/// A one-line prompt whose cursor blinks.
+#[derive(Default)]
+struct Prompt {
+    text: String,
+    cursor_on: bool,
+}
+
+impl Model for Prompt {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        match msg {
+            Msg::Init => {
+                self.cursor_on = true;
+                return Some(every(Duration::from_secs(1), || Some(Msg::custom(Blink))));
+            }
+            Msg::KeyDown {
+                key: Key::Backspace,
+                ..
+            } => {
+                self.text.pop();
+            }
+            Msg::KeyDown {
+                key: Key::Char(c), ..
+            } if PROMPT.len() + self.text.chars().count() < WIDTH as usize - 1 => {
+                self.text.push(c);
+            }
+            Msg::Custom(c) if c.is::<Blink>() => self.cursor_on = !self.cursor_on,
+            _ => {}
+        }
+        None
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        grid.fill(Cell::default());
+        let line = format!("{PROMPT}{}", self.text);
+        for (x, ch) in line.chars().enumerate() {
+            grid.set(Point::new(x as i32, 1), Cell::default().with_char(ch));
+        }
+        if self.cursor_on {
+            let cursor = Style::default().with_bg(Color::from_rgb(200, 200, 200));
+            let x = line.chars().count() as i32;
+            grid.set(Point::new(x, 1), Cell::default().with_style(cursor));
+        }
+    }
+}
+
+fn main() {
+    let config = WebConfig {
+        width: WIDTH,
+        height: HEIGHT,
+        ..WebConfig::default()
+    };
+    let runner = AppRunner::new(Box::new(Prompt::default()), WIDTH, HEIGHT);
+    WebDriver::new(config).run(runner).expect("driver failed");
+}
@@ -0,0 +1,55 @@
+//! A grid that follows the size of the browser window.
+//!
+//! With [`WebConfig::fit_parent`] set, the page gives the canvas its size
+//! and the grid is as many cells as fit: resize the window or zoom the
+//! page, and the model gets a [`Msg::Screen`] with the new size. Build and
+//! serve it with:
+//!
+//! ```sh
+//! cargo build --example fit_parent --target wasm32-unknown-unknown --release
+//! wasm-bindgen --target web --out-dir examples/pkg \
+//!     target/wasm32-unknown-unknown/release/examples/fit_parent.wasm
+//! python3 -m http.server -d examples
+//! ```
+//!
+//! then open <http://localhost:8000/fit_parent.html>.
+
+use gruid_core::{AppRunner, Cell, Effect, EventLoopDriver, Grid, Model, Msg, Point, Style};
+use gruid_ui::{BoxDecor, StyledText};
+use gruid_web::{WebConfig, WebDriver};
+
+/// Frames the screen and tells its size.
+#[derive(Default)]
+struct Frame {
+    size: Point,
+    screens: u32,
+}
+
+impl Model for Frame {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        if let Msg::Screen { width, height, .. } = msg {
+            self.size = Point::new(width, height);
+            self.screens += 1;
+        }
+        None
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        grid.fill(Cell::default());
+        let inner = BoxDecor::new().draw(grid);
+        let text = format!(
+            "{}×{} cells, {} Screen messages",
+            self.size.x, self.size.y, self.screens
+        );
+        StyledText::new(&text, Style::default()).draw(&grid.slice(inner));
+    }
+}
+
+fn main() {
+    let config = WebConfig {
+        fit_parent: true,
+        ..WebConfig::default()
+    };
+    let runner = AppRunner::new(Box::new(Frame::default()), config.width, config.height);
+    WebDriver::new(config).run(runner).expect("driver failed");
+}
@@ -0,0 +1,85 @@
+//! A text prompt usable with the virtual keyboard of a phone.
+//!
+//! With [`WebConfig::capture_text_input`] set, tapping the canvas brings up
+//! the device keyboard, and what is typed on it reaches the [`TextInput`]
+//! as key messages; desktop keyboards work as usual. Build and serve it
+//! with:
+//!
+//! ```sh
+//! cargo build --example text_input --target wasm32-unknown-unknown --release
+//! wasm-bindgen --target web --out-dir examples/pkg \
+//!     target/wasm32-unknown-unknown/release/examples/text_input.wasm
+//! python3 -m http.server -d examples
+//! ```
+//!
+//! then open <http://localhost:8000/text_input.html>, from a phone on the
+//! same network for the virtual keyboard.
+
+use gruid_core::{AppRunner, Cell, Effect, EventLoopDriver, Grid, Model, Msg, Range, Style};
+use gruid_ui::{StyledText, TextInput, TextInputAction, TextInputConfig};
+use gruid_web::{WebConfig, WebDriver};
+
+const WIDTH: i32 = 40;
+const HEIGHT: i32 = 12;
+
+/// Names entered so far, and the prompt for the next one.
+struct Party {
+    input: TextInput,
+    names: Vec<String>,
+}
+
+impl Party {
+    fn new() -> Self {
+        Self {
+            input: TextInput::new(TextInputConfig {
+                grid: Grid::new(WIDTH, 1),
+                content: String::new(),
+                prompt: Some(StyledText::new("Name: ", Style::default())),
+                keys: Default::default(),
+                box_: None,
+                style: Default::default(),
+            }),
+            names: Vec::new(),
+        }
+    }
+}
+
+impl Model for Party {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        match self.input.update(msg) {
+            TextInputAction::Confirm if !self.input.content().is_empty() => {
+                self.names.push(self.input.content().to_string());
+                self.input.set_content("");
+            }
+            TextInputAction::Cancel => self.input.set_content(""),
+            _ => {}
+        }
+        None
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        grid.fill(Cell::default());
+        let hint = "Tap to type, Enter to add:";
+        StyledText::new(hint, Style::default()).draw(&grid.slice(Range::new(0, 0, WIDTH, 1)));
+        self.input
+            .draw(&grid.slice(Range::new(0, 1, WIDTH, 2)));
+        // The latest names first.
+        let latest = self.names.iter().rev().take((HEIGHT - 3) as usize);
+        for (i, name) in latest.enumerate() {
+            let y = 3 + i as i32;
+            StyledText::new(&format!("- {name}"), Style::default())
+                .draw(&grid.slice(Range::new(0, y, WIDTH, y + 1)));
+        }
+    }
+}
+
+fn main() {
+    let config = WebConfig {
+        width: WIDTH,
+        height: HEIGHT,
+        capture_text_input: true,
+        ..WebConfig::default()
+    };
+    let runner = AppRunner::new(Box::new(Party::new()), WIDTH, HEIGHT);
+    WebDriver::new(config).run(runner).expect("driver failed");
+}
@@ -0,0 +1,182 @@
+//! A room drawn with a small embedded tile set, in the browser.
+//!
+//! Map cells are 8×8 tiles scaled to the screen; the status line has no
+//! tiles and falls back to text. Build and serve it with:
+//!
+//! ```sh
+//! cargo build --example tiles --target wasm32-unknown-unknown --release
+//! wasm-bindgen --target web --out-dir examples/pkg \
+//!     target/wasm32-unknown-unknown/release/examples/tiles.wasm
+//! python3 -m http.server -d examples
+//! ```
+//!
+//! then open <http://localhost:8000/tiles.html>.
+
+use std::rc::Rc;
+
+use gruid_core::{
+    AppRunner, Cell, Color, Effect, EventLoopDriver, Grid, Key, Model, Msg, Point, Style,
+    TileManager,
+};
+use gruid_web::{WebConfig, WebDriver};
+
+const WIDTH: i32 = 30;
+const HEIGHT: i32 = 16;
+
+/// Tile size in pixels.
+const TILE: usize = 8;
+
+/// The tiles, drawn with `#` for foreground pixels.
+#[rustfmt::skip]
+const ART: [(char, [&str; TILE]); 4] = [
+    ('#', [
+        "########",
+        "#...#...",
+        "#...#...",
+        "########",
+        "..#...#.",
+        "..#...#.",
+        "########",
+        "#...#...",
+    ]),
+    ('.', [
+        "........",
+        "........",
+        "........",
+        "...##...",
+        "...##...",
+        "........",
+        "........",
+        "........",
+    ]),
+    ('@', [
+        "..####..",
+        "..#..#..",
+        "..####..",
+        "...##...",
+        ".######.",
+        "...##...",
+        "..#..#..",
+        ".##..##.",
+    ]),
+    ('g', [
+        "........",
+        ".#....#.",
+        ".######.",
+        ".#.##.#.",
+        ".######.",
+        "..#..#..",
+        ".##..##.",
+        "........",
+    ]),
+];
+
+/// The tiles of [`ART`] as alpha bitmaps.
+struct Tiles(Vec<(char, Vec<u8>)>);
+
+impl Tiles {
+    fn new() -> Self {
+        Self(
+            ART.iter()
+                .map(|(ch, rows)| {
+                    let alpha = rows
+                        .iter()
+                        .flat_map(|row| row.bytes().map(|b| if b == b'#' { 255 } else { 0 }))
+                        .collect();
+                    (*ch, alpha)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl TileManager for Tiles {
+    fn tile_size(&self) -> (usize, usize) {
+        (TILE, TILE)
+    }
+
+    fn get_tile(&self, cell: &Cell) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|(ch, _)| *ch == cell.ch)
+            .map(|(_, alpha)| &alpha[..])
+    }
+}
+
+/// Walk around a room with the arrow keys.
+struct Room {
+    player: Point,
+    goblin: Point,
+}
+
+impl Room {
+    fn is_wall(p: Point) -> bool {
+        p.x == 0 || p.y == 0 || p.x == WIDTH - 1 || p.y == HEIGHT - 2 || (p.x == 12 && p.y != 7)
+    }
+}
+
+impl Model for Room {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        let Msg::KeyDown { key, .. } = msg else {
+            return None;
+        };
+        let (dx, dy) = match key {
+            Key::ArrowLeft => (-1, 0),
+            Key::ArrowRight => (1, 0),
+            Key::ArrowUp => (0, -1),
+            Key::ArrowDown => (0, 1),
+            _ => return None,
+        };
+        let next = self.player.shift(dx, dy);
+        if !Self::is_wall(next) && next != self.goblin {
+            self.player = next;
+        }
+        None
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        let wall = Style::default().with_fg(Color::from_rgb(150, 120, 90));
+        let floor = Style::default().with_fg(Color::from_rgb(70, 70, 90));
+        for y in 0..HEIGHT - 1 {
+            for x in 0..WIDTH {
+                let p = Point::new(x, y);
+                let (ch, style) = if Self::is_wall(p) {
+                    ('#', wall)
+                } else {
+                    ('.', floor)
+                };
+                grid.set(p, Cell::default().with_char(ch).with_style(style));
+            }
+        }
+        let green = Style::default().with_fg(Color::from_rgb(80, 200, 80));
+        grid.set(
+            self.goblin,
+            Cell::default().with_char('g').with_style(green),
+        );
+        grid.set(self.player, Cell::default().with_char('@'));
+        let status = format!("@ at {},{}  arrows: move", self.player.x, self.player.y);
+        for (i, ch) in status.chars().enumerate() {
+            grid.set(
+                Point::new(i as i32, HEIGHT - 1),
+                Cell::default().with_char(ch),
+            );
+        }
+    }
+}
+
+fn main() {
+    let config = WebConfig {
+        width: WIDTH,
+        height: HEIGHT,
+        tile_manager: Some(Rc::new(Tiles::new())),
+        // Tiles are tiny: draw each pixel as a 3×3 block.
+        tile_scale: 3,
+        ..WebConfig::default()
+    };
+    let room = Room {
+        player: Point::new(4, 4),
+        goblin: Point::new(20, 9),
+    };
+    let runner = AppRunner::new(Box::new(room), WIDTH, HEIGHT);
+    WebDriver::new(config).run(runner).expect("driver failed");
+}
@@ -0,0 +1,263 @@
+//! Asset preloading before the game starts.
+//!
+//! A manifest of [`AssetRequest`]s is validated and ordered (fonts first, so
+//! text metrics are final before anything else is measured), then fetched
+//! with `fetch()` — which goes through any registered service worker — while
+//! a progress callback is told how far along the download is.
+
+use std::collections::{HashMap, HashSet};
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{FontFace, HtmlImageElement, Response};
+
+use crate::{document, window};
+
+// ---------------------------------------------------------------------------
+// Manifest
+// ---------------------------------------------------------------------------
+
+/// What to do with a fetched asset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    /// A web font, registered with the document under `family`.
+    Font { family: String },
+    /// An image, decoded ahead of time (e.g. a tile sheet).
+    Image,
+    /// Arbitrary bytes (maps, data files, sounds).
+    Binary,
+}
+
+/// A single asset to fetch before the game starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetRequest {
+    /// Unique name used to look the asset up in [`PreloadedAssets`].
+    pub name: String,
+    /// URL to fetch, relative to the page.
+    pub url: String,
+    /// How the fetched data is used.
+    pub kind: AssetKind,
+}
+
+impl AssetRequest {
+    /// A font registered as `family`; its name is the family name.
+    pub fn font(family: &str, url: &str) -> Self {
+        Self {
+            name: family.into(),
+            url: url.into(),
+            kind: AssetKind::Font {
+                family: family.into(),
+            },
+        }
+    }
+
+    /// An image to decode.
+    pub fn image(name: &str, url: &str) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            kind: AssetKind::Image,
+        }
+    }
+
+    /// A binary blob.
+    pub fn binary(name: &str, url: &str) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            kind: AssetKind::Binary,
+        }
+    }
+
+    fn order(&self) -> u8 {
+        match self.kind {
+            AssetKind::Font { .. } => 0,
+            AssetKind::Image => 1,
+            AssetKind::Binary => 2,
+        }
+    }
+}
+
+/// Validate a manifest and sort it in load order: fonts, images, then
+/// binaries, keeping the given order within each kind.
+pub(crate) fn plan(mut assets: Vec<AssetRequest>) -> Result<Vec<AssetRequest>, String> {
+    let mut names = HashSet::new();
+    for a in &assets {
+        if a.url.is_empty() {
+            return Err(format!("asset '{}' has an empty url", a.name));
+        }
+        if !names.insert(a.name.as_str()) {
+            return Err(format!("duplicate asset name '{}'", a.name));
+        }
+    }
+    assets.sort_by_key(AssetRequest::order);
+    Ok(assets)
+}
+
+// ---------------------------------------------------------------------------
+// Progress accounting
+// ---------------------------------------------------------------------------
+
+/// Tracks preload progress: one step per asset, plus a final step waiting
+/// for `document.fonts.ready`.
+#[derive(Clone, Debug)]
+pub(crate) struct ProgressTracker {
+    total: usize,
+    done: usize,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(assets: usize) -> Self {
+        Self {
+            total: assets + 1,
+            done: 0,
+        }
+    }
+
+    /// Completed fraction, in `0.0..=1.0`.
+    pub(crate) fn fraction(&self) -> f32 {
+        self.done as f32 / self.total as f32
+    }
+
+    /// Mark one step as done and return the new fraction.
+    pub(crate) fn step(&mut self) -> f32 {
+        self.done = (self.done + 1).min(self.total);
+        self.fraction()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Preloaded assets
+// ---------------------------------------------------------------------------
+
+/// Assets fetched by [`WebDriver::preload`](crate::WebDriver::preload).
+#[derive(Debug, Default)]
+pub struct PreloadedAssets {
+    fonts: Vec<String>,
+    images: HashMap<String, HtmlImageElement>,
+    blobs: HashMap<String, Vec<u8>>,
+}
+
+impl PreloadedAssets {
+    /// Font families registered with the document.
+    pub fn fonts(&self) -> &[String] {
+        &self.fonts
+    }
+
+    /// A decoded image by name.
+    pub fn image(&self, name: &str) -> Option<&HtmlImageElement> {
+        self.images.get(name)
+    }
+
+    /// A binary blob by name.
+    pub fn blob(&self, name: &str) -> Option<&[u8]> {
+        self.blobs.get(name).map(Vec::as_slice)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Fetch glue
+// ---------------------------------------------------------------------------
+
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JsValue> {
+    let resp: Response = JsFuture::from(window().fetch_with_str(url))
+        .await?
+        .dyn_into()?;
+    if !resp.ok() {
+        return Err(JsValue::from_str(&format!(
+            "fetching {url} failed: HTTP {}",
+            resp.status()
+        )));
+    }
+    let buf = JsFuture::from(resp.array_buffer()?).await?;
+    Ok(js_sys::Uint8Array::new(&buf).to_vec())
+}
+
+/// Fetch every asset in `assets`, then wait until `font_css` is usable.
+pub(crate) async fn load(
+    assets: Vec<AssetRequest>,
+    font_css: String,
+    mut on_progress: impl FnMut(f32, &str),
+) -> Result<PreloadedAssets, JsValue> {
+    let assets = plan(assets).map_err(|e| JsValue::from_str(&e))?;
+    let mut tracker = ProgressTracker::new(assets.len());
+    let mut out = PreloadedAssets::default();
+    let fonts = document().fonts();
+
+    for a in assets {
+        on_progress(tracker.fraction(), &a.name);
+        match a.kind {
+            AssetKind::Font { family } => {
+                let bytes = fetch_bytes(&a.url).await?;
+                let face = FontFace::new_with_u8_array(&family, &bytes)?;
+                JsFuture::from(face.load()?).await?;
+                fonts.add(&face)?;
+                out.fonts.push(family);
+            }
+            AssetKind::Image => {
+                let img = HtmlImageElement::new()?;
+                img.set_src(&a.url);
+                JsFuture::from(img.decode()).await?;
+                out.images.insert(a.name, img);
+            }
+            AssetKind::Binary => {
+                let bytes = fetch_bytes(&a.url).await?;
+                out.blobs.insert(a.name, bytes);
+            }
+        }
+        tracker.step();
+    }
+
+    on_progress(tracker.fraction(), "fonts");
+    JsFuture::from(fonts.load(&font_css)).await?;
+    JsFuture::from(fonts.ready()?).await?;
+    on_progress(tracker.step(), "done");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_orders_fonts_first() {
+        let planned = plan(vec![
+            AssetRequest::binary("map", "map.bin"),
+            AssetRequest::image("tiles", "tiles.png"),
+            AssetRequest::font("Mono", "mono.woff2"),
+            AssetRequest::image("ui", "ui.png"),
+        ])
+        .unwrap();
+        let names: Vec<&str> = planned.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["Mono", "tiles", "ui", "map"]);
+    }
+
+    #[test]
+    fn plan_rejects_bad_manifests() {
+        assert!(plan(vec![AssetRequest::image("a", "")]).is_err());
+        assert!(
+            plan(vec![
+                AssetRequest::image("a", "a.png"),
+                AssetRequest::binary("a", "a.bin"),
+            ])
+            .is_err()
+        );
+        assert!(plan(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn progress_counts_fonts_ready_step() {
+        let mut t = ProgressTracker::new(3);
+        assert_eq!(t.fraction(), 0.0);
+        assert_eq!(t.step(), 0.25);
+        t.step();
+        t.step();
+        assert_eq!(t.step(), 1.0);
+        // Extra steps never overshoot.
+        assert_eq!(t.step(), 1.0);
+
+        let mut empty = ProgressTracker::new(0);
+        assert_eq!(empty.step(), 1.0);
+    }
+}
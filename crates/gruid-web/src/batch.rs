@@ -0,0 +1,842 @@
+//! Grouping a frame's cells into canvas draw calls.
+//!
+//! Setting `fillStyle` is the costly part of drawing a cell on a 2D canvas,
+//! so [`batch_cells`] orders the work to set each colour once per frame:
+//! first every background, merged into horizontal runs, then every glyph.
+//! The CSS strings of colours are kept in a [`CssCache`] across frames, so
+//! drawing does not format them again.
+//!
+//! Glyphs of the same colour, face and underline next to each other on a
+//! row are merged into a [`Text`] run, drawn with a single `fillText` when
+//! the font's advance can be made exactly one cell (see [`Layout`]). Only
+//! ASCII characters join runs: others may come from a fallback font with a
+//! different advance.
+//!
+//! Attributes are resolved here too: `REVERSE` and `DIM` change the colours
+//! a cell is drawn with, and each run carries its font [`Face`] and whether
+//! it is underlined.
+//!
+//! Cells with a tile are set apart, to be drawn whole by the tile atlas.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gruid_core::Cell;
+use gruid_core::grid::FrameCell;
+use gruid_core::style::{AttrMask, Color, Style};
+
+/// CSS colour of default backgrounds, [`DEFAULT_BG_RGB`](gruid_core::style::DEFAULT_BG_RGB).
+pub(crate) const DEFAULT_BG: &str = "#000000";
+/// CSS colour of default foregrounds, [`DEFAULT_FG_RGB`](gruid_core::style::DEFAULT_FG_RGB).
+pub(crate) const DEFAULT_FG: &str = "#c8c8c8";
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::from_rgb(r, g, b)
+}
+
+/// Colours cached before the cache is emptied. Game palettes are tiny, so
+/// this only bounds memory for programs that generate colours, such as
+/// smooth gradients.
+const MAX_CACHED_COLORS: usize = 4096;
+
+/// CSS strings of the colours drawn so far, formatted once each.
+#[derive(Debug)]
+pub(crate) struct CssCache {
+    colors: HashMap<Color, Rc<str>>,
+    default_fg: Rc<str>,
+    default_bg: Rc<str>,
+    /// Lookups answered from the cache, and colours formatted.
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl CssCache {
+    pub fn new() -> Self {
+        Self {
+            colors: HashMap::new(),
+            default_fg: Rc::from(DEFAULT_FG),
+            default_bg: Rc::from(DEFAULT_BG),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// CSS colour of a foreground.
+    pub fn fg(&mut self, color: Color) -> Rc<str> {
+        if color == Color::DEFAULT {
+            return Rc::clone(&self.default_fg);
+        }
+        self.get(color)
+    }
+
+    /// CSS colour of a background.
+    pub fn bg(&mut self, color: Color) -> Rc<str> {
+        if color == Color::DEFAULT {
+            return Rc::clone(&self.default_bg);
+        }
+        self.get(color)
+    }
+
+    fn get(&mut self, color: Color) -> Rc<str> {
+        if let Some(css) = self.colors.get(&color) {
+            self.hits += 1;
+            return Rc::clone(css);
+        }
+        self.misses += 1;
+        if self.colors.len() >= MAX_CACHED_COLORS {
+            self.colors.clear();
+        }
+        let css = format!("rgb({},{},{})", color.r(), color.g(), color.b());
+        let css: Rc<str> = Rc::from(css);
+        self.colors.insert(color, Rc::clone(&css));
+        css
+    }
+}
+
+/// A colour to draw with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Ink {
+    /// A style colour, where [`Color::DEFAULT`] is the canvas default for
+    /// the layer.
+    Style(Color),
+    /// Exactly this colour, black included.
+    Rgb(Color),
+}
+
+/// Foreground and background inks of `style`.
+fn inks(style: Style) -> (Ink, Ink) {
+    if !style.attrs.contains(AttrMask::REVERSE) && !style.attrs.contains(AttrMask::DIM) {
+        return (Ink::Style(style.fg), Ink::Style(style.bg));
+    }
+    let (fg, bg) = colors(style);
+    (Ink::Rgb(fg), Ink::Rgb(bg))
+}
+
+/// Foreground and background colours `style` is drawn with, defaults
+/// included: [`Style::rgb`].
+pub(crate) fn colors(style: Style) -> (Color, Color) {
+    let (fg, bg) = style.rgb();
+    (rgb(fg), rgb(bg))
+}
+
+/// Font variant of a glyph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) struct Face {
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl Face {
+    /// The face for the `BOLD` and `ITALIC` attributes in `attrs`.
+    pub fn of(attrs: AttrMask) -> Self {
+        Self {
+            bold: attrs.contains(AttrMask::BOLD),
+            italic: attrs.contains(AttrMask::ITALIC),
+        }
+    }
+
+    /// Write the CSS `font` shorthand for this face of `font`, itself a
+    /// shorthand such as `"16px monospace"`, into `out`.
+    pub fn write_font(self, font: &str, out: &mut String) {
+        out.clear();
+        if self.italic {
+            out.push_str("italic ");
+        }
+        if self.bold {
+            out.push_str("bold ");
+        }
+        out.push_str(font);
+    }
+}
+
+/// Horizontal run of `len` cells starting at `(x, y)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Run {
+    pub x: i32,
+    pub y: i32,
+    pub len: i32,
+}
+
+/// Glyphs drawn in the `len` cells from `(x, y)`, one char per cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Text {
+    pub x: i32,
+    pub y: i32,
+    pub len: i32,
+    pub text: String,
+    pub face: Face,
+    pub underline: bool,
+}
+
+impl Text {
+    /// Whether the glyph `ch` at `(x, y)` can be appended to the run.
+    /// Plain spaces join any run that is not underlined, whatever its face.
+    fn joins(&self, x: i32, y: i32, ch: char, face: Face, underline: bool) -> bool {
+        self.y == y
+            && self.x + self.len == x
+            && self.underline == underline
+            && (ch == ' ' || self.face == face)
+            && ch.is_ascii()
+            && self.text.is_ascii()
+    }
+}
+
+/// The draw calls of a frame, grouped by CSS colour in order of first use.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct DrawBatches {
+    /// Background rectangles, drawn first.
+    pub backgrounds: Vec<(Rc<str>, Vec<Run>)>,
+    /// Glyph runs, drawn over all backgrounds.
+    pub glyphs: Vec<(Rc<str>, Vec<Text>)>,
+    /// Cells drawn as tiles, background included.
+    pub tiles: Vec<FrameCell>,
+}
+
+/// Items grouped by colour, in order of first use.
+struct Groups<T> {
+    index: HashMap<Ink, usize>,
+    groups: Vec<(Ink, Vec<T>)>,
+}
+
+impl<T> Groups<T> {
+    fn new() -> Self {
+        Self {
+            index: HashMap::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// The item last pushed with `color`.
+    fn last_mut(&mut self, color: Ink) -> Option<&mut T> {
+        let &i = self.index.get(&color)?;
+        self.groups[i].1.last_mut()
+    }
+
+    fn push(&mut self, color: Ink, item: T) {
+        match self.index.get(&color) {
+            Some(&i) => self.groups[i].1.push(item),
+            None => {
+                self.index.insert(color, self.groups.len());
+                self.groups.push((color, vec![item]));
+            }
+        }
+    }
+
+    /// The groups with CSS colours, looked up once per group, with `css`
+    /// for style colours. Groups with the same CSS colour are merged.
+    fn into_css(
+        self,
+        cache: &mut CssCache,
+        css: fn(&mut CssCache, Color) -> Rc<str>,
+    ) -> Vec<(Rc<str>, Vec<T>)> {
+        let mut out: Vec<(Rc<str>, Vec<T>)> = Vec::with_capacity(self.groups.len());
+        for (ink, items) in self.groups {
+            let css = match ink {
+                Ink::Style(color) => css(cache, color),
+                Ink::Rgb(color) => cache.get(color),
+            };
+            match out.iter_mut().find(|(c, _)| *c == css) {
+                Some((_, group)) => group.extend(items),
+                None => out.push((css, items)),
+            }
+        }
+        out
+    }
+}
+
+/// Group `cells` into draw batches, with colours from `cache`, leaving the
+/// cells for which `is_tile` holds to be drawn as tiles. Cells are drawn
+/// in row-major order whatever their order in `cells`; a position given
+/// twice keeps its last cell.
+pub(crate) fn batch_cells(
+    cells: &[FrameCell],
+    cache: &mut CssCache,
+    is_tile: impl Fn(&Cell) -> bool,
+) -> DrawBatches {
+    let mut sorted: Vec<(usize, &FrameCell)> = cells.iter().enumerate().collect();
+    sorted.sort_by_key(|&(i, fc)| (fc.pos.y, fc.pos.x, i));
+    sorted.dedup_by(|next, prev| {
+        let same = next.1.pos == prev.1.pos;
+        if same {
+            *prev = *next;
+        }
+        same
+    });
+
+    let mut backgrounds = Groups::new();
+    let mut glyphs: Groups<Text> = Groups::new();
+    // The background run being extended, with its colour.
+    let mut run: Option<(Ink, Run)> = None;
+    let mut tiles = Vec::new();
+    for (_, fc) in sorted {
+        let (p, cell) = (fc.pos, &fc.cell);
+        // Neither runs nor texts join across the gap.
+        if is_tile(cell) {
+            tiles.push(fc.clone());
+            continue;
+        }
+        let (fg, bg) = inks(cell.style);
+        match &mut run {
+            Some((color, r)) if *color == bg && r.y == p.y && r.x + r.len == p.x => r.len += 1,
+            _ => {
+                if let Some((color, r)) = run.take() {
+                    backgrounds.push(color, r);
+                }
+                run = Some((bg, Run { x: p.x, y: p.y, len: 1 }));
+            }
+        }
+        let (ch, face) = (cell.ch, Face::of(cell.style.attrs));
+        let underline = cell.style.attrs.contains(AttrMask::UNDERLINE);
+        match glyphs.last_mut(fg) {
+            Some(t) if t.joins(p.x, p.y, ch, face, underline) => {
+                t.text.push(ch);
+                t.len += 1;
+            }
+            // A plain space only shows the background.
+            _ if ch == ' ' && !underline => {}
+            _ => {
+                let text = Text {
+                    x: p.x,
+                    y: p.y,
+                    len: 1,
+                    text: ch.to_string(),
+                    face,
+                    underline,
+                };
+                glyphs.push(fg, text);
+            }
+        }
+    }
+    if let Some((color, r)) = run {
+        backgrounds.push(color, r);
+    }
+    // Runs never start with a plain space, so trimming leaves one glyph.
+    for (_, runs) in &mut glyphs.groups {
+        for t in runs.iter_mut().filter(|t| !t.underline) {
+            let trimmed = t.text.trim_end_matches(' ').len();
+            t.len -= (t.text.len() - trimmed) as i32;
+            t.text.truncate(trimmed);
+        }
+    }
+    DrawBatches {
+        backgrounds: backgrounds.into_css(cache, CssCache::bg),
+        glyphs: glyphs.into_css(cache, CssCache::fg),
+        tiles,
+    }
+}
+
+/// The 2D context calls drawing makes.
+pub(crate) trait Canvas {
+    fn set_fill_style(&mut self, css: &str);
+    fn set_font(&mut self, font: &str);
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64);
+    fn fill_text(&mut self, text: &str, x: f64, y: f64);
+}
+
+/// Geometry and fonts [`draw`] lays batches out with, in device pixels.
+pub(crate) struct Layout<'a> {
+    pub cell_w: f64,
+    pub cell_h: f64,
+    /// CSS `font` shorthand of the regular face.
+    pub font: &'a str,
+    /// Height of underlines.
+    pub underline: f64,
+    /// Whether one `fillText` lays a run out a cell per glyph, as when
+    /// the canvas letter spacing makes up for a font advance narrower
+    /// than the cell. Otherwise each glyph is drawn on its own.
+    pub text_runs: bool,
+}
+
+/// Draw `batches` with as few calls as `layout` allows. `scratch` holds the
+/// font of other faces.
+pub(crate) fn draw(
+    canvas: &mut impl Canvas,
+    batches: &DrawBatches,
+    layout: &Layout,
+    scratch: &mut String,
+) {
+    let (cw, ch) = (layout.cell_w, layout.cell_h);
+    for (color, runs) in &batches.backgrounds {
+        canvas.set_fill_style(color);
+        for r in runs {
+            canvas.fill_rect(r.x as f64 * cw, r.y as f64 * ch, r.len as f64 * cw, ch);
+        }
+    }
+    if batches.glyphs.is_empty() {
+        return;
+    }
+    // Setting the font is costly too: only do it when the face changes.
+    canvas.set_font(layout.font);
+    let mut face = Face::default();
+    let mut buf = [0; 4];
+    for (color, texts) in &batches.glyphs {
+        canvas.set_fill_style(color);
+        for t in texts {
+            if t.face != face {
+                face = t.face;
+                face.write_font(layout.font, scratch);
+                canvas.set_font(scratch);
+            }
+            let (x, y) = (t.x as f64 * cw, t.y as f64 * ch);
+            // Text sits on a baseline near the cell bottom, leaving room
+            // for descenders.
+            let baseline = y + ch * 0.85;
+            if layout.text_runs || t.len == 1 {
+                canvas.fill_text(&t.text, x, baseline);
+            } else {
+                for (i, c) in t.text.chars().enumerate() {
+                    canvas.fill_text(c.encode_utf8(&mut buf), x + i as f64 * cw, baseline);
+                }
+            }
+            if t.underline {
+                canvas.fill_rect(x, y + ch * 0.9, t.len as f64 * cw, layout.underline);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::Point;
+
+    fn no_tiles(_: &Cell) -> bool {
+        false
+    }
+
+    fn fc(x: i32, y: i32, ch: char, fg: Color, bg: Color) -> FrameCell {
+        FrameCell {
+            cell: Cell::default()
+                .with_char(ch)
+                .with_style(Style::default().with_fg(fg).with_bg(bg)),
+            pos: Point::new(x, y),
+        }
+    }
+
+    #[test]
+    fn runs_and_colour_groups() {
+        let red = Color::from_rgb(255, 0, 0);
+        let blue = Color::from_rgb(0, 0, 255);
+        let d = Color::DEFAULT;
+        // Given out of order: row 1, then row 0.
+        let cells = [
+            fc(0, 1, 'c', red, d),
+            fc(1, 1, ' ', d, d),
+            fc(0, 0, 'a', d, blue),
+            fc(1, 0, 'b', red, blue),
+            fc(2, 0, ' ', d, blue),
+            fc(4, 0, 'e', d, blue),
+        ];
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        let run = |x, y, len| Run { x, y, len };
+        assert_eq!(
+            b.backgrounds,
+            vec![
+                ("rgb(0,0,255)".into(), vec![run(0, 0, 3), run(4, 0, 1)]),
+                ("#000000".into(), vec![run(0, 1, 2)]),
+            ]
+        );
+        assert_eq!(
+            b.glyphs,
+            vec![
+                ("#c8c8c8".into(), vec![text(0, 0, "a"), text(4, 0, "e")]),
+                ("rgb(255,0,0)".into(), vec![text(1, 0, "b"), text(0, 1, "c")]),
+            ]
+        );
+    }
+
+    fn text(x: i32, y: i32, s: &str) -> Text {
+        Text {
+            x,
+            y,
+            len: s.chars().count() as i32,
+            text: s.into(),
+            face: Face::default(),
+            underline: false,
+        }
+    }
+
+    #[test]
+    fn glyph_runs() {
+        let red = Color::from_rgb(255, 0, 0);
+        let d = Color::DEFAULT;
+        let row = |y, s: &str, fg| {
+            s.chars()
+                .enumerate()
+                .map(move |(x, ch)| fc(x as i32, y, ch, fg, d))
+                .collect::<Vec<_>>()
+        };
+        let mut cells = row(0, "You hit it.  ", d);
+        cells.extend(row(1, "  ab", d));
+        cells.extend(row(2, "é!", d));
+        cells.extend(row(3, "x", red));
+        cells.extend(row(3, " y", d).split_off(1));
+        // Spaces join runs but neither start nor end them, and neither
+        // rows nor non-ASCII glyphs are crossed.
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        assert_eq!(
+            b.glyphs[0].1,
+            [
+                text(0, 0, "You hit it."),
+                text(2, 1, "ab"),
+                text(0, 2, "é"),
+                text(1, 2, "!"),
+                text(1, 3, "y"),
+            ]
+        );
+
+        let styled = |x, ch, attrs| FrameCell {
+            cell: Cell::default()
+                .with_char(ch)
+                .with_style(Style::default().with_attrs(attrs)),
+            pos: Point::new(x, 0),
+        };
+        let cells = [
+            styled(0, 'a', AttrMask::BOLD),
+            styled(1, ' ', AttrMask::NONE),
+            styled(2, 'b', AttrMask::BOLD),
+            styled(3, 'c', AttrMask::NONE),
+            styled(4, ' ', AttrMask::UNDERLINE),
+            styled(5, ' ', AttrMask::UNDERLINE),
+            styled(6, ' ', AttrMask::NONE),
+        ];
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        let runs: Vec<_> = b.glyphs[0]
+            .1
+            .iter()
+            .map(|t| (t.x, &*t.text, t.face.bold, t.underline))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                (0, "a b", true, false),
+                (3, "c", false, false),
+                (4, "  ", false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn attributes_change_colours_and_faces() {
+        let red = Color::from_rgb(200, 0, 0);
+        let d = Color::DEFAULT;
+        let with = |x, ch, fg, attrs| FrameCell {
+            cell: Cell::default().with_char(ch).with_style(
+                Style::default()
+                    .with_fg(fg)
+                    .with_bg(d)
+                    .with_attrs(attrs),
+            ),
+            pos: Point::new(x, 0),
+        };
+        let cells = [
+            with(0, 'r', d, AttrMask::REVERSE),
+            with(1, 'd', red, AttrMask::DIM),
+            with(2, 'b', red, AttrMask::BOLD | AttrMask::ITALIC),
+            with(3, ' ', red, AttrMask::UNDERLINE),
+            with(4, ' ', red, AttrMask::NONE),
+        ];
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        let css: Vec<&str> = b.backgrounds.iter().map(|(c, _)| &**c).collect();
+        assert_eq!(css, ["rgb(200,200,200)", "rgb(0,0,0)", "#000000"]);
+        // Black on grey, red halfway to black, then plain red.
+        let css: Vec<&str> = b.glyphs.iter().map(|(c, _)| &**c).collect();
+        assert_eq!(css, ["rgb(0,0,0)", "rgb(100,0,0)", "rgb(200,0,0)"]);
+        let red = &b.glyphs[2].1;
+        assert_eq!(
+            red.iter()
+                .map(|t| (&*t.text, t.face.bold, t.face.italic, t.underline))
+                .collect::<Vec<_>>(),
+            [("b", true, true, false), (" ", false, false, true)]
+        );
+    }
+
+    #[test]
+    fn face_font_strings() {
+        let mut out = String::from("stale");
+        let font = "32px 'Tiles Mono'";
+        Face::default().write_font(font, &mut out);
+        assert_eq!(out, font);
+        Face::of(AttrMask::BOLD | AttrMask::UNDERLINE).write_font(font, &mut out);
+        assert_eq!(out, "bold 32px 'Tiles Mono'");
+        Face::of(AttrMask::ITALIC | AttrMask::BOLD).write_font(font, &mut out);
+        assert_eq!(out, "italic bold 32px 'Tiles Mono'");
+    }
+
+    #[test]
+    fn runs_break_at_row_ends_and_repeats_keep_last() {
+        let d = Color::DEFAULT;
+        let cells = [
+            fc(2, 0, 'x', d, d),
+            fc(0, 1, 'y', d, d),
+            fc(2, 0, 'z', d, d),
+        ];
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        let runs = &b.backgrounds[0].1;
+        assert_eq!(runs.len(), 2, "no run across rows");
+        let texts: Vec<&str> = b.glyphs[0].1.iter().map(|t| &*t.text).collect();
+        assert_eq!(texts, ["z", "y"]);
+        assert_eq!(batch_cells(&[], &mut CssCache::new(), no_tiles), DrawBatches::default());
+    }
+
+    #[test]
+    fn tiles_are_left_out_of_runs() {
+        let blue = Color::from_rgb(0, 0, 255);
+        let d = Color::DEFAULT;
+        let cells = [
+            fc(0, 0, 'a', d, blue),
+            fc(1, 0, '#', d, blue),
+            fc(2, 0, 'b', d, blue),
+        ];
+        let b = batch_cells(&cells, &mut CssCache::new(), |c| c.ch == '#');
+        let run = |x, len| Run { x, y: 0, len };
+        assert_eq!(
+            b.backgrounds,
+            vec![("rgb(0,0,255)".into(), vec![run(0, 1), run(2, 1)])]
+        );
+        assert_eq!(
+            b.glyphs,
+            vec![("#c8c8c8".into(), vec![text(0, 0, "a"), text(2, 0, "b")])]
+        );
+        assert_eq!(b.tiles, [cells[1].clone()]);
+    }
+
+    #[test]
+    fn tile_colors_resolve_defaults_and_attributes() {
+        let red = Color::from_rgb(255, 0, 0);
+        let grey = Color::from_rgb(200, 200, 200);
+        let black = Color::from_rgb(0, 0, 0);
+        assert_eq!(colors(Style::default()), (grey, black));
+        assert_eq!(colors(Style::default().with_fg(red)), (red, black));
+        let reversed = Style::default().with_fg(red).with_attrs(AttrMask::REVERSE);
+        assert_eq!(colors(reversed), (black, red));
+    }
+
+    #[test]
+    fn full_screen_frame_sets_few_styles() {
+        // An 80x24 map: two floor colours, a status bar and a few actors.
+        let floor = Color::from_rgb(40, 40, 40);
+        let lit = Color::from_rgb(90, 90, 60);
+        let bar = Color::from_rgb(30, 30, 50);
+        let mut cells = Vec::new();
+        for y in 0..24 {
+            for x in 0..80 {
+                let (ch, fg, bg) = match (x, y) {
+                    (_, 23) => ('=', Color::from_rgb(200, 200, 200), bar),
+                    (x, y) if (x + y) % 17 == 0 => ('g', Color::from_rgb(0, 200, 0), floor),
+                    (x, _) if x < 40 => ('.', Color::DEFAULT, lit),
+                    _ => ('#', Color::DEFAULT, floor),
+                };
+                cells.push(fc(x, y, ch, fg, bg));
+            }
+        }
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        let style_sets = b.backgrounds.len() + b.glyphs.len();
+        let rects: usize = b.backgrounds.iter().map(|(_, r)| r.len()).sum();
+        // One fillStyle per cell for each pass before batching.
+        assert_eq!(style_sets, 6, "was {}", 2 * cells.len());
+        assert!(rects < 200, "{rects} rects for {} cells", cells.len());
+    }
+
+    /// Records the calls made to a canvas.
+    #[derive(Default)]
+    struct Recorder {
+        calls: usize,
+        texts: Vec<(String, f64, f64)>,
+        fonts: Vec<String>,
+    }
+
+    impl Canvas for Recorder {
+        fn set_fill_style(&mut self, _css: &str) {
+            self.calls += 1;
+        }
+
+        fn set_font(&mut self, font: &str) {
+            self.calls += 1;
+            self.fonts.push(font.into());
+        }
+
+        fn fill_rect(&mut self, _x: f64, _y: f64, _w: f64, _h: f64) {
+            self.calls += 1;
+        }
+
+        fn fill_text(&mut self, text: &str, x: f64, y: f64) {
+            self.calls += 1;
+            self.texts.push((text.into(), x, y));
+        }
+    }
+
+    fn layout(text_runs: bool) -> Layout<'static> {
+        Layout {
+            cell_w: 10.0,
+            cell_h: 20.0,
+            font: "16px monospace",
+            underline: 1.0,
+            text_runs,
+        }
+    }
+
+    #[test]
+    fn draw_places_runs_and_faces() {
+        let styled = |x, ch, attrs| FrameCell {
+            cell: Cell::default()
+                .with_char(ch)
+                .with_style(Style::default().with_attrs(attrs)),
+            pos: Point::new(x, 1),
+        };
+        let cells = [
+            styled(0, 'a', AttrMask::NONE),
+            styled(1, 'b', AttrMask::NONE),
+            styled(2, 'c', AttrMask::BOLD),
+        ];
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        let mut scratch = String::new();
+        let mut rec = Recorder::default();
+        draw(&mut rec, &b, &layout(true), &mut scratch);
+        assert_eq!(
+            rec.texts,
+            [("ab".into(), 0.0, 37.0), ("c".into(), 20.0, 37.0)]
+        );
+        assert_eq!(rec.fonts, ["16px monospace", "bold 16px monospace"]);
+
+        let mut rec = Recorder::default();
+        draw(&mut rec, &b, &layout(false), &mut scratch);
+        let xs: Vec<f64> = rec.texts.iter().map(|t| t.1).collect();
+        assert_eq!(xs, [0.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn full_screen_diff_makes_few_calls() {
+        // An 80x24 screen of message log: words in a few colours.
+        let colors = [
+            Color::DEFAULT,
+            Color::from_rgb(200, 200, 0),
+            Color::from_rgb(200, 0, 0),
+        ];
+        let words = ["The", "goblin", "hits", "you.", "You", "miss", "it."];
+        let mut cells = Vec::new();
+        for y in 0..24 {
+            let mut x = 0;
+            let mut i = y as usize;
+            while x < 80 {
+                let word = words[i % words.len()];
+                let fg = colors[i % colors.len()];
+                for ch in word.chars().chain([' ']) {
+                    if x < 80 {
+                        cells.push(fc(x, y, ch, fg, Color::DEFAULT));
+                        x += 1;
+                    }
+                }
+                i += 1;
+            }
+        }
+        let b = batch_cells(&cells, &mut CssCache::new(), no_tiles);
+        let mut scratch = String::new();
+        let mut batched = Recorder::default();
+        draw(&mut batched, &b, &layout(true), &mut scratch);
+        let mut per_glyph = Recorder::default();
+        draw(&mut per_glyph, &b, &layout(false), &mut scratch);
+
+        // Setting the style and filling once per cell and pass, before
+        // any batching.
+        let per_cell = 4 * cells.len();
+        let glyphs = cells.iter().filter(|c| c.cell.ch != ' ').count();
+        assert_eq!(per_glyph.texts.len(), glyphs);
+        assert!(
+            batched.calls * 3 < per_glyph.calls && per_glyph.calls * 4 < per_cell,
+            "{} batched, {} per glyph, {per_cell} per cell",
+            batched.calls,
+            per_glyph.calls
+        );
+        // Nothing is lost: the runs hold every glyph at its cell.
+        let drawn: usize = batched
+            .texts
+            .iter()
+            .map(|(t, ..)| t.chars().filter(|&c| c != ' ').count())
+            .sum();
+        assert_eq!(drawn, glyphs);
+    }
+
+    #[test]
+    fn css_cache_formats_each_colour_once() {
+        let red = Color::from_rgb(255, 0, 0);
+        let blue = Color::from_rgb(0, 0, 255);
+        let d = Color::DEFAULT;
+        let cells = [fc(0, 0, 'a', red, blue), fc(1, 0, 'b', blue, red), fc(2, 0, 'c', d, d)];
+        let mut cache = CssCache::new();
+        let first = batch_cells(&cells, &mut cache, no_tiles);
+        // One lookup per group: red and blue are formatted once each, as
+        // foreground or background, and defaults are never looked up.
+        assert_eq!((cache.hits, cache.misses), (2, 2));
+        // The next frame formats nothing.
+        let second = batch_cells(&cells, &mut cache, no_tiles);
+        assert_eq!((cache.hits, cache.misses), (6, 2));
+        assert_eq!(first, second);
+        assert!(Rc::ptr_eq(&first.glyphs[0].0, &second.glyphs[0].0));
+        assert_eq!(&*cache.fg(d), DEFAULT_FG);
+        assert_eq!(&*cache.bg(d), DEFAULT_BG);
+    }
+
+    #[test]
+    fn css_cache_is_bounded() {
+        let mut cache = CssCache::new();
+        for i in 0..=MAX_CACHED_COLORS as u32 {
+            cache.fg(Color(i + 1));
+        }
+        assert_eq!(cache.colors.len(), 1);
+        assert_eq!(cache.misses, MAX_CACHED_COLORS + 1);
+        assert_eq!(&*cache.bg(Color(1)), "rgb(0,0,1)");
+    }
+
+    /// `(r, g, b)` of a CSS colour as [`CssCache`] writes them.
+    fn parse_css(css: &str) -> (u8, u8, u8) {
+        if let Some(hex) = css.strip_prefix('#') {
+            let c = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+            return (c(0), c(2), c(4));
+        }
+        let inner = css.strip_prefix("rgb(").unwrap().strip_suffix(')').unwrap();
+        let c: Vec<u8> = inner.split(',').map(|v| v.parse().unwrap()).collect();
+        (c[0], c[1], c[2])
+    }
+
+    #[test]
+    fn draws_the_conformance_cases() {
+        use gruid_core::conformance;
+
+        let shown = conformance::profile("web").attrs;
+        for case in conformance::cases() {
+            let b = batch_cells(&case.frame.cells, &mut CssCache::new(), no_tiles);
+            for FrameCell { cell, pos } in &case.frame.cells {
+                let (p, name) = (*pos, case.name);
+                let (fg, bg) = cell.style.rgb();
+                let covers = |x: i32, y: i32, len: i32| y == p.y && (x..x + len).contains(&p.x);
+                let run_bg = b
+                    .backgrounds
+                    .iter()
+                    .find(|(_, runs)| runs.iter().any(|r| covers(r.x, r.y, r.len)))
+                    .map(|(css, _)| parse_css(css));
+                assert_eq!(run_bg, Some(bg), "{name}: background at {p}");
+
+                let attrs = cell.style.attrs & shown;
+                let glyph = b.glyphs.iter().find_map(|(css, texts)| {
+                    let t = texts.iter().find(|t| covers(t.x, t.y, t.len))?;
+                    Some((css, t))
+                });
+                let Some((css, t)) = glyph else {
+                    assert_eq!(cell.ch, ' ', "{name}: no glyph at {p}");
+                    continue;
+                };
+                assert_eq!(t.text.chars().nth((p.x - t.x) as usize), Some(cell.ch));
+                if cell.ch != ' ' {
+                    assert_eq!(parse_css(css), fg, "{name}: foreground at {p}");
+                    assert_eq!(t.face, Face::of(attrs), "{name}: face at {p}");
+                }
+                assert_eq!(
+                    t.underline,
+                    attrs.contains(AttrMask::UNDERLINE),
+                    "{name}: underline at {p}"
+                );
+            }
+        }
+    }
+}
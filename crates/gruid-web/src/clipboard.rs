@@ -0,0 +1,22 @@
+//! System clipboard through `navigator.clipboard`.
+
+use gruid_core::ClipboardProvider;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::window;
+
+/// Writes with `navigator.clipboard.writeText`. Browsers only allow it in
+/// secure contexts and during a user gesture, which holds for effects
+/// returned from key and mouse messages. The write completes
+/// asynchronously; a refusal is ignored.
+pub(crate) struct NavigatorClipboard;
+
+impl ClipboardProvider for NavigatorClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let promise = window().navigator().clipboard().write_text(text);
+        wasm_bindgen_futures::spawn_local(async move {
+            let _ = JsFuture::from(promise).await;
+        });
+        Ok(())
+    }
+}
@@ -8,17 +8,133 @@
 //! # Limitations
 //!
 //! * **No threading.** WASM's main thread cannot spawn OS threads, so
-//!   [`Effect::Cmd`] and [`Effect::Sub`] will panic at runtime.  Avoid
-//!   returning those effects in WASM builds — use `Effect::End` or
-//!   `Effect::Batch` only.
+//!   [`Effect::Sub`] will panic at runtime; use [`Effect::Subscribe`]
+//!   instead, which the runner polls once per animation frame, e.g. with
+//!   [`every`](gruid_core::app::every) for a blinking cursor, and
+//!   [`Effect::Tick`] rather than a command that sleeps: its timer is
+//!   checked on each animation frame too.
+//!   [`Effect::Cmd`] runs on the main thread, from a `setTimeout(0)`
+//!   callback installed with [`spawn_timeout`]: the page stays blocked
+//!   while it runs, and its message is delivered on the next animation
+//!   frame.  [`Effect::CmdProgress`] is deferred the same way and blocks
+//!   the page while it runs too, so keep each command short and chain
+//!   several of them for long loading work.  Long game-side work, such as resting many turns, is
+//!   best split with [`Effect::Continue`], which resumes it once per
+//!   animation frame, or from timers while the page is hidden.
 //! * The driver takes ownership of the browser event loop via
 //!   `requestAnimationFrame` and event listeners; there is no way to
 //!   "return" from [`EventLoopDriver::run`].
+//! * The grid has a fixed size unless [`WebConfig::fit_parent`] is set, in
+//!   which case it follows the canvas CSS size and the model receives
+//!   [`Msg::Screen`] on changes.  Either way, the canvas is redrawn at the
+//!   new device pixel ratio when the page is zoomed.
+//! * [`Model::on_exit`](gruid_core::Model::on_exit) runs when the model
+//!   returns [`Effect::End`] or on the page's `pagehide` event.  The latter
+//!   is best-effort: browsers may kill a tab without firing it, and only
+//!   synchronous work (e.g. writing `localStorage`) is guaranteed to finish.
+//!
+//! # Ending
+//!
+//! When the model returns [`Effect::End`], the driver stops on the next
+//! animation frame: its event listeners are removed, the canvas shows
+//! what [`WebConfig::on_end`] asks for, and the driver state, model
+//! included, is dropped.  The page learns of it from
+//! [`WebConfig::on_end_callback`], or from a `gruid-ended` event
+//! dispatched on the canvas, which bubbles:
+//!
+//! ```js
+//! canvas.addEventListener('gruid-ended', () => playAgain.hidden = false);
+//! ```
+//!
+//! A driver can then be started again on the canvas, for instance by
+//! calling `start()` from that handler.  Starting one while another still
+//! runs on the canvas stops the older one, without the end overlay or
+//! event.
+//!
+//! # Tiles
+//!
+//! With a [`WebConfig::tile_manager`], cells are the size of its tiles
+//! and those it has a tile for are drawn as bitmaps colourised with their
+//! foreground and background, cached in an offscreen canvas; other cells
+//! are drawn as text.  `examples/tiles.rs` is a small room drawn this way.
+//!
+//! # Clipboard
+//!
+//! [`Effect::SetClipboard`](gruid_core::Effect::SetClipboard) writes with
+//! `navigator.clipboard`, and text pasted while the canvas or its hidden
+//! text field has the focus, with Ctrl+V or a touch keyboard's paste
+//! action, arrives whole as a [`Msg::Paste`].
+//!
+//! # Recording
+//!
+//! With a [`WebConfig::recording`], the frames drawn are also kept in
+//! memory, within [`RecordingLimits`] that drop the oldest ones, so that
+//! the page can offer the session for download and replay it with
+//! [`gruid_ui::Replay`](https://docs.rs/gruid-ui). The exported
+//! `gruidRecording()` function returns it to JavaScript:
+//!
+//! ```js
+//! import init, { gruidRecording } from './pkg/my_app.js';
+//! const bytes = gruidRecording();  // Uint8Array, or undefined
+//! const url = URL.createObjectURL(new Blob([bytes]));
+//! ```
+//!
+//! # Touch
+//!
+//! On touch screens a tap is a [`MouseAction::Main`] click on the cell,
+//! sliding a finger drags from where it landed, and holding it still for
+//! [`WebConfig::long_press_ms`] is a [`MouseAction::Secondary`] click, so
+//! that context actions stay reachable.  Gestures with several fingers are
+//! ignored, and the page does not scroll or zoom while the canvas is
+//! touched.
+//!
+//! Phones only show their keyboard for a text field. With
+//! [`WebConfig::capture_text_input`], tapping the canvas focuses a hidden
+//! one, and what is typed on the virtual keyboard arrives as key messages,
+//! so menus and text inputs can be used.  `examples/text_input.rs` is a
+//! small prompt working this way.
+//!
+//! The keyboard then covers the bottom of the page, and of the canvas.
+//! [`WebConfig::keyboard_behavior`] can shrink the grid to the rows left
+//! in view, or move the canvas to keep the model's cursor in view, until
+//! the keyboard goes away; the driver watches the `visualViewport` for
+//! it, and waits for the keyboard to finish sliding in before reacting.
+//! This only matters with a virtual keyboard, whether for
+//! [`capture_text_input`](WebConfig::capture_text_input) or a text field
+//! of the page: on desktop it does nothing.
+//!
+//! # Preloading assets
+//!
+//! Fonts, tile images and data files can be fetched before the game starts
+//! with [`WebDriver::preload`], which reports progress through a callback
+//! the host page can use to draw its own loading indicator.  All requests
+//! go through `fetch()`, so a service worker can serve them from its cache.
+//!
+//! ```rust,ignore
+//! let assets = vec![
+//!     AssetRequest::font("Tiles Mono", "fonts/tiles-mono.woff2"),
+//!     AssetRequest::image("tiles", "img/tiles.png"),
+//! ];
+//! wasm_bindgen_futures::spawn_local(async move {
+//!     let config = WebConfig { font_family: "'Tiles Mono'".into(), ..Default::default() };
+//!     let loaded = WebDriver::preload(&config, assets, |f, name| set_bar(f, name))
+//!         .await
+//!         .expect("preload failed");
+//!     let runner = AppRunner::new(Box::new(MyModel::new()), 80, 24);
+//!     WebDriver::new(config).run_with_assets(runner, loaded).expect("driver failed");
+//! });
+//! ```
 //!
 //! # Quick start
 //!
+//! [`start_from_element`] reads the configuration from `data-gruid-*`
+//! attributes of the canvas, so the grid size or font change in the HTML
+//! without recompiling:
+//!
 //! ```html
-//! <canvas id="gruid-canvas" tabindex="1"></canvas>
+//! <canvas id="gruid-canvas" tabindex="1"
+//!         data-gruid-width="100" data-gruid-height="30"
+//!         data-gruid-font-size="18"></canvas>
 //! <script type="module">
 //!   import init, { start } from './pkg/my_app.js';
 //!   await init();
@@ -27,36 +143,82 @@
 //! ```
 //!
 //! ```rust,ignore
-//! use gruid_core::{AppRunner, EventLoopDriver, Model};
-//! use gruid_web::{WebConfig, WebDriver};
 //! use wasm_bindgen::prelude::*;
 //!
 //! #[wasm_bindgen]
 //! pub fn start() {
-//!     let config = WebConfig::default();
-//!     let driver = WebDriver::new(config);
-//!     let runner = AppRunner::new(Box::new(MyModel::new()), 80, 24);
-//!     driver.run(runner).expect("driver failed");
+//!     gruid_web::start_from_element("gruid-canvas", |config| {
+//!         (Box::new(MyModel::new()), config.width, config.height)
+//!     })
+//!     .expect("driver failed");
 //! }
 //! ```
+//!
+//! To fill a container instead, set `data-gruid-fit-parent` and size the
+//! canvas with CSS; the grid then follows the window and page layout:
+//!
+//! ```html
+//! <div style="position: fixed; inset: 0">
+//!   <canvas id="gruid-canvas" tabindex="1" data-gruid-fit-parent
+//!           style="display: block; width: 100%; height: 100%"></canvas>
+//! </div>
+//! ```
+//!
+//! For full control, build a [`WebConfig`] and run a [`WebDriver`]
+//! yourself:
+//!
+//! ```rust,ignore
+//! use gruid_core::{AppRunner, EventLoopDriver};
+//! use gruid_web::{WebConfig, WebDriver};
+//!
+//! let config = WebConfig::default();
+//! let runner = AppRunner::new(Box::new(MyModel::new()), config.width, config.height);
+//! WebDriver::new(config).run(runner).expect("driver failed");
+//! ```
+//!
+//! [`Effect::Cmd`]: gruid_core::Effect::Cmd
+//! [`Effect::CmdProgress`]: gruid_core::Effect::CmdProgress
+//! [`Effect::Continue`]: gruid_core::Effect::Continue
+//! [`Effect::End`]: gruid_core::Effect::End
+//! [`Effect::Sub`]: gruid_core::Effect::Sub
+//! [`Effect::Subscribe`]: gruid_core::Effect::Subscribe
+//! [`Effect::Tick`]: gruid_core::Effect::Tick
+
+mod assets;
+mod batch;
+mod clipboard;
+mod lifecycle;
+mod recording;
+mod soft_keyboard;
+mod tiles;
+mod touch;
+mod viewport;
 
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::future::Future;
+use std::rc::{Rc, Weak};
 
+use clipboard::NavigatorClipboard;
 use gruid_core::{
+    AppRunner, ColorDepth, DriverCaps, EventLoopDriver, Job, Platform, Point, SetWindowTitle,
+    app::effect_handler,
     grid::Frame,
-    messages::{Key, ModMask, MouseAction, Msg},
-    style::Color,
-    AppRunner, EventLoopDriver, Point,
+    messages::{Key, ModMask, MouseAction, MouseTracker, Msg, SystemTheme},
 };
+use lifecycle::{EndCause, Instances, Lifeline, Listeners};
 
-use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
 use web_sys::{
-    CanvasRenderingContext2d, Document, HtmlCanvasElement, KeyboardEvent, MouseEvent, WheelEvent,
-    Window,
+    AddEventListenerOptions, CanvasRenderingContext2d, ClipboardEvent, CompositionEvent,
+    CustomEvent, CustomEventInit, Document, FocusOptions, HtmlCanvasElement, HtmlInputElement,
+    InputEvent, KeyboardEvent, MediaQueryList, MouseEvent, TouchEvent, WheelEvent, Window,
 };
 
+pub use assets::{AssetKind, AssetRequest, PreloadedAssets};
+pub use recording::{RecordingLimits, WebRecording};
+pub use gruid_core::TileManager;
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -65,6 +227,15 @@ fn window() -> Window {
     web_sys::window().expect("no global `window`")
 }
 
+/// Run an [`Effect::Cmd`](gruid_core::Effect::Cmd) or
+/// [`Effect::CmdProgress`](gruid_core::Effect::CmdProgress) job from a
+/// `setTimeout(0)` callback, once the current event has been handled. The
+/// driver installs it with [`AppRunner::set_spawner`].
+pub fn spawn_timeout(job: Job) {
+    let cb = Closure::once_into_js(job);
+    let _ = window().set_timeout_with_callback_and_timeout_and_arguments_0(cb.unchecked_ref(), 0);
+}
+
 fn document() -> Document {
     window().document().expect("no `document`")
 }
@@ -76,12 +247,67 @@ fn now_instant() -> std::time::Instant {
     std::time::Instant::now()
 }
 
-/// Convert a gruid [`Color`] to a CSS colour string.
-fn color_to_css(color: Color, default: &str) -> String {
-    if color == Color::DEFAULT {
-        default.to_string()
-    } else {
-        format!("rgb({},{},{})", color.r(), color.g(), color.b())
+/// Measure the cell size for `font_css` on `ctx`.
+fn measure_cell(
+    ctx: &CanvasRenderingContext2d,
+    font_css: &str,
+    font_size: f64,
+) -> (f64, f64, f64) {
+    ctx.set_font(font_css);
+    let metrics = ctx.measure_text("M").expect("measureText failed");
+    let advance = metrics.width();
+    let cell_w = advance.ceil();
+    // Use font_size as cell height (good enough for monospace)
+    let cell_h = (font_size * 1.2).ceil();
+    (cell_w, cell_h, advance)
+}
+
+/// The canvas `letterSpacing` adding `extra` pixels after each glyph, or
+/// `None` if some is needed and the browser cannot space canvas text.
+fn letter_spacing(ctx: &CanvasRenderingContext2d, extra: f64) -> Option<String> {
+    // Tiles may make cells narrower than the advance: the spacing is
+    // negative then.
+    if extra.abs() < 1.0 / 64.0 {
+        return Some("0px".into());
+    }
+    js_sys::Reflect::has(ctx, &"letterSpacing".into())
+        .unwrap_or(false)
+        .then(|| format!("{extra}px"))
+}
+
+impl batch::Canvas for CanvasRenderingContext2d {
+    fn set_fill_style(&mut self, css: &str) {
+        self.set_fill_style_str(css);
+    }
+
+    fn set_font(&mut self, font: &str) {
+        CanvasRenderingContext2d::set_font(self, font);
+    }
+
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64) {
+        CanvasRenderingContext2d::fill_rect(self, x, y, w, h);
+    }
+
+    fn fill_text(&mut self, text: &str, x: f64, y: f64) {
+        let _ = CanvasRenderingContext2d::fill_text(self, text, x, y);
+    }
+}
+
+/// Capabilities of a canvas with `cell_w × cell_h` cells, drawing `tiles`
+/// if set.  Timer commands need threads, so there are no animation ticks;
+/// browsers fall back to other fonts for missing glyphs, so Unicode
+/// symbols render.
+fn web_caps(cell_w: f64, cell_h: f64, tiles: bool) -> DriverCaps {
+    DriverCaps {
+        mouse: true,
+        color_depth: ColorDepth::TrueColor,
+        cell_aspect: (cell_w / cell_h) as f32,
+        tiles,
+        animation_ticks: false,
+        key_release: true,
+        clipboard: true,
+        unicode: true,
+        platform: Platform::Web,
     }
 }
 
@@ -119,6 +345,39 @@ fn translate_key(key: &str, code: &str) -> Option<Key> {
     }
 }
 
+/// The message for a browser keyboard event: a `keydown`, flagged when
+/// `repeat`ed, or a `keyup` when `up` is set.
+fn keyboard_msg(key: &str, code: &str, shift: bool, repeat: bool, up: bool) -> Option<Msg> {
+    let key = translate_key(key, code)?;
+    let modifiers = modifier_mask(shift, false, false, false);
+    let time = now_instant();
+    Some(if up {
+        Msg::KeyUp {
+            key,
+            modifiers,
+            time,
+        }
+    } else {
+        Msg::KeyDown {
+            key,
+            modifiers,
+            time,
+            repeat,
+        }
+    })
+}
+
+/// The press action of a `MouseEvent.button` number, or of the button
+/// being dragged (`-1` for none).
+fn button_action(button: i32) -> Option<MouseAction> {
+    match button {
+        0 => Some(MouseAction::Main),
+        1 => Some(MouseAction::Auxiliary),
+        2 => Some(MouseAction::Secondary),
+        _ => None,
+    }
+}
+
 /// Build a [`ModMask`] from a browser keyboard/mouse event's modifier flags.
 fn modifier_mask(shift: bool, ctrl: bool, alt: bool, meta: bool) -> ModMask {
     let mut m = ModMask::NONE;
@@ -141,8 +400,39 @@ fn modifier_mask(shift: bool, ctrl: bool, alt: bool, meta: bool) -> ModMask {
 // Configuration
 // ---------------------------------------------------------------------------
 
+/// What the canvas shows once the model returned
+/// [`Effect::End`](gruid_core::Effect::End).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum EndBehavior {
+    /// Keep the last frame.
+    #[default]
+    LeaveLastFrame,
+    /// Clear the canvas, leaving it transparent.
+    Clear,
+    /// Dim the last frame and write the given text over it, centred, one
+    /// line per `\n`.
+    Overlay(String),
+}
+
+/// What the driver does when a virtual keyboard covers part of the canvas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyboardBehavior {
+    /// Nothing: the keyboard is drawn over the bottom of the grid.
+    #[default]
+    Overlay,
+    /// Shrink the grid to the rows above the keyboard, sending the model a
+    /// [`Msg::Screen`], and give the rows back once it is gone.  Suits
+    /// layouts that reflow, such as a message log above a prompt.
+    ShrinkRows,
+    /// Move the canvas so that the row of the cursor the model set with
+    /// [`Grid::set_cursor`](gruid_core::Grid::set_cursor) stays in view,
+    /// and back once the keyboard is gone.  Nothing moves while the cursor
+    /// is hidden.
+    PanToCursor,
+}
+
 /// Configuration for [`WebDriver`].
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct WebConfig {
     /// The `id` attribute of the `<canvas>` element (default: `"gruid-canvas"`).
     pub canvas_id: String,
@@ -154,6 +444,75 @@ pub struct WebConfig {
     pub width: i32,
     /// Grid height in cells (default: `24`).
     pub height: i32,
+    /// Size the grid to the canvas instead of the canvas to the grid
+    /// (default: `false`).
+    ///
+    /// The page must then give the canvas a CSS size, for instance
+    /// `width: 100%; height: 100%` inside a sized container.  The grid is
+    /// recomputed when that size or the page zoom changes, and the model
+    /// receives a [`Msg::Screen`] with the new size.
+    pub fit_parent: bool,
+    /// Upper bound on `width × height` in [`fit_parent`](Self::fit_parent)
+    /// mode (default: `40000`).  A zoomed-out page shrinks cells, so
+    /// without a bound it could ask the model for a huge grid.
+    pub max_cells: i32,
+    /// Optional tile manager for custom tile-based rendering (default:
+    /// `None`).  When present, cell dimensions come from
+    /// [`TileManager::tile_size()`] and cells it has a tile for are drawn
+    /// as colourised bitmaps; the others are drawn as text.
+    pub tile_manager: Option<Rc<dyn TileManager>>,
+    /// Integer scale factor for tiles (default 0 = the device pixel ratio,
+    /// rounded).  A value of 2 draws each tile pixel as a 2×2 block, etc.
+    pub tile_scale: u32,
+    /// How long a finger must rest on a cell for a
+    /// [`Secondary`](MouseAction::Secondary) click, in milliseconds
+    /// (default: `500`).
+    pub long_press_ms: u32,
+    /// Show the virtual keyboard of touch devices when the canvas is
+    /// tapped, forwarding what is typed as key messages (default:
+    /// `false`).  Desktop keyboards are not affected.
+    pub capture_text_input: bool,
+    /// What to do while a virtual keyboard covers part of the canvas
+    /// (default: [`KeyboardBehavior::Overlay`]).  See
+    /// [Touch](crate#touch).
+    pub keyboard_behavior: KeyboardBehavior,
+    /// Keep the frames drawn in this recording (default: `None`).  See
+    /// [`WebRecording`].
+    pub recording: Option<WebRecording>,
+    /// What the canvas shows once the model returned
+    /// [`Effect::End`](gruid_core::Effect::End) (default:
+    /// [`EndBehavior::LeaveLastFrame`]).
+    pub on_end: EndBehavior,
+    /// Called with the canvas element once the model returned
+    /// [`Effect::End`](gruid_core::Effect::End) and the driver stopped
+    /// (default: `None`).  A `gruid-ended` event is dispatched on the
+    /// canvas too.
+    pub on_end_callback: Option<js_sys::Function>,
+}
+
+impl std::fmt::Debug for WebConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebConfig")
+            .field("canvas_id", &self.canvas_id)
+            .field("font_size", &self.font_size)
+            .field("font_family", &self.font_family)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("fit_parent", &self.fit_parent)
+            .field("max_cells", &self.max_cells)
+            .field("tile_manager", &self.tile_manager.as_ref().map(|_| ".."))
+            .field("tile_scale", &self.tile_scale)
+            .field("long_press_ms", &self.long_press_ms)
+            .field("capture_text_input", &self.capture_text_input)
+            .field("keyboard_behavior", &self.keyboard_behavior)
+            .field("recording", &self.recording.as_ref().map(|r| r.len()))
+            .field("on_end", &self.on_end)
+            .field(
+                "on_end_callback",
+                &self.on_end_callback.as_ref().map(|_| ".."),
+            )
+            .finish()
+    }
 }
 
 impl Default for WebConfig {
@@ -164,10 +523,107 @@ impl Default for WebConfig {
             font_family: "monospace".into(),
             width: 80,
             height: 24,
+            fit_parent: false,
+            max_cells: 40_000,
+            tile_manager: None,
+            tile_scale: 0,
+            long_press_ms: 500,
+            capture_text_input: false,
+            keyboard_behavior: KeyboardBehavior::Overlay,
+            recording: None,
+            on_end: EndBehavior::LeaveLastFrame,
+            on_end_callback: None,
         }
     }
 }
 
+impl WebConfig {
+    /// The CSS `font` shorthand used for drawing.
+    fn font_css(&self) -> String {
+        self.font_css_scaled(1.0)
+    }
+
+    /// The CSS `font` shorthand for a backing store with `dpr` device pixels
+    /// per CSS pixel.
+    fn font_css_scaled(&self, dpr: f64) -> String {
+        let mut css = String::new();
+        self.write_font_css(dpr, &mut css);
+        css
+    }
+
+    /// Override fields from the `data-gruid-*` attributes among `attrs`,
+    /// given as name and value pairs, as found on the canvas element.
+    ///
+    /// An attribute is named after the field it sets, with dashes for
+    /// underscores: `data-gruid-font-size="20"` sets
+    /// [`font_size`](Self::font_size). Numbers must be positive, and
+    /// `fit-parent` and `capture-text-input` are boolean attributes that
+    /// may also be `"false"`, and `keyboard-behavior` is one of
+    /// `"overlay"`, `"shrink-rows"` and `"pan-to-cursor"`.
+    /// `canvas_id` cannot be set this way. Other attributes are ignored.
+    ///
+    /// Invalid values and unknown `data-gruid-*` names leave the
+    /// configuration unchanged; a warning for each is returned.
+    pub fn apply_data_attributes<'a>(
+        &mut self,
+        attrs: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Vec<String> {
+        fn positive<T: std::str::FromStr + PartialOrd + Default>(v: &str) -> Option<T> {
+            v.trim().parse().ok().filter(|n| *n > T::default())
+        }
+        fn flag(v: &str) -> Option<bool> {
+            match v {
+                "" | "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            }
+        }
+        let mut warnings = Vec::new();
+        for (name, value) in attrs {
+            let Some(key) = name.strip_prefix("data-gruid-") else {
+                continue;
+            };
+            let ok = match key {
+                "font-size" => positive(value)
+                    .filter(|n: &f64| n.is_finite())
+                    .map(|n| self.font_size = n),
+                "font-family" => {
+                    (!value.trim().is_empty()).then(|| self.font_family = value.trim().to_string())
+                }
+                "width" => positive(value).map(|n| self.width = n),
+                "height" => positive(value).map(|n| self.height = n),
+                "max-cells" => positive(value).map(|n| self.max_cells = n),
+                "long-press-ms" => positive(value).map(|n| self.long_press_ms = n),
+                "fit-parent" => flag(value).map(|b| self.fit_parent = b),
+                "capture-text-input" => flag(value).map(|b| self.capture_text_input = b),
+                "keyboard-behavior" => match value.trim() {
+                    "overlay" => Some(KeyboardBehavior::Overlay),
+                    "shrink-rows" => Some(KeyboardBehavior::ShrinkRows),
+                    "pan-to-cursor" => Some(KeyboardBehavior::PanToCursor),
+                    _ => None,
+                }
+                .map(|k| self.keyboard_behavior = k),
+                _ => {
+                    warnings.push(format!("unknown attribute {name}"));
+                    continue;
+                }
+            };
+            if ok.is_none() {
+                warnings.push(format!("invalid value {value:?} for {name}"));
+            }
+        }
+        warnings
+    }
+
+    /// Write [`font_css_scaled`](Self::font_css_scaled) into `out`, reusing
+    /// its buffer.
+    fn write_font_css(&self, dpr: f64, out: &mut String) {
+        use std::fmt::Write;
+        out.clear();
+        let _ = write!(out, "{}px {}", self.font_size * dpr, self.font_family);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // WebDriver
 // ---------------------------------------------------------------------------
@@ -178,12 +634,48 @@ impl Default for WebConfig {
 /// usage instructions.
 pub struct WebDriver {
     config: WebConfig,
+    assets: PreloadedAssets,
 }
 
 impl WebDriver {
     /// Create a new driver from the given configuration.
     pub fn new(config: WebConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            assets: PreloadedAssets::default(),
+        }
+    }
+
+    /// Fetch `assets` before the game starts.
+    ///
+    /// Fonts are loaded first and registered with `document.fonts`, images
+    /// are decoded, and binary assets are kept as bytes.  The returned
+    /// future resolves once the font from `config` is usable, so the first
+    /// frame is measured and drawn with the right glyphs.  `on_progress` is
+    /// called with the completed fraction (`0.0..=1.0`) and the name of the
+    /// asset being fetched.
+    ///
+    /// Fails on an invalid manifest (duplicate names, empty URLs) or on the
+    /// first asset that cannot be fetched or decoded.
+    pub fn preload(
+        config: &WebConfig,
+        assets: Vec<AssetRequest>,
+        on_progress: impl FnMut(f32, &str) + 'static,
+    ) -> impl Future<Output = Result<PreloadedAssets, JsValue>> + 'static {
+        assets::load(assets, config.font_css(), on_progress)
+    }
+
+    /// Run the game with assets fetched by [`preload`](Self::preload).
+    ///
+    /// The driver keeps `assets` alive for the page lifetime, so decoded
+    /// images stay cached.
+    pub fn run_with_assets(
+        mut self,
+        runner: AppRunner,
+        assets: PreloadedAssets,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.assets = assets;
+        self.run(runner)
     }
 }
 
@@ -195,16 +687,45 @@ impl WebDriver {
 struct Shared {
     runner: AppRunner,
     ctx: CanvasRenderingContext2d,
+    config: WebConfig,
+    /// Cell size in device pixels.
     cell_w: f64,
     cell_h: f64,
+    /// Device pixel ratio the canvas was last laid out for.
+    dpr: f64,
     font_css: String,
-    mouse_pos: Point,
+    /// Scratch buffer for the font of the next layout, or of other faces.
+    font_scratch: String,
+    /// Canvas `letterSpacing` making the font's advance one cell, if the
+    /// browser supports it or none is needed. Runs of text are drawn
+    /// glyph by glyph without it.
+    letter_spacing: Option<String>,
+    /// CSS strings of the colours drawn so far.
+    css: batch::CssCache,
+    /// Tiles drawn instead of text, if there is a tile manager.
+    tiles: Option<tiles::TileAtlas>,
+    /// Set by the zoom and resize observers; the next animation frame
+    /// re-lays out the canvas, so bursts of events cost one update.
+    relayout_pending: bool,
+    mouse: MouseTracker,
     mouse_drag: i32, // button number being dragged, or -1
+    touch: touch::TouchTracker,
+    /// Hidden field receiving virtual keyboard input, if captured.
+    text_input: Option<HtmlInputElement>,
+    /// What a virtual keyboard showing did to the grid.
+    keyboard: viewport::KeyboardState,
+    /// The cursor of the last frame drawn.
+    cursor: Option<Point>,
+    _assets: PreloadedAssets,
 }
 
 impl Shared {
     /// Push a message through the runner and immediately try to render.
     fn handle_and_render(&mut self, msg: Msg) {
+        // Once the model quit, the next animation frame stops the driver.
+        if self.runner.should_quit() {
+            return;
+        }
         self.runner.handle_msg(msg);
         self.render();
     }
@@ -213,48 +734,488 @@ impl Shared {
     fn render(&mut self) {
         self.runner.process_pending_msgs();
         if let Some(frame) = self.runner.draw_frame() {
+            if let Some(recording) = &self.config.recording {
+                recording.push(&frame, js_sys::Date::now());
+            }
+            self.cursor = frame.cursor;
             self.flush(frame);
+            self.runner.frame_presented();
         }
     }
 
-    /// Paint a frame diff onto the canvas.
-    fn flush(&self, frame: Frame) {
+    /// Draw what [`WebConfig::on_end`] asks for over the last frame.
+    fn draw_end(&self, canvas: &HtmlCanvasElement) {
+        let (w, h) = (canvas.width() as f64, canvas.height() as f64);
         let ctx = &self.ctx;
-        let cw = self.cell_w;
-        let ch = self.cell_h;
-
-        for fc in &frame.cells {
-            let px = fc.pos.x as f64 * cw;
-            let py = fc.pos.y as f64 * ch;
-
-            // Background
-            let bg = color_to_css(fc.cell.style.bg, "#000000");
-            ctx.set_fill_style_str(&bg);
-            ctx.fill_rect(px, py, cw, ch);
-
-            // Foreground character
-            if fc.cell.ch != ' ' {
-                let fg = color_to_css(fc.cell.style.fg, "#ffffff");
-                ctx.set_fill_style_str(&fg);
+        match &self.config.on_end {
+            EndBehavior::LeaveLastFrame => {}
+            EndBehavior::Clear => ctx.clear_rect(0.0, 0.0, w, h),
+            EndBehavior::Overlay(text) => {
+                // Restored for a driver started again on the canvas.
+                ctx.save();
+                ctx.set_fill_style_str("rgba(0, 0, 0, 0.6)");
+                ctx.fill_rect(0.0, 0.0, w, h);
+                if self.letter_spacing.is_some() {
+                    let _ = js_sys::Reflect::set(ctx, &"letterSpacing".into(), &"0px".into());
+                }
                 ctx.set_font(&self.font_css);
-                // Draw text at baseline (roughly cell bottom minus a small descent)
-                let text_y = py + ch * 0.85;
-                let _ = ctx.fill_text(&fc.cell.ch.to_string(), px, text_y);
+                ctx.set_text_align("center");
+                ctx.set_text_baseline("middle");
+                ctx.set_fill_style_str(batch::DEFAULT_FG);
+                let lines: Vec<&str> = text.lines().collect();
+                let top = h / 2.0 - self.cell_h * (lines.len() as f64 - 1.0) / 2.0;
+                for (i, line) in lines.iter().enumerate() {
+                    let _ = ctx.fill_text(line, w / 2.0, top + self.cell_h * i as f64);
+                }
+                ctx.restore();
+            }
+        }
+    }
+
+    /// Paint a frame diff onto the canvas.
+    fn flush(&mut self, frame: Frame) {
+        let atlas = self.tiles.as_ref();
+        let batches = batch::batch_cells(&frame.cells, &mut self.css, |cell| {
+            atlas.is_some_and(|a| a.has_tile(cell))
+        });
+        // Resizing the canvas resets the spacing with the rest of the state.
+        if let Some(spacing) = &self.letter_spacing
+            && !batches.glyphs.is_empty()
+        {
+            let _ = js_sys::Reflect::set(&self.ctx, &"letterSpacing".into(), &spacing.into());
+        }
+        let layout = batch::Layout {
+            cell_w: self.cell_w,
+            cell_h: self.cell_h,
+            font: &self.font_css,
+            underline: self.dpr.max(1.0).round(),
+            text_runs: self.letter_spacing.is_some(),
+        };
+        batch::draw(&mut self.ctx, &batches, &layout, &mut self.font_scratch);
+        if let Some(atlas) = &mut self.tiles {
+            for fc in &batches.tiles {
+                let (x, y) = (fc.pos.x as f64 * self.cell_w, fc.pos.y as f64 * self.cell_h);
+                atlas.draw(&self.ctx, &fc.cell, x, y);
+            }
+        }
+    }
+
+    /// Re-measure the font at the current device pixel ratio and resize the
+    /// canvas backing store, redrawing everything if anything changed (a
+    /// zoom, a resized canvas, or a web font that finished loading after
+    /// the first measurement).  In [`fit_parent`](WebConfig::fit_parent)
+    /// mode a new grid size is sent to the model as [`Msg::Screen`].
+    fn relayout(&mut self, canvas: &HtmlCanvasElement) {
+        self.relayout_pending = false;
+        let dpr = window().device_pixel_ratio();
+        self.config.write_font_css(dpr, &mut self.font_scratch);
+        let (mut cell_w, mut cell_h, advance) =
+            measure_cell(&self.ctx, &self.font_scratch, self.config.font_size * dpr);
+        if let Some(atlas) = &mut self.tiles {
+            let scale = match self.config.tile_scale {
+                0 => dpr.round().max(1.0) as usize,
+                n => n as usize,
+            };
+            atlas.set_scale(scale);
+            let (w, h) = atlas.cell_size();
+            (cell_w, cell_h) = (w as f64, h as f64);
+        }
+        let (w, h) = (self.runner.width(), self.runner.height());
+        let shrink = self.config.keyboard_behavior == KeyboardBehavior::ShrinkRows;
+        let geom = if self.config.fit_parent {
+            let client = (canvas.client_width() as f64, canvas.client_height() as f64);
+            if client.0 <= 0.0 || client.1 <= 0.0 {
+                // Hidden or not laid out yet.
+                return;
+            }
+            let mut geom = fit_geometry(client, dpr, (cell_w, cell_h), self.config.max_cells);
+            if shrink {
+                geom.rows = self.keyboard.fitted_rows(geom.rows);
+            }
+            geom
+        } else {
+            let rows = if shrink { self.keyboard.fixed_rows(h) } else { h };
+            fixed_geometry(w, rows, (cell_w, cell_h))
+        };
+        if dpr == self.dpr
+            && cell_w == self.cell_w
+            && cell_h == self.cell_h
+            && (geom.cols, geom.rows) == (w, h)
+            && geom.backing == (canvas.width(), canvas.height())
+        {
+            return;
+        }
+        self.dpr = dpr;
+        self.cell_w = cell_w;
+        self.cell_h = cell_h;
+        self.letter_spacing = letter_spacing(&self.ctx, cell_w - advance);
+        std::mem::swap(&mut self.font_css, &mut self.font_scratch);
+        canvas.set_width(geom.backing.0);
+        canvas.set_height(geom.backing.1);
+        if !self.config.fit_parent {
+            // Keep the CSS size constant so only the sharpness changes.
+            let style = canvas.style();
+            let _ = style.set_property("width", &format!("{}px", geom.backing.0 as f64 / dpr));
+            let _ = style.set_property("height", &format!("{}px", geom.backing.1 as f64 / dpr));
+        }
+        self.ctx.set_fill_style_str(batch::DEFAULT_BG);
+        self.ctx
+            .fill_rect(0.0, 0.0, geom.backing.0 as f64, geom.backing.1 as f64);
+        if (geom.cols, geom.rows) != (w, h) {
+            self.runner.screen(geom.cols, geom.rows);
+        } else {
+            self.runner.resize(geom.cols, geom.rows);
+        }
+        self.runner.set_capabilities(web_caps(cell_w, cell_h, self.tiles.is_some()));
+        self.render();
+    }
+
+    /// Once the visual viewport settled at `now`, check whether a virtual
+    /// keyboard covers the canvas and do what
+    /// [`WebConfig::keyboard_behavior`] says, or undo it if the keyboard is
+    /// gone.  A panned canvas also follows the cursor on every call.
+    fn follow_keyboard(&mut self, canvas: &HtmlCanvasElement, now: f64) {
+        let behavior = self.config.keyboard_behavior;
+        let settled = self.keyboard.settled(now);
+        let follow = behavior == KeyboardBehavior::PanToCursor && self.keyboard.shown();
+        if behavior == KeyboardBehavior::Overlay || !(settled || follow) {
+            return;
+        }
+        let Some(vv) = window().visual_viewport() else {
+            return;
+        };
+        let (left, top) = (vv.offset_left(), vv.offset_top());
+        let viewport = viewport::Rect::new(left, top, vv.width(), vv.height());
+        // Where the canvas would be without the pan.
+        let r = canvas.get_bounding_client_rect();
+        let top = r.top() - self.keyboard.pan;
+        let rect = viewport::Rect::new(r.left(), top, r.width(), r.height());
+        let scale = canvas_scale(canvas, self.dpr);
+        if settled {
+            let layout_height = window()
+                .inner_height()
+                .ok()
+                .and_then(|h| h.as_f64())
+                .unwrap_or(viewport.height);
+            let covered = viewport::keyboard_shown(layout_height, viewport.height, vv.scale())
+                && rect.intersect(&viewport).is_some();
+            let rows =
+                covered.then(|| viewport::rows_in_view(rect.top, &viewport, scale, self.cell_h));
+            self.keyboard.set_rows_in_view(rows);
+            if behavior == KeyboardBehavior::ShrinkRows {
+                self.relayout_pending = true;
+            }
+        }
+        if behavior == KeyboardBehavior::PanToCursor {
+            let pan = match self.cursor {
+                Some(p) if self.keyboard.shown() => {
+                    viewport::pan_to_row(&rect, &viewport, p.y, scale, self.cell_h)
+                }
+                _ => 0.0,
+            };
+            if pan != self.keyboard.pan {
+                self.keyboard.pan = pan;
+                let style = canvas.style();
+                let _ = if pan == 0.0 {
+                    style.remove_property("transform").map(drop)
+                } else {
+                    style.set_property("transform", &format!("translateY({pan}px)"))
+                };
             }
         }
     }
 
     /// Convert a mouse event's client coordinates to grid cell coordinates.
-    fn mouse_to_cell(&self, evt: &MouseEvent, canvas: &HtmlCanvasElement) -> Point {
+    fn mouse_to_cell(&mut self, evt: &MouseEvent, canvas: &HtmlCanvasElement) -> Point {
+        self.client_to_cell(evt.client_x(), evt.client_y(), canvas)
+    }
+
+    /// Convert client coordinates, as found on mouse events and touches,
+    /// to grid cell coordinates.
+    fn client_to_cell(&mut self, x: i32, y: i32, canvas: &HtmlCanvasElement) -> Point {
         let rect = canvas.get_bounding_client_rect();
-        let scale_x = canvas.width() as f64 / rect.width();
-        let scale_y = canvas.height() as f64 / rect.height();
-        let x = (evt.client_x() as f64 - rect.left()) * scale_x;
-        let y = (evt.client_y() as f64 - rect.top()) * scale_y;
-        Point::new(
-            ((x - 1.0).max(0.0) / self.cell_w) as i32,
-            ((y - 1.0).max(0.0) / self.cell_h) as i32,
-        )
+        let x = x as f64 - rect.left();
+        let y = y as f64 - rect.top();
+        let scale = canvas_scale(canvas, self.dpr);
+        self.mouse
+            .resize(self.runner.width(), self.runner.height());
+        pixel_to_cell(x, y, scale, self.cell_w, self.cell_h)
+    }
+
+    /// Deliver keys typed on the virtual keyboard, each pressed and
+    /// released.
+    fn handle_typed(&mut self, keys: Vec<Key>) {
+        for key in keys {
+            let time = now_instant();
+            self.handle_and_render(Msg::KeyDown {
+                key: key.clone(),
+                modifiers: ModMask::NONE,
+                time,
+                repeat: false,
+            });
+            self.handle_and_render(Msg::KeyUp {
+                key,
+                modifiers: ModMask::NONE,
+                time,
+            });
+        }
+    }
+
+    /// Whether a key event targets the hidden text field and is left to
+    /// its input events.
+    fn left_to_text_input(&self, e: &KeyboardEvent) -> bool {
+        self.text_input.as_ref().is_some_and(|input| {
+            e.target()
+                .is_some_and(|t| js_sys::Object::is(&t, input))
+                && soft_keyboard::left_to_input(&e.key())
+        })
+    }
+
+    /// Deliver what a touch did as mouse messages.
+    fn handle_touch(&mut self, events: Vec<touch::TouchEvent>) {
+        for event in events {
+            match event {
+                touch::TouchEvent::Moved(pos) => {
+                    let held = self.touch.held();
+                    for msg in self.mouse.dragged(pos, held, ModMask::NONE) {
+                        self.handle_and_render(msg);
+                    }
+                }
+                touch::TouchEvent::Button(action, pos) => {
+                    if let Some(msg) = self.mouse.button(action, Some(pos), ModMask::NONE) {
+                        self.handle_and_render(msg);
+                    }
+                }
+                touch::TouchEvent::Left => {
+                    if let Some(msg) = self.mouse.left(ModMask::NONE) {
+                        self.handle_and_render(msg);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Keep a running continuation going while the page is hidden, when
+/// animation frames stop: take a step from a timer, until the page shows
+/// again or the continuation ends.
+fn keep_continuing(shared: Weak<RefCell<Shared>>, lifeline: Lifeline) {
+    let callback = Closure::once_into_js(move || {
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        if !lifeline.is_alive() || !document().hidden() {
+            return;
+        }
+        {
+            let mut s = shared.borrow_mut();
+            if !s.runner.is_continuing() || s.runner.should_quit() {
+                return;
+            }
+            s.render();
+        }
+        keep_continuing(Rc::downgrade(&shared), lifeline);
+    });
+    let _ = window()
+        .set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), 0);
+}
+
+/// Once `ms` have elapsed, turn touch `serial` into a long-press if the
+/// finger is still resting on its cell.
+fn arm_long_press(shared: Weak<RefCell<Shared>>, serial: u32, ms: u32) {
+    let callback = Closure::once_into_js(move || {
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let mut s = shared.borrow_mut();
+        let events = s.touch.long_press(serial);
+        s.handle_touch(events);
+    });
+    let _ = window().set_timeout_with_callback_and_timeout_and_arguments_0(
+        callback.unchecked_ref(),
+        ms.min(i32::MAX as u32) as i32,
+    );
+}
+
+/// Device pixels per CSS pixel of `canvas`, measured rather than taken
+/// from the device pixel ratio in case page CSS stretches the canvas.
+/// `dpr` is used while the canvas is not laid out.
+fn canvas_scale(canvas: &HtmlCanvasElement, dpr: f64) -> f64 {
+    match canvas.client_width() {
+        0 => dpr,
+        cw => canvas.width() as f64 / cw as f64,
+    }
+}
+
+/// Convert CSS pixels relative to the canvas border box to a cell, for a
+/// backing store with `scale` device pixels per CSS pixel and cells of
+/// `cell_w × cell_h` device pixels.  There is no clamping: the 1px border
+/// and any unused canvas area map outside the grid.
+fn pixel_to_cell(x: f64, y: f64, scale: f64, cell_w: f64, cell_h: f64) -> Point {
+    Point::new(
+        ((x - 1.0) * scale / cell_w).floor() as i32,
+        ((y - 1.0) * scale / cell_h).floor() as i32,
+    )
+}
+
+/// Grid and backing-store size of the canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Geometry {
+    cols: i32,
+    rows: i32,
+    /// Backing-store size in device pixels.
+    backing: (u32, u32),
+}
+
+/// Geometry for a fixed `cols × rows` grid of `cell` device pixels.
+fn fixed_geometry(cols: i32, rows: i32, cell: (f64, f64)) -> Geometry {
+    Geometry {
+        cols,
+        rows,
+        backing: (
+            (cell.0 * cols as f64).ceil() as u32,
+            (cell.1 * rows as f64).ceil() as u32,
+        ),
+    }
+}
+
+/// Geometry filling a canvas of `client` CSS pixels with cells of `cell`
+/// device pixels, at most `max_cells` of them.
+fn fit_geometry(client: (f64, f64), dpr: f64, cell: (f64, f64), max_cells: i32) -> Geometry {
+    let backing = ((client.0 * dpr).round(), (client.1 * dpr).round());
+    let cols = ((backing.0 / cell.0).floor() as i32).max(1);
+    let rows = ((backing.1 / cell.1).floor() as i32).max(1);
+    let (cols, rows) = clamp_cells(cols, rows, max_cells);
+    Geometry {
+        cols,
+        rows,
+        backing: (backing.0 as u32, backing.1 as u32),
+    }
+}
+
+/// Shrink a `cols × rows` grid to at most `max_cells` cells, keeping its
+/// aspect ratio as far as rounding allows.  Never returns less than 1×1.
+fn clamp_cells(cols: i32, rows: i32, max_cells: i32) -> (i32, i32) {
+    let max = max_cells.max(1) as i64;
+    if cols as i64 * rows as i64 <= max {
+        return (cols, rows);
+    }
+    let f = (max as f64 / (cols as f64 * rows as f64)).sqrt();
+    let mut c = ((cols as f64 * f).floor() as i32).max(1);
+    let mut r = ((rows as f64 * f).floor() as i32).max(1);
+    // The lower bound of 1 can still overshoot for very thin grids.
+    while c as i64 * r as i64 > max {
+        if c >= r {
+            c -= 1;
+        } else {
+            r -= 1;
+        }
+    }
+    (c, r)
+}
+
+/// Flag `shared` for a relayout when the device pixel ratio changes, as
+/// it does on browser zoom or when the window moves to another screen.  A
+/// `resolution` media query only matches one ratio, so every change
+/// re-arms the watch with a query for the new ratio, until the driver
+/// stops.
+fn watch_device_pixel_ratio(shared: Weak<RefCell<Shared>>) {
+    let query = format!("(resolution: {}dppx)", window().device_pixel_ratio());
+    let Ok(Some(mql)) = window().match_media(&query) else {
+        return;
+    };
+    let callback = Closure::once_into_js(move || {
+        if let Some(s) = shared.upgrade() {
+            s.borrow_mut().relayout_pending = true;
+            watch_device_pixel_ratio(shared);
+        }
+    });
+    let options = AddEventListenerOptions::new();
+    options.set_once(true);
+    let _ = mql.add_event_listener_with_callback_and_add_event_listener_options(
+        "change",
+        callback.unchecked_ref(),
+        &options,
+    );
+}
+
+/// The theme a `prefers-color-scheme: dark` query result stands for.
+fn theme_of(dark: bool) -> SystemTheme {
+    if dark {
+        SystemTheme::Dark
+    } else {
+        SystemTheme::Light
+    }
+}
+
+/// Report changes of the browser colour scheme to the model.
+fn watch_color_scheme(listeners: &mut Listeners, shared: Rc<RefCell<Shared>>, mql: MediaQueryList) {
+    let query = mql.clone();
+    listeners.add(&mql, "change", move |_: JsValue| {
+        let mut s = shared.borrow_mut();
+        s.runner.set_system_theme(theme_of(query.matches()));
+        s.render();
+    });
+}
+
+thread_local! {
+    /// Recording of the driver started last, for [`gruid_recording`].
+    static RECORDING: RefCell<Option<WebRecording>> = const { RefCell::new(None) };
+    /// Drivers running, so that one started on a canvas stops the one
+    /// there before.
+    static INSTANCES: RefCell<Instances> = RefCell::new(Instances::default());
+}
+
+/// The recording of the running driver, encoded, or `undefined` if its
+/// [`WebConfig::recording`] is not set.  Exported to JavaScript as
+/// `gruidRecording()`, so the page can offer a download.
+#[wasm_bindgen(js_name = gruidRecording)]
+pub fn gruid_recording() -> Option<Vec<u8>> {
+    RECORDING.with_borrow(|r| r.as_ref().map(WebRecording::bytes))
+}
+
+/// Self-referencing `requestAnimationFrame` callback slot.
+type RafCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+/// What is left to do once a driver stopped, from a task of its own.
+struct Stop {
+    listeners: Option<Listeners>,
+    /// The rAF callback, owning the shared state with the listeners.
+    raf: Option<Closure<dyn FnMut()>>,
+    text_input: Option<HtmlInputElement>,
+    /// The canvas and callback to tell that the game ended, unless the
+    /// driver was replaced.
+    notify: Option<(HtmlCanvasElement, Option<js_sys::Function>)>,
+}
+
+impl Stop {
+    fn run(self) {
+        if let Some(listeners) = self.listeners {
+            listeners.remove();
+        }
+        if let Some(input) = self.text_input {
+            input.remove();
+        }
+        // Drops the shared state, now that nothing else holds it.
+        drop(self.raf);
+        if let Some((canvas, callback)) = self.notify {
+            notify_end(&canvas, callback.as_ref());
+        }
+    }
+}
+
+/// Tell the page that the game on `canvas` ended: call `callback` with
+/// the canvas and dispatch a bubbling `gruid-ended` event on it.
+fn notify_end(canvas: &HtmlCanvasElement, callback: Option<&js_sys::Function>) {
+    if let Some(f) = callback
+        && let Err(e) = f.call1(&JsValue::NULL, canvas)
+    {
+        web_sys::console::error_2(&"gruid: on_end_callback failed:".into(), &e);
+    }
+    let init = CustomEventInit::new();
+    init.set_bubbles(true);
+    if let Ok(event) = CustomEvent::new_with_event_init_dict("gruid-ended", &init) {
+        let _ = canvas.dispatch_event(&event);
     }
 }
 
@@ -262,6 +1223,42 @@ impl Shared {
 // EventLoopDriver implementation
 // ---------------------------------------------------------------------------
 
+/// Start the game on the canvas with id `canvas_id`, configured from its
+/// `data-gruid-*` attributes.
+///
+/// The attributes override [`WebConfig::default`] as described in
+/// [`WebConfig::apply_data_attributes`]; invalid ones are reported on the
+/// console and ignored.  `model_factory` receives the resulting
+/// configuration and returns the model with its grid width and height,
+/// usually those of the configuration.
+///
+/// Fails if there is no element with that id.
+pub fn start_from_element(
+    canvas_id: &str,
+    model_factory: impl FnOnce(WebConfig) -> (Box<dyn gruid_core::Model>, i32, i32),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let element = document()
+        .get_element_by_id(canvas_id)
+        .ok_or_else(|| format!("canvas element '{canvas_id}' not found"))?;
+    let attrs: Vec<(String, String)> = element
+        .get_attribute_names()
+        .iter()
+        .filter_map(|name| name.as_string())
+        .filter_map(|name| element.get_attribute(&name).map(|value| (name, value)))
+        .collect();
+    let mut config = WebConfig {
+        canvas_id: canvas_id.to_string(),
+        ..WebConfig::default()
+    };
+    let warnings =
+        config.apply_data_attributes(attrs.iter().map(|(n, v)| (n.as_str(), v.as_str())));
+    for w in warnings {
+        web_sys::console::warn_1(&format!("gruid: {w}").into());
+    }
+    let (model, width, height) = model_factory(config.clone());
+    WebDriver::new(config).run(AppRunner::new(model, width, height))
+}
+
 impl EventLoopDriver for WebDriver {
     fn run(self, mut runner: AppRunner) -> Result<(), Box<dyn std::error::Error>> {
         let cfg = &self.config;
@@ -283,92 +1280,197 @@ impl EventLoopDriver for WebDriver {
             .dyn_into::<CanvasRenderingContext2d>()
             .expect("context is not CanvasRenderingContext2d");
 
-        // --- font metrics ---------------------------------------------------
-        let font_css = format!("{}px {}", cfg.font_size, cfg.font_family);
-        ctx.set_font(&font_css);
-        let metrics = ctx.measure_text("M").expect("measureText failed");
-        let cell_w = metrics.width().ceil();
-        // Use font_size as cell height (good enough for monospace)
-        let cell_h = (cfg.font_size * 1.2).ceil();
-
-        // --- size canvas ----------------------------------------------------
-        canvas.set_width((cell_w * cfg.width as f64) as u32);
-        canvas.set_height((cell_h * cfg.height as f64) as u32);
-
         // --- init model -----------------------------------------------------
+        runner.set_clipboard(Box::new(NavigatorClipboard));
+        runner.set_spawner(spawn_timeout);
+        runner.add_effect_handler(effect_handler(|t: &SetWindowTitle| {
+            document().set_title(&t.0);
+            true
+        }));
+        let color_scheme = window()
+            .match_media("(prefers-color-scheme: dark)")
+            .ok()
+            .flatten();
+        if let Some(ref mql) = color_scheme {
+            runner.set_system_theme(theme_of(mql.matches()));
+        }
+        // Without a clock in std here, draw the seed from the browser,
+        // unless the page chose one.
+        if runner.seed().is_none() {
+            let half = || (js_sys::Math::random() * 4_294_967_296.0) as u64;
+            let seed = half() << 32 | half();
+            web_sys::console::info_1(&format!("gruid: seed {seed}").into());
+            runner.set_seed(seed);
+        }
         runner.init();
+        RECORDING.with_borrow_mut(|r| *r = cfg.recording.clone());
+        let lifeline = Lifeline::default();
+        INSTANCES.with_borrow_mut(|i| i.start(&cfg.canvas_id, lifeline.clone()));
+        let mut listeners = Listeners::new(lifeline.clone());
 
         let shared = Rc::new(RefCell::new(Shared {
             runner,
             ctx,
-            cell_w,
-            cell_h,
-            font_css,
-            mouse_pos: Point::new(-1, -1),
+            config: cfg.clone(),
+            cell_w: 0.0,
+            cell_h: 0.0,
+            dpr: 0.0,
+            font_css: cfg.font_css(),
+            font_scratch: String::new(),
+            letter_spacing: None,
+            css: batch::CssCache::new(),
+            tiles: cfg
+                .tile_manager
+                .clone()
+                .map(|manager| tiles::TileAtlas::new(manager, 1)),
+            relayout_pending: false,
+            mouse: MouseTracker::default(),
             mouse_drag: -1,
+            touch: touch::TouchTracker::default(),
+            text_input: cfg
+                .capture_text_input
+                .then(|| soft_keyboard::create(&document()))
+                .flatten(),
+            keyboard: viewport::KeyboardState::default(),
+            cursor: None,
+            _assets: self.assets,
         }));
 
-        // Initial render
+        // Measure the font, size the canvas and do the initial render.
         {
             let mut s = shared.borrow_mut();
+            s.relayout(&canvas);
             s.render();
         }
 
+        // Safety net: if the font was still loading when we measured it,
+        // re-measure once `document.fonts.ready` resolves.
+        if let Ok(ready) = document().fonts().ready() {
+            let shared = Rc::downgrade(&shared);
+            wasm_bindgen_futures::spawn_local(async move {
+                if wasm_bindgen_futures::JsFuture::from(ready).await.is_ok()
+                    && let Some(s) = shared.upgrade()
+                {
+                    s.borrow_mut().relayout_pending = true;
+                }
+            });
+        }
+
+        // --- colour scheme, zoom and resize ---------------------------------
+        if let Some(mql) = color_scheme {
+            watch_color_scheme(&mut listeners, Rc::clone(&shared), mql);
+        }
+        // The observers only flag a relayout; the rAF loop performs it, once
+        // however many of them fired.  Window resizes are watched too, for
+        // browsers without `ResizeObserver`.
+        watch_device_pixel_ratio(Rc::downgrade(&shared));
+        {
+            let shared = Rc::clone(&shared);
+            listeners.observe_resize(&canvas, move || {
+                shared.borrow_mut().relayout_pending = true;
+            });
+        }
+        {
+            let shared = Rc::clone(&shared);
+            listeners.add(&window(), "resize", move |_: JsValue| {
+                shared.borrow_mut().relayout_pending = true;
+            });
+        }
+
+        // Virtual keyboards shrink the visual viewport, in steps as they
+        // slide in; the rAF loop reacts once it stopped changing.
+        if cfg.keyboard_behavior != KeyboardBehavior::Overlay
+            && let Some(vv) = window().visual_viewport()
+        {
+            for event in ["resize", "scroll"] {
+                let shared = Rc::clone(&shared);
+                listeners.add(&vv, event, move |_: JsValue| {
+                    shared.borrow_mut().keyboard.viewport_moved(js_sys::Date::now());
+                });
+            }
+        }
+
         // --- event listeners ------------------------------------------------
 
-        // We keep Closures alive for the lifetime of the page by leaking them
-        // (`.forget()`).  This is standard practice for wasm_bindgen event
-        // listeners that should live forever.
+        // The listeners hold the shared state until the driver stops: they
+        // ignore events once its lifeline is cut, and the rAF loop then
+        // removes them.
 
-        // -- contextmenu (prevent right-click menu) --------------------------
+        // -- pagehide (tab closed or navigated away) -------------------------
         {
-            let closure = Closure::<dyn FnMut(MouseEvent)>::new(move |e: MouseEvent| {
-                e.prevent_default();
+            let shared = Rc::clone(&shared);
+            listeners.add(&window(), "pagehide", move |_: JsValue| {
+                shared.borrow_mut().runner.shutdown();
             });
-            canvas
-                .add_event_listener_with_callback("contextmenu", closure.as_ref().unchecked_ref())
-                .expect("addEventListener contextmenu");
-            closure.forget();
         }
 
-        // -- keydown ---------------------------------------------------------
-        {
+        // -- contextmenu (prevent right-click menu) --------------------------
+        listeners.add(&canvas, "contextmenu", move |e: MouseEvent| {
+            e.prevent_default();
+        });
+
+        // -- keydown / keyup -------------------------------------------------
+        for (event, up) in [("keydown", false), ("keyup", true)] {
             let shared = Rc::clone(&shared);
-            let closure = Closure::<dyn FnMut(KeyboardEvent)>::new(move |e: KeyboardEvent| {
+            // Listen on document (not just canvas) so keys are caught even
+            // when the canvas isn't focused, matching the Go driver.
+            listeners.add(&document(), event, move |e: KeyboardEvent| {
                 // Skip events with ctrl/meta/alt to avoid conflicting with
                 // browser shortcuts (matches Go driver behaviour).
                 if e.ctrl_key() || e.meta_key() || e.alt_key() {
                     return;
                 }
-                let key_str = e.key();
-                let code = e.code();
-                if let Some(key) = translate_key(&key_str, &code) {
-                    let mut mods = ModMask::NONE;
-                    if e.shift_key() {
-                        mods = mods | ModMask::SHIFT;
-                    }
+                let mut s = shared.borrow_mut();
+                if s.left_to_text_input(&e) {
+                    return;
+                }
+                if let Some(msg) = keyboard_msg(&e.key(), &e.code(), e.shift_key(), e.repeat(), up)
+                {
                     e.prevent_default();
-                    let msg = Msg::KeyDown {
-                        key,
-                        modifiers: mods,
-                        time: now_instant(),
-                    };
-                    shared.borrow_mut().handle_and_render(msg);
+                    s.handle_and_render(msg);
+                }
+            });
+        }
+
+        // -- paste -----------------------------------------------------------
+        // On the canvas and the hidden text field only, so that pasting into
+        // other fields of the page works as usual.
+        let text_input = shared.borrow().text_input.clone();
+        let mut paste_targets: Vec<&web_sys::EventTarget> = vec![&canvas];
+        paste_targets.extend(text_input.as_ref().map(|input| input as &web_sys::EventTarget));
+        for target in paste_targets {
+            let shared = Rc::clone(&shared);
+            listeners.add(target, "paste", move |e: ClipboardEvent| {
+                let Some(text) = e
+                    .clipboard_data()
+                    .and_then(|data| data.get_data("text/plain").ok())
+                else {
+                    return;
+                };
+                // Also keeps the text out of the hidden text field.
+                e.prevent_default();
+                if !text.is_empty() {
+                    shared.borrow_mut().handle_and_render(Msg::Paste(text));
+                }
+            });
+        }
+
+        // -- visibilitychange ------------------------------------------------
+        {
+            let shared = Rc::clone(&shared);
+            let lifeline = lifeline.clone();
+            listeners.add(&document(), "visibilitychange", move |_: web_sys::Event| {
+                if document().hidden() {
+                    keep_continuing(Rc::downgrade(&shared), lifeline.clone());
                 }
             });
-            // Listen on document (not just canvas) so keys are caught even
-            // when the canvas isn't focused, matching the Go driver.
-            document()
-                .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
-                .expect("addEventListener keydown");
-            closure.forget();
         }
 
         // -- mousedown -------------------------------------------------------
         {
             let shared = Rc::clone(&shared);
             let canvas_clone = canvas.clone();
-            let closure = Closure::<dyn FnMut(MouseEvent)>::new(move |e: MouseEvent| {
+            listeners.add(&canvas, "mousedown", move |e: MouseEvent| {
                 if e.ctrl_key() || e.meta_key() || e.shift_key() || e.alt_key() {
                     return;
                 }
@@ -378,34 +1480,23 @@ impl EventLoopDriver for WebDriver {
                     return;
                 }
                 let button = e.button();
-                let action = match button {
-                    0 => MouseAction::Main,
-                    1 => MouseAction::Auxiliary,
-                    2 => MouseAction::Secondary,
-                    _ => return,
+                let Some(action) = button_action(button as i32) else {
+                    return;
                 };
-                s.mouse_drag = button as i32;
                 let pos = s.mouse_to_cell(&e, &canvas_clone);
                 let mods = modifier_mask(e.shift_key(), e.ctrl_key(), e.alt_key(), e.meta_key());
-                let msg = Msg::Mouse {
-                    action,
-                    pos,
-                    modifiers: mods,
-                    time: now_instant(),
-                };
-                s.handle_and_render(msg);
+                if let Some(msg) = s.mouse.button(action, Some(pos), mods) {
+                    s.mouse_drag = button as i32;
+                    s.handle_and_render(msg);
+                }
             });
-            canvas
-                .add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref())
-                .expect("addEventListener mousedown");
-            closure.forget();
         }
 
         // -- mouseup ---------------------------------------------------------
         {
             let shared = Rc::clone(&shared);
             let canvas_clone = canvas.clone();
-            let closure = Closure::<dyn FnMut(MouseEvent)>::new(move |e: MouseEvent| {
+            listeners.add(&canvas, "mouseup", move |e: MouseEvent| {
                 if e.ctrl_key() || e.meta_key() || e.shift_key() || e.alt_key() {
                     return;
                 }
@@ -418,52 +1509,47 @@ impl EventLoopDriver for WebDriver {
                 s.mouse_drag = -1;
                 let pos = s.mouse_to_cell(&e, &canvas_clone);
                 let mods = modifier_mask(e.shift_key(), e.ctrl_key(), e.alt_key(), e.meta_key());
-                let msg = Msg::Mouse {
-                    action: MouseAction::Release,
-                    pos,
-                    modifiers: mods,
-                    time: now_instant(),
-                };
-                s.handle_and_render(msg);
+                if let Some(msg) = s.mouse.button(MouseAction::Release, Some(pos), mods) {
+                    s.handle_and_render(msg);
+                }
             });
-            canvas
-                .add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref())
-                .expect("addEventListener mouseup");
-            closure.forget();
         }
 
         // -- mousemove -------------------------------------------------------
         {
             let shared = Rc::clone(&shared);
             let canvas_clone = canvas.clone();
-            let closure = Closure::<dyn FnMut(MouseEvent)>::new(move |e: MouseEvent| {
+            // `mouseenter` needs no handler of its own: the `mousemove`
+            // that follows it reports `Enter` once over the grid.
+            listeners.add(&canvas, "mousemove", move |e: MouseEvent| {
                 e.prevent_default();
                 let mut s = shared.borrow_mut();
                 let pos = s.mouse_to_cell(&e, &canvas_clone);
-                if pos != s.mouse_pos {
-                    s.mouse_pos = pos;
-                    let mods =
-                        modifier_mask(e.shift_key(), e.ctrl_key(), e.alt_key(), e.meta_key());
-                    let msg = Msg::Mouse {
-                        action: MouseAction::Move,
-                        pos,
-                        modifiers: mods,
-                        time: now_instant(),
-                    };
+                let mods = modifier_mask(e.shift_key(), e.ctrl_key(), e.alt_key(), e.meta_key());
+                let held = button_action(s.mouse_drag);
+                for msg in s.mouse.dragged(pos, held, mods) {
+                    s.handle_and_render(msg);
+                }
+            });
+        }
+
+        // -- mouseleave ------------------------------------------------------
+        {
+            let shared = Rc::clone(&shared);
+            listeners.add(&canvas, "mouseleave", move |e: MouseEvent| {
+                let mut s = shared.borrow_mut();
+                let mods = modifier_mask(e.shift_key(), e.ctrl_key(), e.alt_key(), e.meta_key());
+                if let Some(msg) = s.mouse.left(mods) {
                     s.handle_and_render(msg);
                 }
             });
-            canvas
-                .add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())
-                .expect("addEventListener mousemove");
-            closure.forget();
         }
 
         // -- wheel -----------------------------------------------------------
         {
             let shared = Rc::clone(&shared);
             let canvas_clone = canvas.clone();
-            let closure = Closure::<dyn FnMut(WheelEvent)>::new(move |e: WheelEvent| {
+            listeners.add(&canvas, "wheel", move |e: WheelEvent| {
                 e.prevent_default();
                 let delta = e.delta_y();
                 let action = if delta > 0.0 {
@@ -478,37 +1564,141 @@ impl EventLoopDriver for WebDriver {
                 let mouse_evt: &MouseEvent = e.as_ref();
                 let pos = s.mouse_to_cell(mouse_evt, &canvas_clone);
                 let mods = modifier_mask(e.shift_key(), e.ctrl_key(), e.alt_key(), e.meta_key());
-                let msg = Msg::Mouse {
-                    action,
-                    pos,
-                    modifiers: mods,
-                    time: now_instant(),
+                if let Some(msg) = s.mouse.button(action, Some(pos), mods) {
+                    s.handle_and_render(msg);
+                }
+            });
+        }
+
+        // -- touchstart / touchmove / touchend / touchcancel -----------------
+        // Listeners are not passive, so that `preventDefault` stops the page
+        // from scrolling and the browser from emulating mouse events; CSS
+        // `touch-action` keeps it from panning or zooming meanwhile.
+        let _ = canvas.style().set_property("touch-action", "none");
+        let touch_options = AddEventListenerOptions::new();
+        touch_options.set_passive(false);
+        for event in ["touchstart", "touchmove", "touchend", "touchcancel"] {
+            let shared = Rc::clone(&shared);
+            let canvas_clone = canvas.clone();
+            listeners.add_with_options(&canvas, event, &touch_options, move |e: TouchEvent| {
+                e.prevent_default();
+                let mut s = shared.borrow_mut();
+                let fingers = e.touches().length();
+                let changed = e.changed_touches();
+                let events = match e.type_().as_str() {
+                    "touchstart" => {
+                        let Some(t) = changed.get(0) else {
+                            return;
+                        };
+                        let pos = s.client_to_cell(t.client_x(), t.client_y(), &canvas_clone);
+                        let (events, serial) = s.touch.start(t.identifier(), pos, fingers);
+                        if let Some(serial) = serial {
+                            let ms = s.config.long_press_ms;
+                            arm_long_press(Rc::downgrade(&shared), serial, ms);
+                        }
+                        events
+                    }
+                    "touchcancel" => s.touch.cancel(),
+                    kind => {
+                        // The finger followed, or any other for a touchend
+                        // that only counts the fingers left.
+                        let id = s.touch.id();
+                        let Some(t) = (0..changed.length())
+                            .filter_map(|i| changed.get(i))
+                            .find(|t| Some(t.identifier()) == id)
+                            .or_else(|| changed.get(0))
+                        else {
+                            return;
+                        };
+                        let pos = s.client_to_cell(t.client_x(), t.client_y(), &canvas_clone);
+                        if kind == "touchmove" {
+                            s.touch.moved(t.identifier(), pos)
+                        } else {
+                            // Focusing needs a user gesture, like this one.
+                            if let Some(ref input) = s.text_input {
+                                let options = FocusOptions::new();
+                                options.set_prevent_scroll(true);
+                                let _ = input.focus_with_options(&options);
+                            }
+                            s.touch.end(t.identifier(), pos, fingers)
+                        }
+                    }
                 };
-                s.handle_and_render(msg);
+                s.handle_touch(events);
             });
-            canvas
-                .add_event_listener_with_callback("wheel", closure.as_ref().unchecked_ref())
-                .expect("addEventListener wheel");
-            closure.forget();
+        }
+
+        // -- input / compositionend on the hidden text field -----------------
+        if let Some(input) = shared.borrow().text_input.clone() {
+            {
+                let shared = Rc::clone(&shared);
+                let field = input.clone();
+                listeners.add(&input, "input", move |e: InputEvent| {
+                    let keys =
+                        soft_keyboard::input_keys(&e.input_type(), e.data().as_deref(), e.is_composing());
+                    if !e.is_composing() {
+                        soft_keyboard::reset(&field);
+                    }
+                    shared.borrow_mut().handle_typed(keys);
+                });
+            }
+            {
+                let shared = Rc::clone(&shared);
+                let field = input.clone();
+                listeners.add(&input, "compositionend", move |e: CompositionEvent| {
+                    soft_keyboard::reset(&field);
+                    let keys = soft_keyboard::text_keys(&e.data().unwrap_or_default());
+                    shared.borrow_mut().handle_typed(keys);
+                });
+            }
         }
 
         // --- requestAnimationFrame loop -------------------------------------
         // We use a recurring rAF callback to process any pending background
         // messages and re-render.  Actual input handling happens eagerly in
         // the event-listener closures above, so the rAF loop mainly services
-        // Cmd/Sub feedback and keeps the display up to date.
+        // Cmd/Sub feedback and keeps the display up to date.  It also stops
+        // the driver, once the model quit or another driver started on the
+        // canvas.
         {
             let shared = Rc::clone(&shared);
+            let canvas = canvas.clone();
+            let mut listeners = Some(listeners);
             // The closure must own an Rc to itself so it can re-register.
-            let raf_cb: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
+            let raf_cb: RafCallback = Rc::new(RefCell::new(None));
             let raf_cb2 = Rc::clone(&raf_cb);
 
             *raf_cb.borrow_mut() = Some(Closure::<dyn FnMut()>::new(move || {
                 {
                     let mut s = shared.borrow_mut();
                     if s.runner.should_quit() {
+                        lifeline.cut(EndCause::Quit);
+                    }
+                    if let Some(cause) = lifeline.cause() {
+                        s.runner.shutdown();
+                        let ended = cause == EndCause::Quit;
+                        if ended {
+                            s.draw_end(&canvas);
+                        }
+                        let stop = Stop {
+                            listeners: listeners.take(),
+                            raf: raf_cb2.borrow_mut().take(),
+                            text_input: s.text_input.take(),
+                            notify: ended
+                                .then(|| (canvas.clone(), s.config.on_end_callback.clone())),
+                        };
+                        // This closure cannot be dropped while it runs.
+                        let callback = Closure::once_into_js(move || stop.run());
+                        let _ = window().set_timeout_with_callback_and_timeout_and_arguments_0(
+                            callback.unchecked_ref(),
+                            0,
+                        );
                         return; // stop the rAF loop
                     }
+                    s.follow_keyboard(&canvas, js_sys::Date::now());
+                    if s.relayout_pending {
+                        s.relayout(&canvas);
+                    }
                     s.render();
                 }
                 // Schedule the next frame.
@@ -519,21 +1709,305 @@ impl EventLoopDriver for WebDriver {
             }));
 
             // Kick off the first frame.
-            {
-                let cb_ref = raf_cb.borrow();
-                if let Some(cb) = cb_ref.as_ref() {
-                    window()
-                        .request_animation_frame(cb.as_ref().unchecked_ref())
-                        .expect("requestAnimationFrame");
+            let cb_ref = raf_cb.borrow();
+            if let Some(cb) = cb_ref.as_ref() {
+                window()
+                    .request_animation_frame(cb.as_ref().unchecked_ref())
+                    .expect("requestAnimationFrame");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn capabilities(&self) -> DriverCaps {
+        if let Some(manager) = &self.config.tile_manager {
+            let (w, h) = manager.tile_size();
+            return web_caps(w as f64, h as f64, true);
+        }
+        // Until the font is measured: monospace glyphs are about 0.6 em
+        // wide, and cells 1.2 em high.
+        let size = self.config.font_size;
+        web_caps(0.6 * size, 1.2 * size, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn data_attributes_override_config() {
+        let mut config = WebConfig::default();
+        let warnings = config.apply_data_attributes([
+            ("id", "gruid-canvas"),
+            ("data-gruid-font-size", "20.5"),
+            ("data-gruid-font-family", " 'Fira Mono', monospace "),
+            ("data-gruid-width", "100"),
+            ("data-gruid-height", " 30"),
+            ("data-gruid-fit-parent", ""),
+            ("data-gruid-long-press-ms", "300"),
+            ("data-gruid-capture-text-input", "true"),
+            ("data-gruid-keyboard-behavior", "shrink-rows"),
+        ]);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(config.long_press_ms, 300);
+        assert!(config.capture_text_input);
+        assert_eq!(config.keyboard_behavior, KeyboardBehavior::ShrinkRows);
+        assert_eq!(config.font_size, 20.5);
+        assert_eq!(config.font_family, "'Fira Mono', monospace");
+        assert_eq!((config.width, config.height), (100, 30));
+        assert!(config.fit_parent);
+        assert_eq!(config.canvas_id, "gruid-canvas");
+        assert_eq!(config.max_cells, WebConfig::default().max_cells);
+    }
+
+    #[test]
+    fn invalid_data_attributes_are_ignored() {
+        let mut config = WebConfig::default();
+        let warnings = config.apply_data_attributes([
+            ("data-gruid-width", "wide"),
+            ("data-gruid-height", "-3"),
+            ("data-gruid-font-size", "inf"),
+            ("data-gruid-font-family", "  "),
+            ("data-gruid-fit-parent", "yes"),
+            ("data-gruid-max-cells", "0"),
+            ("data-gruid-canvas-id", "other"),
+            ("data-gruid-speed", "2"),
+            ("data-other", "x"),
+        ]);
+        assert_eq!(warnings.len(), 8, "{warnings:?}");
+        assert!(warnings[0].contains("data-gruid-width"));
+        assert!(warnings[7].contains("unknown attribute data-gruid-speed"));
+        let default = WebConfig::default();
+        assert_eq!(
+            (config.width, config.height),
+            (default.width, default.height)
+        );
+        assert_eq!(config.font_size, default.font_size);
+        assert_eq!(config.font_family, default.font_family);
+        assert_eq!(config.canvas_id, default.canvas_id);
+        assert!(!config.fit_parent);
+    }
+
+    #[test]
+    fn keyboard_events_keep_repeat_flag() {
+        let msg = keyboard_msg("ArrowLeft", "ArrowLeft", false, true, false).unwrap();
+        assert!(
+            matches!(
+                msg,
+                Msg::KeyDown {
+                    key: Key::ArrowLeft,
+                    ..
+                }
+            ) && msg.is_repeat()
+        );
+        let msg = keyboard_msg("a", "KeyA", false, false, false).unwrap();
+        assert!(
+            matches!(
+                msg,
+                Msg::KeyDown {
+                    key: Key::Char('a'),
+                    ..
                 }
+            ) && !msg.is_repeat()
+        );
+        match keyboard_msg("A", "KeyA", true, false, true) {
+            Some(Msg::KeyUp { key, modifiers, .. }) => {
+                assert_eq!((key, modifiers), (Key::Char('A'), ModMask::SHIFT));
+            }
+            m => panic!("{m:?}"),
+        }
+        assert!(keyboard_msg("Shift", "ShiftLeft", true, false, false).is_none());
+    }
+
+    #[test]
+    fn color_scheme_query_to_theme() {
+        assert_eq!(theme_of(true), SystemTheme::Dark);
+        assert_eq!(theme_of(false), SystemTheme::Light);
+    }
+
+    #[test]
+    fn border_and_unused_canvas_map_outside_grid() {
+        // 10px x 20px cells behind the 1px border.
+        assert_eq!(pixel_to_cell(1.0, 1.0, 1.0, 10.0, 20.0), Point::new(0, 0));
+        assert_eq!(pixel_to_cell(30.5, 41.0, 1.0, 10.0, 20.0), Point::new(2, 2));
+        assert_eq!(pixel_to_cell(0.5, 5.0, 1.0, 10.0, 20.0), Point::new(-1, 0));
+
+        // A 3x2 grid: the right-hand strip of a wider canvas is not a cell.
+        let mut mouse = MouseTracker::new(3, 2);
+        let moved = |mouse: &mut MouseTracker, x, y| -> Vec<MouseAction> {
+            mouse
+                .moved(pixel_to_cell(x, y, 1.0, 10.0, 20.0), ModMask::NONE)
+                .map(|m| match m {
+                    Msg::Mouse { action, .. } => action,
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+        assert_eq!(
+            moved(&mut mouse, 25.0, 30.0),
+            [MouseAction::Enter, MouseAction::Move]
+        );
+        assert_eq!(moved(&mut mouse, 35.0, 30.0), [MouseAction::Leave]);
+        assert!(moved(&mut mouse, 36.0, 30.0).is_empty());
+        assert!(mouse.left(ModMask::NONE).is_none());
+    }
+
+    #[test]
+    fn moves_with_button_down_are_drags() {
+        assert_eq!(button_action(0), Some(MouseAction::Main));
+        assert_eq!(button_action(1), Some(MouseAction::Auxiliary));
+        assert_eq!(button_action(2), Some(MouseAction::Secondary));
+        assert_eq!(button_action(3), None);
+
+        let mut mouse = MouseTracker::new(3, 2);
+        let mut moved = |x, drag| -> Vec<MouseAction> {
+            let pos = pixel_to_cell(x, 30.0, 1.0, 10.0, 20.0);
+            mouse
+                .dragged(pos, button_action(drag), ModMask::NONE)
+                .map(|m| match m {
+                    Msg::Mouse { action, .. } => action,
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+        assert_eq!(moved(5.0, -1), [MouseAction::Enter, MouseAction::Move]);
+        assert_eq!(moved(15.0, 0), [MouseAction::MainDrag]);
+        assert_eq!(moved(25.0, 2), [MouseAction::SecondaryDrag]);
+        assert_eq!(moved(15.0, -1), [MouseAction::Move]);
+    }
+
+    #[test]
+    fn clamp_keeps_aspect_within_budget() {
+        assert_eq!(clamp_cells(80, 24, 40_000), (80, 24));
+        assert_eq!(clamp_cells(200, 200, 40_000), (200, 200));
+
+        // A zoomed-out page asking for 1000x400.
+        let (c, r) = clamp_cells(1000, 400, 40_000);
+        assert!(c * r <= 40_000, "{c}x{r}");
+        assert_eq!((c, r), (316, 126));
+
+        // Very thin grids and degenerate budgets still get a cell.
+        let (c, r) = clamp_cells(1, 100_000, 50);
+        assert_eq!((c, r), (1, 50));
+        assert_eq!(clamp_cells(30, 20, 0), (1, 1));
+    }
+
+    #[test]
+    fn geometry_follows_dpr_and_css_size() {
+        // Fixed grid: the backing store scales with the cells.
+        assert_eq!(
+            fixed_geometry(80, 24, (10.0, 20.0)),
+            Geometry {
+                cols: 80,
+                rows: 24,
+                backing: (800, 480)
             }
+        );
+        assert_eq!(fixed_geometry(80, 24, (12.5, 25.0)).backing, (1000, 600));
+
+        // Fit mode: the grid follows the CSS size, not the zoom.
+        let at_1x = fit_geometry((805.0, 490.0), 1.0, (10.0, 20.0), 40_000);
+        assert_eq!((at_1x.cols, at_1x.rows, at_1x.backing), (80, 24, (805, 490)));
+        let at_2x = fit_geometry((805.0, 490.0), 2.0, (20.0, 40.0), 40_000);
+        assert_eq!((at_2x.cols, at_2x.rows, at_2x.backing), (80, 24, (1610, 980)));
 
-            // Leak the closure so it lives for the page lifetime.
-            // (We already `.forget()` the event listeners; the rAF closure
-            // must also be leaked since there's no teardown path in WASM.)
-            std::mem::forget(raf_cb);
+        // Shrinking the window drops the partial cells.
+        let narrow = fit_geometry((399.0, 490.0), 1.0, (10.0, 20.0), 40_000);
+        assert_eq!((narrow.cols, narrow.rows), (39, 24));
+
+        // Zooming out to 25% makes cells tiny: the clamp kicks in.
+        let out = fit_geometry((4000.0, 1600.0), 1.0, (3.0, 5.0), 40_000);
+        assert!(out.cols * out.rows <= 40_000);
+        assert_eq!(out.backing, (4000, 1600));
+
+        // An empty canvas still yields a 1x1 grid.
+        let empty = fit_geometry((0.0, 0.0), 1.0, (10.0, 20.0), 40_000);
+        assert_eq!((empty.cols, empty.rows), (1, 1));
+    }
+
+    #[test]
+    fn hit_testing_survives_dpr_change() {
+        // A 3x2 grid of 10x20 CSS pixel cells, before and after the page is
+        // zoomed to 150% and 200%: the same CSS point hits the same cell.
+        for (dpr, cell) in [(1.0, (10.0, 20.0)), (1.5, (15.0, 30.0)), (2.0, (20.0, 40.0))] {
+            let geom = fixed_geometry(3, 2, cell);
+            let css_w = geom.backing.0 as f64 / dpr;
+            let scale = geom.backing.0 as f64 / css_w;
+            let hit = |x, y| pixel_to_cell(x, y, scale, cell.0, cell.1);
+            assert_eq!(hit(1.0, 1.0), Point::new(0, 0), "dpr {dpr}");
+            assert_eq!(hit(25.0, 30.0), Point::new(2, 1), "dpr {dpr}");
+            assert_eq!(hit(31.5, 30.0), Point::new(3, 1), "dpr {dpr}");
+            assert_eq!(hit(0.5, 5.0), Point::new(-1, 0), "dpr {dpr}");
         }
+    }
 
-        Ok(())
+    /// Headless: `wasm-pack test --headless --firefox`.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn hit_testing_on_a_2x_canvas() {
+        // A 3x2 grid of 10x20 CSS pixel cells at a device pixel ratio of 2.
+        let canvas: HtmlCanvasElement = document()
+            .create_element("canvas")
+            .unwrap()
+            .dyn_into()
+            .unwrap();
+        let geom = fixed_geometry(3, 2, (20.0, 40.0));
+        canvas.set_width(geom.backing.0);
+        canvas.set_height(geom.backing.1);
+        let style = canvas.style();
+        style.set_property("width", "30px").unwrap();
+        style.set_property("height", "40px").unwrap();
+        style.set_property("border", "1px solid").unwrap();
+        document().body().unwrap().append_child(&canvas).unwrap();
+
+        let scale = canvas_scale(&canvas, 1.0);
+        assert_eq!(scale, 2.0);
+        let rect = canvas.get_bounding_client_rect();
+        assert_eq!(rect.width(), 32.0, "border box");
+        let hit = |x, y| pixel_to_cell(x, y, scale, 20.0, 40.0);
+        assert_eq!(hit(1.0, 1.0), Point::new(0, 0));
+        assert_eq!(hit(25.0, 30.0), Point::new(2, 1));
+        assert_eq!(hit(31.5, 30.0), Point::new(3, 1));
+        canvas.remove();
+    }
+
+    #[test]
+    fn capabilities_describe_a_browser() {
+        let caps = WebDriver::new(WebConfig::default()).capabilities();
+        assert_eq!(caps.platform, Platform::Web);
+        assert_eq!(caps.cell_aspect, 0.5);
+        assert!(caps.mouse && caps.unicode);
+        assert!(!caps.animation_ticks && !caps.tiles && caps.clipboard && caps.key_release);
+        assert_eq!(web_caps(10.0, 20.0, false).cell_aspect, 0.5);
+        assert_eq!(web_caps(16.0, 16.0, false).cell_aspect, 1.0);
+    }
+
+    struct Square;
+
+    impl TileManager for Square {
+        fn tile_size(&self) -> (usize, usize) {
+            (8, 16)
+        }
+
+        fn get_tile(&self, _cell: &gruid_core::Cell) -> Option<&[u8]> {
+            None
+        }
+    }
+
+    #[test]
+    fn tiles_set_the_cell_shape() {
+        let config = WebConfig {
+            tile_manager: Some(Rc::new(Square)),
+            ..WebConfig::default()
+        };
+        let caps = WebDriver::new(config.clone()).capabilities();
+        assert!(caps.tiles);
+        assert_eq!(caps.cell_aspect, 0.5);
+        assert!(format!("{config:?}").contains("tile_manager: Some"));
     }
 }
@@ -0,0 +1,211 @@
+//! The end of a running driver.
+//!
+//! The event listeners of a driver share a [`Lifeline`] and ignore their
+//! events once it is cut: when the model returns
+//! [`Effect::End`](gruid_core::Effect::End), or when another driver starts
+//! on the same canvas. The animation frame loop then removes them with
+//! their [`Listeners`], which drops the closures and with them the driver
+//! state they hold.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::convert::FromWasmAbi;
+use wasm_bindgen::prelude::*;
+use web_sys::{AddEventListenerOptions, Element, EventTarget, ResizeObserver};
+
+/// Why a driver stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EndCause {
+    /// The model quit.
+    Quit,
+    /// Another driver started on the same canvas.
+    Replaced,
+}
+
+/// Whether a driver still handles events, shared by its listeners.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Lifeline(Rc<Cell<Option<EndCause>>>);
+
+impl Lifeline {
+    pub(crate) fn is_alive(&self) -> bool {
+        self.0.get().is_none()
+    }
+
+    /// Why the lifeline was cut, if it was.
+    pub(crate) fn cause(&self) -> Option<EndCause> {
+        self.0.get()
+    }
+
+    /// Cut the lifeline for `cause`. Returns `false`, keeping the first
+    /// cause, if it was already cut.
+    pub(crate) fn cut(&self, cause: EndCause) -> bool {
+        let alive = self.is_alive();
+        if alive {
+            self.0.set(Some(cause));
+        }
+        alive
+    }
+
+    /// `f`, doing nothing once the lifeline is cut.
+    pub(crate) fn guard<E, F: FnMut(E)>(&self, mut f: F) -> impl FnMut(E) + use<E, F> {
+        let lifeline = self.clone();
+        move |e| {
+            if lifeline.is_alive() {
+                f(e);
+            }
+        }
+    }
+}
+
+/// The lifelines of the running drivers, by canvas id.
+#[derive(Debug, Default)]
+pub(crate) struct Instances(Vec<(String, Lifeline)>);
+
+impl Instances {
+    /// Record a driver starting on the canvas with id `canvas_id`, cutting
+    /// the lifeline of the one running there before, if any.
+    pub(crate) fn start(&mut self, canvas_id: &str, lifeline: Lifeline) {
+        self.0.retain(|(id, other)| {
+            if id == canvas_id {
+                other.cut(EndCause::Replaced);
+            }
+            other.is_alive()
+        });
+        self.0.push((canvas_id.to_string(), lifeline));
+    }
+}
+
+/// The event listeners and observers of a driver, guarded by its
+/// lifeline and removed together.
+pub(crate) struct Listeners {
+    lifeline: Lifeline,
+    added: Vec<(EventTarget, &'static str, Box<dyn AsRef<JsValue>>)>,
+    observers: Vec<(ResizeObserver, Closure<dyn FnMut()>)>,
+}
+
+impl Listeners {
+    pub(crate) fn new(lifeline: Lifeline) -> Self {
+        Self {
+            lifeline,
+            added: Vec::new(),
+            observers: Vec::new(),
+        }
+    }
+
+    /// Call `f` with the `event` events of `target` while the lifeline
+    /// holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the browser refuses the listener.
+    pub(crate) fn add<E: FromWasmAbi + 'static>(
+        &mut self,
+        target: &EventTarget,
+        event: &'static str,
+        f: impl FnMut(E) + 'static,
+    ) {
+        self.add_with_options(target, event, &AddEventListenerOptions::new(), f);
+    }
+
+    /// [`add`](Self::add), with `options`.
+    pub(crate) fn add_with_options<E: FromWasmAbi + 'static>(
+        &mut self,
+        target: &EventTarget,
+        event: &'static str,
+        options: &AddEventListenerOptions,
+        f: impl FnMut(E) + 'static,
+    ) {
+        let closure = Closure::<dyn FnMut(E)>::new(self.lifeline.guard(f));
+        target
+            .add_event_listener_with_callback_and_add_event_listener_options(
+                event,
+                closure.as_ref().unchecked_ref(),
+                options,
+            )
+            .unwrap_or_else(|_| panic!("addEventListener {event}"));
+        self.added.push((target.clone(), event, Box::new(closure)));
+    }
+
+    /// Call `f` when the size of `target` changes while the lifeline
+    /// holds, if the browser has `ResizeObserver`.
+    pub(crate) fn observe_resize(&mut self, target: &Element, mut f: impl FnMut() + 'static) {
+        let lifeline = self.lifeline.clone();
+        let closure = Closure::<dyn FnMut()>::new(move || {
+            if lifeline.is_alive() {
+                f();
+            }
+        });
+        if let Ok(observer) = ResizeObserver::new(closure.as_ref().unchecked_ref()) {
+            observer.observe(target);
+            self.observers.push((observer, closure));
+        }
+    }
+
+    /// Remove the listeners and disconnect the observers, dropping their
+    /// closures.
+    pub(crate) fn remove(self) {
+        for (target, event, closure) in &self.added {
+            let callback = (**closure).as_ref().unchecked_ref();
+            let _ = target.remove_event_listener_with_callback(event, callback);
+        }
+        for (observer, _) in &self.observers {
+            observer.disconnect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guarded_closures_stop_when_cut() {
+        let lifeline = Lifeline::default();
+        let mut seen = Vec::new();
+        {
+            let mut f = lifeline.guard(|e: i32| seen.push(e));
+            f(1);
+            f(2);
+            assert!(lifeline.cut(EndCause::Quit));
+            f(3);
+        }
+        assert_eq!(seen, [1, 2]);
+        assert!(!lifeline.is_alive());
+        // The first cause is kept.
+        assert!(!lifeline.cut(EndCause::Replaced));
+        assert_eq!(lifeline.cause(), Some(EndCause::Quit));
+    }
+
+    #[test]
+    fn clones_share_the_lifeline() {
+        let lifeline = Lifeline::default();
+        let listener = lifeline.clone();
+        assert!(listener.is_alive());
+        lifeline.cut(EndCause::Replaced);
+        assert_eq!(listener.cause(), Some(EndCause::Replaced));
+    }
+
+    #[test]
+    fn starting_on_a_canvas_replaces_its_driver() {
+        let mut instances = Instances::default();
+        let (first, other) = (Lifeline::default(), Lifeline::default());
+        instances.start("game", first.clone());
+        instances.start("map", other.clone());
+        assert!(first.is_alive() && other.is_alive());
+
+        let second = Lifeline::default();
+        instances.start("game", second.clone());
+        assert_eq!(first.cause(), Some(EndCause::Replaced));
+        assert!(second.is_alive() && other.is_alive());
+
+        // Drivers that ended are forgotten, and a restart cuts nothing.
+        second.cut(EndCause::Quit);
+        let third = Lifeline::default();
+        instances.start("game", third.clone());
+        assert_eq!(second.cause(), Some(EndCause::Quit));
+        assert!(third.is_alive());
+        assert_eq!(instances.0.len(), 2);
+    }
+}
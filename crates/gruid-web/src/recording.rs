@@ -0,0 +1,269 @@
+//! Session recording in memory, for the page to download.
+//!
+//! The frames the driver paints are kept as they are and only encoded with
+//! a [`FrameEncoder`] when the recording is retrieved. Frames are diffs, so
+//! dropping the oldest one to stay within the [`RecordingLimits`] folds
+//! its cells into the next one, which then shows the whole screen: what is
+//! left still replays from a complete first frame.
+
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+
+use gruid_core::grid::{Frame, FrameCell};
+use gruid_core::recording::{FrameEncoder, RecordingMeta};
+
+/// Estimated encoded size of a frame, without cells: its length prefix and
+/// header. Styles, written once per stream, are not counted.
+const FRAME_BYTES: usize = 28;
+/// Encoded size of a cell.
+const CELL_BYTES: usize = 14;
+
+fn frame_bytes(frame: &Frame) -> usize {
+    FRAME_BYTES + CELL_BYTES * frame.cells.len()
+}
+
+/// Bounds on the memory a [`WebRecording`] uses. The oldest frames are
+/// dropped when a limit is exceeded, but the latest one is always kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingLimits {
+    /// Most frames kept (default: `None`, no limit).
+    pub max_frames: Option<usize>,
+    /// Most bytes kept, as estimated from the encoded size of the frames
+    /// (default: 16 MiB).
+    pub max_bytes: Option<usize>,
+}
+
+impl Default for RecordingLimits {
+    fn default() -> Self {
+        Self {
+            max_frames: None,
+            max_bytes: Some(16 << 20),
+        }
+    }
+}
+
+/// The frames kept, oldest first.
+#[derive(Debug, Default)]
+struct Recorder {
+    limits: RecordingLimits,
+    meta: Option<RecordingMeta>,
+    frames: VecDeque<Frame>,
+    /// Estimated encoded size of `frames`.
+    bytes: usize,
+    /// Time of the first frame ever pushed, in milliseconds, from which
+    /// frame times are counted.
+    start_ms: Option<f64>,
+}
+
+impl Recorder {
+    fn push(&mut self, frame: &Frame, now_ms: f64) {
+        if frame.cells.is_empty() {
+            return;
+        }
+        let start = *self.start_ms.get_or_insert(now_ms);
+        let mut frame = frame.clone();
+        frame.time_ms = (now_ms - start).max(0.0) as u64;
+        self.bytes += frame_bytes(&frame);
+        self.frames.push_back(frame);
+        while self.frames.len() > 1 && self.over_limits() {
+            self.evict();
+        }
+    }
+
+    fn over_limits(&self) -> bool {
+        self.limits.max_frames.is_some_and(|n| self.frames.len() > n)
+            || self.limits.max_bytes.is_some_and(|n| self.bytes > n)
+    }
+
+    /// Drop the oldest frame, moving the cells the next one does not
+    /// cover into it.
+    fn evict(&mut self) {
+        let Some(old) = self.frames.pop_front() else {
+            return;
+        };
+        self.bytes -= frame_bytes(&old);
+        let Some(next) = self.frames.front_mut() else {
+            return;
+        };
+        self.bytes -= frame_bytes(next);
+        let covered: HashSet<_> = next.cells.iter().map(|c| c.pos).collect();
+        let (w, h) = (next.width, next.height);
+        let mut cells: Vec<FrameCell> = old
+            .cells
+            .into_iter()
+            .filter(|c| {
+                !covered.contains(&c.pos)
+                    && (0..w).contains(&c.pos.x)
+                    && (0..h).contains(&c.pos.y)
+            })
+            .collect();
+        cells.append(&mut next.cells);
+        next.cells = cells;
+        self.bytes += frame_bytes(next);
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut encoder = FrameEncoder::new(Vec::new());
+        if let Some(meta) = &self.meta {
+            encoder = encoder.with_metadata(meta.clone());
+        }
+        for frame in &self.frames {
+            // Writing to a Vec cannot fail.
+            let _ = encoder.encode(frame);
+        }
+        encoder.into_inner()
+    }
+}
+
+/// A recording of what a [`WebDriver`](crate::WebDriver) draws, kept in
+/// memory within some [`RecordingLimits`].
+///
+/// Set it as [`WebConfig::recording`](crate::WebConfig::recording) and
+/// keep a clone to get the recording with [`bytes`](Self::bytes), in the
+/// format of [`FrameDecoder`](gruid_core::recording::FrameDecoder). The
+/// page can also get the recording of the running driver from JavaScript
+/// with the exported `gruidRecording()` function, as a `Uint8Array`, to
+/// offer it for download. Frame times are those of the browser clock, from
+/// the first frame. Events recorded with
+/// [`RecordEvent`](gruid_core::app::RecordEvent) are not kept.
+#[derive(Clone, Debug, Default)]
+pub struct WebRecording(Rc<RefCell<Recorder>>);
+
+impl WebRecording {
+    /// An empty recording within `limits`.
+    pub fn new(limits: RecordingLimits) -> Self {
+        Self(Rc::new(RefCell::new(Recorder {
+            limits,
+            ..Recorder::default()
+        })))
+    }
+
+    /// Write `meta` at the start of the recording. Build it without
+    /// [`RecordingMeta::new`], which reads the system clock that
+    /// `wasm32-unknown-unknown` does not have: `js_sys::Date::now()` gives
+    /// the start time instead.
+    pub fn with_metadata(self, meta: RecordingMeta) -> Self {
+        self.0.borrow_mut().meta = Some(meta);
+        self
+    }
+
+    /// The frames kept, encoded.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.0.borrow().encode()
+    }
+
+    /// Number of frames kept.
+    pub fn len(&self) -> usize {
+        self.0.borrow().frames.len()
+    }
+
+    /// Whether no frame was kept yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop the frames kept. The next frame starts the recording again,
+    /// and is a complete screen only after a redraw of the whole grid.
+    pub fn clear(&self) {
+        let mut r = self.0.borrow_mut();
+        r.frames.clear();
+        r.bytes = 0;
+        r.start_ms = None;
+    }
+
+    /// Add a frame drawn at `now_ms`, in milliseconds of any clock.
+    pub(crate) fn push(&self, frame: &Frame, now_ms: f64) {
+        self.0.borrow_mut().push(frame, now_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::recording::FrameDecoder;
+    use gruid_core::{Cell, Point};
+
+    fn frame(cells: &[(i32, i32, char)]) -> Frame {
+        Frame {
+            cells: cells
+                .iter()
+                .map(|&(x, y, ch)| FrameCell {
+                    pos: Point::new(x, y),
+                    cell: Cell::default().with_char(ch),
+                })
+                .collect(),
+            width: 3,
+            height: 1,
+            time_ms: 0,
+            cursor: None,
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Vec<Frame> {
+        let mut dec = FrameDecoder::new(bytes);
+        std::iter::from_fn(|| dec.decode().unwrap()).collect()
+    }
+
+    fn chars(frame: &Frame) -> String {
+        let mut cells = frame.cells.clone();
+        cells.sort_by_key(|c| c.pos.x);
+        cells.iter().map(|c| c.cell.ch).collect()
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let rec = WebRecording::new(RecordingLimits::default())
+            .with_metadata(RecordingMeta::new("test", "1"));
+        rec.push(&frame(&[(0, 0, 'a'), (1, 0, 'b'), (2, 0, 'c')]), 1000.0);
+        rec.push(&frame(&[]), 1010.0);
+        rec.push(&frame(&[(1, 0, 'x')]), 1050.0);
+        assert_eq!(rec.len(), 2);
+
+        let bytes = rec.bytes();
+        let frames = decode(&bytes);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(chars(&frames[0]), "abc");
+        assert_eq!(frames[0].time_ms, 0);
+        assert_eq!(chars(&frames[1]), "x");
+        assert_eq!(frames[1].time_ms, 50);
+        let dec = FrameDecoder::new(&bytes[..]);
+        assert_eq!(dec.metadata().unwrap().app_name, "test");
+
+        rec.clear();
+        assert!(rec.is_empty());
+        assert!(decode(&rec.bytes()).is_empty());
+    }
+
+    #[test]
+    fn eviction_keeps_a_complete_first_frame() {
+        let rec = WebRecording::new(RecordingLimits {
+            max_frames: Some(2),
+            max_bytes: None,
+        });
+        rec.push(&frame(&[(0, 0, 'a'), (1, 0, 'b'), (2, 0, 'c')]), 0.0);
+        rec.push(&frame(&[(1, 0, 'x')]), 10.0);
+        rec.push(&frame(&[(2, 0, 'y')]), 20.0);
+        let frames = decode(&rec.bytes());
+        assert_eq!(frames.len(), 2);
+        assert_eq!(chars(&frames[0]), "axc");
+        assert_eq!(frames[0].time_ms, 10);
+        assert_eq!(chars(&frames[1]), "y");
+    }
+
+    #[test]
+    fn byte_limit_keeps_the_latest_frame() {
+        let full = frame(&[(0, 0, 'a'), (1, 0, 'b'), (2, 0, 'c')]);
+        let rec = WebRecording::new(RecordingLimits {
+            max_frames: None,
+            max_bytes: Some(frame_bytes(&full) + 1),
+        });
+        rec.push(&full, 0.0);
+        assert_eq!(rec.len(), 1);
+        rec.push(&frame(&[(0, 0, 'x')]), 10.0);
+        // Merged, the second frame alone fills the budget.
+        assert_eq!(rec.len(), 1);
+        assert_eq!(chars(&decode(&rec.bytes())[0]), "xbc");
+        assert_eq!(rec.0.borrow().bytes, frame_bytes(&full));
+    }
+}
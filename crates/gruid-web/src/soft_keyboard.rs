@@ -0,0 +1,123 @@
+//! A hidden `<input>` for the virtual keyboards of touch devices.
+//!
+//! Phones only show their keyboard for a focused text field, so with
+//! [`WebConfig::capture_text_input`](crate::WebConfig::capture_text_input)
+//! the driver adds an offscreen one and focuses it when the canvas is
+//! tapped. What is typed into it arrives as `input` events, or as a
+//! `compositionend` once an input method finishes a word, and becomes key
+//! messages; the field is then emptied again. Named keys such as Enter,
+//! Backspace or the arrows still come as `keydown` events, when the
+//! virtual keyboard sends them.
+//!
+//! The field is only focused by touches, so on desktop keys keep going to
+//! the grid's document-level handlers as before.
+
+use gruid_core::messages::Key;
+use wasm_bindgen::JsCast;
+use web_sys::{Document, HtmlInputElement};
+
+/// What the field holds between events: one character, so that Backspace
+/// on an empty-looking field still has something to delete, and fires an
+/// `input` event.
+const FILLER: &str = " ";
+
+/// Create the hidden field and add it to the page.
+pub(crate) fn create(document: &Document) -> Option<HtmlInputElement> {
+    let input: HtmlInputElement = document.create_element("input").ok()?.dyn_into().ok()?;
+    input.set_type("text");
+    for (name, value) in [
+        ("autocapitalize", "off"),
+        ("autocomplete", "off"),
+        ("autocorrect", "off"),
+        ("spellcheck", "false"),
+        ("aria-hidden", "true"),
+        ("tabindex", "-1"),
+    ] {
+        let _ = input.set_attribute(name, value);
+    }
+    // Offscreen rather than `display: none`, which cannot be focused. A
+    // font size of 16px stops iOS from zooming in on focus.
+    let _ = input.set_attribute(
+        "style",
+        "position: absolute; left: -10000px; top: 0; width: 1px; height: 1px; \
+         opacity: 0; font-size: 16px",
+    );
+    document.body()?.append_child(&input).ok()?;
+    reset(&input);
+    Some(input)
+}
+
+/// Empty the field again, with the caret after the filler.
+pub(crate) fn reset(input: &HtmlInputElement) {
+    input.set_value(FILLER);
+    let end = FILLER.len() as u32;
+    let _ = input.set_selection_range(end, end);
+}
+
+/// The keys for an `input` event of `input_type` carrying `data`. Text
+/// being composed is left for the `compositionend` event.
+pub(crate) fn input_keys(input_type: &str, data: Option<&str>, composing: bool) -> Vec<Key> {
+    if composing {
+        return Vec::new();
+    }
+    match input_type {
+        "insertText" | "insertReplacementText" => text_keys(data.unwrap_or_default()),
+        "deleteContentBackward" => vec![Key::Backspace],
+        "deleteContentForward" => vec![Key::Delete],
+        "insertLineBreak" | "insertParagraph" => vec![Key::Enter],
+        _ => Vec::new(),
+    }
+}
+
+/// The keys typing `text`, as on a physical keyboard.
+pub(crate) fn text_keys(text: &str) -> Vec<Key> {
+    text.chars()
+        .map(|c| match c {
+            ' ' => Key::Space,
+            '\n' => Key::Enter,
+            '\t' => Key::Tab,
+            c => Key::Char(c),
+        })
+        .collect()
+}
+
+/// Whether a key event with `KeyboardEvent.key` set to `key`, fired on the
+/// field, is left to the field's `input` events: characters and the
+/// placeholders virtual keyboards send for them.
+pub(crate) fn left_to_input(key: &str) -> bool {
+    key.chars().count() == 1 || matches!(key, "Unidentified" | "Process" | "Dead")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_events_to_keys() {
+        assert_eq!(
+            input_keys("insertText", Some("a b"), false),
+            [Key::Char('a'), Key::Space, Key::Char('b')]
+        );
+        assert_eq!(
+            input_keys("deleteContentBackward", None, false),
+            [Key::Backspace]
+        );
+        assert_eq!(input_keys("insertLineBreak", None, false), [Key::Enter]);
+        // Composition is delivered by compositionend.
+        assert_eq!(input_keys("insertCompositionText", Some("wo"), true), []);
+        assert_eq!(input_keys("insertText", Some("x"), true), []);
+        assert_eq!(text_keys("wörd"), "wörd".chars().map(Key::Char).collect::<Vec<_>>());
+        assert_eq!(input_keys("historyUndo", None, false), []);
+    }
+
+    #[test]
+    fn only_named_keys_bypass_the_field() {
+        assert!(left_to_input("a"));
+        assert!(left_to_input("é"));
+        assert!(left_to_input(" "));
+        assert!(left_to_input("Unidentified"));
+        assert!(!left_to_input("Enter"));
+        assert!(!left_to_input("Backspace"));
+        assert!(!left_to_input("ArrowLeft"));
+    }
+}
@@ -0,0 +1,212 @@
+//! Tiles from a [`TileManager`], drawn instead of text.
+//!
+//! Tiles are monochrome bitmaps, colourised with each cell's foreground and
+//! background. Each distinct cell is colourised once, into a slot of an
+//! offscreen atlas canvas, and then copied from there with `drawImage`;
+//! cells without a tile are drawn as text.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gruid_core::{Cell, TileManager, style::Color};
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::batch;
+
+/// Slots per side of the atlas. When all are used the atlas starts over:
+/// tiles already on screen stay, and new ones take the slots back.
+const ATLAS_SIDE: usize = 32;
+
+/// Atlas slots of the cells colourised so far.
+#[derive(Debug)]
+pub(crate) struct Slots {
+    index: HashMap<Cell, usize>,
+    capacity: usize,
+}
+
+impl Slots {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            index: HashMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// The slot of `cell`, and whether it must be colourised first.
+    pub fn get(&mut self, cell: Cell) -> (usize, bool) {
+        if let Some(&slot) = self.index.get(&cell) {
+            return (slot, false);
+        }
+        if self.index.len() >= self.capacity {
+            self.index.clear();
+        }
+        let slot = self.index.len();
+        self.index.insert(cell, slot);
+        (slot, true)
+    }
+
+    pub fn clear(&mut self) {
+        self.index.clear();
+    }
+}
+
+/// RGBA pixels of `bitmap`, a `size` tile scaled `scale` times, blending
+/// `fg` over `bg` by its alpha. Pixels missing from a short bitmap are
+/// background.
+pub(crate) fn colorize(
+    bitmap: &[u8],
+    size: (usize, usize),
+    scale: usize,
+    fg: Color,
+    bg: Color,
+) -> Vec<u8> {
+    let (w, h) = (size.0 * scale, size.1 * scale);
+    let blend = |f: u8, b: u8, a: u8| {
+        let a = a as u32;
+        ((f as u32 * a + b as u32 * (255 - a)) / 255) as u8
+    };
+    let mut out = Vec::with_capacity(w * h * 4);
+    for y in 0..h {
+        for x in 0..w {
+            let a = bitmap
+                .get(y / scale * size.0 + x / scale)
+                .copied()
+                .unwrap_or(0);
+            out.extend([
+                blend(fg.r(), bg.r(), a),
+                blend(fg.g(), bg.g(), a),
+                blend(fg.b(), bg.b(), a),
+                255,
+            ]);
+        }
+    }
+    out
+}
+
+/// Colourised tiles, kept in an offscreen canvas.
+pub(crate) struct TileAtlas {
+    manager: Rc<dyn TileManager>,
+    scale: usize,
+    slots: Slots,
+    canvas: HtmlCanvasElement,
+    ctx: CanvasRenderingContext2d,
+}
+
+impl TileAtlas {
+    /// An empty atlas for `manager`'s tiles drawn `scale` times their size.
+    pub fn new(manager: Rc<dyn TileManager>, scale: usize) -> Self {
+        let canvas: HtmlCanvasElement = crate::document()
+            .create_element("canvas")
+            .expect("createElement canvas")
+            .dyn_into()
+            .expect("element is not a canvas");
+        let ctx = canvas
+            .get_context("2d")
+            .expect("getContext failed")
+            .expect("no 2d context")
+            .dyn_into::<CanvasRenderingContext2d>()
+            .expect("context is not CanvasRenderingContext2d");
+        let mut atlas = Self {
+            manager,
+            scale: 0,
+            slots: Slots::new(ATLAS_SIDE * ATLAS_SIDE),
+            canvas,
+            ctx,
+        };
+        atlas.set_scale(scale);
+        atlas
+    }
+
+    /// Draw tiles `scale` times their size from now on, at least 1.
+    pub fn set_scale(&mut self, scale: usize) {
+        let scale = scale.max(1);
+        if scale == self.scale {
+            return;
+        }
+        self.scale = scale;
+        self.slots.clear();
+        let (w, h) = self.cell_size();
+        self.canvas.set_width((w * ATLAS_SIDE) as u32);
+        self.canvas.set_height((h * ATLAS_SIDE) as u32);
+    }
+
+    /// Cell size in device pixels: the scaled tile size.
+    pub fn cell_size(&self) -> (usize, usize) {
+        let (tw, th) = self.manager.tile_size();
+        ((tw * self.scale).max(1), (th * self.scale).max(1))
+    }
+
+    /// Whether `cell` is drawn as a tile.
+    pub fn has_tile(&self, cell: &Cell) -> bool {
+        self.manager.get_tile(cell).is_some()
+    }
+
+    /// Draw the tile of `cell` on `ctx` with its top-left corner at
+    /// `(x, y)`, colourising it first if it is new.
+    pub fn draw(&mut self, ctx: &CanvasRenderingContext2d, cell: &Cell, x: f64, y: f64) {
+        let Some(bitmap) = self.manager.get_tile(cell) else {
+            return;
+        };
+        let (w, h) = self.cell_size();
+        let (slot, new) = self.slots.get(*cell);
+        let (sx, sy) = (
+            (slot % ATLAS_SIDE * w) as f64,
+            (slot / ATLAS_SIDE * h) as f64,
+        );
+        if new {
+            let (fg, bg) = batch::colors(cell.style);
+            let size = self.manager.tile_size();
+            let pixels = colorize(bitmap, size, self.scale, fg, bg);
+            if let Ok(image) =
+                ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), w as u32, h as u32)
+            {
+                let _ = self.ctx.put_image_data(&image, sx, sy);
+            }
+        }
+        let (w, h) = (w as f64, h as f64);
+        let _ = ctx.draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+            &self.canvas,
+            sx,
+            sy,
+            w,
+            h,
+            x,
+            y,
+            w,
+            h,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorize_blends_and_scales() {
+        let fg = Color::from_rgb(255, 0, 0);
+        let bg = Color::from_rgb(0, 0, 255);
+        let px = colorize(&[255, 0], (2, 1), 2, fg, bg);
+        #[rustfmt::skip]
+        assert_eq!(px, [
+            255, 0, 0, 255,  255, 0, 0, 255,  0, 0, 255, 255,  0, 0, 255, 255,
+            255, 0, 0, 255,  255, 0, 0, 255,  0, 0, 255, 255,  0, 0, 255, 255,
+        ]);
+        // Half alpha, and a short bitmap.
+        let px = colorize(&[128], (2, 1), 1, fg, bg);
+        assert_eq!(px, [128, 0, 127, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn slots_are_reused_then_recycled() {
+        let cell = |ch| Cell::default().with_char(ch);
+        let mut slots = Slots::new(2);
+        assert_eq!(slots.get(cell('a')), (0, true));
+        assert_eq!(slots.get(cell('b')), (1, true));
+        assert_eq!(slots.get(cell('a')), (0, false));
+        // Full: start over.
+        assert_eq!(slots.get(cell('c')), (0, true));
+        assert_eq!(slots.get(cell('a')), (1, true));
+    }
+}
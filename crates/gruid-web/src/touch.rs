@@ -0,0 +1,213 @@
+//! Single-finger touches, turned into mouse actions.
+//!
+//! A tap is a `Main` click on the touched cell. Sliding the finger off
+//! that cell presses `Main` there and drags, and holding it still for the
+//! long-press delay presses `Secondary` instead, so that context actions
+//! stay reachable. The pointer only hovers the grid while a finger is
+//! down. A second finger cancels the touch, and touches are ignored until
+//! all fingers are lifted: pinches and two-finger scrolls do nothing.
+
+use gruid_core::{Point, messages::MouseAction};
+
+/// What a touch does to the pointer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TouchEvent {
+    /// The pointer is over this cell, with [`TouchTracker::held`] down.
+    Moved(Point),
+    /// A button press or release at this cell.
+    Button(MouseAction, Point),
+    /// The pointer left the grid.
+    Left,
+}
+
+/// The finger being followed.
+#[derive(Clone, Copy, Debug)]
+struct Touch {
+    /// `Touch.identifier` of the finger.
+    id: i32,
+    /// Number of the touch, telling a long-press timer whether its touch
+    /// is still the current one.
+    serial: u32,
+    start: Point,
+    last: Point,
+    /// The button pressed, once the touch is known to be a drag or a
+    /// long-press.
+    pressed: Option<MouseAction>,
+}
+
+/// Touch state of the canvas.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TouchTracker {
+    touch: Option<Touch>,
+    serial: u32,
+    /// Set by a second finger, until all fingers are lifted.
+    blocked: bool,
+}
+
+impl TouchTracker {
+    /// `Touch.identifier` of the finger being followed.
+    pub fn id(&self) -> Option<i32> {
+        self.touch.map(|t| t.id)
+    }
+
+    /// The button held by the finger, if any.
+    pub fn held(&self) -> Option<MouseAction> {
+        self.touch.and_then(|t| t.pressed)
+    }
+
+    /// Finger `id` touched cell `pos`, with `fingers` now on the screen.
+    /// Returns the events and, for a touch that could become a long-press,
+    /// the serial to pass to [`long_press`](Self::long_press) once the
+    /// delay elapsed.
+    pub fn start(&mut self, id: i32, pos: Point, fingers: u32) -> (Vec<TouchEvent>, Option<u32>) {
+        if fingers > 1 || self.touch.is_some() {
+            let out = self.cancel();
+            self.blocked = true;
+            return (out, None);
+        }
+        if self.blocked {
+            return (Vec::new(), None);
+        }
+        self.serial = self.serial.wrapping_add(1);
+        self.touch = Some(Touch {
+            id,
+            serial: self.serial,
+            start: pos,
+            last: pos,
+            pressed: None,
+        });
+        (vec![TouchEvent::Moved(pos)], Some(self.serial))
+    }
+
+    /// The finger moved over cell `pos`.
+    pub fn moved(&mut self, id: i32, pos: Point) -> Vec<TouchEvent> {
+        let Some(t) = self.touch.as_mut().filter(|t| t.id == id) else {
+            return Vec::new();
+        };
+        if pos == t.last {
+            return Vec::new();
+        }
+        t.last = pos;
+        let mut out = Vec::new();
+        if t.pressed.is_none() {
+            t.pressed = Some(MouseAction::Main);
+            out.push(TouchEvent::Button(MouseAction::Main, t.start));
+        }
+        out.push(TouchEvent::Moved(pos));
+        out
+    }
+
+    /// The long-press delay of touch `serial` elapsed.
+    pub fn long_press(&mut self, serial: u32) -> Vec<TouchEvent> {
+        match self.touch.as_mut() {
+            Some(t) if t.serial == serial && t.pressed.is_none() => {
+                t.pressed = Some(MouseAction::Secondary);
+                vec![TouchEvent::Button(MouseAction::Secondary, t.start)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The finger was lifted over cell `pos`, leaving `fingers` on the
+    /// screen.
+    pub fn end(&mut self, id: i32, pos: Point, fingers: u32) -> Vec<TouchEvent> {
+        if fingers == 0 {
+            self.blocked = false;
+        }
+        let Some(t) = self.touch.filter(|t| t.id == id) else {
+            return Vec::new();
+        };
+        self.touch = None;
+        let mut out = Vec::new();
+        if t.pressed.is_none() {
+            out.push(TouchEvent::Button(MouseAction::Main, t.start));
+        }
+        out.push(TouchEvent::Button(MouseAction::Release, pos));
+        out.push(TouchEvent::Left);
+        out
+    }
+
+    /// The touch was interrupted, by the browser or another finger:
+    /// release what it holds without clicking. The browser cancels every
+    /// finger at once, so the next touch is followed again.
+    pub fn cancel(&mut self) -> Vec<TouchEvent> {
+        self.blocked = false;
+        let Some(t) = self.touch.take() else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        if t.pressed.is_some() {
+            out.push(TouchEvent::Button(MouseAction::Release, t.last));
+        }
+        out.push(TouchEvent::Left);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MouseAction::*;
+    use TouchEvent::*;
+
+    const A: Point = Point::new(1, 1);
+    const B: Point = Point::new(2, 1);
+
+    #[test]
+    fn tap_clicks_the_cell() {
+        let mut t = TouchTracker::default();
+        let (out, serial) = t.start(7, A, 1);
+        assert_eq!(out, [Moved(A)]);
+        assert_eq!(t.id(), Some(7));
+        assert_eq!(t.end(7, A, 0), [Button(Main, A), Button(Release, A), Left]);
+        // The timer of a finished touch does nothing.
+        assert_eq!(t.long_press(serial.unwrap()), []);
+        assert_eq!(t.id(), None);
+    }
+
+    #[test]
+    fn sliding_drags_from_the_first_cell() {
+        let mut t = TouchTracker::default();
+        let (_, serial) = t.start(0, A, 1);
+        assert_eq!(t.moved(0, A), []);
+        assert_eq!(t.moved(0, B), [Button(Main, A), Moved(B)]);
+        assert_eq!(t.held(), Some(Main));
+        assert_eq!(t.long_press(serial.unwrap()), []);
+        assert_eq!(t.end(0, B, 0), [Button(Release, B), Left]);
+    }
+
+    #[test]
+    fn long_press_is_a_secondary_click() {
+        let mut t = TouchTracker::default();
+        let (_, serial) = t.start(0, A, 1);
+        assert_eq!(t.long_press(serial.unwrap()), [Button(Secondary, A)]);
+        assert_eq!(t.long_press(serial.unwrap()), []);
+        assert_eq!(t.end(0, A, 0), [Button(Release, A), Left]);
+    }
+
+    #[test]
+    fn second_finger_cancels_until_all_are_lifted() {
+        let mut t = TouchTracker::default();
+        t.start(0, A, 1);
+        t.moved(0, B);
+        assert_eq!(t.start(1, A, 2).0, [Button(Release, B), Left]);
+        assert_eq!(t.moved(0, A), []);
+        assert_eq!(t.end(1, A, 1), []);
+        assert_eq!(t.start(2, A, 2), (vec![], None));
+        assert_eq!(t.end(2, A, 1), []);
+        assert_eq!(t.end(0, A, 0), []);
+        // A lone finger is followed again.
+        assert_eq!(t.start(3, B, 1).0, [Moved(B)]);
+    }
+
+    #[test]
+    fn cancel_unblocks() {
+        let mut t = TouchTracker::default();
+        t.start(0, A, 1);
+        t.start(1, B, 2);
+        // The browser cancels the gesture, with no touchend.
+        assert_eq!(t.cancel(), []);
+        assert_eq!(t.start(2, B, 1).0, [Moved(B)]);
+        assert_eq!(t.end(2, B, 0), [Button(Main, B), Button(Release, B), Left]);
+    }
+}
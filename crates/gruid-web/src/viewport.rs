@@ -0,0 +1,241 @@
+//! Keeping the grid usable while a virtual keyboard covers part of it.
+//!
+//! Phones shrink the visual viewport, the part of the page on screen, when
+//! their keyboard shows, and leave the layout alone: the canvas keeps its
+//! size and its bottom rows end up under the keyboard. The driver watches
+//! the visual viewport and, once it settled, either limits the grid to the
+//! rows still in view or pans the canvas so the model's cursor stays in
+//! view, as [`KeyboardBehavior`](crate::KeyboardBehavior) says, and undoes
+//! it when the keyboard goes away.
+//!
+//! Rectangles are in CSS pixels, relative to the layout viewport, like
+//! `getBoundingClientRect()`. Cells are in device pixels, with the canvas'
+//! 1px border around them.
+
+/// How long the visual viewport must stay still before the driver reacts,
+/// in milliseconds: keyboards slide in over a few hundred.
+pub(crate) const SETTLE_MS: f64 = 150.0;
+
+/// How much shorter than the layout viewport the visual viewport must be
+/// to tell a keyboard, in CSS pixels: browser toolbars hiding and showing
+/// take less.
+const KEYBOARD_MIN_HEIGHT: f64 = 120.0;
+
+/// An axis-aligned rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Rect {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub(crate) fn new(left: f64, top: f64, width: f64, height: f64) -> Self {
+        Self {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    fn right(&self) -> f64 {
+        self.left + self.width
+    }
+
+    fn bottom(&self) -> f64 {
+        self.top + self.height
+    }
+
+    /// The part of `self` inside `other`, if they overlap.
+    pub(crate) fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let (left, top) = (self.left.max(other.left), self.top.max(other.top));
+        let (right, bottom) = (
+            self.right().min(other.right()),
+            self.bottom().min(other.bottom()),
+        );
+        (right > left && bottom > top).then(|| Rect::new(left, top, right - left, bottom - top))
+    }
+}
+
+/// Whether a visual viewport of `height` CSS pixels, at `scale` times the
+/// page zoom, leaves room for a keyboard in a layout viewport of
+/// `layout_height`. A pinch-zoomed page shrinks the visual viewport too,
+/// and is not taken for one.
+pub(crate) fn keyboard_shown(layout_height: f64, height: f64, scale: f64) -> bool {
+    (scale - 1.0).abs() < 0.01 && layout_height - height >= KEYBOARD_MIN_HEIGHT
+}
+
+/// How many rows of `cell_h` device pixels, at `scale` device pixels per
+/// CSS pixel, fit between the top of a canvas at `canvas_top` and the
+/// bottom of `viewport`. At least one, and maybe more than the canvas has.
+pub(crate) fn rows_in_view(canvas_top: f64, viewport: &Rect, scale: f64, cell_h: f64) -> i32 {
+    let height = (viewport.bottom() - canvas_top - 1.0) * scale;
+    ((height / cell_h).floor() as i32).max(1)
+}
+
+/// How far to move a canvas at `canvas` down, in CSS pixels, for its row
+/// `row` of `cell_h` device pixels to be inside `viewport`, at `scale`
+/// device pixels per CSS pixel: nothing if it already is, otherwise as far
+/// as brings the row to the middle of the viewport without letting the
+/// viewport go past the canvas' top or bottom edge. Negative moves it up.
+pub(crate) fn pan_to_row(canvas: &Rect, viewport: &Rect, row: i32, scale: f64, cell_h: f64) -> f64 {
+    let top = canvas.top + 1.0 + row as f64 * cell_h / scale;
+    let bottom = top + cell_h / scale;
+    if top >= viewport.top && bottom <= viewport.bottom() {
+        return 0.0;
+    }
+    let shift = viewport.top + viewport.height / 2.0 - (top + bottom) / 2.0;
+    let up = (viewport.bottom() - canvas.bottom()).min(0.0);
+    let down = (viewport.top - canvas.top).max(0.0);
+    shift.clamp(up, down)
+}
+
+/// What the keyboard did to the grid, to be undone when it goes.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct KeyboardState {
+    /// Rows in view above the keyboard, while it shows.
+    rows_in_view: Option<i32>,
+    /// Rows of a fixed-size grid before the keyboard limited them.
+    full_rows: Option<i32>,
+    /// How far the canvas is moved down, in CSS pixels.
+    pub(crate) pan: f64,
+    /// When the visual viewport will have been still long enough, in
+    /// milliseconds since the page loaded.
+    settle_at: Option<f64>,
+}
+
+impl KeyboardState {
+    /// The visual viewport changed at `now`: wait for it to settle again.
+    pub(crate) fn viewport_moved(&mut self, now: f64) {
+        self.settle_at = Some(now + SETTLE_MS);
+    }
+
+    /// Whether the visual viewport settled since it last moved, by `now`.
+    /// True once per move.
+    pub(crate) fn settled(&mut self, now: f64) -> bool {
+        let done = self.settle_at.is_some_and(|at| now >= at);
+        if done {
+            self.settle_at = None;
+        }
+        done
+    }
+
+    /// The keyboard shows, leaving `rows` in view, or is gone, with `None`.
+    pub(crate) fn set_rows_in_view(&mut self, rows: Option<i32>) {
+        self.rows_in_view = rows;
+    }
+
+    pub(crate) fn shown(&self) -> bool {
+        self.rows_in_view.is_some()
+    }
+
+    /// The rows to lay out for a grid sized to its canvas with `rows` rows.
+    pub(crate) fn fitted_rows(&self, rows: i32) -> i32 {
+        self.rows_in_view.map_or(rows, |n| n.clamp(1, rows))
+    }
+
+    /// The rows to lay out for a grid of fixed size with `rows` rows now:
+    /// the rows in view while the keyboard shows, out of those the grid
+    /// had before it, and those again once it is gone.
+    pub(crate) fn fixed_rows(&mut self, rows: i32) -> i32 {
+        let full = if self.rows_in_view.is_some() {
+            *self.full_rows.get_or_insert(rows)
+        } else {
+            self.full_rows.take().unwrap_or(rows)
+        };
+        self.fitted_rows(full)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_part_of_the_canvas() {
+        let canvas = Rect::new(0.0, 50.0, 400.0, 600.0);
+        let viewport = Rect::new(0.0, 0.0, 400.0, 350.0);
+        assert_eq!(
+            canvas.intersect(&viewport),
+            Some(Rect::new(0.0, 50.0, 400.0, 300.0))
+        );
+        let scrolled = Rect::new(10.0, 500.0, 400.0, 350.0);
+        assert_eq!(
+            canvas.intersect(&scrolled),
+            Some(Rect::new(10.0, 500.0, 390.0, 150.0))
+        );
+        assert_eq!(canvas.intersect(&Rect::new(0.0, 650.0, 400.0, 10.0)), None);
+
+        assert!(keyboard_shown(800.0, 450.0, 1.0));
+        assert!(!keyboard_shown(800.0, 740.0, 1.0));
+        assert!(!keyboard_shown(800.0, 400.0, 2.0));
+    }
+
+    #[test]
+    fn rows_above_the_keyboard() {
+        // Cells 32 device pixels high at 2 per CSS pixel: 16 CSS pixels,
+        // below a 1px border.
+        let viewport = |h| Rect::new(0.0, 0.0, 320.0, h);
+        assert_eq!(rows_in_view(100.0, &viewport(485.0), 2.0, 32.0), 24);
+        assert_eq!(rows_in_view(100.0, &viewport(261.0), 2.0, 32.0), 10);
+        assert_eq!(rows_in_view(100.0, &viewport(260.0), 2.0, 32.0), 9);
+        assert_eq!(rows_in_view(100.0, &viewport(90.0), 2.0, 32.0), 1);
+        // Scrolled down: the viewport's bottom is further down the page.
+        let scrolled = Rect::new(0.0, 200.0, 320.0, 261.0);
+        assert_eq!(rows_in_view(100.0, &scrolled, 2.0, 32.0), 22);
+    }
+
+    #[test]
+    fn pans_the_cursor_into_view() {
+        let canvas = Rect::new(0.0, 0.0, 320.0, 401.0);
+        let viewport = Rect::new(0.0, 0.0, 320.0, 200.0);
+        // Rows of 16 CSS pixels: row 5 is in view, row 15 is not.
+        assert_eq!(pan_to_row(&canvas, &viewport, 5, 2.0, 32.0), 0.0);
+        // Row 15 spans 241..257 and moves to the middle, 92..108.
+        assert_eq!(pan_to_row(&canvas, &viewport, 15, 2.0, 32.0), -149.0);
+        // The last row moves no further than brings the canvas' bottom up.
+        assert_eq!(pan_to_row(&canvas, &viewport, 24, 2.0, 32.0), -201.0);
+        // Scrolled down past a row at the top: back down to the canvas' top.
+        let below = Rect::new(0.0, 100.0, 320.0, 200.0);
+        assert_eq!(pan_to_row(&canvas, &below, 0, 2.0, 32.0), 100.0);
+    }
+
+    #[test]
+    fn restores_the_rows_once_the_keyboard_goes() {
+        let mut kb = KeyboardState::default();
+        assert_eq!(kb.fixed_rows(24), 24);
+        kb.set_rows_in_view(Some(10));
+        assert!(kb.shown());
+        assert_eq!(kb.fixed_rows(24), 10);
+        // The grid now has 10 rows, but 24 are remembered.
+        assert_eq!(kb.fixed_rows(10), 10);
+        kb.set_rows_in_view(Some(12));
+        assert_eq!(kb.fixed_rows(10), 12);
+        // More rows in view than the grid had: all of them.
+        kb.set_rows_in_view(Some(40));
+        assert_eq!(kb.fixed_rows(12), 24);
+        kb.set_rows_in_view(None);
+        assert_eq!(kb.fixed_rows(24), 24);
+        kb.set_rows_in_view(Some(10));
+        assert_eq!(kb.fixed_rows(24), 10);
+        kb.set_rows_in_view(None);
+        assert_eq!(kb.fixed_rows(10), 24);
+        assert_eq!(kb.fixed_rows(24), 24);
+
+        kb.set_rows_in_view(Some(8));
+        assert_eq!((kb.fitted_rows(30), kb.fitted_rows(5)), (8, 5));
+    }
+
+    #[test]
+    fn waits_for_the_viewport_to_settle() {
+        let mut kb = KeyboardState::default();
+        assert!(!kb.settled(0.0));
+        kb.viewport_moved(0.0);
+        kb.viewport_moved(100.0);
+        assert!(!kb.settled(200.0));
+        assert!(kb.settled(250.0));
+        assert!(!kb.settled(400.0));
+    }
+}
@@ -0,0 +1,50 @@
+//! Commands run in the browser: `wasm-pack test --headless --firefox`.
+
+#![cfg(target_arch = "wasm32")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gruid_core::{AppRunner, Effect, Grid, Model, Msg, app::cmd};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+struct Pong;
+
+/// Asks for a [`Pong`] on init, and logs what it receives.
+struct Pinger(Rc<RefCell<Vec<Msg>>>);
+
+impl Model for Pinger {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        let effect = matches!(msg, Msg::Init).then(|| cmd(|| Some(Msg::custom(Pong))));
+        self.0.borrow_mut().push(msg);
+        effect
+    }
+
+    fn draw(&self, _grid: &mut Grid) {}
+}
+
+/// Resolves after the tasks queued so far.
+async fn next_task() {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let _ = web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 0);
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn cmd_message_is_delivered() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut runner = AppRunner::new(Box::new(Pinger(Rc::clone(&log))), 10, 4);
+    runner.set_spawner(gruid_web::spawn_timeout);
+    runner.init();
+    runner.process_pending_msgs();
+    assert_eq!(log.borrow().len(), 3, "Seed, Init and Screen");
+    next_task().await;
+    runner.process_pending_msgs();
+    assert!(log.borrow()[3].downcast_ref::<Pong>().is_some());
+}
@@ -0,0 +1,149 @@
+//! A game ending on a page: `wasm-pack test --headless --firefox`.
+
+#![cfg(target_arch = "wasm32")]
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gruid_core::{AppRunner, Cell as GridCell, Effect, EventLoopDriver, Grid, Model, Msg};
+use gruid_web::{EndBehavior, WebConfig, WebDriver};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::wasm_bindgen_test;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// Fills its grid, and quits on init if `quits`. Counts its exits.
+struct Game {
+    quits: bool,
+    exits: Rc<Cell<u32>>,
+}
+
+impl Model for Game {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        (self.quits && matches!(msg, Msg::Init)).then_some(Effect::End)
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        grid.fill(GridCell::default().with_char('#'));
+    }
+
+    fn on_exit(&mut self) {
+        self.exits.set(self.exits.get() + 1);
+    }
+}
+
+/// A canvas with id `id`, added to the page, and the number of
+/// `gruid-ended` events that reached the document from it.
+fn canvas(id: &str) -> (HtmlCanvasElement, Rc<Cell<u32>>) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas: HtmlCanvasElement = document.create_element("canvas").unwrap().unchecked_into();
+    canvas.set_id(id);
+    document.body().unwrap().append_child(&canvas).unwrap();
+    let ended = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&ended);
+    let target = canvas.clone();
+    let listener = Closure::<dyn FnMut(web_sys::Event)>::new(move |e: web_sys::Event| {
+        if e.target().is_some_and(|t| js_sys::Object::is(&t, &target)) {
+            counter.set(counter.get() + 1);
+        }
+    });
+    document
+        .add_event_listener_with_callback("gruid-ended", listener.as_ref().unchecked_ref())
+        .unwrap();
+    listener.forget();
+    (canvas, ended)
+}
+
+fn start(id: &str, quits: bool, config: WebConfig) -> Rc<Cell<u32>> {
+    let exits = Rc::new(Cell::new(0));
+    let game = Game {
+        quits,
+        exits: Rc::clone(&exits),
+    };
+    let config = WebConfig {
+        canvas_id: id.into(),
+        width: 4,
+        height: 2,
+        ..config
+    };
+    WebDriver::new(config)
+        .run(AppRunner::new(Box::new(game), 4, 2))
+        .unwrap();
+    exits
+}
+
+/// Resolves after two animation frames and the tasks they queued.
+async fn settle() {
+    for _ in 0..2 {
+        let promise = js_sys::Promise::new(&mut |resolve, _| {
+            let _ = web_sys::window().unwrap().request_animation_frame(&resolve);
+        });
+        JsFuture::from(promise).await.unwrap();
+    }
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let _ = web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 0);
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+/// Alpha of the top-left pixel of `canvas`.
+fn corner_alpha(canvas: &HtmlCanvasElement) -> u8 {
+    let ctx: CanvasRenderingContext2d = canvas.get_context("2d").unwrap().unwrap().unchecked_into();
+    ctx.get_image_data(0.0, 0.0, 1.0, 1.0).unwrap().data()[3]
+}
+
+#[wasm_bindgen_test]
+async fn end_notifies_the_page_once_per_run() {
+    let (canvas, ended) = canvas("end-notifies");
+    let callback = js_sys::Function::new_with_args(
+        "canvas",
+        "canvas.dataset.ended = Number(canvas.dataset.ended || 0) + 1",
+    );
+    let config = || WebConfig {
+        on_end: EndBehavior::Clear,
+        on_end_callback: Some(callback.clone()),
+        ..WebConfig::default()
+    };
+
+    let exits = start("end-notifies", true, config());
+    settle().await;
+    assert_eq!(ended.get(), 1);
+    assert_eq!(canvas.get_attribute("data-ended").as_deref(), Some("1"));
+    assert_eq!(exits.get(), 1);
+    assert_eq!(corner_alpha(&canvas), 0, "cleared");
+
+    // A second run on the same canvas draws and ends again.
+    let exits = start("end-notifies", true, config());
+    settle().await;
+    assert_eq!(ended.get(), 2);
+    assert_eq!(canvas.get_attribute("data-ended").as_deref(), Some("2"));
+    assert_eq!(exits.get(), 1);
+}
+
+#[wasm_bindgen_test]
+async fn last_frame_is_kept_by_default() {
+    let (canvas, ended) = canvas("end-keeps");
+    start("end-keeps", true, WebConfig::default());
+    settle().await;
+    assert_eq!(ended.get(), 1);
+    assert_eq!(corner_alpha(&canvas), 255, "opaque background");
+}
+
+#[wasm_bindgen_test]
+async fn replaced_driver_stops_silently() {
+    let (_canvas, ended) = canvas("end-replaced");
+    let first = start("end-replaced", false, WebConfig::default());
+    settle().await;
+    assert_eq!(first.get(), 0);
+
+    let second = start("end-replaced", false, WebConfig::default());
+    settle().await;
+    assert_eq!(first.get(), 1, "the first driver stopped");
+    assert_eq!(second.get(), 0);
+    assert_eq!(ended.get(), 0, "no game ended");
+}
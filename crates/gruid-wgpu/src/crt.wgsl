@@ -0,0 +1,17 @@
+// CRT: barrel distortion of a curved screen, with darkened corners.
+//
+// param(0): curvature, from 0 (flat) to about 1
+// param(1): vignette, from 0 (none) to 1 (black corners)
+
+@fragment
+fn fs_post(in: PostIn) -> @location(0) vec4<f32> {
+    // Sample further out towards the edges, so the picture bulges.
+    let c = in.uv * 2.0 - 1.0;
+    let warped = c * (1.0 + max(param(0), 0.0) * 0.25 * dot(c, c));
+    let color = scene_at(warped * 0.5 + 0.5).rgb;
+
+    let vignette = clamp(1.0 - clamp(param(1), 0.0, 1.0) * 0.5 * dot(c, c), 0.0, 1.0);
+    // Past the curved edge of the screen is the black bezel.
+    let inside = all(abs(warped) <= vec2<f32>(1.0));
+    return vec4<f32>(select(vec3<f32>(0.0), color * vignette, inside), 1.0);
+}
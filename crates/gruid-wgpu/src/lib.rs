@@ -7,33 +7,79 @@
 //! Uses:
 //! - [`wgpu`] for GPU rendering
 //! - [`winit`] for window creation and input events
-//! - [`fontdue`] for glyph rasterization into the atlas
+//! - fontdue for glyph rasterization into the atlas
+//!
+//! Input translation, glyph rasterization and atlas packing live in the
+//! internal gruid-graphics-common crate shared with gruid-winit.
 //!
 //! Supports custom tile rendering via the [`TileManager`] trait (same
 //! interface as `gruid-winit`).
+//!
+//! With the `screenshot` feature, pressing
+//! [`screenshot_key`](WgpuConfig::screenshot_key) (F12 by default) saves
+//! the current frame as a timestamped PNG file in the working directory.
+//!
+//! A post-processing pass (scanlines, a curved CRT screen or a custom WGSL
+//! shader) can be run over the grid; see [`WgpuConfig::post_shader`]. With
+//! the `hot-reload` feature, a shader loaded from a file is compiled again
+//! whenever the file changes.
+//!
+//! By default a frame is only rendered when the display changed; see
+//! [`RenderMode`]. For animations, [`WgpuConfig::frame_rate`] sends the
+//! model a [`Msg::Frame`] at a steady rate, rendering what it changes.
+//! Nothing is rendered while the window is minimized or
+//! fully covered, and the model can be told so it pauses (see
+//! [`WgpuConfig::pause_on_occlusion`]).
+//!
+//! Ctrl+V (Cmd+V on macOS), Shift+Insert and the Paste key read the system
+//! clipboard and send its text as a [`Msg::Paste`], instead of the key.
 
-mod input;
+mod pacing;
+mod post;
 mod renderer;
+mod screenshot;
 
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use wgpu::util::DeviceExt;
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
+    event::ElementState,
     event::WindowEvent,
-    event_loop::{ActiveEventLoop, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{Key as WKey, ModifiersState, NamedKey},
     window::{Window, WindowId},
 };
 
 use gruid_core::{
+    DriverCaps,
     app::{AppRunner, EventLoopDriver},
-    messages::Msg,
+    messages::{MouseAction, MouseTracker, Msg, Visibility},
+};
+use gruid_graphics_common::{
+    clipboard::{self, SystemClipboard},
+    config_capabilities,
+    frames::FrameTimer,
+    input,
+    tiles::tile_scale,
+    title::WindowTitle,
+    visibility::WindowVisibility,
+    window::{WindowFullscreen, WindowOptions, window_attributes},
 };
 
+use pacing::Pacer;
+use post::PostPass;
 use renderer::{CellInstance, GridRenderer};
+use screenshot::PendingCapture;
 
 pub use gruid_core::TileManager;
+pub use pacing::{FrameStats, RenderMode};
+pub use post::{MAX_POST_UNIFORMS, PostShader, PostShaderError};
+
+/// How often the event loop wakes to drain messages from background effects.
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
 
 // ---------------------------------------------------------------------------
 // Configuration
@@ -55,6 +101,44 @@ pub struct WgpuConfig {
     pub tile_manager: Option<Box<dyn TileManager>>,
     /// Integer scale factor for tiles (0 = auto-detect from DPI).
     pub tile_scale: u32,
+    /// Key that saves a screenshot of the current frame. The key press is
+    /// consumed by the driver and never reaches the model. Defaults to F12
+    /// with the `screenshot` feature, `None` otherwise.
+    pub screenshot_key: Option<NamedKey>,
+    /// When frames are rendered.
+    pub render_mode: RenderMode,
+    /// Called about once a second with the number of frames rendered.
+    pub on_frame_stats: Option<Box<dyn FnMut(FrameStats)>>,
+    /// Send the model a [`Visibility`] message when the window is
+    /// minimized, fully covered or shown again, so it can pause (default
+    /// false). Rendering stops while hidden either way.
+    pub pause_on_occlusion: bool,
+    /// Shader run over the composed grid before it is shown (default none).
+    /// One that fails to load or compile is logged as an error, and the
+    /// grid is shown as is.
+    pub post_shader: Option<PostShader>,
+    /// Send the model a [`Msg::Frame`] this many times a second while the
+    /// window can be seen, for animations (default `None`: no frames).
+    /// Unlike [`RenderMode::Continuous`], only frames the model changed
+    /// are rendered.
+    pub frame_rate: Option<u32>,
+    /// Start in borderless fullscreen on the current monitor (default
+    /// false). Models switch it with a
+    /// [`SetFullscreen`](gruid_core::SetFullscreen) request.
+    pub fullscreen: bool,
+    /// Whether the window has a title bar and borders (default true).
+    pub decorations: bool,
+    /// Whether the window can be resized (default true).
+    pub resizable: bool,
+    /// Window icon: RGBA pixels, width and height (default none).
+    pub window_icon: Option<(Vec<u8>, u32, u32)>,
+    /// Columns and rows the window cannot be shrunk below (default
+    /// `(0, 0)`: no minimum).
+    pub min_grid_size: (i32, i32),
+    /// Turn the paste shortcuts, Ctrl+V (Cmd+V on macOS), Shift+Insert and
+    /// the Paste key, into a [`Msg::Paste`] of the clipboard text (default
+    /// true). When false, they reach the model as keys.
+    pub paste: bool,
 }
 
 impl Default for WgpuConfig {
@@ -67,6 +151,31 @@ impl Default for WgpuConfig {
             grid_height: 24,
             tile_manager: None,
             tile_scale: 0,
+            screenshot_key: cfg!(feature = "screenshot").then_some(NamedKey::F12),
+            render_mode: RenderMode::default(),
+            on_frame_stats: None,
+            pause_on_occlusion: false,
+            post_shader: None,
+            frame_rate: None,
+            fullscreen: false,
+            decorations: true,
+            resizable: true,
+            window_icon: None,
+            min_grid_size: (0, 0),
+            paste: true,
+        }
+    }
+}
+
+impl WgpuConfig {
+    fn window_options(&self) -> WindowOptions<'_> {
+        WindowOptions {
+            title: &self.title,
+            fullscreen: self.fullscreen,
+            decorations: self.decorations,
+            resizable: self.resizable,
+            icon: self.window_icon.as_ref(),
+            min_grid_size: self.min_grid_size,
         }
     }
 }
@@ -90,18 +199,43 @@ impl WgpuDriver {
 }
 
 impl EventLoopDriver for WgpuDriver {
-    fn run(self, runner: AppRunner) -> Result<(), Box<dyn std::error::Error>> {
+    fn run(self, mut runner: AppRunner) -> Result<(), Box<dyn std::error::Error>> {
+        runner.set_clipboard(Box::new(SystemClipboard::default()));
+        let title = WindowTitle::default();
+        runner.add_effect_handler(title.handler());
         let event_loop = EventLoop::new()?;
-        let mut app = WgpuApp::new(self.config, runner);
+        let mut app = WgpuApp::new(self.config, runner, title);
         event_loop.run_app(&mut app)?;
         Ok(())
     }
+
+    fn capabilities(&self) -> DriverCaps {
+        config_capabilities(
+            self.config.font_data.as_deref(),
+            self.config.font_size,
+            self.config.tile_manager.as_deref(),
+        )
+    }
 }
 
 // ---------------------------------------------------------------------------
 // GPU State
 // ---------------------------------------------------------------------------
 
+/// The format to configure the surface with among `formats`, and the
+/// format to render it with. Colours are written unconverted, blended in
+/// sRGB space like gruid-winit does: a non-sRGB format is preferred, and
+/// an sRGB-only surface is rendered through a view of the same format
+/// without the sRGB conversion.
+fn surface_formats(formats: &[wgpu::TextureFormat]) -> (wgpu::TextureFormat, wgpu::TextureFormat) {
+    let surface = formats
+        .iter()
+        .find(|f| !f.is_srgb())
+        .copied()
+        .unwrap_or(formats[0]);
+    (surface, surface.remove_srgb_suffix())
+}
+
 struct GpuState {
     device: wgpu::Device,
     queue: wgpu::Queue,
@@ -116,6 +250,10 @@ struct GpuState {
     atlas_extent: wgpu::Extent3d,
     _sampler: wgpu::Sampler,
     instance_count: u32,
+    /// Whether the surface supports being copied out (for screenshots).
+    can_capture: bool,
+    /// The grid is rendered through this pass when present.
+    post: Option<PostPass>,
 }
 
 // ---------------------------------------------------------------------------
@@ -129,38 +267,88 @@ struct WgpuApp {
     gpu: Option<GpuState>,
     window: Option<Arc<Window>>,
     scale_factor: f64,
+    /// Set when a screenshot should be taken on the next rendered frame.
+    capture_requested: bool,
+    capture: Option<PendingCapture>,
+    mouse: MouseTracker,
+    /// The button a drag is made with, if one is held.
+    held: Option<MouseAction>,
+    /// Modifiers held, to recognize the paste shortcut.
+    modifiers: ModifiersState,
+    pacer: Pacer,
+    frames: FrameTimer,
+    title: WindowTitle,
+    fullscreen: WindowFullscreen,
+    visibility: WindowVisibility,
+    #[cfg(feature = "hot-reload")]
+    shader_watcher: Option<post::ShaderWatcher>,
 }
 
 impl WgpuApp {
-    fn new(config: WgpuConfig, runner: AppRunner) -> Self {
+    fn new(config: WgpuConfig, mut runner: AppRunner, title: WindowTitle) -> Self {
+        let fullscreen = WindowFullscreen::default();
+        runner.add_effect_handler(fullscreen.handler());
         Self {
+            pacer: Pacer::new(config.render_mode, Instant::now()),
+            frames: FrameTimer::new(config.frame_rate),
+            #[cfg(feature = "hot-reload")]
+            shader_watcher: post::ShaderWatcher::new(config.post_shader.as_ref(), Instant::now()),
             config,
             runner,
             renderer: None,
             gpu: None,
             window: None,
             scale_factor: 1.0,
+            capture_requested: false,
+            capture: None,
+            mouse: MouseTracker::default(),
+            held: None,
+            modifiers: ModifiersState::empty(),
+            title,
+            fullscreen,
+            visibility: WindowVisibility::default(),
         }
     }
 
-    fn render(&mut self) {
-        if self.runner.should_quit() {
+    /// Tell the model about a visibility change, if asked to. The surface
+    /// is rendered again in full when the window shows.
+    fn set_visibility(&mut self, changed: Option<Visibility>) {
+        let Some(visibility) = changed else {
             return;
+        };
+        if self.config.pause_on_occlusion {
+            self.runner.handle_msg(Msg::custom(visibility));
         }
+        if visibility == Visibility::Visible {
+            self.pacer.invalidate();
+        }
+    }
 
+    /// Bring the renderer up to date with the model. Returns whether a frame
+    /// should be rendered: never while the window is hidden.
+    fn update(&mut self) -> bool {
         self.runner.process_pending_msgs();
+        if let Some(frame) = self.runner.draw_frame()
+            && let Some(renderer) = self.renderer.as_mut()
+        {
+            renderer.apply_frame(&frame);
+            self.pacer.invalidate();
+        }
+        self.visibility.is_visible() && self.pacer.due()
+    }
 
-        let frame = self.runner.draw_frame();
+    /// Render and present a frame if one is due. Nothing is submitted to
+    /// the GPU otherwise.
+    fn render(&mut self) {
+        if self.runner.should_quit() || !self.update() {
+            return;
+        }
 
         let renderer = match self.renderer.as_mut() {
             Some(r) => r,
             None => return,
         };
 
-        if let Some(frame) = frame {
-            renderer.apply_frame(&frame);
-        }
-
         let gpu = match self.gpu.as_ref() {
             Some(g) => g,
             None => return,
@@ -206,14 +394,26 @@ impl WgpuApp {
         gpu.queue
             .write_buffer(&gpu.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
 
-        // Render
+        // Render. On failure the frame stays due and is retried.
         let surface_texture = match gpu.surface.get_current_texture() {
             Ok(t) => t,
-            Err(_) => return,
+            Err(err) => {
+                log::debug!("no surface texture: {err}");
+                if matches!(err, wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) {
+                    gpu.surface.configure(&gpu.device, &gpu.surface_config);
+                }
+                if let Some(w) = self.window.as_ref() {
+                    w.request_redraw();
+                }
+                return;
+            }
         };
         let view = surface_texture
             .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+            .create_view(&wgpu::TextureViewDescriptor {
+                format: Some(gpu.surface_config.format.remove_srgb_suffix()),
+                ..Default::default()
+            });
 
         let mut encoder = gpu
             .device
@@ -222,10 +422,11 @@ impl WgpuApp {
             });
 
         {
+            let target = gpu.post.as_ref().map_or(&view, PostPass::target);
             let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("gruid-wgpu pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: target,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -244,9 +445,60 @@ impl WgpuApp {
             pass.set_vertex_buffer(0, gpu.instance_buffer.slice(..));
             pass.draw(0..4, 0..gpu.instance_count);
         }
+        if let Some(post) = gpu.post.as_ref() {
+            let cell = (renderer.cell_width, renderer.cell_height);
+            post.draw(&gpu.queue, &mut encoder, &view, cell);
+        }
+
+        let mut capture = None;
+        if std::mem::take(&mut self.capture_requested) {
+            if !gpu.can_capture {
+                log::error!("screenshot: surface does not support copying out");
+            } else if self.capture.is_some() {
+                log::warn!("screenshot: previous capture still in progress");
+            } else {
+                capture = Some(PendingCapture::record(
+                    &gpu.device,
+                    &mut encoder,
+                    &surface_texture.texture,
+                    screenshot::default_path(),
+                ));
+            }
+        }
 
         gpu.queue.submit(std::iter::once(encoder.finish()));
         surface_texture.present();
+        self.pacer.rendered();
+        self.runner.frame_presented();
+
+        if let Some(capture) = capture {
+            capture.map();
+            self.capture = Some(capture);
+        }
+    }
+
+    /// Swap in the watched post shader if its file changed and compiles.
+    #[cfg(feature = "hot-reload")]
+    fn reload_post_shader(&mut self) {
+        let (Some(watcher), Some(gpu)) = (self.shader_watcher.as_mut(), self.gpu.as_mut()) else {
+            return;
+        };
+        let Some(source) = watcher.poll(Instant::now()) else {
+            return;
+        };
+        let reloaded = match gpu.post.as_mut() {
+            Some(post) => post.reload(&gpu.device, source),
+            // The shader did not compile at startup.
+            None => {
+                let size = (gpu.surface_config.width, gpu.surface_config.height);
+                let format = gpu.surface_config.format.remove_srgb_suffix();
+                PostPass::new(&gpu.device, format, size, source).map(|post| gpu.post = Some(post))
+            }
+        };
+        match reloaded {
+            Ok(()) => self.pacer.invalidate(),
+            Err(err) => log::error!("{err}; keeping the previous post shader"),
+        }
     }
 
     fn init_gpu(&mut self, window: Arc<Window>) {
@@ -254,11 +506,7 @@ impl WgpuApp {
         self.scale_factor = scale_factor;
 
         let physical_font_size = self.config.font_size * scale_factor as f32;
-        let tile_scale = if self.config.tile_scale > 0 {
-            self.config.tile_scale
-        } else {
-            (scale_factor.round() as u32).max(1)
-        };
+        let tile_scale = tile_scale(self.config.tile_scale, scale_factor);
 
         let renderer = GridRenderer::new(
             self.config.font_data.as_deref(),
@@ -292,22 +540,27 @@ impl WgpuApp {
                 .expect("failed to create GPU device");
 
         let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| !f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
+        let (surface_format, render_format) = surface_formats(&surface_caps.formats);
+
+        let can_capture = surface_caps.usages.contains(wgpu::TextureUsages::COPY_SRC);
+        let mut usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+        if can_capture {
+            usage |= wgpu::TextureUsages::COPY_SRC;
+        }
 
         let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: phys_w.max(1),
             height: phys_h.max(1),
             present_mode: wgpu::PresentMode::AutoVsync,
             alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
+            view_formats: if render_format == surface_format {
+                vec![]
+            } else {
+                vec![render_format]
+            },
+            desired_maximum_frame_latency: self.pacer.mode().frame_latency(),
         };
         surface.configure(&device, &surface_config);
 
@@ -488,7 +741,7 @@ impl WgpuApp {
                 module: &shader,
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
+                    format: render_format,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -500,6 +753,13 @@ impl WgpuApp {
 
         let instance_count = renderer.instances.len() as u32;
 
+        let post = post::load_or_log(self.config.post_shader.as_ref()).and_then(|source| {
+            PostPass::new(&device, render_format, (phys_w, phys_h), source)
+                .inspect_err(|err| log::error!("{err}; rendering without post-processing"))
+                .ok()
+        });
+
+        self.runner.set_capabilities(renderer.capabilities());
         self.renderer = Some(renderer);
         self.gpu = Some(GpuState {
             device,
@@ -515,6 +775,8 @@ impl WgpuApp {
             atlas_extent,
             _sampler: sampler,
             instance_count,
+            can_capture,
+            post,
         });
         self.window = Some(window);
     }
@@ -533,11 +795,7 @@ impl ApplicationHandler for WgpuApp {
             .unwrap_or(1.0);
 
         let physical_font_size = self.config.font_size * scale_factor as f32;
-        let tile_scale = if self.config.tile_scale > 0 {
-            self.config.tile_scale
-        } else {
-            (scale_factor.round() as u32).max(1)
-        };
+        let tile_scale = tile_scale(self.config.tile_scale, scale_factor);
 
         // Temporarily create renderer to get window size
         let temp_renderer = GridRenderer::new(
@@ -550,12 +808,14 @@ impl ApplicationHandler for WgpuApp {
         );
         let phys_w = temp_renderer.pixel_width() as u32;
         let phys_h = temp_renderer.pixel_height() as u32;
+        let cell_size = (temp_renderer.cell_width, temp_renderer.cell_height);
         drop(temp_renderer);
 
-        let window_attrs = Window::default_attributes()
-            .with_title(&self.config.title)
-            .with_inner_size(PhysicalSize::new(phys_w, phys_h))
-            .with_resizable(true);
+        let window_attrs = window_attributes(
+            &self.config.window_options(),
+            PhysicalSize::new(phys_w, phys_h),
+            cell_size,
+        );
 
         let window = Arc::new(
             event_loop
@@ -563,6 +823,11 @@ impl ApplicationHandler for WgpuApp {
                 .expect("failed to create window"),
         );
 
+        self.title.attach(window.clone());
+        self.fullscreen.attach(window.clone());
+        if let Some(theme) = window.theme() {
+            self.runner.set_system_theme(input::translate_theme(theme));
+        }
         self.init_gpu(window);
         self.runner.init();
         self.render();
@@ -574,8 +839,12 @@ impl ApplicationHandler for WgpuApp {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
-        let cell_w = self.renderer.as_ref().map(|r| r.cell_width).unwrap_or(8);
-        let cell_h = self.renderer.as_ref().map(|r| r.cell_height).unwrap_or(16);
+        let layout = self
+            .renderer
+            .as_ref()
+            .map(|r| r.layout())
+            .unwrap_or_default();
+        self.mouse.resize(self.runner.width(), self.runner.height());
 
         match event {
             WindowEvent::CloseRequested => {
@@ -583,11 +852,21 @@ impl ApplicationHandler for WgpuApp {
                 event_loop.exit();
             }
 
+            WindowEvent::ThemeChanged(theme) => {
+                self.runner.set_system_theme(input::translate_theme(theme));
+            }
+
             WindowEvent::Resized(PhysicalSize { width, height }) => {
+                let changed = self.visibility.resized(width, height);
+                self.set_visibility(changed);
                 if let Some(gpu) = self.gpu.as_mut() {
                     gpu.surface_config.width = width.max(1);
                     gpu.surface_config.height = height.max(1);
                     gpu.surface.configure(&gpu.device, &gpu.surface_config);
+                    if let Some(post) = gpu.post.as_mut() {
+                        let size = (gpu.surface_config.width, gpu.surface_config.height);
+                        post.resize(&gpu.device, size);
+                    }
 
                     if let Some(renderer) = self.renderer.as_mut() {
                         let (cw, ch) = (renderer.cell_width, renderer.cell_height);
@@ -613,34 +892,72 @@ impl ApplicationHandler for WgpuApp {
                                         });
                                 }
 
-                                self.runner.resize(new_cols, new_rows);
-                                self.runner.handle_msg(Msg::Screen {
-                                    width: new_cols,
-                                    height: new_rows,
-                                    time: std::time::Instant::now(),
-                                });
+                                self.runner.screen(new_cols, new_rows);
                             }
                         }
                     }
                 }
+                // The reconfigured surface has no contents yet.
+                self.pacer.invalidate();
+                if self.runner.should_quit() {
+                    event_loop.exit();
+                    return;
+                }
+                self.render();
+            }
+
+            WindowEvent::Occluded(occluded) => {
+                let changed = self.visibility.set_occluded(occluded);
+                self.set_visibility(changed);
+                if self.runner.should_quit() {
+                    event_loop.exit();
+                    return;
+                }
                 self.render();
             }
 
+            // Either ours, or the window system asking for the contents
+            // again.
             WindowEvent::RedrawRequested => {
+                self.pacer.invalidate();
                 self.render();
             }
 
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+
             WindowEvent::KeyboardInput { event, .. } => {
-                if let Some(msg) = input::translate_keyboard(&event) {
+                if let Some(key) = self.config.screenshot_key
+                    && event.logical_key == WKey::Named(key)
+                {
+                    if event.state == ElementState::Pressed && !event.repeat {
+                        self.capture_requested = true;
+                        self.pacer.invalidate();
+                        if let Some(w) = self.window.as_ref() {
+                            w.request_redraw();
+                        }
+                    }
+                    return;
+                }
+                let paste =
+                    self.config.paste && input::is_paste_key(&event.logical_key, self.modifiers);
+                let msg = if paste {
+                    // The shortcut's release is dropped with it.
+                    (event.state == ElementState::Pressed)
+                        .then(clipboard::paste_text)
+                        .flatten()
+                        .map(Msg::Paste)
+                } else {
+                    input::translate_keyboard(&event)
+                };
+                if let Some(msg) = msg {
                     self.runner.handle_msg(msg);
                     if self.runner.should_quit() {
                         event_loop.exit();
                         return;
                     }
                     self.render();
-                    if let Some(w) = self.window.as_ref() {
-                        w.request_redraw();
-                    }
                 }
             }
 
@@ -649,7 +966,8 @@ impl ApplicationHandler for WgpuApp {
                 button,
                 ..
             } => {
-                if let Some(msg) = input::translate_mouse_button(btn_state, button, cell_w, cell_h)
+                if let Some(msg) =
+                    input::translate_mouse_button(btn_state, button, &mut self.held, &self.mouse)
                 {
                     self.runner.handle_msg(msg);
                     if self.runner.should_quit() {
@@ -661,14 +979,29 @@ impl ApplicationHandler for WgpuApp {
             }
 
             WindowEvent::CursorMoved { position, .. } => {
-                if let Some(msg) = input::translate_cursor_moved(position, cell_w, cell_h) {
+                let mut moved = false;
+                for msg in
+                    input::translate_cursor_moved(position, layout, self.held, &mut self.mouse)
+                {
+                    self.runner.handle_msg(msg);
+                    moved = true;
+                }
+                if moved {
+                    self.render();
+                }
+            }
+
+            // The cursor position is unknown on entry: `Enter` is sent with
+            // the first `CursorMoved` over the grid.
+            WindowEvent::CursorLeft { .. } => {
+                if let Some(msg) = input::translate_cursor_left(&mut self.mouse) {
                     self.runner.handle_msg(msg);
                     self.render();
                 }
             }
 
             WindowEvent::MouseWheel { delta, .. } => {
-                if let Some(msg) = input::translate_mouse_wheel(delta, cell_w, cell_h) {
+                if let Some(msg) = input::translate_mouse_wheel(delta, &self.mouse) {
                     self.runner.handle_msg(msg);
                     if self.runner.should_quit() {
                         event_loop.exit();
@@ -681,6 +1014,63 @@ impl ApplicationHandler for WgpuApp {
             _ => {}
         }
     }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Background effects report through a channel the event loop cannot
+        // see, so wake up regularly to drain it.
+        self.runner.process_pending_msgs();
+        let visible = self.visibility.is_visible();
+        if visible
+            && self.window.is_some()
+            && let Some(frame) = self.frames.due(Instant::now())
+        {
+            self.runner.handle_msg(frame);
+        }
+        if self.runner.should_quit() {
+            event_loop.exit();
+            return;
+        }
+        #[cfg(feature = "hot-reload")]
+        self.reload_post_shader();
+        self.render();
+
+        // Check on an in-flight screenshot until it lands.
+        if let (Some(capture), Some(gpu)) = (self.capture.take(), self.gpu.as_ref()) {
+            self.capture = capture.poll(&gpu.device);
+        }
+        if let Some(stats) = self.pacer.tick(Instant::now())
+            && let Some(hook) = self.config.on_frame_stats.as_mut()
+        {
+            hook(stats);
+        }
+        if self.pacer.mode() == RenderMode::Continuous
+            && visible
+            && let Some(w) = self.window.as_ref()
+        {
+            w.request_redraw();
+        }
+        // Wake up for the next timer or frame too, if it comes sooner, and
+        // at the usual pace while a continuation runs, even hidden: frames
+        // are drawn then, only not presented.
+        let interval = if self.runner.is_continuing() {
+            POLL_INTERVAL
+        } else {
+            self.visibility.poll_interval(POLL_INTERVAL)
+        };
+        let mut wake = Instant::now() + interval;
+        if let Some(tick) = self.runner.next_tick() {
+            wake = wake.min(tick);
+        }
+        if visible && let Some(frame) = self.frames.next() {
+            wake = wake.min(frame);
+        }
+        event_loop.set_control_flow(ControlFlow::WaitUntil(wake));
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        // Every exit path (model End, window close) ends up here.
+        self.runner.shutdown();
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -729,3 +1119,184 @@ mod waker_fn {
         drop(unsafe { Arc::from_raw(ptr as *const F) });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::{Cell, ColorDepth, Platform};
+
+    struct Square;
+
+    impl TileManager for Square {
+        fn tile_size(&self) -> (usize, usize) {
+            (16, 16)
+        }
+
+        fn get_tile(&self, _cell: &Cell) -> Option<&[u8]> {
+            None
+        }
+    }
+
+    #[test]
+    fn capabilities_from_config() {
+        let caps = WgpuDriver::new(Default::default()).capabilities();
+        assert_eq!(caps.platform, Platform::Desktop);
+        assert_eq!(caps.color_depth, ColorDepth::TrueColor);
+        assert!(
+            caps.mouse && caps.animation_ticks && caps.key_release && !caps.tiles && caps.clipboard
+        );
+        // The built-in font has tall cells.
+        assert!(
+            caps.cell_aspect > 0.4 && caps.cell_aspect < 0.8,
+            "{}",
+            caps.cell_aspect
+        );
+
+        let caps = WgpuDriver::new(WgpuConfig {
+            tile_manager: Some(Box::new(Square)),
+            ..Default::default()
+        })
+        .capabilities();
+        assert!(caps.tiles);
+        assert_eq!(caps.cell_aspect, 1.0);
+    }
+
+    /// Draws a `#` in the top-left cell once a key was pressed.
+    struct Static {
+        pressed: bool,
+    }
+
+    impl gruid_core::app::Model for Static {
+        fn update(&mut self, msg: Msg) -> Option<gruid_core::app::Effect> {
+            if let Msg::KeyDown { .. } = msg {
+                self.pressed = true;
+            }
+            None
+        }
+
+        fn draw(&self, grid: &mut gruid_core::Grid) {
+            let ch = if self.pressed { '#' } else { '.' };
+            grid.fill(Cell::default().with_char('.'));
+            grid.set(gruid_core::Point::new(0, 0), Cell::default().with_char(ch));
+        }
+    }
+
+    /// Run one iteration of the render loop without a GPU, as if every due
+    /// frame were presented. Returns whether one was.
+    fn frame(app: &mut WgpuApp) -> bool {
+        let due = app.update();
+        if due {
+            app.pacer.rendered();
+        }
+        due
+    }
+
+    #[test]
+    fn idle_model_renders_nothing() {
+        let t0 = Instant::now();
+        let runner = AppRunner::new(Box::new(Static { pressed: false }), 10, 4);
+        let mut app = WgpuApp::new(Default::default(), runner, WindowTitle::default());
+        app.renderer = Some(GridRenderer::new(None, 18.0, 10, 4, None, 1));
+        app.pacer = Pacer::new(RenderMode::OnChange, t0);
+        app.runner.init();
+        assert!(frame(&mut app), "the first frame is always rendered");
+
+        // A second of idle loop iterations, with messages that change
+        // nothing on screen.
+        for i in 0..60 {
+            if i % 10 == 0 {
+                app.runner.handle_msg(Msg::Screen {
+                    width: 10,
+                    height: 4,
+                    time: Instant::now(),
+                });
+            }
+            assert!(!frame(&mut app));
+        }
+        let stats = app.pacer.tick(t0 + Duration::from_secs(1)).unwrap();
+        assert_eq!(stats.renders, 1);
+
+        app.runner
+            .handle_msg(Msg::key(gruid_core::messages::Key::Char('x')));
+        assert!(frame(&mut app));
+        assert!(!frame(&mut app));
+        let stats = app.pacer.tick(t0 + Duration::from_secs(2)).unwrap();
+        assert_eq!((stats.renders, stats.total), (1, 2));
+    }
+
+    /// Records the visibility messages it gets.
+    struct Pausing {
+        pressed: bool,
+        seen: std::rc::Rc<std::cell::RefCell<Vec<Visibility>>>,
+    }
+
+    impl gruid_core::app::Model for Pausing {
+        fn update(&mut self, msg: Msg) -> Option<gruid_core::app::Effect> {
+            if let Some(v) = msg.visibility() {
+                self.seen.borrow_mut().push(v);
+            } else if let Msg::KeyDown { .. } = msg {
+                self.pressed = !self.pressed;
+            }
+            None
+        }
+
+        fn draw(&self, grid: &mut gruid_core::Grid) {
+            let ch = if self.pressed { '#' } else { '.' };
+            grid.set(gruid_core::Point::new(0, 0), Cell::default().with_char(ch));
+        }
+    }
+
+    #[test]
+    fn hidden_window_renders_nothing() {
+        let t0 = Instant::now();
+        let seen = std::rc::Rc::default();
+        let model = Pausing {
+            pressed: false,
+            seen: std::rc::Rc::clone(&seen),
+        };
+        let runner = AppRunner::new(Box::new(model), 10, 4);
+        let config = WgpuConfig {
+            pause_on_occlusion: true,
+            ..Default::default()
+        };
+        let mut app = WgpuApp::new(config, runner, WindowTitle::default());
+        app.renderer = Some(GridRenderer::new(None, 18.0, 10, 4, None, 1));
+        app.pacer = Pacer::new(RenderMode::OnChange, t0);
+        app.runner.init();
+        assert!(frame(&mut app));
+
+        let changed = app.visibility.set_occluded(true);
+        app.set_visibility(changed);
+        let changed = app.visibility.resized(0, 0);
+        app.set_visibility(changed);
+        assert_eq!(*seen.borrow(), [Visibility::Hidden]);
+        // Input still reaches the model and changes the display, but nothing
+        // is rendered.
+        for _ in 0..11 {
+            app.runner
+                .handle_msg(Msg::key(gruid_core::messages::Key::Char('x')));
+            assert!(!frame(&mut app));
+        }
+        assert!(!app.runner.needs_draw());
+
+        let changed = app.visibility.resized(160, 96);
+        app.set_visibility(changed);
+        assert!(!frame(&mut app), "still occluded");
+        let changed = app.visibility.set_occluded(false);
+        app.set_visibility(changed);
+        assert_eq!(*seen.borrow(), [Visibility::Hidden, Visibility::Visible]);
+        assert!(frame(&mut app), "one full redraw on restore");
+        assert!(!frame(&mut app));
+        let stats = app.pacer.tick(t0 + Duration::from_secs(1)).unwrap();
+        assert_eq!(stats.renders, 2);
+    }
+
+    #[test]
+    fn colours_are_rendered_without_srgb_conversion() {
+        use wgpu::TextureFormat as F;
+        let both = [F::Bgra8UnormSrgb, F::Bgra8Unorm];
+        assert_eq!(surface_formats(&both), (F::Bgra8Unorm, F::Bgra8Unorm));
+        let srgb = [F::Rgba8UnormSrgb];
+        assert_eq!(surface_formats(&srgb), (F::Rgba8UnormSrgb, F::Rgba8Unorm));
+    }
+}
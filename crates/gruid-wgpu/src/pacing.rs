@@ -0,0 +1,152 @@
+//! Deciding when to render, and counting renders.
+//!
+//! In [`RenderMode::OnChange`] the driver only encodes and presents a frame
+//! when something on screen may have changed: a frame from the model, a
+//! resize, or a redraw requested by the window system. Identical frames are
+//! never submitted, so an idle turn-based game leaves the GPU alone.
+
+use std::time::{Duration, Instant};
+
+/// When the wgpu driver renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// Only when the display changed. Frames are presented with at most one
+    /// frame of latency, so a change is never shown late behind queued
+    /// stale frames.
+    #[default]
+    OnChange,
+    /// Every vertical blank, whether anything changed or not.
+    Continuous,
+}
+
+impl RenderMode {
+    /// Value for [`wgpu::SurfaceConfiguration::desired_maximum_frame_latency`].
+    pub(crate) fn frame_latency(self) -> u32 {
+        match self {
+            Self::OnChange => 1,
+            Self::Continuous => 2,
+        }
+    }
+}
+
+/// Render counts over a reporting interval, passed to
+/// [`WgpuConfig::on_frame_stats`](crate::WgpuConfig::on_frame_stats).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    /// Frames presented during the interval.
+    pub renders: u32,
+    /// Length of the interval.
+    pub interval: Duration,
+    /// Frames presented since the driver started.
+    pub total: u64,
+}
+
+impl FrameStats {
+    /// Frames presented per second over the interval.
+    pub fn renders_per_second(&self) -> f64 {
+        let secs = self.interval.as_secs_f64();
+        if secs > 0.0 {
+            self.renders as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// How often [`FrameStats`] are reported.
+pub(crate) const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tracks whether the surface is stale and counts presented frames.
+pub(crate) struct Pacer {
+    mode: RenderMode,
+    /// Something changed since the last presented frame.
+    stale: bool,
+    renders: u32,
+    total: u64,
+    since: Instant,
+}
+
+impl Pacer {
+    /// A pacer whose first frame is due: the surface starts out blank.
+    pub(crate) fn new(mode: RenderMode, now: Instant) -> Self {
+        Self {
+            mode,
+            stale: true,
+            renders: 0,
+            total: 0,
+            since: now,
+        }
+    }
+
+    pub(crate) fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    /// Note that the display changed and must be presented again.
+    pub(crate) fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// Whether a frame should be rendered now.
+    pub(crate) fn due(&self) -> bool {
+        self.stale || self.mode == RenderMode::Continuous
+    }
+
+    /// Record a presented frame.
+    pub(crate) fn rendered(&mut self) {
+        self.stale = false;
+        self.renders += 1;
+        self.total += 1;
+    }
+
+    /// Stats for the interval just ended, once [`STATS_INTERVAL`] has
+    /// passed since the previous report.
+    pub(crate) fn tick(&mut self, now: Instant) -> Option<FrameStats> {
+        let interval = now.saturating_duration_since(self.since);
+        if interval < STATS_INTERVAL {
+            return None;
+        }
+        let stats = FrameStats {
+            renders: std::mem::take(&mut self.renders),
+            interval,
+            total: self.total,
+        };
+        self.since = now;
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_change_renders_once_per_invalidation() {
+        let t0 = Instant::now();
+        let mut p = Pacer::new(RenderMode::OnChange, t0);
+        assert!(p.due());
+        p.rendered();
+        assert!(!p.due());
+        p.invalidate();
+        p.invalidate();
+        assert!(p.due());
+        p.rendered();
+        assert!(!p.due());
+
+        assert_eq!(p.tick(t0 + Duration::from_millis(500)), None);
+        let stats = p.tick(t0 + Duration::from_secs(2)).unwrap();
+        assert_eq!((stats.renders, stats.total), (2, 2));
+        assert_eq!(stats.renders_per_second(), 1.0);
+        let stats = p.tick(t0 + Duration::from_secs(3)).unwrap();
+        assert_eq!((stats.renders, stats.total), (0, 2));
+    }
+
+    #[test]
+    fn continuous_is_always_due() {
+        let mut p = Pacer::new(RenderMode::Continuous, Instant::now());
+        p.rendered();
+        assert!(p.due());
+        assert_eq!(RenderMode::Continuous.frame_latency(), 2);
+        assert_eq!(RenderMode::OnChange.frame_latency(), 1);
+    }
+}
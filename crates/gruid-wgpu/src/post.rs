@@ -0,0 +1,675 @@
+//! Optional post-processing pass over the composed grid.
+//!
+//! With a [`PostShader`] the grid is rendered into an intermediate texture
+//! the size of the surface, and a full-screen fragment shader then samples
+//! it into the swapchain. Every post shader is completed by a prelude
+//! (`post_prelude.wgsl`) declaring its inputs, so a custom one only defines
+//! `fs_post`:
+//!
+//! ```wgsl
+//! @fragment
+//! fn fs_post(in: PostIn) -> @location(0) vec4<f32> {
+//!     // Tint the grid green, by the first parameter.
+//!     let color = scene_at(in.uv).rgb;
+//!     return vec4<f32>(color * vec3<f32>(1.0 - param(0), 1.0, 1.0 - param(0)), 1.0);
+//! }
+//! ```
+//!
+//! `in.pos` is the pixel position in the output, and `post.size` holds the
+//! output size (`xy`) and the cell size (`zw`) in pixels. `post.time.x`
+//! counts seconds since the driver started; it only advances between
+//! rendered frames, so animated effects want [`RenderMode::Continuous`].
+//!
+//! Shaders are checked when loaded. A shader that does not load or compile
+//! is logged as an error and the grid is rendered without post-processing.
+//!
+//! [`RenderMode::Continuous`]: crate::RenderMode::Continuous
+
+use std::borrow::Cow;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::naga;
+
+const PRELUDE: &str = include_str!("post_prelude.wgsl");
+const SCANLINES: &str = include_str!("scanlines.wgsl");
+const CRT: &str = include_str!("crt.wgsl");
+
+/// Most parameters a post shader can take.
+pub const MAX_POST_UNIFORMS: usize = 16;
+
+/// A post-processing shader, see
+/// [`WgpuConfig::post_shader`](crate::WgpuConfig::post_shader).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum PostShader {
+    /// No post-processing, the same as no post shader at all.
+    #[default]
+    None,
+    /// Darkened lines across the screen.
+    Scanlines {
+        /// From 0 (off) to 1 (black lines).
+        strength: f32,
+    },
+    /// A curved screen with darkened corners.
+    Crt {
+        /// From 0 (flat) to about 1.
+        curvature: f32,
+        /// From 0 (none) to 1 (black corners).
+        vignette: f32,
+    },
+    /// WGSL source defining `fs_post`, and the values of its parameters.
+    Custom {
+        wgsl_source: String,
+        uniforms: Vec<f32>,
+    },
+    /// Like [`Custom`](Self::Custom), with the source read from a file
+    /// when the driver starts. With the `hot-reload` feature, the shader
+    /// is compiled again whenever the file changes.
+    File { path: PathBuf, uniforms: Vec<f32> },
+}
+
+impl PostShader {
+    /// Load and compile the shader, without a GPU: useful to check a
+    /// custom shader in a test.
+    pub fn validate(&self) -> Result<(), PostShaderError> {
+        self.load().map(drop)
+    }
+
+    /// The complete source and parameters of the pass, `None` for no pass.
+    pub(crate) fn load(&self) -> Result<Option<PostSource>, PostShaderError> {
+        let (body, params): (Cow<str>, &[f32]) = match self {
+            Self::None => return Ok(None),
+            Self::Scanlines { strength } => (SCANLINES.into(), &[*strength]),
+            Self::Crt {
+                curvature,
+                vignette,
+            } => (CRT.into(), &[*curvature, *vignette]),
+            Self::Custom {
+                wgsl_source,
+                uniforms,
+            } => (wgsl_source.into(), uniforms),
+            Self::File { path, uniforms } => {
+                let source =
+                    std::fs::read_to_string(path).map_err(|error| PostShaderError::Io {
+                        path: path.clone(),
+                        error,
+                    })?;
+                (source.into(), uniforms)
+            }
+        };
+        PostSource::new(&body, params).map(Some)
+    }
+}
+
+/// Why a post shader could not be used.
+#[derive(Debug)]
+pub enum PostShaderError {
+    /// The shader file could not be read.
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The shader did not compile. Holds the compiler's diagnostic.
+    Compile(String),
+    /// More than [`MAX_POST_UNIFORMS`] parameters were given.
+    TooManyUniforms(usize),
+}
+
+impl fmt::Display for PostShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => {
+                write!(f, "cannot read post shader {}: {error}", path.display())
+            }
+            Self::Compile(diagnostic) => write!(f, "post shader does not compile:\n{diagnostic}"),
+            Self::TooManyUniforms(n) => write!(
+                f,
+                "post shader has {n} uniforms, at most {MAX_POST_UNIFORMS} are supported"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PostShaderError {}
+
+/// The checked source of a post pass.
+#[derive(Debug, Clone)]
+pub(crate) struct PostSource {
+    /// The shader followed by the prelude.
+    pub wgsl: String,
+    pub params: Vec<f32>,
+}
+
+impl PostSource {
+    fn new(body: &str, params: &[f32]) -> Result<Self, PostShaderError> {
+        if params.len() > MAX_POST_UNIFORMS {
+            return Err(PostShaderError::TooManyUniforms(params.len()));
+        }
+        let wgsl = format!("{body}\n{PRELUDE}");
+        check(&wgsl)?;
+        Ok(Self {
+            wgsl,
+            params: params.to_vec(),
+        })
+    }
+}
+
+/// Parse and validate `wgsl` as wgpu would, and check it has `fs_post`.
+fn check(wgsl: &str) -> Result<(), PostShaderError> {
+    const PATH: &str = "post shader";
+    let module = naga::front::wgsl::parse_str(wgsl)
+        .map_err(|e| PostShaderError::Compile(e.emit_to_string_with_path(wgsl, PATH)))?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::default(),
+    )
+    .validate(&module)
+    .map_err(|e| PostShaderError::Compile(e.emit_to_string_with_path(wgsl, PATH)))?;
+    let has_entry = module
+        .entry_points
+        .iter()
+        .any(|e| e.name == "fs_post" && e.stage == naga::ShaderStage::Fragment);
+    if !has_entry {
+        return Err(PostShaderError::Compile(
+            "missing `@fragment fn fs_post(in: PostIn) -> @location(0) vec4<f32>`".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Load the configured shader. Errors are logged, and mean no pass.
+pub(crate) fn load_or_log(shader: Option<&PostShader>) -> Option<PostSource> {
+    shader?
+        .load()
+        .inspect_err(|err| log::error!("{err}; rendering without post-processing"))
+        .ok()
+        .flatten()
+}
+
+// ---------------------------------------------------------------------------
+// GPU types (must match post_prelude.wgsl)
+// ---------------------------------------------------------------------------
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Pod, Zeroable)]
+pub(crate) struct PostUniforms {
+    pub size: [f32; 4], // output w, h, cell w, h
+    pub time: [f32; 4], // seconds, unused
+    pub params: [[f32; 4]; MAX_POST_UNIFORMS / 4],
+}
+
+impl PostUniforms {
+    pub fn pack(output: (u32, u32), cell: (usize, usize), time: f32, params: &[f32]) -> Self {
+        let mut packed = [[0.0; 4]; MAX_POST_UNIFORMS / 4];
+        for (i, &p) in params.iter().take(MAX_POST_UNIFORMS).enumerate() {
+            packed[i / 4][i % 4] = p;
+        }
+        Self {
+            size: [
+                output.0 as f32,
+                output.1 as f32,
+                cell.0 as f32,
+                cell.1 as f32,
+            ],
+            time: [time, 0.0, 0.0, 0.0],
+            params: packed,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// PostPass
+// ---------------------------------------------------------------------------
+
+/// The intermediate texture the grid is rendered to, and the pipeline
+/// drawing it to the surface.
+pub(crate) struct PostPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    target_view: wgpu::TextureView,
+    format: wgpu::TextureFormat,
+    size: (u32, u32),
+    params: Vec<f32>,
+    start: Instant,
+}
+
+impl PostPass {
+    /// Set up a pass drawing to a surface of `format` and `size`. Fails if
+    /// wgpu rejects the shader.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: (u32, u32),
+        source: PostSource,
+    ) -> Result<Self, PostShaderError> {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post bgl"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline = create_pipeline(device, &bind_group_layout, format, &source.wgsl)?;
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("post uniforms"),
+            size: std::mem::size_of::<PostUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Linear, as distortions sample between texels.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let (target_view, bind_group) = create_target(
+            device,
+            &bind_group_layout,
+            &uniform_buffer,
+            &sampler,
+            format,
+            size,
+        );
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            bind_group,
+            uniform_buffer,
+            sampler,
+            target_view,
+            format,
+            size,
+            params: source.params,
+            start: Instant::now(),
+        })
+    }
+
+    /// Replace the shader. On error the current one is kept.
+    #[cfg(feature = "hot-reload")]
+    pub fn reload(
+        &mut self,
+        device: &wgpu::Device,
+        source: PostSource,
+    ) -> Result<(), PostShaderError> {
+        self.pipeline =
+            create_pipeline(device, &self.bind_group_layout, self.format, &source.wgsl)?;
+        self.params = source.params;
+        Ok(())
+    }
+
+    /// Follow a resize of the surface.
+    pub fn resize(&mut self, device: &wgpu::Device, size: (u32, u32)) {
+        if size == self.size {
+            return;
+        }
+        self.size = size;
+        (self.target_view, self.bind_group) = create_target(
+            device,
+            &self.bind_group_layout,
+            &self.uniform_buffer,
+            &self.sampler,
+            self.format,
+            size,
+        );
+    }
+
+    /// Where the grid is rendered.
+    pub fn target(&self) -> &wgpu::TextureView {
+        &self.target_view
+    }
+
+    /// Draw the grid rendered to [`target`](Self::target) to `output`.
+    pub fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        cell: (usize, usize),
+    ) {
+        let time = self.start.elapsed().as_secs_f32();
+        let uniforms = PostUniforms::pack(self.size, cell, time, &self.params);
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("gruid-wgpu post pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+/// Build the pipeline, catching the errors wgpu would otherwise panic on.
+fn create_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+    wgsl: &str,
+) -> Result<wgpu::RenderPipeline, PostShaderError> {
+    let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("post shader"),
+        source: wgpu::ShaderSource::Wgsl(wgsl.into()),
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("post pipeline layout"),
+        bind_group_layouts: &[bind_group_layout],
+        immediate_size: 0,
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("post pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_post"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_post"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        multiview_mask: None,
+        cache: None,
+    });
+    match crate::pollster_block_on(scope.pop()) {
+        Some(err) => Err(PostShaderError::Compile(err.to_string())),
+        None => Ok(pipeline),
+    }
+}
+
+/// The intermediate texture, and the bind group sampling it.
+fn create_target(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+    sampler: &wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    (width, height): (u32, u32),
+) -> (wgpu::TextureView, wgpu::BindGroup) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("post target"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("post bg"),
+        layout: bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    });
+    (view, bind_group)
+}
+
+// ---------------------------------------------------------------------------
+// Hot reload
+// ---------------------------------------------------------------------------
+
+/// Watches the file of a [`PostShader::File`] by polling its modification
+/// time, without a dependency on a file system notification crate.
+#[cfg(feature = "hot-reload")]
+pub(crate) struct ShaderWatcher {
+    shader: PostShader,
+    modified: Option<std::time::SystemTime>,
+    next_check: Instant,
+}
+
+#[cfg(feature = "hot-reload")]
+impl ShaderWatcher {
+    /// How often the file is checked.
+    const INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+    /// A watcher for `shader`, if it is a file.
+    pub fn new(shader: Option<&PostShader>, now: Instant) -> Option<Self> {
+        let shader = shader.filter(|s| matches!(s, PostShader::File { .. }))?;
+        let mut watcher = Self {
+            shader: shader.clone(),
+            modified: None,
+            next_check: now,
+        };
+        watcher.modified = watcher.modified_time();
+        Some(watcher)
+    }
+
+    fn modified_time(&self) -> Option<std::time::SystemTime> {
+        let PostShader::File { path, .. } = &self.shader else {
+            return None;
+        };
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// The new source, if the file changed and compiles. Errors are
+    /// logged, so the last good shader stays in use.
+    pub fn poll(&mut self, now: Instant) -> Option<PostSource> {
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + Self::INTERVAL;
+        let modified = self.modified_time();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        match self.shader.load() {
+            Ok(source) => {
+                log::info!("post shader reloaded");
+                source
+            }
+            Err(err) => {
+                log::error!("{err}; keeping the previous post shader");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TINT: &str = "
+@fragment
+fn fs_post(in: PostIn) -> @location(0) vec4<f32> {
+    return vec4<f32>(scene_at(in.uv).rgb * param(0), 1.0);
+}
+";
+
+    fn custom(wgsl: &str, uniforms: Vec<f32>) -> PostShader {
+        PostShader::Custom {
+            wgsl_source: wgsl.into(),
+            uniforms,
+        }
+    }
+
+    #[test]
+    fn uniform_packing() {
+        let params: Vec<f32> = (1..=6).map(|i| i as f32).collect();
+        let u = PostUniforms::pack((640, 480), (9, 18), 1.5, &params);
+        assert_eq!(u.size, [640.0, 480.0, 9.0, 18.0]);
+        assert_eq!(u.time, [1.5, 0.0, 0.0, 0.0]);
+        assert_eq!(u.params[0], [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(u.params[1], [5.0, 6.0, 0.0, 0.0]);
+        assert_eq!(u.params[2..], [[0.0; 4]; 2]);
+        // Laid out as the prelude declares it: two vec4 then the array.
+        assert_eq!(std::mem::size_of::<PostUniforms>(), 6 * 16);
+        let floats: &[f32] = bytemuck::cast_slice(std::slice::from_ref(&u));
+        assert_eq!(floats[8..14], params[..]);
+
+        assert!(matches!(
+            custom(TINT, vec![0.0; MAX_POST_UNIFORMS + 1]).validate(),
+            Err(PostShaderError::TooManyUniforms(17))
+        ));
+        assert!(
+            custom(TINT, vec![0.0; MAX_POST_UNIFORMS])
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn preset_selection() {
+        assert!(PostShader::None.load().unwrap().is_none());
+        assert!(load_or_log(None).is_none());
+
+        let scan = PostShader::Scanlines { strength: 0.4 }
+            .load()
+            .unwrap()
+            .unwrap();
+        assert!(scan.wgsl.starts_with(SCANLINES));
+        assert_eq!(scan.params, [0.4]);
+        let crt = PostShader::Crt {
+            curvature: 0.3,
+            vignette: 0.6,
+        };
+        let crt = load_or_log(Some(&crt)).unwrap();
+        assert!(crt.wgsl.starts_with(CRT));
+        assert!(crt.wgsl.ends_with(PRELUDE));
+        assert_eq!(crt.params, [0.3, 0.6]);
+        let tint = custom(TINT, vec![0.5]).load().unwrap().unwrap();
+        assert_eq!(tint.params, [0.5]);
+    }
+
+    #[test]
+    fn broken_shaders_fall_back() {
+        let typo = custom(&TINT.replace("scene_at", "scene_att"), vec![]);
+        let Err(PostShaderError::Compile(msg)) = typo.validate() else {
+            panic!("should not compile");
+        };
+        // Reported against the shader's own lines, not the prelude's.
+        assert!(
+            msg.contains("scene_att") && msg.contains("post shader:4"),
+            "{msg}"
+        );
+        assert!(load_or_log(Some(&typo)).is_none());
+
+        let unnamed = custom(&TINT.replace("fs_post", "main"), vec![]);
+        assert!(matches!(
+            unnamed.validate(),
+            Err(PostShaderError::Compile(msg)) if msg.contains("fs_post")
+        ));
+        let missing = PostShader::File {
+            path: "no/such/shader.wgsl".into(),
+            uniforms: vec![],
+        };
+        assert!(matches!(
+            missing.validate(),
+            Err(PostShaderError::Io { .. })
+        ));
+        assert!(load_or_log(Some(&missing)).is_none());
+    }
+
+    #[cfg(feature = "hot-reload")]
+    #[test]
+    fn reload_keeps_last_good_shader() {
+        use std::time::{Duration, SystemTime};
+
+        let path = std::env::temp_dir().join(format!("gruid-post-{}.wgsl", std::process::id()));
+        let write = |src: &str, age: u64| {
+            std::fs::write(&path, src).unwrap();
+            let mtime = SystemTime::now() - Duration::from_secs(age);
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        };
+        write(TINT, 30);
+        let shader = PostShader::File {
+            path: path.clone(),
+            uniforms: vec![1.0],
+        };
+        let t0 = Instant::now();
+        let mut watcher = ShaderWatcher::new(Some(&shader), t0).unwrap();
+        assert!(watcher.poll(t0).is_none(), "unchanged");
+
+        write(&TINT.replace("param(0)", "param(1"), 20);
+        assert!(watcher.poll(t0).is_none(), "too soon");
+        let t1 = t0 + ShaderWatcher::INTERVAL;
+        assert!(watcher.poll(t1).is_none(), "broken");
+
+        write(&TINT.replace("param(0)", "param(1)"), 10);
+        let t2 = t1 + ShaderWatcher::INTERVAL;
+        let source = watcher.poll(t2).unwrap();
+        assert!(source.wgsl.contains("param(1)"));
+        assert_eq!(source.params, [1.0]);
+        assert!(watcher.poll(t2 + ShaderWatcher::INTERVAL).is_none());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(ShaderWatcher::new(Some(&PostShader::None), t0).is_none());
+    }
+}
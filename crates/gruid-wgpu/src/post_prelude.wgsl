@@ -0,0 +1,50 @@
+// Post-processing prelude, appended to every post shader so that line
+// numbers in compilation errors are those of the shader itself.
+//
+// The composed grid is in `scene`, the size of the output. A post shader
+// defines the fragment entry point
+//
+//     @fragment
+//     fn fs_post(in: PostIn) -> @location(0) vec4<f32>
+//
+// samples the grid with `scene_at(uv)` and reads its parameters with
+// `param(i)`.
+
+struct PostUniforms {
+    // x, y: output size in pixels; z, w: cell size in pixels
+    size: vec4<f32>,
+    // x: seconds since the driver started
+    time: vec4<f32>,
+    // the user parameters, four per vector
+    params: array<vec4<f32>, 4>,
+};
+
+@group(0) @binding(0) var<uniform> post: PostUniforms;
+@group(0) @binding(1) var scene: texture_2d<f32>;
+@group(0) @binding(2) var scene_sampler: sampler;
+
+struct PostIn {
+    @builtin(position) pos: vec4<f32>,
+    // 0,0 at the top left of the output, 1,1 at the bottom right
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle covering the whole output.
+@vertex
+fn vs_post(@builtin(vertex_index) vi: u32) -> PostIn {
+    let uv = vec2<f32>(f32((vi << 1u) & 2u), f32(vi & 2u));
+    var out: PostIn;
+    out.pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// The i-th parameter, 0 if there are fewer.
+fn param(i: u32) -> f32 {
+    return post.params[i / 4u][i % 4u];
+}
+
+// The grid colour at `uv`, clamped to the edges.
+fn scene_at(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(scene, scene_sampler, uv, 0.0);
+}
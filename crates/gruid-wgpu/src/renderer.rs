@@ -9,11 +9,15 @@
 //! texture atlas. Custom tiles from a [`TileManager`] are also packed into
 //! the atlas.
 
-use std::collections::HashMap;
-
 use bytemuck::{Pod, Zeroable};
-use fontdue::{Font, FontSettings};
-use gruid_core::{Cell, grid::Frame, style::Color};
+use gruid_core::{Cell, DriverCaps, Style, grid::Frame};
+use gruid_graphics_common::{
+    atlas::{AtlasKey, GlyphAtlas},
+    capabilities,
+    font::CellFont,
+    input::GridLayout,
+    tiles::Tiles,
+};
 
 use crate::TileManager;
 
@@ -39,113 +43,12 @@ pub(crate) struct CellInstance {
     pub atlas_rect: [f32; 4], // x, y, w, h in texels (w=0 means no glyph)
 }
 
-// ---------------------------------------------------------------------------
-// Atlas
-// ---------------------------------------------------------------------------
-
-struct AtlasEntry {
-    x: u32,
-    y: u32,
-    w: u32,
-    h: u32,
-}
-
-pub(crate) struct GlyphAtlas {
-    pub width: u32,
-    pub height: u32,
-    pub data: Vec<u8>, // single-channel (R8)
-    entries: HashMap<AtlasKey, AtlasEntry>,
-    cursor_x: u32,
-    cursor_y: u32,
-    row_height: u32,
-}
-
-#[derive(Hash, Eq, PartialEq, Clone)]
-enum AtlasKey {
-    Char(char),
-    // Tile(char, u32, u32), // reserved for future tile-variant caching
-}
-
-const FALLBACK_FONT: &[u8] = include_bytes!("../../gruid-winit/src/builtin_font.ttf");
-
-impl GlyphAtlas {
-    fn new(width: u32, height: u32) -> Self {
-        Self {
-            width,
-            height,
-            data: vec![0; (width * height) as usize],
-            entries: HashMap::new(),
-            cursor_x: 0,
-            cursor_y: 0,
-            row_height: 0,
-        }
-    }
-
-    /// Insert a glyph bitmap into the atlas. Returns the rect.
-    fn insert(&mut self, key: AtlasKey, bitmap: &[u8], w: u32, h: u32) -> AtlasEntry {
-        if let Some(e) = self.entries.get(&key) {
-            return AtlasEntry {
-                x: e.x,
-                y: e.y,
-                w: e.w,
-                h: e.h,
-            };
-        }
-
-        // Advance to next row if needed
-        if self.cursor_x + w > self.width {
-            self.cursor_x = 0;
-            self.cursor_y += self.row_height;
-            self.row_height = 0;
-        }
-
-        // Grow atlas if needed (double height)
-        while self.cursor_y + h > self.height {
-            let old_h = self.height;
-            self.height *= 2;
-            self.data.resize((self.width * self.height) as usize, 0);
-            log::debug!(
-                "Atlas grew to {}x{} (was {})",
-                self.width,
-                old_h,
-                self.height
-            );
-        }
-
-        let x = self.cursor_x;
-        let y = self.cursor_y;
-
-        // Copy bitmap into atlas
-        for row in 0..h {
-            let src_start = (row * w) as usize;
-            let dst_start = ((y + row) * self.width + x) as usize;
-            let len = w as usize;
-            if src_start + len <= bitmap.len() && dst_start + len <= self.data.len() {
-                self.data[dst_start..dst_start + len]
-                    .copy_from_slice(&bitmap[src_start..src_start + len]);
-            }
-        }
-
-        self.cursor_x += w;
-        self.row_height = self.row_height.max(h);
-
-        let entry = AtlasEntry { x, y, w, h };
-        self.entries.insert(key, AtlasEntry { x, y, w, h });
-        entry
-    }
-
-    fn get(&self, key: &AtlasKey) -> Option<&AtlasEntry> {
-        self.entries.get(key)
-    }
-}
-
 // ---------------------------------------------------------------------------
 // GridRenderer
 // ---------------------------------------------------------------------------
 
 pub(crate) struct GridRenderer {
-    font: Font,
-    font_size: f32,
+    font: CellFont,
     pub cell_width: usize,
     pub cell_height: usize,
     pub cols: usize,
@@ -157,8 +60,7 @@ pub(crate) struct GridRenderer {
     pub dirty: bool,
     /// Whether the atlas texture needs re-upload.
     pub atlas_dirty: bool,
-    tile_manager: Option<Box<dyn TileManager>>,
-    tile_scale: u32,
+    tiles: Option<Tiles>,
 }
 
 impl GridRenderer {
@@ -170,27 +72,11 @@ impl GridRenderer {
         tile_manager: Option<Box<dyn TileManager>>,
         tile_scale: u32,
     ) -> Self {
-        let tile_scale = tile_scale.max(1);
-        let data = font_data.unwrap_or(FALLBACK_FONT);
-        let font = Font::from_bytes(data, FontSettings::default()).expect("failed to parse font");
-
-        let (cell_width, cell_height) = if let Some(ref tm) = tile_manager {
-            let (tw, th) = tm.tile_size();
-            let s = tile_scale as usize;
-            ((tw * s).max(1), (th * s).max(1))
-        } else {
-            let metrics = font
-                .horizontal_line_metrics(font_size)
-                .unwrap_or(fontdue::LineMetrics {
-                    ascent: font_size * 0.8,
-                    descent: -(font_size * 0.2),
-                    line_gap: 0.0,
-                    new_line_size: font_size,
-                });
-            let ch = (metrics.ascent - metrics.descent).ceil() as usize;
-            let (m_metrics, _) = font.rasterize('M', font_size);
-            let cw = m_metrics.advance_width.ceil() as usize;
-            (cw.max(1), ch.max(1))
+        let font = CellFont::new(font_data, font_size);
+        let tiles = tile_manager.map(|tm| Tiles::new(tm, tile_scale));
+        let (cell_width, cell_height) = match &tiles {
+            Some(tiles) => tiles.cell_size(),
+            None => font.cell_size(),
         };
 
         let n = cols * rows;
@@ -200,8 +86,8 @@ impl GridRenderer {
                 let row = i / cols;
                 CellInstance {
                     grid_pos: [col as f32, row as f32],
-                    fg_color: pack_color(Color::DEFAULT, true),
-                    bg_color: pack_color(Color::DEFAULT, false),
+                    fg_color: pack_color(Style::default().rgb().0),
+                    bg_color: pack_color(Style::default().rgb().1),
                     atlas_rect: [0.0, 0.0, 0.0, 0.0],
                 }
             })
@@ -213,7 +99,6 @@ impl GridRenderer {
 
         Self {
             font,
-            font_size,
             cell_width,
             cell_height,
             cols,
@@ -222,11 +107,21 @@ impl GridRenderer {
             instances,
             dirty: true,
             atlas_dirty: true,
-            tile_manager,
-            tile_scale,
+            tiles,
         }
     }
 
+    /// Capabilities of a window drawing with this renderer.
+    pub fn capabilities(&self) -> DriverCaps {
+        let cell_size = (self.cell_width, self.cell_height);
+        capabilities(&self.font, cell_size, self.tiles.is_some())
+    }
+
+    /// Where the grid is in the window: always at the top-left corner.
+    pub fn layout(&self) -> GridLayout {
+        GridLayout::new(self.cell_width, self.cell_height)
+    }
+
     pub fn pixel_width(&self) -> usize {
         self.cols * self.cell_width
     }
@@ -243,8 +138,8 @@ impl GridRenderer {
             n,
             CellInstance {
                 grid_pos: [0.0, 0.0],
-                fg_color: pack_color(Color::DEFAULT, true),
-                bg_color: pack_color(Color::DEFAULT, false),
+                fg_color: pack_color(Style::default().rgb().0),
+                bg_color: pack_color(Style::default().rgb().1),
                 atlas_rect: [0.0, 0.0, 0.0, 0.0],
             },
         );
@@ -259,7 +154,7 @@ impl GridRenderer {
 
     /// Apply a frame diff.
     pub fn apply_frame(&mut self, frame: &Frame) {
-        let old_atlas_len = self.atlas.entries.len();
+        let old_atlas_len = self.atlas.len();
 
         for fc in &frame.cells {
             let col = fc.pos.x as usize;
@@ -270,112 +165,40 @@ impl GridRenderer {
             let idx = row * self.cols + col;
             let cell = &fc.cell;
 
-            self.instances[idx].fg_color = pack_color(cell.style.fg, true);
-            self.instances[idx].bg_color = pack_color(cell.style.bg, false);
+            let (fg, bg) = cell.style.rgb();
+            self.instances[idx].fg_color = pack_color(fg);
+            self.instances[idx].bg_color = pack_color(bg);
             self.instances[idx].atlas_rect = self.rasterize_cell(cell);
         }
 
         self.dirty = true;
-        if self.atlas.entries.len() != old_atlas_len {
+        if self.atlas.len() != old_atlas_len {
             self.atlas_dirty = true;
         }
     }
 
     /// Rasterize a cell's glyph/tile and return its atlas rect.
     fn rasterize_cell(&mut self, cell: &Cell) -> [f32; 4] {
-        // Try tile manager first
-        if let Some(ref tm) = self.tile_manager {
-            if let Some(bitmap) = tm.get_tile(cell) {
-                let (tw, th) = tm.tile_size();
-                let s = self.tile_scale as usize;
-                let scaled_w = tw * s;
-                let scaled_h = th * s;
-
-                // Scale the bitmap
-                let mut scaled = vec![0u8; scaled_w * scaled_h];
-                for ty in 0..th {
-                    for tx in 0..tw {
-                        let src_idx = ty * tw + tx;
-                        let alpha = if src_idx < bitmap.len() {
-                            bitmap[src_idx]
-                        } else {
-                            0
-                        };
-                        for sy in 0..s {
-                            for sx in 0..s {
-                                let di = (ty * s + sy) * scaled_w + (tx * s + sx);
-                                scaled[di] = alpha;
-                            }
-                        }
-                    }
-                }
-
-                let key = AtlasKey::Char(cell.ch); // tiles keyed by char
-                let entry = self
-                    .atlas
-                    .insert(key, &scaled, scaled_w as u32, scaled_h as u32);
-                return [
-                    entry.x as f32,
-                    entry.y as f32,
-                    entry.w as f32,
-                    entry.h as f32,
-                ];
-            }
-        }
-
-        let ch = cell.ch;
-        if ch == ' ' || ch == '\0' {
-            return [0.0, 0.0, 0.0, 0.0];
+        // Try tiles first
+        if let Some(tile) = self.tiles.as_ref().and_then(|t| t.tile(cell)) {
+            let key = AtlasKey::Char(cell.ch); // tiles keyed by char
+            let (w, h) = (tile.width() as u32, tile.height() as u32);
+            return self.atlas.insert(key, &tile.to_bitmap(), w, h).rect();
         }
 
         // Check atlas cache
-        let key = AtlasKey::Char(ch);
+        let key = AtlasKey::Char(cell.ch);
         if let Some(e) = self.atlas.get(&key) {
-            return [e.x as f32, e.y as f32, e.w as f32, e.h as f32];
-        }
-
-        // Rasterize glyph
-        let (metrics, bitmap) = self.font.rasterize(ch, self.font_size);
-        if metrics.width == 0 || metrics.height == 0 {
-            return [0.0, 0.0, 0.0, 0.0];
+            return e.rect();
         }
 
         // Composite glyph into a cell-sized bitmap so the atlas entry
         // matches cell dimensions and the shader doesn't need per-glyph offsets.
-        let cw = self.cell_width as u32;
-        let ch_px = self.cell_height as u32;
-        let mut cell_bitmap = vec![0u8; (cw * ch_px) as usize];
-
-        let font_metrics = self.font.horizontal_line_metrics(self.font_size);
-        let ascent = font_metrics
-            .map(|m| m.ascent.ceil() as i32)
-            .unwrap_or(ch_px as i32);
-
-        let glyph_y = ascent - metrics.ymin - metrics.height as i32;
-        let glyph_x = metrics.xmin;
-
-        for gy in 0..metrics.height {
-            for gx in 0..metrics.width {
-                let alpha = bitmap[gy * metrics.width + gx];
-                if alpha == 0 {
-                    continue;
-                }
-                let px = glyph_x + gx as i32;
-                let py = glyph_y + gy as i32;
-                if px < 0 || py < 0 || px >= cw as i32 || py >= ch_px as i32 {
-                    continue;
-                }
-                cell_bitmap[(py as u32 * cw + px as u32) as usize] = alpha;
-            }
+        let (cw, ch) = (self.cell_width, self.cell_height);
+        match self.font.cell_bitmap(cell.ch, cw, ch) {
+            Some(bitmap) => self.atlas.insert(key, &bitmap, cw as u32, ch as u32).rect(),
+            None => [0.0, 0.0, 0.0, 0.0],
         }
-
-        let entry = self.atlas.insert(key, &cell_bitmap, cw, ch_px);
-        [
-            entry.x as f32,
-            entry.y as f32,
-            entry.w as f32,
-            entry.h as f32,
-        ]
     }
 
     pub fn uniforms(&self) -> Uniforms {
@@ -396,15 +219,104 @@ impl GridRenderer {
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn pack_color(c: Color, is_fg: bool) -> u32 {
-    let (r, g, b) = if c == Color::DEFAULT {
-        if is_fg {
-            (200u8, 200u8, 200u8)
-        } else {
-            (0u8, 0u8, 0u8)
-        }
-    } else {
-        (c.r(), c.g(), c.b())
-    };
+fn pack_color((r, g, b): (u8, u8, u8)) -> u32 {
     (r as u32) | ((g as u32) << 8) | ((b as u32) << 16) | (0xFF << 24)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::conformance::{self, Case};
+    use gruid_core::style::AttrMask;
+    use gruid_core::{Point, grid::FrameCell};
+
+    fn render(case: &Case) -> GridRenderer {
+        let f = &case.frame;
+        let mut r = GridRenderer::new(None, 18.0, f.width as usize, f.height as usize, None, 1);
+        r.apply_frame(f);
+        r
+    }
+
+    /// The pixels of the cell at `p`, as `fs_main` of grid.wgsl shades
+    /// them: the background, or the foreground mixed in by the atlas
+    /// texel. Without a GPU, this stands in for rendering to a texture.
+    fn shade(r: &GridRenderer, p: Point) -> Vec<(u8, u8, u8)> {
+        let inst = r.instances[p.y as usize * r.cols + p.x as usize];
+        let unpack = |c: u32| {
+            let [r, g, b, _] = c.to_le_bytes();
+            (r, g, b)
+        };
+        let (fg, bg) = (unpack(inst.fg_color), unpack(inst.bg_color));
+        let [x, y, w, _] = inst.atlas_rect.map(|v| v as usize);
+        let mut out = Vec::new();
+        for dy in 0..r.cell_height {
+            for dx in 0..r.cell_width {
+                if w == 0 {
+                    out.push(bg);
+                    continue;
+                }
+                let a = r.atlas.data[(y + dy) * r.atlas.width as usize + x + dx] as f32 / 255.0;
+                let mix = |f: u8, b: u8| (b as f32 + (f as f32 - b as f32) * a).round() as u8;
+                out.push((mix(fg.0, bg.0), mix(fg.1, bg.1), mix(fg.2, bg.2)));
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn draws_the_conformance_cases() {
+        for case in conformance::cases() {
+            let r = render(&case);
+            for FrameCell { cell, pos } in &case.frame.cells {
+                let at = format!("{} at {pos}", case.name);
+                let (fg, bg) = cell.style.rgb();
+                let inst = r.instances[pos.y as usize * r.cols + pos.x as usize];
+                assert_eq!(
+                    (inst.fg_color, inst.bg_color),
+                    (pack_color(fg), pack_color(bg)),
+                    "{at}"
+                );
+                let pixels = shade(&r, *pos);
+                if cell.ch == ' ' {
+                    assert!(pixels.iter().all(|&px| px == bg), "background of {at}");
+                } else {
+                    assert!(inst.atlas_rect[2] > 0.0, "no glyph for {at}");
+                    assert!(pixels.contains(&bg), "background of {at}");
+                    // Fully covered texels show the foreground itself.
+                    let [x, y, w, h] = inst.atlas_rect.map(|v| v as usize);
+                    let aw = r.atlas.width as usize;
+                    let alpha = (y..y + h)
+                        .flat_map(|ty| (x..x + w).map(move |tx| ty * aw + tx))
+                        .map(|i| r.atlas.data[i])
+                        .max()
+                        .unwrap();
+                    assert!(alpha < 255 || pixels.contains(&fg), "foreground of {at}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ignores_attributes_as_profiled() {
+        let shown = conformance::profile("wgpu").attrs;
+        let case = conformance::attributes();
+        let r = render(&case);
+        let cells = &case.frame.cells;
+        let pixels = |attrs: AttrMask| {
+            let fc = cells
+                .iter()
+                .find(|fc| fc.cell.style.attrs == attrs)
+                .unwrap();
+            shade(&r, fc.pos)
+        };
+        for fc in cells {
+            let attrs = fc.cell.style.attrs;
+            assert_eq!(shade(&r, fc.pos), pixels(attrs & shown), "{attrs:?}");
+        }
+        for (mask, name) in conformance::ATTRIBUTES {
+            if shown.contains(mask) {
+                assert_ne!(pixels(mask), pixels(AttrMask::NONE), "{name}");
+            }
+        }
+    }
+}
@@ -0,0 +1,12 @@
+// Scanlines: darken every other line of pixels, with lines about an
+// eighth of a cell high so they keep their look on high-DPI displays.
+//
+// param(0): strength, from 0 (off) to 1 (black lines)
+
+@fragment
+fn fs_post(in: PostIn) -> @location(0) vec4<f32> {
+    let color = scene_at(in.uv).rgb;
+    let period = max(2.0, round(post.size.w / 8.0) * 2.0);
+    let dark = select(0.0, clamp(param(0), 0.0, 1.0), fract(in.pos.y / period) >= 0.5);
+    return vec4<f32>(color * (1.0 - dark), 1.0);
+}
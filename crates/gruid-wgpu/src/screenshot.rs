@@ -0,0 +1,231 @@
+//! Frame capture: copy the rendered surface out of the GPU and save it as a
+//! PNG file.
+//!
+//! A capture records a texture-to-buffer copy alongside the normal frame,
+//! then maps the buffer asynchronously. The render loop polls the mapping
+//! without blocking; once it completes the pixels are converted and written
+//! on a background thread.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PENDING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+/// Bytes per row of a copy of `width` 4-byte pixels, padded to wgpu's
+/// required row alignment.
+pub(crate) fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * 4).div_ceil(align) * align
+}
+
+/// Strip the row padding from a texture copy and convert its pixels to
+/// tightly packed RGBA8. Alpha is forced opaque, like the window itself.
+///
+/// Only 8-bit RGBA and BGRA surface formats are supported; anything else
+/// is an error.
+pub(crate) fn unpad_to_rgba(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    padded_row: u32,
+    format: wgpu::TextureFormat,
+) -> Result<Vec<u8>, String> {
+    use wgpu::TextureFormat as F;
+    let bgra = match format {
+        F::Rgba8Unorm | F::Rgba8UnormSrgb => false,
+        F::Bgra8Unorm | F::Bgra8UnormSrgb => true,
+        other => return Err(format!("unsupported surface format {other:?}")),
+    };
+    let row = width as usize * 4;
+    let padded = padded_row as usize;
+    if padded < row {
+        return Err(format!("row pitch {padded} is smaller than {row} bytes"));
+    }
+    let needed = match height as usize {
+        0 => 0,
+        h => padded * (h - 1) + row,
+    };
+    if data.len() < needed {
+        return Err(format!("buffer holds {} bytes, need {needed}", data.len()));
+    }
+
+    let mut out = Vec::with_capacity(row * height as usize);
+    for y in 0..height as usize {
+        let src = &data[y * padded..y * padded + row];
+        for px in src.chunks_exact(4) {
+            if bgra {
+                out.extend_from_slice(&[px[2], px[1], px[0], 0xff]);
+            } else {
+                out.extend_from_slice(&[px[0], px[1], px[2], 0xff]);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// A timestamped file name in the current directory.
+pub(crate) fn default_path() -> PathBuf {
+    let t = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    PathBuf::from(format!(
+        "screenshot-{}-{:03}.png",
+        t.as_secs(),
+        t.subsec_millis()
+    ))
+}
+
+#[cfg(feature = "screenshot")]
+fn save_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+    image::save_buffer(path, rgba, width, height, image::ExtendedColorType::Rgba8)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "screenshot"))]
+fn save_png(_path: &Path, _width: u32, _height: u32, _rgba: &[u8]) -> Result<(), String> {
+    Err("PNG output requires the `screenshot` feature".into())
+}
+
+/// An in-flight capture of one rendered frame.
+pub(crate) struct PendingCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_row: u32,
+    format: wgpu::TextureFormat,
+    path: PathBuf,
+    state: Arc<AtomicU8>,
+}
+
+impl PendingCapture {
+    /// Record a copy of `texture` into a fresh readback buffer. Call
+    /// [`map`](Self::map) once `encoder` has been submitted.
+    pub(crate) fn record(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        path: PathBuf,
+    ) -> Self {
+        let width = texture.width();
+        let height = texture.height();
+        let padded_row = padded_bytes_per_row(width);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("screenshot readback"),
+            size: padded_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        Self {
+            buffer,
+            width,
+            height,
+            padded_row,
+            format: texture.format(),
+            path,
+            state: Arc::new(AtomicU8::new(PENDING)),
+        }
+    }
+
+    /// Request asynchronous mapping of the readback buffer.
+    pub(crate) fn map(&self) {
+        let state = Arc::clone(&self.state);
+        self.buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |res| {
+                let s = if res.is_ok() { MAPPED } else { FAILED };
+                state.store(s, Ordering::Release);
+            });
+    }
+
+    /// Poll the device without blocking. Returns the capture back while it
+    /// is still in flight; otherwise hands the pixels to a background
+    /// thread for conversion and saving.
+    pub(crate) fn poll(self, device: &wgpu::Device) -> Option<Self> {
+        let _ = device.poll(wgpu::PollType::Poll);
+        match self.state.load(Ordering::Acquire) {
+            PENDING => Some(self),
+            FAILED => {
+                log::error!("screenshot: mapping the readback buffer failed");
+                None
+            }
+            _ => {
+                let data = self.buffer.slice(..).get_mapped_range().to_vec();
+                self.buffer.unmap();
+                let Self {
+                    width,
+                    height,
+                    padded_row,
+                    format,
+                    path,
+                    ..
+                } = self;
+                std::thread::spawn(move || {
+                    let res = unpad_to_rgba(&data, width, height, padded_row, format)
+                        .and_then(|rgba| save_png(&path, width, height, &rgba));
+                    match res {
+                        Ok(()) => log::info!("screenshot saved to {}", path.display()),
+                        Err(e) => log::error!("screenshot {}: {e}", path.display()),
+                    }
+                });
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_rows_are_aligned() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+    }
+
+    #[test]
+    fn unpad_and_swizzle_bgra() {
+        // 2x2 image, rows padded to 12 bytes.
+        let data = [
+            1, 2, 3, 0, 4, 5, 6, 0, 9, 9, 9, 9, //
+            7, 8, 9, 0, 10, 11, 12, 0, 9, 9, 9, 9,
+        ];
+        let out = unpad_to_rgba(&data, 2, 2, 12, wgpu::TextureFormat::Bgra8Unorm).unwrap();
+        assert_eq!(
+            out,
+            [3, 2, 1, 255, 6, 5, 4, 255, 9, 8, 7, 255, 12, 11, 10, 255]
+        );
+        let out = unpad_to_rgba(&data, 2, 2, 12, wgpu::TextureFormat::Rgba8UnormSrgb).unwrap();
+        assert_eq!(&out[..8], [1, 2, 3, 255, 4, 5, 6, 255]);
+    }
+
+    #[test]
+    fn unpad_rejects_bad_input() {
+        let data = [0u8; 16];
+        assert!(unpad_to_rgba(&data, 2, 2, 8, wgpu::TextureFormat::Rgba16Float).is_err());
+        assert!(unpad_to_rgba(&data, 2, 2, 4, wgpu::TextureFormat::Rgba8Unorm).is_err());
+        assert!(unpad_to_rgba(&data, 2, 3, 8, wgpu::TextureFormat::Rgba8Unorm).is_err());
+        assert!(
+            unpad_to_rgba(&[], 4, 0, 16, wgpu::TextureFormat::Rgba8Unorm)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -3,32 +3,62 @@
 //! Renders the grid as colored text tiles in a native window using:
 //! - [`winit`] for window creation and input events
 //! - [`softbuffer`] for CPU-based pixel rendering
-//! - [`fontdue`] for lightweight font rasterization
+//! - fontdue for lightweight font rasterization, through the internal
+//!   gruid-graphics-common crate it shares with gruid-wgpu
 //!
 //! Handles high-DPI (Retina) displays automatically by scaling the font
 //! size by the monitor's scale factor.
+//!
+//! By default the driver only draws in answer to input, timers and
+//! background effects. For smooth animations, set
+//! [`WinitConfig::frame_rate`]: the model then gets a [`Msg::Frame`] at
+//! that rate, and what it changes is drawn on the next redraw the window
+//! system allows.
+//!
+//! Nothing is drawn while the window is minimized or fully covered: the
+//! event loop only wakes now and then to drain messages, unless a
+//! [`Effect::Continue`](gruid_core::Effect::Continue) runs, and the model
+//! can be told so it pauses (see [`WinitConfig::pause_on_occlusion`]).
+//!
+//! Ctrl+V (Cmd+V on macOS), Shift+Insert and the Paste key read the system
+//! clipboard and send its text as a [`Msg::Paste`], instead of the key.
 
-mod input;
 mod renderer;
 
 use std::num::NonZeroU32;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
-    event::WindowEvent,
-    event_loop::{ActiveEventLoop, EventLoop},
+    event::{ElementState, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::ModifiersState,
     window::{Window, WindowId},
 };
 
 use gruid_core::{
+    DriverCaps,
     app::{AppRunner, EventLoopDriver},
-    messages::Msg,
+    messages::{MouseAction, MouseTracker, Msg, Visibility},
+};
+use gruid_graphics_common::{
+    clipboard::{self, SystemClipboard},
+    config_capabilities,
+    frames::FrameTimer,
+    input,
+    tiles::tile_scale,
+    title::WindowTitle,
+    visibility::WindowVisibility,
+    window::{WindowFullscreen, WindowOptions, grid_size, min_inner_size, window_attributes},
 };
 
 pub use gruid_core::TileManager;
 
+/// How often the event loop wakes to drain messages from background effects.
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
 use renderer::GridRenderer;
 
 // ---------------------------------------------------------------------------
@@ -58,6 +88,31 @@ pub struct WinitConfig {
     /// When 0, the scale is chosen automatically based on the monitor's
     /// DPI scale factor.
     pub tile_scale: u32,
+    /// Send the model a [`Visibility`] message when the window is
+    /// minimized, fully covered or shown again, so it can pause (default
+    /// false). Rendering stops while hidden either way.
+    pub pause_on_occlusion: bool,
+    /// Send the model a [`Msg::Frame`] this many times a second while the
+    /// window can be seen, for animations (default `None`: no frames, the
+    /// driver draws in answer to events only).
+    pub frame_rate: Option<u32>,
+    /// Start in borderless fullscreen on the current monitor (default
+    /// false). Models switch it with a
+    /// [`SetFullscreen`](gruid_core::SetFullscreen) request.
+    pub fullscreen: bool,
+    /// Whether the window has a title bar and borders (default true).
+    pub decorations: bool,
+    /// Whether the window can be resized (default true).
+    pub resizable: bool,
+    /// Window icon: RGBA pixels, width and height (default none).
+    pub window_icon: Option<(Vec<u8>, u32, u32)>,
+    /// Columns and rows the window cannot be shrunk below (default
+    /// `(0, 0)`: no minimum).
+    pub min_grid_size: (i32, i32),
+    /// Turn the paste shortcuts, Ctrl+V (Cmd+V on macOS), Shift+Insert and
+    /// the Paste key, into a [`Msg::Paste`] of the clipboard text (default
+    /// true). When false, they reach the model as keys.
+    pub paste: bool,
 }
 
 impl Default for WinitConfig {
@@ -70,6 +125,27 @@ impl Default for WinitConfig {
             grid_height: 24,
             tile_manager: None,
             tile_scale: 0,
+            pause_on_occlusion: false,
+            frame_rate: None,
+            fullscreen: false,
+            decorations: true,
+            resizable: true,
+            window_icon: None,
+            min_grid_size: (0, 0),
+            paste: true,
+        }
+    }
+}
+
+impl WinitConfig {
+    fn window_options(&self) -> WindowOptions<'_> {
+        WindowOptions {
+            title: &self.title,
+            fullscreen: self.fullscreen,
+            decorations: self.decorations,
+            resizable: self.resizable,
+            icon: self.window_icon.as_ref(),
+            min_grid_size: self.min_grid_size,
         }
     }
 }
@@ -93,12 +169,23 @@ impl WinitDriver {
 }
 
 impl EventLoopDriver for WinitDriver {
-    fn run(self, runner: AppRunner) -> Result<(), Box<dyn std::error::Error>> {
+    fn run(self, mut runner: AppRunner) -> Result<(), Box<dyn std::error::Error>> {
+        runner.set_clipboard(Box::new(SystemClipboard::default()));
+        let title = WindowTitle::default();
+        runner.add_effect_handler(title.handler());
         let event_loop = EventLoop::new()?;
-        let mut app = WinitApp::new(self.config, runner);
+        let mut app = WinitApp::new(self.config, runner, title);
         event_loop.run_app(&mut app)?;
         Ok(())
     }
+
+    fn capabilities(&self) -> DriverCaps {
+        config_capabilities(
+            self.config.font_data.as_deref(),
+            self.config.font_size,
+            self.config.tile_manager.as_deref(),
+        )
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -109,6 +196,15 @@ struct WinitApp {
     config: WinitConfig,
     runner: AppRunner,
     state: Option<WinitState>,
+    mouse: MouseTracker,
+    /// The button a drag is made with, if one is held.
+    held: Option<MouseAction>,
+    /// Modifiers held, to recognize the paste shortcut.
+    modifiers: ModifiersState,
+    title: WindowTitle,
+    fullscreen: WindowFullscreen,
+    visibility: WindowVisibility,
+    frames: FrameTimer,
 }
 
 pub(crate) struct WinitState {
@@ -121,20 +217,78 @@ pub(crate) struct WinitState {
     scale_factor: f64,
 }
 
+impl WinitState {
+    fn resize_surface(&mut self, width: u32, height: u32) {
+        self.phys_width = width;
+        self.phys_height = height;
+        self.surface
+            .resize(
+                NonZeroU32::new(width).unwrap_or(NonZeroU32::new(1).unwrap()),
+                NonZeroU32::new(height).unwrap_or(NonZeroU32::new(1).unwrap()),
+            )
+            .ok();
+    }
+}
+
 impl WinitApp {
-    fn new(config: WinitConfig, runner: AppRunner) -> Self {
+    fn new(config: WinitConfig, mut runner: AppRunner, title: WindowTitle) -> Self {
+        let fullscreen = WindowFullscreen::default();
+        runner.add_effect_handler(fullscreen.handler());
         Self {
+            frames: FrameTimer::new(config.frame_rate),
             config,
             runner,
             state: None,
+            mouse: MouseTracker::default(),
+            held: None,
+            modifiers: ModifiersState::empty(),
+            title,
+            fullscreen,
+            visibility: WindowVisibility::default(),
         }
     }
 
+    /// Tell the model about a visibility change, if asked to.
+    fn notify_visibility(&mut self, changed: Option<Visibility>) {
+        if let Some(visibility) = changed
+            && self.config.pause_on_occlusion
+        {
+            self.runner.handle_msg(Msg::custom(visibility));
+        }
+    }
+
+    /// Follow a new window size in physical pixels: the surface, the grid
+    /// and whether the window is minimized.
+    fn resized(&mut self, width: u32, height: u32) {
+        let changed = self.visibility.resized(width, height);
+        if let Some(state) = self.state.as_mut() {
+            state.resize_surface(width, height);
+            if let Some((cols, rows)) = grid_size(width, height, state.renderer.cell_size()) {
+                state.renderer.resize_grid(cols as usize, rows as usize);
+                self.runner.screen(cols, rows);
+            }
+        }
+        self.notify_visibility(changed);
+    }
+
     fn render(&mut self) {
         if self.runner.should_quit() {
             return;
         }
 
+        // The window may have changed size without a `Resized` reaching us,
+        // typically when restored from minimized: presenting a buffer of
+        // the old size panics on some platforms.
+        if let Some(state) = &self.state {
+            let size = state.window.inner_size();
+            if (size.width, size.height) != (state.phys_width, state.phys_height) {
+                self.resized(size.width, size.height);
+                if self.runner.should_quit() {
+                    return;
+                }
+            }
+        }
+
         // Drain messages from background effects (Cmd/Sub).
         self.runner.process_pending_msgs();
 
@@ -148,6 +302,9 @@ impl WinitApp {
         if let Some(frame) = frame {
             state.renderer.apply_frame(&frame);
         }
+        if !self.visibility.is_visible() {
+            return;
+        }
 
         let width = state.phys_width;
         let height = state.phys_height;
@@ -159,12 +316,17 @@ impl WinitApp {
             Ok(b) => b,
             Err(_) => return,
         };
+        if buf.len() != width as usize * height as usize {
+            return;
+        }
 
         state
             .renderer
             .blit_to_buffer(&mut buf, width as usize, height as usize);
 
-        buf.present().ok();
+        if buf.present().is_ok() {
+            self.runner.frame_presented();
+        }
     }
 }
 
@@ -186,36 +348,32 @@ impl ApplicationHandler for WinitApp {
         // Scale the logical font size by the DPI factor.
         let physical_font_size = self.config.font_size * scale_factor as f32;
 
-        // Determine tile scale: 0 means auto from DPI.
-        let tile_scale = if self.config.tile_scale > 0 {
-            self.config.tile_scale
-        } else {
-            (scale_factor.round() as u32).max(1)
-        };
-
         let renderer = GridRenderer::new(
             self.config.font_data.as_deref(),
             physical_font_size,
             self.config.grid_width as usize,
             self.config.grid_height as usize,
             self.config.tile_manager.take(),
-            tile_scale,
+            tile_scale(self.config.tile_scale, scale_factor),
         );
 
         // The renderer now works entirely in physical pixels.
         let phys_w = renderer.pixel_width() as u32;
         let phys_h = renderer.pixel_height() as u32;
 
-        let window_attrs = Window::default_attributes()
-            .with_title(&self.config.title)
-            .with_inner_size(PhysicalSize::new(phys_w, phys_h))
-            .with_resizable(true);
+        let window_attrs = window_attributes(
+            &self.config.window_options(),
+            PhysicalSize::new(phys_w, phys_h),
+            renderer.cell_size(),
+        );
 
         let window = Arc::new(
             event_loop
                 .create_window(window_attrs)
                 .expect("failed to create window"),
         );
+        self.title.attach(window.clone());
+        self.fullscreen.attach(window.clone());
 
         let context =
             softbuffer::Context::new(window.clone()).expect("failed to create softbuffer context");
@@ -229,6 +387,10 @@ impl ApplicationHandler for WinitApp {
             )
             .ok();
 
+        self.runner.set_capabilities(renderer.capabilities());
+        if let Some(theme) = window.theme() {
+            self.runner.set_system_theme(input::translate_theme(theme));
+        }
         self.state = Some(WinitState {
             window,
             surface,
@@ -248,70 +410,61 @@ impl ApplicationHandler for WinitApp {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        self.mouse.resize(self.runner.width(), self.runner.height());
         match event {
             WindowEvent::CloseRequested => {
                 self.runner.handle_msg(Msg::Quit);
                 event_loop.exit();
             }
 
+            WindowEvent::ThemeChanged(theme) => {
+                self.runner.set_system_theme(input::translate_theme(theme));
+            }
+
             WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                 if let Some(state) = self.state.as_mut() {
                     state.scale_factor = scale_factor;
                     // Rebuild renderer with the new physical font size.
                     let physical_font_size = self.config.font_size * scale_factor as f32;
                     let tile_manager = state.renderer.take_tile_manager();
-                    let tile_scale = if self.config.tile_scale > 0 {
-                        self.config.tile_scale
-                    } else {
-                        (scale_factor.round() as u32).max(1)
-                    };
                     state.renderer = GridRenderer::new(
                         self.config.font_data.as_deref(),
                         physical_font_size,
                         self.runner.width() as usize,
                         self.runner.height() as usize,
                         tile_manager,
-                        tile_scale,
+                        tile_scale(self.config.tile_scale, scale_factor),
                     );
+                    // Cell sizes are rounded, so the aspect ratio may change.
+                    self.runner.set_capabilities(state.renderer.capabilities());
+                    state.window.set_min_inner_size(min_inner_size(
+                        self.config.min_grid_size,
+                        state.renderer.cell_size(),
+                    ));
                     // Force full redraw.
-                    self.runner.handle_msg(Msg::Screen {
-                        width: self.runner.width(),
-                        height: self.runner.height(),
-                        time: std::time::Instant::now(),
-                    });
+                    self.runner
+                        .screen(self.runner.width(), self.runner.height());
                 }
                 self.render();
             }
 
             WindowEvent::Resized(PhysicalSize { width, height }) => {
-                if let Some(state) = self.state.as_mut() {
-                    state.phys_width = width;
-                    state.phys_height = height;
-                    state
-                        .surface
-                        .resize(
-                            NonZeroU32::new(width).unwrap_or(NonZeroU32::new(1).unwrap()),
-                            NonZeroU32::new(height).unwrap_or(NonZeroU32::new(1).unwrap()),
-                        )
-                        .ok();
-
-                    // Recompute grid dimensions in physical pixels.
-                    let (cw, ch) = state.renderer.cell_size();
-                    if cw > 0 && ch > 0 {
-                        let new_cols = (width as i32) / (cw as i32);
-                        let new_rows = (height as i32) / (ch as i32);
-                        if new_cols > 0 && new_rows > 0 {
-                            state
-                                .renderer
-                                .resize_grid(new_cols as usize, new_rows as usize);
-                            self.runner.resize(new_cols, new_rows);
-                            self.runner.handle_msg(Msg::Screen {
-                                width: new_cols,
-                                height: new_rows,
-                                time: std::time::Instant::now(),
-                            });
-                        }
-                    }
+                self.resized(width, height);
+                if self.runner.should_quit() {
+                    event_loop.exit();
+                    return;
+                }
+                self.render();
+            }
+
+            // The whole window is blitted again when it shows, as its
+            // contents may be stale.
+            WindowEvent::Occluded(occluded) => {
+                let changed = self.visibility.set_occluded(occluded);
+                self.notify_visibility(changed);
+                if self.runner.should_quit() {
+                    event_loop.exit();
+                    return;
                 }
                 self.render();
             }
@@ -320,8 +473,23 @@ impl ApplicationHandler for WinitApp {
                 self.render();
             }
 
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+
             WindowEvent::KeyboardInput { event, .. } => {
-                if let Some(msg) = input::translate_keyboard(&event) {
+                let paste =
+                    self.config.paste && input::is_paste_key(&event.logical_key, self.modifiers);
+                let msg = if paste {
+                    // The shortcut's release is dropped with it.
+                    (event.state == ElementState::Pressed)
+                        .then(clipboard::paste_text)
+                        .flatten()
+                        .map(Msg::Paste)
+                } else {
+                    input::translate_keyboard(&event)
+                };
+                if let Some(msg) = msg {
                     self.runner.handle_msg(msg);
                     if self.runner.should_quit() {
                         event_loop.exit();
@@ -340,7 +508,7 @@ impl ApplicationHandler for WinitApp {
                 ..
             } => {
                 if let Some(msg) =
-                    input::translate_mouse_button(btn_state, button, self.state.as_ref())
+                    input::translate_mouse_button(btn_state, button, &mut self.held, &self.mouse)
                 {
                     self.runner.handle_msg(msg);
                     if self.runner.should_quit() {
@@ -352,14 +520,34 @@ impl ApplicationHandler for WinitApp {
             }
 
             WindowEvent::CursorMoved { position, .. } => {
-                if let Some(msg) = input::translate_cursor_moved(position, self.state.as_ref()) {
+                let layout = self
+                    .state
+                    .as_ref()
+                    .map(|s| s.renderer.layout())
+                    .unwrap_or_default();
+                let mut moved = false;
+                for msg in
+                    input::translate_cursor_moved(position, layout, self.held, &mut self.mouse)
+                {
+                    self.runner.handle_msg(msg);
+                    moved = true;
+                }
+                if moved {
+                    self.render();
+                }
+            }
+
+            // The cursor position is unknown on entry: `Enter` is sent with
+            // the first `CursorMoved` over the grid.
+            WindowEvent::CursorLeft { .. } => {
+                if let Some(msg) = input::translate_cursor_left(&mut self.mouse) {
                     self.runner.handle_msg(msg);
                     self.render();
                 }
             }
 
             WindowEvent::MouseWheel { delta, .. } => {
-                if let Some(msg) = input::translate_mouse_wheel(delta, self.state.as_ref()) {
+                if let Some(msg) = input::translate_mouse_wheel(delta, &self.mouse) {
                     self.runner.handle_msg(msg);
                     if self.runner.should_quit() {
                         event_loop.exit();
@@ -372,4 +560,130 @@ impl ApplicationHandler for WinitApp {
             _ => {}
         }
     }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Background effects report through a channel the event loop cannot
+        // see, so wake up regularly to drain it.
+        self.runner.process_pending_msgs();
+        let visible = self.visibility.is_visible();
+        if visible
+            && self.state.is_some()
+            && let Some(frame) = self.frames.due(Instant::now())
+        {
+            self.runner.handle_msg(frame);
+        }
+        if self.runner.should_quit() {
+            event_loop.exit();
+            return;
+        }
+        // Drawn on `RedrawRequested`, when the window system is ready for
+        // it, rather than right away: at a frame rate above the display's,
+        // presenting would block on vsync and delay input.
+        if self.runner.needs_draw()
+            && visible
+            && let Some(w) = self.state.as_ref().map(|s| &s.window)
+        {
+            w.request_redraw();
+        }
+        // A hidden window gets no redraws: keep a running continuation
+        // going by drawing without presenting, at the usual pace.
+        let continuing = self.runner.is_continuing();
+        if continuing && !visible {
+            self.render();
+        }
+        // Wake up for the next timer or frame too, if it comes sooner.
+        let interval = if continuing {
+            POLL_INTERVAL
+        } else {
+            self.visibility.poll_interval(POLL_INTERVAL)
+        };
+        let mut wake = Instant::now() + interval;
+        if let Some(tick) = self.runner.next_tick() {
+            wake = wake.min(tick);
+        }
+        if visible && let Some(frame) = self.frames.next() {
+            wake = wake.min(frame);
+        }
+        event_loop.set_control_flow(ControlFlow::WaitUntil(wake));
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        // Every exit path (model End, window close) ends up here.
+        self.runner.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::{Cell, ColorDepth, Platform};
+
+    struct Square;
+
+    impl TileManager for Square {
+        fn tile_size(&self) -> (usize, usize) {
+            (16, 16)
+        }
+
+        fn get_tile(&self, _cell: &Cell) -> Option<&[u8]> {
+            None
+        }
+    }
+
+    #[test]
+    fn capabilities_from_config() {
+        let caps = WinitDriver::new(Default::default()).capabilities();
+        assert_eq!(caps.platform, Platform::Desktop);
+        assert_eq!(caps.color_depth, ColorDepth::TrueColor);
+        assert!(
+            caps.mouse && caps.animation_ticks && caps.key_release && !caps.tiles && caps.clipboard
+        );
+        // The built-in font has tall cells.
+        assert!(
+            caps.cell_aspect > 0.4 && caps.cell_aspect < 0.8,
+            "{}",
+            caps.cell_aspect
+        );
+
+        let caps = WinitDriver::new(WinitConfig {
+            tile_manager: Some(Box::new(Square)),
+            ..Default::default()
+        })
+        .capabilities();
+        assert!(caps.tiles);
+        assert_eq!(caps.cell_aspect, 1.0);
+    }
+
+    /// Logs the visibility messages it gets.
+    struct Watcher(std::rc::Rc<std::cell::RefCell<Vec<Visibility>>>);
+
+    impl gruid_core::app::Model for Watcher {
+        fn update(&mut self, msg: Msg) -> Option<gruid_core::app::Effect> {
+            if let Some(v) = msg.visibility() {
+                self.0.borrow_mut().push(v);
+            }
+            None
+        }
+
+        fn draw(&self, _grid: &mut gruid_core::Grid) {}
+    }
+
+    #[test]
+    fn resizes_track_minimizing() {
+        let log = std::rc::Rc::default();
+        let runner = AppRunner::new(Box::new(Watcher(std::rc::Rc::clone(&log))), 10, 4);
+        let config = WinitConfig {
+            pause_on_occlusion: true,
+            ..Default::default()
+        };
+        let mut app = WinitApp::new(config, runner, WindowTitle::default());
+        app.resized(0, 0);
+        app.resized(0, 0);
+        assert!(!app.visibility.is_visible());
+        // Restored, as render finds when no `Resized` came.
+        app.resized(800, 480);
+        assert!(app.visibility.is_visible());
+        app.resized(640, 480);
+        assert_eq!(*log.borrow(), [Visibility::Hidden, Visibility::Visible]);
+    }
 }
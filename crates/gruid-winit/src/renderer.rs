@@ -4,42 +4,26 @@
 //! Each grid cell is rendered as a monospace character tile with
 //! foreground/background colors.
 
-use std::collections::HashMap;
-
-use fontdue::{Font, FontSettings};
-use gruid_core::{Cell, grid::Frame, style::Color};
+use gruid_core::{Cell, DriverCaps, grid::Frame};
+use gruid_graphics_common::{
+    capabilities,
+    font::CellFont,
+    input::GridLayout,
+    tiles::{Tile, Tiles},
+};
 
 use crate::TileManager;
 
-/// Default built-in font (DejaVu Sans Mono subset would be ideal, but for
-/// size we embed nothing and fall back to fontdue's glyph-not-found box).
-/// Users should supply their own font via [`WinitConfig::font_data`].
-const FALLBACK_FONT: &[u8] = include_bytes!("builtin_font.ttf");
-
-/// Cached rasterized glyph.
-struct GlyphCache {
-    bitmap: Vec<u8>, // alpha values, width*height
-    width: usize,
-    height: usize,
-    x_offset: i32,
-    y_offset: i32,
-}
-
 pub(crate) struct GridRenderer {
-    font: Font,
-    font_size: f32,
+    font: CellFont,
     cell_width: usize,
     cell_height: usize,
     cols: usize,
     rows: usize,
     /// RGBA pixel buffer (cell_width*cols) x (cell_height*rows)
     pixels: Vec<u32>,
-    /// Glyph cache keyed by character
-    glyph_cache: HashMap<char, GlyphCache>,
-    /// Optional tile manager for custom tile-based rendering.
-    tile_manager: Option<Box<dyn TileManager>>,
-    /// Integer scale factor for tiles (1 = native, 2 = 2x, etc.).
-    tile_scale: u32,
+    /// Optional tiles for custom tile-based rendering.
+    tiles: Option<Tiles>,
 }
 
 impl GridRenderer {
@@ -51,32 +35,15 @@ impl GridRenderer {
         tile_manager: Option<Box<dyn TileManager>>,
         tile_scale: u32,
     ) -> Self {
-        let tile_scale = tile_scale.max(1);
-        let data = font_data.unwrap_or(FALLBACK_FONT);
-        let font = Font::from_bytes(data, FontSettings::default()).expect("failed to parse font");
+        let font = CellFont::new(font_data, font_size);
+        let tiles = tile_manager.map(|tm| Tiles::new(tm, tile_scale));
 
         // When a tile manager is present, cell dimensions come from it
         // multiplied by the tile scale factor.
         // Otherwise compute from font metrics.
-        let (cell_width, cell_height) = if let Some(ref tm) = tile_manager {
-            let (tw, th) = tm.tile_size();
-            let s = tile_scale as usize;
-            ((tw * s).max(1), (th * s).max(1))
-        } else {
-            let metrics = font
-                .horizontal_line_metrics(font_size)
-                .unwrap_or(fontdue::LineMetrics {
-                    ascent: font_size * 0.8,
-                    descent: -(font_size * 0.2),
-                    line_gap: 0.0,
-                    new_line_size: font_size,
-                });
-
-            let ch = (metrics.ascent - metrics.descent).ceil() as usize;
-            // Use 'M' to determine cell width for monospace
-            let (m_metrics, _) = font.rasterize('M', font_size);
-            let cw = m_metrics.advance_width.ceil() as usize;
-            (cw.max(1), ch.max(1))
+        let (cell_width, cell_height) = match &tiles {
+            Some(tiles) => tiles.cell_size(),
+            None => font.cell_size(),
         };
 
         let pixel_count = (cols * cell_width) * (rows * cell_height);
@@ -84,21 +51,23 @@ impl GridRenderer {
 
         Self {
             font,
-            font_size,
             cell_width,
             cell_height,
             cols,
             rows,
             pixels,
-            glyph_cache: HashMap::new(),
-            tile_manager,
-            tile_scale,
+            tiles,
         }
     }
 
+    /// Capabilities of a window drawing with this renderer.
+    pub fn capabilities(&self) -> DriverCaps {
+        capabilities(&self.font, self.cell_size(), self.tiles.is_some())
+    }
+
     /// Take the tile manager out of this renderer (used when rebuilding).
     pub fn take_tile_manager(&mut self) -> Option<Box<dyn TileManager>> {
-        self.tile_manager.take()
+        self.tiles.take().map(Tiles::into_manager)
     }
 
     /// Cell size in pixels.
@@ -106,6 +75,11 @@ impl GridRenderer {
         (self.cell_width, self.cell_height)
     }
 
+    /// Where the grid is in the window: always at the top-left corner.
+    pub fn layout(&self) -> GridLayout {
+        GridLayout::new(self.cell_width, self.cell_height)
+    }
+
     /// Total pixel buffer width.
     pub fn pixel_width(&self) -> usize {
         self.cols * self.cell_width
@@ -137,36 +111,16 @@ impl GridRenderer {
         }
     }
 
-    /// Ensure a glyph is cached, rasterizing it if needed.
-    fn cache_glyph(&mut self, ch: char) {
-        if self.glyph_cache.contains_key(&ch) {
-            return;
-        }
-        let (metrics, bitmap) = self.font.rasterize(ch, self.font_size);
-        self.glyph_cache.insert(
-            ch,
-            GlyphCache {
-                bitmap,
-                width: metrics.width,
-                height: metrics.height,
-                x_offset: metrics.xmin,
-                y_offset: metrics.ymin,
-            },
-        );
-    }
-
     /// Draw a single cell into the pixel buffer.
     fn draw_cell(&mut self, col: usize, row: usize, cell: &Cell) {
-        let fg = cell.style.fg;
-        let bg = cell.style.bg;
         let cw = self.cell_width;
         let ch_px = self.cell_height;
         let buf_w = self.pixel_width();
-        let px_h = self.pixel_height();
         let x0 = col * cw;
         let y0 = row * ch_px;
 
-        let bg_pixel = color_to_pixel(bg);
+        let (fg, bg) = cell.style.rgb();
+        let bg_pixel = rgb_pixel(bg);
 
         // Fill background
         for dy in 0..ch_px {
@@ -178,88 +132,38 @@ impl GridRenderer {
             }
         }
 
-        // Try tile manager first
-        if let Some(ref tm) = self.tile_manager {
-            if let Some(bitmap) = tm.get_tile(cell) {
-                let (fg_r, fg_g, fg_b) = fg_rgb(fg);
-                let (bg_r, bg_g, bg_b) = bg_rgb(bg);
-                let (tw, th) = tm.tile_size();
-                let s = self.tile_scale as usize;
-                // Render monochrome alpha bitmap scaled and colorized
-                for ty in 0..th {
-                    for tx in 0..tw {
-                        let src_idx = ty * tw + tx;
-                        if src_idx >= bitmap.len() {
-                            continue;
-                        }
-                        let alpha = bitmap[src_idx];
-                        let a = alpha as u32;
-                        let inv_a = 255 - a;
-                        let r = (fg_r as u32 * a + bg_r as u32 * inv_a) / 255;
-                        let g = (fg_g as u32 * a + bg_g as u32 * inv_a) / 255;
-                        let b = (fg_b as u32 * a + bg_b as u32 * inv_a) / 255;
-                        let pixel = 0xFF000000 | (r << 16) | (g << 8) | b;
-                        // Write s×s block for this source pixel
-                        for sy in 0..s {
-                            for sx in 0..s {
-                                let px = x0 + tx * s + sx;
-                                let py = y0 + ty * s + sy;
-                                if px >= buf_w || py >= px_h {
-                                    continue;
-                                }
-                                let idx = py * buf_w + px;
-                                if idx < self.pixels.len() {
-                                    self.pixels[idx] = pixel;
-                                }
-                            }
-                        }
-                    }
-                }
-                return;
-            }
-        }
-
-        // Fall back to font-based rendering
-        let ch = cell.ch;
-        if ch == ' ' || ch == '\0' {
+        // Try tiles first
+        if let Some(tile) = self.tiles.as_ref().and_then(|t| t.tile(cell)) {
+            draw_tile(
+                &mut self.pixels,
+                self.cols * cw,
+                self.rows * ch_px,
+                x0,
+                y0,
+                &tile,
+                (fg, bg),
+            );
             return;
         }
 
-        let (fg_r, fg_g, fg_b) = fg_rgb(fg);
-        let (bg_r, bg_g, bg_b) = bg_rgb(bg);
-
-        // Ensure glyph is cached
-        self.cache_glyph(ch);
-        let glyph = &self.glyph_cache[&ch];
-
-        if glyph.width == 0 || glyph.height == 0 {
+        // Fall back to font-based rendering
+        let baseline = self.font.baseline(ch_px);
+        let Some(glyph) = self.font.glyph(cell.ch) else {
             return;
-        }
-
-        // Compute baseline position
-        let font_metrics = self.font.horizontal_line_metrics(self.font_size);
-        let ascent = font_metrics
-            .map(|m| m.ascent.ceil() as i32)
-            .unwrap_or(ch_px as i32);
-
-        // glyph_y is the top-left pixel of the glyph bitmap relative to cell top
-        let glyph_y = ascent - glyph.y_offset - glyph.height as i32;
-        let glyph_x = glyph.x_offset;
-
-        // Copy glyph info to locals to release borrow on self
-        let gw = glyph.width;
-        let gh = glyph.height;
-        let gx_off = glyph_x;
-        let gy_off = glyph_y;
-
-        for gy in 0..gh {
-            for gx in 0..gw {
-                let alpha = self.glyph_cache[&ch].bitmap[gy * gw + gx];
+        };
+        let ((fg_r, fg_g, fg_b), (bg_r, bg_g, bg_b)) = (fg, bg);
+        let px_h = self.rows * ch_px;
+
+        // Glyphs may overflow their cell: they are only cropped to the buffer.
+        let gy_off = glyph.top(baseline);
+        for gy in 0..glyph.height {
+            for gx in 0..glyph.width {
+                let alpha = glyph.bitmap[gy * glyph.width + gx];
                 if alpha == 0 {
                     continue;
                 }
 
-                let px = x0 as i32 + gx_off + gx as i32;
+                let px = x0 as i32 + glyph.x + gx as i32;
                 let py = y0 as i32 + gy_off + gy as i32;
 
                 if px < 0 || py < 0 {
@@ -313,29 +217,143 @@ impl GridRenderer {
     }
 }
 
-#[inline]
-fn color_to_pixel(c: Color) -> u32 {
-    if c == Color::DEFAULT {
-        0xFF000000 // black
-    } else {
-        0xFF000000 | ((c.r() as u32) << 16) | ((c.g() as u32) << 8) | (c.b() as u32)
+/// Draw a tile colorized with `colors`, foreground and background, with
+/// its top-left corner at `(x0, y0)`.
+fn draw_tile(
+    pixels: &mut [u32],
+    buf_w: usize,
+    buf_h: usize,
+    x0: usize,
+    y0: usize,
+    tile: &Tile<'_>,
+    colors: ((u8, u8, u8), (u8, u8, u8)),
+) {
+    let ((fg_r, fg_g, fg_b), (bg_r, bg_g, bg_b)) = colors;
+    for ty in 0..tile.height() {
+        for tx in 0..tile.width() {
+            let (px, py) = (x0 + tx, y0 + ty);
+            if px >= buf_w || py >= buf_h {
+                continue;
+            }
+            let a = tile.alpha(tx, ty) as u32;
+            let inv_a = 255 - a;
+            let r = (fg_r as u32 * a + bg_r as u32 * inv_a) / 255;
+            let g = (fg_g as u32 * a + bg_g as u32 * inv_a) / 255;
+            let b = (fg_b as u32 * a + bg_b as u32 * inv_a) / 255;
+            if let Some(p) = pixels.get_mut(py * buf_w + px) {
+                *p = 0xFF000000 | (r << 16) | (g << 8) | b;
+            }
+        }
     }
 }
 
 #[inline]
-fn fg_rgb(c: Color) -> (u8, u8, u8) {
-    if c == Color::DEFAULT {
-        (200, 200, 200) // light gray for default foreground
-    } else {
-        (c.r(), c.g(), c.b())
-    }
+fn rgb_pixel((r, g, b): (u8, u8, u8)) -> u32 {
+    0xFF000000 | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32)
 }
 
-#[inline]
-fn bg_rgb(c: Color) -> (u8, u8, u8) {
-    if c == Color::DEFAULT {
-        (0, 0, 0) // black for default background
-    } else {
-        (c.r(), c.g(), c.b())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::Point;
+    use gruid_core::conformance::{self, Case};
+    use gruid_core::grid::FrameCell;
+    use gruid_core::style::AttrMask;
+
+    /// Largest difference between the channels of `pixel` and `rgb`.
+    fn distance(pixel: u32, (r, g, b): (u8, u8, u8)) -> u8 {
+        let [_, pr, pg, pb] = pixel.to_be_bytes();
+        pr.abs_diff(r).max(pg.abs_diff(g)).max(pb.abs_diff(b))
+    }
+
+    fn render(case: &Case) -> GridRenderer {
+        let f = &case.frame;
+        let mut r = GridRenderer::new(None, 18.0, f.width as usize, f.height as usize, None, 1);
+        r.apply_frame(f);
+        r
+    }
+
+    /// The pixels of the cell at `p`.
+    fn cell_pixels(r: &GridRenderer, p: Point) -> Vec<u32> {
+        let (cw, ch) = r.cell_size();
+        let (x0, y0) = (p.x as usize * cw, p.y as usize * ch);
+        (0..ch)
+            .flat_map(|dy| (0..cw).map(move |dx| (x0 + dx, y0 + dy)))
+            .map(|(x, y)| r.pixels[y * r.pixel_width() + x])
+            .collect()
+    }
+
+    #[test]
+    fn draws_the_conformance_cases() {
+        for case in conformance::cases() {
+            let r = render(&case);
+            let mut blank = case.clone();
+            for fc in &mut blank.frame.cells {
+                fc.cell.ch = ' ';
+            }
+            let blank = render(&blank);
+            let mut font = CellFont::new(None, 18.0);
+            for FrameCell { cell, pos } in &case.frame.cells {
+                let at = format!("{} at {pos}", case.name);
+                let (fg, bg) = cell.style.rgb();
+                assert!(
+                    cell_pixels(&blank, *pos)
+                        .iter()
+                        .all(|&px| px == rgb_pixel(bg)),
+                    "background of {at}"
+                );
+                if cell.ch == ' ' {
+                    continue;
+                }
+                assert!(font.has_glyph(cell.ch), "built-in font lacks {:?}", cell.ch);
+                // The most covered pixel of the glyph, blended as much
+                // towards the foreground.
+                let alpha = font.glyph(cell.ch).map(|g| *g.bitmap.iter().max().unwrap());
+                let alpha = alpha.unwrap_or_else(|| panic!("no glyph {:?} for {at}", cell.ch));
+                let blend = |f: u8, b: u8| {
+                    ((f as u32 * alpha as u32 + b as u32 * (255 - alpha as u32)) / 255) as u8
+                };
+                let ink = (blend(fg.0, bg.0), blend(fg.1, bg.1), blend(fg.2, bg.2));
+                let drawn = cell_pixels(&r, *pos);
+                let farthest = drawn.iter().map(|&px| distance(px, bg)).max().unwrap();
+                assert!(
+                    farthest.abs_diff(distance(rgb_pixel(ink), bg)) <= 2,
+                    "glyph of {at}: {farthest} from the background"
+                );
+                assert!(
+                    drawn.iter().any(|&px| distance(px, ink) <= 2),
+                    "ink of {at}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ignores_attributes_as_profiled() {
+        let shown = conformance::profile("winit").attrs;
+        let case = conformance::attributes();
+        let r = render(&case);
+        let cells = &case.frame.cells;
+        let pixels = |attrs: AttrMask| {
+            let fc = cells
+                .iter()
+                .find(|fc| fc.cell.style.attrs == attrs)
+                .unwrap();
+            cell_pixels(&r, fc.pos)
+        };
+        for fc in cells {
+            let attrs = fc.cell.style.attrs;
+            assert_eq!(
+                cell_pixels(&r, fc.pos),
+                pixels(attrs & shown),
+                "{attrs:?} draws as {:?}",
+                attrs & shown
+            );
+        }
+        for (mask, name) in conformance::ATTRIBUTES {
+            if shown.contains(mask) {
+                assert_ne!(pixels(mask), pixels(AttrMask::NONE), "{name}");
+            }
+        }
     }
 }
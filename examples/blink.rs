@@ -0,0 +1,25 @@
+//! Blinking cursor driven by a subscription, using crossterm.
+//!
+//! Run: cargo run --bin blink
+
+use gruid_core::app::{App, AppConfig};
+use gruid_crossterm::CrosstermDriver;
+use gruid_examples::blink::{BlinkModel, HEIGHT, WIDTH};
+
+fn main() {
+    let mut app = App::new(AppConfig {
+        model: BlinkModel::new(),
+        driver: CrosstermDriver::new(),
+        width: WIDTH,
+        height: HEIGHT,
+        frame_writer: None,
+        effect_handlers: Vec::new(),
+        double_click: None,
+        seed: None,
+    });
+
+    if let Err(e) = app.run() {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
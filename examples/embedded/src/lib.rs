@@ -0,0 +1,103 @@
+//! `gruid-core` without `std`, as on an RP2040 badge with a small LCD.
+//!
+//! There is no `App` without `std`: the firmware turns button presses
+//! into [`Msg`]s, updates its state, draws into a [`Grid`] and copies the
+//! changed cells to the LCD buffer with [`render_to_slice`]. It is a
+//! library, so that it builds for a bare target without a board support
+//! crate:
+//!
+//! ```sh
+//! cargo check --manifest-path examples/embedded/Cargo.toml --target thumbv6m-none-eabi
+//! ```
+//!
+//! The firmware provides the global allocator and panic handler.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use gruid_core::grid::{Grid, compute_frame, render_to_slice};
+use gruid_core::{Cell, Key, Msg, Point, Range};
+
+pub const WIDTH: i32 = 16;
+pub const HEIGHT: i32 = 4;
+
+/// A counter moved with the arrow keys, shown on a 16×4 character LCD.
+pub struct Badge {
+    count: i32,
+    prev: Grid,
+    curr: Grid,
+    /// What the LCD shows, row by row.
+    lcd: Vec<Cell>,
+}
+
+impl Default for Badge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Badge {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            prev: Grid::new(WIDTH, HEIGHT),
+            curr: Grid::new(WIDTH, HEIGHT),
+            lcd: vec![Cell::default(); (WIDTH * HEIGHT) as usize],
+        }
+    }
+
+    /// Handle a message from the buttons.
+    pub fn update(&mut self, msg: Msg) {
+        if let Msg::KeyDown { key, .. } = msg {
+            match key {
+                Key::ArrowUp => self.count += 1,
+                Key::ArrowDown => self.count -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// Draw the state and update the LCD buffer. Returns the range of
+    /// cells to send to the display, if any changed.
+    pub fn render(&mut self) -> Option<Range> {
+        self.curr.fill(Cell::default());
+        let mut digits = [0u8; 11];
+        let text = itoa(self.count, &mut digits);
+        for (x, ch) in text.chars().enumerate() {
+            let cell = Cell::default().with_char(ch);
+            self.curr.set(Point::new(x as i32, 1), cell);
+        }
+        let frame = compute_frame(&self.prev, &self.curr);
+        self.prev.copy_from(&self.curr);
+        render_to_slice(&frame, &mut self.lcd)
+    }
+
+    /// The LCD buffer, row by row.
+    pub fn lcd(&self) -> &[Cell] {
+        &self.lcd
+    }
+}
+
+/// `n` in decimal, written at the end of `buf`. There is no `format!`
+/// worth its code size on the smallest targets.
+fn itoa(n: i32, buf: &mut [u8; 11]) -> &str {
+    let mut i = buf.len();
+    let mut m = n.unsigned_abs();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (m % 10) as u8;
+        m /= 10;
+        if m == 0 {
+            break;
+        }
+    }
+    if n < 0 {
+        i -= 1;
+        buf[i] = b'-';
+    }
+    core::str::from_utf8(&buf[i..]).unwrap_or_default()
+}
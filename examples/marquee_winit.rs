@@ -0,0 +1,24 @@
+//! A marquee animated by the frames of the winit driver.
+//!
+//! Run: cargo run --bin marquee-winit
+
+use gruid_core::app::{AppRunner, EventLoopDriver};
+use gruid_examples::marquee::{FRAME_RATE, HEIGHT, Marquee, WIDTH};
+use gruid_winit::{WinitConfig, WinitDriver};
+
+fn main() {
+    let driver = WinitDriver::new(WinitConfig {
+        title: "gruid-rs marquee".into(),
+        grid_width: WIDTH,
+        grid_height: HEIGHT,
+        frame_rate: Some(FRAME_RATE),
+        ..Default::default()
+    });
+
+    let runner = AppRunner::new(Box::new(Marquee::new()), WIDTH, HEIGHT);
+
+    if let Err(e) = driver.run(runner) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
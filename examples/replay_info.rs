@@ -0,0 +1,58 @@
+//! Print the metadata, frame count and duration of a recording.
+//!
+//! Run: cargo run --bin gruid-replay-info -- session.grf
+
+use std::fs::File;
+use std::io::BufReader;
+
+use gruid_core::recording::FrameDecoder;
+
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: gruid-replay-info <recording>");
+        std::process::exit(2);
+    };
+    let file = match File::open(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            std::process::exit(1);
+        }
+    };
+    let mut dec = FrameDecoder::new(BufReader::new(file));
+
+    match (dec.metadata(), dec.metadata_error()) {
+        (Some(meta), _) => {
+            println!("App:      {} {}", meta.app_name, meta.app_version);
+            if let Some(seed) = meta.seed {
+                println!("Seed:     {seed}");
+            }
+            println!("Started:  {} (Unix time)", meta.started_at_unix);
+            let mut tags: Vec<_> = meta.custom.iter().collect();
+            tags.sort();
+            for (k, v) in tags {
+                println!("{k}: {v}");
+            }
+        }
+        (None, Some(e)) => println!("Metadata: unreadable ({e})"),
+        (None, None) => println!("Metadata: none"),
+    }
+
+    let mut frames = 0usize;
+    let mut last_ms = 0;
+    loop {
+        match dec.decode() {
+            Ok(Some(frame)) => {
+                frames += 1;
+                last_ms = frame.time_ms;
+            }
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("{path}: frame {}: {e}", frames + 1);
+                break;
+            }
+        }
+    }
+    println!("Frames:   {frames}");
+    println!("Duration: {:.1}s", last_ms as f64 / 1000.0);
+}
@@ -15,6 +15,9 @@ fn main() {
         width: WIDTH,
         height: HEIGHT,
         frame_writer: None,
+        effect_handlers: Vec::new(),
+        double_click: None,
+        seed: None,
     });
 
     if let Err(e) = app.run() {
@@ -0,0 +1,27 @@
+//! Falling-sand playground using crossterm.
+//!
+//! Run: cargo run --bin sandbox
+
+use gruid_core::app::{App, AppConfig};
+use gruid_crossterm::CrosstermDriver;
+use gruid_examples::sandbox::{HEIGHT, Sandbox, WIDTH};
+
+fn main() {
+    let sandbox = Sandbox::new();
+    let driver = CrosstermDriver::new();
+    let mut app = App::new(AppConfig {
+        model: sandbox,
+        driver,
+        width: WIDTH,
+        height: HEIGHT,
+        frame_writer: None,
+        effect_handlers: Vec::new(),
+        double_click: None,
+        seed: None,
+    });
+
+    if let Err(e) = app.run() {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,25 @@
+//! Falling-sand playground using wgpu (GPU-accelerated).
+//!
+//! Run: cargo run --bin sandbox-wgpu
+
+use gruid_core::app::{AppRunner, EventLoopDriver};
+use gruid_examples::sandbox::{HEIGHT, Sandbox, WIDTH};
+use gruid_wgpu::{WgpuConfig, WgpuDriver};
+
+fn main() {
+    let sandbox = Sandbox::new();
+    let driver = WgpuDriver::new(WgpuConfig {
+        title: "gruid-rs sandbox (wgpu)".into(),
+        font_size: 18.0,
+        grid_width: WIDTH,
+        grid_height: HEIGHT,
+        ..Default::default()
+    });
+
+    let runner = AppRunner::new(Box::new(sandbox), WIDTH, HEIGHT);
+
+    if let Err(e) = driver.run(runner) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
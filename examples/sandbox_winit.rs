@@ -0,0 +1,25 @@
+//! Falling-sand playground using winit + softbuffer.
+//!
+//! Run: cargo run --bin sandbox-winit
+
+use gruid_core::app::{AppRunner, EventLoopDriver};
+use gruid_examples::sandbox::{HEIGHT, Sandbox, WIDTH};
+use gruid_winit::{WinitConfig, WinitDriver};
+
+fn main() {
+    let sandbox = Sandbox::new();
+    let driver = WinitDriver::new(WinitConfig {
+        title: "gruid-rs sandbox".into(),
+        font_size: 18.0,
+        grid_width: WIDTH,
+        grid_height: HEIGHT,
+        ..Default::default()
+    });
+
+    let runner = AppRunner::new(Box::new(sandbox), WIDTH, HEIGHT);
+
+    if let Err(e) = driver.run(runner) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,95 @@
+//! Blinking cursor shared by the `blink` example, driven by a
+//! [`Subscription`](gruid_core::app::Subscription) rather than a thread, so
+//! the same model runs in the browser through `gruid-web`.
+
+use std::time::Duration;
+
+use gruid_core::{
+    Cell, Point,
+    app::{Effect, every},
+    grid::Grid,
+    messages::{Key, Msg},
+    style::{Color, Style},
+};
+
+pub const WIDTH: i32 = 40;
+pub const HEIGHT: i32 = 3;
+
+const BLINK: Duration = Duration::from_secs(1);
+const PROMPT: &str = "Name: ";
+
+/// Message of the blink subscription.
+struct Blink;
+
+/// A one-line prompt whose cursor blinks once per second. Escape quits.
+#[derive(Debug, Default)]
+pub struct BlinkModel {
+    text: String,
+    cursor_on: bool,
+}
+
+impl BlinkModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl gruid_core::app::Model for BlinkModel {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        match msg {
+            Msg::Init => {
+                self.cursor_on = true;
+                return Some(every(BLINK, || Some(Msg::custom(Blink))));
+            }
+            Msg::KeyDown { key, .. } => match key {
+                Key::Escape => return Some(Effect::End),
+                Key::Backspace => {
+                    self.text.pop();
+                }
+                Key::Char(c) if self.text.chars().count() < (WIDTH as usize - PROMPT.len() - 1) => {
+                    self.text.push(c);
+                }
+                _ => return None,
+            },
+            _ if msg.downcast_ref::<Blink>().is_some() => {
+                self.cursor_on = !self.cursor_on;
+                return None;
+            }
+            _ => return None,
+        }
+        // Typing shows the cursor at once.
+        self.cursor_on = true;
+        None
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        grid.fill(Cell::default());
+        let line = format!("{PROMPT}{}", self.text);
+        for (i, ch) in line.chars().enumerate() {
+            grid.set(Point::new(i as i32, 1), Cell::default().with_char(ch));
+        }
+        if self.cursor_on {
+            let cursor = Style::default().with_bg(Color::from_rgb(200, 200, 200));
+            let x = line.chars().count() as i32;
+            grid.set(Point::new(x, 1), Cell::default().with_style(cursor));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::app::Model;
+
+    #[test]
+    fn blink_toggles_cursor() {
+        let mut m = BlinkModel::new();
+        assert!(matches!(m.update(Msg::Init), Some(Effect::Subscribe(_))));
+        assert!(m.cursor_on);
+        m.update(Msg::custom(Blink));
+        assert!(!m.cursor_on);
+        m.update(Msg::key(Key::Char('a')));
+        assert!(m.cursor_on);
+        assert_eq!(m.text, "a");
+    }
+}
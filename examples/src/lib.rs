@@ -1,24 +1,48 @@
 //! Shared roguelike game model used by both terminal and graphical examples.
 //!
-//! Demonstrates: cave generation, FOV, A* pathfinding, Dijkstra maps,
-//! UI widgets (status bar, message log, help pager), mouse support,
-//! and simple monster AI.
+//! Demonstrates: cave generation behind a loading screen, FOV, A*
+//! pathfinding, Dijkstra maps, UI widgets (status bar, message log, help
+//! pager), mouse support, monster AI with two factions that fight the
+//! player and each other, and a day and night cycle that narrows the
+//! player's sight after dusk.
+//!
+//! The [`sandbox`] module holds a second, falling-sand example,
+//! [`blink`] a minimal one driven by a subscription, and [`marquee`] one
+//! animated by the frames of the graphical drivers.
+
+pub mod blink;
+pub mod marquee;
+pub mod sandbox;
+
+use std::rc::Rc;
+use std::time::Duration;
 
 use gruid_core::{
-    Cell, Point, Range,
-    app::Effect,
-    grid::Grid,
-    messages::{Key, MouseAction, Msg},
+    Cell, Direction, DriverCaps, Platform, Point, Range,
+    app::{Effect, Progress, TurnBudget, cmd_progress, every, set_clipboard},
+    grid::{Grid, OverlayGrid},
+    messages::{Key, MouseAction, Msg, SystemTheme},
+    seed::sub_seed,
     style::{AttrMask, Color, Style},
 };
-use gruid_paths::{AstarPather, PathRange, Pather, WeightedPather};
+use gruid_paths::PathRange;
 use gruid_rl::{
-    fov::{CircularLighter, FOV, FovShape, Lighter},
+    clock::{DayPhase, GameClock, effective_sight_radius},
+    effects::{EffectDef, EffectEvent, Effects, Stacking},
+    entities::{Entities, EntityId},
+    factions::{FactionId, FactionTable, Relation, TargetPolicy},
+    fov::{CircularLighter, FOV, FovShape},
     grid::{Cell as RlCell, Grid as RlGrid},
     mapgen::{CellularAutomataRule, MapGen},
+    terrain::{TerrainFlags, TerrainMask},
+    triggers::{TriggerEvent, TriggerShape, Triggers},
 };
-use gruid_ui::{BoxDecor, Pager, PagerAction, PagerConfig, PagerKeys, PagerStyle, StyledText};
-use rand::{RngExt, SeedableRng};
+use gruid_ui::{
+    AccessibilityNode, Announcer, BoxDecor, ContextMenu, ContextMenuConfig, LoadingScreen,
+    LoadingStyle, MenuAction, MenuEntry, MenuKeys, MenuStyle, Pager, PagerAction, PagerConfig,
+    PagerKeys, PagerStyle, Role, StyledText,
+};
+use rand::{RngExt, SeedableRng, rngs::StdRng};
 
 pub const WIDTH: i32 = 80;
 pub const HEIGHT: i32 = 24;
@@ -32,94 +56,165 @@ const COL_WALL_LIT: Color = Color::from_rgb(100, 100, 130);
 const COL_FLOOR_LIT: Color = Color::from_rgb(60, 55, 50);
 const COL_WALL_DARK: Color = Color::from_rgb(35, 35, 50);
 const COL_FLOOR_DARK: Color = Color::from_rgb(30, 28, 25);
+/// Visibility levels of lit floor in graphical back-ends, which fade it out
+/// at the edge of the field of vision.
+const FOV_LEVELS: u8 = 8;
 const COL_PLAYER: Color = Color::from_rgb(255, 220, 80);
 const COL_MONSTER: Color = Color::from_rgb(220, 50, 50);
+const COL_BEAST: Color = Color::from_rgb(220, 150, 50);
 const COL_PATH: Color = Color::from_rgb(50, 180, 255);
+const COL_EXPLORED: Color = Color::from_rgb(30, 50, 80);
 const COL_DIJKSTRA_NEAR: Color = Color::from_rgb(40, 120, 60);
 const COL_DIJKSTRA_FAR: Color = Color::from_rgb(120, 40, 40);
 const COL_STATUS_FG: Color = Color::from_rgb(200, 200, 200);
 const COL_STATUS_BG: Color = Color::from_rgb(30, 30, 50);
 const COL_LOG_FG: Color = Color::from_rgb(170, 170, 190);
+const COL_SPRING: Color = Color::from_rgb(80, 160, 255);
 const COL_CURSOR: Color = Color::from_rgb(80, 200, 80);
+const COL_MENU_ACTIVE: Color = Color::from_rgb(60, 60, 100);
 
 const HELP_TEXT: &str = "\
-Movement:    arrows / hjkl / yubn (diagonals)
+Movement:    arrows / hjkl / yubn (diagonals) / numpad; hold an arrow
+             to keep walking where key releases are reported
 Wait:        . or space
-Mouse:       click to auto-move toward target
+Rest:        R to wait up to 100 turns, until a monster shows up
+Mouse:       click to auto-move toward target, right-click for actions
 Examine:     x to enter look mode, move cursor, ESC to exit
 Pathfinding: p to toggle path overlay
 Algorithm:   TAB to switch A* / JPS
 Dijkstra:    d to toggle distance heatmap
+Explored:    v to show cells visited by the last path search
 FOV shape:   f to toggle square / circle
 Help:        ? to show this screen
+Announce:    F2 to log descriptions of menus and this screen
 Quit:        q or ESC";
 
+/// The help text, without mouse hints if there is no mouse.
+fn help_text(caps: &DriverCaps) -> String {
+    HELP_TEXT
+        .lines()
+        .filter(|l| caps.mouse || !l.starts_with("Mouse:"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 // ---------------------------------------------------------------------------
 // Monster
 // ---------------------------------------------------------------------------
 
+const PLAYER_FACTION: FactionId = FactionId(0);
+/// Goblins and their kin.
+const CAVE_FACTION: FactionId = FactionId(1);
+/// Wild animals, hostile to everyone else.
+const BEAST_FACTION: FactionId = FactionId(2);
+
+fn factions() -> FactionTable {
+    let mut table = FactionTable::new(3);
+    table.set_relation(PLAYER_FACTION, CAVE_FACTION, Relation::Hostile);
+    table.set_relation(PLAYER_FACTION, BEAST_FACTION, Relation::Hostile);
+    table.set_relation(CAVE_FACTION, BEAST_FACTION, Relation::Hostile);
+    table
+}
+
+/// Trigger regions placed on the map.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Feature {
+    /// Restores the player's HP once.
+    HealingSpring,
+}
+
+/// Timed status effects.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Status {
+    /// Damage each turn, from a beast's bite.
+    Poison,
+}
+
+/// A beast's bite poisons for 1 HP a turn over three turns; another bite
+/// restarts it.
+const VENOM: EffectDef<Status> = EffectDef {
+    kind: Status::Poison,
+    magnitude: 1,
+    duration: 3,
+    stacking: Stacking::Refresh,
+};
+
+/// Something a monster can attack, ordered so the player wins ties.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Actor {
+    Player,
+    Monster(EntityId),
+}
+
+/// A monster, positioned by the `Entities` store holding it.
 #[derive(Clone)]
 struct Monster {
-    pos: Point,
     ch: char,
     hp: i32,
+    faction: FactionId,
     awake: bool,
+    /// Area the monster never leaves: a 17×11 box centred on its spawn
+    /// point, cut to the map. The cave has no rooms to use instead.
+    territory: Range,
 }
 
 // ---------------------------------------------------------------------------
-// Map pather for A* / Dijkstra
+// Terrain flags, for FOV and pathfinding
 // ---------------------------------------------------------------------------
 
-struct MapPather<'a> {
-    map: &'a RlGrid,
-}
+/// How far the player sees, in daylight.
+const SIGHT: i32 = 8;
 
-impl Pather for MapPather<'_> {
-    fn neighbors(&self, p: Point, buf: &mut Vec<Point>) {
-        for &d in &[
-            Point::new(1, 0),
-            Point::new(-1, 0),
-            Point::new(0, 1),
-            Point::new(0, -1),
-        ] {
-            let np = p.shift(d.x, d.y);
-            if self.map.at(np) == Some(FLOOR) {
-                buf.push(np);
-            }
-        }
-    }
-}
+/// Turns in a day, short so that nightfall comes while exploring.
+const TURNS_PER_DAY: u64 = 200;
 
-impl WeightedPather for MapPather<'_> {
-    fn cost(&self, _from: Point, _to: Point) -> i32 {
-        1
-    }
-}
-
-impl AstarPather for MapPather<'_> {
-    fn estimate(&self, from: Point, to: Point) -> i32 {
-        gruid_paths::manhattan(from, to)
+/// What the terrain of a map cell blocks.
+fn terrain(c: RlCell) -> TerrainMask {
+    if c == WALL {
+        TerrainMask::SOLID
+    } else {
+        TerrainMask::NONE
     }
 }
 
 // ---------------------------------------------------------------------------
-// FOV lighter
+// UI palette
 // ---------------------------------------------------------------------------
 
-struct MapLighter<'a> {
-    map: &'a RlGrid,
+/// Colours of the status bar and message log, which follow the system
+/// theme. The map keeps its own dark colours.
+#[derive(Clone, Copy)]
+struct UiPalette {
+    status_fg: Color,
+    status_bg: Color,
+    log_fg: Color,
+    log_bg: Color,
 }
 
-impl Lighter for MapLighter<'_> {
-    fn cost(&self, _src: Point, from: Point, _to: Point) -> i32 {
-        if self.map.at(from) == Some(WALL) {
-            i32::MAX
-        } else {
-            1
+impl UiPalette {
+    const fn dark() -> Self {
+        Self {
+            status_fg: COL_STATUS_FG,
+            status_bg: COL_STATUS_BG,
+            log_fg: COL_LOG_FG,
+            log_bg: COL_BG,
         }
     }
-    fn max_cost(&self, _src: Point) -> i32 {
-        8
+
+    const fn light() -> Self {
+        Self {
+            status_fg: Color::from_rgb(40, 40, 60),
+            status_bg: Color::from_rgb(190, 190, 210),
+            log_fg: Color::from_rgb(50, 50, 70),
+            log_bg: Color::from_rgb(235, 235, 240),
+        }
+    }
+
+    fn for_theme(theme: SystemTheme) -> Self {
+        match theme {
+            SystemTheme::Dark => Self::dark(),
+            SystemTheme::Light => Self::light(),
+        }
     }
 }
 
@@ -134,9 +229,19 @@ enum Mode {
     Help,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-/// Auto-move tick message.
-struct AutoMoveTick;
+/// Turns waited by the rest command, unless interrupted.
+const REST_TURNS: u32 = 100;
+
+/// Time between steps while an arrow key is held.
+const HOLD_STEP: Duration = Duration::from_millis(120);
+
+/// Sent every [`HOLD_STEP`] while an arrow key is held, by the
+/// subscription of the press with this number.
+struct HoldStep(u32);
+
+/// Actions of the right-click menu, by entry key.
+const ACTION_WALK: u64 = 0;
+const ACTION_EXAMINE: u64 = 1;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum PathAlgo {
@@ -160,128 +265,295 @@ impl PathAlgo {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Level generation (runs on a worker thread behind the loading screen)
+// ---------------------------------------------------------------------------
+
+/// A generated level, sent back from the loading worker.
+struct Level {
+    cells: Vec<RlCell>,
+    player: Point,
+    monsters: Entities<Monster>,
+    spring: Point,
+}
+
+/// Generate the level of the run with master seed `seed`. The caves and
+/// the placements draw from generators of their own, so that changing how
+/// monsters spawn leaves the caves of a seed as they were.
+fn generate_level(progress: &Progress, seed: u64) -> Level {
+    progress.report(0.0, "Carving caves...");
+    let map = RlGrid::new(WIDTH, MAP_HEIGHT);
+    map.fill(WALL);
+
+    let mapgen = StdRng::seed_from_u64(sub_seed(seed, "mapgen"));
+    let mut mg = MapGen::with_grid(map.clone(), mapgen);
+    let rules = vec![
+        CellularAutomataRule {
+            w_cutoff1: 5,
+            w_cutoff2: 25,
+            walls_out_of_range: true,
+            reps: 4,
+        },
+        CellularAutomataRule {
+            w_cutoff1: 5,
+            w_cutoff2: 25,
+            walls_out_of_range: true,
+            reps: 3,
+        },
+    ];
+    mg.cellular_automata_cave(WALL, FLOOR, 0.45, &rules);
+    let map = mg.grid.clone();
+
+    progress.report(0.6, "Placing the player...");
+    let mut player = Point::new(WIDTH / 2, MAP_HEIGHT / 2);
+    'find_player: for y in 0..MAP_HEIGHT {
+        for x in 0..WIDTH {
+            let p = Point::new(x, y);
+            if map.at(p) == Some(FLOOR) {
+                player = p;
+                break 'find_player;
+            }
+        }
+    }
+
+    progress.report(0.8, "Spawning monsters...");
+    let mut rng = StdRng::seed_from_u64(sub_seed(seed, "spawns"));
+    let cave_chars = ['g', 'k', 'o'];
+    let beast_chars = ['r', 's', 'j'];
+    let mut monsters = Entities::new();
+    let mut attempts = 0;
+    while monsters.len() < 8 && attempts < 500 {
+        let p = Point::new(rng.random_range(0..WIDTH), rng.random_range(0..MAP_HEIGHT));
+        attempts += 1;
+        if map.at(p) != Some(FLOOR)
+            || p == player
+            || gruid_paths::manhattan(p, player) < 5
+            || monsters.at(p).next().is_some()
+        {
+            continue;
+        }
+        // Alternate factions so both live all over the cave.
+        let n = monsters.len();
+        let (faction, ch) = if n % 2 == 0 {
+            (CAVE_FACTION, cave_chars[n / 2 % cave_chars.len()])
+        } else {
+            (BEAST_FACTION, beast_chars[n / 2 % beast_chars.len()])
+        };
+        monsters.insert(
+            p,
+            Monster {
+                ch,
+                hp: 3,
+                faction,
+                awake: false,
+                territory: Range::new(p.x - 8, p.y - 5, p.x + 9, p.y + 6)
+                    .intersect(Range::new(0, 0, WIDTH, MAP_HEIGHT)),
+            },
+        );
+    }
+
+    // A healing spring a few steps from the start.
+    let mut spring = player;
+    for _ in 0..500 {
+        let p = Point::new(rng.random_range(0..WIDTH), rng.random_range(0..MAP_HEIGHT));
+        let d = gruid_paths::manhattan(p, player);
+        if map.at(p) == Some(FLOOR) && (3..=8).contains(&d) && monsters.at(p).next().is_none() {
+            spring = p;
+            break;
+        }
+    }
+
+    progress.report(1.0, "Done.");
+    Level {
+        cells: map.iter().map(|(_, c)| c).collect(),
+        player,
+        monsters,
+        spring,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Game
 // ---------------------------------------------------------------------------
 
 pub struct Game {
     map: RlGrid,
+    /// What the map blocks, kept in sync with it.
+    terrain: TerrainFlags,
     fov: FOV,
+    /// Monster line of sight, for picking targets.
+    monster_fov: FOV,
+    factions: FactionTable,
     path_range: PathRange,
     seen: Vec<bool>,
     player: Point,
     hp: i32,
     max_hp: i32,
     turns: u32,
-    monsters: Vec<Monster>,
+    /// Time of day, advanced a tick a turn.
+    clock: GameClock,
+    monsters: Entities<Monster>,
+    triggers: Triggers<Feature>,
+    statuses: Effects<Actor, Status>,
+    /// Where the healing spring is, drawn while its trigger remains.
+    spring: Point,
     messages: Vec<String>,
     // Overlays
     show_path: bool,
     show_dijkstra: bool,
+    show_explored: bool,
     path_algo: PathAlgo,
     fov_shape: FovShape,
     path_cache: Vec<Point>,
+    // Cells visited by the search that produced `path_cache`.
+    explored: Vec<Point>,
     // Cursor / mouse
     cursor: Point,
     mode: Mode,
     // Help pager
     pager: Option<Pager>,
-    // Auto-move
+    // Right-click menu, and the grid it draws into.
+    context: Option<ContextMenu>,
+    context_grid: Grid,
+    // Auto-move, one step per frame.
     auto_path: Vec<Point>,
     auto_step: usize,
+    // Turns of rest left, resolved over as many frames as needed.
+    rest_left: u32,
+    // Direction of the arrow key held, shared with the subscription
+    // stepping that way until the key is released.
+    held: Rc<std::cell::Cell<Option<Direction>>>,
+    // Number of the last press that started stepping: subscriptions of
+    // earlier presses, released since, stop.
+    hold_press: Rc<std::cell::Cell<u32>>,
+    // Whether key repeats are ignored for movement, one step per press.
+    ignore_repeats: bool,
+    // Shown until the level has been generated.
+    loading: Option<LoadingScreen>,
+    // What the driver supports.
+    caps: DriverCaps,
+    // Status bar and log colours, from the system theme.
+    ui: UiPalette,
+    // Logs what the focused widget shows, as a screen reader would say it.
+    announcer: Option<Announcer>,
+    // Master seed of the run, from the runner.
+    seed: u64,
 }
 
 impl Game {
     pub fn new() -> Self {
-        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let rg = Range::new(0, 0, WIDTH, MAP_HEIGHT);
         let map = RlGrid::new(WIDTH, MAP_HEIGHT);
         map.fill(WALL);
+        let loading_style = LoadingStyle {
+            label: Style::default().with_fg(COL_STATUS_FG).with_bg(COL_BG),
+            bar: Style::default().with_fg(COL_PLAYER).with_bg(COL_BG),
+            empty: Style::default().with_fg(COL_WALL_DARK).with_bg(COL_BG),
+        };
 
-        let mut mg = MapGen::with_grid(map.clone(), rand::rngs::StdRng::from_rng(&mut rng));
-        let rules = vec![
-            CellularAutomataRule {
-                w_cutoff1: 5,
-                w_cutoff2: 25,
-                walls_out_of_range: true,
-                reps: 4,
-            },
-            CellularAutomataRule {
-                w_cutoff1: 5,
-                w_cutoff2: 25,
-                walls_out_of_range: true,
-                reps: 3,
-            },
-        ];
-        mg.cellular_automata_cave(WALL, FLOOR, 0.45, &rules);
-        let map = mg.grid.clone();
-
-        // Find a floor tile for the player.
-        let mut player = Point::new(WIDTH / 2, MAP_HEIGHT / 2);
-        'find_player: for y in 0..MAP_HEIGHT {
-            for x in 0..WIDTH {
-                let p = Point::new(x, y);
-                if map.at(p) == Some(FLOOR) {
-                    player = p;
-                    break 'find_player;
-                }
-            }
-        }
-
-        // Spawn some monsters on random floor tiles.
-        let monster_chars = ['g', 'k', 'r', 's', 'z'];
-        let mut monsters = Vec::new();
-        let mut attempts = 0;
-        while monsters.len() < 8 && attempts < 500 {
-            let p = Point::new(rng.random_range(0..WIDTH), rng.random_range(0..MAP_HEIGHT));
-            attempts += 1;
-            if map.at(p) != Some(FLOOR)
-                || p == player
-                || gruid_paths::manhattan(p, player) < 5
-                || monsters.iter().any(|m: &Monster| m.pos == p)
-            {
-                continue;
-            }
-            monsters.push(Monster {
-                pos: p,
-                ch: monster_chars[monsters.len() % monster_chars.len()],
-                hp: 3,
-                awake: false,
-            });
-        }
-
-        let rg = Range::new(0, 0, WIDTH, MAP_HEIGHT);
-        let fov = FOV::new(rg);
-        let path_range = PathRange::new(rg);
-        let seen = vec![false; (WIDTH * MAP_HEIGHT) as usize];
-
-        let mut game = Game {
+        Game {
+            terrain: TerrainFlags::from_grid(&map, terrain),
             map,
-            fov,
-            path_range,
-            seen,
-            player,
+            fov: FOV::new(rg),
+            monster_fov: FOV::new(rg),
+            factions: factions(),
+            path_range: PathRange::new(rg),
+            seen: vec![false; (WIDTH * MAP_HEIGHT) as usize],
+            player: Point::ZERO,
             hp: 20,
             max_hp: 20,
             turns: 0,
-            monsters,
+            // From the morning, with a moonlit night the player still sees
+            // a few cells in.
+            clock: GameClock::new(TURNS_PER_DAY)
+                .with_light(80, 255)
+                .with_ticks(TURNS_PER_DAY * 8 / 24),
+            monsters: Entities::new(),
+            triggers: Triggers::new(),
+            statuses: Effects::new(),
+            spring: Point::ZERO,
             messages: vec!["Welcome! Press ? for help.".into()],
             show_path: false,
             show_dijkstra: false,
+            show_explored: false,
             path_algo: PathAlgo::Astar,
             fov_shape: FovShape::Square,
             path_cache: Vec::new(),
-            cursor: player,
+            explored: Vec::new(),
+            cursor: Point::ZERO,
             mode: Mode::Play,
             pager: None,
+            context: None,
+            context_grid: Grid::new(WIDTH, HEIGHT),
             auto_path: Vec::new(),
             auto_step: 0,
-        };
-        game.compute_fov();
-        game
+            rest_left: 0,
+            held: Rc::new(std::cell::Cell::new(None)),
+            hold_press: Rc::new(std::cell::Cell::new(0)),
+            ignore_repeats: false,
+            loading: Some(
+                LoadingScreen::new("Starting...")
+                    .with_spinner(true)
+                    .with_style(loading_style),
+            ),
+            caps: DriverCaps::default(),
+            ui: UiPalette::dark(),
+            announcer: None,
+            seed: 0,
+        }
+    }
+
+    /// Move one step per key press, ignoring the repeats of a held key
+    /// (builder).
+    pub fn with_ignore_repeats(mut self, ignore: bool) -> Self {
+        self.ignore_repeats = ignore;
+        self
+    }
+
+    /// Install a level generated by [`generate_level`] and leave the
+    /// loading screen.
+    fn load_level(&mut self, level: &Level) {
+        self.map
+            .map_cells(|p, _| level.cells[(p.y * WIDTH + p.x) as usize]);
+        self.terrain.update_from(&self.map, terrain);
+        self.player = level.player;
+        self.cursor = level.player;
+        self.monsters = level.monsters.clone();
+        self.spring = level.spring;
+        self.triggers = Triggers::new();
+        if level.spring != level.player {
+            let shape = TriggerShape::Points([level.spring].into());
+            self.triggers.add_one_shot(shape, Feature::HealingSpring);
+        }
+        self.loading = None;
+        self.compute_fov();
+    }
+
+    fn update_loading(&mut self, msg: Msg) -> Option<Effect> {
+        match msg {
+            Msg::Init => {
+                let seed = self.seed;
+                Some(cmd_progress(move |p| {
+                    Some(Msg::custom(generate_level(p, seed)))
+                }))
+            }
+            Msg::Quit => Some(Effect::End),
+            _ => {
+                if let Some(seed) = msg.seed() {
+                    self.seed = seed;
+                } else if let Some(level) = msg.downcast_ref::<Level>() {
+                    self.load_level(level);
+                } else if let Some(loading) = self.loading.as_mut() {
+                    loading.update(&msg);
+                }
+                None
+            }
+        }
     }
 
     fn compute_fov(&mut self) {
-        let base = MapLighter { map: &self.map };
+        let sight = effective_sight_radius(SIGHT, self.clock.ambient_light());
+        let base = self.terrain.sight(sight);
         match self.fov_shape {
             FovShape::Square => {
                 self.fov.vision_map(&base, self.player);
@@ -298,8 +570,8 @@ impl Game {
             }
         }
         // Wake up monsters in FOV.
-        for m in &mut self.monsters {
-            if self.fov.at(m.pos).is_some() {
+        for (_, pos, m) in self.monsters.iter_mut() {
+            if self.fov.at(pos).is_some() {
                 m.awake = true;
             }
         }
@@ -308,15 +580,14 @@ impl Game {
     fn find_path(&mut self, from: Point, to: Point) -> Option<Vec<Point>> {
         match self.path_algo {
             PathAlgo::Astar => {
-                let pather = MapPather { map: &self.map };
+                let pather = self.terrain.moves(TerrainMask::BLOCKS_MOVE);
                 self.path_range.astar_path(&pather, from, to)
             }
             PathAlgo::Jps => {
-                let map = &self.map;
                 self.path_range.jps_path(
                     from,
                     to,
-                    |p| map.at(p) == Some(FLOOR),
+                    self.terrain.as_passable(TerrainMask::BLOCKS_MOVE),
                     false, // 4-way cardinal only
                 )
             }
@@ -325,26 +596,52 @@ impl Game {
 
     fn recompute_path(&mut self) {
         self.path_cache = self.find_path(self.player, self.cursor).unwrap_or_default();
+        // Snapshot now: monster moves and the heatmap reuse the same caches.
+        self.explored.clear();
+        self.explored.extend(self.path_range.last_visited());
     }
 
-    fn try_move(&mut self, dx: i32, dy: i32) -> bool {
-        let np = self.player.shift(dx, dy);
+    /// Step toward `dir` for an arrow key press, then keep stepping every
+    /// [`HOLD_STEP`] until its [`Msg::KeyUp`]. Key repeats are ignored.
+    fn hold(&mut self, dir: Direction) -> Option<Effect> {
+        let was_held = self.held.replace(Some(dir));
+        if was_held == Some(dir) {
+            return None;
+        }
+        self.try_move(dir);
+        if was_held.is_some() {
+            // The running subscription follows the new direction.
+            return None;
+        }
+        // A subscription of an earlier press may still wait for its next
+        // step, the key released and pressed again since: it stops then.
+        let press = self.hold_press.get().wrapping_add(1);
+        self.hold_press.set(press);
+        let (held, current) = (Rc::clone(&self.held), Rc::clone(&self.hold_press));
+        Some(every(HOLD_STEP, move || {
+            (held.get().is_some() && current.get() == press).then(|| Msg::custom(HoldStep(press)))
+        }))
+    }
+
+    fn try_move(&mut self, dir: Direction) -> bool {
+        let np = self.player.step(dir);
         if np.x < 0 || np.x >= WIDTH || np.y < 0 || np.y >= MAP_HEIGHT {
             return false;
         }
-        if self.map.at(np) != Some(FLOOR) {
+        if self.terrain.blocks(np, TerrainMask::BLOCKS_MOVE) {
             return false;
         }
 
         // Check for monster at target.
-        if let Some(mi) = self.monsters.iter().position(|m| m.pos == np && m.hp > 0) {
-            self.monsters[mi].hp -= 1;
-            let ch = self.monsters[mi].ch;
-            if self.monsters[mi].hp <= 0 {
+        let target = self.monsters.at(np).next();
+        if let Some(id) = target {
+            let m = &mut self.monsters[id];
+            m.hp -= 1;
+            let (ch, hp) = (m.ch, m.hp);
+            if hp <= 0 {
                 self.log(format!("You kill the {ch}!"));
-                self.monsters.remove(mi);
+                self.monsters.remove(id);
             } else {
-                let hp = self.monsters[mi].hp;
                 self.log(format!("You hit the {ch} ({hp} hp left)."));
             }
             self.turns += 1;
@@ -353,61 +650,169 @@ impl Game {
             return true;
         }
 
+        let old = self.player;
         self.player = np;
+        for event in self.triggers.update(Actor::Player, old, np) {
+            if let TriggerEvent::Entered {
+                trigger: Feature::HealingSpring,
+                ..
+            } = event
+            {
+                self.hp = self.max_hp;
+                self.log("You drink from the spring and feel restored.".into());
+            }
+        }
         self.turns += 1;
         self.tick_monsters();
         self.compute_fov();
-        if self.show_path || self.show_dijkstra {
+        if self.show_path || self.show_dijkstra || self.show_explored {
             self.recompute_path();
         }
         true
     }
 
+    /// Hurt the player by the poison in their veins, then let a turn pass
+    /// for the status effects.
+    fn tick_statuses(&mut self) {
+        let poison = self.statuses.total_magnitude(Actor::Player, Status::Poison);
+        if poison > 0 {
+            self.hp -= poison;
+            self.log(format!("The poison burns ({poison} hp)."));
+        }
+        for event in self.statuses.tick(1) {
+            if let EffectEvent::Expired {
+                entity: Actor::Player,
+                kind: Status::Poison,
+            } = event
+            {
+                self.log("The poison wears off.".into());
+            }
+        }
+    }
+
     fn tick_monsters(&mut self) {
+        self.tick_statuses();
+        if let Some(phase) = self.clock.advance(1) {
+            self.log(
+                match phase {
+                    DayPhase::Dawn => "Dawn breaks.",
+                    DayPhase::Day => "The sun is up.",
+                    DayPhase::Dusk => "Dusk falls.",
+                    DayPhase::Night => "Night has come.",
+                }
+                .into(),
+            );
+        }
         let player = self.player;
-        // Collect attack messages first, then apply movement.
-        let mut attacks: Vec<(usize, char)> = Vec::new();
-        let mut moves: Vec<(usize, Point)> = Vec::new();
+        let lighter = self.terrain.sight(SIGHT);
+        // Vision source `i` is the i-th monster in store order.
+        let actors: Vec<(EntityId, Point)> =
+            self.monsters.iter().map(|(id, pos, _)| (id, pos)).collect();
+        let sources: Vec<Point> = actors.iter().map(|&(_, pos)| pos).collect();
+        let vision = self.monster_fov.vision_maps(&lighter, &sources).clone();
+        let mut candidates = vec![(Actor::Player, player, PLAYER_FACTION)];
+        candidates.extend(
+            self.monsters
+                .iter()
+                .map(|(id, pos, m)| (Actor::Monster(id), pos, m.faction)),
+        );
 
-        for i in 0..self.monsters.len() {
-            if !self.monsters[i].awake || self.monsters[i].hp <= 0 {
+        // Collect attacks first, then apply them and the movement.
+        let mut attacks: Vec<(EntityId, Actor)> = Vec::new();
+        let mut moves: Vec<(EntityId, Point)> = Vec::new();
+
+        for (i, &(id, mpos)) in actors.iter().enumerate() {
+            if !self.monsters[id].awake {
                 continue;
             }
-            let mpos = self.monsters[i].pos;
-            if gruid_paths::manhattan(mpos, player) <= 1 {
-                attacks.push((i, self.monsters[i].ch));
+            // Go for the nearest enemy in sight, or keep hunting the player.
+            let los = |_: Point, to: Point| vision.sees(i, to);
+            let target = self
+                .factions
+                .select_target(
+                    mpos,
+                    self.monsters[id].faction,
+                    candidates.iter().copied(),
+                    TargetPolicy::NearestVisible(&los),
+                )
+                .unwrap_or(Actor::Player);
+            let tpos = match target {
+                Actor::Player => player,
+                Actor::Monster(j) => self.monsters.pos(j).unwrap_or(player),
+            };
+            if gruid_paths::manhattan(mpos, tpos) <= 1 {
+                attacks.push((id, target));
                 continue;
             }
-            // Move toward player using A*.
-            let pather = MapPather { map: &self.map };
+            // Move toward the target using A*, without leaving the territory.
+            let pather = self.terrain.moves(TerrainMask::BLOCKS_MOVE);
+            let passable = self.terrain.as_passable(TerrainMask::BLOCKS_MOVE);
+            let territory = self.monsters[id].territory;
 
             if let Some(path) = if self.path_algo == PathAlgo::Jps {
-                self.path_range
-                    .jps_path(mpos, player, |p| self.map.at(p) == Some(FLOOR), false)
+                if territory.contains(tpos) {
+                    self.path_range.jps_path(
+                        mpos,
+                        tpos,
+                        |p| territory.contains(p) && passable(p),
+                        false,
+                    )
+                } else {
+                    None
+                }
             } else {
-                self.path_range.astar_path(&pather, mpos, player)
+                self.path_range
+                    .astar_path_within(&pather, mpos, tpos, territory)
             } {
                 if path.len() >= 2 {
                     let next = path[1];
-                    let blocked = self
-                        .monsters
-                        .iter()
-                        .enumerate()
-                        .any(|(j, m)| j != i && m.pos == next && m.hp > 0);
+                    let blocked = self.monsters.at(next).any(|j| j != id);
                     if !blocked {
-                        moves.push((i, next));
+                        moves.push((id, next));
                     }
                 }
             }
         }
 
-        for (_, ch) in &attacks {
-            self.hp -= 1;
-            self.log(format!("The {ch} hits you!"));
+        for (id, target) in attacks {
+            // Killed earlier this turn, before its attack.
+            if self.monsters[id].hp <= 0 {
+                continue;
+            }
+            let ch = self.monsters[id].ch;
+            match target {
+                Actor::Player => {
+                    self.hp -= 1;
+                    if self.monsters[id].faction == BEAST_FACTION {
+                        self.log(format!("The {ch} bites you! You are poisoned."));
+                        self.statuses.apply(Actor::Player, VENOM);
+                    } else {
+                        self.log(format!("The {ch} hits you!"));
+                    }
+                }
+                Actor::Monster(j) => {
+                    // The victim may already have died this turn.
+                    if self.monsters[j].hp <= 0 {
+                        continue;
+                    }
+                    self.monsters[j].hp -= 1;
+                    let victim = &self.monsters[j];
+                    if self
+                        .monsters
+                        .pos(j)
+                        .is_some_and(|p| self.fov.at(p).is_some())
+                    {
+                        let verb = if victim.hp <= 0 { "kills" } else { "bites" };
+                        let other = victim.ch;
+                        self.log(format!("The {ch} {verb} the {other}."));
+                    }
+                }
+            }
         }
-        for &(i, next) in &moves {
-            self.monsters[i].pos = next;
+        for &(id, next) in &moves {
+            self.monsters.move_to(id, next);
         }
+        self.monsters.retain(|_, m| m.hp > 0);
     }
 
     fn log(&mut self, msg: String) {
@@ -417,6 +822,27 @@ impl Game {
         }
     }
 
+    /// Log the description of the focused widget when it changed, if
+    /// announcements are on.
+    fn announce(&mut self) {
+        let node = if self.mode == Mode::Help {
+            self.pager.as_ref().map(Pager::describe)
+        } else {
+            self.context.as_ref().map(ContextMenu::describe)
+        };
+        let Some(announcer) = &mut self.announcer else {
+            return;
+        };
+        let Some(node) = node else {
+            announcer.reset();
+            return;
+        };
+        if let Some(node) = announcer.changed(node) {
+            let text = speak(node);
+            self.log(text);
+        }
+    }
+
     fn open_help(&mut self) {
         let grid = Grid::new(WIDTH, HEIGHT);
         let box_ = BoxDecor {
@@ -424,7 +850,10 @@ impl Game {
             ..BoxDecor::new()
         };
         self.pager = Some(Pager::new(PagerConfig {
-            content: StyledText::new(HELP_TEXT, Style::default().with_fg(COL_STATUS_FG)),
+            content: StyledText::new(
+                &help_text(&self.caps),
+                Style::default().with_fg(COL_STATUS_FG),
+            ),
             grid,
             keys: PagerKeys {
                 quit: vec![Key::Escape, Key::Char('q'), Key::Char('?')],
@@ -435,6 +864,181 @@ impl Game {
         }));
         self.mode = Mode::Help;
     }
+
+    /// Open the right-click menu next to `p`.
+    fn open_context(&mut self, p: Point) {
+        let entry = |text: &str, key: char, id: u64| {
+            let mut e = MenuEntry::new(StyledText::new(
+                text,
+                Style::default().with_fg(COL_STATUS_FG),
+            ))
+            .with_key(id);
+            e.keys.push(Key::Char(key));
+            e
+        };
+        self.context = Some(ContextMenu::new(ContextMenuConfig {
+            grid: self.context_grid.clone(),
+            anchor: p,
+            entries: vec![
+                entry(" Walk here ", 'w', ACTION_WALK),
+                entry(" Examine ", 'e', ACTION_EXAMINE),
+            ],
+            keys: MenuKeys::default(),
+            box_: BoxDecor::new()
+                .with_fill(Cell::default().with_style(Style::default().with_bg(COL_STATUS_BG))),
+            style: MenuStyle {
+                active: Style::default().with_bg(COL_MENU_ACTIVE),
+                ..MenuStyle::default()
+            },
+            connector: Some(Style::default().with_fg(COL_CURSOR)),
+        }));
+    }
+
+    /// Handle a message while the right-click menu is open. Returns `None`
+    /// if the message was not for the menu: the pointer is outside it.
+    fn update_context(&mut self, msg: &Msg) -> Option<Option<Effect>> {
+        let cm = self.context.as_mut()?;
+        let outside = matches!(*msg, Msg::Mouse { pos, .. } if !cm.contains(pos));
+        let effect = match cm.update(msg.clone()) {
+            MenuAction::Invoke => {
+                let (key, p) = (cm.active_key(), cm.anchor());
+                self.context = None;
+                match key {
+                    Some(ACTION_WALK) => self.auto_move(p),
+                    Some(ACTION_EXAMINE) => {
+                        let info = self.describe(p);
+                        self.log(info);
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            MenuAction::Quit => {
+                self.context = None;
+                None
+            }
+            _ => None,
+        };
+        // A click outside closes the menu and then acts on the map.
+        if outside { None } else { Some(effect) }
+    }
+
+    /// Start walking towards `to` along a path.
+    fn auto_move(&mut self, to: Point) -> Option<Effect> {
+        let path = self.find_path(self.player, to)?;
+        if path.len() <= 1 {
+            return None;
+        }
+        self.auto_path = path;
+        self.auto_step = 1;
+        // Take the first step once the click has been drawn.
+        Some(Effect::Continue)
+    }
+
+    /// Whether a monster is in view.
+    fn monster_in_view(&self) -> bool {
+        self.monsters
+            .iter()
+            .any(|(_, pos, _)| self.fov.at(pos).is_some())
+    }
+
+    /// Resume auto-move or rest after a frame: one step per frame for
+    /// auto-move, so that the walk can be followed, and as many turns as
+    /// fit in the frame budget for rest.
+    fn resume(&mut self) -> Option<Effect> {
+        if self.auto_step < self.auto_path.len() {
+            let next = self.auto_path[self.auto_step];
+            if let Some(dir) = Direction::from_delta_exact(next - self.player)
+                && self.try_move(dir)
+            {
+                self.auto_step += 1;
+                if self.auto_step < self.auto_path.len() && self.hp > 0 {
+                    return Some(Effect::Continue);
+                }
+            }
+            self.auto_path.clear();
+            self.auto_step = 0;
+            if self.show_dijkstra {
+                let pather = self.terrain.moves(TerrainMask::BLOCKS_MOVE);
+                self.path_range
+                    .dijkstra_map(&pather, &[self.player], gruid_paths::UNREACHABLE);
+            }
+            return None;
+        }
+        if self.rest_left == 0 {
+            return None;
+        }
+        let budget = TurnBudget::default();
+        loop {
+            self.rest_left -= 1;
+            self.turns += 1;
+            self.tick_monsters();
+            self.compute_fov();
+            if self.hp <= 0 {
+                self.rest_left = 0;
+                return None;
+            }
+            if self.monster_in_view() {
+                self.rest_left = 0;
+                self.log("You stop resting: a monster is near.".into());
+                return None;
+            }
+            if self.rest_left == 0 {
+                self.log("You finish resting.".into());
+                return None;
+            }
+            if budget.exhausted() {
+                return Some(Effect::Continue);
+            }
+        }
+    }
+
+    /// What is at `p`, as shown in look mode.
+    fn describe(&self, p: Point) -> String {
+        let mut info = format!("({},{}) ", p.x, p.y);
+        if let Some(terrain) = self.map.at(p) {
+            if terrain == WALL {
+                info.push_str("Wall");
+            } else {
+                info.push_str("Floor");
+            }
+        }
+        if let Some(m) = self.monsters.at(p).next().map(|id| &self.monsters[id]) {
+            info.push_str(&format!(" | Monster '{}' HP:{}", m.ch, m.hp));
+        }
+        if self.player == p {
+            info.push_str(" | You");
+        }
+        info
+    }
+
+    /// Lit floor colour at `p`: in graphical back-ends, scaled down towards
+    /// the dark floor colour by the visibility level and tinted by the time
+    /// of day; terminals keep the plain lit colour.
+    fn lit_floor_color(&self, p: Point) -> Color {
+        if !self.graphical() {
+            return COL_FLOOR_LIT;
+        }
+        let t = self.fov.visibility_level(p, FOV_LEVELS) as f32 / FOV_LEVELS as f32;
+        self.daylight(Color::from_rgb(
+            lerp_u8(COL_FLOOR_DARK.r(), COL_FLOOR_LIT.r(), t),
+            lerp_u8(COL_FLOOR_DARK.g(), COL_FLOOR_LIT.g(), t),
+            lerp_u8(COL_FLOOR_DARK.b(), COL_FLOOR_LIT.b(), t),
+        ))
+    }
+
+    /// `c` in the ambient light of the time of day, in graphical back-ends.
+    fn daylight(&self, c: Color) -> Color {
+        if self.graphical() {
+            self.clock.tint(c)
+        } else {
+            c
+        }
+    }
+
+    fn graphical(&self) -> bool {
+        matches!(self.caps.platform, Platform::Desktop | Platform::Web)
+    }
 }
 
 impl Default for Game {
@@ -443,31 +1047,105 @@ impl Default for Game {
     }
 }
 
-impl gruid_core::app::Model for Game {
-    fn update(&mut self, msg: Msg) -> Option<Effect> {
+/// Put a widget description into words.
+fn speak(node: &AccessibilityNode) -> String {
+    let mut parts = Vec::new();
+    if let Some(label) = &node.label {
+        parts.push(label.clone());
+    }
+    parts.push(
+        match node.role {
+            Role::Menu => "menu",
+            Role::Pager => "text",
+            Role::TextInput => "text field",
+            Role::Replay => "replay",
+            Role::Progress => "progress",
+            Role::Toggle => "checkbox",
+            Role::Picker => "choice",
+        }
+        .to_string(),
+    );
+    if let Some(value) = &node.value {
+        parts.push(format!("\"{}\"", value.trim()));
+    }
+    if let Some(checked) = node.checked {
+        parts.push(if checked { "checked" } else { "not checked" }.into());
+    }
+    if node.disabled {
+        parts.push("unavailable".into());
+    }
+    if let Some(error) = &node.error {
+        parts.push(format!("invalid: {error}"));
+    }
+    let noun = if matches!(node.role, Role::Menu | Role::Picker) {
+        "item"
+    } else {
+        "line"
+    };
+    if let (Some(i), Some(n)) = (node.index, node.count) {
+        parts.push(format!("{noun} {i} of {n}"));
+    }
+    if let (Some(p), Some(n)) = (node.page, node.pages) {
+        parts.push(format!("page {p} of {n}"));
+    }
+    parts.join(", ")
+}
+
+impl Game {
+    fn handle(&mut self, msg: Msg) -> Option<Effect> {
+        if let Msg::Capabilities(caps) = msg {
+            self.caps = caps;
+            return None;
+        }
+        if let Some(theme) = msg.system_theme() {
+            self.ui = UiPalette::for_theme(theme);
+            return None;
+        }
+
+        // ---- Loading ----
+        if self.loading.is_some() {
+            return self.update_loading(msg);
+        }
+
         // ---- Help mode ----
         if self.mode == Mode::Help {
             if let Some(ref mut pager) = self.pager {
-                let action = pager.update(msg);
-                if action == PagerAction::Quit {
-                    self.pager = None;
-                    self.mode = Mode::Play;
+                match pager.update(msg) {
+                    PagerAction::Quit => {
+                        self.pager = None;
+                        self.mode = Mode::Play;
+                    }
+                    PagerAction::Copy => return pager.selection_text().map(set_clipboard),
+                    _ => {}
                 }
             }
             return None;
         }
 
+        if matches!(msg, Msg::KeyDown { .. } | Msg::Mouse { .. })
+            && let Some(effect) = self.update_context(&msg)
+        {
+            return effect;
+        }
+
         match msg {
             Msg::Init => None,
             Msg::Quit => Some(Effect::End),
 
             // ---- Keyboard ----
-            Msg::KeyDown { ref key, .. } => {
-                // Auto-move step: any key cancels it.
+            Msg::KeyDown {
+                ref key, repeat, ..
+            } => {
+                // Any key cancels auto-move and rest.
                 if !self.auto_path.is_empty() {
                     self.auto_path.clear();
                     self.auto_step = 0;
                 }
+                if self.rest_left > 0 {
+                    self.rest_left = 0;
+                    self.log("You stop resting.".into());
+                    return None;
+                }
 
                 match self.mode {
                     Mode::Look => match key {
@@ -475,47 +1153,31 @@ impl gruid_core::app::Model for Game {
                             self.mode = Mode::Play;
                             self.cursor = self.player;
                         }
-                        Key::ArrowUp | Key::Char('k') => self.cursor = self.cursor.shift(0, -1),
-                        Key::ArrowDown | Key::Char('j') => self.cursor = self.cursor.shift(0, 1),
-                        Key::ArrowLeft | Key::Char('h') => self.cursor = self.cursor.shift(-1, 0),
-                        Key::ArrowRight | Key::Char('l') => self.cursor = self.cursor.shift(1, 0),
-                        _ => {}
+                        _ => {
+                            if let Some(dir) = Direction::from_key(key) {
+                                self.cursor = self.cursor.step(dir);
+                            }
+                        }
                     },
                     Mode::Play => match key {
                         Key::Escape | Key::Char('q') | Key::Char('Q') => {
                             return Some(Effect::End);
                         }
-                        // Movement
-                        Key::ArrowUp | Key::Char('k') => {
-                            self.try_move(0, -1);
-                        }
-                        Key::ArrowDown | Key::Char('j') => {
-                            self.try_move(0, 1);
-                        }
-                        Key::ArrowLeft | Key::Char('h') => {
-                            self.try_move(-1, 0);
-                        }
-                        Key::ArrowRight | Key::Char('l') => {
-                            self.try_move(1, 0);
-                        }
-                        Key::Char('y') => {
-                            self.try_move(-1, -1);
-                        }
-                        Key::Char('u') => {
-                            self.try_move(1, -1);
-                        }
-                        Key::Char('b') => {
-                            self.try_move(-1, 1);
-                        }
-                        Key::Char('n') => {
-                            self.try_move(1, 1);
-                        }
                         // Wait
                         Key::Char('.') | Key::Space => {
                             self.turns += 1;
                             self.tick_monsters();
                             self.compute_fov();
                         }
+                        Key::Char('R') => {
+                            if self.monster_in_view() {
+                                self.log("You cannot rest with a monster in view.".into());
+                            } else {
+                                self.rest_left = REST_TURNS;
+                                self.log("You rest...".into());
+                                return Some(Effect::Continue);
+                            }
+                        }
                         // Toggles
                         Key::Char('p') => {
                             self.show_path = !self.show_path;
@@ -530,7 +1192,7 @@ impl gruid_core::app::Model for Game {
                         Key::Char('d') => {
                             self.show_dijkstra = !self.show_dijkstra;
                             if self.show_dijkstra {
-                                let pather = MapPather { map: &self.map };
+                                let pather = self.terrain.moves(TerrainMask::BLOCKS_MOVE);
                                 self.path_range.dijkstra_map(
                                     &pather,
                                     &[self.player],
@@ -541,11 +1203,22 @@ impl gruid_core::app::Model for Game {
                                 self.log("Dijkstra heatmap OFF.".into());
                             }
                         }
+                        Key::Char('v') => {
+                            self.show_explored = !self.show_explored;
+                            if self.show_explored {
+                                self.recompute_path();
+                                let n = self.explored.len();
+                                self.log(format!("Explored overlay ON ({n} cells)."));
+                            } else {
+                                self.explored.clear();
+                                self.log("Explored overlay OFF.".into());
+                            }
+                        }
                         Key::Tab => {
                             self.path_algo = self.path_algo.toggle();
                             let label = self.path_algo.label();
                             self.log(format!("Pathfinding: {label}"));
-                            if self.show_path {
+                            if self.show_path || self.show_explored {
                                 self.recompute_path();
                             }
                         }
@@ -569,13 +1242,25 @@ impl gruid_core::app::Model for Game {
                         Key::Char('?') => {
                             self.open_help();
                         }
-                        _ => {}
+                        // Movement
+                        _ => {
+                            if self.caps.key_release
+                                && let Some(dir) = Direction::from_arrow_key(key)
+                            {
+                                return self.hold(dir);
+                            }
+                            if let Some(dir) = Direction::from_key(key)
+                                && !(repeat && self.ignore_repeats)
+                            {
+                                self.try_move(dir);
+                            }
+                        }
                     },
                     _ => {}
                 }
 
                 // Update path in look mode.
-                if self.mode == Mode::Look && self.show_path {
+                if self.mode == Mode::Look && (self.show_path || self.show_explored) {
                     self.recompute_path();
                 }
 
@@ -588,62 +1273,73 @@ impl gruid_core::app::Model for Game {
                 if pos.y >= 0 && pos.y < MAP_HEIGHT && pos.x >= 0 && pos.x < WIDTH {
                     self.cursor = pos;
 
-                    if self.show_path {
+                    if self.show_path || self.show_explored {
                         self.recompute_path();
                     }
 
-                    if action == MouseAction::Main && self.mode == Mode::Play {
-                        // Click to auto-move.
-                        if let Some(path) = self.find_path(self.player, pos) {
-                            if path.len() > 1 {
-                                self.auto_path = path;
-                                self.auto_step = 1;
-                                // Trigger first step.
-                                return Some(Effect::Cmd(Box::new(|| {
-                                    Some(Msg::custom(AutoMoveTick))
-                                })));
+                    if self.mode == Mode::Play {
+                        match action {
+                            // Click to auto-move.
+                            MouseAction::Main => return self.auto_move(pos),
+                            MouseAction::Secondary if self.fov.at(pos).is_some() => {
+                                self.open_context(pos);
                             }
+                            _ => {}
                         }
                     }
                 }
                 None
             }
 
-            // ---- Timer tick for auto-move ----
-            _ if msg.downcast_ref::<AutoMoveTick>().is_some() => {
-                if self.auto_step < self.auto_path.len() {
-                    let next = self.auto_path[self.auto_step];
-                    let dx = next.x - self.player.x;
-                    let dy = next.y - self.player.y;
-                    if self.try_move(dx, dy) {
-                        self.auto_step += 1;
-                        if self.auto_step < self.auto_path.len() && self.hp > 0 {
-                            // Schedule next step.
-                            return Some(Effect::Cmd(Box::new(|| {
-                                std::thread::sleep(std::time::Duration::from_millis(60));
-                                Some(Msg::custom(AutoMoveTick))
-                            })));
-                        }
-                    }
-                    self.auto_path.clear();
-                    self.auto_step = 0;
+            Msg::KeyUp { ref key, .. } => {
+                if Direction::from_arrow_key(key).is_some_and(|d| self.held.get() == Some(d)) {
+                    self.held.set(None);
                 }
+                None
+            }
 
-                if self.show_dijkstra {
-                    println!("Computing Dijkstra heatmap");
-                    let pather = MapPather { map: &self.map };
-                    self.path_range
-                        .dijkstra_map(&pather, &[self.player], gruid_paths::UNREACHABLE);
-                }
+            // ---- Auto-move and rest, a slice per frame ----
+            Msg::Continue => self.resume(),
 
+            Msg::Custom(c) if c.is::<HoldStep>() => {
+                if self.mode == Mode::Play
+                    && c.downcast_ref::<HoldStep>()
+                        .is_some_and(|s| s.0 == self.hold_press.get())
+                    && let Some(dir) = self.held.get()
+                {
+                    self.try_move(dir);
+                }
                 None
             }
 
             _ => None,
         }
     }
+}
+
+impl gruid_core::app::Model for Game {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        if matches!(msg, Msg::KeyDown { key: Key::F(2), .. }) {
+            if self.announcer.take().is_some() {
+                self.log("Announcements OFF.".into());
+            } else {
+                self.announcer = Some(Announcer::new());
+                self.log("Announcements ON.".into());
+            }
+        }
+        let effect = self.handle(msg);
+        self.announce();
+        effect
+    }
 
     fn draw(&self, grid: &mut Grid) {
+        // ---- Loading screen ----
+        if let Some(ref loading) = self.loading {
+            grid.fill(Cell::default().with_style(Style::default().with_bg(COL_BG)));
+            loading.draw(grid);
+            return;
+        }
+
         // ---- Help overlay ----
         if self.mode == Mode::Help {
             if let Some(ref pager) = self.pager {
@@ -668,14 +1364,17 @@ impl gruid_core::app::Model for Game {
                     continue;
                 }
 
-                let (ch, fg, bg) = if terrain == Some(WALL) {
+                let spring = p == self.spring && self.triggers.contains(Feature::HealingSpring);
+                let (ch, fg, bg) = if spring {
+                    ('+', COL_SPRING, COL_BG)
+                } else if terrain == Some(WALL) {
                     if lit {
-                        ('#', COL_WALL_LIT, COL_BG)
+                        ('#', self.daylight(COL_WALL_LIT), COL_BG)
                     } else {
                         ('#', COL_WALL_DARK, COL_BG)
                     }
                 } else if lit {
-                    ('.', COL_FLOOR_LIT, COL_BG)
+                    ('.', self.lit_floor_color(p), COL_BG)
                 } else {
                     ('.', COL_FLOOR_DARK, COL_BG)
                 };
@@ -715,26 +1414,29 @@ impl gruid_core::app::Model for Game {
             }
         }
 
-        // ---- A* path overlay ----
-        if self.show_path && self.path_cache.len() > 1 {
-            for &p in &self.path_cache[1..] {
-                if p == self.player {
-                    continue;
-                }
+        // ---- Search exploration overlay ----
+        if self.show_explored {
+            for &p in &self.explored {
                 let existing = grid.at(p);
-                let style = existing.style.with_fg(COL_PATH).with_attrs(AttrMask::BOLD);
-                grid.set(p, Cell::default().with_char('*').with_style(style));
+                grid.set(
+                    p,
+                    Cell::default()
+                        .with_char(existing.ch)
+                        .with_style(existing.style.with_bg(COL_EXPLORED)),
+                );
             }
         }
 
         // ---- Monsters ----
-        for m in &self.monsters {
-            if m.hp <= 0 {
-                continue;
-            }
-            if self.fov.at(m.pos).is_some() {
-                let style = Style::default().with_fg(COL_MONSTER).with_bg(COL_BG);
-                grid.set(m.pos, Cell::default().with_char(m.ch).with_style(style));
+        for (_, pos, m) in self.monsters.iter() {
+            if self.fov.at(pos).is_some() {
+                let fg = if m.faction == BEAST_FACTION {
+                    COL_BEAST
+                } else {
+                    COL_MONSTER
+                };
+                let style = Style::default().with_fg(fg).with_bg(COL_BG);
+                grid.set(pos, Cell::default().with_char(m.ch).with_style(style));
             }
         }
 
@@ -748,21 +1450,11 @@ impl gruid_core::app::Model for Game {
             Cell::default().with_char('@').with_style(player_style),
         );
 
-        // ---- Look cursor ----
-        if self.mode == Mode::Look {
-            let existing = grid.at(self.cursor);
-            let style = existing.style.with_bg(COL_CURSOR);
-            grid.set(
-                self.cursor,
-                Cell::default().with_char(existing.ch).with_style(style),
-            );
-        }
-
         // ---- Status bar (row MAP_HEIGHT) ----
         let status_y = MAP_HEIGHT;
         let status_style = Style::default()
-            .with_fg(COL_STATUS_FG)
-            .with_bg(COL_STATUS_BG);
+            .with_fg(self.ui.status_fg)
+            .with_bg(self.ui.status_bg);
         for x in 0..WIDTH {
             grid.set(
                 Point::new(x, status_y),
@@ -770,9 +1462,14 @@ impl gruid_core::app::Model for Game {
             );
         }
 
-        let hp_text = format!(" HP: {}/{}", self.hp, self.max_hp);
+        let poisoned = if self.statuses.has(Actor::Player, Status::Poison) {
+            " Poisoned"
+        } else {
+            ""
+        };
+        let hp_text = format!(" HP: {}/{}{poisoned}", self.hp, self.max_hp);
         let pos_text = format!("Pos: ({},{})", self.player.x, self.player.y);
-        let turn_text = format!("Turn: {}", self.turns);
+        let turn_text = format!("Turn: {} {}", self.turns, self.clock.phase());
         let mode_text = match self.mode {
             Mode::Look => "[LOOK]",
             _ => "",
@@ -782,7 +1479,7 @@ impl gruid_core::app::Model for Game {
             FovShape::Square => "",
         };
         let overlays = format!(
-            "{}{}{}",
+            "{}{}{}{}",
             if self.show_path {
                 match self.path_algo {
                     PathAlgo::Astar => "[A*]",
@@ -792,6 +1489,7 @@ impl gruid_core::app::Model for Game {
                 ""
             },
             if self.show_dijkstra { "[DJKS]" } else { "" },
+            if self.show_explored { "[VIS]" } else { "" },
             fov_tag,
         );
 
@@ -818,8 +1516,7 @@ impl gruid_core::app::Model for Game {
         }
 
         // Monster count on the right side.
-        let alive = self.monsters.iter().filter(|m| m.hp > 0).count();
-        let right_text = format!("Monsters: {} ", alive);
+        let right_text = format!("Monsters: {} ", self.monsters.len());
         let start_x = (WIDTH - right_text.len() as i32).max(0);
         for (i, ch) in right_text.chars().enumerate() {
             grid.set(
@@ -830,7 +1527,11 @@ impl gruid_core::app::Model for Game {
 
         // ---- Message log (rows MAP_HEIGHT+1 .. HEIGHT-1) ----
         let log_rows = (HEIGHT - MAP_HEIGHT - 1) as usize;
-        let log_style = Style::default().with_fg(COL_LOG_FG).with_bg(COL_BG);
+        let log_style = Style::default()
+            .with_fg(self.ui.log_fg)
+            .with_bg(self.ui.log_bg);
+        grid.slice(Range::new(0, MAP_HEIGHT + 1, WIDTH, HEIGHT))
+            .fill(Cell::default().with_style(log_style));
         let start = self.messages.len().saturating_sub(log_rows);
         for (row, msg) in self.messages[start..].iter().enumerate() {
             let y = MAP_HEIGHT + 1 + row as i32;
@@ -849,24 +1550,7 @@ impl gruid_core::app::Model for Game {
         // ---- Look mode info ----
         if self.mode == Mode::Look {
             let info_y = HEIGHT - 1;
-            let mut info = format!("({},{}) ", self.cursor.x, self.cursor.y);
-            if let Some(terrain) = self.map.at(self.cursor) {
-                if terrain == WALL {
-                    info.push_str("Wall");
-                } else {
-                    info.push_str("Floor");
-                }
-            }
-            if let Some(m) = self
-                .monsters
-                .iter()
-                .find(|m| m.pos == self.cursor && m.hp > 0)
-            {
-                info.push_str(&format!(" | Monster '{}' HP:{}", m.ch, m.hp));
-            }
-            if self.player == self.cursor {
-                info.push_str(" | You");
-            }
+            let info = self.describe(self.cursor);
             let info_style = Style::default().with_fg(COL_PLAYER).with_bg(COL_BG);
             for (i, ch) in info.chars().enumerate() {
                 if i as i32 >= WIDTH {
@@ -878,9 +1562,150 @@ impl gruid_core::app::Model for Game {
                 );
             }
         }
+
+        // ---- Right-click menu ----
+        if let Some(ref cm) = self.context {
+            let rg = cm.draw();
+            grid.slice(rg).copy_from(&self.context_grid.slice(rg));
+        }
+    }
+
+    fn draw_transient(&self, overlay: &mut OverlayGrid) {
+        if self.loading.is_some() || self.mode == Mode::Help {
+            return;
+        }
+        let hidden = |p: Point| self.context.as_ref().is_some_and(|cm| cm.contains(p));
+
+        // ---- A* path overlay ----
+        if self.show_path && self.path_cache.len() > 1 {
+            for &p in &self.path_cache[1..] {
+                let monster = self.fov.at(p).is_some() && self.monsters.at(p).next().is_some();
+                if p == self.player || monster || hidden(p) {
+                    continue;
+                }
+                let existing = overlay.base(p);
+                let style = existing.style.with_fg(COL_PATH).with_attrs(AttrMask::BOLD);
+                let ch = self.caps.symbol('○', 'o');
+                overlay.set(p, Cell::default().with_char(ch).with_style(style));
+            }
+        }
+
+        // ---- Look cursor ----
+        if self.mode == Mode::Look && !hidden(self.cursor) {
+            let existing = overlay.at(self.cursor).unwrap_or(overlay.base(self.cursor));
+            let style = existing.style.with_bg(COL_CURSOR);
+            overlay.set(
+                self.cursor,
+                Cell::default().with_char(existing.ch).with_style(style),
+            );
+        }
     }
 }
 
 fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
     (a as f32 + (b as f32 - a as f32) * t) as u8
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::app::TestRunner;
+
+    /// The screen after a headless run with master seed `seed`, once the
+    /// level is loaded and `keys` pressed and released.
+    fn play(seed: u64, keys: &[Key]) -> Vec<Cell> {
+        let mut t = TestRunner::new(Box::new(Game::new()), WIDTH, HEIGHT).with_seed(seed);
+        for key in keys {
+            t.key(key.clone());
+            t.send(Msg::key_up(key.clone()));
+        }
+        t.grid().iter().map(|(_, c)| c).collect()
+    }
+
+    const KEYS: [Key; 8] = [
+        Key::ArrowRight,
+        Key::ArrowRight,
+        Key::ArrowDown,
+        Key::Char('d'),
+        Key::ArrowDown,
+        Key::ArrowLeft,
+        Key::Char('d'),
+        Key::ArrowUp,
+    ];
+
+    #[test]
+    fn pressing_again_stops_the_released_hold() {
+        use gruid_core::app::SubPoll;
+        let mut game = Game::new();
+        // Steps north from the corner go nowhere, whatever the map.
+        game.player = Point::ZERO;
+        let Some(Effect::Subscribe(mut first)) = game.hold(Direction::N) else {
+            panic!("no subscription");
+        };
+        // Released, then pressed again before the first one's next step.
+        game.held.set(None);
+        let Some(Effect::Subscribe(mut second)) = game.hold(Direction::N) else {
+            panic!("no subscription");
+        };
+        let t = std::time::Instant::now();
+        assert!(matches!(first.poll(t), SubPoll::Pending));
+        assert!(matches!(second.poll(t), SubPoll::Pending));
+        assert!(matches!(first.poll(t + HOLD_STEP), SubPoll::Done));
+        assert!(matches!(second.poll(t + HOLD_STEP), SubPoll::Msg(_)));
+    }
+
+    /// An awake one-hp monster that can go anywhere.
+    fn monster(ch: char, faction: FactionId) -> Monster {
+        Monster {
+            ch,
+            hp: 1,
+            faction,
+            awake: true,
+            territory: Range::new(0, 0, WIDTH, MAP_HEIGHT),
+        }
+    }
+
+    /// A game on an open floor, the player in the corner.
+    fn open_floor(monsters: Entities<Monster>) -> Game {
+        let mut game = Game::new();
+        game.load_level(&Level {
+            cells: vec![FLOOR; (WIDTH * MAP_HEIGHT) as usize],
+            player: Point::ZERO,
+            monsters,
+            spring: Point::ZERO,
+        });
+        game
+    }
+
+    #[test]
+    fn monsters_killed_this_turn_do_not_attack() {
+        let mut monsters = Entities::new();
+        // The goblin acts first and kills the rat next to it.
+        let goblin = monsters.insert(Point::new(10, 5), monster('g', CAVE_FACTION));
+        monsters.insert(Point::new(11, 5), monster('r', BEAST_FACTION));
+        let mut game = open_floor(monsters);
+        game.tick_monsters();
+        assert_eq!(game.monsters.len(), 1);
+        assert_eq!(game.monsters[goblin].hp, 1);
+    }
+
+    #[test]
+    fn dying_while_resting_is_not_a_monster_warning() {
+        let mut monsters = Entities::new();
+        monsters.insert(Point::new(1, 0), monster('g', CAVE_FACTION));
+        let mut game = open_floor(monsters);
+        game.hp = 1;
+        game.rest_left = 10;
+        assert!(game.resume().is_none());
+        assert!(game.hp <= 0);
+        assert_eq!(game.rest_left, 0);
+        assert_eq!(game.messages.last().unwrap(), "The g hits you!");
+    }
+
+    #[test]
+    fn seed_decides_the_run() {
+        let a = play(1, &KEYS);
+        assert_eq!(a, play(1, &KEYS));
+        assert_ne!(a, play(2, &KEYS));
+    }
+}
@@ -0,0 +1,97 @@
+//! A marquee scrolling across a status bar, shared by the `marquee-winit`
+//! and `marquee-wgpu` examples. It moves on the [`Msg::Frame`] messages of
+//! a driver configured with a frame rate, by the time each frame carries,
+//! so it scrolls at the same speed whatever the rate.
+
+use std::time::Instant;
+
+use gruid_core::{
+    Cell, Point,
+    app::{Effect, Model},
+    grid::Grid,
+    messages::{Key, Msg},
+    style::{Color, Style},
+};
+
+pub const WIDTH: i32 = 40;
+pub const HEIGHT: i32 = 4;
+
+/// Frames a second the examples ask their driver for.
+pub const FRAME_RATE: u32 = 60;
+
+/// Cells the marquee moves by in a second.
+const SPEED: f64 = 8.0;
+const TEXT: &str = "gruid-rs: smooth animation with Msg::Frame  ~  ";
+const HINT: &str = "Escape quits.";
+
+/// The marquee. Escape quits.
+#[derive(Debug, Default)]
+pub struct Marquee {
+    /// Time of the first frame.
+    start: Option<Instant>,
+    /// Characters of the text scrolled out on the left.
+    offset: usize,
+}
+
+impl Marquee {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Model for Marquee {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        match msg {
+            Msg::Frame { time } => {
+                let start = *self.start.get_or_insert(time);
+                let cells = time.saturating_duration_since(start).as_secs_f64() * SPEED;
+                self.offset = cells as usize % TEXT.chars().count();
+            }
+            Msg::KeyDown {
+                key: Key::Escape, ..
+            } => return Some(Effect::End),
+            _ => {}
+        }
+        None
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        grid.fill(Cell::default());
+        for (x, ch) in HINT.chars().enumerate() {
+            grid.set(Point::new(x as i32 + 1, 1), Cell::default().with_char(ch));
+        }
+        let bar = Style::default()
+            .with_fg(Color::from_rgb(20, 20, 30))
+            .with_bg(Color::from_rgb(220, 180, 60));
+        let text = TEXT.chars().cycle().skip(self.offset);
+        for (x, ch) in (0..WIDTH).zip(text) {
+            let cell = Cell::default().with_char(ch).with_style(bar);
+            grid.set(Point::new(x, HEIGHT - 1), cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn scrolls_by_frame_time() {
+        let t0 = Instant::now();
+        let mut m = Marquee::new();
+        m.update(Msg::Frame { time: t0 });
+        assert_eq!(m.offset, 0);
+        m.update(Msg::Frame {
+            time: t0 + Duration::from_millis(500),
+        });
+        assert_eq!(m.offset, 4);
+        // Around the text and back to its start.
+        let lap = TEXT.chars().count() as f64 / SPEED;
+        m.update(Msg::Frame {
+            time: t0 + Duration::from_secs_f64(lap + 0.01),
+        });
+        assert_eq!(m.offset, 0);
+        assert!(matches!(m.update(Msg::key(Key::Escape)), Some(Effect::End)));
+    }
+}
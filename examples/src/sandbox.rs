@@ -0,0 +1,608 @@
+//! Falling-sand playground shared by the `sandbox*` examples.
+//!
+//! Every cell may change each tick, so this stresses full-grid redraws in
+//! the drivers. The simulation core ([`Sim`]) is independent of any driver;
+//! [`Sandbox`] wraps it in a model with a material menu strip, mouse
+//! painting and a status line.
+
+use std::time::{Duration, Instant};
+
+use gruid_core::{
+    Cell, Point, Range,
+    app::{Effect, tick},
+    grid::Grid,
+    messages::{Key, MouseAction, Msg},
+    seed::sub_seed,
+    style::{Color, Style},
+};
+use gruid_ui::{Menu, MenuAction, MenuConfig, MenuEntry, MenuKeys, MenuStyle, StyledText};
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+
+pub const WIDTH: i32 = 80;
+pub const HEIGHT: i32 = 24;
+/// Rows between the menu strip (row 0) and the status line (last row).
+pub const SIM_HEIGHT: i32 = HEIGHT - 2;
+
+const TICK: Duration = Duration::from_millis(33);
+const MAX_BRUSH: i32 = 6;
+
+const COL_BG: Color = Color::from_rgb(10, 10, 16);
+const COL_STATUS_FG: Color = Color::from_rgb(200, 200, 200);
+const COL_STATUS_BG: Color = Color::from_rgb(30, 30, 50);
+const COL_MENU_ACTIVE: Color = Color::from_rgb(80, 80, 130);
+
+// ---------------------------------------------------------------------------
+// Materials
+// ---------------------------------------------------------------------------
+
+/// A cell's contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Material {
+    #[default]
+    Empty,
+    Sand,
+    Water,
+    Wall,
+    /// Burns when fire touches it.
+    Wood,
+    /// Burns out after a while, igniting wood and evaporating water.
+    Fire,
+}
+
+impl Material {
+    /// Materials offered in the menu strip, in order.
+    pub const PALETTE: [Material; 6] = [
+        Material::Sand,
+        Material::Water,
+        Material::Wall,
+        Material::Wood,
+        Material::Fire,
+        Material::Empty,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Empty => "Erase",
+            Self::Sand => "Sand",
+            Self::Water => "Water",
+            Self::Wall => "Wall",
+            Self::Wood => "Wood",
+            Self::Fire => "Fire",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Simulation
+// ---------------------------------------------------------------------------
+
+/// A double-buffered falling-sand simulation.
+///
+/// Each [`step`](Self::step) reads the current buffer and writes the next
+/// one, so a particle moves at most one cell per tick whatever the scan
+/// order.
+pub struct Sim {
+    width: i32,
+    height: i32,
+    cells: Vec<Material>,
+    next: Vec<Material>,
+    /// Remaining ticks for fire cells.
+    ttl: Vec<u8>,
+    next_ttl: Vec<u8>,
+    rng: StdRng,
+    tick: u64,
+}
+
+impl Sim {
+    /// An empty `width` × `height` simulation.
+    pub fn new(width: i32, height: i32, seed: u64) -> Self {
+        let len = (width * height) as usize;
+        Self {
+            width,
+            height,
+            cells: vec![Material::Empty; len],
+            next: vec![Material::Empty; len],
+            ttl: vec![0; len],
+            next_ttl: vec![0; len],
+            rng: StdRng::seed_from_u64(seed),
+            tick: 0,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Number of steps run so far.
+    pub fn ticks(&self) -> u64 {
+        self.tick
+    }
+
+    fn idx(&self, p: Point) -> Option<usize> {
+        (p.x >= 0 && p.y >= 0 && p.x < self.width && p.y < self.height)
+            .then(|| (p.y * self.width + p.x) as usize)
+    }
+
+    /// The material at `p`; walls outside the simulation.
+    pub fn get(&self, p: Point) -> Material {
+        self.idx(p).map_or(Material::Wall, |i| self.cells[i])
+    }
+
+    /// Put `m` at `p`. Positions outside the simulation are ignored.
+    pub fn set(&mut self, p: Point, m: Material) {
+        if let Some(i) = self.idx(p) {
+            self.cells[i] = m;
+            self.ttl[i] = if m == Material::Fire {
+                self.rng.random_range(10..20)
+            } else {
+                0
+            };
+        }
+    }
+
+    /// Fill a disc of the given radius (0 is a single cell) around `center`.
+    pub fn paint(&mut self, center: Point, radius: i32, m: Material) {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius + radius {
+                    self.set(center.shift(dx, dy), m);
+                }
+            }
+        }
+    }
+
+    /// Empty every cell.
+    pub fn clear(&mut self) {
+        self.cells.fill(Material::Empty);
+        self.ttl.fill(0);
+    }
+
+    /// Number of cells holding `m`.
+    pub fn count(&self, m: Material) -> usize {
+        self.cells.iter().filter(|&&c| c == m).count()
+    }
+
+    /// Advance one tick. Returns the number of cells whose material changed.
+    pub fn step(&mut self) -> usize {
+        self.next.copy_from_slice(&self.cells);
+        self.next_ttl.copy_from_slice(&self.ttl);
+        // Alternate the horizontal scan direction so nothing drifts one way.
+        let flip = self.tick % 2 == 1;
+        for y in (0..self.height).rev() {
+            for xi in 0..self.width {
+                let x = if flip { self.width - 1 - xi } else { xi };
+                let p = Point::new(x, y);
+                let i = (y * self.width + x) as usize;
+                let m = self.cells[i];
+                // Displaced by a swap earlier in this tick.
+                if self.next[i] != m {
+                    continue;
+                }
+                match m {
+                    Material::Sand => self.fall(p, i, false),
+                    Material::Water => self.fall(p, i, true),
+                    Material::Fire => self.burn(p, i),
+                    Material::Empty | Material::Wall | Material::Wood => {}
+                }
+            }
+        }
+        let changed = self
+            .cells
+            .iter()
+            .zip(&self.next)
+            .filter(|(a, b)| a != b)
+            .count();
+        std::mem::swap(&mut self.cells, &mut self.next);
+        std::mem::swap(&mut self.ttl, &mut self.next_ttl);
+        self.tick += 1;
+        changed
+    }
+
+    /// Whether a particle may move into `q` in the next buffer.
+    fn free(&self, q: Point) -> Option<usize> {
+        self.idx(q).filter(|&j| self.next[j] == Material::Empty)
+    }
+
+    fn move_to(&mut self, i: usize, j: usize) {
+        self.next[j] = self.next[i];
+        self.next[i] = Material::Empty;
+    }
+
+    fn fall(&mut self, p: Point, i: usize, liquid: bool) {
+        let below = p.shift(0, 1);
+        if let Some(j) = self.free(below) {
+            return self.move_to(i, j);
+        }
+        // Sand sinks through water.
+        if !liquid
+            && let Some(j) = self.idx(below)
+            && self.cells[j] == Material::Water
+            && self.next[j] == Material::Water
+        {
+            self.next.swap(i, j);
+            return;
+        }
+        let d = if self.rng.random_range(0..2) == 0 {
+            1
+        } else {
+            -1
+        };
+        for q in [p.shift(d, 1), p.shift(-d, 1)] {
+            if let Some(j) = self.free(q) {
+                return self.move_to(i, j);
+            }
+        }
+        if liquid {
+            for q in [p.shift(d, 0), p.shift(-d, 0)] {
+                if let Some(j) = self.free(q) {
+                    return self.move_to(i, j);
+                }
+            }
+        }
+    }
+
+    fn burn(&mut self, p: Point, i: usize) {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let Some(j) = self.idx(p.shift(dx, dy)) else {
+                    continue;
+                };
+                match (self.cells[j], self.next[j]) {
+                    (Material::Water, Material::Water) => {
+                        // Steam: both go.
+                        self.next[j] = Material::Empty;
+                        self.next[i] = Material::Empty;
+                        return;
+                    }
+                    (Material::Wood, Material::Wood) if self.rng.random_range(0..2) == 0 => {
+                        self.next[j] = Material::Fire;
+                        self.next_ttl[j] = self.rng.random_range(10..20);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let ttl = self.ttl[i].saturating_sub(1);
+        self.next_ttl[i] = ttl;
+        if ttl == 0 {
+            self.next[i] = Material::Empty;
+        }
+    }
+
+    /// Colour of the cell at `p`. Sand grains, water and fire shimmer from
+    /// tick to tick.
+    fn color(&self, p: Point) -> Color {
+        let i = (p.y * self.width + p.x) as usize;
+        let n = noise(p, self.tick);
+        match self.cells[i] {
+            Material::Empty => COL_BG,
+            Material::Sand => {
+                let v = (p.x * 7 + p.y * 13) as u8 % 24;
+                Color::from_rgb(200 + v, 170 + v, 90)
+            }
+            Material::Water => Color::from_rgb(30, 80 + n % 30, 200 + n % 40),
+            Material::Wall => Color::from_rgb(110, 110, 120),
+            Material::Wood => Color::from_rgb(110, 70, 30),
+            Material::Fire => {
+                let t = self.ttl[i].min(20) * 6;
+                Color::from_rgb(255, 60 + t + n % 40, n % 30)
+            }
+        }
+    }
+}
+
+/// Cheap per-cell, per-tick pseudo-random byte for colour jitter.
+fn noise(p: Point, tick: u64) -> u8 {
+    let mut h = (p.x as u64).wrapping_mul(0x9e37_79b9) ^ (p.y as u64).wrapping_mul(0x85eb_ca6b);
+    h ^= tick.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    (h >> 24) as u8
+}
+
+// ---------------------------------------------------------------------------
+// Model
+// ---------------------------------------------------------------------------
+
+/// Simulation timer message.
+struct SimTick;
+
+fn schedule_tick() -> Effect {
+    tick(TICK, Msg::custom(SimTick))
+}
+
+/// The sandbox model: a menu strip on row 0, the simulation below it and a
+/// status line at the bottom.
+pub struct Sandbox {
+    sim: Sim,
+    menu: Menu,
+    menu_grid: Grid,
+    material: Material,
+    brush: i32,
+    paused: bool,
+    /// Material being painted while a button is held.
+    painting: Option<Material>,
+    changed: usize,
+    // Ticks-per-second counter.
+    tps: u32,
+    tps_count: u32,
+    tps_since: Instant,
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        let menu_grid = Grid::new(WIDTH, 1);
+        let entries = Material::PALETTE
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let text = format!(" {}:{} ", i + 1, m.label());
+                MenuEntry::new(StyledText::new(&text, Style::default()))
+            })
+            .collect::<Vec<_>>();
+        let n = entries.len() as i32;
+        let menu = Menu::new(MenuConfig {
+            grid: menu_grid.clone(),
+            entries,
+            provider: None,
+            keys: MenuKeys::default(),
+            box_: None,
+            style: MenuStyle {
+                layout: Point::new(n, 1),
+                active: Style::default().with_bg(COL_MENU_ACTIVE),
+                ..Default::default()
+            },
+            reorderable: false,
+        });
+        let mut sim = Sim::new(WIDTH, SIM_HEIGHT, 1);
+        // A starting scene: a bowl with some sand above it.
+        for x in 20..60 {
+            sim.set(Point::new(x, SIM_HEIGHT - 4), Material::Wall);
+        }
+        sim.paint(Point::new(40, 4), 3, Material::Sand);
+        Self {
+            sim,
+            menu,
+            menu_grid,
+            material: Material::Sand,
+            brush: 1,
+            paused: false,
+            painting: None,
+            changed: 0,
+            tps: 0,
+            tps_count: 0,
+            tps_since: Instant::now(),
+        }
+    }
+
+    fn select(&mut self, i: usize) {
+        if let Some(&m) = Material::PALETTE.get(i) {
+            self.material = m;
+            self.menu.set_active(i);
+        }
+    }
+
+    /// Simulation coordinates of a screen position.
+    fn sim_pos(pos: Point) -> Point {
+        pos.shift(0, -1)
+    }
+
+    fn on_tick(&mut self) {
+        if !self.paused {
+            self.changed = self.sim.step();
+        }
+        self.tps_count += 1;
+        if self.tps_since.elapsed() >= Duration::from_secs(1) {
+            self.tps = self.tps_count;
+            self.tps_count = 0;
+            self.tps_since = Instant::now();
+        }
+    }
+
+    fn on_key(&mut self, key: Key) -> Option<Effect> {
+        match key {
+            Key::Char('q') | Key::Escape => return Some(Effect::End),
+            Key::Char(' ') | Key::Space | Key::Char('p') => self.paused = !self.paused,
+            Key::Char('.') if self.paused => self.changed = self.sim.step(),
+            Key::Char('c') => self.sim.clear(),
+            Key::Char('+') | Key::Char('=') | Key::Char(']') => {
+                self.brush = (self.brush + 1).min(MAX_BRUSH);
+            }
+            Key::Char('-') | Key::Char('[') => self.brush = (self.brush - 1).max(1),
+            Key::Char(c @ '1'..='9') => self.select(c as usize - '1' as usize),
+            _ => {}
+        }
+        None
+    }
+
+    fn on_mouse(&mut self, action: MouseAction, pos: Point, msg: Msg) {
+        if pos.y == 0 && self.painting.is_none() {
+            if matches!(self.menu.update(msg), MenuAction::Invoke) {
+                self.select(self.menu.active());
+            }
+            return;
+        }
+        match action {
+            MouseAction::Main => self.painting = Some(self.material),
+            MouseAction::Secondary => self.painting = Some(Material::Empty),
+            MouseAction::Release | MouseAction::Leave => self.painting = None,
+            _ => {}
+        }
+        if let Some(m) = self.painting
+            && (matches!(action, MouseAction::Main | MouseAction::Secondary) || action.is_move())
+        {
+            self.sim.paint(Self::sim_pos(pos), self.brush - 1, m);
+        }
+    }
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl gruid_core::app::Model for Sandbox {
+    fn update(&mut self, msg: Msg) -> Option<Effect> {
+        if let Some(seed) = msg.seed() {
+            // Still empty: start over with the run's generator.
+            self.sim = Sim::new(WIDTH, SIM_HEIGHT, sub_seed(seed, "sim"));
+            return None;
+        }
+        match msg {
+            Msg::Init => Some(schedule_tick()),
+            Msg::Quit => Some(Effect::End),
+            Msg::KeyDown { key, .. } => self.on_key(key),
+            Msg::Mouse { action, pos, .. } => {
+                self.on_mouse(action, pos, msg);
+                None
+            }
+            _ if msg.downcast_ref::<SimTick>().is_some() => {
+                self.on_tick();
+                Some(schedule_tick())
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(&self, grid: &mut Grid) {
+        let rg = self.menu.draw();
+        grid.fill(Cell::default().with_style(Style::default().with_bg(COL_BG)));
+        grid.slice(Range::new(0, 0, WIDTH, 1))
+            .copy_from(&self.menu_grid.slice(rg));
+
+        for y in 0..self.sim.height() {
+            for x in 0..self.sim.width() {
+                let p = Point::new(x, y);
+                let style = Style::default().with_bg(self.sim.color(p));
+                grid.set(
+                    p.shift(0, 1),
+                    Cell::default().with_char(' ').with_style(style),
+                );
+            }
+        }
+
+        let status = format!(
+            " {}  brush {}  tps {:>2}  changed {:>4}  tick {}{}   [1-6] material  [+/-] brush  [space] pause  [c] clear",
+            self.material.label(),
+            self.brush,
+            self.tps,
+            self.changed,
+            self.sim.ticks(),
+            if self.paused { "  PAUSED" } else { "" },
+        );
+        let style = Style::default()
+            .with_fg(COL_STATUS_FG)
+            .with_bg(COL_STATUS_BG);
+        let mut chars = status.chars();
+        for x in 0..WIDTH {
+            let ch = chars.next().unwrap_or(' ');
+            grid.set(
+                Point::new(x, HEIGHT - 1),
+                Cell::default().with_char(ch).with_style(style),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sand_falls_one_cell_per_tick() {
+        let mut sim = Sim::new(5, 6, 0);
+        sim.set(Point::new(2, 0), Material::Sand);
+        assert_eq!(sim.step(), 2);
+        assert_eq!(sim.get(Point::new(2, 1)), Material::Sand);
+        for _ in 0..10 {
+            sim.step();
+        }
+        assert_eq!(sim.get(Point::new(2, 5)), Material::Sand);
+        assert_eq!(sim.count(Material::Sand), 1);
+        // Resting on the floor: nothing changes.
+        assert_eq!(sim.step(), 0);
+    }
+
+    #[test]
+    fn sand_piles_and_sinks_in_water() {
+        let mut sim = Sim::new(5, 3, 0);
+        sim.set(Point::new(2, 2), Material::Water);
+        sim.set(Point::new(2, 1), Material::Sand);
+        sim.set(Point::new(0, 2), Material::Wall);
+        sim.set(Point::new(4, 2), Material::Wall);
+        sim.step();
+        assert_eq!(sim.get(Point::new(2, 2)), Material::Sand);
+        assert_eq!(sim.count(Material::Water), 1);
+
+        // A second grain slides off the first.
+        let mut sim = Sim::new(3, 2, 0);
+        sim.set(Point::new(1, 1), Material::Sand);
+        sim.set(Point::new(1, 0), Material::Sand);
+        sim.step();
+        assert_eq!(sim.get(Point::new(1, 0)), Material::Empty);
+        assert_eq!(sim.count(Material::Sand), 2);
+    }
+
+    #[test]
+    fn water_flows_sideways() {
+        let mut sim = Sim::new(9, 4, 3);
+        for y in 0..4 {
+            sim.set(Point::new(4, y), Material::Water);
+        }
+        for _ in 0..40 {
+            sim.step();
+        }
+        assert_eq!(sim.count(Material::Water), 4);
+        // The column has spread out along the floor.
+        for x in 0..9 {
+            for y in 0..3 {
+                assert_eq!(sim.get(Point::new(x, y)), Material::Empty, "({x},{y})");
+            }
+        }
+    }
+
+    #[test]
+    fn fire_consumes_wood_and_expires() {
+        let mut sim = Sim::new(12, 3, 7);
+        for x in 0..12 {
+            sim.set(Point::new(x, 2), Material::Wood);
+        }
+        sim.set(Point::new(0, 1), Material::Fire);
+        let mut burned = false;
+        for _ in 0..400 {
+            sim.step();
+            burned |= sim.count(Material::Fire) > 1;
+        }
+        assert!(burned);
+        assert_eq!(sim.count(Material::Wood), 0);
+        assert_eq!(sim.count(Material::Fire), 0);
+    }
+
+    #[test]
+    fn water_puts_out_fire() {
+        let mut sim = Sim::new(3, 3, 0);
+        sim.set(Point::new(1, 1), Material::Fire);
+        sim.set(Point::new(0, 2), Material::Water);
+        sim.set(Point::new(1, 2), Material::Wall);
+        sim.set(Point::new(2, 2), Material::Wall);
+        sim.step();
+        assert_eq!(sim.count(Material::Fire), 0);
+        assert_eq!(sim.count(Material::Water), 0);
+    }
+
+    #[test]
+    fn paint_clips_to_bounds() {
+        let mut sim = Sim::new(4, 4, 0);
+        sim.paint(Point::new(0, 0), 2, Material::Wall);
+        assert!(sim.count(Material::Wall) > 4);
+        assert_eq!(sim.get(Point::new(3, 3)), Material::Empty);
+        sim.paint(Point::new(9, 9), 1, Material::Sand);
+        assert_eq!(sim.count(Material::Sand), 0);
+    }
+}
@@ -5,6 +5,8 @@
 
 use gruid_core::style::Color;
 
+use crate::log::LogStyle;
+
 // -- Backgrounds --
 
 /// Default terminal background (reset).
@@ -68,3 +70,40 @@ pub fn monster_color(ch: char) -> Color {
         _ => FG,
     }
 }
+
+// -- Message log colours --
+
+/// Turn numbers in the message log.
+pub const LOG_TURN_FG: Color = FG_DIM;
+
+/// Get colour for a message log entry based on its style.
+pub fn log_color(style: LogStyle) -> Color {
+    match style {
+        LogStyle::Normal => FG_EMPH,
+        LogStyle::Confirm => CYAN,
+        LogStyle::Error => ORANGE,
+        LogStyle::Combat => FG,
+        LogStyle::HurtMonster => GREEN,
+        LogStyle::HurtPlayer => RED,
+        LogStyle::Notable => VIOLET,
+        LogStyle::Special => MAGENTA,
+        LogStyle::StatusEnd => BLUE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_colors_by_category() {
+        // Damage stands out from system messages.
+        assert_eq!(log_color(LogStyle::HurtPlayer), RED);
+        assert_eq!(log_color(LogStyle::HurtMonster), GREEN);
+        assert_eq!(log_color(LogStyle::Normal), FG_EMPH);
+        // Turn numbers are dimmer than any message.
+        for style in LogStyle::ALL {
+            assert_ne!(log_color(style), LOG_TURN_FG, "{style:?}");
+        }
+    }
+}
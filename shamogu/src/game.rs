@@ -1,6 +1,7 @@
 //! Core game state.
 
 use gruid_core::Point;
+use gruid_core::seed::sub_seed;
 use gruid_paths::PathRange;
 use rand::rngs::SmallRng;
 use rand::{Rng, RngExt, SeedableRng};
@@ -18,18 +19,38 @@ pub struct Game {
     pub pr: PathRange,
     pub turn: i32,
     pub log: GameLog,
-    pub rng: SmallRng,
+    pub rngs: GameRngs,
 }
 
-impl Default for Game {
-    fn default() -> Self {
-        Self::new()
+/// The random generators of a game, one per purpose, so that rolling more
+/// in one system leaves the others as they were for the same seed.
+pub struct GameRngs {
+    /// Map layout, including where monsters may spawn.
+    pub mapgen: SmallRng,
+    /// Player placement and monster kinds.
+    pub spawns: SmallRng,
+    /// Hit and damage rolls.
+    pub combat: SmallRng,
+    /// Monster wandering.
+    pub ai: SmallRng,
+}
+
+impl GameRngs {
+    /// Generators derived from the master seed with [`sub_seed`].
+    pub fn new(seed: u64) -> Self {
+        let rng = |purpose| SmallRng::seed_from_u64(sub_seed(seed, purpose));
+        Self {
+            mapgen: rng("mapgen"),
+            spawns: rng("spawns"),
+            combat: rng("combat"),
+            ai: rng("ai"),
+        }
     }
 }
 
 impl Game {
-    pub fn new() -> Self {
-        let rng = SmallRng::from_rng(&mut rand::rng());
+    /// A game whose randomness all derives from the master `seed`.
+    pub fn new(seed: u64) -> Self {
         let map = GameMap::new();
         let map_range = gruid_core::Range::new(0, 0, MAP_WIDTH, MAP_HEIGHT);
         let pr = PathRange::new(map_range);
@@ -39,7 +60,7 @@ impl Game {
             pr,
             turn: 0,
             log: GameLog::new(),
-            rng,
+            rngs: GameRngs::new(seed),
         }
     }
 
@@ -49,18 +70,18 @@ impl Game {
         self.entities = vec![None; INVENTORY_SIZE + 1];
 
         // Generate map
-        let (terrain, waypoints, spawns) = generate_map(&mut self.rng, &mut self.pr);
+        let (terrain, waypoints, spawns) = generate_map(&mut self.rngs.mapgen, &mut self.pr);
         self.map.terrain.copy_from(&terrain);
         self.map.known_terrain.fill(UNKNOWN);
         self.map.waypoints = waypoints;
 
         // Place player at a random waypoint
         let player_pos = if !self.map.waypoints.is_empty() {
-            let idx = self.rng.random_range(0..self.map.waypoints.len());
+            let idx = self.rngs.spawns.random_range(0..self.map.waypoints.len());
             self.map.waypoints[idx]
         } else {
             // Fallback: find any floor tile
-            random_floor(&self.map.terrain, &mut self.rng)
+            random_floor(&self.map.terrain, &mut self.rngs.spawns)
         };
 
         // Create player entity
@@ -102,9 +123,9 @@ impl Game {
             }
 
             let kind = if i < n_early {
-                EARLY_MONSTERS[self.rng.random_range(0..EARLY_MONSTERS.len())]
+                EARLY_MONSTERS[self.rngs.spawns.random_range(0..EARLY_MONSTERS.len())]
             } else {
-                MID_MONSTERS[self.rng.random_range(0..MID_MONSTERS.len())]
+                MID_MONSTERS[self.rngs.spawns.random_range(0..MID_MONSTERS.len())]
             };
             let data = monster_data(kind);
             let actor = Actor::new_monster(data, kind);
@@ -212,7 +233,7 @@ impl Game {
             (e.name.clone(), a.defense)
         };
 
-        let dmg = combat::compute_damage(&mut self.rng, atk_stat, def_stat);
+        let dmg = combat::compute_damage(&mut self.rngs.combat, atk_stat, def_stat);
 
         if dmg > 0 {
             // Apply damage
@@ -237,8 +258,10 @@ impl Game {
                 self.kill(defender_id);
             }
         } else {
-            self.log
-                .log(&format!("{} attacks {} but misses.", atk_name, def_name));
+            self.log.log_styled(
+                &format!("{} attacks {} but misses.", atk_name, def_name),
+                LogStyle::Combat,
+            );
         }
 
         true
@@ -352,7 +375,7 @@ impl Game {
                     } else {
                         // Pick a random waypoint
                         if !self.map.waypoints.is_empty() {
-                            let idx = self.rng.random_range(0..self.map.waypoints.len());
+                            let idx = self.rngs.ai.random_range(0..self.map.waypoints.len());
                             self.map.waypoints[idx]
                         } else {
                             mon_pos
@@ -364,7 +387,7 @@ impl Game {
                         // Try cardinal directions toward target
                         let dx = (wander_target.x - mon_pos.x).signum();
                         let dy = (wander_target.y - mon_pos.y).signum();
-                        let candidates = if self.rng.random_range(0..2) == 0 {
+                        let candidates = if self.rngs.ai.random_range(0..2) == 0 {
                             [Point::new(dx, 0), Point::new(0, dy)]
                         } else {
                             [Point::new(0, dy), Point::new(dx, 0)]
@@ -495,7 +518,7 @@ impl Game {
     /// Process end of turn.
     pub fn end_turn(&mut self) {
         self.turn += 1;
-        self.log.new_turn();
+        self.log.new_turn(self.turn);
         self.process_monsters();
         self.update_fov();
     }
@@ -521,7 +544,7 @@ mod tests {
 
     #[test]
     fn test_game_init() {
-        let mut game = Game::new();
+        let mut game = Game::new(1);
         game.init();
 
         // Map has floor tiles
@@ -556,9 +579,22 @@ mod tests {
         assert!(mon_count > 0, "Expected monsters, got {mon_count}");
     }
 
+    #[test]
+    fn test_seeded_init() {
+        let layout = |seed| {
+            let mut game = Game::new(seed);
+            game.init();
+            let terrain: Vec<_> = game.map.terrain.iter().map(|(_, c)| c).collect();
+            let monsters: Vec<_> = game.monsters().map(|(_, e)| (e.ch, e.pos)).collect();
+            (terrain, game.pp(), monsters)
+        };
+        assert_eq!(layout(7), layout(7));
+        assert_ne!(layout(7), layout(8));
+    }
+
     #[test]
     fn test_combat() {
-        let mut game = Game::new();
+        let mut game = Game::new(1);
         game.init();
 
         // Place a test monster adjacent to player
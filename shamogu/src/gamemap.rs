@@ -8,7 +8,8 @@ use gruid_rl::{
     mapgen::{CellularAutomataRule, MapGen},
     vault::Vault,
 };
-use rand::{Rng, RngExt};
+use rand::rngs::SmallRng;
+use rand::{Rng, RngExt, SeedableRng};
 
 use crate::terrain::*;
 
@@ -214,7 +215,7 @@ pub fn generate_map(rng: &mut impl Rng, pr: &mut PathRange) -> (RlGrid, Vec<Poin
         let seed = waypoints[rng.random_range(0..waypoints.len())];
         let pass = |p: Point| -> bool { mg.terrain.at(p).is_some_and(passable) };
         pr.cc_map(&MappingPath { passable: pass }, seed);
-        let rl_mg = MapGen::with_grid(mg.terrain.clone(), rand::rng());
+        let rl_mg = MapGen::with_grid(mg.terrain.clone(), SmallRng::from_rng(rng));
         let ntiles = rl_mg.keep_connected(pr, seed, WALL);
         // Sync back — keep_connected wrote to rl_mg.grid
         mg.terrain.copy_from(&rl_mg.grid);
@@ -273,7 +274,7 @@ fn gen_cellular_automata(mg: &mut MapGenState, rng: &mut impl Rng) {
         1 => 0.45,
         _ => 0.48,
     };
-    let mut map_gen = MapGen::with_grid(mg.terrain.clone(), rand::rng());
+    let mut map_gen = MapGen::with_grid(mg.terrain.clone(), SmallRng::from_rng(rng));
     map_gen.cellular_automata_cave(WALL, FLOOR, n, &rules);
     mg.terrain.copy_from(&map_gen.grid);
 }
@@ -303,7 +304,7 @@ fn gen_foliage(mg: &mut MapGenState, rng: &mut impl Rng) {
         1 => 0.53,
         _ => 0.55,
     };
-    let mut fol_gen = MapGen::with_grid(foliage_grid, rand::rng());
+    let mut fol_gen = MapGen::with_grid(foliage_grid, SmallRng::from_rng(rng));
     fol_gen.cellular_automata_cave(WALL, FOLIAGE, winit, &rules);
 
     // Apply foliage where both terrain is floor and overlay is foliage
@@ -447,51 +448,35 @@ fn dig_vault(mg: &mut MapGenState, vi: &mut VaultInfo, rng: &mut impl Rng) {
             mg.vault_mask[idx] = true;
         }
         match c {
-            '.' | '!' | '-' | '>' | 'W' => {
-                if in_map(q) {
-                    mg.terrain.set(q, FLOOR);
-                }
+            '.' | '!' | '-' | '>' | 'W' if in_map(q) => {
+                mg.terrain.set(q, FLOOR);
             }
-            '#' | '+' => {
-                if in_map(q) {
-                    mg.terrain.set(q, WALL);
-                }
+            '#' | '+' if in_map(q) => {
+                mg.terrain.set(q, WALL);
             }
-            '$' => {
-                if in_map(q) {
+            '$' if in_map(q) => {
+                mg.terrain.set(q, TRANSLUCENT_WALL);
+            }
+            '%' if in_map(q) => {
+                if rng.random_range(0..2) == 0 {
+                    mg.terrain.set(q, WALL);
+                } else {
                     mg.terrain.set(q, TRANSLUCENT_WALL);
                 }
             }
-            '%' => {
-                if in_map(q) {
-                    if rng.random_range(0..2) == 0 {
-                        mg.terrain.set(q, WALL);
-                    } else {
-                        mg.terrain.set(q, TRANSLUCENT_WALL);
-                    }
-                }
+            '&' if in_map(q) => {
+                let choices = [WALL, TRANSLUCENT_WALL, FOLIAGE, RUBBLE, FLOOR];
+                mg.terrain.set(q, choices[rng.random_range(0..5)]);
             }
-            '&' => {
-                if in_map(q) {
-                    let choices = [WALL, TRANSLUCENT_WALL, FOLIAGE, RUBBLE, FLOOR];
-                    mg.terrain.set(q, choices[rng.random_range(0..5)]);
-                }
+            '"' if in_map(q) => {
+                mg.terrain.set(q, FOLIAGE);
             }
-            '"' => {
-                if in_map(q) {
-                    mg.terrain.set(q, FOLIAGE);
-                }
+            '^' if in_map(q) => {
+                mg.terrain.set(q, RUBBLE);
             }
-            '^' => {
-                if in_map(q) {
-                    mg.terrain.set(q, RUBBLE);
-                }
-            }
-            ':' => {
-                if in_map(q) {
-                    let choices = [FLOOR, FOLIAGE, RUBBLE];
-                    mg.terrain.set(q, choices[rng.random_range(0..3)]);
-                }
+            ':' if in_map(q) => {
+                let choices = [FLOOR, FOLIAGE, RUBBLE];
+                mg.terrain.set(q, choices[rng.random_range(0..3)]);
             }
             '?' => {}
             _ => {}
@@ -523,13 +508,11 @@ fn dig_vault(mg: &mut MapGenState, vi: &mut VaultInfo, rng: &mut impl Rng) {
                     mg.item_place[idx] = true;
                 }
             }
-            '+' | '-' => {
-                if q.x > 0 && q.x < MAP_WIDTH - 1 && q.y > 0 && q.y < MAP_HEIGHT - 1 {
-                    vi.entries.push(VaultEntry {
-                        pos: q,
-                        used: false,
-                    });
-                }
+            '+' | '-' if q.x > 0 && q.x < MAP_WIDTH - 1 && q.y > 0 && q.y < MAP_HEIGHT - 1 => {
+                vi.entries.push(VaultEntry {
+                    pos: q,
+                    used: false,
+                });
             }
             _ => {}
         }
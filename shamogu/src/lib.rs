@@ -13,5 +13,5 @@ pub mod terrain;
 pub mod tile_data;
 pub mod tiles;
 
-pub use model::{ShamoguModel, UI_HEIGHT, UI_WIDTH};
+pub use model::{KEYS_ENV, ShamoguModel, UI_HEIGHT, UI_WIDTH, keys_from_env};
 pub use tiles::ATTR_IN_MAP;
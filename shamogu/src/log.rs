@@ -1,11 +1,32 @@
 //! Game message log.
+//!
+//! Consecutive identical messages collapse into one entry with a repeat
+//! count, and entries remember their turn so that the display can group
+//! them, showing the turn number on the first entry of each turn.
 
-/// Style of a log entry, mapped to display color.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use std::collections::HashMap;
+
+use gruid_core::style::Style;
+use gruid_ui::StyledText;
+
+use crate::colors::{LOG_TURN_FG, log_color};
+
+/// Default number of entries kept before the oldest turns are dropped.
+pub const MAX_ENTRIES: usize = 10000;
+
+/// Style of a log entry, mapped to display color by
+/// [`log_color`](crate::colors::log_color).
+///
+/// Styles fall into two categories: combat ([`Combat`](Self::Combat),
+/// [`HurtMonster`](Self::HurtMonster), [`HurtPlayer`](Self::HurtPlayer)),
+/// and system messages (the others).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LogStyle {
     Normal,
     Confirm,
     Error,
+    /// Attacks that do no damage.
+    Combat,
     HurtMonster,
     HurtPlayer,
     Notable,
@@ -13,19 +34,65 @@ pub enum LogStyle {
     StatusEnd,
 }
 
+impl LogStyle {
+    pub const ALL: [LogStyle; 9] = [
+        LogStyle::Normal,
+        LogStyle::Confirm,
+        LogStyle::Error,
+        LogStyle::Combat,
+        LogStyle::HurtMonster,
+        LogStyle::HurtPlayer,
+        LogStyle::Notable,
+        LogStyle::Special,
+        LogStyle::StatusEnd,
+    ];
+
+    /// Markup character selecting this style in [`StyledText`].
+    fn marker(self) -> char {
+        match self {
+            LogStyle::Normal => 'n',
+            LogStyle::Confirm => 'o',
+            LogStyle::Error => 'e',
+            LogStyle::Combat => 'c',
+            LogStyle::HurtMonster => 'm',
+            LogStyle::HurtPlayer => 'p',
+            LogStyle::Notable => 'a',
+            LogStyle::Special => 's',
+            LogStyle::StatusEnd => 'x',
+        }
+    }
+}
+
+/// Markup character for turn numbers.
+const TURN_MARKER: char = 't';
+
 /// A single log entry.
 #[derive(Debug, Clone)]
 pub struct LogEntry {
     pub text: String,
     pub style: LogStyle,
-    pub tick: bool,
+    /// Turn of the last occurrence.
+    pub turn: i32,
+    /// Occurrences after the first.
     pub dups: i32,
 }
 
+impl LogEntry {
+    /// The text with its repeat count, if any.
+    pub fn display(&self) -> String {
+        if self.dups > 0 {
+            format!("{} (x{})", self.text, self.dups + 1)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
 /// The game's message log.
 pub struct GameLog {
     pub entries: Vec<LogEntry>,
-    next_tick: bool,
+    turn: i32,
+    max_entries: usize,
 }
 
 impl Default for GameLog {
@@ -38,13 +105,20 @@ impl GameLog {
     pub fn new() -> Self {
         Self {
             entries: Vec::new(),
-            next_tick: false,
+            turn: 0,
+            max_entries: MAX_ENTRIES,
         }
     }
 
-    /// Mark the start of a new turn (next entry gets a tick marker).
-    pub fn new_turn(&mut self) {
-        self.next_tick = true;
+    /// Keep at most `n` entries (at least 1).
+    pub fn with_max_entries(mut self, n: usize) -> Self {
+        self.max_entries = n.max(1);
+        self
+    }
+
+    /// Mark the start of a new turn: the next entries belong to `turn`.
+    pub fn new_turn(&mut self, turn: i32) {
+        self.turn = turn;
     }
 
     /// Add a message with a given style.
@@ -52,10 +126,12 @@ impl GameLog {
         // Uppercase first char
         let text = uppercase_first(text);
 
-        // Dedup consecutive identical messages
+        // Collapse consecutive identical messages. A repeat in a later turn
+        // moves the entry to that turn: it is the newest one.
         if let Some(last) = self.entries.last_mut() {
-            if last.text == text && last.style == style && !self.next_tick {
+            if last.text == text && last.style == style {
                 last.dups += 1;
+                last.turn = self.turn;
                 return;
             }
         }
@@ -63,15 +139,10 @@ impl GameLog {
         self.entries.push(LogEntry {
             text,
             style,
-            tick: self.next_tick,
+            turn: self.turn,
             dups: 0,
         });
-        self.next_tick = false;
-
-        // Trim if too many
-        if self.entries.len() > 10000 {
-            self.entries.drain(0..1000);
-        }
+        self.evict();
     }
 
     /// Add a normal-style message.
@@ -79,59 +150,71 @@ impl GameLog {
         self.log_styled(text, LogStyle::Normal);
     }
 
-    /// Get the most recent entries for display, formatted into lines.
-    /// Returns up to `max_lines` lines of width `width`.
-    pub fn recent_lines(&self, width: usize, max_lines: usize) -> Vec<String> {
-        let mut parts: Vec<String> = Vec::new();
+    /// Drop the oldest entries beyond the limit, along with the rest of
+    /// their turn, so that no turn is shown partially. A turn with more
+    /// entries than the limit loses its oldest ones.
+    fn evict(&mut self) {
+        let len = self.entries.len();
+        if len <= self.max_entries {
+            return;
+        }
+        let excess = len - self.max_entries;
+        let mut cut = excess;
+        while cut < len && self.entries[cut].turn == self.entries[cut - 1].turn {
+            cut += 1;
+        }
+        if cut == len {
+            cut = excess;
+        }
+        self.entries.drain(..cut);
+    }
 
-        // Collect from newest to oldest
-        for entry in self.entries.iter().rev() {
-            let mut s = String::new();
-            if entry.tick {
-                s.push_str("• ");
-            }
-            s.push_str(&entry.text);
-            if entry.dups > 0 {
-                s.push_str(&format!(" ({}×)", entry.dups + 1));
-            }
-            parts.push(s);
+    /// Whether entry `i` is the first one of its turn.
+    pub fn starts_turn(&self, i: usize) -> bool {
+        i == 0 || self.entries[i - 1].turn != self.entries[i].turn
+    }
 
-            // Stop early if we have enough text
-            let total_len: usize = parts.iter().map(|p| p.len() + 1).sum();
-            if total_len > width * max_lines {
-                break;
-            }
-        }
+    /// The last `n` entries as one paragraph, for the message strip.
+    pub fn recent_text(&self, n: usize) -> StyledText {
+        let from = self.entries.len().saturating_sub(n);
+        self.styled_text(from, " ")
+    }
 
-        // Reverse to chronological order and join
-        parts.reverse();
-        let joined = parts.join(" ");
-
-        // Word wrap into lines
-        let mut lines: Vec<String> = Vec::new();
-        let mut current = String::new();
-        for word in joined.split_whitespace() {
-            if !current.is_empty() && current.len() + 1 + word.len() > width {
-                lines.push(current);
-                current = String::new();
+    /// All the entries, one per line, for the log review screen.
+    pub fn full_text(&self) -> StyledText {
+        self.styled_text(0, "\n")
+    }
+
+    /// Entries from `from` on, joined by `sep`, colored by style, with
+    /// the turn number on the first entry of each turn. The first entry
+    /// shown always has it.
+    fn styled_text(&self, from: usize, sep: &str) -> StyledText {
+        let mut text = String::new();
+        for (i, entry) in self.entries.iter().enumerate().skip(from) {
+            if i > from {
+                text.push_str(sep);
             }
-            if !current.is_empty() {
-                current.push(' ');
+            if i == from || self.starts_turn(i) {
+                text.push_str(&format!("@{TURN_MARKER}{} ", entry.turn));
             }
-            current.push_str(word);
-        }
-        if !current.is_empty() {
-            lines.push(current);
+            text.push('@');
+            text.push(entry.style.marker());
+            text.push_str(&entry.display().replace('@', "@@"));
         }
-
-        // Take last max_lines
-        if lines.len() > max_lines {
-            lines = lines[lines.len() - max_lines..].to_vec();
-        }
-        lines
+        StyledText::textf(text).with_markups(markups())
     }
 }
 
+/// Markups of all log styles and turn numbers.
+fn markups() -> HashMap<char, Style> {
+    let mut m: HashMap<char, Style> = LogStyle::ALL
+        .iter()
+        .map(|&s| (s.marker(), Style::default().with_fg(log_color(s))))
+        .collect();
+    m.insert(TURN_MARKER, Style::default().with_fg(LOG_TURN_FG));
+    m
+}
+
 fn uppercase_first(s: &str) -> String {
     let mut chars = s.chars();
     match chars.next() {
@@ -139,3 +222,89 @@ fn uppercase_first(s: &str) -> String {
         Some(c) => c.to_uppercase().to_string() + chars.as_str(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gruid_core::{Grid, Point};
+
+    fn texts(log: &GameLog) -> Vec<String> {
+        log.entries.iter().map(LogEntry::display).collect()
+    }
+
+    #[test]
+    fn collapse_repeats() {
+        let mut log = GameLog::new();
+        log.log_styled("the rat hits you!", LogStyle::HurtPlayer);
+        log.log_styled("The rat hits you!", LogStyle::HurtPlayer);
+        log.new_turn(1);
+        log.log_styled("The rat hits you!", LogStyle::HurtPlayer);
+        assert_eq!(texts(&log), ["The rat hits you! (x3)"]);
+        assert_eq!(log.entries[0].turn, 1);
+        // A different message resets the count.
+        log.log("You wait.");
+        log.log_styled("The rat hits you!", LogStyle::HurtPlayer);
+        assert_eq!(
+            texts(&log),
+            ["The rat hits you! (x3)", "You wait.", "The rat hits you!"]
+        );
+        // So does a different style.
+        log.log("The rat hits you!");
+        assert_eq!(log.entries.len(), 4);
+    }
+
+    #[test]
+    fn turn_groups() {
+        let mut log = GameLog::new();
+        log.log("Welcome.");
+        log.new_turn(1);
+        log.log("A");
+        log.log("B");
+        log.new_turn(2);
+        log.new_turn(3);
+        log.log("C");
+        let starts: Vec<bool> = (0..4).map(|i| log.starts_turn(i)).collect();
+        assert_eq!(starts, [true, true, false, true]);
+        assert_eq!(
+            log.full_text().content(),
+            "@t0 @nWelcome.\n@t1 @nA\n@nB\n@t3 @nC"
+        );
+        // The strip labels its first entry even mid-turn.
+        assert_eq!(log.recent_text(2).content(), "@t1 @nB @t3 @nC");
+    }
+
+    #[test]
+    fn eviction_drops_whole_turns() {
+        let mut log = GameLog::new().with_max_entries(4);
+        for (turn, msgs) in [(1, "ab"), (2, "cde"), (3, "f")] {
+            log.new_turn(turn);
+            for m in msgs.chars() {
+                log.log(&m.to_string());
+            }
+        }
+        // Turn 1 went whole, though dropping "A" was enough.
+        assert_eq!(texts(&log), ["C", "D", "E", "F"]);
+        log.log("G");
+        assert_eq!(texts(&log), ["F", "G"]);
+        // A single turn over the limit keeps its newest entries.
+        for m in ["H", "I", "J"] {
+            log.log(m);
+        }
+        assert_eq!(texts(&log), ["G", "H", "I", "J"]);
+    }
+
+    #[test]
+    fn styles_and_escapes() {
+        let mut log = GameLog::new();
+        log.log_styled("you hit the @ mimic.", LogStyle::HurtMonster);
+        let text = log.full_text();
+        let grid = Grid::new(30, 1);
+        text.draw(&grid);
+        let cell = |x| grid.at(Point::new(x, 0));
+        // "0 " then the message.
+        assert_eq!(cell(0).style.fg, LOG_TURN_FG);
+        assert_eq!(cell(2).ch, 'Y');
+        assert_eq!(cell(2).style.fg, log_color(LogStyle::HurtMonster));
+        assert_eq!(cell(14).ch, '@');
+    }
+}
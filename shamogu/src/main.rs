@@ -2,10 +2,10 @@
 
 use gruid_core::app::{App, AppConfig};
 use gruid_crossterm::CrosstermDriver;
-use shamogu_lib::{ShamoguModel, UI_HEIGHT, UI_WIDTH};
+use shamogu_lib::{ShamoguModel, UI_HEIGHT, UI_WIDTH, keys_from_env};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let model = ShamoguModel::new();
+    let model = ShamoguModel::new().with_keys(keys_from_env());
     let driver = CrosstermDriver::new();
     let mut app = App::new(AppConfig {
         model,
@@ -13,6 +13,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         width: UI_WIDTH,
         height: UI_HEIGHT,
         frame_writer: None,
+        effect_handlers: Vec::new(),
+        double_click: None,
+        seed: None,
     });
     app.run()?;
     Ok(())
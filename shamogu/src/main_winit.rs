@@ -3,10 +3,10 @@
 use gruid_core::app::{AppRunner, EventLoopDriver};
 use gruid_winit::{WinitConfig, WinitDriver};
 use shamogu_lib::tiles::ShamoguTileManager;
-use shamogu_lib::{ShamoguModel, UI_HEIGHT, UI_WIDTH};
+use shamogu_lib::{ShamoguModel, UI_HEIGHT, UI_WIDTH, keys_from_env};
 
 fn main() {
-    let model = ShamoguModel::new();
+    let model = ShamoguModel::new().with_keys(keys_from_env());
     let driver = WinitDriver::new(WinitConfig {
         title: "Shamogu".into(),
         font_size: 18.0,
@@ -2,12 +2,12 @@
 
 use gruid_core::{
     Cell, Point, Range,
-    app::Effect,
+    app::{Effect, set_clipboard},
     grid::Grid,
-    messages::{Key, Msg},
+    messages::{Key, ModMask, Msg},
     style::{AttrMask, Color, Style},
 };
-use gruid_ui::{Pager, PagerAction, PagerConfig, PagerKeys, PagerStyle, StyledText};
+use gruid_ui::{KeyBindings, Pager, PagerAction, PagerConfig, PagerKeys, PagerStyle, StyledText};
 
 use crate::colors::*;
 use crate::entity::*;
@@ -23,18 +23,27 @@ const HELP_TEXT: &str = "\
 Movement:    arrows / hjkl / yubn (vi keys + diagonals)
 Wait:        . or space
 Examine:     x or mouse move
+Messages:    m or Ctrl+M to review the log
 Help:        ? to show this screen
 Quit:        Q or Ctrl+C\n\
 \n\
 Bump into monsters to attack them.\n\
 Explore the cave and survive!";
 
+/// Environment variable picking the keys of the help and log screens:
+/// `arrows` (the default), `vi` or `wasd`.
+pub const KEYS_ENV: &str = "SHAMOGU_KEYS";
+
+/// Log entries shown in the message strip above the map.
+const RECENT_ENTRIES: usize = 4;
+
 /// UI mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Mode {
     Normal,
     GameOver,
     Help,
+    Log,
 }
 
 /// The Shamogu game model.
@@ -42,6 +51,7 @@ pub struct ShamoguModel {
     game: Game,
     mode: Mode,
     pager: Option<Pager>,
+    keys: KeyBindings,
 }
 
 impl Default for ShamoguModel {
@@ -53,24 +63,51 @@ impl Default for ShamoguModel {
 impl ShamoguModel {
     pub fn new() -> Self {
         Self {
-            game: Game::new(),
+            // Replaced by the game of the run's seed before Init.
+            game: Game::new(0),
             mode: Mode::Normal,
             pager: None,
+            keys: KeyBindings::default_arrows(),
         }
     }
+
+    /// Use `keys` in the help and log screens.
+    pub fn with_keys(mut self, keys: KeyBindings) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
+/// The key preset named by [`KEYS_ENV`], arrows if unset or unknown.
+pub fn keys_from_env() -> KeyBindings {
+    match std::env::var(KEYS_ENV).as_deref() {
+        Ok("vi") => KeyBindings::vi(),
+        Ok("wasd") => KeyBindings::wasd(),
+        _ => KeyBindings::default_arrows(),
+    }
 }
 
 impl gruid_core::app::Model for ShamoguModel {
     fn update(&mut self, msg: Msg) -> Option<Effect> {
+        if let Some(seed) = msg.seed() {
+            self.game = Game::new(seed);
+            return None;
+        }
         match msg {
             Msg::Init => {
                 self.game.init();
                 None
             }
+            // Raw-mode terminals deliver Ctrl+C as a key press.
+            Msg::KeyDown {
+                key: Key::Char('c'),
+                modifiers,
+                ..
+            } if modifiers.contains(ModMask::CTRL) => Some(Effect::End),
             _ => match self.mode {
                 Mode::Normal => self.update_normal(msg),
                 Mode::GameOver => self.update_game_over(msg),
-                Mode::Help => self.update_help(msg),
+                Mode::Help | Mode::Log => self.update_pager(msg),
             },
         }
     }
@@ -79,7 +116,7 @@ impl gruid_core::app::Model for ShamoguModel {
         grid.fill(Cell::default());
 
         match self.mode {
-            Mode::Help => {
+            Mode::Help | Mode::Log => {
                 if let Some(pager) = &self.pager {
                     let area = grid.slice(Range::new(0, 0, UI_WIDTH, UI_HEIGHT));
                     pager.draw(&area);
@@ -149,6 +186,11 @@ impl ShamoguModel {
                 self.show_help();
                 return None;
             }
+            // Message log. Terminals send Ctrl+M as Enter.
+            Key::Char('m') | Key::Enter => {
+                self.show_log();
+                return None;
+            }
             // Quit
             Key::Char('Q') => return Some(Effect::End),
             Key::Escape => return Some(Effect::End),
@@ -169,19 +211,19 @@ impl ShamoguModel {
     }
 
     fn show_help(&mut self) {
-        let stt = StyledText::text(HELP_TEXT);
-        let grid = Grid::new(UI_WIDTH, UI_HEIGHT);
-        let pager = Pager::new(PagerConfig {
-            grid,
-            content: stt,
-            keys: PagerKeys::default(),
-            box_: None,
-            style: PagerStyle::default(),
-        });
-        self.pager = Some(pager);
+        self.pager = Some(new_pager(StyledText::text(HELP_TEXT), &self.keys));
         self.mode = Mode::Help;
     }
 
+    /// Show the whole message log, scrolled to the newest entries.
+    fn show_log(&mut self) {
+        let mut pager = new_pager(self.game.log.full_text(), &self.keys);
+        // Long entries wrap, so scroll by the pager's lines, not entries.
+        pager.set_cursor(Point::new(0, pager.lines() as i32));
+        self.pager = Some(pager);
+        self.mode = Mode::Log;
+    }
+
     fn update_game_over(&mut self, msg: Msg) -> Option<Effect> {
         match msg {
             Msg::KeyDown {
@@ -192,12 +234,15 @@ impl ShamoguModel {
         }
     }
 
-    fn update_help(&mut self, msg: Msg) -> Option<Effect> {
+    fn update_pager(&mut self, msg: Msg) -> Option<Effect> {
         if let Some(pager) = &mut self.pager {
-            let action = pager.update(msg);
-            if action == PagerAction::Quit {
-                self.mode = Mode::Normal;
-                self.pager = None;
+            match pager.update(msg) {
+                PagerAction::Quit => {
+                    self.mode = Mode::Normal;
+                    self.pager = None;
+                }
+                PagerAction::Copy => return pager.selection_text().map(set_clipboard),
+                _ => {}
             }
         }
         None
@@ -209,19 +254,15 @@ impl ShamoguModel {
 
     fn draw_log(&self, grid: &mut Grid) {
         let log_area = grid.slice(Range::new(0, 0, UI_WIDTH, 2));
-        let lines = self.game.log.recent_lines(UI_WIDTH as usize - 1, 2);
-
-        for (y, line) in lines.iter().enumerate() {
-            for (x, ch) in line.chars().enumerate() {
-                if x >= UI_WIDTH as usize {
-                    break;
-                }
-                let style = Style::default().with_fg(FG_EMPH);
-                log_area.set(
-                    Point::new(x as i32, y as i32),
-                    Cell::default().with_char(ch).with_style(style),
-                );
-            }
+        let text = self
+            .game
+            .log
+            .recent_text(RECENT_ENTRIES)
+            .format(UI_WIDTH as usize - 1);
+        // Keep the newest lines.
+        let lines = text.lines();
+        for (y, line) in lines.iter().rev().take(2).rev().enumerate() {
+            line.draw(&log_area.slice(Range::new(0, y as i32, UI_WIDTH, y as i32 + 1)));
         }
     }
 
@@ -369,6 +410,18 @@ impl ShamoguModel {
     }
 }
 
+/// A full-screen pager showing `content`, with the player's `keys`.
+fn new_pager(content: StyledText, keys: &KeyBindings) -> Pager {
+    let config = PagerConfig {
+        grid: Grid::new(UI_WIDTH, UI_HEIGHT),
+        content,
+        keys: PagerKeys::default(),
+        box_: None,
+        style: PagerStyle::default(),
+    };
+    Pager::new(config.with_keys(PagerKeys::from_bindings(keys)))
+}
+
 /// Map terrain type to its foreground colour when lit.
 fn terrain_fg(t: gruid_rl::grid::Cell) -> Color {
     match t {